@@ -0,0 +1,101 @@
+// Reads through POST /graphql (only the fields shown) and marks chapters read through POST /manga/:id/read.
+"use strict";
+
+const QUERY = `query ($filter: MangaFilter, $sort: MangaSort, $descending: Boolean!) {
+  mangas(filter: $filter, sort: $sort, descending: $descending) {
+    id title titleRomanized url urlWithChapter chapter latestAvailableChapter unreadCount tags notes
+  }
+}`;
+
+const list = document.getElementById("list");
+const search = document.getElementById("search");
+const unreadOnly = document.getElementById("unread-only");
+const sort = document.getElementById("sort");
+const status = document.getElementById("status");
+const template = document.getElementById("entry");
+let mangas = [];
+
+async function load() {
+  status.textContent = "loading...";
+  const response = await fetch("/graphql", {
+    method: "POST",
+    headers: { "content-type": "application/json" },
+    body: JSON.stringify({
+      query: QUERY,
+      variables: {
+        filter: { unreadOnly: unreadOnly.checked },
+        sort: sort.value,
+        // most unread / most recently read first
+        descending: sort.value !== "TITLE",
+      },
+    }),
+  });
+  const result = await response.json();
+  if (result.errors) {
+    status.textContent = result.errors.map(e => e.message).join("; ");
+    return;
+  }
+  mangas = result.data.mangas;
+  render();
+}
+
+function matches(manga, terms) {
+  const text = [manga.title, manga.titleRomanized, manga.notes, ...manga.tags].join(" ").toLowerCase();
+  return terms.every(term => text.includes(term));
+}
+
+function render() {
+  const terms = search.value.toLowerCase().split(/\s+/).filter(term => term);
+  const shown = mangas.filter(manga => matches(manga, terms));
+  list.replaceChildren(...shown.map(entry));
+  status.textContent = `${shown.length} of ${mangas.length}`;
+}
+
+function entry(manga) {
+  const article = template.content.firstElementChild.cloneNode(true);
+  const link = article.querySelector(".title a");
+  link.href = manga.urlWithChapter || manga.url;
+  link.textContent = manga.title;
+  if (manga.unreadCount) article.querySelector(".unread").textContent = `+${manga.unreadCount}`;
+  if (manga.titleRomanized && manga.titleRomanized !== manga.title)
+    article.querySelector(".romanized").textContent = manga.titleRomanized;
+  article.querySelector(".tags").replaceChildren(...manga.tags.map(tag => {
+    const span = document.createElement("span");
+    span.textContent = tag;
+    return span;
+  }));
+  article.querySelector(".chapter").textContent =
+    (manga.chapter ? `ch. ${manga.chapter}` : "not started") +
+    (manga.latestAvailableChapter ? ` / ${manga.latestAvailableChapter}` : "");
+
+  const form = article.querySelector(".mark-read");
+  const readLatest = article.querySelector(".read-latest");
+  readLatest.hidden = !manga.unreadCount;
+  form.addEventListener("submit", event => {
+    event.preventDefault();
+    const chapter = form.elements.chapter.value.trim();
+    if (chapter) markRead(manga, chapter);
+  });
+  readLatest.addEventListener("click", () => markRead(manga, null));
+  return article;
+}
+
+async function markRead(manga, chapter) {
+  const response = await fetch(`/manga/${manga.id}/read`, {
+    method: "POST",
+    headers: { "content-type": "application/json" },
+    body: JSON.stringify({ chapter }),
+  });
+  const result = await response.json();
+  if (!response.ok) {
+    status.textContent = `${manga.title}: ${result.error}`;
+    return;
+  }
+  await load();
+  status.textContent = `${manga.title}: chapter ${result.chapter} read`;
+}
+
+search.addEventListener("input", render);
+unreadOnly.addEventListener("change", load);
+sort.addEventListener("change", load);
+load();
@@ -0,0 +1,13 @@
+// proto/manga.proto is only compiled for `--features grpc`; protoc comes vendored so that nothing
+// needs to be installed beyond cargo
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/manga.proto");
+        std::env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("vendored protoc"),
+        );
+        tonic_build::compile_protos("proto/manga.proto").expect("compiling proto/manga.proto");
+    }
+}
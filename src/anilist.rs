@@ -0,0 +1,234 @@
+// AniList lookups through its public GraphQL API (https://anilist.gitbook.io/anilist-apiv2-docs/), the
+// alternative to myanimelist when `enrichment_source = "anilist"` in the TOML config (or `--source anilist`).
+// No API key is needed for searching, but it is rate limited (90 requests per minute).  Results carry the
+// MAL id when AniList knows it, so my_anime_list is filled in the same as by the MAL path.
+pub mod anilist {
+    use serde::Deserialize;
+    use std::{thread, time::Duration};
+
+    use crate::enrichment::enrichment::EnrichmentMatch;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::MangaMetadata;
+    use crate::update_checker::update_checker;
+
+    pub const K_SOURCE: &str = "anilist";
+    const K_ANILIST_GRAPHQL_URL: &str = "https://graphql.anilist.co";
+    const K_SEARCH_LIMIT: usize = 5;
+    const K_RATE_LIMIT_PAUSE_MILLIS: u64 = 700;
+    const K_SEARCH_QUERY: &str = "query ($search: String, $perPage: Int) {
+        Page(perPage: $perPage) {
+            media(search: $search, type: MANGA) {
+                idMal
+                siteUrl
+                title { romaji english native }
+                synonyms
+                status
+                description(asHtml: false)
+                coverImage { large }
+                genres
+                staff(perPage: 5, sort: [RELEVANCE]) { edges { role node { name { full } } } }
+            }
+        }
+    }";
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListTitle {
+        romaji: Option<String>,
+        english: Option<String>,
+        native: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListCoverImage {
+        large: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListName {
+        full: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListStaff {
+        #[serde(default)]
+        name: AniListName,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListStaffEdge {
+        #[serde(default)]
+        role: String, // i.e. "Story & Art", "Story", "Art", "Translator (English)"
+        #[serde(default)]
+        node: AniListStaff,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListStaffConnection {
+        #[serde(default)]
+        edges: Vec<AniListStaffEdge>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct AniListMedia {
+        id_mal: Option<u32>,
+        site_url: Option<String>,
+        #[serde(default)]
+        title: AniListTitle,
+        #[serde(default)]
+        synonyms: Vec<String>,
+        status: Option<String>, // i.e. "RELEASING", "FINISHED"
+        description: Option<String>,
+        #[serde(default)]
+        cover_image: AniListCoverImage,
+        #[serde(default)]
+        genres: Vec<String>,
+        #[serde(default)]
+        staff: AniListStaffConnection,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListPage {
+        #[serde(default)]
+        media: Vec<AniListMedia>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct AniListData {
+        #[serde(default)]
+        page: AniListPage,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct AniListResponse {
+        #[serde(default)]
+        data: AniListData,
+    }
+
+    // only the people who made it, not translators and such
+    fn is_author_role(role: &str) -> bool {
+        let role = role.to_lowercase();
+        role.contains("story") || role.contains("art") || role.contains("original creator")
+    }
+
+    fn to_enrichment_match(manga_id: i64, found: AniListMedia) -> EnrichmentMatch {
+        let mut titles = Vec::new();
+        titles.extend(found.title.romaji.iter().cloned());
+        titles.extend(found.title.english.iter().cloned());
+        titles.extend(found.title.native.iter().cloned());
+        titles.extend(found.synonyms.iter().cloned());
+        titles.retain(|title| !title.is_empty());
+        titles.dedup();
+
+        let mut authors = Vec::new();
+        for edge in found
+            .staff
+            .edges
+            .iter()
+            .filter(|edge| is_author_role(&edge.role))
+        {
+            if let Some(name) = &edge.node.name.full {
+                if !authors.contains(name) {
+                    authors.push(name.clone());
+                }
+            }
+        }
+
+        EnrichmentMatch {
+            possible_my_anime_list: found
+                .id_mal
+                .map(|id_mal| format!("https://myanimelist.net/manga/{}", id_mal)),
+            metadata: MangaMetadata {
+                manga_id,
+                source: K_SOURCE.to_string(),
+                source_url: found.site_url,
+                title_romaji: found.title.romaji,
+                title_native: found.title.native,
+                authors,
+                publication_status: found.status,
+                synopsis: found.description,
+                cover_url: found.cover_image.large,
+                updated_at_millis: chrono::Utc::now().timestamp_millis(),
+            },
+            titles,
+            genres: found.genres,
+            demographics: Vec::new(), // AniList has no demographics, only (very many) tags
+        }
+    }
+
+    pub fn parse_search_response(
+        manga_id: i64,
+        json: &str,
+    ) -> Result<Vec<EnrichmentMatch>, Box<dyn std::error::Error>> {
+        let response: AniListResponse = serde_json::from_str(json)?;
+        Ok(response
+            .data
+            .page
+            .media
+            .into_iter()
+            .map(|found| to_enrichment_match(manga_id, found))
+            .collect())
+    }
+
+    // best matches first (as ranked by AniList), at most K_SEARCH_LIMIT of them
+    pub fn search(
+        manga_id: i64,
+        title: &str,
+    ) -> Result<Vec<EnrichmentMatch>, Box<dyn std::error::Error>> {
+        thread::sleep(Duration::from_millis(K_RATE_LIMIT_PAUSE_MILLIS));
+        let json = update_checker::post_json(
+            K_ANILIST_GRAPHQL_URL,
+            &serde_json::json!({
+                "query": K_SEARCH_QUERY,
+                "variables": { "search": title, "perPage": K_SEARCH_LIMIT },
+            }),
+        )?;
+        parse_search_response(manga_id, &json)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_search_response() {
+            let json = r#"{"data":{"Page":{"media":[{
+                "idMal": 27399,
+                "siteUrl": "https://anilist.co/manga/86635",
+                "title": {"romaji": "Gate: Jieitai Kanochi nite, Kaku Tatakaeri", "english": "Gate", "native": "ゲート"},
+                "synonyms": ["GATE"],
+                "status": "RELEASING",
+                "description": "The JSDF crosses a gate.",
+                "coverImage": {"large": "https://s4.anilist.co/file/cover.jpg"},
+                "genres": ["Action", "Fantasy"],
+                "staff": {"edges": [
+                    {"role": "Story", "node": {"name": {"full": "Takumi Yanai"}}},
+                    {"role": "Translator (English)", "node": {"name": {"full": "Someone Else"}}},
+                    {"role": "Art", "node": {"name": {"full": "Satoru Sao"}}}
+                ]}
+            }]}}}"#;
+            let matches = parse_search_response(7, json).unwrap();
+            assert_eq!(matches.len(), 1);
+            let found = &matches[0];
+            assert_eq!(
+                found.possible_my_anime_list,
+                Some("https://myanimelist.net/manga/27399".to_string())
+            );
+            assert_eq!(found.metadata.source, K_SOURCE);
+            assert_eq!(found.metadata.title_native, Some("ゲート".to_string()));
+            assert_eq!(found.metadata.authors, vec!["Takumi Yanai", "Satoru Sao"]);
+            assert_eq!(
+                found.metadata.cover_url,
+                Some("https://s4.anilist.co/file/cover.jpg".to_string())
+            );
+            assert!(found.titles.contains(&"GATE".to_string()));
+            assert_eq!(found.genres, vec!["Action", "Fantasy"]);
+
+            assert!(
+                parse_search_response(7, r#"{"data":{"Page":{"media":[]}}}"#)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+    }
+}
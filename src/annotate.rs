@@ -0,0 +1,215 @@
+// Bulk curation in a text editor (`manga annotate <filter>`): the matching manga are written to a temporary
+// YAML file, opened in $VISUAL (or $EDITOR, or vi), and whatever was changed on save is written back:
+//      # id is how an entry is matched back, leave it as is; removing an entry leaves that manga untouched
+//      - id: 1
+//        title: ゲート
+//        chapter: '12'
+//        notes: |-
+//          JSDF arc
+//        tags:
+//        - isekai
+// Only title, chapter, notes and tags are editable; use export-yaml/import-yaml for the other fields.
+pub mod annotate {
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    use crate::model_manga::model_manga::{MangaModel, MangaUpdateRequest};
+
+    const K_HEADER: &str = "# id is how an entry is matched back, leave it as is; removing an entry leaves that manga untouched\n";
+    const K_FALLBACK_EDITOR: &str = "vi";
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Annotation {
+        pub id: i64,
+        pub title: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub chapter: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub notes: Option<String>,
+        #[serde(default)]
+        pub tags: Vec<String>,
+    }
+
+    pub fn to_annotation(manga: &MangaModel) -> Annotation {
+        let non_empty =
+            |possible_text: Option<String>| possible_text.filter(|text| !text.trim().is_empty());
+        Annotation {
+            id: manga.id(),
+            title: manga.title().to_string(),
+            chapter: non_empty(manga.chapter()),
+            notes: non_empty(manga.notes()),
+            tags: manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn to_yaml(mangas: &[MangaModel]) -> Result<String, serde_yaml::Error> {
+        let annotations: Vec<Annotation> = mangas.iter().map(to_annotation).collect();
+        Ok(format!(
+            "{}{}",
+            K_HEADER,
+            serde_yaml::to_string(&annotations)?
+        ))
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Vec<Annotation>, serde_yaml::Error> {
+        let is_empty = yaml
+            .lines()
+            .all(|line| line.trim().is_empty() || line.trim_start().starts_with('#'));
+        match is_empty {
+            true => Ok(Vec::new()),
+            false => serde_yaml::from_str::<Vec<Annotation>>(yaml),
+        }
+    }
+
+    // (id, request) of each edited entry which differs from the manga it was written from, with only the fields
+    // which changed; ids which were not in the file written are an error, as is the same id twice
+    pub fn changes(
+        mangas: &[MangaModel],
+        edited: &[Annotation],
+    ) -> Result<Vec<(i64, MangaUpdateRequest)>, String> {
+        let originals: HashMap<i64, Annotation> = mangas
+            .iter()
+            .map(|manga| (manga.id(), to_annotation(manga)))
+            .collect();
+        let mut seen_ids = Vec::new();
+        let mut changes = Vec::new();
+        for annotation in edited {
+            let original = match originals.get(&annotation.id) {
+                Some(original) => original,
+                None => {
+                    return Err(format!(
+                        "annotate: id={} was not being edited",
+                        annotation.id
+                    ))
+                }
+            };
+            if seen_ids.contains(&annotation.id) {
+                return Err(format!("annotate: id={} is listed twice", annotation.id));
+            }
+            seen_ids.push(annotation.id);
+
+            let changed = |edited: &Option<String>, original: &Option<String>| {
+                let edited = edited.clone().unwrap_or_default();
+                match edited.trim() == original.clone().unwrap_or_default().trim() {
+                    true => None,
+                    false => Some(edited.trim().to_string()),
+                }
+            };
+            let tags: Vec<String> = annotation
+                .tags
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            let request = MangaUpdateRequest {
+                title: changed(
+                    &Some(annotation.title.clone()),
+                    &Some(original.title.clone()),
+                ),
+                title_romanized: None,
+                url: None,
+                url_with_chapter: None,
+                chapter: changed(&annotation.chapter, &original.chapter),
+                last_update: None,
+                notes: changed(&annotation.notes, &original.notes),
+                tags: match tags == original.tags {
+                    true => None,
+                    false => Some(tags.join(", ")),
+                },
+            };
+            let is_changed = request.title.is_some()
+                || request.chapter.is_some()
+                || request.notes.is_some()
+                || request.tags.is_some();
+            if is_changed {
+                changes.push((annotation.id, request));
+            }
+        }
+        Ok(changes)
+    }
+
+    // $VISUAL, then $EDITOR, may carry arguments, i.e. "code --wait"
+    fn editor_command() -> Vec<String> {
+        let possible_editor = ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|editor| !editor.trim().is_empty());
+        possible_editor
+            .unwrap_or_else(|| K_FALLBACK_EDITOR.to_string())
+            .split_whitespace()
+            .map(|part| part.to_string())
+            .collect()
+    }
+
+    // returns the text as saved; an editor which exits with a failure discards the edits
+    pub fn edit_in_editor(text: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let file_paths =
+            std::env::temp_dir().join(format!("manga-annotate-{}.yaml", std::process::id()));
+        std::fs::write(&file_paths, text)?;
+        let command = editor_command();
+        let status = std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .arg(&file_paths)
+            .status();
+        let edited = std::fs::read_to_string(&file_paths);
+        std::fs::remove_file(&file_paths).unwrap_or_default();
+        match status {
+            Ok(status) if status.success() => Ok(edited?),
+            Ok(status) => Err(format!(
+                "annotate: '{}' exited with {}, nothing saved",
+                command[0], status
+            )
+            .into()),
+            Err(e) => Err(format!("annotate: cannot run '{}': {}", command[0], e).into()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_changes() {
+            let mut gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            gate.set_chapter(Some("12".to_string()));
+            gate.set_tags(vec!["isekai".to_string()]);
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            let mangas = vec![gate, akira];
+
+            let yaml = to_yaml(&mangas).unwrap();
+            assert!(yaml.starts_with('#'));
+            let untouched = from_yaml(&yaml).unwrap();
+            assert_eq!(untouched.len(), 2);
+            assert!(changes(&mangas, &untouched).unwrap().is_empty());
+
+            let edited = from_yaml(
+                "- id: 1\n  title: Gate\n  chapter: '13'\n  notes: JSDF arc\n  tags: [isekai, seinen]\n",
+            )
+            .unwrap();
+            let changes_made = changes(&mangas, &edited).unwrap();
+            assert_eq!(changes_made.len(), 1);
+            let (id, request) = &changes_made[0];
+            assert_eq!(*id, 1);
+            assert_eq!(request.title, None);
+            assert_eq!(request.chapter, Some("13".to_string()));
+            assert_eq!(request.notes, Some("JSDF arc".to_string()));
+            assert_eq!(request.tags, Some("isekai, seinen".to_string()));
+
+            // everything deleted is no change at all, unknown or repeated ids are refused
+            assert!(from_yaml("# nothing left\n").unwrap().is_empty());
+            let unknown = from_yaml("- id: 3\n  title: Gate\n").unwrap();
+            assert!(changes(&mangas, &unknown).is_err());
+            let twice = from_yaml("- id: 2\n  title: A\n- id: 2\n  title: B\n").unwrap();
+            assert!(changes(&mangas, &twice).is_err());
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// `--sync` of the legacy import (`-i bookmarks.json -d 漫画.sqlite3 --sync`): rather than only adding the bookmarks
+// which are not in the database yet, a bookmark and the row it is (matched by the GUID of the bookmark, else by
+// normalized url, as source_merge does) which disagree on the chapter are settled by which was updated last:
+//      last update  both have one and they differ: the newer one wins (lastModified of the bookmark against
+//                   last_update_millis of the row)
+//      chapter      otherwise, the further chapter wins (see MangaModel::newer_cmp())
+// A row which loses takes the chapter of the bookmark (logged as a reading session, as `mark-read` does); a
+// bookmark which loses is only reported, the database keeps its chapter.  Every decision is reported, and with
+// `--sync-report <report.json>` written out as well.
+pub mod bookmark_sync {
+    use serde::Serialize;
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::{
+        self, BookmarkOrigin,
+    };
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::source_merge::source_merge;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Side {
+        Bookmark,
+        Database,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum DecidedBy {
+        LastUpdate,
+        Chapter,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct SyncDecision {
+        pub id: i64, // of the row
+        pub title: String,
+        pub winner: Side,
+        pub decided_by: DecidedBy,
+        pub bookmark_chapter: Option<String>,
+        pub bookmark_last_update: Option<String>,
+        pub database_chapter: Option<String>,
+        pub database_last_update: Option<String>,
+    }
+
+    impl fmt::Display for SyncDecision {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let bookmark = format!(
+                "bookmark '{}' ({})",
+                self.bookmark_chapter.clone().unwrap_or_default(),
+                self.bookmark_last_update.clone().unwrap_or_default()
+            );
+            let database = format!(
+                "database '{}' ({})",
+                self.database_chapter.clone().unwrap_or_default(),
+                self.database_last_update.clone().unwrap_or_default()
+            );
+            let (winner, loser) = match self.winner {
+                Side::Bookmark => (bookmark, database),
+                Side::Database => (database, bookmark),
+            };
+            write!(
+                f,
+                "id={} '{}': chapter of the {} over the {}, by {}",
+                self.id,
+                self.title,
+                winner,
+                loser,
+                match self.decided_by {
+                    DecidedBy::LastUpdate => "last update",
+                    DecidedBy::Chapter => "chapter",
+                }
+            )
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+    pub struct SyncOutcome {
+        pub updated: Vec<MangaModel>, // rows which take the chapter of their bookmark
+        pub inserted: Vec<MangaModel>, // bookmarks not in the database
+        pub decisions: Vec<SyncDecision>, // in the order of the rows
+    }
+
+    fn database_millis(manga: &MangaModel) -> Option<i64> {
+        manga
+            .last_update_millis()
+            .or_else(|| source_merge::last_update_millis(manga))
+    }
+
+    // None when there is nothing to settle: the bookmark has no chapter, or the same one
+    fn decide(bookmark: &MangaModel, database: &MangaModel) -> Option<(Side, DecidedBy)> {
+        if bookmark.chapter().is_none() || database.newer_cmp(bookmark) == Ordering::Equal {
+            return None;
+        }
+        let millis = (
+            source_merge::last_update_millis(bookmark),
+            database_millis(database),
+        );
+        Some(match (database.chapter(), millis) {
+            (None, _) => (Side::Bookmark, DecidedBy::Chapter),
+            (Some(_), (Some(bookmark_millis), Some(database_millis)))
+                if bookmark_millis != database_millis =>
+            {
+                match bookmark_millis > database_millis {
+                    true => (Side::Bookmark, DecidedBy::LastUpdate),
+                    false => (Side::Database, DecidedBy::LastUpdate),
+                }
+            }
+            _ => match database.newer_cmp(bookmark) {
+                Ordering::Less => (Side::Bookmark, DecidedBy::Chapter),
+                _ => (Side::Database, DecidedBy::Chapter),
+            },
+        })
+    }
+
+    pub fn sync(bookmarks: &[MangaModel], database: &[MangaModel]) -> SyncOutcome {
+        let mut index_by_guid: HashMap<String, usize> = HashMap::new();
+        let mut index_by_url: HashMap<String, usize> = HashMap::new();
+        for (i, manga) in database.iter().enumerate() {
+            if let Some(guid) = manga.bookmark_guid() {
+                index_by_guid.entry(guid).or_insert(i);
+            }
+            index_by_url
+                .entry(model_sqlite3_manga::normalized_url(manga.url()))
+                .or_insert(i);
+        }
+
+        // several bookmarks of the same row (i.e. of two chapters) count as the one updated last
+        let mut outcome = SyncOutcome::default();
+        let mut bookmark_by_index: HashMap<usize, &MangaModel> = HashMap::new();
+        for bookmark in bookmarks.iter() {
+            let possible_index = bookmark
+                .bookmark_guid()
+                .and_then(|guid| index_by_guid.get(&guid).cloned())
+                .or_else(|| {
+                    index_by_url
+                        .get(&model_sqlite3_manga::normalized_url(bookmark.url()))
+                        .cloned()
+                });
+            match possible_index {
+                Some(i) => {
+                    let is_newer = bookmark_by_index.get(&i).is_none_or(|held| {
+                        let by_last_update = source_merge::last_update_millis(bookmark)
+                            .cmp(&source_merge::last_update_millis(held));
+                        by_last_update.then_with(|| bookmark.newer_cmp(held)) == Ordering::Greater
+                    });
+                    if is_newer {
+                        bookmark_by_index.insert(i, bookmark);
+                    }
+                }
+                None => outcome.inserted.push(bookmark.clone()),
+            }
+        }
+
+        let mut indices: Vec<usize> = bookmark_by_index.keys().cloned().collect();
+        indices.sort();
+        for i in indices {
+            let (bookmark, row) = (bookmark_by_index[&i], &database[i]);
+            let (winner, decided_by) = match decide(bookmark, row) {
+                Some(decision) => decision,
+                None => continue,
+            };
+            outcome.decisions.push(SyncDecision {
+                id: row.id(),
+                title: row.title().to_string(),
+                winner,
+                decided_by,
+                bookmark_chapter: bookmark.chapter(),
+                bookmark_last_update: bookmark.last_update(),
+                database_chapter: row.chapter(),
+                database_last_update: row.last_update(),
+            });
+            if winner == Side::Bookmark {
+                let mut updated = row.clone();
+                updated.set_chapter(bookmark.chapter());
+                updated.set_url_with_chapter(bookmark.url_with_chapter());
+                updated.set_last_update(bookmark.last_update());
+                updated.set_last_update_millis(source_merge::last_update_millis(bookmark));
+                outcome.updated.push(updated);
+            }
+        }
+        outcome
+    }
+
+    // writes the outcome of sync(); origins are those of the bookmarks read
+    pub fn apply(
+        db_full_paths: &str,
+        outcome: &SyncOutcome,
+        origins: &[BookmarkOrigin],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        model_sqlite3_manga::update_read_chapters(db_full_paths, &outcome.updated)?;
+        let mut store = model_sqlite3_manga::MangaStore::open(db_full_paths)?;
+        let mut written = outcome.updated.clone();
+        written.extend(store.insert_batch(&outcome.inserted)?.mangas);
+        model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+            store.connection(),
+            origins,
+            &written,
+        )?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn manga(id: i64, title: &str, chapter: &str, last_update: &str) -> MangaModel {
+            let mut manga = MangaModel::new_from_required_elements(
+                title,
+                &format!("https://example.com/{}/", title.to_lowercase()),
+                id,
+            )
+            .unwrap();
+            manga.set_chapter(Some(chapter.to_string()));
+            manga.set_last_update(Some(last_update.to_string()));
+            manga
+        }
+
+        #[test]
+        fn test_sync() {
+            let database = vec![
+                manga(1, "Gate", "10", "2023-09-01 00:00:00"),
+                manga(2, "Akira", "20", "2023-09-05 00:00:00"),
+                manga(3, "Monster", "9", ""),
+                manga(4, "Berserk", "7", "2023-09-01 00:00:00"),
+            ];
+            let bookmarks = vec![
+                // read further since
+                manga(101, "Gate", "11", "2023-09-02T00:00:00"),
+                // a bookmark older than the row, even though its chapter is further
+                manga(102, "Akira", "21", "2023-09-04T00:00:00"),
+                // no last update to go by: "100" is after "9"
+                manga(103, "Monster", "100", ""),
+                // nothing to settle
+                manga(104, "Berserk", "7", "2023-09-09T00:00:00"),
+                manga(105, "Blame", "1", "2023-09-09T00:00:00"),
+            ];
+            let outcome = sync(&bookmarks, &database);
+            assert_eq!(
+                outcome
+                    .inserted
+                    .iter()
+                    .map(|manga| manga.title())
+                    .collect::<Vec<&str>>(),
+                vec!["Blame"]
+            );
+            let decisions: Vec<(i64, Side, DecidedBy)> = outcome
+                .decisions
+                .iter()
+                .map(|decision| (decision.id, decision.winner, decision.decided_by))
+                .collect();
+            assert_eq!(
+                decisions,
+                vec![
+                    (1, Side::Bookmark, DecidedBy::LastUpdate),
+                    (2, Side::Database, DecidedBy::LastUpdate),
+                    (3, Side::Bookmark, DecidedBy::Chapter),
+                ]
+            );
+            assert_eq!(
+                outcome
+                    .updated
+                    .iter()
+                    .map(|manga| (manga.id(), manga.chapter().unwrap()))
+                    .collect::<Vec<(i64, String)>>(),
+                vec![(1, "11".to_string()), (3, "100".to_string())]
+            );
+            assert_eq!(
+                outcome.decisions[1].to_string(),
+                "id=2 'Akira': chapter of the database '20' (2023-09-05 00:00:00) over the bookmark '21' (2023-09-04T00:00:00), by last update"
+            );
+        }
+    }
+}
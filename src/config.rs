@@ -0,0 +1,706 @@
+// Optional TOML configuration (default "漫画.toml" in current directory, or `--config <file.toml>`)
+// for settings that are too verbose (or too secret, i.e. webhook URLs) to pass as arguments each time.
+// A missing config file is NOT an error, it just means everything is default.
+// Example:
+//      check_schedule = "0 */6 * * *"     # daemon: when to run the update checker (see cron_schedule)
+//      metrics_bind = "127.0.0.1:9464"     # daemon: where to serve Prometheus /metrics (see daemon_metrics)
+//      enrichment_source = "anilist"       # enrich: "myanimelist" (default) or "anilist"
+//      mangadex_language = "en"            # check: language of MangaDex chapters (default "en")
+//      cover_cache_dir = "covers"          # fetch-covers: where cover images are kept (default "covers")
+//      cover_cache_max_megabytes = 200     # fetch-covers: oldest covers are removed beyond this
+//      snapshot_dir = "snapshots"          # snapshot: where named snapshots are kept (default "snapshots")
+//      title_rules_file = "titles.toml"    # more [[title_rules]], kept in a file of their own
+//      title_collision = "update-title"    # imports: a known url under another title renames its row, the old
+//                                          # title kept in alt_titles; "keep-both" (default) or "ask"
+//      romanization = "kunrei"             # romaji of Japanese titles: "hepburn" (default) or "kunrei"; run
+//                                          # `manga re-romanize` after changing it, mixed styles do not match
+//      romanization_dictionary_file = "romaji.toml"  # more [romanization_dictionary], in a file of their own
+//
+//      [romanization_dictionary]           # romaji kakasi gets wrong: a Japanese term is romanized as given
+//      "異世界" = "isekai"                 # wherever it is in a title, other terms replace the words of the
+//      "geeto" = "gate"                    # romaji (case-insensitively) once kakasi is done
+//
+//      [[webhooks]]
+//      kind = "discord"
+//      url = "https://discord.com/api/webhooks/1234/abcd"
+//
+//      [[webhooks]]
+//      kind = "slack"
+//      url = "https://hooks.slack.com/services/T000/B000/XXXX"
+//
+//      [[aggregators]]                     # migrate-site: where else to look for a title
+//      name = "example"
+//      base_url = "https://example.com/manga/"
+//      search_url = "https://example.com/?s={query}"
+//
+//      [[title_rules]]                     # import, clean-titles, repair-titles: regex patterns removed from
+//                                          # titles of that domain (and its subdomains)
+//      domain = "example.com"
+//      strip = ["\\s*\\|\\s*Example Scans$"]
+//
+//      [[feed_rules]]                      # export-opml: feed of series without feed_url, derived from url
+//      domain = "example.com"
+//      template = "https://example.com/rss/{slug}.xml"
+//
+//      [sync]                              # sync push/pull: where the database is shared (see remote_sync)
+//      kind = "webdav"                     # or "s3" (then also bucket, region)
+//      url = "https://dav.example.com/manga/"
+//      username = "me"
+//      password = "secret"
+//
+//      [git_export]                        # after imports: commit CSV/JSON exports to a repo (see git_export)
+//      repo = "/home/me/manga-history"
+//
+//      [sources.firefox_main]              # import: named sources of bookmarks (see import_sources)
+//      format = "firefox"                  # "firefox" (backup JSON/jsonlz4), "places", "chrome",
+//                                          # "html" (Netscape bookmarks.html) or "csv"
+//      path = "/home/me/bookmarks.json"
+//
+//      [normalize]                         # imports: stages of titles and urls, in order (see normalize_pipeline)
+//      stages = ["trim-quotes", "sanitize", "url-normalize", "chapter-extract", "romanize"]
+//      sanitize = { "," = ";" }            # what the sanitize stage substitutes, instead of ',' => '、' and quotes
+//                                          # => '’'; {} keeps the punctuation as is
+//      scheme_policy = "reject"            # a bookmark of another scheme than allowed_schemes (i.e.
+//      allowed_schemes = ["http", "https"] # "about:downloads") is imported (allow, default), "warn" or "reject"
+//      skip_schemes = ["about", "place"]   # bookmarks of these schemes are skipped before anything else, instead
+//                                          # of about, chrome, data, file, javascript, moz-extension, place...
+//
+//      [merge]                             # --merge: where each field is taken from (see source_merge)
+//      title = "bookmarks"                 # "newest" (default), "bookmarks", "csv" or "database"
+//      notes = "csv"
+pub mod config {
+    use serde::{Deserialize, Serialize};
+    use std::{collections::BTreeMap, path::Path};
+
+    pub const K_DEFAULT_CONFIG_FILE: &str = "漫画.toml";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum WebhookKind {
+        Discord,
+        Slack,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct WebhookConfig {
+        pub kind: WebhookKind,
+        pub url: String,
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum EnrichmentSource {
+        #[default]
+        MyAnimeList,
+        AniList,
+    }
+
+    impl std::str::FromStr for EnrichmentSource {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "myanimelist" | "mal" => Ok(EnrichmentSource::MyAnimeList),
+                "anilist" => Ok(EnrichmentSource::AniList),
+                _ => Err(format!(
+                    "unknown enrichment source '{}', expected myanimelist or anilist",
+                    s
+                )),
+            }
+        }
+    }
+
+    // what an imported bookmark does to the row of its url when their titles differ (see
+    // model_sqlite3_manga::upsert_manga_with()); the servers and the daemon have no one to ask, ask is keep-both there
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum TitleCollisionPolicy {
+        UpdateTitle,
+        #[default]
+        KeepBoth,
+        Ask,
+    }
+
+    impl std::str::FromStr for TitleCollisionPolicy {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "update-title" => Ok(TitleCollisionPolicy::UpdateTitle),
+                "keep-both" => Ok(TitleCollisionPolicy::KeepBoth),
+                "ask" => Ok(TitleCollisionPolicy::Ask),
+                _ => Err(format!(
+                    "unknown title collision policy '{}', expected update-title, keep-both or ask",
+                    s
+                )),
+            }
+        }
+    }
+
+    // romaji of Japanese titles (see MangaModel::romanize_title_with()): "tsuki" as Hepburn, "tuki" as Kunrei
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum RomanizationStyle {
+        #[default]
+        Hepburn,
+        Kunrei,
+    }
+
+    impl std::str::FromStr for RomanizationStyle {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "hepburn" => Ok(RomanizationStyle::Hepburn),
+                "kunrei" => Ok(RomanizationStyle::Kunrei),
+                _ => Err(format!(
+                    "unknown romanization style '{}', expected hepburn or kunrei",
+                    s
+                )),
+            }
+        }
+    }
+
+    // everything romanize_title_with() is told by the config: the style and the dictionary
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct RomanizationOptions {
+        pub style: RomanizationStyle,
+        pub dictionary: BTreeMap<String, String>, // term => preferred romaji
+    }
+
+    // what imports do with a bookmark whose url scheme is not one of [normalize] allowed_schemes
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum SchemePolicy {
+        #[default]
+        Allow,
+        Warn,
+        Reject,
+    }
+
+    impl std::str::FromStr for SchemePolicy {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "allow" => Ok(SchemePolicy::Allow),
+                "warn" => Ok(SchemePolicy::Warn),
+                "reject" => Ok(SchemePolicy::Reject),
+                _ => Err(format!(
+                    "unknown scheme policy '{}', expected allow, warn or reject",
+                    s
+                )),
+            }
+        }
+    }
+
+    // search_url must contain "{query}" which is replaced by the url-encoded romanized title
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct AggregatorConfig {
+        pub name: String,
+        pub base_url: String,
+        pub search_url: Option<String>,
+    }
+
+    // rules apply to the domain and its subdomains, in addition to the generic cleaning in title_repair
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct TitleRuleConfig {
+        pub domain: String,
+        #[serde(default)]
+        pub strip: Vec<String>,
+    }
+
+    // template may contain "{slug}" (last part of the url path) and "{url}" (url-encoded url)
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct FeedRuleConfig {
+        pub domain: String,
+        pub template: String,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum SyncKind {
+        WebDav,
+        S3,
+    }
+
+    // url is the WebDAV directory, or the S3 endpoint; for S3, username and password are the access key pair
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SyncConfig {
+        pub kind: SyncKind,
+        pub url: String,
+        #[serde(default)]
+        pub bucket: Option<String>,
+        #[serde(default)]
+        pub region: Option<String>,
+        #[serde(default)]
+        pub username: Option<String>,
+        #[serde(default)]
+        pub password: Option<String>,
+        #[serde(default)]
+        pub file_name: Option<String>, // default: file name of the local database
+    }
+
+    // repo must be an existing git repository; files are relative to it
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct GitExportConfig {
+        pub repo: String,
+        #[serde(default)]
+        pub csv_file: Option<String>,
+        #[serde(default)]
+        pub json_file: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum SourceFormat {
+        Firefox, // "Backup..." JSON, or a bookmarkbackups/*.jsonlz4
+        Places,  // places.sqlite, or the profile directory holding it
+        Csv,
+        Chrome, // `Bookmarks` JSON of a Chrome/Chromium profile
+        Html,   // Netscape bookmarks.html, as exported by any browser
+    }
+
+    impl std::str::FromStr for SourceFormat {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "firefox" => Ok(SourceFormat::Firefox),
+                "places" => Ok(SourceFormat::Places),
+                "csv" => Ok(SourceFormat::Csv),
+                "chrome" | "chromium" => Ok(SourceFormat::Chrome),
+                "html" | "netscape" => Ok(SourceFormat::Html),
+                _ => Err(format!(
+                    "unknown format '{}', expected firefox, places, csv, chrome or html",
+                    s
+                )),
+            }
+        }
+    }
+
+    // tag defaults to "source:<name>"
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SourceConfig {
+        pub format: SourceFormat,
+        pub path: String,
+        #[serde(default)]
+        pub tag: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum MergeRule {
+        #[default]
+        Newest, // the most recently modified source; a conflict when all three disagree
+        Bookmarks,
+        Csv,
+        Database,
+    }
+
+    // per merged field; when the source named has no such row (or does not carry the field, i.e. the notes of
+    // bookmarks) the field falls back to newest
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct MergePolicyConfig {
+        #[serde(default)]
+        pub title: MergeRule,
+        #[serde(default)]
+        pub url: MergeRule,
+        #[serde(default)]
+        pub url_with_chapter: MergeRule,
+        #[serde(default)]
+        pub chapter: MergeRule,
+        #[serde(default)]
+        pub notes: MergeRule,
+        #[serde(default)]
+        pub tags: MergeRule,
+    }
+
+    impl MergePolicyConfig {
+        pub fn rule(&self, field: &str) -> MergeRule {
+            match field {
+                "title" => self.title,
+                "url" => self.url,
+                "url_with_chapter" => self.url_with_chapter,
+                "chapter" => self.chapter,
+                "notes" => self.notes,
+                "tags" => self.tags,
+                _ => MergeRule::Newest,
+            }
+        }
+    }
+
+    // stage names of normalize_pipeline, checked when the pipeline is built; None is the default order.
+    // sanitize replaces my_libs::K_DEFAULT_SANITIZE_SUBSTITUTIONS as a whole, None keeps them; allowed_schemes
+    // and skip_schemes None are normalize_pipeline::K_DEFAULT_ALLOWED_SCHEMES and K_DEFAULT_SKIP_SCHEMES, an empty
+    // skip_schemes skips nothing
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct NormalizeConfig {
+        #[serde(default)]
+        pub stages: Option<Vec<String>>,
+        #[serde(default)]
+        pub sanitize: Option<BTreeMap<String, String>>,
+        #[serde(default)]
+        pub scheme_policy: SchemePolicy,
+        #[serde(default)]
+        pub allowed_schemes: Option<Vec<String>>,
+        #[serde(default)]
+        pub skip_schemes: Option<Vec<String>>,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    pub struct Config {
+        #[serde(default)]
+        pub check_schedule: Option<String>,
+        #[serde(default)]
+        pub metrics_bind: Option<String>,
+        #[serde(default)]
+        pub enrichment_source: EnrichmentSource,
+        #[serde(default)]
+        pub mangadex_language: Option<String>,
+        #[serde(default)]
+        pub cover_cache_dir: Option<String>,
+        #[serde(default)]
+        pub cover_cache_max_megabytes: Option<u64>,
+        #[serde(default)]
+        pub snapshot_dir: Option<String>,
+        #[serde(default)]
+        pub title_rules_file: Option<String>, // relative to the current directory, as the other paths
+        #[serde(default)]
+        pub title_collision: TitleCollisionPolicy,
+        #[serde(default)]
+        pub romanization: RomanizationStyle,
+        #[serde(default)]
+        pub romanization_dictionary_file: Option<String>,
+        #[serde(default)]
+        pub romanization_dictionary: BTreeMap<String, String>,
+        #[serde(default)]
+        pub webhooks: Vec<WebhookConfig>,
+        #[serde(default)]
+        pub aggregators: Vec<AggregatorConfig>,
+        #[serde(default)]
+        pub title_rules: Vec<TitleRuleConfig>,
+        #[serde(default)]
+        pub feed_rules: Vec<FeedRuleConfig>,
+        #[serde(default)]
+        pub sync: Option<SyncConfig>,
+        #[serde(default)]
+        pub git_export: Option<GitExportConfig>,
+        #[serde(default)]
+        pub sources: BTreeMap<String, SourceConfig>, // by name
+        #[serde(default)]
+        pub merge: MergePolicyConfig,
+        #[serde(default)]
+        pub normalize: NormalizeConfig,
+    }
+
+    pub fn from_toml_str(toml_str: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        Ok(toml::from_str::<Config>(toml_str)?)
+    }
+
+    // a typo in a webhook url should fail at load time, not after the update checker has run
+    fn validate_webhooks(webhooks: &[WebhookConfig]) -> Result<(), Box<dyn std::error::Error>> {
+        for webhook in webhooks {
+            match url::Url::parse(&webhook.url) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                _ => {
+                    return Err(format!(
+                        "Invalid {:?} webhook url '{}': expected an http(s) url",
+                        webhook.kind, webhook.url
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(config_full_paths: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        if !Path::new(config_full_paths).exists() {
+            #[cfg(debug_assertions)]
+            {
+                println!(
+                    "> config::load('{}'): not found, using defaults",
+                    config_full_paths
+                );
+            }
+            return Ok(Config::default());
+        }
+        let toml_str = std::fs::read_to_string(config_full_paths)?;
+        let mut config = match from_toml_str(&toml_str) {
+            Ok(config) => config,
+            Err(e) => {
+                return Err(format!("Error parsing config '{}': {}", config_full_paths, e).into())
+            }
+        };
+        validate_webhooks(&config.webhooks)?;
+        if let Some(title_rules_file) = config.title_rules_file.clone() {
+            let rules = load_title_rules(&title_rules_file)?;
+            config.title_rules.extend(rules);
+        }
+        if let Some(dictionary_file) = config.romanization_dictionary_file.clone() {
+            let dictionary = load_romanization_dictionary(&dictionary_file)?;
+            config.romanization_dictionary.extend(dictionary);
+        }
+        Ok(config)
+    }
+
+    impl Config {
+        pub fn romanization_options(&self) -> RomanizationOptions {
+            RomanizationOptions {
+                style: self.romanization,
+                dictionary: self.romanization_dictionary.clone(),
+            }
+        }
+    }
+
+    // "term" = "romaji" lines, same as [romanization_dictionary] without the header; the terms of the file win
+    // over the ones of the config, and the file must exist once named
+    pub fn load_romanization_dictionary(
+        dictionary_full_paths: &str,
+    ) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+        let toml_str = std::fs::read_to_string(dictionary_full_paths).map_err(|e| {
+            format!(
+                "Error reading romanization dictionary '{}': {}",
+                dictionary_full_paths, e
+            )
+        })?;
+        toml::from_str::<BTreeMap<String, String>>(&toml_str).map_err(|e| {
+            format!(
+                "Error parsing romanization dictionary '{}': {}",
+                dictionary_full_paths, e
+            )
+            .into()
+        })
+    }
+
+    // a file of nothing but [[title_rules]], i.e. shared between machines or kept under version control; unlike
+    // the config itself, it must exist once named
+    pub fn load_title_rules(
+        title_rules_full_paths: &str,
+    ) -> Result<Vec<TitleRuleConfig>, Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct TitleRulesFile {
+            #[serde(default)]
+            title_rules: Vec<TitleRuleConfig>,
+        }
+        let toml_str = std::fs::read_to_string(title_rules_full_paths).map_err(|e| {
+            format!(
+                "Error reading title rules '{}': {}",
+                title_rules_full_paths, e
+            )
+        })?;
+        match toml::from_str::<TitleRulesFile>(&toml_str) {
+            Ok(file) => Ok(file.title_rules),
+            Err(e) => Err(format!(
+                "Error parsing title rules '{}': {}",
+                title_rules_full_paths, e
+            )
+            .into()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_from_toml_str() {
+            let config = from_toml_str(
+                r#"
+                check_schedule = "0 */6 * * *"
+                enrichment_source = "anilist"
+                mangadex_language = "pt-br"
+                cover_cache_max_megabytes = 50
+                title_collision = "update-title"
+
+                [[webhooks]]
+                kind = "discord"
+                url = "https://discord.com/api/webhooks/1234/abcd"
+
+                [[webhooks]]
+                kind = "slack"
+                url = "https://hooks.slack.com/services/T000/B000/XXXX"
+
+                [[aggregators]]
+                name = "example"
+                base_url = "https://example.com/manga/"
+
+                [[title_rules]]
+                domain = "example.com"
+                strip = ['\s*\|\s*Example Scans$']
+
+                [[feed_rules]]
+                domain = "example.com"
+                template = "https://example.com/rss/{slug}.xml"
+
+                [sync]
+                kind = "s3"
+                url = "https://s3.example.com"
+                bucket = "manga"
+
+                [git_export]
+                repo = "/home/me/manga-history"
+
+                [sources.firefox_main]
+                format = "firefox"
+                path = "bookmarks.json"
+
+                [sources.phone_csv]
+                format = "csv"
+                path = "phone.csv"
+                tag = "phone"
+
+                [merge]
+                title = "bookmarks"
+                notes = "csv"
+                "#,
+            )
+            .unwrap();
+            assert_eq!(config.check_schedule, Some("0 */6 * * *".to_string()));
+            assert_eq!(config.webhooks.len(), 2);
+            assert_eq!(config.webhooks[0].kind, WebhookKind::Discord);
+            assert_eq!(config.webhooks[1].kind, WebhookKind::Slack);
+            assert_eq!(config.enrichment_source, EnrichmentSource::AniList);
+            assert_eq!(config.mangadex_language, Some("pt-br".to_string()));
+            assert_eq!(config.cover_cache_max_megabytes, Some(50));
+            assert_eq!(config.cover_cache_dir, None);
+            assert_eq!(config.title_collision, TitleCollisionPolicy::UpdateTitle);
+            assert_eq!(config.aggregators[0].name, "example");
+            assert_eq!(config.aggregators[0].search_url, None);
+            assert_eq!(config.title_rules[0].strip, vec![r"\s*\|\s*Example Scans$"]);
+            assert_eq!(
+                config.feed_rules[0].template,
+                "https://example.com/rss/{slug}.xml"
+            );
+            let sync = config.sync.unwrap();
+            assert_eq!(sync.kind, SyncKind::S3);
+            assert_eq!(sync.bucket, Some("manga".to_string()));
+            assert_eq!(sync.region, None);
+            let git_export = config.git_export.unwrap();
+            assert_eq!(git_export.repo, "/home/me/manga-history");
+            assert_eq!(git_export.csv_file, None);
+            assert_eq!(config.sources.len(), 2);
+            assert_eq!(config.sources["firefox_main"].format, SourceFormat::Firefox);
+            assert_eq!(config.sources["phone_csv"].tag, Some("phone".to_string()));
+            assert_eq!(config.merge.rule("title"), MergeRule::Bookmarks);
+            assert_eq!(config.merge.rule("notes"), MergeRule::Csv);
+            assert_eq!(config.merge.rule("chapter"), MergeRule::Newest);
+
+            // empty config is all defaults
+            assert_eq!(from_toml_str("").unwrap(), Config::default());
+            // unknown kind should fail rather than silently drop the webhook
+            assert!(from_toml_str("[[webhooks]]\nkind = \"irc\"\nurl = \"x\"").is_err());
+            // as would a misspelled field of [merge]
+            assert!(from_toml_str("[merge]\nnote = \"csv\"").is_err());
+            assert_eq!(
+                from_toml_str("[normalize]\nstages = [\"sanitize\", \"romanize\"]")
+                    .unwrap()
+                    .normalize
+                    .stages,
+                Some(vec!["sanitize".to_string(), "romanize".to_string()])
+            );
+            assert_eq!(
+                from_toml_str("[normalize]\nsanitize = { \",\" = \";\" }")
+                    .unwrap()
+                    .normalize
+                    .sanitize,
+                Some(BTreeMap::from([(",".to_string(), ";".to_string())]))
+            );
+            assert_eq!(
+                from_toml_str("[normalize]\nscheme_policy = \"reject\"")
+                    .unwrap()
+                    .normalize
+                    .scheme_policy,
+                SchemePolicy::Reject
+            );
+            assert_eq!(
+                from_toml_str("romanization = \"kunrei\"")
+                    .unwrap()
+                    .romanization,
+                RomanizationStyle::Kunrei
+            );
+            assert!(from_toml_str("romanization = \"nihon\"").is_err());
+        }
+
+        #[test]
+        fn test_load_title_rules_file() {
+            let config_full_paths = "samples/test_load_title_rules_file.toml";
+            let title_rules_full_paths = "samples/test_load_title_rules_file_titles.toml";
+            std::fs::write(
+                config_full_paths,
+                format!(
+                    "title_rules_file = \"{}\"\n\n[[title_rules]]\ndomain = \"example.com\"\n",
+                    title_rules_full_paths
+                ),
+            )
+            .unwrap();
+            std::fs::write(
+                title_rules_full_paths,
+                "[[title_rules]]\ndomain = \"rawkuma.com\"\nstrip = [' – Rawkuma$']\n",
+            )
+            .unwrap();
+            let config = load(config_full_paths).unwrap();
+            assert_eq!(
+                config
+                    .title_rules
+                    .iter()
+                    .map(|rule| rule.domain.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["example.com", "rawkuma.com"]
+            );
+            assert_eq!(config.title_rules[1].strip, vec![" – Rawkuma$"]);
+
+            std::fs::remove_file(title_rules_full_paths).unwrap();
+            assert!(load(config_full_paths).is_err());
+            std::fs::remove_file(config_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_load_romanization_dictionary_file() {
+            let config_full_paths = "samples/test_load_romanization_dictionary_file.toml";
+            let dictionary_full_paths =
+                "samples/test_load_romanization_dictionary_file_romaji.toml";
+            std::fs::write(
+                config_full_paths,
+                format!(
+                    "romanization_dictionary_file = \"{}\"\n\n[romanization_dictionary]\n\"異世界\" = \"isekai\"\n\"geeto\" = \"geto\"\n",
+                    dictionary_full_paths
+                ),
+            )
+            .unwrap();
+            std::fs::write(dictionary_full_paths, "\"geeto\" = \"gate\"\n").unwrap();
+            let options = load(config_full_paths).unwrap().romanization_options();
+            assert_eq!(options.style, RomanizationStyle::Hepburn);
+            assert_eq!(
+                options.dictionary,
+                BTreeMap::from([
+                    ("geeto".to_string(), "gate".to_string()),
+                    ("異世界".to_string(), "isekai".to_string()),
+                ])
+            );
+
+            std::fs::write(dictionary_full_paths, "[geeto]\n").unwrap();
+            assert!(load(config_full_paths).is_err());
+            std::fs::remove_file(dictionary_full_paths).unwrap();
+            assert!(load(config_full_paths).is_err());
+            std::fs::remove_file(config_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_load_invalid_webhook_url() {
+            let config_full_paths = "samples/test_load_invalid_webhook_url.toml";
+            std::fs::write(
+                config_full_paths,
+                "[[webhooks]]\nkind = \"slack\"\nurl = \"hooks.slack.com/services/T0/B0/x\"\n",
+            )
+            .unwrap();
+            assert!(load(config_full_paths).is_err());
+            std::fs::remove_file(config_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_load_missing_file() {
+            let config = load("samples/this_config_does_not_exist.toml").unwrap();
+            assert!(config.webhooks.is_empty());
+        }
+    }
+}
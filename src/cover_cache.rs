@@ -0,0 +1,149 @@
+// Local copies of cover images (manga_metadata.cover_url, as found by `enrich`) so that the HTML
+// report and the TUI can show covers offline.  Files are named by a hash of the cover url (plus the
+// extension of the url), so the same cover is never downloaded twice and nothing needs to be recorded
+// in the database: whether a cover is cached is just whether the file exists.  When the directory grows
+// past its size limit, the least recently downloaded covers are removed first.
+// Config (TOML):
+//      cover_cache_dir = "covers"          # default "covers" next to where manga is run
+//      cover_cache_max_megabytes = 200     # default 200
+pub mod cover_cache {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    };
+
+    use crate::update_checker::update_checker;
+
+    pub const K_DEFAULT_COVER_CACHE_DIR: &str = "covers";
+    pub const K_DEFAULT_MAX_MEGABYTES: u64 = 200;
+    // no cover is this large, anything bigger is most likely an error page or not an image at all
+    const K_MAX_COVER_BYTES: u64 = 10 * 1024 * 1024;
+    const K_IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "gif"];
+    const K_CRC64: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_XZ);
+
+    // i.e. "https://cdn.example.com/images/1.jpg?s=abc" => "5f0c3c8e1a2b4d6e.jpg"
+    pub fn cover_file_name(cover_url: &str) -> String {
+        let extension = url::Url::parse(cover_url)
+            .ok()
+            .and_then(|parsed_url| {
+                Path::new(parsed_url.path())
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_lowercase())
+            })
+            .filter(|extension| K_IMAGE_EXTENSIONS.contains(&extension.as_str()))
+            .unwrap_or_else(|| "img".to_string());
+        format!(
+            "{:016x}.{}",
+            K_CRC64.checksum(cover_url.as_bytes()),
+            extension
+        )
+    }
+
+    pub fn cover_path(cache_dir: &str, cover_url: &str) -> PathBuf {
+        Path::new(cache_dir).join(cover_file_name(cover_url))
+    }
+
+    // None when the cover has not been fetched (yet), or was evicted
+    pub fn cached_cover_path(cache_dir: &str, cover_url: &str) -> Option<PathBuf> {
+        let path = cover_path(cache_dir, cover_url);
+        match path.is_file() {
+            true => Some(path),
+            false => None,
+        }
+    }
+
+    // Ok(true) when downloaded, Ok(false) when it was already cached
+    pub fn fetch_cover(
+        cache_dir: &str,
+        cover_url: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if cached_cover_path(cache_dir, cover_url).is_some() {
+            return Ok(false);
+        }
+        let image = update_checker::fetch_bytes(cover_url, K_MAX_COVER_BYTES)?;
+        fs::create_dir_all(cache_dir)?;
+        // written aside and renamed, so an interrupted download never looks like a cached cover
+        let path = cover_path(cache_dir, cover_url);
+        let partial_path = path.with_extension("part");
+        fs::write(&partial_path, image)?;
+        fs::rename(&partial_path, &path)?;
+        Ok(true)
+    }
+
+    // removes the oldest covers until the directory is at most max_bytes; returns the number removed
+    pub fn enforce_size_limit(
+        cache_dir: &str,
+        max_bytes: u64,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if !Path::new(cache_dir).is_dir() {
+            return Ok(0);
+        }
+        let mut covers: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+        for entry in fs::read_dir(cache_dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                covers.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        covers.sort();
+
+        let mut total_bytes: u64 = covers.iter().map(|(_, bytes, _)| bytes).sum();
+        let mut removed_count = 0;
+        for (_, bytes, path) in covers.iter() {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            fs::remove_file(path)?;
+            total_bytes -= bytes;
+            removed_count += 1;
+        }
+        Ok(removed_count)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_cover_file_name() {
+            let name = cover_file_name("https://cdn.example.com/images/1.JPG?s=abc");
+            assert_eq!(name.len(), 16 + ".jpg".len());
+            assert!(name.ends_with(".jpg"));
+            // same url, same name; different url, different name
+            assert_eq!(
+                name,
+                cover_file_name("https://cdn.example.com/images/1.JPG?s=abc")
+            );
+            assert_ne!(
+                name,
+                cover_file_name("https://cdn.example.com/images/2.JPG?s=abc")
+            );
+            assert!(cover_file_name("https://cdn.example.com/cover?id=1").ends_with(".img"));
+        }
+
+        #[test]
+        fn test_enforce_size_limit() {
+            let cache_dir = "samples/test_cover_cache";
+            fs::remove_dir_all(cache_dir).unwrap_or_default();
+            fs::create_dir_all(cache_dir).unwrap();
+            for name in ["a.jpg", "b.jpg", "c.jpg"] {
+                fs::write(Path::new(cache_dir).join(name), [0u8; 100]).unwrap();
+                // modification times need to differ for "oldest first"
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+
+            assert_eq!(enforce_size_limit(cache_dir, 300).unwrap(), 0);
+            assert_eq!(enforce_size_limit(cache_dir, 150).unwrap(), 2);
+            assert!(!Path::new(cache_dir).join("a.jpg").exists());
+            assert!(Path::new(cache_dir).join("c.jpg").exists());
+            assert_eq!(
+                enforce_size_limit("samples/no_such_cover_cache", 0).unwrap(),
+                0
+            );
+
+            fs::remove_dir_all(cache_dir).unwrap();
+        }
+    }
+}
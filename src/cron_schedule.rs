@@ -0,0 +1,157 @@
+// Minimal cron-like schedule (i.e. "0 */6 * * *") used by the daemon to decide when to run the
+// update checker.  Only the classic 5 fields are supported:
+//      minute(0-59) hour(0-23) day-of-month(1-31) month(1-12) day-of-week(0-6, 0 and 7 are Sunday)
+// and each field may be "*", "*/step", "N", "N-M", "N-M/step", or a comma separated list of those.
+// Like cron(8), when BOTH day-of-month and day-of-week are restricted, either one matching is enough; a field
+// starting with "*" (i.e. "*/2") counts as unrestricted, so then both have to match.
+pub mod cron_schedule {
+    use chrono::{Datelike, Timelike};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CronSchedule {
+        expression: String,
+        minutes: Vec<bool>,       // index 0..=59
+        hours: Vec<bool>,         // index 0..=23
+        days_of_month: Vec<bool>, // index 1..=31 (0 unused)
+        months: Vec<bool>,        // index 1..=12 (0 unused)
+        days_of_week: Vec<bool>,  // index 0..=6 (Sunday is 0)
+        day_of_month_restricted: bool,
+        day_of_week_restricted: bool,
+    }
+
+    // expands single field into a lookup table of size max+1
+    fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+        let mut allowed = vec![false; (max + 1) as usize];
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => match step.parse::<u32>() {
+                    Ok(step) if step > 0 => (range, step),
+                    _ => return Err(format!("invalid step '{}' in '{}'", step, field)),
+                },
+                None => (part, 1),
+            };
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                match (start.parse::<u32>(), end.parse::<u32>()) {
+                    (Ok(start), Ok(end)) => (start, end),
+                    _ => return Err(format!("invalid range '{}' in '{}'", range, field)),
+                }
+            } else {
+                match range.parse::<u32>() {
+                    // "N/step" means from N to max
+                    Ok(start) if part.contains('/') => (start, max),
+                    Ok(start) => (start, start),
+                    Err(_) => return Err(format!("invalid value '{}' in '{}'", range, field)),
+                }
+            };
+            if start < min || end > max || start > end {
+                return Err(format!(
+                    "'{}' is out of range {}-{} in '{}'",
+                    range, min, max, field
+                ));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                allowed[value as usize] = true;
+            }
+        }
+        Ok(allowed)
+    }
+
+    impl CronSchedule {
+        pub fn parse(expression: &str) -> Result<CronSchedule, Box<dyn std::error::Error>> {
+            let fields = expression.split_whitespace().collect::<Vec<&str>>();
+            if fields.len() != 5 {
+                return Err(format!(
+                    "cron expression '{}' must have 5 fields (minute hour day-of-month month day-of-week)",
+                    expression
+                )
+                .into());
+            }
+            let mut days_of_week = parse_field(fields[4], 0, 7)?;
+            if days_of_week[7] {
+                days_of_week[0] = true; // 7 is also Sunday
+            }
+            days_of_week.truncate(7);
+
+            Ok(CronSchedule {
+                expression: expression.to_string(),
+                minutes: parse_field(fields[0], 0, 59)?,
+                hours: parse_field(fields[1], 0, 23)?,
+                days_of_month: parse_field(fields[2], 1, 31)?,
+                months: parse_field(fields[3], 1, 12)?,
+                days_of_week,
+                day_of_month_restricted: !fields[2].starts_with('*'),
+                day_of_week_restricted: !fields[4].starts_with('*'),
+            })
+        }
+
+        pub fn expression(&self) -> &str {
+            self.expression.as_str()
+        }
+
+        // seconds are ignored, so this is true for the entire matching minute
+        pub fn matches<Tz: chrono::TimeZone>(&self, datetime: &chrono::DateTime<Tz>) -> bool {
+            let day_of_month_matches = self.days_of_month[datetime.day() as usize];
+            let day_of_week_matches =
+                self.days_of_week[datetime.weekday().num_days_from_sunday() as usize];
+            let day_matches = match (self.day_of_month_restricted, self.day_of_week_restricted) {
+                (true, true) => day_of_month_matches || day_of_week_matches,
+                _ => day_of_month_matches && day_of_week_matches,
+            };
+            self.minutes[datetime.minute() as usize]
+                && self.hours[datetime.hour() as usize]
+                && self.months[datetime.month() as usize]
+                && day_matches
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::TimeZone;
+
+        fn utc(y: i32, m: u32, d: u32, hh: u32, mm: u32) -> chrono::DateTime<chrono::Utc> {
+            chrono::Utc.with_ymd_and_hms(y, m, d, hh, mm, 0).unwrap()
+        }
+
+        #[test]
+        fn test_every_six_hours() {
+            let schedule = CronSchedule::parse("0 */6 * * *").unwrap();
+            assert!(schedule.matches(&utc(2023, 9, 6, 0, 0)));
+            assert!(schedule.matches(&utc(2023, 9, 6, 18, 0)));
+            assert!(!schedule.matches(&utc(2023, 9, 6, 18, 1)));
+            assert!(!schedule.matches(&utc(2023, 9, 6, 19, 0)));
+        }
+
+        #[test]
+        fn test_lists_ranges_and_weekdays() {
+            // 2023-09-06 is a Wednesday
+            let schedule = CronSchedule::parse("30 8,20 * * 1-5").unwrap();
+            assert!(schedule.matches(&utc(2023, 9, 6, 8, 30)));
+            assert!(schedule.matches(&utc(2023, 9, 6, 20, 30)));
+            assert!(!schedule.matches(&utc(2023, 9, 9, 8, 30))); // Saturday
+
+            // both day-of-month and day-of-week restricted: either one matches
+            let schedule = CronSchedule::parse("0 0 1 * 7").unwrap();
+            assert!(schedule.matches(&utc(2023, 9, 1, 0, 0))); // Friday, but 1st
+            assert!(schedule.matches(&utc(2023, 9, 10, 0, 0))); // Sunday
+            assert!(!schedule.matches(&utc(2023, 9, 6, 0, 0)));
+
+            // "*/2" is not a restriction of its own: odd days which are also Mondays
+            let schedule = CronSchedule::parse("0 0 */2 * 1").unwrap();
+            assert!(schedule.matches(&utc(2023, 9, 11, 0, 0))); // Monday, 11th
+            assert!(!schedule.matches(&utc(2023, 9, 18, 0, 0))); // Monday, 18th
+            assert!(!schedule.matches(&utc(2023, 9, 13, 0, 0))); // Wednesday, 13th
+        }
+
+        #[test]
+        fn test_invalid_expressions() {
+            assert!(CronSchedule::parse("* * * *").is_err());
+            assert!(CronSchedule::parse("60 * * * *").is_err());
+            assert!(CronSchedule::parse("*/0 * * * *").is_err());
+            assert!(CronSchedule::parse("a * * * *").is_err());
+            assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// Long-running mode: `manga daemon -i bookmarks.json [-d db.sqlite3] [--check-schedule "0 */6 * * *"]`
+// Once a minute it (a) re-imports the bookmarks JSON if the file was modified since last import, and
+// (b) runs the chapter update checker when the cron-like check_schedule matches the current minute.
+// The schedule comes from `--check-schedule`, else `check_schedule` in the TOML config; without either,
+// the daemon only re-imports bookmarks.  While it runs it holds "<db>.daemon.lock" (see daemon_lock), which
+// keeps destructive subcommands and a second daemon off the same database; `--force` takes over a lock anyway.
+// With `--metrics-bind <address:port>` (or metrics_bind in the config) it also serves Prometheus /metrics there,
+// see daemon_metrics.
+pub mod daemon {
+    use std::{
+        fs::File,
+        io::{BufReader, Read},
+        path::Path,
+        sync::{atomic::Ordering, Arc},
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    use chrono::Timelike;
+
+    use crate::config::config::{
+        Config, RomanizationOptions, TitleCollisionPolicy, TitleRuleConfig,
+    };
+    use crate::cron_schedule::cron_schedule::CronSchedule;
+    use crate::daemon_lock::daemon_lock;
+    use crate::daemon_metrics::daemon_metrics::{self, DaemonMetrics};
+    use crate::mangadex::mangadex;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::notifier::notifier;
+    use crate::service::service::MangaService;
+    use crate::title_repair::title_repair;
+    use crate::update_checker::update_checker;
+
+    pub struct DaemonOptions {
+        pub db_full_paths: String,
+        pub possible_bookmarks_full_paths: Option<String>,
+        pub possible_check_schedule: Option<CronSchedule>,
+        pub possible_metrics_bind: Option<String>,
+        pub config: Config,
+        pub debug_flag: bool,
+        pub force: bool, // run even though another daemon holds the lock
+    }
+
+    fn modified_time(file_full_paths: &str) -> Option<SystemTime> {
+        std::fs::metadata(file_full_paths)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    // same as the legacy `-i bookmarks.json -d db.sqlite3` import, returns number of bookmarks read
+    pub fn import_bookmarks_file(
+        db_full_paths: &str,
+        bookmarks_full_paths: &str,
+        title_rules: &[TitleRuleConfig],
+        title_collision: TitleCollisionPolicy,
+        romanization: &RomanizationOptions,
+        debug_flag: bool,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let bookmarks_reader = BufReader::new(File::open(Path::new(bookmarks_full_paths))?);
+        import_bookmarks_reader(
+            db_full_paths,
+            bookmarks_reader,
+            title_rules,
+            title_collision,
+            romanization,
+            debug_flag,
+        )
+    }
+
+    // bookmarks JSON from anywhere (i.e. sent over gRPC), returns number of bookmarks read; titles are cleaned
+    // by the [[title_rules]] of their domain before they are written
+    pub fn import_bookmarks_reader<R: Read>(
+        db_full_paths: &str,
+        bookmarks_reader: R,
+        title_rules: &[TitleRuleConfig],
+        title_collision: TitleCollisionPolicy,
+        romanization: &RomanizationOptions,
+        debug_flag: bool,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let service = MangaService::open(db_full_paths)?
+            .with_title_collision(title_collision)
+            .with_romanization(romanization.clone());
+        let summary = service.import_bookmarks_with(bookmarks_reader, |mangas| {
+            title_repair::strip_imported_titles(mangas, title_rules, romanization);
+        })?;
+        for failure in summary.failures.iter() {
+            println!("ERROR: daemon: failed writing bookmarks: {}", failure);
+        }
+        if debug_flag {
+            println!(
+                "daemon: {} bookmarks read, {} failed",
+                summary.bookmarks_count,
+                summary.failures.len()
+            );
+        }
+        Ok(summary.bookmarks_count)
+    }
+
+    fn run_scheduled_check(
+        options: &DaemonOptions,
+        metrics: &DaemonMetrics,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let updates = update_checker::check_for_updates_with(
+            &options.db_full_paths,
+            None,
+            options
+                .config
+                .mangadex_language
+                .as_deref()
+                .unwrap_or(mangadex::K_DEFAULT_LANGUAGE),
+            &mut |_, _| {
+                metrics.http_errors.fetch_add(1, Ordering::Relaxed);
+            },
+        )?;
+        println!("daemon: {} new chapter(s) found", updates.len());
+        metrics
+            .new_chapters
+            .fetch_add(updates.len() as u64, Ordering::Relaxed);
+        let failed_count = notifier::notify(&options.config.webhooks, &updates);
+        if failed_count > 0 {
+            // not recorded as seen, so the next check notifies them again
+            println!(
+                "WARNING: daemon: {} webhook(s) failed, the new chapters will be notified again",
+                failed_count
+            );
+            return Ok(());
+        }
+        update_checker::record_updates(&options.db_full_paths, &updates)
+    }
+
+    // never returns unless the process is killed; errors in a single iteration are reported
+    // and the daemon keeps going, since a site or a half-written bookmarks file is usually transient
+    pub fn run(options: DaemonOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let _lock = daemon_lock::acquire(&options.db_full_paths, options.force)?;
+        model_sqlite3_manga::create_tables(&options.db_full_paths)?;
+        let metrics = Arc::new(DaemonMetrics::default());
+        if let Some(metrics_bind) = &options.possible_metrics_bind {
+            daemon_metrics::serve(metrics_bind, &options.db_full_paths, metrics.clone())?;
+        }
+        println!(
+            "daemon: db='{}', bookmarks={:?}, check_schedule={:?}",
+            options.db_full_paths,
+            options.possible_bookmarks_full_paths,
+            options
+                .possible_check_schedule
+                .as_ref()
+                .map(|schedule| schedule.expression())
+        );
+
+        let mut possible_last_imported_time: Option<SystemTime> = None;
+        loop {
+            if let Some(bookmarks_full_paths) = &options.possible_bookmarks_full_paths {
+                let possible_modified_time = modified_time(bookmarks_full_paths);
+                if possible_modified_time.is_some()
+                    && possible_modified_time != possible_last_imported_time
+                {
+                    match import_bookmarks_file(
+                        &options.db_full_paths,
+                        bookmarks_full_paths,
+                        &options.config.title_rules,
+                        options.config.title_collision,
+                        &options.config.romanization_options(),
+                        options.debug_flag,
+                    ) {
+                        Ok(count) => {
+                            println!(
+                                "daemon: imported {} bookmarks from '{}'",
+                                count, bookmarks_full_paths
+                            );
+                            possible_last_imported_time = possible_modified_time;
+                            metrics.imports.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            println!(
+                                "ERROR: daemon: failed importing '{}': {}",
+                                bookmarks_full_paths, e
+                            );
+                            metrics.import_errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+
+            if let Some(schedule) = &options.possible_check_schedule {
+                if schedule.matches(&chrono::Local::now()) {
+                    match run_scheduled_check(&options, &metrics) {
+                        Ok(()) => {
+                            metrics.update_checks.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            println!("ERROR: daemon: update check failed: {}", e);
+                            metrics.update_check_errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+
+            // sleep until the start of next minute, so that each minute is evaluated exactly once
+            // (an update check that takes longer than a minute will just skip the minutes it overlapped)
+            let seconds_into_minute = chrono::Local::now().second() as u64;
+            thread::sleep(Duration::from_secs(60 - seconds_into_minute.min(59)));
+        }
+    }
+}
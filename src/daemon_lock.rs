@@ -0,0 +1,222 @@
+// Advisory lock of `manga daemon` on its database: "<db>.daemon.lock", a small JSON document
+//      {"pid":4242,"started_millis":1694008642000,"heartbeat_millis":1694008702000}
+// written when the daemon starts, its heartbeat refreshed every minute and removed when the daemon exits.
+// Destructive subcommands (migrate-site, sync pull, the dedupe of the tui) look for it and refuse to run while
+// the daemon is active, since its next import or update check would write over (or into) what they change,
+// unless given --force.  A lock left behind by a killed daemon is stale, and ignored, once its heartbeat is
+// older than K_STALE_AFTER or (unix) its pid is gone.
+pub mod daemon_lock {
+    use serde::{Deserialize, Serialize};
+    use std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    pub const K_LOCK_SUFFIX: &str = ".daemon.lock";
+    const K_HEARTBEAT: Duration = Duration::from_secs(60);
+    const K_STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct DaemonLease {
+        pub pid: u32,
+        pub started_millis: i64,
+        pub heartbeat_millis: i64,
+    }
+
+    impl DaemonLease {
+        // i.e. "pid 4242, running since 2023-09-06 13:57"
+        pub fn describe(&self) -> String {
+            let started =
+                chrono::TimeZone::timestamp_millis_opt(&chrono::Local, self.started_millis)
+                    .single()
+                    .map(|datetime| datetime.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+            format!("pid {}, running since {}", self.pid, started)
+        }
+    }
+
+    // held by the daemon for as long as it runs; dropping it stops the heartbeat and removes the file
+    pub struct DaemonLock {
+        path: PathBuf,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Drop for DaemonLock {
+        fn drop(&mut self) {
+            self.stopped.store(true, Ordering::SeqCst);
+            // only our own lock, a --force'd second daemon may have replaced it
+            if read_lease(&self.path).map_or(false, |lease| lease.pid == std::process::id()) {
+                fs::remove_file(&self.path).unwrap_or_default();
+            }
+        }
+    }
+
+    pub fn lock_path(db_full_paths: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}", db_full_paths, K_LOCK_SUFFIX))
+    }
+
+    fn read_lease(path: &Path) -> Option<DaemonLease> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    #[cfg(unix)]
+    fn is_process_alive(pid: u32) -> bool {
+        // signal 0 only checks that the process exists; EPERM means it does, under another user
+        let signaled = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+        signaled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(not(unix))]
+    fn is_process_alive(_pid: u32) -> bool {
+        true // the heartbeat alone tells
+    }
+
+    fn is_active(lease: &DaemonLease, now_millis: i64) -> bool {
+        now_millis - lease.heartbeat_millis < K_STALE_AFTER.as_millis() as i64
+            && (lease.pid == std::process::id() || is_process_alive(lease.pid))
+    }
+
+    // the daemon currently running on db_full_paths, None when there is none or only a stale lock
+    pub fn active_daemon(db_full_paths: &str) -> Option<DaemonLease> {
+        read_lease(&lock_path(db_full_paths))
+            .filter(|lease| is_active(lease, chrono::Utc::now().timestamp_millis()))
+    }
+
+    // for destructive operations: error naming the daemon while one is active, unless forced
+    pub fn refuse_while_active(
+        db_full_paths: &str,
+        operation: &str,
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match active_daemon(db_full_paths) {
+            Some(lease) if force => {
+                eprintln!(
+                    "WARNING: {}: the daemon ({}) is using '{}', going ahead because of --force",
+                    operation,
+                    lease.describe(),
+                    db_full_paths
+                );
+                Ok(())
+            }
+            Some(lease) => Err(format!(
+                "{}: refusing to run while the daemon ({}) is using '{}'; stop it first, or use --force",
+                operation,
+                lease.describe(),
+                db_full_paths
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    fn write_lease(path: &Path, lease: &DaemonLease, create: bool) -> io::Result<()> {
+        let mut file = match create {
+            // fails if another process got there first
+            true => fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)?,
+            // never recreates a lock removed in the meantime, nor truncates it under a reader
+            false => fs::OpenOptions::new().write(true).open(path)?,
+        };
+        let content = serde_json::to_string(lease)?;
+        file.write_all(content.as_bytes())?;
+        file.set_len(content.len() as u64)
+    }
+
+    // taken by the daemon on start; fails while another daemon is active on the same database (unless forced)
+    pub fn acquire(
+        db_full_paths: &str,
+        force: bool,
+    ) -> Result<DaemonLock, Box<dyn std::error::Error>> {
+        let path = lock_path(db_full_paths);
+        if let Some(lease) = active_daemon(db_full_paths).filter(|_| !force) {
+            return Err(format!(
+                "daemon: another daemon ({}) is already using '{}' (lock '{}')",
+                lease.describe(),
+                db_full_paths,
+                path.display()
+            )
+            .into());
+        }
+        if path.exists() {
+            fs::remove_file(&path)?; // stale, or forced
+        }
+        let now_millis = chrono::Utc::now().timestamp_millis();
+        let mut lease = DaemonLease {
+            pid: std::process::id(),
+            started_millis: now_millis,
+            heartbeat_millis: now_millis,
+        };
+        write_lease(&path, &lease, true)?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let heartbeat_stopped = stopped.clone();
+        let heartbeat_path = path.clone();
+        // a thread rather than the daemon loop, whose update check can take longer than K_STALE_AFTER
+        thread::spawn(move || loop {
+            thread::sleep(K_HEARTBEAT);
+            if heartbeat_stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            lease.heartbeat_millis = chrono::Utc::now().timestamp_millis();
+            if let Err(e) = write_lease(&heartbeat_path, &lease, false) {
+                println!(
+                    "WARNING: daemon: failed refreshing '{}': {}",
+                    heartbeat_path.display(),
+                    e
+                );
+            }
+        });
+        Ok(DaemonLock { path, stopped })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_daemon_lock() {
+            let db_full_paths = "samples/test_daemon_lock.db";
+            let path = lock_path(db_full_paths);
+            fs::remove_file(&path).unwrap_or_default();
+            assert_eq!(active_daemon(db_full_paths), None);
+            assert!(refuse_while_active(db_full_paths, "migrate-site", false).is_ok());
+
+            let lock = acquire(db_full_paths, false).unwrap();
+            let lease = active_daemon(db_full_paths).unwrap();
+            assert_eq!(lease.pid, std::process::id());
+            let error = refuse_while_active(db_full_paths, "migrate-site", false)
+                .unwrap_err()
+                .to_string();
+            assert!(error.starts_with("migrate-site: refusing to run while the daemon (pid "));
+            assert!(error.ends_with("or use --force"));
+            assert!(refuse_while_active(db_full_paths, "migrate-site", true).is_ok());
+            assert!(acquire(db_full_paths, false).is_err());
+            drop(lock);
+            assert!(!path.exists());
+
+            // left behind by a daemon which was killed long ago: ignored, then replaced
+            let stale = DaemonLease {
+                pid: std::process::id(),
+                started_millis: 0,
+                heartbeat_millis: 0,
+            };
+            write_lease(&path, &stale, true).unwrap();
+            assert_eq!(active_daemon(db_full_paths), None);
+            let lock = acquire(db_full_paths, false).unwrap();
+            assert!(active_daemon(db_full_paths).unwrap().started_millis > 0);
+            drop(lock);
+            assert!(!path.exists());
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Prometheus metrics of `manga daemon` (`--metrics-bind <address:port>`, or metrics_bind in the config), so that
+// the long-running process can be monitored like any other service: GET /metrics answers in the text exposition
+// format, i.e.
+//      # HELP manga_rows_total Rows of the manga table.
+//      # TYPE manga_rows_total gauge
+//      manga_rows_total 812
+//      # HELP manga_imports_total Imports of the bookmarks file run by the daemon, by result.
+//      # TYPE manga_imports_total counter
+//      manga_imports_total{result="ok"} 3
+//      manga_imports_total{result="error"} 0
+// Counters start from 0 each time the daemon starts, as Prometheus expects of them.
+pub mod daemon_metrics {
+    use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    const K_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+    #[derive(Debug, Default)]
+    pub struct DaemonMetrics {
+        pub imports: AtomicU64,
+        pub import_errors: AtomicU64,
+        pub update_checks: AtomicU64,
+        pub update_check_errors: AtomicU64,
+        pub http_errors: AtomicU64, // manga whose site could not be checked
+        pub new_chapters: AtomicU64,
+    }
+
+    impl DaemonMetrics {
+        // possible_rows_total is None when the database could not be read, the gauge is then left out
+        pub fn render(&self, possible_rows_total: Option<usize>) -> String {
+            let value = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+            let mut text = String::new();
+            let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(&str, u64)>| {
+                text.push_str(&format!(
+                    "# HELP {} {}\n# TYPE {} {}\n",
+                    name, help, name, kind
+                ));
+                for (labels, sample) in samples {
+                    text.push_str(&format!("{}{} {}\n", name, labels, sample));
+                }
+            };
+            if let Some(rows_total) = possible_rows_total {
+                metric(
+                    "manga_rows_total",
+                    "gauge",
+                    "Rows of the manga table.",
+                    vec![("", rows_total as u64)],
+                );
+            }
+            metric(
+                "manga_imports_total",
+                "counter",
+                "Imports of the bookmarks file run by the daemon, by result.",
+                vec![
+                    ("{result=\"ok\"}", value(&self.imports)),
+                    ("{result=\"error\"}", value(&self.import_errors)),
+                ],
+            );
+            metric(
+                "manga_update_checks_total",
+                "counter",
+                "Scheduled update checks run by the daemon, by result.",
+                vec![
+                    ("{result=\"ok\"}", value(&self.update_checks)),
+                    ("{result=\"error\"}", value(&self.update_check_errors)),
+                ],
+            );
+            metric(
+                "manga_http_errors_total",
+                "counter",
+                "Manga whose site could not be checked for new chapters.",
+                vec![("", value(&self.http_errors))],
+            );
+            metric(
+                "manga_new_chapters_total",
+                "counter",
+                "New chapters found by the update checks.",
+                vec![("", value(&self.new_chapters))],
+            );
+            text
+        }
+    }
+
+    fn rows_total(db_full_paths: &str) -> Option<usize> {
+        let conn = model_sqlite3_connection::open(db_full_paths).ok()?;
+        conn.query_row("SELECT COUNT(*) FROM manga", [], |row| {
+            row.get::<usize, i64>(0)
+        })
+        .ok()
+        .map(|count| count as usize)
+    }
+
+    async fn get_metrics(
+        State((db_full_paths, metrics)): State<(String, Arc<DaemonMetrics>)>,
+    ) -> impl IntoResponse {
+        let text = tokio::task::spawn_blocking(move || metrics.render(rows_total(&db_full_paths)))
+            .await
+            .unwrap_or_default();
+        ([(header::CONTENT_TYPE, K_CONTENT_TYPE)], text)
+    }
+
+    // serves /metrics on a thread of its own for as long as the daemon runs; binding is done before returning, so
+    // that an address already in use stops the daemon from starting
+    pub fn serve(
+        bind: &str,
+        db_full_paths: &str,
+        metrics: Arc<DaemonMetrics>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        let listener = runtime.block_on(tokio::net::TcpListener::bind(bind))?;
+        println!(
+            "daemon: metrics on http://{}/metrics",
+            listener.local_addr()?
+        );
+        let router = Router::new()
+            .route("/metrics", get(get_metrics))
+            .with_state((db_full_paths.to_string(), metrics));
+        thread::spawn(move || {
+            if let Err(e) = runtime.block_on(async { axum::serve(listener, router).await }) {
+                println!("ERROR: daemon: metrics server stopped: {}", e);
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_render() {
+            let metrics = DaemonMetrics::default();
+            metrics.imports.fetch_add(2, Ordering::Relaxed);
+            metrics.new_chapters.fetch_add(5, Ordering::Relaxed);
+            let text = metrics.render(Some(812));
+            assert!(text.contains("# TYPE manga_rows_total gauge\nmanga_rows_total 812\n"));
+            assert!(text.contains("manga_imports_total{result=\"ok\"} 2\n"));
+            assert!(text.contains("manga_imports_total{result=\"error\"} 0\n"));
+            assert!(text.contains("manga_new_chapters_total 5\n"));
+            assert!(!metrics.render(None).contains("manga_rows_total"));
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Delta export (`manga export --since <date|last-export>`) for feeding downstream systems incrementally: the rows
+// added or modified since then (manga.updated_at), and a tombstone for each row deleted since then (see
+// model_sqlite3_change_log), either as
+//      CSV     id,title,title_romanized,url,url_with_chapter,chapter,last_update,notes,tags,bookmark_guid,updated_at_millis
+//              (tags joined by ';'), the tombstones in a CSV of their own: manga_id,title,url,deleted_at_millis
+//      NDJSON  one JSON object per line, the rows first then the tombstones, told apart by "op":
+//              {"op":"upsert","id":1,"title":"Gate",...}
+//              {"op":"delete","manga_id":2,"title":"Akira","url":"https://example.com/akira/","deleted_at_millis":1694008642000}
+//      URLS    one url per line (each url once), the url of the series or the one with the chapter, for link
+//              checkers, archivers (wget -i, ArchiveBox) or read-later services; deletions are left out
+pub mod delta_export {
+    use serde::Serialize;
+    use std::io::Write;
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_change_log::model_sqlite3_change_log::Tombstone;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DeltaFormat {
+        Csv,
+        Ndjson,
+        Urls,
+    }
+
+    impl std::str::FromStr for DeltaFormat {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "csv" => Ok(DeltaFormat::Csv),
+                "ndjson" | "jsonl" => Ok(DeltaFormat::Ndjson),
+                "urls" => Ok(DeltaFormat::Urls),
+                _ => Err(format!(
+                    "unknown export format '{}', expected csv, ndjson or urls",
+                    s
+                )),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct DeltaRow {
+        pub id: i64,
+        pub title: String,
+        pub title_romanized: String,
+        pub url: String,
+        pub url_with_chapter: String,
+        pub chapter: String,
+        pub last_update: String,
+        pub notes: String,
+        pub tags: String,
+        pub bookmark_guid: String,
+        pub updated_at_millis: Option<i64>,
+    }
+
+    #[derive(Debug, Serialize)]
+    #[serde(tag = "op", rename_all = "lowercase")]
+    enum DeltaRecord<'a> {
+        Upsert(&'a DeltaRow),
+        Delete(&'a Tombstone),
+    }
+
+    pub fn to_delta_rows(mangas: &[MangaModel]) -> Vec<DeltaRow> {
+        mangas
+            .iter()
+            .map(|manga| DeltaRow {
+                id: manga.id(),
+                title: manga.title().to_string(),
+                title_romanized: manga.title_romanized().unwrap_or_default(),
+                url: manga.url().to_string(),
+                url_with_chapter: manga.url_with_chapter().unwrap_or_default(),
+                chapter: manga.chapter().unwrap_or_default(),
+                last_update: manga.last_update().unwrap_or_default(),
+                notes: manga.notes().unwrap_or_default(),
+                tags: MangaModel::tags_to_csv(&manga.tags()),
+                bookmark_guid: manga.bookmark_guid().unwrap_or_default(),
+                updated_at_millis: manga.updated_at_millis(),
+            })
+            .collect()
+    }
+
+    // header is written by serde based on the field names, as for the tombstones
+    pub fn write_rows_csv(
+        output_writer: &mut dyn Write,
+        rows: &[DeltaRow],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut csv_writer = csv::Writer::from_writer(output_writer);
+        for row in rows {
+            csv_writer.serialize(row)?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write_tombstones_csv(
+        output_writer: &mut dyn Write,
+        tombstones: &[Tombstone],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut csv_writer = csv::Writer::from_writer(output_writer);
+        for tombstone in tombstones {
+            csv_writer.serialize(tombstone)?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write_ndjson(
+        output_writer: &mut dyn Write,
+        rows: &[DeltaRow],
+        tombstones: &[Tombstone],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let records = rows
+            .iter()
+            .map(DeltaRecord::Upsert)
+            .chain(tombstones.iter().map(DeltaRecord::Delete));
+        for record in records {
+            serde_json::to_writer(&mut *output_writer, &record)?;
+            writeln!(output_writer)?;
+        }
+        output_writer.flush()?;
+        Ok(())
+    }
+
+    // with_chapter is url_with_chapter, the url of the series for the rows which have none
+    pub fn write_urls(
+        output_writer: &mut dyn Write,
+        rows: &[DeltaRow],
+        with_chapter: bool,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut written = std::collections::HashSet::new();
+        for row in rows {
+            let url = match with_chapter && !row.url_with_chapter.is_empty() {
+                true => &row.url_with_chapter,
+                false => &row.url,
+            };
+            if written.insert(url.as_str()) {
+                writeln!(output_writer, "{}", url)?;
+            }
+        }
+        output_writer.flush()?;
+        Ok(written.len())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_write_delta() {
+            let mut gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            gate.set_chapter(Some("12".to_string()));
+            gate.set_tags(vec!["isekai".to_string(), "seinen".to_string()]);
+            let rows = to_delta_rows(&[gate]);
+            let tombstones = vec![Tombstone {
+                manga_id: 2,
+                title: "Akira".to_string(),
+                url: "https://example.com/akira/".to_string(),
+                deleted_at_millis: 1_694_008_642_000,
+            }];
+
+            let mut buffer = Vec::new();
+            write_rows_csv(&mut buffer, &rows).unwrap();
+            let csv = String::from_utf8(buffer).unwrap();
+            let lines = csv.lines().collect::<Vec<&str>>();
+            assert_eq!(lines[0], "id,title,title_romanized,url,url_with_chapter,chapter,last_update,notes,tags,bookmark_guid,updated_at_millis");
+            assert_eq!(
+                lines[1],
+                "1,Gate,,https://example.com/gate/,https://example.com/gate/,12,,,isekai;seinen,,"
+            );
+
+            let mut buffer = Vec::new();
+            write_tombstones_csv(&mut buffer, &tombstones).unwrap();
+            assert_eq!(
+                String::from_utf8(buffer).unwrap(),
+                "manga_id,title,url,deleted_at_millis\n2,Akira,https://example.com/akira/,1694008642000\n"
+            );
+
+            let mut buffer = Vec::new();
+            write_ndjson(&mut buffer, &rows, &tombstones).unwrap();
+            let ndjson = String::from_utf8(buffer).unwrap();
+            let lines = ndjson.lines().collect::<Vec<&str>>();
+            assert_eq!(lines.len(), 2);
+            assert!(lines[0].starts_with(r#"{"op":"upsert","id":1,"title":"Gate","#));
+            assert_eq!(
+                lines[1],
+                r#"{"op":"delete","manga_id":2,"title":"Akira","url":"https://example.com/akira/","deleted_at_millis":1694008642000}"#
+            );
+        }
+
+        #[test]
+        fn test_write_urls() {
+            let gate = MangaModel::new_from_required_elements(
+                "Gate",
+                "https://example.com/gate-chapter-12/",
+                1,
+            )
+            .unwrap();
+            let gate_raw = MangaModel::new_from_required_elements(
+                "Gate (raw)",
+                "https://example.com/gate/",
+                2,
+            )
+            .unwrap();
+            let rows = to_delta_rows(&[gate, gate_raw]);
+
+            let mut buffer = Vec::new();
+            assert_eq!(write_urls(&mut buffer, &rows, false).unwrap(), 1);
+            assert_eq!(
+                String::from_utf8(buffer).unwrap(),
+                "https://example.com/gate/\n"
+            );
+            let mut buffer = Vec::new();
+            assert_eq!(write_urls(&mut buffer, &rows, true).unwrap(), 2);
+            assert_eq!(
+                String::from_utf8(buffer).unwrap(),
+                "https://example.com/gate-chapter-12/\nhttps://example.com/gate/\n"
+            );
+            assert_eq!("urls".parse::<DeltaFormat>(), Ok(DeltaFormat::Urls));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// Common ground for the metadata integrations (MyAnimeList, AniList, ...): each one searches by title and
+// returns EnrichmentMatch candidates, which are stored the same way regardless of where they came from:
+// metadata into manga_metadata, and genres/demographics as ordinary tags prefixed "genre:"
+// (i.e. "Slice of Life" => "genre:slice-of-life") attached through the manga_to_tags_map table, so
+// `manga list --genre romance` works without tagging anything by hand.
+pub mod enrichment {
+    use crate::anilist::anilist;
+    use crate::config::config::{EnrichmentSource, TitleRuleConfig};
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::{self, MangaMetadata};
+    use crate::my_libs::sanitize_string;
+    use crate::myanimelist::myanimelist;
+    use crate::site_migration::site_migration;
+    use crate::title_repair::title_repair;
+
+    pub const K_GENRE_TAG_PREFIX: &str = "genre:";
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct EnrichmentMatch {
+        pub metadata: MangaMetadata,
+        pub possible_my_anime_list: Option<String>, // MAL url, if the source knows it
+        pub titles: Vec<String>,                    // every title the source knows the series by
+        pub genres: Vec<String>,
+        pub demographics: Vec<String>,
+    }
+
+    // what to search for: romanized title when there is one, cleaned of "Chapter 12" and site boilerplate
+    pub fn search_title(manga: &MangaModel, rules: &[TitleRuleConfig]) -> String {
+        let title = manga
+            .title_romanized()
+            .unwrap_or_else(|| manga.title().to_string());
+        let cleaned = title_repair::clean_title(&title, manga.url(), rules);
+        match cleaned.is_empty() {
+            true => title,
+            false => cleaned,
+        }
+    }
+
+    // best matches first, as ranked by the source
+    pub fn search(
+        source: EnrichmentSource,
+        manga_id: i64,
+        title: &str,
+    ) -> Result<Vec<EnrichmentMatch>, Box<dyn std::error::Error>> {
+        match source {
+            EnrichmentSource::MyAnimeList => myanimelist::search(manga_id, title),
+            EnrichmentSource::AniList => anilist::search(manga_id, title),
+        }
+    }
+
+    // confident when one of the titles the source knows is the same as ours, ignoring case and punctuation;
+    // anything else is ambiguous, and is left for the user to pick
+    pub fn is_confident_match(searched_title: &str, found: &EnrichmentMatch) -> bool {
+        let searched_slug = site_migration::slugify(searched_title);
+        !searched_slug.is_empty()
+            && found
+                .titles
+                .iter()
+                .any(|title| site_migration::slugify(title) == searched_slug)
+    }
+
+    // stores metadata, the MAL url (when known) and genre tags; returns number of genre tags newly attached
+    pub fn apply_match(
+        db_full_paths: &str,
+        found: &EnrichmentMatch,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        model_sqlite3_manga_metadata::upsert_manga_metadata(db_full_paths, &found.metadata)?;
+        if let Some(my_anime_list) = &found.possible_my_anime_list {
+            model_sqlite3_manga::update_my_anime_list(
+                db_full_paths,
+                found.metadata.manga_id,
+                my_anime_list,
+            )?;
+        }
+        attach_genre_tags(
+            db_full_paths,
+            found.metadata.manga_id,
+            &found.genres,
+            &found.demographics,
+        )
+    }
+
+    // None when nothing is left of the name, i.e. ""
+    pub fn genre_tag(genre_or_demographic: &str) -> Option<String> {
+        let name = sanitize_string(genre_or_demographic)
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join("-");
+        match name.is_empty() {
+            true => None,
+            false => Some(format!("{}{}", K_GENRE_TAG_PREFIX, name)),
+        }
+    }
+
+    // genres first, then demographics (i.e. "Seinen"), without duplicates
+    pub fn genre_tags(genres: &[String], demographics: &[String]) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in genres
+            .iter()
+            .chain(demographics.iter())
+            .filter_map(|name| genre_tag(name))
+        {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    // returns the number of tags newly attached (tags the manga already had are not counted)
+    pub fn attach_genre_tags(
+        db_full_paths: &str,
+        manga_id: i64,
+        genres: &[String],
+        demographics: &[String],
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let tags = genre_tags(genres, demographics);
+        Ok(model_sqlite3_manga::add_tags_to_manga(
+            db_full_paths,
+            manga_id,
+            &tags,
+        )?)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_genre_tags() {
+            assert_eq!(
+                genre_tag(" Slice of Life "),
+                Some("genre:slice-of-life".to_string())
+            );
+            assert_eq!(genre_tag("  "), None);
+            assert_eq!(
+                genre_tags(
+                    &vec!["Action".to_string(), "Isekai".to_string()],
+                    &vec!["Seinen".to_string(), "action".to_string()]
+                ),
+                vec!["genre:action", "genre:isekai", "genre:seinen"]
+            );
+        }
+
+        #[test]
+        fn test_is_confident_match() {
+            let found = EnrichmentMatch {
+                metadata: MangaMetadata::default(),
+                possible_my_anime_list: None,
+                titles: vec![
+                    "Gate: Jieitai Kanochi nite, Kaku Tatakaeri".to_string(),
+                    "Gate".to_string(),
+                ],
+                genres: Vec::new(),
+                demographics: Vec::new(),
+            };
+            assert!(is_confident_match("gate", &found));
+            assert!(is_confident_match(
+                "Gate - Jieitai Kanochi nite、 Kaku Tatakaeri",
+                &found
+            ));
+            assert!(!is_confident_match("Gate 2", &found));
+            assert!(!is_confident_match("", &found));
+        }
+    }
+}
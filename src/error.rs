@@ -0,0 +1,78 @@
+// The error of the crate for what used to panic!() (a bookmark without a title, a url which does not parse, ...)
+// or was reported as Box<dyn Error>: one enum, so that a library user can tell a malformed bookmark, which is
+// worth skipping, from a database which cannot be written, which is not.  Converts into Box<dyn Error> with `?`
+// as any error does, so callers which still return that need no change.
+pub mod error {
+    use std::fmt;
+
+    #[derive(Debug)]
+    pub enum Error {
+        Io(std::io::Error),
+        Csv(csv::Error),
+        Sqlite(rusqlite::Error),
+        JsonParse(serde_json::Error),
+        InvalidUrl {
+            url: String,
+            source: url::ParseError,
+        },
+        EmptyTitle,
+        EmptyUrl,
+        InvalidId(i64),          // 0, which is never the id of a row
+        NotFound(i64),           // id of the manga
+        InvalidArgument(String), // of the command line
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Error::Io(e) => write!(f, "{}", e),
+                Error::Csv(e) => write!(f, "CSV error: {}", e),
+                Error::Sqlite(e) => write!(f, "database error: {}", e),
+                Error::JsonParse(e) => write!(f, "JSON error: {}", e),
+                Error::InvalidUrl { url, source } => write!(f, "invalid url '{}': {}", url, source),
+                Error::EmptyTitle => write!(f, "title is empty"),
+                Error::EmptyUrl => write!(f, "url is empty"),
+                Error::InvalidId(id) => write!(f, "invalid id {}", id),
+                Error::NotFound(id) => write!(f, "manga id={} not found", id),
+                Error::InvalidArgument(message) => write!(f, "{}", message),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Error::Io(e) => Some(e),
+                Error::Csv(e) => Some(e),
+                Error::Sqlite(e) => Some(e),
+                Error::JsonParse(e) => Some(e),
+                Error::InvalidUrl { source, .. } => Some(source),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<std::io::Error> for Error {
+        fn from(e: std::io::Error) -> Self {
+            Error::Io(e)
+        }
+    }
+
+    impl From<csv::Error> for Error {
+        fn from(e: csv::Error) -> Self {
+            Error::Csv(e)
+        }
+    }
+
+    impl From<rusqlite::Error> for Error {
+        fn from(e: rusqlite::Error) -> Self {
+            Error::Sqlite(e)
+        }
+    }
+
+    impl From<serde_json::Error> for Error {
+        fn from(e: serde_json::Error) -> Self {
+            Error::JsonParse(e)
+        }
+    }
+}
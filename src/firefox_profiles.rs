@@ -0,0 +1,484 @@
+// Firefox profiles, so that bookmarks can be imported straight from the browser (`--profile <name|all>`) rather
+// than from a "Backup..." JSON file.  Profiles are listed in profiles.ini of the Firefox directory:
+//      Linux       ~/.mozilla/firefox (or the snap/flatpak equivalents)
+//      macOS       ~/Library/Application Support/Firefox
+//      Windows     %APPDATA%\Mozilla\Firefox
+//      [Profile0]
+//      Name=default-release
+//      IsRelative=1
+//      Path=Profiles/abcd1234.default-release
+// and the bookmarks of each live in its places.sqlite (moz_bookmarks of type 1, url from moz_places).  Firefox
+// keeps that database locked while it runs, so it is read from a copy (WAL included, for the latest changes).
+// `--auto-input` instead reads the newest of the daily backups Firefox keeps in bookmarkbackups/ of the default
+// profile, the same JSON as "Backup...", mozLz4 compressed ("mozLz40\0", u32 LE size, then one LZ4 block).
+pub mod firefox_profiles {
+    use rusqlite::{Connection, OpenFlags};
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::normalize_pipeline::normalize_pipeline::Pipeline;
+    use crate::service::service::{self, BookmarkOrigin};
+
+    pub const K_PROFILE_TAG_PREFIX: &str = "profile:"; // i.e. "profile:work" on everything imported from "work"
+    pub const K_ALL_PROFILES: &str = "all";
+    const K_PROFILES_INI: &str = "profiles.ini";
+    const K_PLACES_DB: &str = "places.sqlite";
+    const K_BOOKMARK_BACKUPS_DIR: &str = "bookmarkbackups";
+    const K_MOZLZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FirefoxProfile {
+        pub name: String,
+        pub path: PathBuf, // profile directory
+        pub is_default: bool,
+    }
+
+    // candidates in order of preference; the first one with a profiles.ini is the one in use
+    fn firefox_dir_candidates() -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        if cfg!(target_os = "windows") {
+            if let Ok(app_data) = std::env::var("APPDATA") {
+                candidates.push(Path::new(&app_data).join("Mozilla").join("Firefox"));
+            }
+        } else if let Ok(home) = std::env::var("HOME") {
+            let home = Path::new(&home);
+            if cfg!(target_os = "macos") {
+                candidates.push(home.join("Library/Application Support/Firefox"));
+            } else {
+                candidates.push(home.join(".mozilla/firefox"));
+                candidates.push(home.join("snap/firefox/common/.mozilla/firefox"));
+                candidates.push(home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"));
+            }
+        }
+        candidates
+    }
+
+    pub fn firefox_dir() -> Option<PathBuf> {
+        firefox_dir_candidates()
+            .into_iter()
+            .find(|candidate| candidate.join(K_PROFILES_INI).exists())
+    }
+
+    // sections in order, each with its key=value pairs
+    fn parse_ini(ini: &str) -> Vec<(String, HashMap<String, String>)> {
+        let mut sections: Vec<(String, HashMap<String, String>)> = Vec::new();
+        for line in ini.lines().map(|line| line.trim()) {
+            if line.starts_with('[') && line.ends_with(']') {
+                sections.push((line[1..line.len() - 1].to_string(), HashMap::new()));
+            } else if let (Some((key, value)), Some((_, entries))) =
+                (line.split_once('='), sections.last_mut())
+            {
+                entries.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+        sections
+    }
+
+    // the default is the one of the [Install...] section (what Firefox actually starts with), else Default=1
+    pub fn parse_profiles_ini(ini: &str, firefox_dir: &Path) -> Vec<FirefoxProfile> {
+        let sections = parse_ini(ini);
+        let mut profiles: Vec<FirefoxProfile> = sections
+            .iter()
+            .filter(|(section, _)| section.starts_with("Profile"))
+            .filter_map(|(_, entries)| {
+                let path = entries.get("Path")?;
+                Some(FirefoxProfile {
+                    name: entries.get("Name")?.to_string(),
+                    path: match entries.get("IsRelative").map(|value| value.as_str()) {
+                        Some("0") => PathBuf::from(path),
+                        _ => firefox_dir.join(path),
+                    },
+                    is_default: entries.get("Default").map(|value| value.as_str()) == Some("1"),
+                })
+            })
+            .collect();
+
+        let possible_install_default = sections
+            .iter()
+            .filter(|(section, _)| section.starts_with("Install"))
+            .find_map(|(_, entries)| entries.get("Default"))
+            .map(|install_default| firefox_dir.join(install_default));
+        if let Some(install_default_path) = possible_install_default {
+            if profiles
+                .iter()
+                .any(|profile| profile.path == install_default_path)
+            {
+                for profile in profiles.iter_mut() {
+                    profile.is_default = profile.path == install_default_path;
+                }
+            }
+        }
+        profiles
+    }
+
+    pub fn discover_profiles() -> Result<Vec<FirefoxProfile>, Box<dyn std::error::Error>> {
+        let firefox_dir = match firefox_dir() {
+            Some(firefox_dir) => firefox_dir,
+            None => return Err("no Firefox profiles.ini found".into()),
+        };
+        let ini = fs::read_to_string(firefox_dir.join(K_PROFILES_INI))?;
+        Ok(parse_profiles_ini(&ini, &firefox_dir))
+    }
+
+    // selection is a profile name (case insensitive), "default", or "all"
+    pub fn select_profiles(
+        profiles: &[FirefoxProfile],
+        selection: &str,
+    ) -> Result<Vec<FirefoxProfile>, String> {
+        let selected: Vec<FirefoxProfile> = profiles
+            .iter()
+            .filter(|profile| {
+                selection.eq_ignore_ascii_case(K_ALL_PROFILES)
+                    || profile.name.eq_ignore_ascii_case(selection)
+                    || (selection.eq_ignore_ascii_case("default") && profile.is_default)
+            })
+            .cloned()
+            .collect();
+        match selected.is_empty() {
+            false => Ok(selected),
+            true => Err(format!(
+                "no Firefox profile '{}', expected one of: {}, {}",
+                selection,
+                profiles
+                    .iter()
+                    .map(|profile| profile.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+                K_ALL_PROFILES
+            )),
+        }
+    }
+
+    // every bookmark of places.sqlite (not folders, separators nor "place:" queries), sorted by url, along with
+    // where each is (see service::bookmark_origins()); those the pipeline skipped or rejected are left out, origin
+    // included
+    pub fn read_places_bookmarks(
+        profile_dir: &Path,
+        profile: &str,
+        pipeline: &Pipeline,
+    ) -> Result<(Vec<MangaModel>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+        read_places_file(&profile_dir.join(K_PLACES_DB), profile, pipeline)
+    }
+
+    // places database of a path given by the user (`-p`, [sources] of format "places"): either the file itself,
+    // whatever its name, or the profile directory it is in
+    pub fn places_path_of(path: &Path) -> PathBuf {
+        match path.is_dir() {
+            true => path.join(K_PLACES_DB),
+            false => path.to_path_buf(),
+        }
+    }
+
+    // same as read_places_bookmarks(), of the places database at places_path
+    pub fn read_places_file(
+        places_path: &Path,
+        profile: &str,
+        pipeline: &Pipeline,
+    ) -> Result<(Vec<MangaModel>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+        if !places_path.exists() {
+            return Err(format!("'{}' does not exist", places_path.display()).into());
+        }
+        let copy_dir = std::env::temp_dir().join(format!(
+            "firefox_places_{}_{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_millis()
+        ));
+        fs::create_dir_all(&copy_dir)?;
+        let copy_path = copy_dir.join(K_PLACES_DB);
+        fs::copy(places_path, &copy_path)?;
+        let wal_path = PathBuf::from(format!("{}-wal", places_path.display()));
+        if wal_path.exists() {
+            fs::copy(&wal_path, copy_dir.join(format!("{}-wal", K_PLACES_DB)))?;
+        }
+
+        type PlacesBookmarks = (Vec<MangaModel>, Vec<BookmarkOrigin>);
+        let result = (|| -> Result<PlacesBookmarks, Box<dyn std::error::Error>> {
+            let conn = Connection::open_with_flags(&copy_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+            // folders by id: parent, title (the root is parent 0, without a title)
+            let mut folders_stmt = conn.prepare(
+                "SELECT id, parent, COALESCE(title, '') FROM moz_bookmarks WHERE type = 2",
+            )?;
+            let folders = folders_stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<usize, i64>(0)?,
+                        (row.get::<usize, i64>(1)?, row.get::<usize, String>(2)?),
+                    ))
+                })?
+                .collect::<Result<HashMap<i64, (i64, String)>, rusqlite::Error>>()?;
+            let folder_path = |mut folder_id: i64| {
+                let mut titles = Vec::new();
+                // bounded by the number of folders, in case parents loop
+                while let Some((parent, title)) = folders.get(&folder_id) {
+                    if !title.is_empty() {
+                        titles.push(title.as_str());
+                    }
+                    if titles.len() > folders.len() {
+                        break;
+                    }
+                    folder_id = *parent;
+                }
+                titles.reverse();
+                titles.join("/")
+            };
+
+            let mut stmt = conn.prepare(
+                "SELECT COALESCE(NULLIF(b.title, ''), p.title, p.url), p.url, COALESCE(b.lastModified, b.dateAdded, 0), b.guid,
+                    b.parent, COALESCE(b.dateAdded, 0)
+                FROM moz_bookmarks AS b JOIN moz_places AS p ON b.fk = p.id
+                WHERE b.type = 1 AND p.url NOT LIKE 'place:%'",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<usize, String>(0)?,
+                    row.get::<usize, String>(1)?,
+                    row.get::<usize, i64>(2)?,
+                    row.get::<usize, Option<String>>(3)?,
+                    row.get::<usize, Option<i64>>(4)?,
+                    row.get::<usize, i64>(5)?,
+                ))
+            })?;
+            let mut mangas = Vec::new();
+            let mut origins = Vec::new();
+            for row in rows {
+                let (
+                    title,
+                    url,
+                    last_modified_micros,
+                    possible_guid,
+                    possible_parent,
+                    date_added_micros,
+                ) = row?;
+                let possible_manga = service::bookmark_to_manga(
+                    pipeline,
+                    &title,
+                    &url,
+                    last_modified_micros,
+                    possible_guid.as_deref(),
+                )?;
+                match possible_manga {
+                    Some(manga) => mangas.push(manga),
+                    None => continue, // skipped or rejected by the pipeline
+                }
+                if let Some(guid) = possible_guid {
+                    origins.push(BookmarkOrigin {
+                        id: 0,
+                        manga_id: 0,
+                        profile: profile.to_string(),
+                        folder_path: possible_parent.map(folder_path).unwrap_or_default(),
+                        guid,
+                        date_added_millis: date_added_micros / 1000,
+                    });
+                }
+            }
+            mangas.sort_by(|a, b| a.url().cmp(b.url()));
+            Ok((mangas, origins))
+        })();
+        fs::remove_dir_all(&copy_dir).unwrap_or_default();
+        result
+    }
+
+    // the default profile, or the only one when none is marked default
+    pub fn default_profile(profiles: &[FirefoxProfile]) -> Option<&FirefoxProfile> {
+        profiles
+            .iter()
+            .find(|profile| profile.is_default)
+            .or(match profiles.len() {
+                1 => profiles.first(),
+                _ => None,
+            })
+    }
+
+    // newest (last modified) .jsonlz4 or .json of bookmarkbackups/
+    pub fn newest_bookmark_backup(
+        profile_dir: &Path,
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let backups_dir = profile_dir.join(K_BOOKMARK_BACKUPS_DIR);
+        let mut backups: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
+        for entry in fs::read_dir(&backups_dir)
+            .map_err(|e| format!("cannot read '{}': {}", backups_dir.display(), e))?
+        {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            if file_name.ends_with(".jsonlz4") || file_name.ends_with(".json") {
+                backups.push((fs::metadata(&path)?.modified()?, path));
+            }
+        }
+        // file names start with the date (bookmarks-2023-09-06_...), for backups copied with the same mtime
+        backups.sort();
+        match backups.pop() {
+            Some((_, path)) => Ok(path),
+            None => Err(format!("no bookmark backup in '{}'", backups_dir.display()).into()),
+        }
+    }
+
+    pub fn decode_mozlz4(bytes: &[u8]) -> Result<Vec<u8>, String> {
+        if !bytes.starts_with(K_MOZLZ4_MAGIC) || bytes.len() < K_MOZLZ4_MAGIC.len() + 4 {
+            return Err("not a mozLz4 file".to_string());
+        }
+        let size_bytes = &bytes[K_MOZLZ4_MAGIC.len()..K_MOZLZ4_MAGIC.len() + 4];
+        let size = u32::from_le_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]);
+        let json = lz4_flex::block::decompress(&bytes[K_MOZLZ4_MAGIC.len() + 4..], size as usize)
+            .map_err(|e| format!("corrupt mozLz4 file: {}", e))?;
+        match json.len() == size as usize {
+            true => Ok(json),
+            false => Err(format!(
+                "corrupt mozLz4 file: {} bytes instead of {}",
+                json.len(),
+                size
+            )),
+        }
+    }
+
+    // the bookmarks JSON of a backup, compressed or not
+    pub fn read_bookmark_backup(backup_path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let bytes = fs::read(backup_path)?;
+        match bytes.starts_with(K_MOZLZ4_MAGIC) {
+            true => Ok(decode_mozlz4(&bytes)?),
+            false => Ok(bytes),
+        }
+    }
+
+    // `--auto-input`: newest backup of the default profile
+    pub fn auto_input() -> Result<(PathBuf, Vec<u8>), Box<dyn std::error::Error>> {
+        let profiles = discover_profiles()?;
+        let profile = match default_profile(&profiles) {
+            Some(profile) => profile,
+            None => {
+                return Err(
+                    "no default Firefox profile, use --profile <name> or -i <bookmarks.json>"
+                        .into(),
+                )
+            }
+        };
+        let backup_path = newest_bookmark_backup(&profile.path)?;
+        let json = read_bookmark_backup(&backup_path)?;
+        Ok((backup_path, json))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_profiles_ini() {
+            let firefox_dir = Path::new("/home/me/.mozilla/firefox");
+            let profiles = parse_profiles_ini(
+                "[Install4F96D1932A9F858E]\nDefault=Profiles/abcd.default-release\nLocked=1\n\n\
+                [Profile1]\nName=work\nIsRelative=0\nPath=/data/firefox/work\nDefault=1\n\n\
+                [Profile0]\nName=default-release\nIsRelative=1\nPath=Profiles/abcd.default-release\n\n\
+                [General]\nStartWithLastProfile=1\nVersion=2\n",
+                firefox_dir,
+            );
+            assert_eq!(
+                profiles,
+                vec![
+                    FirefoxProfile {
+                        name: "work".to_string(),
+                        path: PathBuf::from("/data/firefox/work"),
+                        is_default: false,
+                    },
+                    FirefoxProfile {
+                        name: "default-release".to_string(),
+                        path: firefox_dir.join("Profiles/abcd.default-release"),
+                        is_default: true,
+                    },
+                ]
+            );
+            assert_eq!(select_profiles(&profiles, "all").unwrap().len(), 2);
+            assert_eq!(select_profiles(&profiles, "WORK").unwrap()[0].name, "work");
+            assert_eq!(
+                select_profiles(&profiles, "default").unwrap()[0].name,
+                "default-release"
+            );
+            assert!(select_profiles(&profiles, "personal")
+                .unwrap_err()
+                .contains("work, default-release, all"));
+        }
+
+        #[test]
+        fn test_read_places_bookmarks() {
+            let profile_dir = Path::new("samples/test_firefox_profile");
+            fs::create_dir_all(profile_dir).unwrap();
+            let places_path = profile_dir.join(K_PLACES_DB);
+            fs::remove_file(&places_path).unwrap_or_default();
+            let conn = Connection::open(&places_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+                CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER, title TEXT, dateAdded INTEGER, lastModified INTEGER, guid TEXT);
+                INSERT INTO moz_places VALUES (1, 'https://example.com/manga/gate/chapter-5', 'Gate - Chapter 5');
+                INSERT INTO moz_places VALUES (2, 'place:sort=8&maxResults=10', NULL);
+                INSERT INTO moz_bookmarks VALUES (1, 2, NULL, 0, 'manga', 1694008642000000, 1694008642000000, 'menu________');
+                INSERT INTO moz_bookmarks VALUES (2, 1, 1, 4, 'ゲート', 1694008642000000, 1694008642000000, 'a1B2c3D4e5F6');
+                INSERT INTO moz_bookmarks VALUES (3, 1, 2, 1, 'Most Visited', 1694008642000000, 1694008642000000, 'g7H8i9J0k1L2');
+                INSERT INTO moz_bookmarks VALUES (4, 2, NULL, 1, 'Reading', 1694008642000000, 1694008642000000, 'm3N4o5P6q7R8');",
+            )
+            .unwrap();
+            drop(conn);
+
+            let (mangas, origins) = read_places_bookmarks(profile_dir, "work", &Pipeline::default()).unwrap();
+            assert_eq!(mangas.len(), 1);
+            assert_eq!(mangas[0].title(), "ゲート");
+            assert_eq!(mangas[0].bookmark_guid(), Some("a1B2c3D4e5F6".to_string()));
+            assert_eq!(
+                mangas[0].url_with_chapter(),
+                Some("https://example.com/manga/gate/chapter-5".to_string())
+            );
+            assert_eq!(origins.len(), 1);
+            assert_eq!(
+                (origins[0].profile.as_str(), origins[0].folder_path.as_str()),
+                ("work", "manga/Reading")
+            );
+            assert_eq!(origins[0].date_added_millis, 1694008642000);
+            assert!(read_places_bookmarks(
+                Path::new("samples/no_such_profile"),
+                "",
+                &Pipeline::default()
+            ).is_err());
+
+            // the file itself, under any name, or the directory it is in
+            assert_eq!(places_path_of(profile_dir), places_path);
+            let renamed_path = profile_dir.join("copy.sqlite");
+            fs::copy(&places_path, &renamed_path).unwrap();
+            assert_eq!(places_path_of(&renamed_path), renamed_path);
+            let (mangas, _) = read_places_file(&renamed_path, "", &Pipeline::default()).unwrap();
+            assert_eq!(mangas[0].title(), "ゲート");
+            fs::remove_dir_all(profile_dir).unwrap();
+        }
+
+        #[test]
+        fn test_newest_bookmark_backup() {
+            let profile_dir = Path::new("samples/test_firefox_backups");
+            let backups_dir = profile_dir.join(K_BOOKMARK_BACKUPS_DIR);
+            fs::remove_dir_all(profile_dir).unwrap_or_default();
+            fs::create_dir_all(&backups_dir).unwrap();
+            let json = br#"{"guid":"root________","title":"","children":[]}"#;
+            let mut mozlz4 = K_MOZLZ4_MAGIC.to_vec();
+            mozlz4.extend_from_slice(&(json.len() as u32).to_le_bytes());
+            mozlz4.extend_from_slice(&lz4_flex::block::compress(json));
+            fs::write(
+                backups_dir.join("bookmarks-2023-09-05_10_aaaa.jsonlz4"),
+                b"old",
+            )
+            .unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            fs::write(
+                backups_dir.join("bookmarks-2023-09-06_12_bbbb.jsonlz4"),
+                &mozlz4,
+            )
+            .unwrap();
+            fs::write(backups_dir.join("notes.txt"), b"not a backup").unwrap();
+
+            let newest = newest_bookmark_backup(profile_dir).unwrap();
+            assert!(newest.ends_with("bookmarks-2023-09-06_12_bbbb.jsonlz4"));
+            assert_eq!(read_bookmark_backup(&newest).unwrap(), json.to_vec());
+            assert!(decode_mozlz4(b"mozLz40\0\x10\0\0\0garbage").is_err());
+            assert!(newest_bookmark_backup(Path::new("samples/no_such_profile")).is_err());
+            fs::remove_dir_all(profile_dir).unwrap();
+        }
+    }
+}
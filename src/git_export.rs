@@ -0,0 +1,267 @@
+// Versioned history of the collection for free: after an import (bookmarks, `import-yaml`), the whole collection
+// is re-exported as CSV and JSON into a git repository and committed, with what the import changed as the
+// message:
+//      import bookmarks: 3 added, 1 updated
+//
+//      added: ゲート
+//      ...
+// Both exports are sorted by id so that an unchanged collection gives identical files, and nothing is committed
+// when the files did not change.  Only when configured (the repository must already exist, `git init` it once):
+//      [git_export]
+//      repo = "/home/me/manga-history"
+//      csv_file = "漫画.csv"              # default, relative to repo
+//      json_file = "漫画.json"            # default, relative to repo
+pub mod git_export {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        process::Command,
+    };
+
+    use crate::config::config::GitExportConfig;
+    use crate::model_manga::model_manga::MangaModel;
+
+    pub const K_DEFAULT_CSV_FILE: &str = "漫画.csv";
+    pub const K_DEFAULT_JSON_FILE: &str = "漫画.json";
+    const K_CSV_HEADER: [&str; 11] = [
+        "id",
+        "title",
+        "title_romanized",
+        "url",
+        "url_with_chapter",
+        "chapter",
+        "last_update",
+        "notes",
+        "tags",
+        "my_anime_list",
+        "feed_url",
+    ];
+    const K_MAX_LISTED_TITLES: usize = 50; // per kind of change, the message is not the place for a full diff
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct CollectionChanges {
+        pub added: Vec<String>, // titles
+        pub updated: Vec<String>,
+        pub removed: Vec<String>,
+    }
+
+    impl CollectionChanges {
+        pub fn is_empty(&self) -> bool {
+            self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+        }
+    }
+
+    // by id; a manga is updated when anything it serializes differs (MangaModel's PartialEq only looks at title and url)
+    pub fn collection_changes(before: &[MangaModel], after: &[MangaModel]) -> CollectionChanges {
+        let before_by_id: HashMap<i64, &MangaModel> =
+            before.iter().map(|manga| (manga.id(), manga)).collect();
+        let after_by_id: HashMap<i64, &MangaModel> =
+            after.iter().map(|manga| (manga.id(), manga)).collect();
+        let mut changes = CollectionChanges::default();
+        for manga in sorted_by_id(after).iter() {
+            match before_by_id.get(&manga.id()) {
+                None => changes.added.push(manga.title().to_string()),
+                Some(previous) => {
+                    if serde_json::to_value(previous).ok() != serde_json::to_value(manga).ok() {
+                        changes.updated.push(manga.title().to_string());
+                    }
+                }
+            }
+        }
+        for manga in sorted_by_id(before).iter() {
+            if !after_by_id.contains_key(&manga.id()) {
+                changes.removed.push(manga.title().to_string());
+            }
+        }
+        changes
+    }
+
+    // i.e. "import bookmarks: 3 added, 1 updated" followed by the titles
+    pub fn commit_message(source: &str, changes: &CollectionChanges) -> String {
+        let mut counts: Vec<String> = Vec::new();
+        for (kind, titles) in [
+            ("added", &changes.added),
+            ("updated", &changes.updated),
+            ("removed", &changes.removed),
+        ] {
+            if !titles.is_empty() {
+                counts.push(format!("{} {}", titles.len(), kind));
+            }
+        }
+        let mut message = match counts.is_empty() {
+            true => format!("{}: no changes", source),
+            false => format!("{}: {}", source, counts.join(", ")),
+        };
+        if !changes.is_empty() {
+            message.push('\n');
+        }
+        for (kind, titles) in [
+            ("added", &changes.added),
+            ("updated", &changes.updated),
+            ("removed", &changes.removed),
+        ] {
+            for title in titles.iter().take(K_MAX_LISTED_TITLES) {
+                message.push_str(&format!("\n{}: {}", kind, title));
+            }
+            if titles.len() > K_MAX_LISTED_TITLES {
+                message.push_str(&format!(
+                    "\n{}: ... and {} more",
+                    kind,
+                    titles.len() - K_MAX_LISTED_TITLES
+                ));
+            }
+        }
+        message
+    }
+
+    fn sorted_by_id(mangas: &[MangaModel]) -> Vec<MangaModel> {
+        let mut sorted = mangas.to_vec();
+        sorted.sort_by_key(|manga| manga.id());
+        sorted
+    }
+
+    fn to_csv_record(manga: &MangaModel) -> Vec<String> {
+        let tags: Vec<&str> = manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        vec![
+            manga.id().to_string(),
+            manga.title().to_string(),
+            manga.title_romanized().unwrap_or_default(),
+            manga.url().to_string(),
+            manga.url_with_chapter().unwrap_or_default(),
+            manga.chapter().unwrap_or_default(),
+            manga.last_update().unwrap_or_default(),
+            manga.notes().unwrap_or_default(),
+            MangaModel::tags_to_csv(&tags), // same separator (and escaping) as the legacy CSV
+            manga.my_anime_list().unwrap_or_default(),
+            manga.feed_url().unwrap_or_default(),
+        ]
+    }
+
+    // CSV with one column per field (id included, unlike the legacy `-o` CSV) and the JSON of the REST API,
+    // both sorted by id
+    pub fn write_exports(
+        config: &GitExportConfig,
+        mangas: &[MangaModel],
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if !Path::new(&config.repo).is_dir() {
+            return Err(format!("repository '{}' does not exist", config.repo).into());
+        }
+        let sorted = sorted_by_id(mangas);
+        let csv_file = config
+            .csv_file
+            .clone()
+            .unwrap_or_else(|| K_DEFAULT_CSV_FILE.to_string());
+        let json_file = config
+            .json_file
+            .clone()
+            .unwrap_or_else(|| K_DEFAULT_JSON_FILE.to_string());
+
+        let mut csv_writer = csv::WriterBuilder::new()
+            .quote_style(csv::QuoteStyle::Always)
+            .from_path(Path::new(&config.repo).join(&csv_file))?;
+        csv_writer.write_record(K_CSV_HEADER)?;
+        for manga in sorted.iter() {
+            csv_writer.write_record(to_csv_record(manga))?;
+        }
+        csv_writer.flush()?;
+        let mut json_writer =
+            BufWriter::new(File::create(Path::new(&config.repo).join(&json_file))?);
+        serde_json::to_writer_pretty(&mut json_writer, &sorted)?;
+        json_writer.write_all(b"\n")?;
+        json_writer.flush()?;
+        Ok(vec![csv_file, json_file])
+    }
+
+    fn git(repo: &str, args: &[&str]) -> Result<std::process::Output, Box<dyn std::error::Error>> {
+        match Command::new("git").arg("-C").arg(repo).args(args).output() {
+            Ok(output) => Ok(output),
+            Err(e) => Err(format!("cannot run git: {}", e).into()),
+        }
+    }
+
+    fn git_checked(repo: &str, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        let output = git(repo, args)?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(format!(
+                "'git {}' failed in '{}': {}",
+                args.join(" "),
+                repo,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into()),
+        }
+    }
+
+    // returns the first line of the commit message, or None when the exports did not change
+    pub fn commit_exports(
+        config: &GitExportConfig,
+        source: &str,
+        before: &[MangaModel],
+        after: &[MangaModel],
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let files = write_exports(config, after)?;
+        let mut add_args = vec!["add", "--"];
+        add_args.extend(files.iter().map(|file| file.as_str()));
+        git_checked(&config.repo, &add_args)?;
+        // exit code 0 when nothing is staged
+        if git(&config.repo, &["diff", "--cached", "--quiet"])?
+            .status
+            .success()
+        {
+            return Ok(None);
+        }
+        let message = commit_message(source, &collection_changes(before, after));
+        git_checked(&config.repo, &["commit", "--quiet", "-m", &message])?;
+        Ok(message.lines().next().map(|line| line.to_string()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_commit_message() {
+            let gate = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            let mut akira_read = akira.clone();
+            akira_read.set_chapter(Some("12".to_string()));
+            let dr_stone =
+                MangaModel::new_from_required_elements("Dr. Stone", "https://example.com/dr/", 3)
+                    .unwrap();
+
+            let changes = collection_changes(
+                &[akira.clone(), gate.clone()],
+                &[dr_stone, akira_read, gate.clone()],
+            );
+            assert_eq!(changes.added, vec!["Dr. Stone"]);
+            assert_eq!(changes.updated, vec!["Akira"]);
+            assert!(changes.removed.is_empty());
+            assert_eq!(
+                commit_message("import bookmarks", &changes),
+                "import bookmarks: 1 added, 1 updated\n\nadded: Dr. Stone\nupdated: Akira"
+            );
+
+            let changes = collection_changes(&[akira, gate.clone()], &[gate]);
+            assert_eq!(changes.removed, vec!["Akira"]);
+            assert_eq!(
+                commit_message("import-yaml", &CollectionChanges::default()),
+                "import-yaml: no changes"
+            );
+        }
+    }
+}
@@ -0,0 +1,439 @@
+// GraphQL over the collection, served by `manga serve` next to the REST API: POST /graphql for queries,
+// GET /graphql for the GraphiQL explorer.  Lets front-ends ask for exactly the fields they need, i.e.
+//      { mangas(filter: {unreadOnly: true}, sort: UNREAD_COUNT, descending: true) { title unreadCount } }
+// Read-only; edits go through the REST API.  Reading history, url history, bookmark origins and enrichment
+// metadata are only queried when asked for.
+pub mod graphql_api {
+    use async_graphql::{
+        Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema, SimpleObject,
+    };
+    use std::collections::BTreeMap;
+
+    use crate::error::error::Error;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::{
+        self, BookmarkOrigin,
+    };
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::{self, MangaMetadata};
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions::{
+        self, ReadingSession,
+    };
+    use crate::model_sqlite3_url_history::model_sqlite3_url_history::{self, UrlHistory};
+
+    pub type MangaSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+    struct GraphQlContext {
+        db_full_paths: String,
+    }
+
+    fn db_full_paths<'a>(ctx: &Context<'a>) -> &'a str {
+        &ctx.data_unchecked::<GraphQlContext>().db_full_paths
+    }
+
+    pub fn build_schema(db_full_paths: &str) -> MangaSchema {
+        Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .data(GraphQlContext {
+                db_full_paths: db_full_paths.to_string(),
+            })
+            .finish()
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+    pub enum MangaSort {
+        Id,
+        Title,
+        LastUpdate,
+        UnreadCount,
+    }
+
+    // every field given must match
+    #[derive(Debug, Clone, Default, InputObject)]
+    pub struct MangaFilter {
+        pub title: Option<String>, // part of the title or romanized title, case insensitive
+        pub tag: Option<String>,
+        pub domain: Option<String>, // the domain or any of its subdomains
+        pub unread_only: Option<bool>,
+    }
+
+    impl MangaFilter {
+        pub fn matches(&self, manga: &MangaModel) -> bool {
+            let title_matches = match &self.title {
+                Some(title) => {
+                    let title = title.to_lowercase();
+                    manga.title().to_lowercase().contains(&title)
+                        || manga
+                            .title_romanized()
+                            .map_or(false, |romanized| romanized.to_lowercase().contains(&title))
+                }
+                None => true,
+            };
+            let tag_matches = match &self.tag {
+                Some(tag) => manga
+                    .tags()
+                    .iter()
+                    .any(|t| t.trim().to_lowercase() == tag.trim().to_lowercase()),
+                None => true,
+            };
+            let domain_matches = match &self.domain {
+                Some(domain) => url::Url::parse(manga.url())
+                    .ok()
+                    .and_then(|url| url.host_str().map(|host| host.to_string()))
+                    .map_or(false, |host| {
+                        host == *domain || host.ends_with(&format!(".{}", domain))
+                    }),
+                None => true,
+            };
+            let unread_matches = match self.unread_only {
+                Some(true) => manga.unread_count().unwrap_or(0) > 0,
+                _ => true,
+            };
+            title_matches && tag_matches && domain_matches && unread_matches
+        }
+    }
+
+    pub fn sort_mangas(mangas: &mut [MangaModel], sort: MangaSort, descending: bool) {
+        match sort {
+            MangaSort::Id => mangas.sort_by_key(|manga| manga.id()),
+            MangaSort::Title => mangas.sort_by_key(|manga| manga.title().to_lowercase()),
+            MangaSort::LastUpdate => mangas.sort_by_key(|manga| manga.last_update_millis()),
+            MangaSort::UnreadCount => mangas.sort_by_key(|manga| manga.unread_count()),
+        }
+        if descending {
+            mangas.reverse();
+        }
+    }
+
+    #[derive(Debug, Clone, SimpleObject)]
+    pub struct TagCount {
+        pub tag: String,
+        pub count: usize,
+    }
+
+    #[derive(Debug, Clone, SimpleObject)]
+    pub struct ReadingSessionObject {
+        pub manga_id: i64,
+        pub chapter: String,
+        pub read_at_millis: i64,
+    }
+
+    impl From<ReadingSession> for ReadingSessionObject {
+        fn from(session: ReadingSession) -> Self {
+            ReadingSessionObject {
+                manga_id: session.manga_id,
+                chapter: session.chapter,
+                read_at_millis: session.read_at_millis,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, SimpleObject)]
+    pub struct UrlHistoryObject {
+        pub old_url: String,
+        pub new_url: String,
+        pub reason: String,
+        pub changed_at_millis: i64,
+    }
+
+    impl From<UrlHistory> for UrlHistoryObject {
+        fn from(history: UrlHistory) -> Self {
+            UrlHistoryObject {
+                old_url: history.old_url,
+                new_url: history.new_url,
+                reason: history.reason,
+                changed_at_millis: history.changed_at_millis,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, SimpleObject)]
+    pub struct BookmarkOriginObject {
+        pub profile: String,
+        pub folder_path: String,
+        pub guid: String,
+        pub date_added_millis: i64,
+    }
+
+    impl From<BookmarkOrigin> for BookmarkOriginObject {
+        fn from(origin: BookmarkOrigin) -> Self {
+            BookmarkOriginObject {
+                profile: origin.profile,
+                folder_path: origin.folder_path,
+                guid: origin.guid,
+                date_added_millis: origin.date_added_millis,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, SimpleObject)]
+    pub struct MetadataObject {
+        pub source: String,
+        pub source_url: Option<String>,
+        pub title_romaji: Option<String>,
+        pub title_native: Option<String>,
+        pub authors: Vec<String>,
+        pub publication_status: Option<String>,
+        pub synopsis: Option<String>,
+        pub cover_url: Option<String>,
+        pub updated_at_millis: i64,
+    }
+
+    impl From<MangaMetadata> for MetadataObject {
+        fn from(metadata: MangaMetadata) -> Self {
+            MetadataObject {
+                source: metadata.source,
+                source_url: metadata.source_url,
+                title_romaji: metadata.title_romaji,
+                title_native: metadata.title_native,
+                authors: metadata.authors,
+                publication_status: metadata.publication_status,
+                synopsis: metadata.synopsis,
+                cover_url: metadata.cover_url,
+                updated_at_millis: metadata.updated_at_millis,
+            }
+        }
+    }
+
+    pub struct MangaObject(MangaModel);
+
+    #[Object]
+    impl MangaObject {
+        async fn id(&self) -> i64 {
+            self.0.id()
+        }
+        async fn title(&self) -> &str {
+            self.0.title()
+        }
+        async fn title_romanized(&self) -> Option<String> {
+            self.0.title_romanized()
+        }
+        async fn url(&self) -> &str {
+            self.0.url()
+        }
+        async fn url_with_chapter(&self) -> Option<String> {
+            self.0.url_with_chapter()
+        }
+        async fn chapter(&self) -> Option<String> {
+            self.0.chapter()
+        }
+        async fn last_update(&self) -> Option<String> {
+            self.0.last_update()
+        }
+        async fn last_update_millis(&self) -> Option<i64> {
+            self.0.last_update_millis()
+        }
+        async fn notes(&self) -> Option<String> {
+            self.0.notes()
+        }
+        async fn tags(&self) -> Vec<String> {
+            self.0
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .collect()
+        }
+        async fn my_anime_list(&self) -> Option<String> {
+            self.0.my_anime_list()
+        }
+        async fn feed_url(&self) -> Option<String> {
+            self.0.feed_url()
+        }
+        async fn latest_available_chapter(&self) -> Option<String> {
+            self.0.latest_available_chapter()
+        }
+        async fn last_checked_millis(&self) -> Option<i64> {
+            self.0.last_checked_millis()
+        }
+        async fn updated_at_millis(&self) -> Option<i64> {
+            self.0.updated_at_millis()
+        }
+        // null when either chapter is unknown
+        async fn unread_count(&self) -> Option<u32> {
+            self.0.unread_count()
+        }
+        // oldest first
+        async fn reading_sessions(
+            &self,
+            ctx: &Context<'_>,
+        ) -> async_graphql::Result<Vec<ReadingSessionObject>> {
+            Ok(
+                model_sqlite3_reading_sessions::select_reading_sessions_of_manga(
+                    db_full_paths(ctx),
+                    self.0.id(),
+                )?
+                .into_iter()
+                .map(ReadingSessionObject::from)
+                .collect(),
+            )
+        }
+        // oldest first
+        async fn url_history(
+            &self,
+            ctx: &Context<'_>,
+        ) -> async_graphql::Result<Vec<UrlHistoryObject>> {
+            Ok(model_sqlite3_url_history::select_url_history_of_manga(
+                db_full_paths(ctx),
+                self.0.id(),
+            )?
+            .into_iter()
+            .map(UrlHistoryObject::from)
+            .collect())
+        }
+        // the Firefox bookmark(s) it was imported from, oldest first
+        async fn bookmark_origins(
+            &self,
+            ctx: &Context<'_>,
+        ) -> async_graphql::Result<Vec<BookmarkOriginObject>> {
+            Ok(
+                model_sqlite3_bookmark_origin::select_bookmark_origins_of_manga(
+                    db_full_paths(ctx),
+                    self.0.id(),
+                )?
+                .into_iter()
+                .map(BookmarkOriginObject::from)
+                .collect(),
+            )
+        }
+        // null until `enrich` found the series
+        async fn metadata(
+            &self,
+            ctx: &Context<'_>,
+        ) -> async_graphql::Result<Option<MetadataObject>> {
+            Ok(model_sqlite3_manga_metadata::select_manga_metadata(
+                db_full_paths(ctx),
+                self.0.id(),
+            )?
+            .map(MetadataObject::from))
+        }
+    }
+
+    fn select_all_manga_or_empty(db_full_paths: &str) -> async_graphql::Result<Vec<MangaModel>> {
+        match model_sqlite3_manga::select_all_manga(db_full_paths) {
+            Ok(mangas) => Ok(mangas),
+            Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub struct QueryRoot;
+
+    #[Object]
+    impl QueryRoot {
+        // null when there is no such id
+        async fn manga(
+            &self,
+            ctx: &Context<'_>,
+            id: i64,
+        ) -> async_graphql::Result<Option<MangaObject>> {
+            match model_sqlite3_manga::select_manga_by_id(db_full_paths(ctx), id) {
+                Ok(manga) => Ok(Some(MangaObject(manga))),
+                Err(Error::NotFound(_)) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        // sorted by id unless told otherwise
+        async fn mangas(
+            &self,
+            ctx: &Context<'_>,
+            filter: Option<MangaFilter>,
+            sort: Option<MangaSort>,
+            #[graphql(default = false)] descending: bool,
+            offset: Option<usize>,
+            limit: Option<usize>,
+        ) -> async_graphql::Result<Vec<MangaObject>> {
+            let filter = filter.unwrap_or_default();
+            let mut mangas: Vec<MangaModel> = select_all_manga_or_empty(db_full_paths(ctx))?
+                .into_iter()
+                .filter(|manga| filter.matches(manga))
+                .collect();
+            sort_mangas(&mut mangas, sort.unwrap_or(MangaSort::Id), descending);
+            Ok(mangas
+                .into_iter()
+                .skip(offset.unwrap_or(0))
+                .take(limit.unwrap_or(usize::MAX))
+                .map(MangaObject)
+                .collect())
+        }
+
+        // every tag in use and how many manga have it, most used first
+        async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TagCount>> {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for manga in select_all_manga_or_empty(db_full_paths(ctx))?.iter() {
+                for tag in manga.tags().iter().map(|tag| tag.trim()) {
+                    if !tag.is_empty() {
+                        *counts.entry(tag.to_string()).or_insert(0) += 1;
+                    }
+                }
+            }
+            let mut tag_counts: Vec<TagCount> = counts
+                .into_iter()
+                .map(|(tag, count)| TagCount { tag, count })
+                .collect();
+            tag_counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.tag.cmp(&b.tag)));
+            Ok(tag_counts)
+        }
+
+        // reading history of the whole collection, oldest first
+        async fn reading_sessions(
+            &self,
+            ctx: &Context<'_>,
+            #[graphql(default = 0)] since_millis: i64,
+            until_millis: Option<i64>,
+        ) -> async_graphql::Result<Vec<ReadingSessionObject>> {
+            Ok(model_sqlite3_reading_sessions::select_reading_sessions(
+                db_full_paths(ctx),
+                since_millis,
+                until_millis.unwrap_or(i64::MAX),
+            )?
+            .into_iter()
+            .map(ReadingSessionObject::from)
+            .collect())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn sample_manga(id: i64, title: &str, url: &str) -> MangaModel {
+            MangaModel::new_from_required_elements(title, url, id).unwrap()
+        }
+
+        #[test]
+        fn test_filter_and_sort() {
+            let mut mangas = vec![
+                sample_manga(1, "Gate", "https://www.example.com/manga/gate/"),
+                sample_manga(2, "Akira", "https://other.com/manga/akira/"),
+            ];
+            mangas[0].set_tags(vec!["seinen".to_string(), " isekai".to_string()]);
+
+            let filter = MangaFilter {
+                tag: Some("isekai".to_string()),
+                ..MangaFilter::default()
+            };
+            assert!(filter.matches(&mangas[0]));
+            assert!(!filter.matches(&mangas[1]));
+            let filter = MangaFilter {
+                domain: Some("example.com".to_string()),
+                title: Some("GAT".to_string()),
+                ..MangaFilter::default()
+            };
+            assert!(filter.matches(&mangas[0]));
+            assert!(!filter.matches(&mangas[1]));
+
+            sort_mangas(&mut mangas, MangaSort::Title, false);
+            assert_eq!(mangas[0].title(), "Akira");
+            sort_mangas(&mut mangas, MangaSort::Id, true);
+            assert_eq!(mangas[0].id(), 2);
+        }
+
+        #[test]
+        fn test_schema_sdl() {
+            let sdl = build_schema("samples/no_such.db").sdl();
+            assert!(sdl.contains("unreadCount"));
+            assert!(sdl.contains("readingSessions"));
+            assert!(sdl.contains("UNREAD_COUNT"));
+        }
+    }
+}
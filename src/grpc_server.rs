@@ -0,0 +1,277 @@
+// gRPC counterpart of rest_server: `manga serve-grpc [--bind 127.0.0.1:50051]`, only when built with
+// `--features grpc` (tonic, plus a vendored protoc to compile proto/manga.proto in build.rs).
+// Lists and searches are server streams, and bookmarks can be imported by sending the bookmarks JSON.
+// Requests are converted to the model_manga REST request structs, so both servers validate and apply
+// them through the very same service functions.
+pub mod grpc_server {
+    use tonic::{transport::Server, Request, Response, Status};
+
+    use crate::config::config::{RomanizationOptions, TitleCollisionPolicy, TitleRuleConfig};
+    use crate::daemon::daemon;
+    use crate::model_manga::model_manga::{
+        MangaCreateRequest, MangaModel, MangaSearchRequest, MangaUpdateRequest,
+    };
+    use crate::service::service::{self, ServiceError};
+
+    pub mod proto {
+        tonic::include_proto!("manga");
+    }
+
+    use proto::manga_service_server::{MangaService, MangaServiceServer};
+
+    pub const K_DEFAULT_BIND: &str = "127.0.0.1:50051";
+
+    pub struct GrpcServerOptions {
+        pub db_full_paths: String,
+        pub bind: String,
+        pub title_rules: Vec<TitleRuleConfig>, // applied to imported bookmarks, as by the daemon
+        pub title_collision: TitleCollisionPolicy,
+        pub romanization: RomanizationOptions,
+        pub debug_flag: bool,
+    }
+
+    struct MangaServiceImpl {
+        service: service::MangaService,
+        title_rules: Vec<TitleRuleConfig>,
+        title_collision: TitleCollisionPolicy,
+        romanization: RomanizationOptions,
+        debug_flag: bool,
+    }
+
+    type MangaStream = tokio_stream::Iter<std::vec::IntoIter<Result<proto::Manga, Status>>>;
+
+    pub fn to_proto_manga(manga: &MangaModel) -> proto::Manga {
+        proto::Manga {
+            id: manga.id(),
+            title: manga.title().to_string(),
+            title_romanized: manga.title_romanized(),
+            url: manga.url().to_string(),
+            url_with_chapter: manga.url_with_chapter(),
+            chapter: manga.chapter(),
+            last_update: manga.last_update(),
+            last_update_millis: manga.last_update_millis(),
+            notes: manga.notes(),
+            tags: manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .collect(),
+            my_anime_list: manga.my_anime_list(),
+            feed_url: manga.feed_url(),
+            latest_available_chapter: manga.latest_available_chapter(),
+            last_checked_millis: manga.last_checked_millis(),
+            updated_at_millis: manga.updated_at_millis(),
+        }
+    }
+
+    fn to_status(e: ServiceError) -> Status {
+        match e {
+            ServiceError::NotFound(_) => Status::not_found(e.to_string()),
+            ServiceError::AlreadyExists(_) => Status::already_exists(e.to_string()),
+            ServiceError::InvalidInput(_) => Status::invalid_argument(e.to_string()),
+            ServiceError::DatabaseBusy(_) => Status::unavailable(e.to_string()),
+            ServiceError::Database(_) | ServiceError::Io(_) => Status::internal(e.to_string()),
+        }
+    }
+
+    // the database layer is synchronous (a Connection per call), so keep it off the async workers
+    async fn blocking<T, F>(f: F) -> Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, ServiceError> + Send + 'static,
+    {
+        match tokio::task::spawn_blocking(f).await {
+            Ok(result) => result.map_err(to_status),
+            Err(e) => Err(Status::internal(e.to_string())),
+        }
+    }
+
+    fn to_stream(mangas: Vec<MangaModel>) -> MangaStream {
+        let messages: Vec<Result<proto::Manga, Status>> = mangas
+            .iter()
+            .map(|manga| Ok(to_proto_manga(manga)))
+            .collect();
+        tokio_stream::iter(messages)
+    }
+
+    #[tonic::async_trait]
+    impl MangaService for MangaServiceImpl {
+        type ListMangaStream = MangaStream;
+        type SearchMangaStream = MangaStream;
+
+        async fn list_manga(
+            &self,
+            _request: Request<proto::ListMangaRequest>,
+        ) -> Result<Response<Self::ListMangaStream>, Status> {
+            let service = self.service.clone();
+            let mangas = blocking(move || service.list()).await?;
+            Ok(Response::new(to_stream(mangas)))
+        }
+
+        async fn get_manga(
+            &self,
+            request: Request<proto::GetMangaRequest>,
+        ) -> Result<Response<proto::Manga>, Status> {
+            let service = self.service.clone();
+            let id = request.into_inner().id;
+            let manga = blocking(move || service.get(id)).await?;
+            Ok(Response::new(to_proto_manga(&manga)))
+        }
+
+        async fn create_manga(
+            &self,
+            request: Request<proto::CreateMangaRequest>,
+        ) -> Result<Response<proto::CreateMangaResponse>, Status> {
+            let service = self.service.clone();
+            let request = request.into_inner();
+            let inserted = blocking(move || {
+                service.create(MangaCreateRequest {
+                    title: request.title,
+                    title_romanized: request.title_romanized,
+                    url: request.url,
+                    url_with_chapter: request.url_with_chapter,
+                    chapter: request.chapter,
+                    last_update: request.last_update,
+                    notes: request.notes,
+                    tags: request.tags,
+                })
+            })
+            .await?;
+            Ok(Response::new(proto::CreateMangaResponse {
+                id: inserted.id(),
+            }))
+        }
+
+        async fn update_manga(
+            &self,
+            request: Request<proto::UpdateMangaRequest>,
+        ) -> Result<Response<proto::UpdateMangaResponse>, Status> {
+            let service = self.service.clone();
+            let request = request.into_inner();
+            let id = request.id;
+            blocking(move || {
+                service.update(
+                    id,
+                    MangaUpdateRequest {
+                        title: request.title,
+                        title_romanized: request.title_romanized,
+                        url: request.url,
+                        url_with_chapter: request.url_with_chapter,
+                        chapter: request.chapter,
+                        last_update: request.last_update,
+                        notes: request.notes,
+                        tags: request.tags,
+                    },
+                )
+            })
+            .await?;
+            Ok(Response::new(proto::UpdateMangaResponse { id }))
+        }
+
+        async fn delete_manga(
+            &self,
+            request: Request<proto::DeleteMangaRequest>,
+        ) -> Result<Response<proto::DeleteMangaResponse>, Status> {
+            let service = self.service.clone();
+            let id = request.into_inner().id;
+            blocking(move || service.delete(id)).await?;
+            Ok(Response::new(proto::DeleteMangaResponse { id }))
+        }
+
+        async fn search_manga(
+            &self,
+            request: Request<proto::SearchMangaRequest>,
+        ) -> Result<Response<Self::SearchMangaStream>, Status> {
+            let service = self.service.clone();
+            let request = request.into_inner();
+            let search_request = MangaSearchRequest {
+                title: request.title,
+                title_romanized: request.title_romanized,
+                url: request.url,
+                url_with_chapter: request.url_with_chapter,
+                chapter: request.chapter,
+                last_update: request.last_update,
+                notes: request.notes,
+                tags: request.tags,
+                tag_expr: request.tag_expr,
+            };
+            let mangas = blocking(move || service.search(&search_request)).await?;
+            Ok(Response::new(to_stream(mangas)))
+        }
+
+        async fn import_bookmarks(
+            &self,
+            request: Request<proto::ImportBookmarksRequest>,
+        ) -> Result<Response<proto::ImportBookmarksResponse>, Status> {
+            let db_full_paths = self.service.db_full_paths().to_string();
+            let debug_flag = self.debug_flag;
+            let title_rules = self.title_rules.clone();
+            let title_collision = self.title_collision;
+            let romanization = self.romanization.clone();
+            let bookmarks_json = request.into_inner().bookmarks_json;
+            let bookmarks_count = blocking(move || {
+                daemon::import_bookmarks_reader(
+                    &db_full_paths,
+                    bookmarks_json.as_bytes(),
+                    &title_rules,
+                    title_collision,
+                    &romanization,
+                    debug_flag,
+                )
+                .map_err(|e| ServiceError::InvalidInput(e.to_string()))
+            })
+            .await?;
+            Ok(Response::new(proto::ImportBookmarksResponse {
+                bookmarks_count: bookmarks_count as u32,
+            }))
+        }
+    }
+
+    // blocks until the process is stopped
+    pub fn run(options: GrpcServerOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let address = options.bind.parse()?;
+        let service = MangaServiceImpl {
+            service: service::MangaService::open(&options.db_full_paths)?
+                .with_title_collision(options.title_collision)
+                .with_romanization(options.romanization.clone()),
+            title_rules: options.title_rules,
+            title_collision: options.title_collision,
+            romanization: options.romanization,
+            debug_flag: options.debug_flag,
+        };
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(async {
+            println!(
+                "serve-grpc: listening on {} (database '{}')",
+                address, options.db_full_paths
+            );
+            Server::builder()
+                .add_service(MangaServiceServer::new(service))
+                .serve(address)
+                .await?;
+            Ok(())
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_to_proto_manga() {
+            let mut manga = MangaModel::new_from_required_elements(
+                "Gate",
+                "https://example.com/manga/gate/chapter-58",
+                7,
+            )
+            .unwrap();
+            manga.set_tags(vec!["seinen".to_string(), " isekai".to_string()]);
+            let message = to_proto_manga(&manga);
+            assert_eq!(message.id, 7);
+            assert_eq!(message.title, "Gate");
+            assert_eq!(message.url, manga.url());
+            assert_eq!(message.tags, vec!["seinen", "isekai"]);
+            assert_eq!(message.feed_url, None);
+        }
+    }
+}
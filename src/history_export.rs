@@ -0,0 +1,134 @@
+// Reading history in "long" format (one row per chapter read) for spreadsheets and plotting tools:
+//      manga_id,title,chapter,read_at,read_at_millis
+//      1,ゲート,12,2023-09-06 22:57:22,1694008642000
+// read_at is local time "YYYY-MM-DD HH:MM:SS" (same as manga.last_update) for humans, read_at_millis for tools
+pub mod history_export {
+    use chrono::TimeZone;
+    use serde::Serialize;
+    use std::{collections::HashMap, io::Write};
+
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions::ReadingSession;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum HistoryFormat {
+        Csv,
+        Json,
+    }
+
+    impl std::str::FromStr for HistoryFormat {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "csv" => Ok(HistoryFormat::Csv),
+                "json" => Ok(HistoryFormat::Json),
+                _ => Err(format!(
+                    "unknown history format '{}', expected csv or json",
+                    s
+                )),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct HistoryRow {
+        pub manga_id: i64,
+        pub title: String,
+        pub chapter: String,
+        pub read_at: String,
+        pub read_at_millis: i64,
+    }
+
+    // titles maps manga_id to title; sessions of deleted manga keep an empty title rather than being dropped
+    pub fn to_history_rows(
+        sessions: &[ReadingSession],
+        titles: &HashMap<i64, String>,
+    ) -> Vec<HistoryRow> {
+        sessions
+            .iter()
+            .map(|session| HistoryRow {
+                manga_id: session.manga_id,
+                title: titles.get(&session.manga_id).cloned().unwrap_or_default(),
+                chapter: session.chapter.clone(),
+                read_at: match chrono::Local
+                    .timestamp_millis_opt(session.read_at_millis)
+                    .single()
+                {
+                    Some(datetime) => datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    None => String::new(),
+                },
+                read_at_millis: session.read_at_millis,
+            })
+            .collect()
+    }
+
+    pub fn write_history(
+        output_writer: &mut dyn Write,
+        format: HistoryFormat,
+        rows: &[HistoryRow],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            HistoryFormat::Csv => {
+                // header is written by serde based on HistoryRow field names
+                let mut csv_writer = csv::Writer::from_writer(output_writer);
+                for row in rows {
+                    csv_writer.serialize(row)?;
+                }
+                csv_writer.flush()?;
+            }
+            HistoryFormat::Json => {
+                serde_json::to_writer_pretty(&mut *output_writer, rows)?;
+                writeln!(output_writer)?;
+                output_writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn make_sample_rows() -> Vec<HistoryRow> {
+            let sessions = vec![
+                ReadingSession {
+                    id: 1,
+                    manga_id: 1,
+                    chapter: "12".to_string(),
+                    read_at_millis: 1_694_008_642_000,
+                },
+                ReadingSession {
+                    id: 2,
+                    manga_id: 2,
+                    chapter: "3.1".to_string(),
+                    read_at_millis: 1_694_008_700_000,
+                },
+            ];
+            to_history_rows(
+                &sessions,
+                &HashMap::from([(1, "ゲート, 自衛隊".to_string())]),
+            )
+        }
+
+        #[test]
+        fn test_write_history_csv() {
+            let mut buffer = Vec::new();
+            write_history(&mut buffer, HistoryFormat::Csv, &make_sample_rows()).unwrap();
+            let csv = String::from_utf8(buffer).unwrap();
+            let lines = csv.lines().collect::<Vec<&str>>();
+            assert_eq!(lines[0], "manga_id,title,chapter,read_at,read_at_millis");
+            assert!(lines[1].starts_with("1,\"ゲート, 自衛隊\",12,"));
+            assert!(lines[2].starts_with("2,,3.1,"));
+        }
+
+        #[test]
+        fn test_write_history_json() {
+            let mut buffer = Vec::new();
+            write_history(&mut buffer, HistoryFormat::Json, &make_sample_rows()).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+            assert_eq!(json.as_array().unwrap().len(), 2);
+            assert_eq!(json[0]["read_at_millis"], 1_694_008_642_000i64);
+            assert_eq!("CSV".parse::<HistoryFormat>(), Ok(HistoryFormat::Csv));
+            assert!("xml".parse::<HistoryFormat>().is_err());
+        }
+    }
+}
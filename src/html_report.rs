@@ -0,0 +1,248 @@
+// A single self-contained HTML file of the collection (`manga report --out manga.html`) which can be opened
+// on a phone or sent to someone without installing anything: the data is embedded as JSON and rendered by a
+// bit of inline JavaScript (search as you type, click a column header to sort).  Titles link to
+// url_with_chapter so that a tap continues where one left off.  Covers cached by `fetch-covers` are embedded
+// as data: urls, so they show offline; favicons are loaded from the sites and simply hidden when offline.
+pub mod html_report {
+    use base64::Engine;
+    use serde::Serialize;
+    use std::{io::Write, path::Path};
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct ReportEntry {
+        pub id: i64,
+        pub title: String,
+        pub title_romanized: String,
+        pub link: String, // url_with_chapter, or url when no chapter was read yet
+        pub domain: String,
+        pub chapter: String,
+        pub latest_chapter: String,
+        pub unread: Option<u32>,
+        pub last_read: String,
+        pub last_read_millis: i64,
+        pub tags: Vec<String>,
+        pub notes: String,
+        pub cover: Option<String>, // data: url
+    }
+
+    pub fn to_report_entry(manga: &MangaModel, possible_cover: Option<String>) -> ReportEntry {
+        ReportEntry {
+            id: manga.id(),
+            title: manga.title().to_string(),
+            title_romanized: manga.title_romanized().unwrap_or_default(),
+            link: manga
+                .url_with_chapter()
+                .filter(|url_with_chapter| !url_with_chapter.is_empty())
+                .unwrap_or_else(|| manga.url().to_string()),
+            domain: url::Url::parse(manga.url())
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_string()))
+                .unwrap_or_default(),
+            chapter: manga.chapter().unwrap_or_default(),
+            latest_chapter: manga.latest_available_chapter().unwrap_or_default(),
+            unread: manga.unread_count(),
+            last_read: manga.last_update().unwrap_or_default(),
+            last_read_millis: manga.last_update_millis().unwrap_or(0),
+            tags: manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            notes: manga.notes().unwrap_or_default(),
+            cover: possible_cover,
+        }
+    }
+
+    // None when the file cannot be read or is not an image the browsers all understand
+    pub fn cover_data_url(cover_path: &Path) -> Option<String> {
+        let bytes = std::fs::read(cover_path).ok()?;
+        // cached covers may be named ".img", so go by the content rather than the extension
+        let mime_type = match bytes.as_slice() {
+            [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+            [0x89, b'P', b'N', b'G', ..] => "image/png",
+            [b'G', b'I', b'F', b'8', ..] => "image/gif",
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+            _ => return None,
+        };
+        Some(format!(
+            "data:{};base64,{}",
+            mime_type,
+            base64::engine::general_purpose::STANDARD.encode(&bytes)
+        ))
+    }
+
+    pub fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;")
+    }
+
+    pub fn write_report(
+        output_writer: &mut dyn Write,
+        report_title: &str,
+        generated_at: &str,
+        entries: &[ReportEntry],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // "</script>" inside a title or note would end the embedding script early
+        let entries_json = serde_json::to_string(entries)?.replace("</", "<\\/");
+        let html = K_REPORT_TEMPLATE
+            .replace("{{TITLE}}", &escape_html(report_title))
+            .replace("{{GENERATED_AT}}", &escape_html(generated_at))
+            .replace("{{ENTRIES_JSON}}", &entries_json);
+        output_writer.write_all(html.as_bytes())?;
+        Ok(())
+    }
+
+    const K_REPORT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{TITLE}}</title>
+<style>
+  body { font-family: system-ui, sans-serif; margin: 0.5em; }
+  header { display: flex; flex-wrap: wrap; gap: 0.5em; align-items: baseline; }
+  #search { flex: 1; min-width: 12em; font-size: 1em; padding: 0.3em; }
+  table { border-collapse: collapse; width: 100%; margin-top: 0.5em; }
+  th, td { text-align: left; padding: 0.25em 0.4em; border-bottom: 1px solid #ddd; vertical-align: top; }
+  th { cursor: pointer; user-select: none; position: sticky; top: 0; background: #f4f4f4; }
+  th.asc::after { content: " \25B2"; }
+  th.desc::after { content: " \25BC"; }
+  img.cover { width: 48px; }
+  img.favicon { width: 16px; height: 16px; vertical-align: middle; margin-right: 0.3em; }
+  .romanized, .notes, small { color: #666; }
+  .tag { display: inline-block; background: #eef; border-radius: 3px; padding: 0 0.3em; margin: 1px; font-size: 0.85em; }
+  .unread { font-weight: bold; color: #b00; }
+  @media (max-width: 40em) { .wide { display: none; } }
+</style>
+</head>
+<body>
+<header>
+  <h1>{{TITLE}}</h1>
+  <input id="search" type="search" placeholder="search title, tag, notes, site" autofocus>
+  <small><span id="count"></span> &middot; generated {{GENERATED_AT}}</small>
+</header>
+<table>
+  <thead><tr>
+    <th class="wide"></th>
+    <th data-key="title">title</th>
+    <th data-key="chapter">chapter</th>
+    <th data-key="unread">unread</th>
+    <th data-key="last_read_millis" class="wide">last read</th>
+    <th data-key="tags" class="wide">tags</th>
+  </tr></thead>
+  <tbody id="rows"></tbody>
+</table>
+<script type="application/json" id="entries">{{ENTRIES_JSON}}</script>
+<script>
+  const entries = JSON.parse(document.getElementById("entries").textContent);
+  const rows = document.getElementById("rows");
+  const search = document.getElementById("search");
+  let sortKey = "title", ascending = true;
+
+  function element(tag, attributes, ...children) {
+    const e = document.createElement(tag);
+    Object.entries(attributes || {}).forEach(([name, value]) => e.setAttribute(name, value));
+    children.forEach(child => e.append(child));
+    return e;
+  }
+
+  function sortValue(entry, key) {
+    const value = entry[key];
+    if (key === "unread") return value === null ? -1 : value;
+    if (key === "chapter") return parseFloat(value) || 0;
+    if (Array.isArray(value)) return value.join(", ").toLowerCase();
+    return typeof value === "string" ? value.toLowerCase() : value;
+  }
+
+  function render() {
+    const terms = search.value.toLowerCase().split(/\s+/).filter(term => term);
+    const shown = entries
+      .filter(entry => {
+        const text = [entry.title, entry.title_romanized, entry.domain, entry.notes, ...entry.tags]
+          .join(" ").toLowerCase();
+        return terms.every(term => text.includes(term));
+      })
+      .sort((a, b) => {
+        const x = sortValue(a, sortKey), y = sortValue(b, sortKey);
+        return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+      });
+    rows.replaceChildren(...shown.map(entry => {
+      const favicon = element("img", { class: "favicon", src: "https://" + entry.domain + "/favicon.ico", alt: "" });
+      favicon.onerror = () => favicon.remove();
+      const title = element("td", {}, favicon, element("a", { href: entry.link }, entry.title));
+      if (entry.title_romanized && entry.title_romanized !== entry.title)
+        title.append(element("br"), element("span", { class: "romanized" }, entry.title_romanized));
+      if (entry.notes) title.append(element("br"), element("span", { class: "notes" }, entry.notes));
+      const unread = entry.unread ? element("span", { class: "unread" }, "+" + entry.unread) : "";
+      return element("tr", {},
+        element("td", { class: "wide" }, entry.cover ? element("img", { class: "cover", src: entry.cover, alt: "" }) : ""),
+        title,
+        element("td", {}, entry.chapter, entry.latest_chapter ? element("small", {}, " / " + entry.latest_chapter) : ""),
+        element("td", {}, unread),
+        element("td", { class: "wide" }, entry.last_read),
+        element("td", { class: "wide" }, ...entry.tags.map(tag => element("span", { class: "tag" }, tag))));
+    }));
+    document.getElementById("count").textContent = shown.length + " of " + entries.length;
+    document.querySelectorAll("th[data-key]").forEach(th => {
+      th.classList.toggle("asc", th.dataset.key === sortKey && ascending);
+      th.classList.toggle("desc", th.dataset.key === sortKey && !ascending);
+    });
+  }
+
+  document.querySelectorAll("th[data-key]").forEach(th => th.addEventListener("click", () => {
+    ascending = th.dataset.key === sortKey ? !ascending : true;
+    sortKey = th.dataset.key;
+    render();
+  }));
+  search.addEventListener("input", render);
+  render();
+</script>
+</body>
+</html>
+"#;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_write_report() {
+            let mut manga = MangaModel::new_from_required_elements(
+                "Gate </script><b>",
+                "https://www.example.com/manga/gate/chapter-58",
+                7,
+            )
+            .unwrap();
+            manga.set_tags(vec!["seinen".to_string(), " isekai".to_string()]);
+            let entry = to_report_entry(&manga, None);
+            assert_eq!(entry.domain, "www.example.com");
+            assert_eq!(entry.tags, vec!["seinen", "isekai"]);
+
+            let mut html = Vec::new();
+            write_report(&mut html, "My <manga>", "2023-09-06 22:57", &[entry]).unwrap();
+            let html = String::from_utf8(html).unwrap();
+            assert!(html.contains("<title>My &lt;manga&gt;</title>"));
+            assert!(html.contains(r#""title":"Gate <\/script><b>""#));
+            assert_eq!(html.matches("</script>").count(), 2);
+        }
+
+        #[test]
+        fn test_cover_data_url() {
+            let cover_path = Path::new("samples/test_html_report_cover.img");
+            std::fs::write(cover_path, [0x89, b'P', b'N', b'G', 0x0D, 0x0A]).unwrap();
+            assert_eq!(
+                cover_data_url(cover_path),
+                Some("data:image/png;base64,iVBORw0K".to_string())
+            );
+            std::fs::write(cover_path, b"<html>not found</html>").unwrap();
+            assert_eq!(cover_data_url(cover_path), None);
+            std::fs::remove_file(cover_path).unwrap_or_default();
+        }
+    }
+}
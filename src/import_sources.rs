@@ -0,0 +1,379 @@
+// Named sources of bookmarks, so that the same imports can be repeated with one command (`manga import
+// firefox_main phone_csv`) rather than retyping paths each time.  Each is a table of the config:
+//      [sources.firefox_main]
+//      format = "firefox"          # "Backup..." JSON, or a bookmarkbackups/*.jsonlz4
+//      path = "/home/me/bookmarks.json"
+//
+//      [sources.laptop]
+//      format = "places"           # places.sqlite of a profile (the file or the profile directory)
+//      path = "/home/me/.mozilla/firefox/abcd1234.default-release"
+//
+//      [sources.work]
+//      format = "chrome"           # `Bookmarks` of a Chrome/Chromium profile
+//      path = "/home/me/.config/chromium/Default/Bookmarks"
+//
+//      [sources.old_laptop]
+//      format = "html"             # Netscape bookmarks.html, as any browser exports them
+//      path = "bookmarks.html"
+//
+//      [sources.phone_csv]
+//      format = "csv"              # same CSV as `-c`
+//      path = "phone.csv"
+//      tag = "phone"               # default "source:<name>", i.e. "source:phone_csv"
+// Sources are imported one after the other into the same database; bookmarks already in it (same title and url)
+// are left as they are (or, for the same url under another title, as on_title_collision says, see
+// model_sqlite3_manga::upsert_manga_with()), but every bookmark of a source gets its tag, so that one can tell where each came from.
+// Browser bookmarks also get a bookmark_origin row (folder and date added), with the name of the source as profile.
+// Bookmarks are written in batches, each checkpointed in the import_log table, so that `manga import <name>
+// --resume` carries on after the last batch of an interrupted import rather than writing everything again.
+pub mod import_sources {
+    use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+    use crate::config::config::{SourceConfig, SourceFormat};
+    use crate::firefox_profiles::firefox_profiles;
+    use crate::model_csv_manga::model_csv_manga;
+    use crate::model_json_chrome_bookmarks::model_json_chrome_bookmarks::ChromeBookmarks;
+    use crate::model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::BookmarkRootFolder;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_netscape_bookmarks::model_netscape_bookmarks;
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_import_log::model_sqlite3_import_log;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::normalize_pipeline::normalize_pipeline::{Pipeline, Rejection, Stage};
+    use crate::service::service::{self, BookmarkOrigin};
+    use crate::title_repair::title_repair;
+
+    pub const K_SOURCE_TAG_PREFIX: &str = "source:";
+    const K_IMPORT_BATCH_SIZE: usize = 500; // bookmarks written per transaction, i.e. per checkpoint
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct SourceImportSummary {
+        pub read: usize,
+        pub added: usize,
+        pub renamed: usize, // rows of the same url which took the title of the bookmark
+        pub rejected: Vec<Rejection>, // bookmarks the pipeline refused, i.e. of another scheme than http(s)
+        pub skipped: BTreeMap<String, usize>, // bookmarks of the browser's own schemes, by scheme
+        pub failures: Vec<String>,    // why the bookmarks of the source could not be written
+        pub resumed_at: Option<usize>, // bookmarks already written by the interrupted import, with resume
+    }
+
+    pub fn source_tag(name: &str, source: &SourceConfig) -> String {
+        source
+            .tag
+            .clone()
+            .unwrap_or_else(|| format!("{}{}", K_SOURCE_TAG_PREFIX, name))
+    }
+
+    // origins of the bookmarks are empty for CSV and bookmarks.html, which have no GUIDs; CSV rows were normalized
+    // when they were written, only the title-clean stage of the pipeline applies to them
+    pub fn read_source(
+        name: &str,
+        source: &SourceConfig,
+        pipeline: &Pipeline,
+    ) -> Result<(Vec<MangaModel>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+        let path = Path::new(&source.path);
+        match source.format {
+            SourceFormat::Firefox => {
+                let json = firefox_profiles::read_bookmark_backup(path)
+                    .map_err(|e| format!("cannot read '{}': {}", source.path, e))?;
+                let bookmark_folders: BookmarkRootFolder = serde_json::from_slice(&json)
+                    .map_err(|e| format!("invalid bookmarks JSON '{}': {}", source.path, e))?;
+                let mangas = service::bookmarks_to_manga(&bookmark_folders, pipeline)?;
+                Ok((mangas, service::bookmark_origins(&bookmark_folders, name)))
+            }
+            SourceFormat::Chrome => {
+                let json = std::fs::read(path)
+                    .map_err(|e| format!("cannot read '{}': {}", source.path, e))?;
+                let chrome_bookmarks: ChromeBookmarks = serde_json::from_slice(&json)
+                    .map_err(|e| format!("invalid Chrome bookmarks '{}': {}", source.path, e))?;
+                let mangas = service::chrome_bookmarks_to_manga(&chrome_bookmarks, pipeline)?;
+                Ok((
+                    mangas,
+                    service::chrome_bookmark_origins(&chrome_bookmarks, name),
+                ))
+            }
+            SourceFormat::Html => {
+                let html = std::fs::read_to_string(path)
+                    .map_err(|e| format!("cannot read '{}': {}", source.path, e))?;
+                let bookmarks = model_netscape_bookmarks::read_bookmarks_html(&html);
+                Ok((
+                    service::netscape_bookmarks_to_manga(&bookmarks, pipeline)?,
+                    Vec::new(),
+                ))
+            }
+            SourceFormat::Places => firefox_profiles::read_places_file(
+                &firefox_profiles::places_path_of(path),
+                name,
+                pipeline,
+            ),
+            SourceFormat::Csv => {
+                let csv_file = File::open(path)
+                    .map_err(|e| format!("cannot read '{}': {}", source.path, e))?;
+                let mut csv_util =
+                    model_csv_manga::Utils::new(Some(Box::new(io::sink())), Box::new(csv_file));
+                let mut mangas = Vec::new();
+                while let Some(result) = csv_util.next() {
+                    match result {
+                        Ok(manga) => mangas.push(manga),
+                        Err(e) => eprintln!("import: '{}': {}", source.path, e),
+                    }
+                }
+                if pipeline.stages.contains(&Stage::TitleClean) {
+                    title_repair::strip_imported_titles(
+                        &mut mangas,
+                        &pipeline.title_rules,
+                        &pipeline.romanization,
+                    );
+                }
+                Ok((mangas, Vec::new()))
+            }
+        }
+    }
+
+    // where the unfinished import of the same source stopped, None if there is none; the bookmarks read have to be
+    // the same as then (as many, and the last one written of the same GUID), else resuming would skip others
+    fn resume_position(
+        db_full_paths: &str,
+        name: &str,
+        source: &SourceConfig,
+        mangas: &[MangaModel],
+    ) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        let import_log = match model_sqlite3_import_log::select_import_log(db_full_paths, name)? {
+            Some(import_log) if !import_log.is_finished() => import_log,
+            _ => return Ok(None),
+        };
+        let is_same_source = import_log.path == source.path
+            && import_log.total == mangas.len()
+            && import_log.position <= mangas.len()
+            && match import_log.position {
+                0 => true,
+                position => mangas[position - 1].bookmark_guid() == import_log.last_guid,
+            };
+        match is_same_source {
+            true => Ok(Some(import_log.position)),
+            false => Err(format!(
+                "'{}' is not what the interrupted import read ({} of {} written), import it again without --resume",
+                source.path, import_log.position, import_log.total
+            )
+            .into()),
+        }
+    }
+
+    // bookmarks not yet in the database are added (as normalized by the pipeline); all of them are tagged with the
+    // tag of the source.  With resume, the bookmarks an interrupted import of the source already wrote are skipped
+    pub fn import_source(
+        db_full_paths: &str,
+        name: &str,
+        source: &SourceConfig,
+        pipeline: &Pipeline,
+        resume: bool,
+        on_title_collision: &mut dyn FnMut(i64, &str, &MangaModel) -> bool,
+    ) -> Result<SourceImportSummary, Box<dyn std::error::Error>> {
+        let (mangas, origins) = read_source(name, source, pipeline)?;
+        let tag = source_tag(name, source);
+        let mut summary = SourceImportSummary {
+            read: mangas.len(),
+            rejected: pipeline.take_rejections(),
+            skipped: pipeline.take_skipped(),
+            resumed_at: match resume {
+                true => resume_position(db_full_paths, name, source, &mangas)?,
+                false => None,
+            },
+            ..SourceImportSummary::default()
+        };
+        let tagged: Vec<MangaModel> = mangas
+            .into_iter()
+            .map(|mut manga| {
+                let mut tags: Vec<String> =
+                    manga.tags().iter().map(|tag| tag.to_string()).collect();
+                tags.push(tag.clone());
+                manga.set_tags(tags);
+                manga
+            })
+            .collect();
+        let mut conn = model_sqlite3_connection::open(db_full_paths)?;
+        let mut position = summary.resumed_at.unwrap_or(0);
+        if summary.resumed_at.is_none() {
+            model_sqlite3_import_log::start_import(&conn, name, &source.path, tagged.len())?;
+        }
+        for batch in tagged[position..].chunks(K_IMPORT_BATCH_SIZE) {
+            match model_sqlite3_manga::bulk_insert_manga_with(&mut conn, batch, on_title_collision)
+            {
+                Ok(written) => {
+                    summary.added += written.inserted;
+                    summary.renamed += written.renamed;
+                    if let Err(e) = model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+                        &mut conn,
+                        &origins,
+                        &written.mangas,
+                    ) {
+                        summary
+                            .failures
+                            .push(format!("bookmark origins not written: {}", e))
+                    }
+                }
+                // single transaction per batch, so none of the rest was written; a later --resume retries it
+                Err(e) => {
+                    summary.failures.push(format!(
+                        "{} bookmarks not written: {}",
+                        tagged.len() - position,
+                        e
+                    ));
+                    return Ok(summary);
+                }
+            }
+            position += batch.len();
+            let possible_last_guid = batch.last().and_then(|manga| manga.bookmark_guid());
+            model_sqlite3_import_log::checkpoint(
+                &conn,
+                name,
+                position,
+                possible_last_guid.as_deref(),
+            )?;
+        }
+        model_sqlite3_import_log::finish_import(&conn, name)?;
+        Ok(summary)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_import_source() {
+            let db_full_paths = "samples/test_import_source.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let source = SourceConfig {
+                format: SourceFormat::Firefox,
+                path: "samples/input.json".to_string(),
+                tag: None,
+            };
+            assert_eq!(source_tag("firefox_main", &source), "source:firefox_main");
+
+            let summary = import_source(
+                db_full_paths,
+                "firefox_main",
+                &source,
+                &Pipeline::default(),
+                false,
+                &mut |_, _, _| false,
+            )
+            .unwrap();
+            assert!(summary.read > 0);
+            assert!(summary.failures.is_empty());
+            // the same bookmark twice in the file is added once
+            let mangas = model_sqlite3_manga::select_all_manga(db_full_paths).unwrap();
+            assert_eq!(summary.added, mangas.len());
+            // again, under another name: nothing added, but everything tagged with both
+            let summary = import_source(
+                db_full_paths,
+                "again",
+                &source,
+                &Pipeline::default(),
+                false,
+                &mut |_, _, _| false,
+            )
+            .unwrap();
+            assert_eq!(summary.added, 0);
+            let mangas = model_sqlite3_manga::select_all_manga(db_full_paths).unwrap();
+            assert!(mangas.iter().all(|manga| {
+                let tags: Vec<&str> = manga.tags().iter().map(|tag| tag.trim()).collect();
+                tags.contains(&"source:firefox_main") && tags.contains(&"source:again")
+            }));
+
+            let missing = SourceConfig {
+                format: SourceFormat::Csv,
+                path: "samples/no_such_file.csv".to_string(),
+                tag: Some("phone".to_string()),
+            };
+            assert!(import_source(
+                db_full_paths,
+                "phone_csv",
+                &missing,
+                &Pipeline::default(),
+                false,
+                &mut |_, _, _| false
+            )
+            .is_err());
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_import_source_resume() {
+            let db_full_paths = "samples/test_import_source_resume.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let source = SourceConfig {
+                format: SourceFormat::Firefox,
+                path: "samples/input.json".to_string(),
+                tag: None,
+            };
+            let (mangas, _) = read_source("firefox_main", &source, &Pipeline::default()).unwrap();
+            assert!(mangas.len() > 2);
+            let import = |resume: bool| {
+                import_source(
+                    db_full_paths,
+                    "firefox_main",
+                    &source,
+                    &Pipeline::default(),
+                    resume,
+                    &mut |_, _, _| false,
+                )
+            };
+
+            // nothing to resume is the same as a whole import
+            let summary = import(true).unwrap();
+            assert_eq!(summary.resumed_at, None);
+            let import_log =
+                model_sqlite3_import_log::select_import_log(db_full_paths, "firefox_main")
+                    .unwrap()
+                    .unwrap();
+            assert!(import_log.is_finished());
+            assert_eq!(import_log.position, mangas.len());
+
+            // an import interrupted after the first 2 bookmarks carries on from the third
+            let conn = model_sqlite3_connection::open(db_full_paths).unwrap();
+            model_sqlite3_import_log::start_import(
+                &conn,
+                "firefox_main",
+                &source.path,
+                mangas.len(),
+            )
+            .unwrap();
+            model_sqlite3_import_log::checkpoint(
+                &conn,
+                "firefox_main",
+                2,
+                mangas[1].bookmark_guid().as_deref(),
+            )
+            .unwrap();
+            let summary = import(true).unwrap();
+            assert_eq!(summary.resumed_at, Some(2));
+            assert_eq!(summary.read, mangas.len());
+            assert!(
+                model_sqlite3_import_log::select_import_log(db_full_paths, "firefox_main")
+                    .unwrap()
+                    .unwrap()
+                    .is_finished()
+            );
+
+            // not the same bookmarks as when it was interrupted
+            model_sqlite3_import_log::start_import(
+                &conn,
+                "firefox_main",
+                &source.path,
+                mangas.len(),
+            )
+            .unwrap();
+            model_sqlite3_import_log::checkpoint(&conn, "firefox_main", 2, Some("no-such-guid"))
+                .unwrap();
+            assert!(import(true).is_err());
+            assert_eq!(import(false).unwrap().resumed_at, None);
+
+            drop(conn);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
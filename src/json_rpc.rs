@@ -0,0 +1,254 @@
+// JSON-RPC 2.0 over stdin/stdout for editors, launchers (Raycast, Alfred) and other local tools:
+//      manga --rpc [-d <db.sqlite3>]
+// One request per line in, one response per line out (notifications, i.e. without "id", get no response);
+// the process keeps running between queries (no start-up per query) and exits at end of input.
+//      {"jsonrpc":"2.0","id":1,"method":"list","params":{"unread_only":true,"tag":"isekai"}}
+//      {"jsonrpc":"2.0","id":2,"method":"search","params":{"title":"gate"}}               (MangaSearchRequest)
+//      {"jsonrpc":"2.0","id":3,"method":"upsert","params":{"title":"..","url":".."}}     (MangaCreateRequest)
+//      {"jsonrpc":"2.0","id":4,"method":"mark_read","params":{"id":7,"chapter":"58"}}   (no chapter = latest)
+// list/search return the manga the same way as `manga serve` does (MangaModel), upsert returns {"id":N} and
+// mark_read returns {"id":N,"chapter":".."}.  Errors use the JSON-RPC codes, -32000 for database errors.
+// Anything the database layer prints is sent to stderr, so stdout only ever carries responses; that takes moving
+// fd 1, hence unix only (elsewhere `--rpc` refuses to start).
+pub mod json_rpc {
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::io::{self, BufRead, Write};
+
+    use crate::graphql_api::graphql_api::MangaFilter;
+    use crate::model_manga::model_manga::{MangaCreateRequest, MangaModel, MangaSearchRequest};
+    use crate::service::service::{MangaService, ServiceError};
+
+    const K_PARSE_ERROR: i64 = -32700;
+    const K_INVALID_REQUEST: i64 = -32600;
+    const K_METHOD_NOT_FOUND: i64 = -32601;
+    const K_INVALID_PARAMS: i64 = -32602;
+    const K_SERVER_ERROR: i64 = -32000;
+    const K_DATABASE_BUSY: i64 = -32001; // implementation-defined server error, the request may be retried
+
+    #[derive(Debug, Deserialize)]
+    struct RpcRequest {
+        jsonrpc: Option<String>,
+        id: Option<Value>,
+        method: String,
+        #[serde(default)]
+        params: Value,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    struct ListParams {
+        title: Option<String>,
+        tag: Option<String>,
+        domain: Option<String>,
+        unread_only: Option<bool>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct MarkReadParams {
+        id: i64,
+        chapter: Option<String>,
+    }
+
+    struct RpcError {
+        code: i64,
+        message: String,
+    }
+
+    impl RpcError {
+        fn new(code: i64, message: String) -> RpcError {
+            RpcError { code, message }
+        }
+    }
+
+    impl From<ServiceError> for RpcError {
+        fn from(e: ServiceError) -> Self {
+            let code = match e {
+                ServiceError::NotFound(_)
+                | ServiceError::AlreadyExists(_)
+                | ServiceError::InvalidInput(_) => K_INVALID_PARAMS,
+                ServiceError::DatabaseBusy(_) => K_DATABASE_BUSY,
+                ServiceError::Database(_) | ServiceError::Io(_) => K_SERVER_ERROR,
+            };
+            RpcError::new(code, e.to_string())
+        }
+    }
+
+    // params are optional for list, so null is the same as {}
+    fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+        let params = match params {
+            Value::Null => json!({}),
+            params => params,
+        };
+        serde_json::from_value(params).map_err(|e| RpcError::new(K_INVALID_PARAMS, e.to_string()))
+    }
+
+    fn call(service: &MangaService, method: &str, raw_params: Value) -> Result<Value, RpcError> {
+        match method {
+            "list" => {
+                let list_params: ListParams = params(raw_params)?;
+                let filter = MangaFilter {
+                    title: list_params.title,
+                    tag: list_params.tag,
+                    domain: list_params.domain,
+                    unread_only: list_params.unread_only,
+                };
+                let mangas: Vec<MangaModel> = service
+                    .list()?
+                    .into_iter()
+                    .filter(|manga| filter.matches(manga))
+                    .collect();
+                Ok(json!(mangas))
+            }
+            "search" => {
+                let request: MangaSearchRequest = params(raw_params)?;
+                Ok(json!(service.search(&request)?))
+            }
+            "upsert" => {
+                let request: MangaCreateRequest = params(raw_params)?;
+                let upserted = service.upsert(request)?;
+                Ok(json!({ "id": upserted.id() }))
+            }
+            "mark_read" => {
+                let mark_read_params: MarkReadParams = params(raw_params)?;
+                let manga_read =
+                    service.mark_read(mark_read_params.id, mark_read_params.chapter.as_deref())?;
+                Ok(json!({ "id": manga_read.id(), "chapter": manga_read.chapter() }))
+            }
+            _ => Err(RpcError::new(
+                K_METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn error_response(id: Value, error: RpcError) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        })
+    }
+
+    // None for notifications (requests without an id), which get no response
+    pub fn handle_line(service: &MangaService, line: &str) -> Option<String> {
+        let request: RpcRequest = match serde_json::from_str::<Value>(line) {
+            Ok(value) => match serde_json::from_value(value) {
+                Ok(request) => request,
+                Err(e) => {
+                    let error = RpcError::new(K_INVALID_REQUEST, e.to_string());
+                    return Some(error_response(Value::Null, error).to_string());
+                }
+            },
+            Err(e) => {
+                let error = RpcError::new(K_PARSE_ERROR, e.to_string());
+                return Some(error_response(Value::Null, error).to_string());
+            }
+        };
+        let id = request.id?;
+        if request.jsonrpc.as_deref() != Some("2.0") {
+            let error = RpcError::new(K_INVALID_REQUEST, "jsonrpc must be \"2.0\"".to_string());
+            return Some(error_response(id, error).to_string());
+        }
+        let response = match call(service, &request.method, request.params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        };
+        Some(response.to_string())
+    }
+
+    // the database layer reports progress with println!, which would corrupt the responses, so from here on
+    // stdout is pointed at stderr and the responses go to (a duplicate of) the original stdout
+    #[cfg(unix)]
+    pub fn protocol_writer() -> io::Result<Box<dyn Write>> {
+        use std::os::fd::AsFd;
+        io::stdout().flush()?;
+        let original_stdout = io::stdout().as_fd().try_clone_to_owned()?;
+        // SAFETY: both are open descriptors of this process; dup2 only replaces what fd 1 refers to
+        if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Box::new(std::fs::File::from(original_stdout)))
+    }
+
+    // std writes stdout through the console handle rather than fd 1 here, so there is no telling it apart from
+    // the progress messages; better to refuse than to hand out a corrupted stream
+    #[cfg(not(unix))]
+    pub fn protocol_writer() -> io::Result<Box<dyn Write>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "writing a protocol to stdout is only supported on unix, where progress messages go to stderr",
+        ))
+    }
+
+    // blocks until stdin is closed
+    pub fn run(db_full_paths: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = protocol_writer()?;
+        let service = MangaService::open(db_full_paths)?;
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = handle_line(&service, &line) {
+                writeln!(writer, "{}", response)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn response(service: &MangaService, line: &str) -> Value {
+            serde_json::from_str(&handle_line(service, line).unwrap()).unwrap()
+        }
+
+        #[test]
+        fn test_handle_line() {
+            let db_full_paths = "samples/test_json_rpc.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+
+            let upserted = response(
+                &service,
+                r#"{"jsonrpc":"2.0","id":1,"method":"upsert","params":{"title":"Gate","url":"https://example.com/manga/gate/","tags":"seinen, isekai"}}"#,
+            );
+            let id = upserted["result"]["id"].as_u64().unwrap();
+            let listed = response(
+                &service,
+                r#"{"jsonrpc":"2.0","id":"a","method":"list","params":{"tag":"isekai"}}"#,
+            );
+            assert_eq!(listed["id"], "a");
+            assert_eq!(listed["result"][0]["title"], "Gate");
+
+            let marked = response(
+                &service,
+                &format!(
+                    r#"{{"jsonrpc":"2.0","id":2,"method":"mark_read","params":{{"id":{},"chapter":"12-1"}}}}"#,
+                    id
+                ),
+            );
+            assert_eq!(marked["result"]["chapter"], "12.1");
+            let searched = response(
+                &service,
+                r#"{"jsonrpc":"2.0","id":3,"method":"search","params":{"chapter":"12.1"}}"#,
+            );
+            assert_eq!(searched["result"].as_array().unwrap().len(), 1);
+
+            let unknown = response(&service, r#"{"jsonrpc":"2.0","id":4,"method":"nope"}"#);
+            assert_eq!(unknown["error"]["code"], K_METHOD_NOT_FOUND);
+            let missing_id = response(
+                &service,
+                r#"{"jsonrpc":"2.0","id":5,"method":"mark_read","params":{}}"#,
+            );
+            assert_eq!(missing_id["error"]["code"], K_INVALID_PARAMS);
+            assert_eq!(response(&service, "{")["error"]["code"], K_PARSE_ERROR);
+            assert_eq!(
+                handle_line(&service, r#"{"jsonrpc":"2.0","method":"list"}"#),
+                None
+            );
+        }
+    }
+}
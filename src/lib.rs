@@ -1,11 +1,100 @@
 //mod firefox_bookmarks_to_csv;
+mod anilist;
+mod config;
+mod cover_cache;
+mod cron_schedule;
+mod delta_export;
+mod enrichment;
+mod error; // the Error of the crate, see prelude
+mod history_export;
+mod html_report;
+mod manga_diff;
+mod mangadex;
+mod model_atom_manga;
 mod model_csv_manga;
+mod model_json_chrome_bookmarks;
 mod model_json_mozilla_bookmarks;
 mod model_manga;
+mod model_netscape_bookmarks;
+mod model_sqlite3_bookmark_origin;
+mod model_sqlite3_change_log;
+mod model_sqlite3_connection;
+mod model_sqlite3_import_log;
+mod model_sqlite3_link_checks;
 mod model_sqlite3_manga;
+mod model_sqlite3_manga_archive;
+mod model_sqlite3_manga_fts;
+mod model_sqlite3_manga_metadata;
+mod model_sqlite3_reading_sessions;
+mod model_sqlite3_sync_state;
+mod model_sqlite3_url_history;
+mod model_yaml_manga;
+mod myanimelist;
+mod normalize_pipeline;
+mod notifier;
+mod obsidian_export;
+mod ranked_search;
+pub mod service; // typed operations for front-ends (desktop apps) linking against this crate
+mod site_migration;
+mod stats;
+mod tag_expression;
 mod text_type; // used by model_manga to make it flexible for different text types
+mod title_repair;
+mod update_checker;
+
+// What other Rust programs embedding the import (rather than running the binary) are meant to use; names here
+// stay as they are across versions even when the modules behind them move around:
+//      use firefox_bookmark_to_csv::prelude::*;
+//      let service = MangaService::open("漫画.sqlite3")?;
+//      let summary = service.import_bookmarks(std::fs::File::open("bookmarks.json")?)?;
+pub mod prelude {
+    pub use crate::error::error::Error;
+    pub use crate::model_csv_manga::model_csv_manga::{CsvMangaModel, Utils as CsvUtils};
+    pub use crate::model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::{
+        BookmarkNodes, BookmarkRootFolder, Type as BookmarkType,
+    };
+    pub use crate::model_manga::model_manga::MangaModel;
+    pub use crate::model_sqlite3_connection::model_sqlite3_connection::open as open_connection;
+    pub use crate::model_sqlite3_manga::model_sqlite3_manga as sqlite_store; // the manga table, i.e. sqlite_store::select_all_manga()
+    pub use crate::normalize_pipeline::normalize_pipeline::Pipeline;
+    pub use crate::service::service::{
+        bookmark_origins, bookmarks_to_manga, ImportSummary, MangaService, ServiceError,
+    };
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_prelude_import() {
+            let db_full_paths = "samples/test_prelude_import.sqlite3";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            let summary = service
+                .import_bookmarks(std::fs::File::open("samples/bookmarks.json").unwrap())
+                .unwrap();
+            let mangas: Vec<MangaModel> = sqlite_store::select_all_manga(db_full_paths).unwrap();
+            assert!(summary.failures.is_empty());
+            // bookmarks of the same title and url are one row
+            assert!(!mangas.is_empty() && mangas.len() <= summary.bookmarks_count);
+
+            // same bookmarks without a database
+            let bookmark_folders: BookmarkRootFolder =
+                serde_json::from_reader(std::fs::File::open("samples/bookmarks.json").unwrap())
+                    .unwrap();
+            let pipeline = Pipeline::new(None, &[], Vec::new());
+            let from_json = bookmarks_to_manga(&bookmark_folders, &pipeline).unwrap();
+            assert_eq!(from_json.len(), summary.bookmarks_count);
+            let csv_manga = CsvMangaModel::new(&from_json[0]);
+            assert_eq!(csv_manga.title(), from_json[0].title());
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
 
 pub mod my_libs {
+    use std::collections::BTreeMap;
+
     pub trait Flattener<T: Clone> {
         fn flatten(&self) -> Vec<T>;
     }
@@ -48,14 +137,47 @@ pub mod my_libs {
     // * `'` (single quote) - SQLite will treat it as a string delimiter, so strings such as "Notes: I don't know" will be treated as "Notes: I "
     // * ``` (ticks) - BASH scripts hates this, so we need to replace it with something else
     // | sed 's/,/、/g' | sed 's/"/’/g' | sed "s/'/’/g"
+    pub const K_DEFAULT_SANITIZE_SUBSTITUTIONS: [(&str, &str); 4] =
+        [(",", "、"), ("'", "’"), ("\"", "’"), ("`", "’")];
+
+    pub fn default_sanitize_substitutions() -> BTreeMap<String, String> {
+        K_DEFAULT_SANITIZE_SUBSTITUTIONS
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
     pub fn sanitize_string<T: AsRef<str>>(s: T) -> String {
+        sanitize_string_with(s, &default_sanitize_substitutions())
+    }
+
+    // same as sanitize_string() but with substitutions of its own (i.e. [normalize] sanitize of the config), an
+    // empty map only trims the quotes; substitutions are made in a single pass, so that what one of them puts in
+    // is never substituted again by another
+    pub fn sanitize_string_with<T: AsRef<str>>(
+        s: T,
+        substitutions: &BTreeMap<String, String>,
+    ) -> String {
         // first, trim the edges of the quotes, if any, BECAUSE we want to only sanitize the string INSIDE the quotes
         let s = trim_quotes(s);
-        let s = s.replace(",", "、");
-        let s = s.replace("'", "’");
-        let s = s.replace("\"", "’");
-        let s = s.replace("`", "’");
-        s
+        let mut sanitized = String::with_capacity(s.len());
+        let mut rest = s.as_str();
+        while let Some(c) = rest.chars().next() {
+            let possible_substitution = substitutions
+                .iter()
+                .find(|(from, _)| !from.is_empty() && rest.starts_with(from.as_str()));
+            match possible_substitution {
+                Some((from, to)) => {
+                    sanitized.push_str(to);
+                    rest = &rest[from.len()..];
+                }
+                None => {
+                    sanitized.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        sanitized
     }
 
     // Allow both String and &str to be passed in with magic of AsRef<T> and s.as_ref() combination
@@ -88,50 +210,56 @@ pub mod my_libs {
     }
 
     pub fn from_epoch_to_str(epoch: i64) -> String {
-        // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds
+        // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds;
+        // one out of chrono's range is the epoch itself rather than a panic
         let from_epoch_timespan = chrono::NaiveDateTime::from_timestamp_opt(
             epoch / 1_000_000,
             (epoch % 1_000_000) as u32,
         )
-        .unwrap();
+        .unwrap_or_default();
         let last_update_yyyymmdd_thhmmss =
             from_epoch_timespan.format("%Y-%m-%dT%H:%M:%S").to_string(); // have to call to_string() to format
         last_update_yyyymmdd_thhmmss // and then convert it back to &str
     }
-    pub fn str_to_epoch_millis(time_yyyymmdd_thhmmss: String) -> i64 {
-        // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds
-        let timespan_yyyymmdd_thhmmss =
-            chrono::NaiveDateTime::parse_from_str(&time_yyyymmdd_thhmmss, "%Y-%m-%dT%H:%M:%S")
-                .unwrap()
-                .timestamp_millis();
-        timespan_yyyymmdd_thhmmss
+    // "YYYY-MM-DDTHH:MM:SS" as the CSV files have it, or "YYYY-MM-DD HH:MM:SS" as insert_manga() stamps a row
+    // that comes without last_update; None for anything else (a hand-edited cell), rather than a panic
+    fn parse_yyyymmdd_thhmmss(time_yyyymmdd_thhmmss: &str) -> Option<chrono::NaiveDateTime> {
+        chrono::NaiveDateTime::parse_from_str(time_yyyymmdd_thhmmss, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| {
+                chrono::NaiveDateTime::parse_from_str(time_yyyymmdd_thhmmss, "%Y-%m-%d %H:%M:%S")
+            })
+            .ok()
     }
-    pub fn str_to_epoch_micros(time_yyyymmdd_thhmmss: String) -> i64 {
-        // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds
-        let timespan_yyyymmdd_thhmmss =
-            chrono::NaiveDateTime::parse_from_str(&time_yyyymmdd_thhmmss, "%Y-%m-%dT%H:%M:%S")
-                .unwrap()
-                .timestamp_micros();
-        timespan_yyyymmdd_thhmmss
+    pub fn str_to_epoch_millis(time_yyyymmdd_thhmmss: String) -> Option<i64> {
+        parse_yyyymmdd_thhmmss(&time_yyyymmdd_thhmmss)
+            .map(|timespan| timespan.and_utc().timestamp_millis())
+    }
+    pub fn str_to_epoch_micros(time_yyyymmdd_thhmmss: String) -> Option<i64> {
+        // last_update is encoded as unix epoch time in microseconds
+        parse_yyyymmdd_thhmmss(&time_yyyymmdd_thhmmss)
+            .map(|timespan| timespan.and_utc().timestamp_micros())
     }
     // format a chrono::DateTime<chrono::Utc> into a String in the format of "YYYY-MM-DDTHH:MM:SS"
     pub fn datetime_to_string(datetime: &chrono::DateTime<chrono::Utc>) -> String {
         datetime.format("%Y-%m-%dT%H:%M:%S").to_string()
     }
-    pub fn string_datetime_to_epoch_millis(datetime: &str) -> i64 {
+    pub fn string_datetime_to_epoch_millis(datetime: &str) -> Option<i64> {
         // Note that rfc3339() is formatted as "YYYY-MM-DDTHH:MM:SS+00:00" (with timezone offset)
         // like so: `1996-12-19T16:39:57-08:00` but our format is "YYYY-MM-DDTHH:MM:SS" (no timezone offset)
         // so we assume it is GMT/UTC timezone and append "+00:00" to it:
         let datetime = format!("{}+00:00", datetime);
-        let datetime_fixed_offset =
-            chrono::DateTime::parse_from_rfc3339(datetime.as_str()).unwrap();
-        datetime_fixed_offset.timestamp_millis()
+        let datetime_fixed_offset = chrono::DateTime::parse_from_rfc3339(datetime.as_str()).ok()?;
+        Some(datetime_fixed_offset.timestamp_millis())
     }
 
     #[cfg(test)]
     mod tests {
         #[allow(dead_code, unused_variables)]
-        use crate::my_libs::{make_none_if_empty, trim_quotes, Flattener};
+        use crate::my_libs::{
+            make_none_if_empty, sanitize_string, sanitize_string_with, str_to_epoch_micros,
+            string_datetime_to_epoch_millis, trim_quotes, Flattener,
+        };
+        use std::collections::BTreeMap;
 
         #[test]
         #[allow(dead_code, unused_variables)]
@@ -177,6 +305,36 @@ pub mod my_libs {
             println!("{}", trimmed1); // prints "Hello"
             println!("{}", trimmed2); // prints "World"
         }
+
+        #[test]
+        fn test_sanitize_string_with() {
+            assert_eq!(sanitize_string(" Gate, it's "), "Gate、 it’s");
+            let substitutions = BTreeMap::from([
+                (",".to_string(), ";".to_string()),
+                (";".to_string(), ",".to_string()),
+            ]);
+            assert_eq!(sanitize_string_with("a,b;c'd", &substitutions), "a;b,c'd");
+            assert_eq!(sanitize_string_with(" a, b ", &BTreeMap::new()), "a, b");
+        }
+
+        #[test]
+        fn test_str_to_epoch_micros() {
+            assert_eq!(
+                str_to_epoch_micros("2023-09-01T12:00:00".to_string()),
+                Some(1_693_569_600_000_000)
+            );
+            assert_eq!(
+                str_to_epoch_micros("2023-09-01 12:00:00".to_string()),
+                Some(1_693_569_600_000_000)
+            );
+            assert_eq!(str_to_epoch_micros("last week".to_string()), None);
+            assert_eq!(
+                string_datetime_to_epoch_millis("2023-09-01T12:00:00"),
+                Some(1_693_569_600_000)
+            );
+            assert_eq!(string_datetime_to_epoch_millis("2023-09-01"), None);
+        }
+
         #[allow(dead_code, unused_variables)]
         fn test_make_none() {
             assert_eq!(make_none_if_empty(Some("")), None);
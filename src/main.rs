@@ -1,17 +1,79 @@
+mod anilist;
+mod annotate;
+mod bookmark_sync;
+mod config;
+mod cover_cache;
+mod cron_schedule;
+mod daemon;
+mod daemon_lock;
+mod daemon_metrics;
+mod delta_export;
+mod enrichment;
+mod error;
+mod firefox_profiles;
+mod git_export;
+mod graphql_api;
+#[cfg(feature = "grpc")]
+mod grpc_server;
+mod history_export;
+mod html_report;
+mod import_sources;
+mod json_rpc;
+mod manga_diff;
+mod mangadex;
+mod markdown_table;
+mod model_atom_manga;
 mod model_csv_manga;
+mod model_json_chrome_bookmarks;
 mod model_json_mozilla_bookmarks;
 mod model_manga; // this is the same as `mod model_json; pub use model_json::*;`
+mod model_netscape_bookmarks;
+mod model_opml_manga;
+mod model_sqlite3_bookmark_origin;
+mod model_sqlite3_change_log;
+mod model_sqlite3_connection;
+mod model_sqlite3_import_log;
+mod model_sqlite3_link_checks;
 mod model_sqlite3_manga;
+mod model_sqlite3_manga_archive;
+mod model_sqlite3_manga_fts;
+mod model_sqlite3_manga_metadata;
+mod model_sqlite3_reading_sessions;
+mod model_sqlite3_sync_state;
+mod model_sqlite3_url_history;
+mod model_xlsx_manga;
+mod model_yaml_manga;
+mod myanimelist;
+mod native_host;
+mod normalize_pipeline;
+mod notifier;
+mod obsidian_export;
+mod ranked_search;
+mod remote_sync;
+mod rest_server;
+mod service;
+mod shadow_database;
+mod shell;
+mod site_migration;
+mod snapshots;
+mod source_merge;
+mod stats;
+mod subcommands;
+mod tag_expression;
+mod title_repair;
+mod tui;
+mod update_checker;
 
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, Read, Write};
 
 use firefox_bookmark_to_csv::my_libs;
-use json_to_csv::upsert_db;
 
-use model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::{
-    BookmarkNodes, BookmarkRootFolder,
-};
+use config::config::SourceFormat;
+use model_json_chrome_bookmarks::model_json_chrome_bookmarks::ChromeBookmarks;
+use model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::BookmarkRootFolder;
 use model_manga::model_manga::MangaModel;
+use normalize_pipeline::normalize_pipeline::Pipeline;
+use service::service::BookmarkOrigin;
 
 mod json_to_csv {
     //use serde_json::Value;
@@ -19,59 +81,46 @@ mod json_to_csv {
     use std::{
         env,
         fs::File,
-        io::{self, BufRead, BufReader, BufWriter, Write},
+        io::{self, BufRead, BufReader, BufWriter, Read, Write},
     };
 
+    use crate::error::error::Error;
     #[allow(unused_imports)]
     // following are actually used, but compiler is complaining that it's not used
     use crate::model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::{
         BookmarkNodes, BookmarkRootFolder,
     };
-    use crate::{model_csv_manga, model_manga::model_manga::MangaModel, model_sqlite3_manga};
+    use crate::{
+        model_csv_manga, model_manga::model_manga::MangaModel, model_sqlite3_connection,
+        model_sqlite3_manga, subcommands,
+    };
 
-    pub fn upsert_db(
-        db_full_paths: &str,
-        manga: &MangaModel,
-        continue_on_error: bool,
-        debug_flag: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        //let upsert_result = model_sqlite3_manga::model_sqlite3_manga::upsert_manga(
-        //    db_full_paths,
-        //    &manga, // need to clone so that we do not steal/borrow the ownership of possible_csv_row/result
-        //);
-        let insert_result = model_sqlite3_manga::model_sqlite3_manga::insert_manga(
-            db_full_paths,
-            &manga, // need to clone so that we do not steal/borrow the ownership of possible_csv_row/result
-        );
-        match insert_result {
-            Ok(upsert_row_returned) => {
-                // do nothing (for now) if successfully inserted
-                if debug_flag {
-                    println!("> inserted_row_model SUCCESS: {}", upsert_row_returned)
-                }
+    // `-i -`, `-c -` and `-o -` are stdin/stdout rather than a file of that name
+    const K_STDIO: &str = "-";
+
+    // each `-i` as given, except that a directory stands for the bookmarks JSON files (*.json) in it, by name
+    pub fn bookmark_json_files(input_paths: &[String]) -> Result<Vec<String>, std::io::Error> {
+        let mut files = Vec::new();
+        for input_path in input_paths {
+            if !std::path::Path::new(input_path).is_dir() {
+                files.push(input_path.clone());
+                continue;
             }
-            Err(insert_or_update_error) => {
-                // for now, panic!() if it was an error based on unique constraint (because it's a programmer bug rather than actual error)
-                if insert_or_update_error
-                    .to_string()
-                    .contains("UNIQUE constraint failed")
+            let mut dir_files = Vec::new();
+            for entry in std::fs::read_dir(input_path)? {
+                let path = entry?.path();
+                if path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|extension| extension == "json")
                 {
-                    println!("\n");
-                    panic!( "Programmer (logic) Error - UNIEQUE constraint should have ben handled elsewhere: Attempting to write CSV row\n>\t{:?}:\n>\t{}", manga, insert_or_update_error);
-                }
-                // if the error is based on unique constraint, UPSERT should have taken care of it, so
-                // assume that it's some other error and print it out
-                println!(
-                    "ERROR upsert_db(): Error writing CSV row: {}\n\t{:#?}\n",
-                    insert_or_update_error, manga,
-                );
-
-                if continue_on_error == false {
-                    return Err(Box::new(insert_or_update_error));
+                    dir_files.push(path.to_string_lossy().to_string());
                 }
             }
+            dir_files.sort();
+            files.extend(dir_files);
         }
-        Ok(())
+        Ok(files)
     }
 
     // read existing CSV file and deserialize each row, we'll directly
@@ -95,6 +144,7 @@ mod json_to_csv {
         //       just print out the error and continue on to the next row
         //       but track all the errors and return it at the end
         let mut ret_errors: Vec<Result<(), Box<dyn std::error::Error>>> = Vec::new(); // vec![];
+        let mut csv_rows: Vec<MangaModel> = Vec::new();
         while possible_csv_row.is_some() {
             // write to SQLite
             #[cfg(debug_assertions)]
@@ -112,16 +162,8 @@ mod json_to_csv {
                             {
                                 println!("#\tcsv_row (parsed): {:?}", &csv_row);
                             }
-                            // write to SQLite - the model from DB SHOULD have correct Manga.ID
-                            match upsert_db(db_full_paths, &csv_row, true, debug_flag) {
-                                Ok(_) => {
-                                    update_count += 1;
-                                }
-                                Err(e) => {
-                                    println!("ERROR read_csv_and_update_sqlite(): Error writing CSV row {:?}:\n>\t{}\n", &csv_row, &e);
-                                    ret_errors.push(Err(e));
-                                }
-                            }
+                            // written to SQLite all at once after the last row
+                            csv_rows.push(csv_row);
                         }
                         Err(csv_error) => {
                             // do nothing, let it
@@ -140,9 +182,37 @@ mod json_to_csv {
             possible_csv_row = csv_util.next(); // should return if there is no more row to read (EOF:w
         }
 
+        // single transaction for the whole file rather than opening the database for each row; rows already in
+        // it (same title and url) are updated from the file
+        match model_sqlite3_connection::model_sqlite3_connection::open(db_full_paths).and_then(
+            |mut conn| {
+                model_sqlite3_manga::model_sqlite3_manga::bulk_upsert_manga(&mut conn, &csv_rows)
+            },
+        ) {
+            Ok(summary) => {
+                if debug_flag {
+                    println!(
+                        "> inserted {} new rows, updated {}",
+                        summary.inserted, summary.updated
+                    )
+                }
+                update_count = summary.mangas.len();
+            }
+            Err(e) => {
+                println!(
+                    "ERROR read_csv_and_update_sqlite(): Error writing CSV rows:\n>\t{}\n",
+                    &e
+                );
+                ret_errors.push(Err(Box::new(e)));
+            }
+        }
+
         // print some stats on completions (success of fail) of filename and number of rows (lines) processed
         println!("CSV file: {}", db_full_paths);
         println!("CSV file lines read: {}", line_count);
+        if let Some(schema) = csv_util.schema() {
+            println!("CSV file layout: {:?}", schema);
+        }
         println!("Rows (lines) upserted: {}", update_count);
         match ret_errors.len() {
             0 => {
@@ -177,13 +247,30 @@ mod json_to_csv {
         args: Vec<String>,
     ) -> Result<
         (
-            String,                           // SQLite3 database full path
-            Box<dyn BufRead + 'static>, // -i: either stdin or input file-stream of JSON (bookmak) file (NOTE: special case of using 'static)
+            String,                                    // SQLite3 database full path
+            Vec<(String, Box<dyn BufRead + 'static>)>, // -i: either stdin or input file-stream(s) of JSON (bookmak) file(s), by name (NOTE: special case of using 'static)
             Option<Box<dyn Write + 'static>>, // -o: either stdout or output file-stream of CSV file
-            bool,                       // -D debug flag
+            bool,                             // -D debug flag
         ),
-        Box<dyn std::error::Error>,
+        Error,
     > {
+        // `-o -` writes the CSV to stdout, so everything said along the way (here and by the database layer) has
+        // to go to stderr from the very start, which only unix allows (see json_rpc::protocol_writer())
+        let possible_stdout_csv: Option<Box<dyn Write + 'static>> = match args
+            .windows(2)
+            .any(|pair| pair[0] == "-o" && pair[1] == K_STDIO)
+        {
+            true => match crate::json_rpc::json_rpc::protocol_writer() {
+                Ok(writer) => Some(Box::new(BufWriter::new(writer))),
+                Err(e) => {
+                    return Err(Error::InvalidArgument(format!(
+                        "-o {}: {}, write to a file instead",
+                        K_STDIO, e
+                    )))
+                }
+            },
+            false => None,
+        };
         #[cfg(debug_assertions)]
         {
             println!("args: {:?}", args);
@@ -192,52 +279,178 @@ mod json_to_csv {
         let mut has_output_file = false;
         let mut has_possible_input_csv_file = false;
         let mut has_db_file = false;
-        let mut input_filepaths_bookmark_json = String::new();
+        let mut input_filepaths_bookmark_json: Vec<String> = Vec::new(); // -i can be repeated
         let mut output_filepaths_csv = String::new();
         let mut possible_last_csv: Option<String> = None;
         let mut db_full_paths = String::new();
         let mut debug_flag = false;
-        let mut i = 0;
+        let mut auto_input = false;
+        let mut has_places_input = false; // -p or --profile
+        let mut preview = false;
+        let mut merge = false;
+        let mut sync = false;
+        let mut stream = false;
+        // the flags below which are followed by a value
+        let value_of = |i: usize| -> Result<String, Error> {
+            match args.get(i + 1) {
+                Some(value) => Ok(value.clone()),
+                None => Err(Error::InvalidArgument(format!(
+                    "{} needs a value, see --help",
+                    args[i]
+                ))),
+            }
+        };
+        let mut i = 1; // args[0] is the executable
         while i < args.len() {
-            println!("arg[{}]: {}", i, args[i]);
+            #[cfg(debug_assertions)]
+            {
+                println!("arg[{}]: {}", i, args[i]);
+            }
             if args[i] == "-i" {
                 has_input_file = true;
-                input_filepaths_bookmark_json = args[i + 1].clone();
+                input_filepaths_bookmark_json.push(value_of(i)?);
                 i += 2; // increment by 2 to skip the next argument
             } else if args[i] == "-o" {
                 has_output_file = true;
-                output_filepaths_csv = args[i + 1].clone();
+                output_filepaths_csv = value_of(i)?;
                 i += 2; // increment by 2 to skip the next argument
             } else if args[i] == "-c" {
                 has_possible_input_csv_file = true;
-                possible_last_csv = Some(args[i + 1].clone());
+                possible_last_csv = Some(value_of(i)?);
                 i += 2; // increment by 2 to skip the next argument
             } else if args[i] == "-d" {
                 has_db_file = true;
-                db_full_paths = args[i + 1].clone();
+                db_full_paths = value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--auto-input" {
+                auto_input = true;
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--preview" {
+                preview = true;
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--merge" {
+                // handled by main(), along with the bookmarks
+                merge = true;
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--sync" {
+                // handled by main(), along with the bookmarks
+                sync = true;
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--folder-tags" {
+                // handled by main(), along with the bookmarks
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--include-folder"
+                || args[i] == "--exclude-folder"
+                || args[i] == "--url-filter"
+            {
+                // handled by main(), see service::BookmarkFilter
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--stream" {
+                // handled by main(), instead of reading the bookmarks whole
+                stream = true;
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--sync-report" {
+                // handled by main(), with --sync
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "-p" || args[i] == "--profile" {
+                // handled by main(), instead of -i
+                has_places_input = true;
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--format" {
+                // handled by main(), how the -i files are read
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--config" {
+                // handled by the [git_export] hook
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--stages" || args[i] == "--skip-stages" {
+                // handled by main(), see normalize_pipeline
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--explain" {
+                // handled by main(), see normalize_pipeline
+                i += 1; // increment by 1 to skip the next argument
+            } else if args[i] == "--title-collision" {
+                // handled by main(), see model_sqlite3_manga::upsert_manga_with()
+                value_of(i)?;
+                i += 2; // increment by 2 to skip the next argument
+            } else if args[i] == "--scheme-policy" {
+                // handled by main(), see normalize_pipeline
+                value_of(i)?;
                 i += 2; // increment by 2 to skip the next argument
             } else if args[i] == "-D" {
                 // debug mode
                 debug_flag = true;
                 i += 1; // increment by 1 to skip the next argument
             } else if args[i] == "-h" || args[i] == "--help" {
-                println!("Usage: {} [-i <bookmark.json>] [-o <output.csv>] [-c <last.csv>] [-d <db.sqlite3>] [-D]", args[0]);
-                println!("-i is not specified, then stdin will be used");
+                println!("Usage: {} [-i <bookmark.json|->] [--format firefox|chrome|html] [-p <places.sqlite>] [-o <output.csv|->] [-c <last.csv|->] [-d <db.sqlite3>] [-D]", args[0]);
+                println!("-i is not specified, then stdin will be used; -i can be repeated, and a directory stands for the *.json files in it, each imported in turn into the same database; '-' is stdin");
+                println!("--format <firefox|chrome|html> (optional): what the -i files are, Firefox bookmarks JSON (default), the Bookmarks file of a Chrome/Chromium profile, or a Netscape bookmarks.html as any browser exports them");
                 println!("-d (required): SQLite3 database file");
-                println!("-o (optional): if not specified, then NO CSV will be written");
-                println!("-c (optional): if not specified, then it will be ignored");
+                println!("-o (optional): the whole database as CSV, after the import; if not specified, then NO CSV will be written; '-' writes it to stdout, everything else printed then goes to stderr (unix only)");
+                println!("-c (optional): if not specified, then it will be ignored; '-' reads it from stdin, which then needs -i <bookmark.json>");
+                println!("--preview (optional): with -c, show which rows the CSV would overwrite and ask before writing them");
+                println!("--merge (optional): three-way merge of the bookmarks, the -c CSV and the database, field by field, newest source wins unless [merge] of the config says otherwise (see source_merge)");
+                println!("--sync (optional): bookmarks and database which disagree on the chapter are settled by which was updated last (else by the further chapter), the database taking the chapter of a bookmark which wins; every decision is printed (see bookmark_sync)");
+                println!("--sync-report <report.json> (optional): with --sync, the decisions written as JSON as well");
+                println!("--folder-tags (optional): the folders each bookmark is in (i.e. 'toolbar/Manga/連載中' tags it 'Manga' and '連載中') become tags of its manga, the browser's own root folder left out; not for --format html, whose bookmarks cannot be matched to their folders");
+                println!("--include-folder <folder> (optional, repeatable): only the bookmarks in the folder (a name such as 'Manga', or a path such as 'toolbar/Manga', ignoring case), or in the folders below it; not for --format html, whose folders are only known to the url filters");
+                println!("--exclude-folder <folder> (optional, repeatable): none of the bookmarks in the folder, or in the folders below it, even in an --include-folder");
+                println!("--url-filter <regex|glob> (optional, repeatable): only the bookmarks whose url matches one of them, a regex found anywhere in the url ('mangadex\\.org/title/') or a glob of the whole url ('*://mangadex.org/*')");
+                println!("--stream (optional): Firefox bookmarks JSON is written as it is read, {} bookmarks per transaction, rather than read whole first; for exports too large to fit in memory (not with --merge or --sync)", crate::service::service::K_STREAM_BATCH_SIZE);
                 println!("-D (optional): Debug outpupt");
+                println!("--auto-input (optional): instead of -i, use the newest bookmark backup (bookmarkbackups/) of the default Firefox profile");
+                println!("-p <places.sqlite> (optional): instead of -i, read the bookmarks straight out of a Firefox places database (the file, or the profile directory it is in); Firefox may keep running, a copy is read");
+                println!("--profile <name|default|all> (optional): instead of -i, read the bookmarks of the Firefox profile(s) found in profiles.ini, tagged 'profile:<name>'");
+                println!("--stages <stage,...> (optional): normalization stages of titles and urls, in that order, instead of [normalize] of the config or the default trim-quotes,sanitize,url-normalize,chapter-extract,title-clean,romanize (see normalize_pipeline)");
+                println!("--skip-stages <stage,...> (optional): normalization stages left out");
+                println!("--explain (optional): print what each normalization stage changed, for every bookmark");
+                println!("--title-collision <update-title|keep-both|ask> (optional): what a bookmark does to the row of its url when their titles differ, instead of title_collision of the config (default keep-both); update-title renames the row, keeping the old title in alt_titles");
+                println!("--scheme-policy <allow|warn|reject> (optional): what becomes of bookmarks whose url is neither http nor https (i.e. about:downloads), instead of [normalize] scheme_policy of the config (default allow); rejected ones are listed with why");
+                println!("--rpc [-d <db.sqlite3>]: JSON-RPC on stdin/stdout instead of importing (unix only)");
+                println!("Subcommands (i.e. 'search', 'stats', 'dedupe', 'export') each have their own flags, see '{} help' and '{} help <subcommand>'", args[0], args[0]);
+                std::process::exit(0);
             } else {
-                println!("Unknown argument: '{}'", args[i]);
-                // throw error
-                //return Err(format!("Unknown argument: {}", args[i]));
-                i += 1; // increment by 1 to skip the next argument
+                // a typo would otherwise import with the default of whatever was meant
+                return Err(Error::InvalidArgument(format!(
+                    "Unknown argument: '{}', see --help",
+                    args[i]
+                )));
             }
         }
 
+        if stream && (merge || sync) {
+            return Err(Error::InvalidArgument(
+                "--stream writes the bookmarks as they are read, it cannot be given with --merge or --sync"
+                    .to_string(),
+            ));
+        }
+        if merge && sync {
+            return Err(Error::InvalidArgument(
+                "--merge and --sync are different ways of importing, give one of them".to_string(),
+            ));
+        }
+
+        // stdin can only be read once: by a single `-i -` (or no -i at all), or by `-c -`
+        let stdin_readers = input_filepaths_bookmark_json
+            .iter()
+            .filter(|input_paths| *input_paths == K_STDIO)
+            .count()
+            + usize::from(!has_input_file && !auto_input && !has_places_input)
+            + usize::from(possible_last_csv.as_deref() == Some(K_STDIO));
+        if stdin_readers > 1 {
+            return Err(Error::InvalidArgument(
+                "stdin ('-') can only be one of the inputs, give -i <bookmark.json> along with -c -".to_string()),
+            );
+        }
+
         println!("DB_file (SQLite3): {} '{}'", has_db_file, db_full_paths);
         println!(
-            "Input_file (bookmark JSON): {} '{}'",
+            "Input_file (bookmark JSON): {} {:?}",
             has_input_file, input_filepaths_bookmark_json
         );
         println!(
@@ -250,6 +463,7 @@ mod json_to_csv {
         );
         println!("NOTE: If Input_csv_file is optional if SQLite3 is up-to-date, and the CSV is basically human-readable version of SQLite3");
         println!("If there are conflicts between CSV and SQLite3, then the CSV will take precedence over SQLite3 and updates will be written to SQLite3");
+        println!("(with --merge, the newest source takes precedence instead, field by field, or the source named for that field in [merge] of the config)");
         println!(
             "This way, one can hand-edit and update CSV file and then re-import it into SQLite3"
         );
@@ -257,41 +471,58 @@ mod json_to_csv {
         // first, read all available data and build database from both CSV (i.e. 漫画.csv) and JSON (i.e. bookmark.json)
         // into SQLite3 database 漫画.sqlite3
         if db_full_paths.is_empty() {
-            // locate to see if '漫画.csv' exists in current directory
-            let db_full_paths = String::from("漫画.sqlite3");
-            if !std::path::Path::new(&db_full_paths).exists() {
-                // exit application via panic!()
-                panic!("Error: DB file '{}' does not exist", db_full_paths);
-            }
-        } else {
-            // make sure that the DB file exists (accessible)
-            if !std::path::Path::new(&db_full_paths).exists() {
-                // exit application
-                panic!("Error: DB file '{}' does not exist", db_full_paths);
-            }
+            // locate to see if '漫画.sqlite3' exists in current directory
+            db_full_paths = String::from("漫画.sqlite3");
+        }
+        // make sure that the DB file exists (accessible)
+        if !std::path::Path::new(&db_full_paths).exists() {
+            return Err(Error::InvalidArgument(format!(
+                "DB file '{}' does not exist",
+                db_full_paths
+            )));
         }
 
         // append/read (deserialize) from input CSV file (if it exists)
         // note that when deserializing to the SQLite3, we'll just overwrite the existing entry
         // via upsert() method so ideally, caller should backup the existing database file
-        if has_possible_input_csv_file && possible_last_csv.is_some() {
+        if has_possible_input_csv_file && possible_last_csv.is_some() && !merge {
             match possible_last_csv {
                 Some(last_csv) => {
                     // make sure that the CSV file exists (accessible)
-                    if !std::path::Path::new(&last_csv).exists() {
+                    if last_csv != K_STDIO && !std::path::Path::new(&last_csv).exists() {
                         // No need to panic, just ignore and use the SQLite3 file
                         println!("Error: CSV file '{}' does not exist", last_csv);
                     }
-                    // open stream for csv file
-                    match File::open(last_csv.clone()) {
+                    // open stream for csv file (or stdin)
+                    let possible_input_csv: io::Result<Box<dyn Read>> = match last_csv.as_str() {
+                        K_STDIO => Ok(Box::new(io::stdin())),
+                        _ => File::open(last_csv.clone())
+                            .map(|input_csv_file| Box::new(input_csv_file) as Box<dyn Read>),
+                    };
+                    match possible_input_csv {
                         Ok(input_csv_file) => {
                             // read CSV file and deserialize each row, we'll directly
-                            // pass/transfer it down to SQLite
-                            match read_csv_and_update_sqlite(
-                                Box::new(input_csv_file),
-                                &db_full_paths.clone(),
-                                debug_flag,
-                            ) {
+                            // pass/transfer it down to SQLite (or to a copy of it first, with --preview)
+                            let csv_result = match preview {
+                                true => subcommands::subcommands::run_previewed(
+                                    &db_full_paths,
+                                    "csv import",
+                                    false,
+                                    |shadow_full_paths| {
+                                        read_csv_and_update_sqlite(
+                                            input_csv_file,
+                                            shadow_full_paths,
+                                            debug_flag,
+                                        )
+                                    },
+                                ),
+                                false => read_csv_and_update_sqlite(
+                                    input_csv_file,
+                                    &db_full_paths.clone(),
+                                    debug_flag,
+                                ),
+                            };
+                            match csv_result {
                                 Ok(()) => {
                                     // read line and written/updated to sqlite3...
                                 }
@@ -313,41 +544,90 @@ mod json_to_csv {
             }
         }
 
-        // now depending on stdin or firefox bookmark JSON file, we'll create a stream
-        let input_reader_bookmark_json: Box<dyn BufRead + 'static> = if has_input_file {
-            // open stream for input file
-            match File::open(input_filepaths_bookmark_json.clone()) {
-                Ok(input_file) => {
-                    Box::new(BufReader::new(input_file)) as Box<dyn BufRead + 'static>
-                }
-                Err(e) => {
-                    // file doesn't exist, just log error and continue on
-                    panic!(
-                        "Error opening input file '{}': {}",
-                        input_filepaths_bookmark_json.clone(),
-                        e
-                    );
+        // --auto-input (unless -i is given): the newest backup of the default firefox profile
+        let possible_auto_input_reader: Option<Box<dyn BufRead + 'static>> =
+            match auto_input && !has_input_file {
+                true => match crate::firefox_profiles::firefox_profiles::auto_input() {
+                    Ok((backup_path, json)) => {
+                        println!("Input_file (bookmark backup): '{}'", backup_path.display());
+                        Some(Box::new(io::Cursor::new(json)) as Box<dyn BufRead + 'static>)
+                    }
+                    Err(e) => {
+                        return Err(Error::InvalidArgument(format!(
+                            "Error locating Firefox bookmark backup: {}",
+                            e
+                        )));
+                    }
+                },
+                false => None,
+            };
+
+        // now depending on stdin or firefox bookmark JSON file(s), we'll create a stream for each
+        let input_readers_bookmark_json: Vec<(String, Box<dyn BufRead + 'static>)> =
+            if has_input_file {
+                let input_files = match bookmark_json_files(&input_filepaths_bookmark_json) {
+                    Ok(input_files) => input_files,
+                    Err(e) => {
+                        return Err(Error::InvalidArgument(format!(
+                            "Error listing input files {:?}: {}",
+                            input_filepaths_bookmark_json, e
+                        )));
+                    }
+                };
+                if input_files.is_empty() {
+                    return Err(Error::InvalidArgument(format!(
+                        "no bookmark JSON file in {:?}",
+                        input_filepaths_bookmark_json
+                    )));
                 }
-            }
-        } else {
-            // use stdin
-            Box::new(BufReader::new(io::stdin())) as Box<dyn BufRead + 'static>
-        };
+                // open stream for each input file ('-' being stdin)
+                input_files
+                    .into_iter()
+                    .map(|input_file_paths| match input_file_paths.as_str() {
+                        K_STDIO => Ok((
+                            String::from("stdin"),
+                            Box::new(BufReader::new(io::stdin())) as Box<dyn BufRead + 'static>,
+                        )),
+                        _ => match File::open(&input_file_paths) {
+                            Ok(input_file) => Ok((
+                                input_file_paths,
+                                Box::new(BufReader::new(input_file)) as Box<dyn BufRead + 'static>,
+                            )),
+                            Err(e) => {
+                                // file doesn't exist, nothing is imported rather than only some of the files
+                                Err(format!(
+                                    "Error opening input file '{}': {}",
+                                    input_file_paths, e
+                                ))
+                            }
+                        },
+                    })
+                    .collect::<Result<Vec<(String, Box<dyn BufRead + 'static>)>, String>>()
+                    .map_err(Error::InvalidArgument)?
+            } else if let Some(auto_input_reader) = possible_auto_input_reader {
+                vec![(String::from("bookmark backup"), auto_input_reader)]
+            } else {
+                // use stdin
+                vec![(
+                    String::from("stdin"),
+                    Box::new(BufReader::new(io::stdin())) as Box<dyn BufRead + 'static>,
+                )]
+            };
 
         // next, create a stream for CSV output (either csv file or stdout)
-        let output_writer_csv: Option<Box<dyn Write + 'static>> = if has_output_file {
+        let output_writer_csv: Option<Box<dyn Write + 'static>> = if possible_stdout_csv.is_some() {
+            possible_stdout_csv
+        } else if has_output_file {
             // open stream for output file
             match File::create(output_filepaths_csv.clone()) {
                 Ok(output_file) => {
                     Some(Box::new(BufWriter::new(output_file)) as Box<dyn Write + 'static>)
                 }
                 Err(e) => {
-                    // file doesn't exist, just log error and continue on
-                    panic!(
+                    return Err(Error::InvalidArgument(format!(
                         "Error opening output file '{}': {}",
-                        output_filepaths_csv.clone(),
-                        e
-                    );
+                        output_filepaths_csv, e
+                    )));
                 }
             }
         } else {
@@ -358,8 +638,8 @@ mod json_to_csv {
 
         let ret_tuple = (
             db_full_paths.clone(),
-            input_reader_bookmark_json, // -i
-            output_writer_csv,          // -o
+            input_readers_bookmark_json, // -i
+            output_writer_csv,           // -o
             debug_flag,
         );
 
@@ -372,6 +652,7 @@ mod json_to_csv {
 
         // Test with input file and output file
         let args = vec![
+            String::from("firefox_bookmark_to_csv"),
             String::from("-i"),
             String::from("samples/input.json"),
             String::from("-o"),
@@ -413,13 +694,16 @@ mod json_to_csv {
 
         // read test JSON files and attempt to deserialize it
         let args = vec![
+            String::from("firefox_bookmark_to_csv"),
             String::from("-i"),
             String::from("samples/input.json"),
             String::from("-d"),
             String::from(tmp_dir.clone() + "parse_args.sqlite3"),
         ];
         match parse_args(args) {
-            Ok((_db_paths, input_json, possible_output_csv, _)) => {
+            Ok((_db_paths, mut input_jsons, possible_output_csv, _)) => {
+                assert_eq!(input_jsons.len(), 1);
+                let (_input_name, input_json) = input_jsons.remove(0);
                 // deserialize - from_reader() method needs to access io::Read::bytes() method
                 let bookmark_folders: BookmarkRootFolder =
                     serde_json::from_reader(input_json).unwrap();
@@ -452,127 +736,653 @@ mod json_to_csv {
                 panic!("{}", e);
             }
         }
+
+        // typos and missing files are errors rather than imports of something else
+        let db_args = |extra_args: &[&str]| -> Vec<String> {
+            let mut args = vec![
+                String::from("firefox_bookmark_to_csv"),
+                String::from("-d"),
+                tmp_dir.clone() + "parse_args.sqlite3",
+            ];
+            args.extend(extra_args.iter().map(|arg| arg.to_string()));
+            args
+        };
+        for bad_args in [
+            db_args(&["-i", "samples/input.json", "--prevew"]),
+            db_args(&["-i"]),
+            db_args(&["-i", "samples/no_such_file.json"]),
+            vec![
+                String::from("firefox_bookmark_to_csv"),
+                String::from("-d"),
+                tmp_dir.clone() + "no_such_database.sqlite3",
+            ],
+        ] {
+            assert!(parse_args(bad_args.clone()).is_err(), "{:?}", bad_args);
+        }
+    }
+
+    #[test]
+    fn test_bookmark_json_files() {
+        let files =
+            bookmark_json_files(&[String::from("samples"), String::from("samples/input.json")])
+                .unwrap();
+        assert!(files.iter().all(|file| file.ends_with(".json")));
+        // once from the directory, once as given
+        assert_eq!(
+            files
+                .iter()
+                .filter(|file| file.ends_with("input.json"))
+                .count(),
+            2
+        );
     }
 }
 
-fn get_args() -> (
-    String,                           // db_full_paths*/
-    Box<dyn BufRead + 'static>,       // input_reader_bookmark_json*/
-    Option<Box<dyn Write + 'static>>, // output_writer_csv*/
-    bool,                             // debug_flag
+fn get_args(
+    args: Vec<String>,
+) -> (
+    String,                                    // db_full_paths*/
+    Vec<(String, Box<dyn BufRead + 'static>)>, // input_readers_bookmark_json*/
+    Option<Box<dyn Write + 'static>>,          // output_writer_csv*/
+    bool,                                      // debug_flag
 ) {
-    let args: Vec<String> = std::env::args().collect();
-
-    // read in JSON either from stdin or file
-    let (db_full_paths, input_reader_bookmark_json, possible_output_writer_csv, debug_flag) =
+    // read in JSON either from stdin or file(s)
+    let (db_full_paths, input_readers_bookmark_json, possible_output_writer_csv, debug_flag) =
         match json_to_csv::parse_args(args) {
-            Ok((db_full_paths, input_reader_json, output_writer_csv, debug_flag)) => (
+            Ok((db_full_paths, input_readers_json, output_writer_csv, debug_flag)) => (
                 db_full_paths,
-                input_reader_json,
+                input_readers_json,
                 output_writer_csv,
                 debug_flag,
             ),
             Err(e) => {
-                // do we want to just panic?
-                panic!("{}", e);
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
             }
         };
     (
         db_full_paths,
-        input_reader_bookmark_json,
+        input_readers_bookmark_json,
         possible_output_writer_csv,
         debug_flag,
     )
 }
 
 fn read_bookmarks_into_manga<'a>(
-    result_bookmark_folders: &Result<BookmarkRootFolder, serde_json::Error>,
-) -> Result<Vec<MangaModel>, Box<dyn std::error::Error + '_>> {
-    let bookmarks_raw: Vec<BookmarkNodes> = match result_bookmark_folders {
-        Ok(bookmark_folders) => {
-            // recursively visit each child and return Some tuple if it is bookmark, else return None for containers and separators
-            fn traverse_children(children: &Vec<BookmarkNodes>) -> Vec<BookmarkNodes> {
-                let mut bookmarks: Vec<BookmarkNodes> = Vec::new();
-                for child in children {
-                    if child.is_bookmark() {
-                        bookmarks.push(child.clone());
-                    } else if let Some(children) = &child.possible_children() {
-                        bookmarks.append(&mut traverse_children(children));
-                    }
-                    // else, it's a separator, so we'll ignore it...
-                }
-                bookmarks
-            }
-            traverse_children(bookmark_folders.children())
-        }
+    result_bookmark_folders: &'a Result<BookmarkRootFolder, serde_json::Error>,
+    pipeline: &Pipeline,
+) -> Result<Vec<MangaModel>, Box<dyn std::error::Error + 'a>> {
+    match result_bookmark_folders {
+        Ok(bookmark_folders) => service::service::bookmarks_to_manga(bookmark_folders, pipeline),
         Err(e) => {
-            let err = e.clone();
             // pretty much, if we cannot read the JSON, then this app is useless, so just panic!() at the caller level
-            // just opt-out early and bail out of this function
-            println!("Error deserializing JSON: {}", err);
-            let ret = Box::new(err);
-            return Err(ret);
+            println!("Error deserializing JSON: {}", e);
+            Err(Box::new(e.clone()))
+        }
+    }
+}
+
+// `--format chrome`: the Bookmarks file of a Chrome/Chromium profile instead of the Firefox JSON, and where each
+// bookmark is in it
+fn read_chrome_bookmarks_into_manga(
+    input_reader_bookmark_json: Box<dyn BufRead>,
+    pipeline: &Pipeline,
+) -> Result<(Vec<MangaModel>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+    let chrome_bookmarks: ChromeBookmarks = serde_json::from_reader(input_reader_bookmark_json)?;
+    Ok((
+        service::service::chrome_bookmarks_to_manga(&chrome_bookmarks, pipeline)?,
+        service::service::chrome_bookmark_origins(&chrome_bookmarks, ""),
+    ))
+}
+
+// `--format html`: a Netscape bookmarks.html, as any browser exports them; it has no GUIDs, so no origins either,
+// which is why the folders of bookmark_filter apply here, before the bookmarks become manga
+fn read_netscape_bookmarks_into_manga(
+    mut input_reader_bookmark_html: Box<dyn BufRead>,
+    pipeline: &Pipeline,
+    bookmark_filter: &service::service::BookmarkFilter,
+) -> Result<(Vec<MangaModel>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+    let mut html = String::new();
+    input_reader_bookmark_html.read_to_string(&mut html)?;
+    let mut bookmarks =
+        model_netscape_bookmarks::model_netscape_bookmarks::read_bookmarks_html(&html);
+    bookmarks.retain(|bookmark| bookmark_filter.keeps(Some(&bookmark.folder_path), &bookmark.href));
+    Ok((
+        service::service::netscape_bookmarks_to_manga(&bookmarks, pipeline)?,
+        Vec::new(),
+    ))
+}
+
+// bookmarks of every selected profile, each with its "profile:<name>" tag, and where they are in the profile
+fn read_profiles_into_manga(
+    selection: &str,
+    pipeline: &Pipeline,
+) -> Result<(Vec<(MangaModel, Option<String>)>, Vec<BookmarkOrigin>), Box<dyn std::error::Error>> {
+    let profiles = firefox_profiles::firefox_profiles::select_profiles(
+        &firefox_profiles::firefox_profiles::discover_profiles()?,
+        selection,
+    )?;
+    let mut mangas = Vec::new();
+    let mut origins = Vec::new();
+    for profile in profiles.iter() {
+        let (profile_mangas, profile_origins) =
+            firefox_profiles::firefox_profiles::read_places_bookmarks(
+                &profile.path,
+                &profile.name,
+                pipeline,
+            )?;
+        println!(
+            "Firefox profile '{}': {} bookmarks",
+            profile.name,
+            profile_mangas.len()
+        );
+        let profile_tag = format!(
+            "{}{}",
+            firefox_profiles::firefox_profiles::K_PROFILE_TAG_PREFIX,
+            profile.name
+        );
+        mangas.extend(
+            profile_mangas
+                .into_iter()
+                .map(|manga| (manga, Some(profile_tag.clone()))),
+        );
+        origins.extend(profile_origins);
+    }
+    Ok((mangas, origins))
+}
+
+// bookmarks of one `-i` file (or of the `--profile` selection): its name, each bookmark with its profile tag (if
+// any), and where they were in the bookmarks
+type BookmarkInput = (
+    String,
+    Vec<(MangaModel, Option<String>)>,
+    Vec<BookmarkOrigin>,
+);
+
+// bookmarks the pipeline rejected (`--scheme-policy reject`) are not imported, and why is said for each; those of
+// the browser's own schemes (about:, place:...) are only counted; either way of the bookmarks read since the last call
+fn report_dropped_bookmarks(pipeline: &Pipeline) {
+    let rejections = pipeline.take_rejections();
+    for rejection in rejections.iter() {
+        eprintln!("rejected: {}", rejection);
+    }
+    if !rejections.is_empty() {
+        eprintln!("{} bookmarks rejected", rejections.len());
+    }
+    let skipped = pipeline.take_skipped();
+    if !skipped.is_empty() {
+        eprintln!(
+            "skipped: {}",
+            normalize_pipeline::normalize_pipeline::skip_report(&skipped)
+        );
+    }
+}
+
+// `-o`: the whole database as it is after the import, header first (nothing without -o)
+fn write_database_csv(
+    possible_csv_writer_util: Option<&mut model_csv_manga::model_csv_manga::Utils>,
+    db_full_paths: &str,
+) {
+    if let Some(csv_writer_util) = possible_csv_writer_util {
+        let written =
+            subcommands::subcommands::select_all_manga_or_empty(db_full_paths).and_then(|mangas| {
+                csv_writer_util.write_csv_header()?;
+                csv_writer_util.write_csv(&mangas)?;
+                Ok(())
+            });
+        if let Err(e) = written {
+            eprintln!("Error writing CSV of '{}': {}", db_full_paths, e);
         }
+    }
+}
+
+// three-way merge of the bookmarks read, the `-c` CSV (if any) and the database; conflicts are listed, not written
+fn merge_sources(
+    args: &[String],
+    db_full_paths: &str,
+    bookmarks: &[MangaModel],
+    origins: &[BookmarkOrigin],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let csv_rows = match subcommands::subcommands::flag_value(args, "-c") {
+        Some(csv_full_paths) => subcommands::subcommands::read_csv_rows(&csv_full_paths)?,
+        None => Vec::new(),
     };
+    let database = model_sqlite3_manga::model_sqlite3_manga::select_all_manga(db_full_paths)
+        .or_else(|e| match e {
+            error::error::Error::Sqlite(rusqlite::Error::QueryReturnedNoRows) => Ok(Vec::new()),
+            e => Err(e),
+        })?;
+    let policy = subcommands::subcommands::load_config(args)?.merge;
+    let outcome = source_merge::source_merge::merge(bookmarks, &csv_rows, &database, &policy);
+    for conflict in outcome.conflicts.iter() {
+        println!(
+            "CONFLICT: id={} '{}' {}: {} (the database's is kept)",
+            conflict.id,
+            conflict.title,
+            conflict.field,
+            conflict
+                .values
+                .iter()
+                .map(|(source, value)| format!("{:?} '{}'", source, value))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    source_merge::source_merge::apply(db_full_paths, &outcome, origins)?;
+    println!(
+        "merge: {} row(s) updated, {} added, {} conflict(s)",
+        outcome.updated.len(),
+        outcome.inserted.len(),
+        outcome.conflicts.len()
+    );
+    Ok(())
+}
 
-    // now that we've got it as data-model, we will just travese down each child and print out the title, URI, and last modified date, sorted by last modified date
-    let mut bookmarks_sorted: Vec<BookmarkNodes> = bookmarks_raw.clone();
-    //bookmarks_sorted.sort_by(|a, b| a.last_modified().cmp(&b.last_modified()));   // sort by date-column
-    bookmarks_sorted.sort_by(|a, b| a.uri().cmp(&b.uri())); // sort by URI
-
-    // CSV output, we're assuming that by here, only the "places" nodes are left, so we can just print them out in CSV format
-    // either to the stdout or to the output file stream
-    //let mut csv_writer = csv::WriterBuilder::new()
-    //    .quote_style(csv::QuoteStyle::Always) // just easier to just quote everything including numbers
-    //    .from_writer(output_writer);
-    let mut mangas_mut = Vec::new();
-    for bookmark in bookmarks_sorted {
-        // convert the last_modified i64 to datetime - last_modified is encoded as unix epoch time in microseconds
-        let str_last_modified = my_libs::from_epoch_to_str(*bookmark.last_modified());
-        let mut mm: MangaModel = match MangaModel::new_from_required_elements(
-            bookmark.title(),
-            bookmark.uri().clone().as_str(),
-            model_manga::CASTAGNOLI.checksum(bookmark.uri().clone().as_bytes()),
-        ) {
-            Ok(mm) => mm,
-            Err(e) => {
-                // let the caller handle panic, here we'll just opt out early and return error
-                println!("Error creating MangaModel: {}", e);
-                return Err(e);
-            }
-        };
-        mm.set_last_update(Some(str_last_modified));
-        mangas_mut.push(mm);
+// `--sync`: chapters of the bookmarks read and of the database settled by last update; every decision is printed
+fn sync_bookmarks(
+    args: &[String],
+    db_full_paths: &str,
+    bookmarks: &[MangaModel],
+    origins: &[BookmarkOrigin],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let database = model_sqlite3_manga::model_sqlite3_manga::select_all_manga(db_full_paths)
+        .or_else(|e| match e {
+            error::error::Error::Sqlite(rusqlite::Error::QueryReturnedNoRows) => Ok(Vec::new()),
+            e => Err(e),
+        })?;
+    let outcome = bookmark_sync::bookmark_sync::sync(bookmarks, &database);
+    for decision in outcome.decisions.iter() {
+        println!("SYNC: {}", decision);
+    }
+    bookmark_sync::bookmark_sync::apply(db_full_paths, &outcome, origins)?;
+    if let Some(report_full_paths) = subcommands::subcommands::flag_value(args, "--sync-report") {
+        std::fs::write(
+            &report_full_paths,
+            serde_json::to_string_pretty(&outcome.decisions)?,
+        )?;
     }
+    println!(
+        "sync: {} row(s) updated from the bookmarks, {} kept over them, {} added",
+        outcome.updated.len(),
+        outcome.decisions.len() - outcome.updated.len(),
+        outcome.inserted.len()
+    );
+    Ok(())
+}
 
-    // now that new and old are merged, sort by last_modified and print out the CSV
-    mangas_mut.sort_by(|a, b| a.url().cmp(&b.url()));
-    Ok(mangas_mut)
+// `--stream`: each of the Firefox bookmarks JSON inputs written as it is read, a batch at a time, rather than read
+// whole first
+fn stream_bookmarks(
+    db_full_paths: &str,
+    input_readers_bookmark_json: Vec<(String, Box<dyn BufRead + 'static>)>,
+    pipeline: &Pipeline,
+    title_collision: config::config::TitleCollisionPolicy,
+    bookmark_filter: &service::service::BookmarkFilter,
+    folder_tags: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut store = model_sqlite3_manga::model_sqlite3_manga::MangaStore::open(db_full_paths)?;
+    for (input_name, input_reader_bookmark_json) in input_readers_bookmark_json {
+        let (mut inserted, mut renamed, mut filtered_out) = (0, 0, 0);
+        let written_count = service::service::stream_bookmarks_to_manga(
+            input_reader_bookmark_json,
+            pipeline,
+            "",
+            service::service::K_STREAM_BATCH_SIZE,
+            &mut |mut mangas, mut origins| {
+                filtered_out +=
+                    service::service::retain_bookmarks(bookmark_filter, &mut mangas, &mut origins);
+                if folder_tags {
+                    service::service::tag_with_folders(&mut mangas, &origins);
+                }
+                let written = store.insert_batch_with(&mangas, &mut |id, title, manga| {
+                    subcommands::subcommands::renames_on_title_collision(
+                        title_collision,
+                        id,
+                        title,
+                        manga,
+                    )
+                })?;
+                model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+                    store.connection(),
+                    &origins,
+                    &written.mangas,
+                )?;
+                inserted += written.inserted;
+                renamed += written.renamed;
+                Ok(())
+            },
+        )
+        .map_err(|e| format!("reading bookmarks '{}': {}", input_name, e))?;
+        report_dropped_bookmarks(pipeline);
+        if filtered_out > 0 {
+            println!("'{}': {} bookmarks filtered out", input_name, filtered_out);
+        }
+        println!(
+            "'{}': {} bookmarks written, {} new, {} renamed",
+            input_name,
+            written_count - filtered_out,
+            inserted,
+            renamed
+        );
+    }
+    Ok(())
 }
 
 fn main() {
-    // read in JSON either from stdin or file
-    let (db_full_paths, input_reader_bookmark_json, possible_output_writer_csv, debug_flag) =
-        get_args();
+    // subcommands (i.e. `manga feed --out updates.xml`) bypass the legacy -i/-o/-c/-d import entirely
+    let args: Vec<String> = std::env::args().collect();
+    // `import -i <bookmarks.json>` is the same as without the subcommand
+    let args = subcommands::subcommands::legacy_import_args(&args).unwrap_or(args);
+    if subcommands::subcommands::is_subcommand(&args) {
+        if let Err(e) = subcommands::subcommands::run(&args) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `--rpc` serves JSON-RPC on stdin/stdout (for editors and launchers) instead of importing
+    if args.iter().any(|arg| arg == "--rpc") {
+        if let Err(e) = json_rpc::json_rpc::run(&subcommands::subcommands::db_full_paths(&args)) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    // read in JSON and deserialize it as Bookmark structure
-    let bookmark_folders: Result<BookmarkRootFolder, serde_json::Error> =
-        serde_json::from_reader(input_reader_bookmark_json);
+    // read in JSON either from stdin or file(s)
+    let (db_full_paths, input_readers_bookmark_json, possible_output_writer_csv, debug_flag) =
+        get_args(args.clone());
 
-    let _mut_csv_writer_util = model_csv_manga::model_csv_manga::Utils::new(
-        possible_output_writer_csv,
-        Box::new(BufReader::new(io::stdin())),
-    );
-    // read in json (firefox bookmarks) and deserialize it into MangaModel - pass writer by ref
-    let mangas_mut = read_bookmarks_into_manga(&bookmark_folders).unwrap(); // let's panic if it fails
-
-    // update local sqlite database with mangas_mut (Vec<MangaModel> list)
-    for manga in &mangas_mut {
-        if debug_flag {
-            //println!("manga: {:#?}", manga);
-            //println!("manga: {:?}", manga);
-            println!("manga => {}", manga); // since Display is impl'ed for MangaModel, we can just print it out
+    // how titles and urls of the bookmarks are normalized: [normalize] and [[title_rules]] of the config, or
+    // `--stages`, `--skip-stages` and `--explain`; what they do to a row of their url under another title; and which
+    // of them are imported at all
+    let (pipeline, title_collision, bookmark_filter) =
+        match subcommands::subcommands::load_config(&args).and_then(|config| {
+            Ok((
+                subcommands::subcommands::normalize_pipeline_of(&args, &config)?,
+                subcommands::subcommands::title_collision_of(&args, &config)?,
+                subcommands::subcommands::bookmark_filter_of(&args)?,
+            ))
+        }) {
+            Ok(pipeline_title_collision_and_filter) => pipeline_title_collision_and_filter,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+    // `--format chrome|html`: the -i files are Chrome/Chromium bookmarks, or a bookmarks.html, rather than Firefox
+    // bookmarks JSON
+    let bookmark_format = match subcommands::subcommands::flag_value(&args, "--format") {
+        None => SourceFormat::Firefox,
+        Some(format) => match format.parse::<SourceFormat>() {
+            Ok(SourceFormat::Firefox) => SourceFormat::Firefox,
+            Ok(SourceFormat::Chrome) => SourceFormat::Chrome,
+            Ok(SourceFormat::Html) => SourceFormat::Html,
+            Ok(_) => {
+                eprintln!(
+                    "Error: -i cannot be '{}', expected firefox, chrome or html",
+                    format
+                );
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // `--stream`: Firefox bookmarks JSON too large to read whole is written as it is read
+    if args.iter().any(|arg| arg == "--stream") {
+        if bookmark_format != SourceFormat::Firefox
+            || subcommands::subcommands::flag_value(&args, "--profile").is_some()
+            || subcommands::subcommands::flag_value(&args, "-p").is_some()
+        {
+            eprintln!("Error: --stream only reads Firefox bookmarks JSON (-i), not --format, -p or --profile");
+            std::process::exit(1);
+        }
+        let possible_git_export =
+            subcommands::subcommands::git_export_snapshot(&args, &db_full_paths);
+        let _table_created =
+            model_sqlite3_manga::model_sqlite3_manga::create_tables(&db_full_paths);
+        if let Err(e) = stream_bookmarks(
+            &db_full_paths,
+            input_readers_bookmark_json,
+            &pipeline,
+            title_collision,
+            &bookmark_filter,
+            args.iter().any(|arg| arg == "--folder-tags"),
+        ) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
-        let _db_result = upsert_db(&db_full_paths, manga, true, debug_flag);
+        let mut possible_csv_writer_util = possible_output_writer_csv.map(|output_writer_csv| {
+            model_csv_manga::model_csv_manga::Utils::new(
+                Some(output_writer_csv),
+                Box::new(io::empty()),
+            )
+        });
+        write_database_csv(possible_csv_writer_util.as_mut(), &db_full_paths);
+        subcommands::subcommands::git_export_commit(
+            possible_git_export,
+            &db_full_paths,
+            "import bookmarks",
+        );
+        return;
     }
+
+    // `--profile <name|all>` reads the bookmarks of the Firefox profile(s) instead of the JSON, each tagged with
+    // the profile it came from, and `-p` those of a places.sqlite; otherwise each `-i` file is read (and later
+    // written) in turn, by its name
+    let inputs: Vec<BookmarkInput> = match (
+        subcommands::subcommands::flag_value(&args, "--profile"),
+        subcommands::subcommands::flag_value(&args, "-p"),
+    ) {
+        (Some(selection), _) => match read_profiles_into_manga(&selection, &pipeline) {
+            Ok((mangas, origins)) => {
+                report_dropped_bookmarks(&pipeline);
+                vec![(format!("--profile {}", selection), mangas, origins)]
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(places_paths)) => match firefox_profiles::firefox_profiles::read_places_file(
+            &firefox_profiles::firefox_profiles::places_path_of(std::path::Path::new(
+                &places_paths,
+            )),
+            "",
+            &pipeline,
+        ) {
+            Ok((mangas, origins)) => {
+                println!("'{}': {} bookmarks read", places_paths, mangas.len());
+                report_dropped_bookmarks(&pipeline);
+                let mangas = mangas.into_iter().map(|manga| (manga, None)).collect();
+                vec![(places_paths, mangas, origins)]
+            }
+            Err(e) => {
+                eprintln!("Error reading Firefox places '{}': {}", places_paths, e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => input_readers_bookmark_json
+            .into_iter()
+            .map(|(input_name, input_reader_bookmark_json)| {
+                if bookmark_format != SourceFormat::Firefox {
+                    let read = match bookmark_format {
+                        SourceFormat::Html => read_netscape_bookmarks_into_manga(
+                            input_reader_bookmark_json,
+                            &pipeline,
+                            &bookmark_filter,
+                        ),
+                        _ => {
+                            read_chrome_bookmarks_into_manga(input_reader_bookmark_json, &pipeline)
+                        }
+                    };
+                    let (mangas, origins) = read.unwrap_or_else(|e| {
+                        // same as the Firefox JSON, nothing to import without it
+                        eprintln!("Error reading bookmarks '{}': {}", input_name, e);
+                        std::process::exit(1);
+                    });
+                    println!("'{}': {} bookmarks read", input_name, mangas.len());
+                    report_dropped_bookmarks(&pipeline);
+                    let mangas = mangas.into_iter().map(|manga| (manga, None)).collect();
+                    return (input_name, mangas, origins);
+                }
+                // read in JSON and deserialize it as Bookmark structure
+                let bookmark_folders: Result<BookmarkRootFolder, serde_json::Error> =
+                    serde_json::from_reader(input_reader_bookmark_json);
+                // read in json (firefox bookmarks) and deserialize it into MangaModel - pass writer by ref
+                let mangas: Vec<(MangaModel, Option<String>)> =
+                    read_bookmarks_into_manga(&bookmark_folders, &pipeline)
+                        .unwrap() // let's panic if it fails
+                        .into_iter()
+                        .map(|manga| (manga, None))
+                        .collect();
+                let origins = match &bookmark_folders {
+                    Ok(bookmark_folders) => {
+                        service::service::bookmark_origins(bookmark_folders, "")
+                    }
+                    Err(_) => Vec::new(),
+                };
+                println!("'{}': {} bookmarks read", input_name, mangas.len());
+                report_dropped_bookmarks(&pipeline);
+                (input_name, mangas, origins)
+            })
+            .collect(),
+    };
+
+    let mut possible_csv_writer_util = possible_output_writer_csv.map(|output_writer_csv| {
+        model_csv_manga::model_csv_manga::Utils::new(Some(output_writer_csv), Box::new(io::empty()))
+    });
+    let possible_git_export = subcommands::subcommands::git_export_snapshot(&args, &db_full_paths);
+
+    // update local sqlite database with the mangas of each input (Vec<MangaModel> list) in a single transaction
+    // each; bookmarks already in the database are left as they are (INSERT OR IGNORE), but still get their profile
+    // tag; those of a known url under another title are as title_collision says
+    let folder_tags = args.iter().any(|arg| arg == "--folder-tags");
+    let inputs: Vec<(String, Vec<MangaModel>, Vec<BookmarkOrigin>)> = inputs
+        .into_iter()
+        .map(|(input_name, mangas_mut, mut origins)| {
+            let mut mangas: Vec<MangaModel> = mangas_mut
+                .into_iter()
+                .map(|(mut manga, possible_profile_tag)| {
+                    if debug_flag {
+                        println!("manga => {}", manga); // since Display is impl'ed for MangaModel, we can just print it out
+                    }
+                    if let Some(profile_tag) = possible_profile_tag {
+                        let mut tags: Vec<String> =
+                            manga.tags().iter().map(|tag| tag.to_string()).collect();
+                        tags.push(profile_tag);
+                        manga.set_tags(tags);
+                    }
+                    manga
+                })
+                .collect();
+            // `--include-folder`, `--exclude-folder` and `--url-filter`: only the bookmarks asked for
+            let filtered_out =
+                service::service::retain_bookmarks(&bookmark_filter, &mut mangas, &mut origins);
+            if filtered_out > 0 {
+                println!("'{}': {} bookmarks filtered out", input_name, filtered_out);
+            }
+            // `--folder-tags`: the folders of the bookmarks (i.e. "連載中") as tags of their manga
+            if folder_tags {
+                service::service::tag_with_folders(&mut mangas, &origins);
+            }
+            (input_name, mangas, origins)
+        })
+        .collect();
+    // whether tables already exist or not (older databases get their missing columns)
+    let _table_created = model_sqlite3_manga::model_sqlite3_manga::create_tables(&db_full_paths);
+    // `--merge`: bookmarks (of all the inputs), CSV and database merged field by field rather than only adding new
+    // bookmarks
+    if args.iter().any(|arg| arg == "--merge") {
+        let mangas: Vec<MangaModel> = inputs
+            .iter()
+            .flat_map(|(_, mangas, _)| mangas.iter().cloned())
+            .collect();
+        let origins: Vec<BookmarkOrigin> = inputs
+            .iter()
+            .flat_map(|(_, _, origins)| origins.iter().cloned())
+            .collect();
+        if let Err(e) = merge_sources(&args, &db_full_paths, &mangas, &origins) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        write_database_csv(possible_csv_writer_util.as_mut(), &db_full_paths);
+        subcommands::subcommands::git_export_commit(possible_git_export, &db_full_paths, "merge");
+        return;
+    }
+    // `--sync`: chapters which differ between a bookmark and the database are settled by last update
+    if args.iter().any(|arg| arg == "--sync") {
+        let mangas: Vec<MangaModel> = inputs
+            .iter()
+            .flat_map(|(_, mangas, _)| mangas.iter().cloned())
+            .collect();
+        let origins: Vec<BookmarkOrigin> = inputs
+            .iter()
+            .flat_map(|(_, _, origins)| origins.iter().cloned())
+            .collect();
+        if let Err(e) = sync_bookmarks(&args, &db_full_paths, &mangas, &origins) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        write_database_csv(possible_csv_writer_util.as_mut(), &db_full_paths);
+        subcommands::subcommands::git_export_commit(possible_git_export, &db_full_paths, "sync");
+        return;
+    }
+    // one connection for all of the inputs, each of them written in a single transaction
+    let mut store = match model_sqlite3_manga::model_sqlite3_manga::MangaStore::open(&db_full_paths)
+    {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error: cannot open '{}': {}", db_full_paths, e);
+            std::process::exit(1);
+        }
+    };
+    for (input_name, mangas, origins) in inputs.iter() {
+        let _db_result = store
+            .insert_batch_with(mangas, &mut |id, title, manga| {
+                subcommands::subcommands::renames_on_title_collision(
+                    title_collision,
+                    id,
+                    title,
+                    manga,
+                )
+            })
+            .and_then(|written| {
+                // which bookmark(s) each row came from
+                model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+                    store.connection(),
+                    origins,
+                    &written.mangas,
+                )?;
+                Ok(written)
+            })
+            .map(|written| {
+                println!(
+                    "'{}': {} bookmarks written, {} new, {} renamed",
+                    input_name,
+                    mangas.len(),
+                    written.inserted,
+                    written.renamed
+                )
+            })
+            .map_err(|e| {
+                println!(
+                    "ERROR: writing {} bookmarks of '{}': {}",
+                    mangas.len(),
+                    input_name,
+                    e
+                )
+            });
+    }
+    write_database_csv(possible_csv_writer_util.as_mut(), &db_full_paths);
+    subcommands::subcommands::git_export_commit(
+        possible_git_export,
+        &db_full_paths,
+        "import bookmarks",
+    );
 }
@@ -0,0 +1,247 @@
+// Row-level differences between two versions of the collection, rows matched by id (or any other key, see
+// diff_by()): rows only in the first (i.e. deleted by an operation), rows only in the second (added), and the
+// fields which differ on the rest.
+// Fields are compared as displayed: tags trimmed and sorted, user fields as "key=value" pairs, and the
+// values maintained by the database itself (updated_at, last_update_millis, last_checked_millis) left out.
+pub mod manga_diff {
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct FieldDiff {
+        pub field: &'static str,
+        pub before: String,
+        pub after: String,
+    }
+
+    // what a row read from CSV carries (see model_csv_manga::Utils::next()), the rest is the database's own
+    pub const K_CSV_FIELDS: [&str; 8] = [
+        "title",
+        "title_romanized",
+        "url",
+        "url_with_chapter",
+        "chapter",
+        "last_update",
+        "notes",
+        "tags",
+    ];
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct ChangedManga {
+        pub id: i64,       // as it is before
+        pub title: String, // as it is after
+        pub fields: Vec<FieldDiff>,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+    pub struct MangaDiff {
+        pub only_before: Vec<MangaModel>,
+        pub only_after: Vec<MangaModel>,
+        pub changed: Vec<ChangedManga>,
+    }
+
+    // fields in the order they are reported
+    pub fn field_values(manga: &MangaModel) -> Vec<(&'static str, String)> {
+        let mut tags: Vec<String> = manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .collect();
+        tags.sort();
+        vec![
+            ("title", manga.title().to_string()),
+            (
+                "title_romanized",
+                manga.title_romanized().unwrap_or_default(),
+            ),
+            ("url", manga.url().to_string()),
+            (
+                "url_with_chapter",
+                manga.url_with_chapter().unwrap_or_default(),
+            ),
+            // the database writes no chapter as "0"
+            (
+                "chapter",
+                manga.chapter().unwrap_or_else(|| "0".to_string()),
+            ),
+            ("last_update", manga.last_update().unwrap_or_default()),
+            ("notes", manga.notes().unwrap_or_default()),
+            ("tags", tags.join(", ")),
+            ("my_anime_list", manga.my_anime_list().unwrap_or_default()),
+            ("feed_url", manga.feed_url().unwrap_or_default()),
+            (
+                "latest_available_chapter",
+                manga.latest_available_chapter().unwrap_or_default(),
+            ),
+            ("bookmark_guid", manga.bookmark_guid().unwrap_or_default()),
+            (
+                "user_fields",
+                manga
+                    .user_fields()
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+        ]
+    }
+
+    pub fn field_diffs(before: &MangaModel, after: &MangaModel) -> Vec<FieldDiff> {
+        field_values(before)
+            .into_iter()
+            .zip(field_values(after))
+            .filter(|((_, before_value), (_, after_value))| before_value != after_value)
+            .map(|((field, before_value), (_, after_value))| FieldDiff {
+                field,
+                before: before_value,
+                after: after_value,
+            })
+            .collect()
+    }
+
+    pub fn diff(before: &[MangaModel], after: &[MangaModel]) -> MangaDiff {
+        diff_by(before, after, |manga| manga.id(), None)
+    }
+
+    // rows matched by key rather than by id, i.e. by title and url for rows which have no id of the database
+    // yet (when several rows have the same key, the last one counts); with possible_fields, only those fields
+    // are compared.  Each list is sorted by key
+    pub fn diff_by<K: Ord>(
+        before: &[MangaModel],
+        after: &[MangaModel],
+        key: impl Fn(&MangaModel) -> K,
+        possible_fields: Option<&[&str]>,
+    ) -> MangaDiff {
+        let before_by_key: BTreeMap<K, &MangaModel> =
+            before.iter().map(|manga| (key(manga), manga)).collect();
+        let after_by_key: BTreeMap<K, &MangaModel> =
+            after.iter().map(|manga| (key(manga), manga)).collect();
+        let mut manga_diff = MangaDiff::default();
+        for (manga_key, before_manga) in before_by_key.iter() {
+            match after_by_key.get(manga_key) {
+                Some(after_manga) => {
+                    let fields: Vec<FieldDiff> = field_diffs(before_manga, after_manga)
+                        .into_iter()
+                        .filter(|field| {
+                            possible_fields.map_or(true, |fields| fields.contains(&field.field))
+                        })
+                        .collect();
+                    if !fields.is_empty() {
+                        manga_diff.changed.push(ChangedManga {
+                            id: before_manga.id(),
+                            title: after_manga.title().to_string(),
+                            fields,
+                        });
+                    }
+                }
+                None => manga_diff.only_before.push((*before_manga).clone()),
+            }
+        }
+        manga_diff.only_after = after_by_key
+            .iter()
+            .filter(|(manga_key, _)| !before_by_key.contains_key(manga_key))
+            .map(|(_, manga)| (*manga).clone())
+            .collect();
+        manga_diff
+    }
+
+    impl MangaDiff {
+        pub fn is_empty(&self) -> bool {
+            self.only_before.is_empty() && self.only_after.is_empty() && self.changed.is_empty()
+        }
+
+        // one line per row only on one side ("-" before, "+" after), and per changed row ("~") followed by
+        // one indented line per field, i.e.
+        //      ~ id=3 'Gate'
+        //          chapter: '12' => '13'
+        pub fn to_lines(&self) -> Vec<String> {
+            let mut lines = Vec::new();
+            for manga in self.only_before.iter() {
+                lines.push(format!(
+                    "- id={} '{}' {}",
+                    manga.id(),
+                    manga.title(),
+                    manga.url()
+                ));
+            }
+            for manga in self.only_after.iter() {
+                lines.push(format!(
+                    "+ id={} '{}' {}",
+                    manga.id(),
+                    manga.title(),
+                    manga.url()
+                ));
+            }
+            for changed in self.changed.iter() {
+                lines.push(format!("~ id={} '{}'", changed.id, changed.title));
+                for field in changed.fields.iter() {
+                    lines.push(format!(
+                        "    {}: '{}' => '{}'",
+                        field.field, field.before, field.after
+                    ));
+                }
+            }
+            lines
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_diff() {
+            let gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            let mut gate_after = gate.clone();
+            gate_after.set_chapter(Some("13".to_string()));
+            gate_after.set_tags(vec![" seinen".to_string(), "isekai".to_string()]);
+            let mut gate_retagged = gate.clone();
+            gate_retagged.set_tags(vec!["isekai".to_string(), "seinen ".to_string()]);
+            let blame =
+                MangaModel::new_from_required_elements("Blame!", "https://example.com/blame/", 3)
+                    .unwrap();
+
+            assert!(diff(
+                &[gate.clone(), akira.clone()],
+                &[akira.clone(), gate.clone()]
+            )
+            .is_empty());
+            assert!(field_diffs(&gate_after, &gate_retagged)
+                .iter()
+                .all(|field| field.field != "tags"));
+
+            // by title, whatever the id, comparing only the chapter
+            let mut gate_renumbered = gate_after.clone();
+            gate_renumbered.set_id(9);
+            let renumbered = diff_by(
+                &[gate.clone()],
+                &[gate_renumbered],
+                |manga| manga.title().to_string(),
+                Some(&["chapter"]),
+            );
+            assert_eq!(
+                renumbered.to_lines(),
+                vec!["~ id=1 'Gate'", "    chapter: '0' => '13'"]
+            );
+
+            let manga_diff = diff(&[gate, akira], &[gate_after, blame]);
+            assert_eq!(
+                manga_diff.to_lines(),
+                vec![
+                    "- id=2 'Akira' https://example.com/akira/",
+                    "+ id=3 'Blame!' https://example.com/blame/",
+                    "~ id=1 'Gate'",
+                    "    chapter: '0' => '13'",
+                    "    tags: '' => 'isekai, seinen'",
+                ]
+            );
+        }
+    }
+}
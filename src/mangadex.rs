@@ -0,0 +1,143 @@
+// MangaDex has an official API (https://api.mangadex.org/docs/), so for series hosted there we ask it for
+// the latest chapter translated into our language rather than scraping the (JavaScript rendered) pages.
+// The MangaDex id (a UUID) is taken from the url ("https://mangadex.org/title/<id>/gate"), or from the
+// feed_url, so a series read elsewhere can still be checked via MangaDex with
+// `manga set-feed <id> https://mangadex.org/title/<id>`.
+pub mod mangadex {
+    use regex::Regex;
+    use serde::Deserialize;
+    use url::Url;
+
+    use crate::model_manga::model_manga::{Chapter, MangaModel};
+    use crate::update_checker::update_checker;
+
+    pub const K_DEFAULT_LANGUAGE: &str = "en";
+    const K_MANGADEX_API_URL: &str = "https://api.mangadex.org";
+    const K_MANGADEX_CHAPTER_URL: &str = "https://mangadex.org/chapter";
+    // a few, since chapters of different scanlation groups (and re-uploads) share the same number
+    const K_FEED_LIMIT: usize = 10;
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct ChapterAttributes {
+        chapter: Option<String>, // None for oneshots
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct ChapterData {
+        id: String,
+        #[serde(default)]
+        attributes: ChapterAttributes,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct ChapterFeedResponse {
+        #[serde(default)]
+        data: Vec<ChapterData>,
+    }
+
+    // "https://mangadex.org/title/<uuid>/slug" or "https://api.mangadex.org/manga/<uuid>/feed" => "<uuid>"
+    pub fn mangadex_id_from_url(url: &str) -> Option<String> {
+        let parsed_url = Url::parse(url).ok()?;
+        let host = parsed_url.host_str()?;
+        if host != "mangadex.org" && !host.ends_with(".mangadex.org") {
+            return None;
+        }
+        let uuid_regex = Regex::new(
+            r"(?i)/(?:title|manga)/([0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})",
+        )
+        .unwrap();
+        uuid_regex
+            .captures(parsed_url.path())
+            .map(|c| c[1].to_lowercase())
+    }
+
+    pub fn mangadex_id(manga: &MangaModel) -> Option<String> {
+        mangadex_id_from_url(manga.url()).or_else(|| {
+            manga
+                .feed_url()
+                .and_then(|feed_url| mangadex_id_from_url(&feed_url))
+        })
+    }
+
+    // returns (chapter, chapter_url) of the highest numbered chapter in the response
+    pub fn latest_chapter_from_feed_json(
+        json: &str,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        let response: ChapterFeedResponse = serde_json::from_str(json)?;
+        let latest = response
+            .data
+            .into_iter()
+            .filter_map(|data| {
+                let chapter = data.attributes.chapter?;
+                Chapter::parse(&chapter)?; // skip anything that is not a chapter number
+                Some((chapter, format!("{}/{}", K_MANGADEX_CHAPTER_URL, data.id)))
+            })
+            .fold(None, update_checker::keep_newest);
+        Ok(latest)
+    }
+
+    // language is ISO 639-1 as MangaDex uses it, i.e. "en", "ja", "pt-br"
+    pub fn latest_chapter(
+        mangadex_id: &str,
+        language: &str,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        let feed_url = format!(
+            "{}/manga/{}/feed?translatedLanguage[]={}&order[chapter]=desc&limit={}\
+                &contentRating[]=safe&contentRating[]=suggestive&contentRating[]=erotica&contentRating[]=pornographic",
+            K_MANGADEX_API_URL, mangadex_id, language, K_FEED_LIMIT
+        );
+        let json = update_checker::fetch_text(&feed_url)?;
+        latest_chapter_from_feed_json(&json)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_mangadex_id_from_url() {
+            assert_eq!(
+                mangadex_id_from_url(
+                    "https://mangadex.org/title/A1C7C817-4E59-43B7-9365-09675A149A6F/one-piece"
+                ),
+                Some("a1c7c817-4e59-43b7-9365-09675a149a6f".to_string())
+            );
+            assert_eq!(
+                mangadex_id_from_url(
+                    "https://api.mangadex.org/manga/a1c7c817-4e59-43b7-9365-09675a149a6f/feed"
+                ),
+                Some("a1c7c817-4e59-43b7-9365-09675a149a6f".to_string())
+            );
+            assert_eq!(
+                mangadex_id_from_url(
+                    "https://example.com/title/a1c7c817-4e59-43b7-9365-09675a149a6f/"
+                ),
+                None
+            );
+            assert_eq!(
+                mangadex_id_from_url("https://mangadex.org/titles/latest"),
+                None
+            );
+        }
+
+        #[test]
+        fn test_latest_chapter_from_feed_json() {
+            let json = r#"{"result":"ok","response":"collection","data":[
+                {"id":"11111111-0000-0000-0000-000000000000","type":"chapter","attributes":{"chapter":"1000","translatedLanguage":"en"}},
+                {"id":"22222222-0000-0000-0000-000000000000","type":"chapter","attributes":{"chapter":"1001.5","translatedLanguage":"en"}},
+                {"id":"33333333-0000-0000-0000-000000000000","type":"chapter","attributes":{"chapter":null,"translatedLanguage":"en"}}
+            ],"limit":10,"offset":0,"total":3}"#;
+            assert_eq!(
+                latest_chapter_from_feed_json(json).unwrap(),
+                Some((
+                    "1001.5".to_string(),
+                    "https://mangadex.org/chapter/22222222-0000-0000-0000-000000000000".to_string()
+                ))
+            );
+            assert_eq!(
+                latest_chapter_from_feed_json(r#"{"result":"ok","data":[]}"#).unwrap(),
+                None
+            );
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// The collection as a GitHub-flavored Markdown table (`manga export-markdown --out manga.md`), compact enough to
+// paste into a README, a gist or a forum post:
+//      | Title | Chapter | Last update | Tags |
+//      | --- | --- | --- | --- |
+//      | [ゲート](https://example.com/manga/gate/chapter-12) | 12 | 2023-09-06 | isekai, seinen |
+// Titles link to url_with_chapter (url when no chapter was read yet) so a reader lands where one left off.
+pub mod markdown_table {
+    use std::io::{self, Write};
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    const K_HEADER: &str = "| Title | Chapter | Last update | Tags |\n| --- | --- | --- | --- |";
+
+    // anything that would end the cell or the row early; "*_`" and such are left to render as they do
+    fn escape_cell(text: &str) -> String {
+        text.split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+            .replace('\\', "\\\\")
+            .replace('|', "\\|")
+    }
+
+    // [title](url), with the brackets of the title escaped and the url in <> when it has spaces or parentheses
+    pub fn markdown_link(title: &str, url: &str) -> String {
+        let text = escape_cell(title).replace('[', "\\[").replace(']', "\\]");
+        let destination = match url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+            true => format!("<{}>", url.replace(' ', "%20")),
+            false => url.to_string(),
+        };
+        format!("[{}]({})", text, destination.replace('|', "%7C"))
+    }
+
+    pub fn to_row(manga: &MangaModel) -> String {
+        let link = manga
+            .url_with_chapter()
+            .filter(|url_with_chapter| !url_with_chapter.is_empty())
+            .unwrap_or_else(|| manga.url().to_string());
+        // the date is enough here, the time of day is noise
+        let last_update = manga
+            .last_update()
+            .map(|last_update| {
+                last_update
+                    .chars()
+                    .take("YYYY-MM-DD".len())
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        let tags: Vec<&str> = manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        format!(
+            "| {} | {} | {} | {} |",
+            markdown_link(manga.title(), &link),
+            escape_cell(&manga.chapter().unwrap_or_default()),
+            escape_cell(&last_update),
+            escape_cell(&tags.join(", "))
+        )
+    }
+
+    // rows in the order given
+    pub fn write_table(output_writer: &mut dyn Write, mangas: &[MangaModel]) -> io::Result<()> {
+        writeln!(output_writer, "{}", K_HEADER)?;
+        for manga in mangas.iter() {
+            writeln!(output_writer, "{}", to_row(manga))?;
+        }
+        output_writer.flush()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_write_table() {
+            assert_eq!(
+                markdown_link("[Oshi] no Ko", "https://example.com/a (1)"),
+                "[\\[Oshi\\] no Ko](<https://example.com/a%20(1)>)"
+            );
+
+            let mut manga = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            manga.set_url_with_chapter(Some(
+                "https://example.com/manga/gate/chapter-12".to_string(),
+            ));
+            manga.set_chapter(Some("12".to_string()));
+            manga.set_last_update(Some("2023-09-06 22:57:22".to_string()));
+            manga.set_tags(vec![" isekai".to_string(), " seinen".to_string()]);
+            let mut table = Vec::new();
+            write_table(&mut table, &[manga]).unwrap();
+            assert_eq!(
+                String::from_utf8(table).unwrap(),
+                format!(
+                    "{}\n{}\n",
+                    K_HEADER,
+                    "| [ゲート](https://example.com/manga/gate/chapter-12) | 12 | 2023-09-06 | isekai, seinen |"
+                )
+            );
+        }
+    }
+}
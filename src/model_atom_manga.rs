@@ -0,0 +1,192 @@
+// Atom (RFC 4287) feed of recently changed manga rows, so that the collection
+// can be followed in any feed reader.  Write-only: we never read Atom back in.
+// Each entry is a single manga row, keyed by ID+chapter so that a feed reader
+// treats a chapter bump as a NEW item rather than silently updating the old one.
+pub mod model_atom_manga {
+    use std::io::Write;
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    const K_FEED_ID: &str = "urn:firefox-bookmark-to-sqlite3:manga-updates";
+    const K_GENERATOR: &str = "firefox_bookmark_to_csv";
+
+    // XML needs these 5 characters escaped, everything else (including UTF8 Japanese) is legal as-is
+    pub fn xml_escape(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    // Atom requires RFC3339 timestamps (i.e. "2023-09-06T13:57:22Z")
+    pub fn millis_to_rfc3339(millis: i64) -> String {
+        use chrono::TimeZone;
+        match chrono::Utc.timestamp_millis_opt(millis).single() {
+            Some(datetime) => datetime.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            None => "1970-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    // Entry ID has to be stable across re-generation of the feed, otherwise feed readers will
+    // mark everything as unread each time; chapter is part of the ID so that new chapters are new entries
+    fn entry_id(manga: &MangaModel) -> String {
+        format!(
+            "urn:manga:{}:chapter:{}",
+            manga.id(),
+            manga.chapter().unwrap_or_else(|| "0".to_string())
+        )
+    }
+
+    fn entry_title(manga: &MangaModel) -> String {
+        match manga.chapter() {
+            Some(chapter) => format!("{} - chapter {}", manga.title(), chapter),
+            None => manga.title().to_string(),
+        }
+    }
+
+    pub fn to_atom_entry(manga: &MangaModel) -> String {
+        let link = manga
+            .url_with_chapter()
+            .unwrap_or_else(|| manga.url().to_string());
+        let mut entry = String::new();
+        entry.push_str("  <entry>\n");
+        entry.push_str(&format!("    <id>{}</id>\n", xml_escape(&entry_id(manga))));
+        entry.push_str(&format!(
+            "    <title>{}</title>\n",
+            xml_escape(&entry_title(manga))
+        ));
+        entry.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            millis_to_rfc3339(manga.last_update_millis().unwrap_or(0))
+        ));
+        entry.push_str(&format!(
+            "    <link rel=\"alternate\" href=\"{}\"/>\n",
+            xml_escape(&link)
+        ));
+        for tag in manga.tags() {
+            entry.push_str(&format!(
+                "    <category term=\"{}\"/>\n",
+                xml_escape(tag.trim())
+            ));
+        }
+        let mut summary = String::new();
+        if let Some(romanized) = manga.title_romanized() {
+            summary.push_str(&romanized);
+        }
+        if let Some(notes) = manga.notes() {
+            if !summary.is_empty() {
+                summary.push_str(" / ");
+            }
+            summary.push_str(&notes);
+        }
+        if !summary.is_empty() {
+            entry.push_str(&format!(
+                "    <summary>{}</summary>\n",
+                xml_escape(&summary)
+            ));
+        }
+        entry.push_str("  </entry>\n");
+        entry
+    }
+
+    // mangas are expected to be pre-sorted (newest first) by the caller, we do not re-sort here
+    pub fn to_atom_feed(feed_title: &str, mangas: &[MangaModel]) -> String {
+        // feed-level <updated> is the newest entry, or now if there are no entries
+        let feed_updated_millis = mangas
+            .iter()
+            .filter_map(|manga| manga.last_update_millis())
+            .max()
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+
+        let mut feed = String::new();
+        feed.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        feed.push_str(&format!("  <id>{}</id>\n", K_FEED_ID));
+        feed.push_str(&format!("  <title>{}</title>\n", xml_escape(feed_title)));
+        feed.push_str(&format!(
+            "  <updated>{}</updated>\n",
+            millis_to_rfc3339(feed_updated_millis)
+        ));
+        feed.push_str(&format!("  <generator>{}</generator>\n", K_GENERATOR));
+        // Atom requires an author either on the feed or on every entry
+        feed.push_str("  <author><name>manga</name></author>\n");
+        for manga in mangas {
+            feed.push_str(&to_atom_entry(manga));
+        }
+        feed.push_str("</feed>\n");
+        feed
+    }
+
+    pub fn write_atom_feed(
+        output_writer: &mut dyn Write,
+        feed_title: &str,
+        mangas: &[MangaModel],
+    ) -> std::io::Result<()> {
+        output_writer.write_all(to_atom_feed(feed_title, mangas).as_bytes())?;
+        output_writer.flush()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn make_sample_row(id: i64, chapter: &str, millis: i64) -> MangaModel {
+            MangaModel::with_values(
+                id,
+                "ゆるキャン△ & <friends>".to_owned(),
+                Some("yuru kyan".to_owned()),
+                "https://example.com/manga/yurucamp/".to_owned(),
+                Some(format!(
+                    "https://example.com/manga/yurucamp-chapter-{}/",
+                    chapter
+                )),
+                Some(chapter.to_owned()),
+                None,
+                Some(millis),
+                None,
+                vec!["アニメ化".to_owned()],
+                None,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn test_xml_escape() {
+            assert_eq!(
+                xml_escape("a & b <c> \"d\" 'e'"),
+                "a &amp; b &lt;c&gt; &quot;d&quot; &apos;e&apos;"
+            );
+            assert_eq!(xml_escape("漫画"), "漫画");
+        }
+
+        #[test]
+        fn test_millis_to_rfc3339() {
+            assert_eq!(millis_to_rfc3339(0), "1970-01-01T00:00:00Z");
+            assert_eq!(millis_to_rfc3339(1_694_008_642_000), "2023-09-06T13:57:22Z");
+        }
+
+        #[test]
+        fn test_to_atom_feed() {
+            let mangas = vec![
+                make_sample_row(2, "11", 1_694_008_642_000),
+                make_sample_row(1, "10", 1_600_000_000_000),
+            ];
+            let feed = to_atom_feed("My manga", &mangas);
+            println!("{}", feed);
+            assert!(feed.starts_with("<?xml"));
+            assert_eq!(feed.matches("<entry>").count(), 2);
+            assert!(feed.contains("<id>urn:manga:2:chapter:11</id>"));
+            assert!(feed.contains("ゆるキャン△ &amp; &lt;friends&gt; - chapter 11"));
+            // feed-level updated is the newest entry
+            assert!(feed.contains("  <updated>2023-09-06T13:57:22Z</updated>"));
+        }
+    }
+}
@@ -113,14 +113,8 @@ pub mod model_csv_manga {
 
     #[allow(dead_code)]
     impl CsvMangaModel {
-        // only exposing this function so that use-depencies of kakasi will be limited to this module only
-        // but mainly, also want to preserve at least the UTF8 comma ("、") in the title
-        fn romanized(title: &str) -> String {
-            fix_comma_in_string(kakasi::convert(title).romaji.as_str())
-        }
-
         pub fn get_last_update(&self) -> i64 {
-            str_to_epoch_micros(self.last_update().clone().to_string())
+            str_to_epoch_micros(self.last_update().clone().to_string()).unwrap_or(0)
         }
         fn strip_chapter_from_url(url_with_chapters: String) -> (String, String) {
             Utils::strip_chapter_from_url(&url_with_chapters)
@@ -128,8 +122,9 @@ pub mod model_csv_manga {
         pub fn new(model: &MangaModel) -> Self {
             let bookmark_last_update_epoch_micros = match model.last_update() {
                 Some(ref s) => {
-                    // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds
-                    str_to_epoch_micros(s.to_string().clone())
+                    // convert the last_update i64 to datetime - last_update is encoded as unix epoch time in microseconds;
+                    // one that does not parse is treated as missing
+                    str_to_epoch_micros(s.to_string().clone()).unwrap_or(0)
                 }
                 None => 0,
             };
@@ -141,9 +136,10 @@ pub mod model_csv_manga {
 
             CsvMangaModel {
                 title: fix_comma_in_string(model.title()),
+                // romanized the same way as MangaModel does when the model has none (i.e. built by hand)
                 title_romanized: match model.title_romanized() {
                     Some(ref s) => Some(fix_comma_in_string(s.as_str())),
-                    None => None,
+                    None => MangaModel::romanize_title(model.title()),
                 },
                 url: fix_comma_in_string(&model.url()),
                 url_with_chapter: match model.url_with_chapter() {
@@ -160,7 +156,7 @@ pub mod model_csv_manga {
                     None => None,
                 },
                 tags: match model.tags().len() > 0 {
-                    true => Some(fix_comma_in_string(model.tags().join(";").as_str())), // NOTE: Using ';' instead of ',' for tags
+                    true => Some(fix_comma_in_string(MangaModel::tags_to_csv(&model.tags()).as_str())), // NOTE: Using ';' instead of ',' for tags
                     false => None,
                 },
                 my_anime_list: match model.my_anime_list() {
@@ -274,13 +270,7 @@ pub mod model_csv_manga {
                 Ok(mut model) => {
                     model.set_last_update(Some(csv_model_des.last_update().clone().to_string()));
                     model.set_notes(Some(csv_model_des.notes().clone().to_string()));
-                    model.set_tags(
-                        csv_model_des
-                            .tags()
-                            .split(';')
-                            .map(|s| s.to_string())
-                            .collect(),
-                    );
+                    model.set_tags(MangaModel::csv_to_tags(csv_model_des.tags()));
 
                     //let record = model.build_record();
                     Ok(CsvMangaModel::new(&model))
@@ -377,7 +367,7 @@ pub mod model_csv_manga {
         //        None =>
         //        // String::from(""),    // empty string
         //        {
-        //            self.set_romanized_title ( Some(CsvMangaModel::romanized(&self.title)));
+        //            self.set_romanized_title ( MangaModel::romanize_title(&self.title));
         //            &self.romanized_title().as_ref().unwrap()
         //        } // alternative
         //    }
@@ -458,6 +448,181 @@ pub mod model_csv_manga {
         tags: String,
     }
 
+    // which columns a CSV file has, in which order, as the versions of this tool wrote them over time
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CsvSchema {
+        V1, // CsvMangaModelV1: Title, URL_with_Chapters, Chapter, last_update, Notes, Tags (i.e. samples/v1.csv)
+        V2, // CsvMangaModelV2: URL and Romanized_Title, then the columns of V1
+        Record, // what write_csv() writes: the columns of V1, then URL and Romanized_Title (see build_record())
+        Model, // CsvMangaModel: title, title_romanized, url, url_with_chapter, chapter, last_update, notes, tags,
+               // my_anime_list (i.e. samples/v2.csv)
+    }
+
+    // ' "title"' as older versions wrote it (a space after the comma keeps the quotes in the field) is 'title'
+    fn trim_field(field: &str) -> String {
+        field.trim().trim_matches('"').trim().to_string()
+    }
+
+    // positions of the columns a MangaModel is made of
+    struct CsvColumns {
+        title: usize,
+        possible_url: Option<usize>, // V1 has only the url with the chapter
+        url_with_chapter: usize,
+        last_update: usize,
+        notes: usize,
+        tags: usize,
+    }
+
+    impl CsvSchema {
+        const K_ALL: [CsvSchema; 4] = [
+            CsvSchema::V1,
+            CsvSchema::V2,
+            CsvSchema::Record,
+            CsvSchema::Model,
+        ];
+
+        // the header row of each, as the serde names of its struct (and build_record_header() for Record) have it
+        fn header(self) -> &'static [&'static str] {
+            match self {
+                CsvSchema::V1 => &[
+                    "Title",
+                    "URL_with_Chapters",
+                    "Chapter",
+                    "last_update",
+                    "Notes",
+                    "Tags",
+                ],
+                CsvSchema::V2 => &[
+                    "URL",
+                    "Romanized_Title",
+                    "Title",
+                    "URL_with_Chapters",
+                    "Chapter",
+                    "last_update",
+                    "Notes",
+                    "Tags",
+                ],
+                CsvSchema::Record => &[
+                    "Title",
+                    "URL_with_Chapters",
+                    "Chapter",
+                    "last_update",
+                    "Notes",
+                    "Tags",
+                    "Possible_Romanized_Title",
+                    "Romanized_Title",
+                ],
+                CsvSchema::Model => &[
+                    "title",
+                    "title_romanized",
+                    "url",
+                    "url_with_chapter",
+                    "chapter",
+                    "last_update",
+                    "notes",
+                    "tags",
+                    "my_anime_list",
+                ],
+            }
+        }
+
+        fn columns(self) -> CsvColumns {
+            match self {
+                CsvSchema::V1 => CsvColumns {
+                    title: 0,
+                    possible_url: None,
+                    url_with_chapter: 1,
+                    last_update: 3,
+                    notes: 4,
+                    tags: 5,
+                },
+                CsvSchema::V2 => CsvColumns {
+                    title: 2,
+                    possible_url: Some(0),
+                    url_with_chapter: 3,
+                    last_update: 5,
+                    notes: 6,
+                    tags: 7,
+                },
+                CsvSchema::Record => CsvColumns {
+                    title: 0,
+                    possible_url: Some(6),
+                    url_with_chapter: 1,
+                    last_update: 3,
+                    notes: 4,
+                    tags: 5,
+                },
+                CsvSchema::Model => CsvColumns {
+                    title: 0,
+                    possible_url: Some(2),
+                    url_with_chapter: 3,
+                    last_update: 5,
+                    notes: 6,
+                    tags: 7,
+                },
+            }
+        }
+
+        // schema of a file from its first record, and whether that record is the header row rather than a row; a
+        // header is only one whose names are those of a schema (ignoring case), any other record is a row, matched
+        // by its number of columns, V2 and Record (8 each) told apart by which one starts with the url
+        pub fn detect(first_record: &csv::StringRecord) -> Result<(CsvSchema, bool), String> {
+            let fields: Vec<String> = first_record.iter().map(trim_field).collect();
+            let possible_header = Self::K_ALL.iter().find(|schema| {
+                schema.header().len() == fields.len()
+                    && schema
+                        .header()
+                        .iter()
+                        .zip(fields.iter())
+                        .all(|(name, field)| name.eq_ignore_ascii_case(field))
+            });
+            if let Some(schema) = possible_header {
+                return Ok((*schema, true));
+            }
+            let schema = match fields.len() {
+                6 => CsvSchema::V1,
+                8 if fields[0].contains("://") => CsvSchema::V2,
+                8 => CsvSchema::Record,
+                9 => CsvSchema::Model,
+                column_count => {
+                    return Err(format!(
+                        "unknown CSV layout of {} columns: '{}'",
+                        column_count,
+                        fields.join(",")
+                    ))
+                }
+            };
+            Ok((schema, false))
+        }
+
+        // the MangaModel of a row of this schema; the id is the checksum of the url with the chapter, as always
+        pub fn to_manga(
+            self,
+            record: &csv::StringRecord,
+        ) -> Result<MangaModel, Box<dyn std::error::Error>> {
+            let columns = self.columns();
+            let field = |i: usize| trim_field(record.get(i).unwrap_or_default());
+            let url_with_chapter = match (field(columns.url_with_chapter), columns.possible_url) {
+                (url_with_chapter, Some(url)) if url_with_chapter.is_empty() => field(url),
+                (url_with_chapter, _) => url_with_chapter,
+            };
+            let mut manga = MangaModel::new_from_required_elements(
+                &field(columns.title),
+                &url_with_chapter,
+                model_manga::CASTAGNOLI.checksum(url_with_chapter.as_bytes()),
+            )?;
+            manga.set_last_update(Some(field(columns.last_update)));
+            manga.set_notes(Some(field(columns.notes)));
+            manga.set_tags(
+                MangaModel::csv_to_tags(&field(columns.tags))
+                    .iter()
+                    .map(|s| s.trim().to_string())
+                    .collect::<Vec<String>>(),
+            );
+            Ok(manga)
+        }
+    }
+
     pub struct Utils {
         //csv_writer: Writer<Box<dyn Write + 'static>>, // mutable reference to a trait object
         csv_writer: Writer<Box<dyn Write>>, // mutable reference to a trait object
@@ -465,6 +630,9 @@ pub mod model_csv_manga {
         // iterator for reading each CSV rows that is mutable
         //csv_reader: csv::Reader<Box<dyn std::io::Read + 'static>>,
         csv_reader: csv::Reader<Box<dyn std::io::Read>>,
+
+        // of the file being read, None until its first record is (see CsvSchema::detect())
+        possible_schema: Option<CsvSchema>,
     }
     impl Drop for Utils {
         fn drop(&mut self) {
@@ -494,6 +662,7 @@ pub mod model_csv_manga {
                     .escape(Some(b'\\')) // rather than ("") ours use (\") to represent embedded quotes
                     .comment(Some(b'#')) // allow # to be on first column to indicate comments
                     .from_reader(input_reader),
+                possible_schema: None,
             }
         }
 
@@ -505,59 +674,66 @@ pub mod model_csv_manga {
                 .escape(Some(b'\\')) // rather than ("") ours use (\") to represent embedded quotes
                 .comment(Some(b'#')) // allow # to be on first column to indicate comments
                 .from_reader(input_reader);
+            self.possible_schema = None;
         }
 
-        // iterator rdr to next row for deserializing
+        // schema of the file being read, once its first record is
+        pub fn schema(&self) -> Option<CsvSchema> {
+            self.possible_schema
+        }
+
+        // iterator rdr to next row for deserializing (Utils also writes, so it is not an Iterator itself); the
+        // columns are those of the schema of the file, detected on its first record (skipped when it is the header)
+        #[allow(clippy::should_implement_trait)]
         pub fn next(&mut self) -> Option<Result<MangaModel, Error>> {
-            let result = self.csv_reader.deserialize().next(); // possibly use csv_reader.records() instead?
-                                                               // return as MangaModel IF we've not reached the end of stream (None if end of stream)
-            match result {
-                Some(Ok(deserialized_record)) => {
-                    // by explicitly casting to CsvMangaModel, we can get the deserialized record (very weird approach)
-                    let csv_manga_model_record: CsvMangaModel = deserialized_record;
-                    #[cfg(debug_assertions)]
-                    {
-                        println!(">> csv::next: CsvMangaRecord({:?})", csv_manga_model_record);
+            let mut record = csv::StringRecord::new();
+            let schema = loop {
+                match self.csv_reader.read_record(&mut record) {
+                    Ok(true) => {}
+                    Ok(false) => return None, // end of stream
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return None;
                     }
-
-                    // push a copy
-                    match MangaModel::new_from_required_elements(
-                        csv_manga_model_record.title().clone(),
-                        csv_manga_model_record.url_with_chapter().clone(),
-                        model_manga::CASTAGNOLI
-                            .checksum(csv_manga_model_record.url_with_chapter().clone().as_bytes()),
-                    ) {
-                        Ok(mut m) => {
-                            m.set_last_update(Some(
-                                csv_manga_model_record.last_update().clone().to_string(),
-                            ));
-                            m.set_notes(Some(csv_manga_model_record.notes().clone().to_string()));
-                            m.set_tags(
-                                csv_manga_model_record
-                                    .tags()
-                                    .clone()
-                                    .split(';')
-                                    .map(|s| s.trim().to_string())
-                                    .collect::<Vec<String>>(),
-                            );
-                            //m.my_anime_list = csv_manga_model_record.m
+                }
+                match self.possible_schema {
+                    Some(schema) => break schema,
+                    None => match CsvSchema::detect(&record) {
+                        Ok((schema, is_header)) => {
                             #[cfg(debug_assertions)]
                             {
-                                println!(">>> csv::next: MangaModel({:?})", m);
+                                println!(
+                                    ">> csv::next: {:?} schema (header: {})",
+                                    schema, is_header
+                                );
+                            }
+                            self.possible_schema = Some(schema);
+                            if !is_header {
+                                break schema;
                             }
-                            return Some(Ok(m)); // brute-force bail out immediately here
                         }
                         Err(e) => {
-                            println!("Error:read_next(): could not create MangaModel from CSV record - {}", e);
-                            return None; // brute-force bail out immediately here
+                            return Some(Err(Error::from(std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                e,
+                            ))));
                         }
-                    };
+                    },
                 }
-                Some(Err(e)) => {
-                    eprintln!("Error: {}", e);
-                    None
+            };
+            #[cfg(debug_assertions)]
+            {
+                println!(">> csv::next: {:?}", record);
+            }
+            match schema.to_manga(&record) {
+                Ok(m) => Some(Ok(m)),
+                Err(e) => {
+                    println!(
+                        "Error:read_next(): could not create MangaModel from CSV record - {}",
+                        e
+                    );
+                    None // brute-force bail out immediately here
                 }
-                None => None,
             }
         }
 
@@ -700,8 +876,8 @@ pub mod model_csv_manga {
 
         // chose this title for a critical reason that if withinside quotes, there is a UTF8 comma ("、"), which is a problem for CSV if it was converted to ","
         const K_MANGA_TITLE: &str = "ゲート―自衛隊彼の地にて、斯く戦えり";
-        // notice that libkakasi converts "ゲート" to "geeto" and knows the difference between the dash in "ゲート" and the dash in "―" (which is a UTF8 dash), sadly it convers the UTF8 comma ("、") to a regular comma (","), in which we force it
-        const K_EXPECTED_ROMANIZED_TITLE: &str = "geeto ― jieitai kano chi nite、 kaku tatakae ri"; // NOTE: the UTF8 comma ("、") is converted to a regular comma (",")
+        // notice that libkakasi converts "ゲート" to "geeto" and knows the difference between the dash in "ゲート" and the dash in "―" (which is a UTF8 dash), sadly it convers the UTF8 comma ("、") to a regular comma (","), which MangaModel::romanize_title() turns back
+        const K_EXPECTED_ROMANIZED_TITLE: &str = "geeto ― jieitai kano chi nite、 kaku tatakae ri"; // NOTE: the UTF8 comma ("、"), not a regular comma (",")
         const K_MANGA_URL: &str = "https://example.com/manga/gate/";
         const K_MANGA_URL_WITH_CHAPTERS: &str = "https://example.com/manga/gate-chapter-10/";
         const K_MANGA_CHAPTER: &str = "10";
@@ -781,14 +957,14 @@ pub mod model_csv_manga {
                 String::from(K_MANGA_TAGS_SEMICOLON_SEPARATED).as_str(),
             ));
             assert!(manga_model.title_romanized().clone().is_some());
-            assert_ne!(
-                manga_model.title_romanized().clone().unwrap(), // because regular version mismatches on UTF8 comma ("、"), it's not the same as the expected
+            assert_eq!(
+                manga_model.title_romanized().clone().unwrap(), // UTF8 comma ("、") in both the regular and the CSV version
                 K_EXPECTED_ROMANIZED_TITLE
             );
 
             let csv_manga_model = CsvMangaModel::new(&manga_model.clone());
             assert_eq!(
-                csv_manga_model.title_romanized().clone(), // Unlike regular version, CSV verion should have UTF8 comma ("、") intact
+                csv_manga_model.title_romanized().clone(),
                 K_EXPECTED_ROMANIZED_TITLE
             );
             (manga_model, csv_manga_model)
@@ -846,6 +1022,139 @@ THE,END
             }
         }
 
+        #[test]
+        fn test_csv_schema_detection() {
+            use super::{CsvSchema, Utils};
+            let read = |csv: &str| {
+                let mut util = Utils::new(
+                    Some(Box::new(std::io::sink())),
+                    Box::new(std::io::Cursor::new(csv.to_string())),
+                );
+                let mut mangas = Vec::new();
+                while let Some(Ok(manga)) = util.next() {
+                    mangas.push(manga);
+                }
+                (util.schema(), mangas)
+            };
+            let row_v1 = format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"-\",\"isekai\"",
+                K_MANGA_TITLE, K_MANGA_URL_WITH_CHAPTERS, K_MANGA_CHAPTER, K_MANGA_LAST_UPDATE
+            );
+            let row_v2 = format!("\"{}\",\"geeto\",{}", K_MANGA_URL, row_v1);
+            let row_record = format!("{},\"{}\",\"geeto\"", row_v1, K_MANGA_URL);
+            let row_model = format!(
+                "\"{}\",\"geeto\",\"{}\",\"{}\",\"{}\",\"{}\",\"-\",\"isekai\",\"\"",
+                K_MANGA_TITLE,
+                K_MANGA_URL,
+                K_MANGA_URL_WITH_CHAPTERS,
+                K_MANGA_CHAPTER,
+                K_MANGA_LAST_UPDATE
+            );
+            let header = |schema: CsvSchema| schema.header().join(",");
+            for (csv, expected_schema) in [
+                (row_v1.clone(), CsvSchema::V1),
+                (
+                    format!("{}\n{}", header(CsvSchema::V1), row_v1),
+                    CsvSchema::V1,
+                ),
+                (row_v2.clone(), CsvSchema::V2),
+                (
+                    format!("{}\n{}", header(CsvSchema::V2).to_lowercase(), row_v2),
+                    CsvSchema::V2,
+                ),
+                (row_record.clone(), CsvSchema::Record),
+                (
+                    format!("{}\n{}", header(CsvSchema::Record), row_record),
+                    CsvSchema::Record,
+                ),
+                (row_model.clone(), CsvSchema::Model),
+                (
+                    format!("{}\n{}", header(CsvSchema::Model), row_model),
+                    CsvSchema::Model,
+                ),
+            ] {
+                let (schema, mangas) = read(&csv);
+                assert_eq!(schema, Some(expected_schema), "{}", csv);
+                assert_eq!(mangas.len(), 1, "{}", csv);
+                assert_eq!(mangas[0].title(), K_MANGA_TITLE);
+                assert_eq!(
+                    mangas[0].url_with_chapter(),
+                    Some(K_MANGA_URL_WITH_CHAPTERS.to_string())
+                );
+                assert_eq!(
+                    mangas[0].last_update(),
+                    Some(K_MANGA_LAST_UPDATE.to_string())
+                );
+                assert_eq!(mangas[0].tags(), ["isekai".to_string()]);
+            }
+            // what write_csv() writes reads back
+            let csv_full_paths = "samples/test_csv_schema_detection.csv";
+            {
+                let mut manga = MangaModel::new_from_required_elements(
+                    K_MANGA_TITLE,
+                    K_MANGA_URL_WITH_CHAPTERS,
+                    1,
+                )
+                .unwrap();
+                manga.set_last_update(Some(K_MANGA_LAST_UPDATE.to_string()));
+                let mut util = Utils::new(
+                    Some(Box::new(std::fs::File::create(csv_full_paths).unwrap())),
+                    Box::new(std::io::empty()),
+                );
+                util.write_csv_header().unwrap();
+                util.write_csv(&vec![manga]).unwrap();
+            }
+            let (schema, mangas) = read(&std::fs::read_to_string(csv_full_paths).unwrap());
+            std::fs::remove_file(csv_full_paths).unwrap();
+            assert_eq!(schema, Some(CsvSchema::Record));
+            assert_eq!(
+                mangas[0].url_with_chapter(),
+                Some(K_MANGA_URL_WITH_CHAPTERS.to_string())
+            );
+            // the samples of each version
+            assert_eq!(
+                read(&std::fs::read_to_string("samples/v1.csv").unwrap()).0,
+                Some(CsvSchema::V1)
+            );
+            assert_eq!(
+                read(&std::fs::read_to_string("samples/v2.csv").unwrap()).0,
+                Some(CsvSchema::Model)
+            );
+            assert!(read("a,b,c\n").1.is_empty());
+            // a row without a url scheme is still a row, not a header
+            let row_of = |fields: &[&str]| csv::StringRecord::from(fields.to_vec());
+            let schemeless_v1 = [
+                K_MANGA_TITLE,
+                "example.com/manga/gate/chapter-1",
+                "1",
+                K_MANGA_LAST_UPDATE,
+                "-",
+                "isekai",
+            ];
+            assert_eq!(
+                CsvSchema::detect(&row_of(&schemeless_v1)),
+                Ok((CsvSchema::V1, false))
+            );
+            let schemeless_record = [
+                K_MANGA_TITLE,
+                "example.com/manga/gate/chapter-1",
+                "1",
+                K_MANGA_LAST_UPDATE,
+                "-",
+                "isekai",
+                "example.com/manga/gate/",
+                "geeto",
+            ];
+            assert_eq!(
+                CsvSchema::detect(&row_of(&schemeless_record)),
+                Ok((CsvSchema::Record, false))
+            );
+            assert_eq!(
+                CsvSchema::detect(&row_of(CsvSchema::V1.header())),
+                Ok((CsvSchema::V1, true))
+            );
+        }
+
         #[test]
         fn test_title() {
             let (_manga, csv_manga) = make_default_model();
@@ -854,7 +1163,7 @@ THE,END
 
         #[test]
         fn test_romanized_title() {
-            let (_manga, csv_manga) = make_default_model();
+            let (manga, csv_manga) = make_default_model();
             assert_eq!(
                 csv_manga.clone().title_romanized(),
                 K_EXPECTED_ROMANIZED_TITLE
@@ -863,6 +1172,18 @@ THE,END
                 csv_manga.clone().title_romanized(),
                 K_EXPECTED_ROMANIZED_TITLE
             );
+
+            // a model without a romanized title is romanized the same way when exported
+            let mut unromanized = manga.clone();
+            unromanized.set_title_romanized(None);
+            assert_eq!(
+                CsvMangaModel::new(&unromanized).title_romanized(),
+                manga.title_romanized().unwrap()
+            );
+            assert_eq!(MangaModel::romanize_title("Gate"), None);
+            assert!(!MangaModel::romanize_title(K_MANGA_TITLE)
+                .unwrap()
+                .contains(','));
         }
 
         // test serialization to CSV
@@ -0,0 +1,192 @@
+// data model (schema) for json serde of the Chrome/Chromium `Bookmarks` file (i.e.
+// ~/.config/chromium/Default/Bookmarks), which Chrome rewrites as it goes, so a copy of it is as good as a backup:
+//      { "checksum": "..", "version": 1, "roots": {
+//          "bookmark_bar": { "type": "folder", "name": "Bookmarks bar", "children": [
+//              { "type": "url", "name": "Gate", "url": "https://example.com/gate-chapter-12/",
+//                "date_added": "13338482242000000", "guid": "..", "id": "7" } ], .. },
+//          "other": { .. }, "synced": { .. } } }
+// Times are strings of microseconds since 1601-01-01 (WebKit/Windows epoch), not since 1970 as Firefox has them.
+pub mod model_json_chrome_bookmarks {
+    use serde::{Deserialize, Serialize};
+    use std::fmt::{Debug, Display};
+
+    // microseconds between 1601-01-01 and 1970-01-01
+    const K_WEBKIT_EPOCH_OFFSET_MICROS: i64 = 11_644_473_600_000_000;
+
+    // 0 (unix epoch) when the time is missing or not a number, like "0" of Chrome itself
+    pub fn webkit_to_unix_micros(webkit_micros: &str) -> i64 {
+        match webkit_micros.trim().parse::<i64>() {
+            Ok(micros) if micros > K_WEBKIT_EPOCH_OFFSET_MICROS => {
+                micros - K_WEBKIT_EPOCH_OFFSET_MICROS
+            }
+            _ => 0,
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct ChromeBookmarks {
+        #[serde(rename = "roots")]
+        roots: ChromeRoots,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct ChromeRoots {
+        #[serde(rename = "bookmark_bar")]
+        bookmark_bar: ChromeNode,
+
+        #[serde(rename = "other", default)]
+        possible_other: Option<ChromeNode>,
+
+        #[serde(rename = "synced", default)]
+        possible_synced: Option<ChromeNode>, // "Mobile bookmarks"
+    }
+
+    impl ChromeBookmarks {
+        // bookmarks bar first, then other and mobile bookmarks, as Chrome shows them
+        pub fn roots(&self) -> Vec<&ChromeNode> {
+            let mut roots = vec![&self.roots.bookmark_bar];
+            roots.extend(self.roots.possible_other.iter());
+            roots.extend(self.roots.possible_synced.iter());
+            roots
+        }
+
+        // every bookmark (not folders) with the path of its folder, i.e. "Bookmarks bar/Manga", depth first
+        pub fn bookmarks(&self) -> Vec<(String, &ChromeNode)> {
+            fn traverse_children<'a>(
+                children: &'a [ChromeNode],
+                folder_path: &str,
+                bookmarks: &mut Vec<(String, &'a ChromeNode)>,
+            ) {
+                for child in children {
+                    if child.is_bookmark() {
+                        bookmarks.push((folder_path.to_string(), child));
+                    } else if let Some(children) = &child.possible_children {
+                        traverse_children(
+                            children,
+                            &format!("{}/{}", folder_path, child.name),
+                            bookmarks,
+                        );
+                    }
+                }
+            }
+            let mut bookmarks = Vec::new();
+            for root in self.roots() {
+                if let Some(children) = &root.possible_children {
+                    traverse_children(children, &root.name, &mut bookmarks);
+                }
+            }
+            bookmarks
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct ChromeNode {
+        #[serde(rename = "guid", default)]
+        guid: String,
+
+        #[serde(rename = "name", default)]
+        name: String,
+
+        #[serde(rename = "type")]
+        node_type: Type,
+
+        #[serde(rename = "date_added", default)]
+        date_added: String,
+
+        #[serde(rename = "date_modified", default)]
+        possible_date_modified: Option<String>, // folders only
+
+        #[serde(rename = "url")]
+        possible_url: Option<String>,
+
+        #[serde(rename = "children")]
+        possible_children: Option<Vec<ChromeNode>>,
+    }
+
+    impl ChromeNode {
+        pub fn is_bookmark(&self) -> bool {
+            self.node_type == Type::Url
+        }
+
+        pub fn name(&self) -> &String {
+            &self.name
+        }
+
+        pub fn guid(&self) -> &String {
+            &self.guid
+        }
+
+        // empty if it is not a bookmark
+        pub fn url(&self) -> String {
+            match (&self.node_type, &self.possible_url) {
+                (Type::Url, Some(url)) => url.clone(),
+                _ => String::from(""),
+            }
+        }
+
+        // unix epoch time in microseconds, same as dateAdded of Firefox bookmarks
+        pub fn date_added_micros(&self) -> i64 {
+            webkit_to_unix_micros(&self.date_added)
+        }
+
+        // bookmarks have no date_modified, they are as old as when they were added
+        pub fn last_modified_micros(&self) -> i64 {
+            match &self.possible_date_modified {
+                Some(date_modified) => webkit_to_unix_micros(date_modified),
+                None => self.date_added_micros(),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+    pub enum Type {
+        #[serde(rename = "url")]
+        Url,
+
+        #[serde(rename = "folder")]
+        Folder,
+    }
+
+    impl Display for Type {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Type::Url => write!(f, "url"),
+                Type::Folder => write!(f, "folder"),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_deserialize_chrome_bookmarks() {
+            let json_data = r#"
+{ "checksum": "6d1cb6d5a5b5a0b5c9c0e0b2f0d2a1c4", "version": 1, "roots": {
+  "bookmark_bar": { "children": [
+    { "children": [
+      { "date_added": "13338482242000000", "guid": "0b0c7c9a-5f0e-4f4e-9d8e-1a2b3c4d5e6f", "id": "7", "name": "ゲート", "type": "url", "url": "https://example.com/gate-chapter-12/" } ],
+      "date_added": "13339093400000000", "date_modified": "13338482242000000", "guid": "6e3f0f3a-1c1e-4f44-8a55-0d7d7f0e9c21", "id": "6", "name": "Manga", "type": "folder" },
+    { "date_added": "13339093500000000", "guid": "1f2e3d4c-5b6a-4978-8a9b-0c1d2e3f4a5b", "id": "8", "name": "Downloads", "type": "url", "url": "chrome://downloads/" } ],
+    "date_added": "13339093000000000", "date_modified": "0", "guid": "0bc5d13f-2cba-5d74-951f-3f233fe6c908", "id": "1", "name": "Bookmarks bar", "type": "folder" },
+  "other": { "children": [], "date_added": "13339093000000000", "guid": "82b081ec-3dd3-529c-8475-ab6c344590dd", "id": "2", "name": "Other bookmarks", "type": "folder" },
+  "synced": { "children": [], "date_added": "13339093000000000", "guid": "4cf2e351-0e85-532b-bb37-df045d8f8d0f", "id": "3", "name": "Mobile bookmarks", "type": "folder" } } }
+        "#;
+            let bookmarks: ChromeBookmarks = serde_json::from_str(json_data).unwrap();
+            assert_eq!(bookmarks.roots().len(), 3);
+            let found = bookmarks.bookmarks();
+            assert_eq!(found.len(), 2);
+            let (folder_path, gate) = &found[0];
+            assert_eq!(folder_path, "Bookmarks bar/Manga");
+            assert_eq!(gate.name(), "ゲート");
+            assert_eq!(gate.url(), "https://example.com/gate-chapter-12/");
+            // 2023-09-06T13:57:22Z
+            assert_eq!(gate.date_added_micros(), 1694008642000000);
+            assert_eq!(gate.last_modified_micros(), 1694008642000000);
+            assert_eq!(found[1].0, "Bookmarks bar");
+            assert_eq!(webkit_to_unix_micros("0"), 0);
+            assert_eq!(webkit_to_unix_micros(""), 0);
+        }
+    }
+}
@@ -2,8 +2,10 @@
 // data model (schema) for json serde
 pub mod model_json_mozilla_bookmarks {
     use crate::model_manga::{self, model_manga::MangaModel};
+    use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
     use serde::{Deserialize, Serialize};
     use std::fmt::{Debug, Display};
+    use std::io::Read;
 
     /// Generated by https://quicktype.io
 
@@ -190,6 +192,11 @@ pub mod model_json_mozilla_bookmarks {
             &self.title
         }
 
+        // stable across renames and moves of the bookmark, unlike title and uri
+        pub fn guid(&self) -> &String {
+            &self.guid
+        }
+
         pub fn uri(&self) -> String {
             // return asn empty string if it is not a bookmark and/or is None
             if self.child_type != Type::TextXMozPlace {
@@ -205,6 +212,10 @@ pub mod model_json_mozilla_bookmarks {
             &self.last_modified
         }
 
+        pub fn date_added(&self) -> &i64 {
+            &self.date_added
+        }
+
         pub fn possible_children(&self) -> &Option<Vec<BookmarkNodes>> {
             &self.possible_children
         }
@@ -262,6 +273,121 @@ pub mod model_json_mozilla_bookmarks {
         }
     }
 
+    // Each bookmark ("text/x-moz-place") of the JSON, as it is read, along with the folder it is in (i.e.
+    // "toolbar/Manga", same as service::bookmark_origins()); containers and separators are walked through but
+    // never built, so memory stays at one bookmark no matter how large the export is.  The folder of a container
+    // is its title as read so far, which Firefox always writes before its children.  An Err of on_bookmark stops
+    // the walk, and comes back as the error of the JSON at that point
+    pub fn for_each_bookmark<R, F>(reader: R, on_bookmark: &mut F) -> Result<(), serde_json::Error>
+    where
+        R: Read,
+        F: FnMut(BookmarkNodes, &str) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        NodeSeed { depth: 0, folder_path: "", on_bookmark }.deserialize(&mut deserializer)?;
+        deserializer.end()
+    }
+
+    struct NodeSeed<'a, F> {
+        depth: usize, // 0 is the root, which is not a folder of its bookmarks
+        folder_path: &'a str,
+        on_bookmark: &'a mut F,
+    }
+
+    struct ChildrenSeed<'a, F> {
+        depth: usize,
+        folder_path: &'a str,
+        on_bookmark: &'a mut F,
+    }
+
+    impl<'de, F> DeserializeSeed<'de> for NodeSeed<'_, F>
+    where
+        F: FnMut(BookmarkNodes, &str) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        type Value = ();
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            deserializer.deserialize_map(self)
+        }
+    }
+
+    impl<'de, F> Visitor<'de> for NodeSeed<'_, F>
+    where
+        F: FnMut(BookmarkNodes, &str) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        type Value = ();
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a Firefox bookmark node")
+        }
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            let mut node = BookmarkNodes::new(String::new(), String::new(), 0, 0, 0, 0, 0, Type::TextXMozPlaceContainer, None, None, None);
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "guid" => node.guid = map.next_value()?,
+                    "title" => node.title = map.next_value()?,
+                    "index" => node.index = map.next_value()?,
+                    "dateAdded" => node.date_added = map.next_value()?,
+                    "lastModified" => node.last_modified = map.next_value()?,
+                    "id" => node.id = map.next_value()?,
+                    "typeCode" => node.type_code = map.next_value()?,
+                    "type" => node.child_type = map.next_value()?,
+                    "root" => node.possible_root = map.next_value()?,
+                    "uri" => node.possible_uri = map.next_value()?,
+                    "children" => {
+                        let children_path = match (self.depth, self.folder_path.is_empty()) {
+                            (0, _) => String::new(),
+                            (_, true) => node.title.clone(),
+                            (_, false) => format!("{}/{}", self.folder_path, node.title),
+                        };
+                        map.next_value_seed(ChildrenSeed {
+                            depth: self.depth + 1,
+                            folder_path: &children_path,
+                            on_bookmark: &mut *self.on_bookmark,
+                        })?;
+                    }
+                    _ => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+            if node.is_bookmark() {
+                (self.on_bookmark)(node, self.folder_path).map_err(de::Error::custom)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<'de, F> DeserializeSeed<'de> for ChildrenSeed<'_, F>
+    where
+        F: FnMut(BookmarkNodes, &str) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        type Value = ();
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de, F> Visitor<'de> for ChildrenSeed<'_, F>
+    where
+        F: FnMut(BookmarkNodes, &str) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        type Value = ();
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "the children of a Firefox bookmark folder")
+        }
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            loop {
+                let seed = NodeSeed {
+                    depth: self.depth,
+                    folder_path: self.folder_path,
+                    on_bookmark: &mut *self.on_bookmark,
+                };
+                if seq.next_element_seed(seed)?.is_none() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use std::{fs::File, io::BufReader};
@@ -290,6 +416,45 @@ pub mod model_json_mozilla_bookmarks {
             traverse_children(&bookmark.children);
         }
 
+        #[test]
+        fn test_for_each_bookmark() {
+            let mut streamed: Vec<(String, String)> = Vec::new();
+            for_each_bookmark(File::open("samples/input.prettified.json").unwrap(), &mut |bookmark, folder_path| {
+                streamed.push((bookmark.guid().clone(), folder_path.to_string()));
+                Ok(())
+            })
+            .unwrap();
+
+            // same bookmarks, in the same folders, as walking the whole tree
+            fn traverse_children(children: &Vec<BookmarkNodes>, folder_path: &str, bookmarks: &mut Vec<(String, String)>) {
+                for child in children {
+                    if child.is_bookmark() {
+                        bookmarks.push((child.guid().clone(), folder_path.to_string()));
+                    } else if let Some(children) = child.possible_children() {
+                        let child_path = match folder_path.is_empty() {
+                            true => child.title().clone(),
+                            false => format!("{}/{}", folder_path, child.title()),
+                        };
+                        traverse_children(children, &child_path, bookmarks);
+                    }
+                }
+            }
+            let bookmark_folders: BookmarkRootFolder = serde_json::from_reader(BufReader::new(File::open("samples/input.prettified.json").unwrap())).unwrap();
+            let mut walked = Vec::new();
+            traverse_children(bookmark_folders.children(), "", &mut walked);
+            assert!(!streamed.is_empty());
+            assert_eq!(streamed, walked);
+
+            // an error of the callback stops the walk
+            let mut count = 0;
+            let result = for_each_bookmark(File::open("samples/input.prettified.json").unwrap(), &mut |_, _| {
+                count += 1;
+                Err("stop".into())
+            });
+            assert!(result.unwrap_err().to_string().starts_with("stop"));
+            assert_eq!(count, 1);
+        }
+
         #[test]
         fn test_deserialize_bookmark_with_two_nodes() {
             //let json_data = json!(
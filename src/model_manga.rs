@@ -1,10 +1,15 @@
 pub const CASTAGNOLI: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
 
 pub mod model_manga {
+    use regex::Regex;
     use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
     use std::marker::{Send, Sync};
     use url::Url;
+    use utoipa::ToSchema;
 
+    pub use crate::config::config::{RomanizationOptions, RomanizationStyle};
+    use crate::error::error::Error;
     use crate::my_libs::make_none_if_empty;
     use crate::my_libs::sanitize_string;
 
@@ -23,13 +28,13 @@ pub mod model_manga {
     // cases where we will encounter String that are using UTF8 commas (i.e. "，")
     // instead of ASCII commas (i.e. ",").
     //#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Sync, Send)]
-    #[derive(Debug, Clone, Eq, Serialize, Deserialize)] // TODO: derive Sync and Send in future
+    #[derive(Debug, Clone, Eq, Serialize, Deserialize, ToSchema)] // TODO: derive Sync and Send in future
     pub struct MangaModel {
         // NOTE: almost (if not) all fields are private, and enforces usage of accessor methods
         // Also, because we do not wish to have it mutate, we probably want it as &str but
         // that would require impl<'a> for MangaModel<'a> and we don't want to do at the moment
         // due to complexities of traits such as clone and display...
-        id: u32,       // primary key - either prune or ignore if id is 0
+        id: i64,       // primary key (SQLite rowid) - either prune or ignore if id is 0
         title: String, // UTF8 encoded
         possible_title_romanized: Option<String>, // is Some() ONLY if title was in Japanese
         url: String,   // home page of manga (see impl of to_url and from_url validation)
@@ -40,6 +45,20 @@ pub mod model_manga {
         possible_notes: Option<String>,
         tags: Vec<String>, // i.e. "#アニメ化" ; empty vec[] is same as None
         possible_my_anime_list: Option<String>, // provides author and artist
+        #[serde(default)]
+        possible_feed_url: Option<String>, // per-series RSS/Atom feed, preferred over HTML scraping
+        #[serde(default)]
+        possible_latest_available_chapter: Option<String>, // newest chapter seen by the update checker
+        #[serde(default)]
+        possible_last_checked_millis: Option<i64>, // when the update checker last visited the site
+        #[serde(default)]
+        possible_bookmark_guid: Option<String>, // Firefox bookmark it came from, same GUID across renames and moves
+        #[serde(default)]
+        user_fields: BTreeMap<String, String>, // personal fields (i.e. "rating" => "9"), no schema change per field
+        #[serde(default)]
+        possible_updated_at_millis: Option<i64>, // last modification of the row, kept by the database (read only)
+        #[serde(default)]
+        alt_titles: Vec<String>, // titles the row had before a bookmark renamed it (see TitleCollisionPolicy)
     }
 
     impl PartialEq for MangaModel {
@@ -121,12 +140,10 @@ pub mod model_manga {
             // last update is formated in format "YYYY-MM-DDTHH:mm:ss" (24hr) IF it came from CSV,
             // but we'll NOT be sure what it is based off of JSON.  On SQLite, it is stored
             // both as string (i.e. "2023-10-06T17:44:11") and as long-long epoch time
-            match self.chapter().partial_cmp(&other.chapter()) {
-                Some(std::cmp::Ordering::Equal) => std::cmp::Ordering::Equal,
-                Some(std::cmp::Ordering::Less) => std::cmp::Ordering::Less,
-                Some(std::cmp::Ordering::Greater) => std::cmp::Ordering::Greater,
-                None => std::cmp::Ordering::Equal,
-            }
+            // so only the chapters are compared, in Chapter order ("100" after "5", "12.10" after "12.9"); a
+            // chapter is newer than none, or than one which is not a number (i.e. "extra")
+            let chapter_of = |manga: &Self| manga.chapter().and_then(|chapter| Chapter::parse(&chapter));
+            chapter_of(self).cmp(&chapter_of(other))
         }
     }
 
@@ -175,17 +192,18 @@ pub mod model_manga {
                     },
                     None => "",
                 },
-                &self
-                    .tags
-                    .iter()
-                    .filter_map(|tag| {
-                        match tag.trim().trim_end_matches('"').is_empty() {
-                            false => Some(tag.clone()),
-                            true => None,
-                        }
-                    })
-                    .collect::<Vec<String>>()
-                    .join(";"),
+                MangaModel::tags_to_csv(
+                    &self
+                        .tags
+                        .iter()
+                        .filter_map(|tag| {
+                            match tag.trim().trim_end_matches('"').is_empty() {
+                                false => Some(tag.clone()),
+                                true => None,
+                            }
+                        })
+                        .collect::<Vec<String>>(),
+                ),
                 match &self.possible_my_anime_list {
                     Some(s) => match s.trim().trim_end_matches('"').is_empty() {
                         false => s.as_str(),
@@ -197,17 +215,78 @@ pub mod model_manga {
         }
     } // Display
 
-    #[derive(Debug, Serialize, Deserialize)]
+    // Structured chapter, i.e. "12" => [12], "12.1" (or "12-1" as it appears in urls) => [12, 1]
+    // Unlike a float comparison, "12.10" is correctly newer than "12.9" (see newer_cmp())
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Chapter {
+        parts: Vec<u32>, // never empty; compared left to right, so [12] < [12, 1] < [13]
+    }
+
+    impl Chapter {
+        // None if chapter is empty or any part is not a number (i.e. "extra", "12a")
+        pub fn parse(chapter: &str) -> Option<Chapter> {
+            let parts = chapter
+                .trim()
+                .split(|c| c == '.' || c == '-')
+                .map(|part| part.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>()?;
+            match parts.is_empty() {
+                true => None,
+                false => Some(Chapter { parts }),
+            }
+        }
+
+        pub fn major(&self) -> u32 {
+            self.parts[0]
+        }
+
+        // same order as Chapter, as a number SQLite can index (see model_sqlite3_manga::chapter_numeric): each
+        // sub-chapter part is 1/1000 of the part before it, so 12.1 => 12.001 and 12.10 => 12.010 (newer than
+        // 12.9 => 12.009); parts past 999 are taken as 999
+        pub fn numeric(&self) -> f64 {
+            let mut numeric = self.major() as f64;
+            let mut scale = 1.0;
+            for part in self.parts.iter().skip(1) {
+                scale /= 1000.0;
+                numeric += std::cmp::min(*part, 999) as f64 * scale;
+            }
+            numeric
+        }
+
+        // number of chapters between read (self) and latest; sub-chapters (i.e. 12 => 12.1) count as one
+        pub fn unread_until(&self, latest: &Chapter) -> u32 {
+            if latest <= self {
+                return 0;
+            }
+            std::cmp::max(1, latest.major().saturating_sub(self.major()))
+        }
+    }
+
+    impl std::fmt::Display for Chapter {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(
+                f,
+                "{}",
+                self.parts
+                    .iter()
+                    .map(|part| part.to_string())
+                    .collect::<Vec<String>>()
+                    .join(".")
+            )
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaList {
         pub data: Vec<MangaModel>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaResponse {
         pub data: MangaModel,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaRequest {
         pub title: String,
         pub title_romanized: Option<String>,
@@ -219,7 +298,7 @@ pub mod model_manga {
         pub tags: Option<String>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaUpdateRequest {
         pub title: Option<String>,
         pub title_romanized: Option<String>,
@@ -231,27 +310,27 @@ pub mod model_manga {
         pub tags: Option<String>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaDeleteRequest {
-        pub id: i32,
+        pub id: i64,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaDeleteResponse {
-        pub id: i32,
+        pub id: i64,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaUpdateResponse {
-        pub id: i32,
+        pub id: i64,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaCreateResponse {
-        pub id: i32,
+        pub id: i64,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaCreateRequest {
         pub title: String,
         pub title_romanized: Option<String>,
@@ -263,7 +342,7 @@ pub mod model_manga {
         pub tags: Option<String>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaSearchRequest {
         pub title: Option<String>,
         pub title_romanized: Option<String>,
@@ -273,57 +352,195 @@ pub mod model_manga {
         pub last_update: Option<String>,
         pub notes: Option<String>,
         pub tags: Option<String>,
+        pub tag_expr: Option<String>, // i.e. "isekai AND NOT dropped", see tag_expression
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaSearchResponse {
         pub data: Vec<MangaModel>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaSearchByTitleRequest {
         pub title: String,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
     pub struct MangaSearchByTitleResponse {
         pub data: Vec<MangaModel>,
     }
 
-    // Default implementation of the with_values method that sets validate_url to false
+    // chapter is None to mark the latest available chapter (as found by `check`) read
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
+    pub struct MangaMarkReadRequest {
+        pub chapter: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, ToSchema)]
+    pub struct MangaMarkReadResponse {
+        pub id: i64,
+        pub chapter: String,
+    }
+
+    // all empty, id 0: not a manga which with_values() would accept
     impl Default for MangaModel {
         fn default() -> Self {
-            MangaModel::with_values(
-                0,
-                String::new(),
-                None,
-                String::new(),
-                None,
-                None,
-                None,
-                None, // last_update_millis
-                None,
-                Vec::new(),
-                None,
-                //false, // Set validate_url to false by default
-            )
+            MangaModel::new()
         }
     }
 
     impl MangaModel {
+        // the one romanization of titles, whatever model or export it ends up in: None unless the title is in
+        // Japanese, else its romaji sanitized as any other title is (see my_libs::sanitize_string), i.e. kakasi
+        // turns '、' into ',' and the ',' is turned back into '、', so that a romanized title never has a comma
         pub fn romanize_title(title: &str) -> Option<String> {
-            match kakasi::is_japanese(title) {
-                kakasi::IsJapanese::True => Some(sanitize_string(kakasi::convert(title).romaji)),
-                _ => None,
+            Self::romanize_title_with(title, &RomanizationOptions::default())
+        }
+
+        // same, in the style and with the dictionary of the config: a Japanese term of the dictionary is romanized
+        // as given wherever it is in the title (the earliest, then longest, first), whatever is around it by
+        // kakasi; any other term replaces the same word(s) of the romaji afterwards, case-insensitively
+        pub fn romanize_title_with(title: &str, options: &RomanizationOptions) -> Option<String> {
+            if !matches!(kakasi::is_japanese(title), kakasi::IsJapanese::True) {
+                return None;
+            }
+            let (japanese_terms, romaji_terms): (Vec<_>, Vec<_>) =
+                options.dictionary.iter().partition(|(term, _)| {
+                    matches!(kakasi::is_japanese(term), kakasi::IsJapanese::True)
+                });
+            // pieces of romaji are kept apart by a space, as kakasi keeps its words apart
+            let push_spaced = |romaji: &mut String, piece: &str| {
+                let needs_space = !romaji.is_empty()
+                    && !romaji.ends_with(char::is_whitespace)
+                    && !piece.starts_with(char::is_whitespace);
+                if !piece.is_empty() && needs_space {
+                    romaji.push(' ');
+                }
+                romaji.push_str(piece);
+            };
+
+            let mut romaji = String::new();
+            let mut rest = title;
+            while !rest.is_empty() {
+                let possible_term = japanese_terms
+                    .iter()
+                    .filter_map(|(term, preferred)| {
+                        rest.find(term.as_str())
+                            .map(|index| (index, term.len(), preferred.as_str()))
+                    })
+                    .min_by_key(|(index, term_length, _)| (*index, usize::MAX - term_length));
+                match possible_term {
+                    Some((index, term_length, preferred)) => {
+                        push_spaced(&mut romaji, &Self::to_romaji(&rest[..index], options.style));
+                        push_spaced(&mut romaji, preferred);
+                        rest = &rest[index + term_length..];
+                    }
+                    None => {
+                        push_spaced(&mut romaji, &Self::to_romaji(rest, options.style));
+                        rest = "";
+                    }
+                }
+            }
+            for (term, preferred) in romaji_terms {
+                if let Ok(word) = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term))) {
+                    romaji = word
+                        .replace_all(&romaji, regex::NoExpand(preferred))
+                        .to_string();
+                }
             }
+            Some(sanitize_string(romaji))
         }
 
+        // kakasi only knows Hepburn, so for Kunrei each run of Japanese text is converted on its own and its
+        // romaji respelled, leaving the words already in latin letters (i.e. "Shadow") as they are
+        fn to_romaji(text: &str, style: RomanizationStyle) -> String {
+            match style {
+                RomanizationStyle::Hepburn => kakasi::convert(text).romaji,
+                RomanizationStyle::Kunrei => {
+                    let is_japanese = |c: char| {
+                        matches!(
+                            kakasi::is_japanese(&c.to_string()),
+                            kakasi::IsJapanese::True
+                        )
+                    };
+                    let mut romaji = String::new();
+                    let mut rest = text;
+                    while let Some(first) = rest.chars().next() {
+                        let run_length = rest
+                            .char_indices()
+                            .find(|(_, c)| is_japanese(*c) != is_japanese(first))
+                            .map_or(rest.len(), |(index, _)| index);
+                        let (run, remainder) = rest.split_at(run_length);
+                        match is_japanese(first) {
+                            true => romaji
+                                .push_str(&Self::hepburn_to_kunrei(&kakasi::convert(run).romaji)),
+                            false => romaji.push_str(run),
+                        }
+                        rest = remainder;
+                    }
+                    romaji
+                }
+            }
+        }
+
+        // "shoujo" => "syouzyo", "tsuki" => "tuki", "matcha" => "mattya"; kakasi's romaji is lower case
+        pub fn hepburn_to_kunrei(romaji: &str) -> String {
+            const K_KUNREI_SYLLABLES: [(&str, &str); 17] = [
+                ("sha", "sya"),
+                ("shu", "syu"),
+                ("sho", "syo"),
+                ("she", "sye"),
+                ("shi", "si"),
+                ("cha", "tya"),
+                ("chu", "tyu"),
+                ("cho", "tyo"),
+                ("che", "tye"),
+                ("chi", "ti"),
+                ("tsu", "tu"),
+                ("fu", "hu"),
+                ("ja", "zya"),
+                ("ju", "zyu"),
+                ("jo", "zyo"),
+                ("je", "zye"),
+                ("ji", "zi"),
+            ];
+            let mut kunrei = String::with_capacity(romaji.len());
+            let mut rest = romaji;
+            while let Some(c) = rest.chars().next() {
+                match K_KUNREI_SYLLABLES
+                    .iter()
+                    .find(|(hepburn, _)| rest.starts_with(hepburn))
+                {
+                    Some((hepburn, syllable)) => {
+                        kunrei.push_str(syllable);
+                        rest = &rest[hepburn.len()..];
+                    }
+                    None => {
+                        kunrei.push(c);
+                        rest = &rest[c.len_utf8()..];
+                    }
+                }
+            }
+            kunrei
+        }
+
+        // tags are a single cell of the CSV, separated by ';'; a '%' or ';' inside a tag is percent-encoded ("%25",
+        // "%3B") rather than backslash-escaped, since the CSV reader already takes '\' as its own escape
+        pub fn tags_to_csv<T: AsRef<str>>(tags: &[T]) -> String {
+            tags.iter()
+                .map(|tag| tag.as_ref().replace('%', "%25").replace(';', "%3B"))
+                .collect::<Vec<String>>()
+                .join(";")
+        }
+
+        // inverse of tags_to_csv(), an empty cell being no tags at all; any other '%' is left as it is
         pub fn csv_to_tags(csv: &str) -> Vec<String> {
-            let mut tags: Vec<String> = Vec::new();
-            for tag in csv.split(",") {
-                tags.push(sanitize_string(tag));
+            if csv.is_empty() {
+                return Vec::new();
             }
-            tags
+            csv.split(';')
+                .map(|tag| tag.replace("%3B", ";").replace("%3b", ";").replace("%25", "%"))
+                .collect()
         }
 
         pub fn url_and_chapter(
@@ -343,9 +560,12 @@ pub mod model_manga {
             // base_url = "https://some.example.com/tsuki-ga-michibiku-isekai-douchuu/"
             // chapter = None
             let (url_as_is, base_url, chapter) = {
-                let mut path_segments = url_parsed.path_segments().unwrap().collect::<Vec<_>>();
+                // urls such as "about:downloads" have no path segments at all, hence no chapter
+                let mut path_segments = url_parsed
+                    .path_segments()
+                    .map_or(Vec::new(), |segments| segments.collect::<Vec<_>>());
                 // if last segment is empty, then pop it off
-                if path_segments.last().unwrap().is_empty() {
+                if path_segments.last().is_some_and(|segment| segment.is_empty()) {
                     path_segments.pop();
                 }
                 if let Some(last_segment) = path_segments.last_mut() {
@@ -445,11 +665,20 @@ pub mod model_manga {
                 possible_notes: None,
                 tags: Vec::new(),
                 possible_my_anime_list: None,
+                possible_feed_url: None,
+                possible_latest_available_chapter: None,
+                possible_last_checked_millis: None,
+                possible_bookmark_guid: None,
+                user_fields: BTreeMap::new(),
+                possible_updated_at_millis: None,
+                alt_titles: Vec::new(),
             }
         }
         // Public constructor that constructs a MangaModel object with the given values
+        // id: anything that converts losslessly to i64 (i.e. the u32 checksums used as temporary ids)
+        // Err when the title or url is empty, the id is 0, or the url does not parse
         pub fn with_values(
-            id: u32,
+            id: impl Into<i64>,
             title: String,
             title_romanized: Option<String>,
             url: String,
@@ -460,26 +689,26 @@ pub mod model_manga {
             notes: Option<String>,
             tags: Vec<String>,
             my_anime_list: Option<String>,
-        ) -> MangaModel {
+        ) -> Result<MangaModel, Error> {
             // ideally, rather than setting ID=0, use CRC32 of title as ID to make it unique prior to calling this...
+            let id: i64 = id.into();
             if title.trim().trim_end_matches('"').is_empty() {
-                panic!("title is empty");
+                return Err(Error::EmptyTitle);
             }
             if url.trim().trim_end_matches('"').is_empty() {
-                panic!("url is empty");
+                return Err(Error::EmptyUrl);
             }
             if id == 0 {
-                panic!("id is 0");
+                return Err(Error::InvalidId(id));
             }
 
-            // validate url passed as string is valid url via url::Url, similar to above tests,
-            // we'll panic if url is invalid
+            // validate url passed as string is valid url via url::Url, similar to above tests
             let url_parsed = match Url::parse(url.as_str()) {
                 Ok(validated_url) => validated_url,
-                Err(e) => panic!("Error parsing url ({:?}): {:?}", url, e),
+                Err(e) => return Err(Error::InvalidUrl { url, source: e }),
             };
 
-            MangaModel {
+            Ok(MangaModel {
                 id: id,       // primary key - either prune or ignore if id is 0
                 title: title, // UTF8 encoded, uniqueness based on this and/or url
                 possible_title_romanized: make_none_if_empty(title_romanized),
@@ -491,7 +720,14 @@ pub mod model_manga {
                 possible_notes: make_none_if_empty(notes),
                 tags: tags,
                 possible_my_anime_list: make_none_if_empty(my_anime_list),
-            }
+                possible_feed_url: None, // see set_feed_url()
+                possible_latest_available_chapter: None, // see set_latest_available_chapter()
+                possible_last_checked_millis: None, // see set_last_checked_millis()
+                possible_bookmark_guid: None, // see set_bookmark_guid()
+                user_fields: BTreeMap::new(), // see set_user_fields()
+                possible_updated_at_millis: None, // see set_updated_at_millis()
+                alt_titles: Vec::new(), // see add_alt_title()
+            })
         }
 
         // disallow empty title, url, or id; note that id passed is commonly/usually from
@@ -500,8 +736,9 @@ pub mod model_manga {
         pub fn new_from_required_elements(
             title_possibly_in_kanji: &str,
             url_with_possible_chapter: &str,
-            id: u32,
+            id: impl Into<i64>,
         ) -> Result<MangaModel, Box<dyn std::error::Error>> {
+            let id: i64 = id.into();
             let possible_title_romanized =
                 Self::romanize_title(&sanitize_string(title_possibly_in_kanji));
 
@@ -549,13 +786,13 @@ pub mod model_manga {
                         None,
                         Vec::new(), // empty vec[] is same as None
                         None,
-                    ))
+                    )?)
                 }
                 Err(str_err) => Err(str_err.into()),
             }
         }
 
-        pub fn id(&self) -> u32 {
+        pub fn id(&self) -> i64 {
             self.id
         }
         pub fn title(&self) -> &str {
@@ -598,9 +835,58 @@ pub mod model_manga {
         pub fn my_anime_list(&self) -> Option<String> {
             make_none_if_empty(self.possible_my_anime_list.as_ref())
         }
+        pub fn feed_url(&self) -> Option<String> {
+            make_none_if_empty(self.possible_feed_url.as_ref())
+        }
+        pub fn latest_available_chapter(&self) -> Option<String> {
+            make_none_if_empty(self.possible_latest_available_chapter.as_ref())
+        }
+        pub fn last_checked_millis(&self) -> Option<i64> {
+            self.possible_last_checked_millis
+        }
+        pub fn bookmark_guid(&self) -> Option<String> {
+            make_none_if_empty(self.possible_bookmark_guid.as_ref())
+        }
+        pub fn user_fields(&self) -> &BTreeMap<String, String> {
+            &self.user_fields
+        }
+        pub fn updated_at_millis(&self) -> Option<i64> {
+            self.possible_updated_at_millis
+        }
+        pub fn alt_titles(&self) -> &[String] {
+            &self.alt_titles
+        }
+        // derives the url of given chapter from the url pattern of url_with_chapter, i.e. chapter "13.1" with
+        // "https://example.com/gate-chapter-12/" => "https://example.com/gate-chapter-13-1/"
+        // None if url_with_chapter is missing or has no "-chapter-" to go by (we do not guess)
+        pub fn url_for_chapter(&self, chapter: &str) -> Option<String> {
+            let url_with_chapter = self.url_with_chapter()?;
+            let chapter_index = url_with_chapter.rfind("-chapter-")? + "-chapter-".len();
+            let (head, tail) = url_with_chapter.split_at(chapter_index);
+            // tail is "12/" or "12-1/?page=2", so keep everything after the chapter number
+            let chapter_length = tail
+                .find(|c: char| !(c.is_ascii_digit() || c == '-' || c == '.'))
+                .unwrap_or(tail.len());
+            Some(format!(
+                "{}{}{}",
+                head,
+                chapter.replace(".", "-"),
+                &tail[chapter_length..]
+            ))
+        }
+        // derived: latest_available_chapter minus last read chapter (nothing read yet counts from chapter 0);
+        // None when the update checker has not (successfully) found anything for this manga
+        pub fn unread_count(&self) -> Option<u32> {
+            let latest = Chapter::parse(&self.latest_available_chapter()?)?;
+            let read = self
+                .chapter()
+                .and_then(|chapter| Chapter::parse(&chapter))
+                .unwrap_or(Chapter { parts: vec![0] });
+            Some(read.unread_until(&latest))
+        }
 
-        pub fn set_id(&mut self, id: u32) {
-            self.id = id;
+        pub fn set_id(&mut self, id: impl Into<i64>) {
+            self.id = id.into();
         }
         pub fn set_title(&mut self, title: String) {
             self.title = title;
@@ -632,6 +918,37 @@ pub mod model_manga {
         pub fn set_my_anime_list(&mut self, my_anime_list: Option<String>) {
             self.possible_my_anime_list = my_anime_list.map(|s| sanitize_string(s));
         }
+        // kept as given, sanitize_string() would turn the "," of a query into "、"; set-feed checks it parses
+        pub fn set_feed_url(&mut self, feed_url: Option<String>) {
+            self.possible_feed_url = feed_url.map(|s| s.trim().to_string());
+        }
+        pub fn set_latest_available_chapter(&mut self, latest_available_chapter: Option<String>) {
+            self.possible_latest_available_chapter =
+                latest_available_chapter.map(|s| sanitize_string(s));
+        }
+        pub fn set_last_checked_millis(&mut self, last_checked_millis: Option<i64>) {
+            self.possible_last_checked_millis = last_checked_millis;
+        }
+        pub fn set_bookmark_guid(&mut self, bookmark_guid: Option<String>) {
+            self.possible_bookmark_guid = bookmark_guid.map(|s| sanitize_string(s));
+        }
+        // only meant for what was read from the database, writers never write it (see model_sqlite3_manga)
+        pub fn set_updated_at_millis(&mut self, updated_at_millis: Option<i64>) {
+            self.possible_updated_at_millis = updated_at_millis;
+        }
+        pub fn set_user_fields(&mut self, user_fields: BTreeMap<String, String>) {
+            self.user_fields = user_fields;
+        }
+        pub fn set_alt_titles(&mut self, alt_titles: Vec<String>) {
+            self.alt_titles = alt_titles;
+        }
+        // the same title twice, the current one, or an empty one are not added
+        pub fn add_alt_title(&mut self, alt_title: &str) {
+            let alt_title = alt_title.trim();
+            if !alt_title.is_empty() && alt_title != self.title && !self.alt_titles.iter().any(|title| title == alt_title) {
+                self.alt_titles.push(alt_title.to_string());
+            }
+        }
     }
 
     fn is_normal<T: Sized + Send + Sync + Unpin>() {}
@@ -649,6 +966,172 @@ pub mod model_manga {
         #[test]
         fn test_default_trait() {}
 
+        #[test]
+        fn test_chapter() {
+            assert_eq!(Chapter::parse("12-1"), Chapter::parse("12.1"));
+            assert!(Chapter::parse("12.10") > Chapter::parse("12.9"));
+            assert!(Chapter::parse("13") > Chapter::parse("12.1"));
+            assert_eq!(Chapter::parse("extra"), None);
+            assert_eq!(Chapter::parse(""), None);
+            assert_eq!(Chapter::parse("3-2-1").unwrap().to_string(), "3.2.1");
+            assert_eq!(Chapter::parse("12.1").unwrap().numeric(), 12.001);
+            assert!(
+                Chapter::parse("12.10").unwrap().numeric()
+                    > Chapter::parse("12.9").unwrap().numeric()
+            );
+            assert!(
+                Chapter::parse("100").unwrap().numeric()
+                    > Chapter::parse("99.999").unwrap().numeric()
+            );
+
+            let read = Chapter::parse("10").unwrap();
+            assert_eq!(read.unread_until(&Chapter::parse("15").unwrap()), 5);
+            assert_eq!(read.unread_until(&Chapter::parse("10.1").unwrap()), 1);
+            assert_eq!(read.unread_until(&Chapter::parse("10").unwrap()), 0);
+            assert_eq!(read.unread_until(&Chapter::parse("9").unwrap()), 0);
+
+            let with_chapter = |chapter: Option<&str>| {
+                let mut manga = MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap();
+                manga.set_chapter(chapter.map(|chapter| chapter.to_string()));
+                manga
+            };
+            assert_eq!(with_chapter(Some("5")).newer_cmp(&with_chapter(Some("100"))), std::cmp::Ordering::Less);
+            assert_eq!(with_chapter(Some("12.10")).newer_cmp(&with_chapter(Some("12.9"))), std::cmp::Ordering::Greater);
+            assert_eq!(with_chapter(None).newer_cmp(&with_chapter(Some("1"))), std::cmp::Ordering::Less);
+            assert_eq!(with_chapter(Some("12-1")).newer_cmp(&with_chapter(Some("12.1"))), std::cmp::Ordering::Equal);
+        }
+
+        #[test]
+        fn test_with_values_errors() {
+            let with_title_url_id = |title: &str, url: &str, id: i64| {
+                MangaModel::with_values(id, title.to_owned(), None, url.to_owned(), None, None, None, None, None, Vec::new(), None)
+            };
+            assert!(with_title_url_id("Gate", "https://example.com/gate/", 1).is_ok());
+            assert!(matches!(with_title_url_id(" ", "https://example.com/gate/", 1), Err(Error::EmptyTitle)));
+            assert!(matches!(with_title_url_id("Gate", "", 1), Err(Error::EmptyUrl)));
+            assert!(matches!(with_title_url_id("Gate", "https://example.com/gate/", 0), Err(Error::InvalidId(0))));
+            match with_title_url_id("Gate", "example.com/gate", 1) {
+                Err(Error::InvalidUrl { url, .. }) => assert_eq!(url, "example.com/gate"),
+                other => panic!("expected InvalidUrl, got {:?}", other),
+            }
+            // the malformed bookmark is an error of its own, not the end of the import
+            assert!(MangaModel::new_from_required_elements("", "https://example.com/gate/", 1).is_err());
+        }
+
+        #[test]
+        fn test_unread_count() {
+            let mut manga = MangaModel::with_values(
+                1,
+                "My Manga".to_owned(),
+                None,
+                "https://example.com/manga".to_owned(),
+                None,
+                Some("10".to_string()),
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+            ).unwrap();
+            assert_eq!(manga.unread_count(), None); // never checked
+            manga.set_latest_available_chapter(Some("13".to_string()));
+            assert_eq!(manga.unread_count(), Some(3));
+            manga.set_chapter(None);
+            assert_eq!(manga.unread_count(), Some(13));
+        }
+
+        #[test]
+        fn test_set_feed_url() {
+            let mut manga =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            manga.set_feed_url(Some(
+                " https://example.com/feed?tags=isekai,seinen&title='gate' ".to_string(),
+            ));
+            assert_eq!(
+                manga.feed_url(),
+                Some("https://example.com/feed?tags=isekai,seinen&title='gate'".to_string())
+            );
+            manga.set_feed_url(Some(" ".to_string()));
+            assert_eq!(manga.feed_url(), None);
+        }
+
+        #[test]
+        fn test_tags_to_csv() {
+            let tags = vec![
+                "isekai".to_string(),
+                "rated 5;5".to_string(),
+                "100%".to_string(),
+                "%3B".to_string(),
+            ];
+            let csv = MangaModel::tags_to_csv(&tags);
+            assert_eq!(csv, "isekai;rated 5%3B5;100%25;%253B");
+            assert_eq!(MangaModel::csv_to_tags(&csv), tags);
+            assert_eq!(MangaModel::csv_to_tags("a; b"), vec!["a", " b"]);
+            assert_eq!(MangaModel::csv_to_tags("50%"), vec!["50%"]);
+            assert!(MangaModel::csv_to_tags("").is_empty());
+        }
+
+        #[test]
+        fn test_hepburn_to_kunrei() {
+            assert_eq!(
+                MangaModel::hepburn_to_kunrei("tsuki ga michibiku isekai douchuu"),
+                "tuki ga mitibiku isekai doutyuu"
+            );
+            assert_eq!(MangaModel::hepburn_to_kunrei("shoujo"), "syouzyo");
+            assert_eq!(MangaModel::hepburn_to_kunrei("matcha fuji"), "mattya huzi");
+            assert_eq!(MangaModel::hepburn_to_kunrei("geeto"), "geeto");
+        }
+
+        #[test]
+        fn test_romanize_title_with_dictionary() {
+            let options = RomanizationOptions {
+                style: RomanizationStyle::Hepburn,
+                dictionary: BTreeMap::from([
+                    ("異世界".to_string(), "isekai".to_string()),
+                    ("ゲート".to_string(), "geeto".to_string()),
+                    ("geeto".to_string(), "Gate".to_string()),
+                ]),
+            };
+            assert_eq!(
+                MangaModel::romanize_title_with("ゲート異世界", &options),
+                Some("Gate isekai".to_string())
+            );
+            assert_eq!(MangaModel::romanize_title_with("Gate", &options), None);
+            assert_eq!(
+                MangaModel::romanize_title_with("ゲート", &RomanizationOptions::default()),
+                MangaModel::romanize_title("ゲート")
+            );
+        }
+
+        #[test]
+        fn test_url_for_chapter() {
+            let mut manga = MangaModel::with_values(
+                1,
+                "Gate".to_owned(),
+                None,
+                "https://example.com/gate/".to_owned(),
+                Some("https://example.com/gate-chapter-12/".to_string()),
+                Some("12".to_string()),
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+            ).unwrap();
+            assert_eq!(
+                manga.url_for_chapter("13.1"),
+                Some("https://example.com/gate-chapter-13-1/".to_string())
+            );
+            manga.set_url_with_chapter(Some("https://example.com/gate-chapter-12-1?page=2".to_string()));
+            assert_eq!(
+                manga.url_for_chapter("14"),
+                Some("https://example.com/gate-chapter-14?page=2".to_string())
+            );
+            manga.set_url_with_chapter(None);
+            assert_eq!(manga.url_for_chapter("14"), None);
+        }
+
         #[test]
         fn test_manga_model() {
             let mut manga = MangaModel::with_values(
@@ -663,7 +1146,7 @@ pub mod model_manga {
                 Some("My notes".to_string()),
                 vec!["tag1".to_owned(), "tag2".to_owned()],
                 Some("https://myanimelist.net/manga/1".to_string()),
-            );
+            ).unwrap();
 
             assert_eq!(manga.id(), 1);
             assert_eq!(manga.title(), "My Manga");
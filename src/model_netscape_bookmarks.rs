@@ -0,0 +1,385 @@
+// Netscape bookmark file (`bookmarks.html`), the format every browser imports and exports, so that the cleaned
+// up collection can go back into Firefox, Chrome, Safari...  (`manga export-bookmarks --out bookmarks.html`):
+//      <!DOCTYPE NETSCAPE-Bookmark-file-1>
+//      ...
+//      <DL><p>
+//          <DT><H3>漫画</H3>
+//          <DL><p>
+//              <DT><H3>isekai</H3>
+//              <DL><p>
+//                  <DT><A HREF="https://example.com/manga/gate/chapter-12" ADD_DATE="1694008642" TAGS="isekai,seinen">ゲート</A>
+//                  <DD>notes
+//              </DL><p>
+//          </DL><p>
+//      </DL><p>
+// Bookmarks point at url_with_chapter, same as the Firefox bookmarks this all started from, so importing them
+// back gives the chapter too.  Each manga is in one folder (its first tag, or its publication status, or none
+// at all), all of its tags are in TAGS, which Firefox restores as bookmark tags and other browsers ignore.
+// The same file exported by any browser can be imported too (`-i bookmarks.html --format html`): every
+// <DT><A HREF=..> in it, whatever folders it is nested in, with its ADD_DATE, LAST_MODIFIED and TAGS if any.
+pub mod model_netscape_bookmarks {
+    use regex::Regex;
+    use std::{
+        collections::BTreeMap,
+        io::{self, Write},
+    };
+
+    use crate::html_report::html_report::escape_html;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::update_checker::update_checker;
+
+    pub const K_DEFAULT_ROOT_FOLDER: &str = "漫画";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum FolderGrouping {
+        Tag,
+        Status,
+        None,
+    }
+
+    impl std::str::FromStr for FolderGrouping {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "tag" | "tags" => Ok(FolderGrouping::Tag),
+                "status" => Ok(FolderGrouping::Status),
+                "none" => Ok(FolderGrouping::None),
+                _ => Err(format!(
+                    "unknown folder grouping '{}', expected tag, status or none",
+                    s
+                )),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct BookmarkEntry {
+        pub folder: Option<String>, // None is directly in the root folder
+        pub manga: MangaModel,
+    }
+
+    // possible_status is the publication status found by `enrich` (manga_metadata), only used for Status
+    pub fn to_bookmark_entry(
+        manga: &MangaModel,
+        grouping: FolderGrouping,
+        possible_status: Option<String>,
+    ) -> BookmarkEntry {
+        let folder = match grouping {
+            FolderGrouping::Tag => manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim())
+                .find(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string()),
+            FolderGrouping::Status => possible_status.filter(|status| !status.trim().is_empty()),
+            FolderGrouping::None => None,
+        };
+        BookmarkEntry {
+            folder,
+            manga: manga.clone(),
+        }
+    }
+
+    fn write_bookmark(
+        output_writer: &mut dyn Write,
+        indent: &str,
+        manga: &MangaModel,
+    ) -> io::Result<()> {
+        let href = manga
+            .url_with_chapter()
+            .filter(|url_with_chapter| !url_with_chapter.is_empty())
+            .unwrap_or_else(|| manga.url().to_string());
+        let mut attributes = format!("HREF=\"{}\"", escape_html(&href));
+        if let Some(millis) = manga.last_update_millis().filter(|millis| *millis > 0) {
+            // seconds, not milliseconds
+            attributes.push_str(&format!(
+                " ADD_DATE=\"{0}\" LAST_MODIFIED=\"{0}\"",
+                millis / 1000
+            ));
+        }
+        let tags: Vec<&str> = manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if !tags.is_empty() {
+            attributes.push_str(&format!(" TAGS=\"{}\"", escape_html(&tags.join(","))));
+        }
+        writeln!(
+            output_writer,
+            "{}<DT><A {}>{}</A>",
+            indent,
+            attributes,
+            escape_html(manga.title())
+        )?;
+        if let Some(notes) = manga.notes().filter(|notes| !notes.trim().is_empty()) {
+            writeln!(
+                output_writer,
+                "{}<DD>{}",
+                indent,
+                escape_html(&notes.split_whitespace().collect::<Vec<&str>>().join(" "))
+            )?;
+        }
+        Ok(())
+    }
+
+    // folders sorted by name, then the entries without a folder; bookmarks sorted by title within each
+    pub fn write_bookmarks_html(
+        output_writer: &mut dyn Write,
+        root_folder: &str,
+        entries: &[BookmarkEntry],
+    ) -> io::Result<()> {
+        let mut folders: BTreeMap<&str, Vec<&MangaModel>> = BTreeMap::new();
+        let mut unfiled: Vec<&MangaModel> = Vec::new();
+        for entry in entries.iter() {
+            match &entry.folder {
+                Some(folder) => folders
+                    .entry(folder.as_str())
+                    .or_default()
+                    .push(&entry.manga),
+                None => unfiled.push(&entry.manga),
+            }
+        }
+
+        writeln!(output_writer, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+        writeln!(
+            output_writer,
+            "<!-- This is an automatically generated file."
+        )?;
+        writeln!(output_writer, "     It will be read and overwritten.")?;
+        writeln!(output_writer, "     DO NOT EDIT! -->")?;
+        writeln!(
+            output_writer,
+            "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">"
+        )?;
+        writeln!(output_writer, "<TITLE>Bookmarks</TITLE>")?;
+        writeln!(output_writer, "<H1>Bookmarks</H1>")?;
+        writeln!(output_writer, "<DL><p>")?;
+        writeln!(
+            output_writer,
+            "    <DT><H3>{}</H3>",
+            escape_html(root_folder)
+        )?;
+        writeln!(output_writer, "    <DL><p>")?;
+        for (folder, mut mangas) in folders.into_iter() {
+            mangas.sort_by_key(|manga| manga.title().to_lowercase());
+            writeln!(
+                output_writer,
+                "        <DT><H3>{}</H3>",
+                escape_html(folder)
+            )?;
+            writeln!(output_writer, "        <DL><p>")?;
+            for manga in mangas.iter() {
+                write_bookmark(output_writer, "            ", manga)?;
+            }
+            writeln!(output_writer, "        </DL><p>")?;
+        }
+        unfiled.sort_by_key(|manga| manga.title().to_lowercase());
+        for manga in unfiled.iter() {
+            write_bookmark(output_writer, "        ", manga)?;
+        }
+        writeln!(output_writer, "    </DL><p>")?;
+        writeln!(output_writer, "</DL><p>")?;
+        output_writer.flush()
+    }
+
+    // a <DT><A> of a bookmarks.html; times are unix epoch seconds, as Netscape had them
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NetscapeBookmark {
+        pub folder_path: String, // i.e. "漫画/isekai", empty outside of any folder
+        pub title: String,
+        pub href: String,
+        pub possible_add_date: Option<i64>,
+        pub possible_last_modified: Option<i64>,
+        pub tags: Vec<String>,
+    }
+
+    impl NetscapeBookmark {
+        // LAST_MODIFIED, else ADD_DATE, in microseconds as Firefox bookmarks have them (0 when neither is given)
+        pub fn last_modified_micros(&self) -> i64 {
+            self.possible_last_modified
+                .or(self.possible_add_date)
+                .unwrap_or(0)
+                * 1_000_000
+        }
+    }
+
+    // not an HTML parser, only what browsers write: each <H3> names the <DL> after it, which holds the <DT>s of
+    // that folder until its </DL>; anything else (<HR>, <DD> notes, separators) is passed over
+    pub fn read_bookmarks_html(html: &str) -> Vec<NetscapeBookmark> {
+        let tag_regex =
+            Regex::new(r"(?is)<(/?)(DL|H3|A)\b([^>]*)>(?:([^<]*)</(?:H3|A)\s*>)?").unwrap();
+        let attribute_regex = Regex::new(r#"(?s)([A-Za-z_]+)\s*=\s*"([^"]*)""#).unwrap();
+
+        let mut folders: Vec<Option<String>> = Vec::new(); // one for each <DL> we are in, None for the top one
+        let mut possible_folder: Option<String> = None; // <H3> of the next <DL>
+        let mut bookmarks = Vec::new();
+        for tag in tag_regex.captures_iter(html) {
+            let is_closing = !tag[1].is_empty();
+            let text = tag.get(4).map_or("", |text| text.as_str());
+            match (tag[2].to_uppercase().as_str(), is_closing) {
+                ("DL", false) => folders.push(possible_folder.take()),
+                ("DL", true) => {
+                    folders.pop();
+                }
+                ("H3", false) => possible_folder = Some(update_checker::xml_text(text)),
+                ("A", false) => {
+                    let attributes: BTreeMap<String, String> = attribute_regex
+                        .captures_iter(&tag[3])
+                        .map(|c| (c[1].to_uppercase(), update_checker::xml_text(&c[2])))
+                        .collect();
+                    let seconds = |name: &str| {
+                        attributes
+                            .get(name)
+                            .and_then(|value| value.parse::<i64>().ok())
+                    };
+                    bookmarks.push(NetscapeBookmark {
+                        folder_path: folders
+                            .iter()
+                            .flatten()
+                            .cloned()
+                            .collect::<Vec<String>>()
+                            .join("/"),
+                        title: update_checker::xml_text(text),
+                        href: attributes.get("HREF").cloned().unwrap_or_default(),
+                        possible_add_date: seconds("ADD_DATE"),
+                        possible_last_modified: seconds("LAST_MODIFIED"),
+                        tags: attributes
+                            .get("TAGS")
+                            .map(|tags| {
+                                tags.split(',')
+                                    .map(|tag| tag.trim().to_string())
+                                    .filter(|tag| !tag.is_empty())
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        bookmarks
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_write_bookmarks_html() {
+            let mut gate = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            gate.set_url_with_chapter(Some(
+                "https://example.com/manga/gate/chapter-12".to_string(),
+            ));
+            gate.set_last_update_millis(Some(1694008642000));
+            gate.set_tags(vec![" isekai".to_string(), " seinen".to_string()]);
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            let entries = vec![
+                to_bookmark_entry(&gate, FolderGrouping::Tag, None),
+                to_bookmark_entry(&akira, FolderGrouping::Tag, None),
+            ];
+            assert_eq!(entries[0].folder, Some("isekai".to_string()));
+            assert_eq!(
+                to_bookmark_entry(
+                    &gate,
+                    FolderGrouping::Status,
+                    Some("Publishing".to_string())
+                )
+                .folder,
+                Some("Publishing".to_string())
+            );
+
+            let mut html = Vec::new();
+            write_bookmarks_html(&mut html, "漫画 & co", &entries).unwrap();
+            let html = String::from_utf8(html).unwrap();
+            assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n"));
+            assert!(html.contains("    <DT><H3>漫画 &amp; co</H3>\n"));
+            assert!(html.contains(
+                "        <DT><H3>isekai</H3>\n        <DL><p>\n            <DT><A HREF=\"https://example.com/manga/gate/chapter-12\" ADD_DATE=\"1694008642\" LAST_MODIFIED=\"1694008642\" TAGS=\"isekai,seinen\">ゲート</A>\n        </DL><p>\n"
+            ));
+            // no folder, straight in the root folder after the folders
+            assert!(html.contains(
+                "        </DL><p>\n        <DT><A HREF=\"https://example.com/akira/\">Akira</A>\n    </DL><p>\n"
+            ));
+        }
+
+        #[test]
+        fn test_read_bookmarks_html() {
+            let mut gate = MangaModel::new_from_required_elements(
+                "ゲート & co",
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            gate.set_url_with_chapter(Some(
+                "https://example.com/manga/gate/chapter-12".to_string(),
+            ));
+            gate.set_last_update_millis(Some(1694008642000));
+            gate.set_tags(vec!["isekai".to_string(), "seinen".to_string()]);
+            gate.set_notes(Some("notes".to_string()));
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            let mut html = Vec::new();
+            write_bookmarks_html(
+                &mut html,
+                K_DEFAULT_ROOT_FOLDER,
+                &[
+                    to_bookmark_entry(&gate, FolderGrouping::Tag, None),
+                    to_bookmark_entry(&akira, FolderGrouping::Tag, None),
+                ],
+            )
+            .unwrap();
+
+            // what was written reads back
+            let bookmarks = read_bookmarks_html(&String::from_utf8(html).unwrap());
+            assert_eq!(
+                bookmarks,
+                vec![
+                    NetscapeBookmark {
+                        folder_path: "漫画/isekai".to_string(),
+                        title: "ゲート & co".to_string(),
+                        href: "https://example.com/manga/gate/chapter-12".to_string(),
+                        possible_add_date: Some(1694008642),
+                        possible_last_modified: Some(1694008642),
+                        tags: vec!["isekai".to_string(), "seinen".to_string()],
+                    },
+                    NetscapeBookmark {
+                        folder_path: "漫画".to_string(),
+                        title: "Akira".to_string(),
+                        href: "https://example.com/akira/".to_string(),
+                        possible_add_date: None,
+                        possible_last_modified: None,
+                        tags: Vec::new(),
+                    },
+                ]
+            );
+            assert_eq!(bookmarks[0].last_modified_micros(), 1694008642000000);
+
+            // as Chrome exports them: lowercase attributes, the toolbar as a folder of its own
+            let bookmarks = read_bookmarks_html(
+                r#"<DL><p>
+    <DT><H3 ADD_DATE="1694008600" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A href="https://example.com/gate-chapter-5/" add_date="1694008642" ICON="data:image/png;base64,AAAA">Gate</A>
+        <HR>
+    </DL><p>
+    <DT><A HREF="https://example.com/akira/">Akira</A>
+</DL><p>"#,
+            );
+            assert_eq!(bookmarks.len(), 2);
+            assert_eq!(bookmarks[0].folder_path, "Bookmarks bar");
+            assert_eq!(bookmarks[0].href, "https://example.com/gate-chapter-5/");
+            assert_eq!(bookmarks[0].possible_add_date, Some(1694008642));
+            assert_eq!(bookmarks[1].folder_path, "");
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// OPML 2.0 subscription list of the series that have a feed, so that they can be followed in any feed reader
+// (`manga export-opml --out manga.opml`), one folder per (first) tag:
+//      <outline text="isekai" title="isekai">
+//        <outline type="rss" text="ゲート" title="ゲート" xmlUrl="https://example.com/rss/gate.xml" htmlUrl="https://example.com/manga/gate/"/>
+//      </outline>
+// The feed is feed_url (see `set-feed`) when there is one, otherwise derived from url by the first of the
+// [[feed_rules]] in the config for that domain (and its subdomains):
+//      [[feed_rules]]
+//      domain = "example.com"
+//      template = "https://example.com/rss/{slug}.xml"     # {slug}: last part of the url path, {url}: encoded url
+// Series without either are left out.  A MangaDex feed_url only carries the MangaDex id, it is not a feed.
+pub mod model_opml_manga {
+    use std::{
+        collections::BTreeMap,
+        io::{self, Write},
+    };
+    use url::Url;
+
+    use crate::config::config::FeedRuleConfig;
+    use crate::mangadex::mangadex;
+    use crate::model_atom_manga::model_atom_manga::xml_escape;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::site_migration::site_migration;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FeedOutline {
+        pub folder: Option<String>, // None is at the top level of the body
+        pub title: String,
+        pub xml_url: String,
+        pub html_url: String,
+    }
+
+    // i.e. "https://example.com/manga/gate/" => "gate"
+    pub fn url_slug(url: &str) -> Option<String> {
+        Url::parse(url).ok().and_then(|parsed_url| {
+            parsed_url
+                .path_segments()
+                .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+                .map(|segment| segment.to_string())
+        })
+    }
+
+    pub fn derived_feed_url(manga: &MangaModel, rules: &[FeedRuleConfig]) -> Option<String> {
+        let rule = rules
+            .iter()
+            .find(|rule| site_migration::is_on_domain(manga.url(), &rule.domain))?;
+        let mut feed_url = rule.template.clone();
+        if feed_url.contains("{slug}") {
+            feed_url = feed_url.replace("{slug}", &url_slug(manga.url())?);
+        }
+        let encoded_url: String =
+            url::form_urlencoded::byte_serialize(manga.url().as_bytes()).collect();
+        Some(feed_url.replace("{url}", &encoded_url))
+    }
+
+    // None when the manga has no feed to follow
+    pub fn to_feed_outline(manga: &MangaModel, rules: &[FeedRuleConfig]) -> Option<FeedOutline> {
+        let xml_url = manga
+            .feed_url()
+            .filter(|feed_url| mangadex::mangadex_id_from_url(feed_url).is_none())
+            .or_else(|| derived_feed_url(manga, rules))?;
+        let folder = manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim())
+            .find(|tag| !tag.is_empty())
+            .map(|tag| tag.to_string());
+        Some(FeedOutline {
+            folder,
+            title: manga.title().to_string(),
+            xml_url,
+            html_url: manga.url().to_string(),
+        })
+    }
+
+    fn to_outline_element(outline: &FeedOutline) -> String {
+        format!(
+            "<outline type=\"rss\" text=\"{0}\" title=\"{0}\" xmlUrl=\"{1}\" htmlUrl=\"{2}\"/>",
+            xml_escape(&outline.title),
+            xml_escape(&outline.xml_url),
+            xml_escape(&outline.html_url)
+        )
+    }
+
+    // folders sorted by name followed by the outlines without one, each sorted by title
+    pub fn to_opml(opml_title: &str, outlines: &[FeedOutline]) -> String {
+        let mut folders: BTreeMap<&str, Vec<&FeedOutline>> = BTreeMap::new();
+        let mut unfiled: Vec<&FeedOutline> = Vec::new();
+        for outline in outlines.iter() {
+            match &outline.folder {
+                Some(folder) => folders.entry(folder.as_str()).or_default().push(outline),
+                None => unfiled.push(outline),
+            }
+        }
+
+        let mut opml = String::new();
+        opml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        opml.push_str("<opml version=\"2.0\">\n");
+        opml.push_str("  <head>\n");
+        opml.push_str(&format!("    <title>{}</title>\n", xml_escape(opml_title)));
+        opml.push_str(&format!(
+            "    <dateCreated>{}</dateCreated>\n",
+            chrono::Utc::now().to_rfc2822()
+        ));
+        opml.push_str("  </head>\n");
+        opml.push_str("  <body>\n");
+        for (folder, mut folder_outlines) in folders.into_iter() {
+            folder_outlines.sort_by_key(|outline| outline.title.to_lowercase());
+            opml.push_str(&format!(
+                "    <outline text=\"{0}\" title=\"{0}\">\n",
+                xml_escape(folder)
+            ));
+            for outline in folder_outlines.iter() {
+                opml.push_str(&format!("      {}\n", to_outline_element(outline)));
+            }
+            opml.push_str("    </outline>\n");
+        }
+        unfiled.sort_by_key(|outline| outline.title.to_lowercase());
+        for outline in unfiled.iter() {
+            opml.push_str(&format!("    {}\n", to_outline_element(outline)));
+        }
+        opml.push_str("  </body>\n");
+        opml.push_str("</opml>\n");
+        opml
+    }
+
+    pub fn write_opml(
+        output_writer: &mut dyn Write,
+        opml_title: &str,
+        outlines: &[FeedOutline],
+    ) -> io::Result<()> {
+        output_writer.write_all(to_opml(opml_title, outlines).as_bytes())?;
+        output_writer.flush()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_to_opml() {
+            let rules = vec![FeedRuleConfig {
+                domain: "example.com".to_string(),
+                template: "https://example.com/rss/{slug}.xml?from={url}".to_string(),
+            }];
+            let mut gate = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://www.example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            gate.set_tags(vec![" isekai".to_string()]);
+            let mut akira =
+                MangaModel::new_from_required_elements("Akira & co", "https://other.com/akira/", 2)
+                    .unwrap();
+            akira.set_feed_url(Some("https://other.com/akira/feed".to_string()));
+            let mut mangadex = MangaModel::new_from_required_elements(
+                "Dr. Stone",
+                "https://other.com/dr-stone/",
+                3,
+            )
+            .unwrap();
+            mangadex.set_feed_url(Some(
+                "https://mangadex.org/title/a1c7c817-4e59-43b7-9365-09675a149a6f".to_string(),
+            ));
+
+            assert_eq!(
+                url_slug("https://example.com/manga/gate/"),
+                Some("gate".to_string())
+            );
+            let outlines: Vec<FeedOutline> = [&gate, &akira, &mangadex]
+                .iter()
+                .filter_map(|manga| to_feed_outline(manga, &rules))
+                .collect();
+            assert_eq!(outlines.len(), 2);
+            assert_eq!(
+                outlines[0].xml_url,
+                "https://example.com/rss/gate.xml?from=https%3A%2F%2Fwww.example.com%2Fmanga%2Fgate%2F"
+            );
+
+            let opml = to_opml("漫画", &outlines);
+            assert!(opml.contains(
+                "    <outline text=\"isekai\" title=\"isekai\">\n      <outline type=\"rss\" text=\"ゲート\""
+            ));
+            assert!(opml.contains(
+                "    </outline>\n    <outline type=\"rss\" text=\"Akira &amp; co\" title=\"Akira &amp; co\" xmlUrl=\"https://other.com/akira/feed\" htmlUrl=\"https://other.com/akira/\"/>\n  </body>"
+            ));
+        }
+    }
+}
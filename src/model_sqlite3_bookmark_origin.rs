@@ -0,0 +1,131 @@
+// SQLite3 bookmark origin: the Firefox bookmark(s) each manga row was imported from, so that a row can be traced
+// back to where it is bookmarked (the same url may be bookmarked in several folders, or in several profiles)
+// TABLE bookmark_origin:
+// 0: id (PRIMARY KEY)
+// 1: manga_id - foreign key to manga table
+// 2: profile (NOT NULL) - Firefox profile (or import source) the bookmark was read from, "" for a plain JSON backup
+// 3: folder_path (NOT NULL) - i.e. "toolbar/Manga/Ongoing"
+// 4: guid (NOT NULL) - GUID of the bookmark, UNIQUE per profile
+// 5: date_added_millis (NOT NULL) - epoch time i64 in milliseconds, when the bookmark was created in Firefox
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_bookmark_origin {
+    use rusqlite::{params, Connection, Result};
+    use serde::Serialize;
+    use std::{collections::HashMap, path::Path};
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct BookmarkOrigin {
+        pub id: i64,
+        pub manga_id: i64, // 0 until the bookmark is written (see upsert_bookmark_origins())
+        pub profile: String,
+        pub folder_path: String,
+        pub guid: String,
+        pub date_added_millis: i64,
+    }
+
+    pub fn create_bookmark_origin_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_bookmark_origin_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS bookmark_origin (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                manga_id INTEGER NOT NULL,
+                profile TEXT NOT NULL,
+                folder_path TEXT NOT NULL,
+                guid TEXT NOT NULL,
+                date_added_millis INTEGER NOT NULL,
+                UNIQUE(profile, guid),
+                FOREIGN KEY(manga_id) REFERENCES manga(id)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS bookmark_origin_manga_id ON bookmark_origin (manga_id)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // origins of the bookmarks just written by bulk_insert_manga()/bulk_upsert_manga(), matched to their rows by
+    // the GUID of the bookmark; a bookmark seen again (same profile and GUID) is updated to where it is now, i.e.
+    // moved to another folder.  Origins with no written row of the same GUID are skipped.  Returns number of rows
+    // written
+    pub fn upsert_bookmark_origins(
+        conn: &mut Connection,
+        origins: &[BookmarkOrigin],
+        written: &[MangaModel],
+    ) -> Result<usize> {
+        let manga_ids: HashMap<String, i64> = written
+            .iter()
+            .filter_map(|manga| manga.bookmark_guid().map(|guid| (guid, manga.id())))
+            .collect();
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let mut count = 0;
+            {
+                let mut upsert_stmt = transaction.prepare_cached(
+                    "INSERT INTO bookmark_origin (manga_id, profile, folder_path, guid, date_added_millis)
+                        VALUES (?1, ?2, ?3, ?4, ?5)
+                        ON CONFLICT(profile, guid) DO UPDATE SET
+                            manga_id = excluded.manga_id,
+                            folder_path = excluded.folder_path,
+                            date_added_millis = excluded.date_added_millis",
+                )?;
+                for origin in origins.iter() {
+                    if let Some(manga_id) = manga_ids.get(&origin.guid) {
+                        count += upsert_stmt.execute(params![
+                            manga_id,
+                            origin.profile,
+                            origin.folder_path,
+                            origin.guid,
+                            origin.date_added_millis,
+                        ])?;
+                    }
+                }
+            }
+            transaction.commit()?;
+            Ok(count)
+        })
+    }
+
+    // called by delete_manga() prior to deleting the manga row, otherwise the foreign key constraint fails
+    pub fn delete_bookmark_origins_of_manga(conn: &Connection, manga_id: i64) -> Result<usize> {
+        conn.execute(
+            "DELETE FROM bookmark_origin WHERE manga_id = ?1",
+            params![manga_id],
+        )
+    }
+
+    // oldest bookmark first; 0 rows is NOT an error (i.e. added by hand, or imported from CSV)
+    pub fn select_bookmark_origins_of_manga(
+        db_full_paths: &str,
+        manga_id: i64,
+    ) -> Result<Vec<BookmarkOrigin>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, manga_id, profile, folder_path, guid, date_added_millis FROM bookmark_origin
+                WHERE manga_id = ?1
+                ORDER BY date_added_millis ASC, id ASC",
+        )?;
+        let origins = stmt
+            .query_map(params![manga_id], |row| {
+                Ok(BookmarkOrigin {
+                    id: row.get(0)?,
+                    manga_id: row.get(1)?,
+                    profile: row.get(2)?,
+                    folder_path: row.get(3)?,
+                    guid: row.get(4)?,
+                    date_added_millis: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<BookmarkOrigin>>>()?;
+        Ok(origins)
+    }
+}
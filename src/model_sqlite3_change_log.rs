@@ -0,0 +1,153 @@
+// SQLite3 change log for `manga export --since`, which hands downstream systems only what changed: rows added
+// or modified are told by manga.updated_at, rows deleted leave a tombstone behind (the rows themselves are gone)
+// TABLE manga_tombstones:
+// 0: manga_id (PRIMARY KEY) - id of the manga row deleted; removed again if a row of that id is inserted later
+// 1: title (NOT NULL)
+// 2: url (NOT NULL)
+// 3: deleted_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// TABLE delta_export:
+// 0: id (PRIMARY KEY) - always 1, single row
+// 1: exported_at_millis (NOT NULL) - when the last export started, for `--since last-export`
+// Tombstones are kept by triggers on manga, so that deletions by any writer (CLI, servers, tui) are recorded
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_change_log {
+    use rusqlite::{params, OptionalExtension, Result};
+    use serde::Serialize;
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Tombstone {
+        pub manga_id: i64,
+        pub title: String,
+        pub url: String,
+        pub deleted_at_millis: i64,
+    }
+
+    // after the manga table is created
+    pub fn create_change_log_tables(db_full_paths: &str) -> Result<()> {
+        println!(">> create_change_log_tables('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS manga_tombstones (
+                manga_id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                url TEXT NOT NULL,
+                deleted_at_millis INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS delta_export (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                exported_at_millis INTEGER NOT NULL
+            );
+            CREATE TRIGGER IF NOT EXISTS manga_tombstones_after_delete AFTER DELETE ON manga BEGIN
+                INSERT OR REPLACE INTO manga_tombstones (manga_id, title, url, deleted_at_millis)
+                    VALUES (OLD.id, OLD.title, OLD.url,
+                        CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));
+            END;
+            CREATE TRIGGER IF NOT EXISTS manga_tombstones_after_insert AFTER INSERT ON manga BEGIN
+                DELETE FROM manga_tombstones WHERE manga_id = NEW.id;
+            END;",
+        )?;
+
+        Ok(())
+    }
+
+    // deleted on or after since_millis, oldest first; 0 rows is NOT an error
+    pub fn select_tombstones_since(
+        db_full_paths: &str,
+        since_millis: i64,
+    ) -> Result<Vec<Tombstone>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT manga_id, title, url, deleted_at_millis FROM manga_tombstones
+                WHERE deleted_at_millis >= ?1 ORDER BY deleted_at_millis, manga_id",
+        )?;
+        let rows = stmt.query_map(params![since_millis], |row| {
+            Ok(Tombstone {
+                manga_id: row.get(0)?,
+                title: row.get(1)?,
+                url: row.get(2)?,
+                deleted_at_millis: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // None when never exported
+    pub fn select_last_export_millis(db_full_paths: &str) -> Result<Option<i64>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.query_row(
+            "SELECT exported_at_millis FROM delta_export WHERE id = 1",
+            [],
+            |row| row.get::<usize, i64>(0),
+        )
+        .optional()
+    }
+
+    // after an export which started at exported_at_millis (taken before reading, so that what is written
+    // while exporting is in the next one)
+    pub fn mark_exported(db_full_paths: &str, exported_at_millis: i64) -> Result<()> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO delta_export (id, exported_at_millis) VALUES (1, ?1)",
+            params![exported_at_millis],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_change_log() {
+            let db_full_paths = "samples/test_change_log.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            crate::model_sqlite3_manga::model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            assert_eq!(select_last_export_millis(db_full_paths).unwrap(), None);
+
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute(
+                "INSERT INTO manga (id, title, url) VALUES (1, 'Gate', 'https://example.com/gate/'), (2, 'Akira', 'https://example.com/akira/')",
+                [],
+            )
+            .unwrap();
+            conn.execute("DELETE FROM manga WHERE id IN (1, 2)", [])
+                .unwrap();
+            let tombstones = select_tombstones_since(db_full_paths, 0).unwrap();
+            assert_eq!(
+                tombstones
+                    .iter()
+                    .map(|tombstone| (tombstone.manga_id, tombstone.title.as_str()))
+                    .collect::<Vec<(i64, &str)>>(),
+                vec![(1, "Gate"), (2, "Akira")]
+            );
+            let future_millis = tombstones[0].deleted_at_millis + 60_000;
+            assert!(select_tombstones_since(db_full_paths, future_millis)
+                .unwrap()
+                .is_empty());
+
+            // back again: no longer deleted
+            conn.execute(
+                "INSERT INTO manga (id, title, url) VALUES (1, 'Gate', 'https://example.com/gate/')",
+                [],
+            )
+            .unwrap();
+            assert_eq!(select_tombstones_since(db_full_paths, 0).unwrap().len(), 1);
+
+            mark_exported(db_full_paths, future_millis).unwrap();
+            assert_eq!(
+                select_last_export_millis(db_full_paths).unwrap(),
+                Some(future_millis)
+            );
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// SQLite3 connections of the model_sqlite3_* modules: the CLI, the servers and the daemon may all have the same
+// database open, so rather than failing right away with "database is locked" while another process writes, a
+// connection waits for it (busy_timeout), and writes which SQLite still refuses as busy (i.e. a transaction which
+// read before the other writer started, which waiting cannot help) are retried from the start with backoff.
+// What is still busy after that is an error for which is_busy() is true, see service::ServiceError::DatabaseBusy
+pub mod model_sqlite3_connection {
+    use rusqlite::{Connection, ErrorCode, Result};
+    use std::{path::Path, thread, time::Duration};
+
+    pub const K_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+    const K_BUSY_RETRIES: u32 = 4;
+    const K_FIRST_BACKOFF: Duration = Duration::from_millis(100); // doubled on each retry
+
+    // same as Connection::open(), waiting up to K_BUSY_TIMEOUT for other writers
+    pub fn open<P: AsRef<Path>>(db_full_paths: P) -> Result<Connection> {
+        let conn = Connection::open(db_full_paths)?;
+        conn.busy_timeout(K_BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
+
+    // SQLITE_BUSY (another connection holds the lock) or SQLITE_LOCKED (same connection, i.e. a pending statement)
+    pub fn is_busy(e: &rusqlite::Error) -> bool {
+        matches!(
+            e,
+            rusqlite::Error::SqliteFailure(failure, _)
+                if failure.code == ErrorCode::DatabaseBusy || failure.code == ErrorCode::DatabaseLocked
+        )
+    }
+
+    // write is run again while it fails with is_busy(), so it must be all-or-nothing, i.e. a single statement
+    // or a whole transaction (which SQLite rolls back when it fails)
+    pub fn with_busy_retry<T, F>(mut write: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut backoff = K_FIRST_BACKOFF;
+        for _ in 0..K_BUSY_RETRIES {
+            match write() {
+                Err(e) if is_busy(&e) => {
+                    println!("> with_busy_retry: {}, retrying in {:?}", e, backoff);
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+        write()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_with_busy_retry() {
+            let db_full_paths = "samples/test_with_busy_retry.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let writer = open(db_full_paths).unwrap();
+            writer
+                .execute("CREATE TABLE counter (n INTEGER)", [])
+                .unwrap();
+
+            // another process holds the write lock: not waited for, it is busy
+            let mut other = Connection::open(db_full_paths).unwrap();
+            let other_transaction = other
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+                .unwrap();
+            writer.busy_timeout(Duration::ZERO).unwrap();
+            let result = writer.execute("INSERT INTO counter (n) VALUES (1)", []);
+            assert!(is_busy(&result.unwrap_err()));
+
+            // until it is done
+            let mut attempts = 0;
+            let mut possible_other_transaction = Some(other_transaction);
+            let inserted = with_busy_retry(|| {
+                attempts += 1;
+                let result = writer.execute("INSERT INTO counter (n) VALUES (1)", []);
+                if let Some(other_transaction) = possible_other_transaction.take() {
+                    other_transaction.commit()?;
+                }
+                result
+            })
+            .unwrap();
+            assert_eq!((inserted, attempts), (1, 2));
+            assert!(!is_busy(&rusqlite::Error::QueryReturnedNoRows));
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
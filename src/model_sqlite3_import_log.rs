@@ -0,0 +1,121 @@
+// SQLite3 import log: how far the last `manga import` of each source got, checkpointed after every batch of
+// bookmarks written, so that an interrupted import can be resumed (`manga import <source> --resume`) where it
+// stopped rather than written all over again
+// TABLE import_log:
+// 0: source (PRIMARY KEY) - name of the source, i.e. "firefox_main"
+// 1: path (NOT NULL) - what was read
+// 2: total (NOT NULL) - bookmarks (or CSV rows) read
+// 3: position (NOT NULL) - how many of them are written, in the order read (for CSV, the last row written)
+// 4: last_guid - GUID of the last bookmark written (NULL for CSV), to tell that the source is still the same
+// 5: started_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// 6: updated_at_millis (NOT NULL) - time of the last checkpoint
+// 7: finished_at_millis - NULL until every bookmark is written
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_import_log {
+    use rusqlite::{params, Connection, OptionalExtension, Result};
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ImportLog {
+        pub source: String,
+        pub path: String,
+        pub total: usize,
+        pub position: usize,
+        pub last_guid: Option<String>,
+        pub started_at_millis: i64,
+        pub updated_at_millis: i64,
+        pub finished_at_millis: Option<i64>,
+    }
+
+    impl ImportLog {
+        pub fn is_finished(&self) -> bool {
+            self.finished_at_millis.is_some()
+        }
+    }
+
+    pub fn create_import_log_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_import_log_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS import_log (
+                source TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                total INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                last_guid TEXT,
+                started_at_millis INTEGER NOT NULL,
+                updated_at_millis INTEGER NOT NULL,
+                finished_at_millis INTEGER
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // a new import of source replaces whatever was logged of the previous one
+    pub fn start_import(conn: &Connection, source: &str, path: &str, total: usize) -> Result<()> {
+        let now_millis = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "INSERT OR REPLACE INTO import_log (source, path, total, position, last_guid, started_at_millis, updated_at_millis, finished_at_millis)
+                VALUES (?1, ?2, ?3, 0, NULL, ?4, ?4, NULL)",
+            params![source, path, total as i64, now_millis],
+        )?;
+        Ok(())
+    }
+
+    // after each batch written; position is the number of bookmarks written so far
+    pub fn checkpoint(
+        conn: &Connection,
+        source: &str,
+        position: usize,
+        possible_last_guid: Option<&str>,
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE import_log SET position = ?2, last_guid = ?3, updated_at_millis = ?4 WHERE source = ?1",
+            params![
+                source,
+                position as i64,
+                possible_last_guid,
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_import(conn: &Connection, source: &str) -> Result<()> {
+        conn.execute(
+            "UPDATE import_log SET finished_at_millis = ?2 WHERE source = ?1",
+            params![source, chrono::Utc::now().timestamp_millis()],
+        )?;
+        Ok(())
+    }
+
+    // None if source was never imported
+    pub fn select_import_log(db_full_paths: &str, source: &str) -> Result<Option<ImportLog>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.query_row(
+            "SELECT source, path, total, position, last_guid, started_at_millis, updated_at_millis, finished_at_millis
+                FROM import_log WHERE source = ?1",
+            params![source],
+            |row| {
+                Ok(ImportLog {
+                    source: row.get(0)?,
+                    path: row.get(1)?,
+                    total: row.get::<usize, i64>(2)? as usize,
+                    position: row.get::<usize, i64>(3)? as usize,
+                    last_guid: row.get(4)?,
+                    started_at_millis: row.get(5)?,
+                    updated_at_millis: row.get(6)?,
+                    finished_at_millis: row.get(7)?,
+                })
+            },
+        )
+        .optional()
+    }
+}
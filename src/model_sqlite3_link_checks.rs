@@ -0,0 +1,111 @@
+// SQLite3 link check log: one row per url probed by `manga verify-urls`, kept so that availability of
+// each source site can be followed over time (see stats::site_availability())
+// TABLE link_checks:
+// 0: id (PRIMARY KEY)
+// 1: manga_id - foreign key to manga table
+// 2: url (NOT NULL) - url as it was at the time of the check (manga.url may have changed since)
+// 3: status (NOT NULL) - final HTTP status after redirects, 0 when the site could not be reached at all
+// 4: response_millis (NOT NULL)
+// 5: checked_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_link_checks {
+    use rusqlite::{params, Connection, Result};
+    use serde::Serialize;
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    pub const K_STATUS_UNREACHABLE: u16 = 0;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct LinkCheck {
+        pub id: i64,
+        pub manga_id: i64,
+        pub url: String,
+        pub status: u16,
+        pub response_millis: i64,
+        pub checked_at_millis: i64,
+    }
+
+    impl LinkCheck {
+        // unreachable, 4xx and 5xx; redirects are followed before the status is recorded
+        pub fn is_failure(&self) -> bool {
+            self.status == K_STATUS_UNREACHABLE || self.status >= 400
+        }
+    }
+
+    pub fn create_link_checks_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_link_checks_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS link_checks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                manga_id INTEGER NOT NULL,
+                url TEXT NOT NULL,
+                status INTEGER NOT NULL,
+                response_millis INTEGER NOT NULL,
+                checked_at_millis INTEGER NOT NULL,
+                FOREIGN KEY(manga_id) REFERENCES manga(id)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS link_checks_checked_at ON link_checks(checked_at_millis)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // returns the new link check id
+    pub fn insert_link_check(
+        db_full_paths: &str,
+        manga_id: i64,
+        url: &str,
+        status: u16,
+        response_millis: i64,
+        checked_at_millis: i64,
+    ) -> Result<i64> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.execute(
+            "INSERT INTO link_checks (manga_id, url, status, response_millis, checked_at_millis) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![manga_id, url, status, response_millis, checked_at_millis],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // called by delete_manga() prior to deleting the manga row, otherwise the foreign key constraint fails
+    pub fn delete_link_checks_of_manga(conn: &Connection, manga_id: i64) -> Result<usize> {
+        conn.execute(
+            "DELETE FROM link_checks WHERE manga_id = ?1",
+            params![manga_id],
+        )
+    }
+
+    // checks where checked_at_millis >= since_millis, oldest first; 0 rows is NOT an error
+    pub fn select_link_checks(db_full_paths: &str, since_millis: i64) -> Result<Vec<LinkCheck>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, manga_id, url, status, response_millis, checked_at_millis FROM link_checks
+                WHERE checked_at_millis >= ?1
+                ORDER BY checked_at_millis ASC, id ASC",
+        )?;
+        let checks = stmt
+            .query_map(params![since_millis], |row| {
+                Ok(LinkCheck {
+                    id: row.get(0)?,
+                    manga_id: row.get(1)?,
+                    url: row.get(2)?,
+                    status: row.get(3)?,
+                    response_millis: row.get(4)?,
+                    checked_at_millis: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<LinkCheck>>>()?;
+        Ok(checks)
+    }
+}
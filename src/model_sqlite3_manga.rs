@@ -2,12 +2,25 @@
 // TABLE manga_no_id:
 // id, title, title_romanized, url, chapter, url_with_chapter, last_update, notes, tags
 pub mod model_sqlite3_manga {
-    use core::panic;
-    use rusqlite::types::FromSql;
+    use rusqlite::types::Value;
     use rusqlite::{params, Connection, Result, Row};
+    use std::collections::{BTreeMap, HashMap, HashSet};
     use std::path::Path;
 
-    use crate::model_manga::model_manga::MangaModel;
+    use crate::error::error::Error;
+    use crate::model_manga::model_manga::{Chapter, MangaModel};
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin;
+    use crate::model_sqlite3_change_log::model_sqlite3_change_log;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_import_log::model_sqlite3_import_log;
+    use crate::model_sqlite3_link_checks::model_sqlite3_link_checks;
+    use crate::model_sqlite3_manga_archive::model_sqlite3_manga_archive;
+    use crate::model_sqlite3_manga_fts::model_sqlite3_manga_fts;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata;
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions;
+    use crate::model_sqlite3_sync_state::model_sqlite3_sync_state;
+    use crate::model_sqlite3_url_history::model_sqlite3_url_history;
+    use crate::tag_expression::tag_expression;
 
     use crate::my_libs::make_none_if_empty;
 
@@ -32,6 +45,25 @@ pub mod model_sqlite3_manga {
     // 8: notes
     // 9: tags - foreign key to tag_group_maps table
     // 10: my_anime_list
+    // 11: feed_url - per-series RSS/Atom feed (update checker prefers this over scraping url)
+    // 12: latest_available_chapter - newest chapter the update checker has seen on the site
+    // 13: last_checked_millis - when the update checker last visited the site (epoch i64 in milliseconds)
+    // 14: url_checksum - CASTAGNOLI checksum of normalized_url(url), indexed, so that imports find an existing
+    //     row with an index lookup (NULL until computed, see backfill_url_checksums())
+    // 15: bookmark_guid - GUID of the Firefox bookmark it was imported from (indexed), so that a renamed or moved
+    //     bookmark still maps to its row
+    // 16: domain - host of url (i.e. "www.example.com", "" when url has none), indexed, so that per-site queries
+    //     (see select_by_domain()) need not parse every url; written along with url
+    // 17: added_millis - when the row was first written (epoch i64 in milliseconds), indexed like
+    //     last_update_millis for updated_between()/added_between()
+    // 18: chapter_numeric - chapter as a number (see chapter_numeric()), NULL when chapter is not one, indexed, so
+    //     that "chapter >= 100" and ordering by progress do not compare strings; written along with chapter
+    // 19: user_fields - JSON object of MangaModel::user_fields() (i.e. {"rating": "9"}), NULL when there are
+    //     none; personal fields need no column of their own, see get_field()/set_field()/select_by_field()
+    // 20: updated_at - last modification of the row (epoch i64 in milliseconds), kept by the triggers of
+    //     create_manga_indexes() whatever wrote the row (CLI, import, servers, or by hand), writers never set it
+    // 21: alt_titles - JSON array of MangaModel::alt_titles(), the titles the row had before an import renamed it
+    //     (see upsert_manga_with()), NULL when there are none
     // append new columns to the end of the list, never between
     // Schemas:
     // CREATE TABLE manga (
@@ -59,7 +91,7 @@ pub mod model_sqlite3_manga {
     fn create_manga_table(db_full_paths: &str) -> Result<()> {
         println!(">> create_manga_table('{}')", db_full_paths);
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS manga (
@@ -74,6 +106,17 @@ pub mod model_sqlite3_manga {
                 notes TEXT,
                 tags TEXT,  -- just preserve the tags that may have come from original
                 my_anime_list TEXT,
+                feed_url TEXT,
+                latest_available_chapter TEXT,
+                last_checked_millis INTEGER,
+                url_checksum INTEGER,
+                bookmark_guid TEXT,
+                domain TEXT,
+                added_millis INTEGER,
+                chapter_numeric REAL,
+                user_fields TEXT,
+                updated_at INTEGER,
+                alt_titles TEXT,
                 UNIQUE(title, url)
             )",
             [],
@@ -87,7 +130,7 @@ pub mod model_sqlite3_manga {
         println!(">> create_manga_to_tags_map_table('{}')", db_full_paths);
 
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS manga_to_tags_map (
@@ -107,7 +150,7 @@ pub mod model_sqlite3_manga {
         println!(">> create_tags_table('{}')", db_full_paths);
 
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS tags (
@@ -121,19 +164,424 @@ pub mod model_sqlite3_manga {
         Ok(())
     }
 
+    // databases created by older versions will not have the appended columns, and because
+    // 'CREATE TABLE IF NOT EXISTS' will not alter an existing table, we'll ALTER TABLE for each
+    // missing column (in the same order as they are listed above, so that column-index stays consistent)
+    fn add_missing_manga_columns(db_full_paths: &str) -> Result<()> {
+        let appended_columns = [
+            ("feed_url", "TEXT"),
+            ("latest_available_chapter", "TEXT"),
+            ("last_checked_millis", "INTEGER"),
+            ("url_checksum", "INTEGER"),
+            ("bookmark_guid", "TEXT"),
+            ("domain", "TEXT"),
+            ("added_millis", "INTEGER"),
+            ("chapter_numeric", "REAL"),
+            ("user_fields", "TEXT"),
+            ("updated_at", "INTEGER"),
+            ("alt_titles", "TEXT"),
+        ];
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        let mut stmt = conn.prepare("PRAGMA table_info(manga)")?;
+        let existing_columns = stmt
+            .query_map([], |row| row.get::<usize, String>(1))? // 1: name
+            .collect::<Result<Vec<String>>>()?;
+        for (column_name, column_type) in appended_columns.iter() {
+            if existing_columns.iter().any(|c| c == column_name) {
+                continue;
+            }
+            println!(
+                ">> add_missing_manga_columns('{}'): adding column '{}'",
+                db_full_paths, column_name
+            );
+            conn.execute(
+                format!("ALTER TABLE manga ADD COLUMN {} {}", column_name, column_type).as_str(),
+                [],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // not UNIQUE: a 32 bit checksum can collide, and older databases may already hold look-alike rows (see
+    // duplicate_groups()).  Only the import path writes the checksum; any other writer that changes the url
+    // (or inserts without it) leaves it NULL through the trigger, and it is recomputed before the next import.
+    // bookmark_guid is not UNIQUE either, the same bookmark synced to several profiles is the same row.
+    // domain and chapter_numeric have no such trigger: every writer of url (chapter) writes it, and rows from
+    // older versions are backfilled.  updated_at is only ever written by triggers: a row inserted without it
+    // gets the current time, and so does any UPDATE that does not set it itself, always later than before even
+    // within the same millisecond (so that sync_changes can tell the trigger's own write apart); rows of older
+    // versions are left NULL, hence untouched, until backfill_updated_at()
+    fn create_manga_indexes(db_full_paths: &str) -> Result<()> {
+        println!(">> create_manga_indexes('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS manga_url_checksum ON manga (url_checksum);
+            CREATE INDEX IF NOT EXISTS manga_bookmark_guid ON manga (bookmark_guid);
+            CREATE INDEX IF NOT EXISTS manga_domain ON manga (domain);
+            CREATE INDEX IF NOT EXISTS manga_last_update_millis ON manga (last_update_millis);
+            CREATE INDEX IF NOT EXISTS manga_added_millis ON manga (added_millis);
+            CREATE INDEX IF NOT EXISTS manga_chapter_numeric ON manga (chapter_numeric);
+            CREATE TRIGGER IF NOT EXISTS manga_url_checksum_after_update_url AFTER UPDATE OF url ON manga
+                WHEN OLD.url IS NOT NEW.url AND NEW.url_checksum IS OLD.url_checksum BEGIN
+                    UPDATE manga SET url_checksum = NULL WHERE id = NEW.id;
+                END;
+            CREATE TRIGGER IF NOT EXISTS manga_updated_at_after_insert AFTER INSERT ON manga
+                WHEN NEW.updated_at IS NULL BEGIN
+                    UPDATE manga SET updated_at = CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)
+                        WHERE id = NEW.id;
+                END;
+            CREATE TRIGGER IF NOT EXISTS manga_updated_at_after_update AFTER UPDATE ON manga
+                WHEN OLD.updated_at IS NOT NULL AND NEW.updated_at IS OLD.updated_at BEGIN
+                    UPDATE manga SET updated_at =
+                            MAX(CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER), OLD.updated_at + 1)
+                        WHERE id = NEW.id;
+                END;",
+        )?;
+
+        Ok(())
+    }
+
+    // same series whatever the scheme, "www." or trailing "/": "https://www.Example.com/gate/" is "example.com/gate"
+    pub fn normalized_url(url: &str) -> String {
+        let url = url.to_lowercase();
+        let without_scheme = url.split("://").last().unwrap_or_default();
+        without_scheme
+            .trim_start_matches("www.")
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    pub fn url_checksum(url: &str) -> u32 {
+        crate::model_manga::CASTAGNOLI.checksum(normalized_url(url).as_bytes())
+    }
+
+    // returns number of rows whose url_checksum was (re)computed
+    fn backfill_url_checksums(conn: &Connection) -> Result<usize> {
+        let mut stmt = conn.prepare("SELECT id, url FROM manga WHERE url_checksum IS NULL")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<usize, i64>(0)?, row.get::<usize, String>(1)?))
+            })?
+            .collect::<Result<Vec<(i64, String)>>>()?;
+        let mut update_stmt = conn.prepare_cached("UPDATE manga SET url_checksum = ?1 WHERE id = ?2")?;
+        for (id, url) in rows.iter() {
+            update_stmt.execute(params![url_checksum(url), id])?;
+        }
+        Ok(rows.len())
+    }
+
+    // i.e. "https://www.Example.com/gate/" => "www.example.com"; "" if the url cannot be parsed or has no host
+    pub fn url_domain(url: &str) -> String {
+        match url::Url::parse(url) {
+            Ok(parsed_url) => parsed_url.host_str().unwrap_or_default().to_string(),
+            Err(_) => String::new(),
+        }
+    }
+
+    // rows written before the domain column existed (or by hand); returns number of rows updated
+    fn backfill_domains(conn: &Connection) -> Result<usize> {
+        let mut stmt = conn.prepare("SELECT id, url FROM manga WHERE domain IS NULL")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<usize, i64>(0)?, row.get::<usize, String>(1)?))
+            })?
+            .collect::<Result<Vec<(i64, String)>>>()?;
+        let mut update_stmt = conn.prepare_cached("UPDATE manga SET domain = ?1 WHERE id = ?2")?;
+        for (id, url) in rows.iter() {
+            update_stmt.execute(params![url_domain(url), id])?;
+        }
+        Ok(rows.len())
+    }
+
+    // i.e. "12" => 12.0, "12.1" (or "12-1") => 12.001, see Chapter::numeric(); None for "extra", "" and the like
+    pub fn chapter_numeric(chapter: &str) -> Option<f64> {
+        Chapter::parse(chapter).map(|chapter| chapter.numeric())
+    }
+
+    // rows written before the chapter_numeric column existed (or whose chapter was written by hand); rows whose
+    // chapter is not a number stay NULL.  Returns number of rows updated
+    fn backfill_chapter_numerics(conn: &Connection) -> Result<usize> {
+        let mut stmt = conn.prepare(
+            "SELECT id, chapter FROM manga WHERE chapter_numeric IS NULL AND chapter IS NOT NULL AND chapter != ''",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<usize, i64>(0)?, row.get::<usize, String>(1)?))
+            })?
+            .collect::<Result<Vec<(i64, String)>>>()?;
+        let mut update_stmt = conn.prepare_cached("UPDATE manga SET chapter_numeric = ?1 WHERE id = ?2")?;
+        let mut rows_updated = 0;
+        for (id, chapter) in rows.iter() {
+            if let Some(numeric) = chapter_numeric(chapter) {
+                rows_updated += update_stmt.execute(params![numeric, id])?;
+            }
+        }
+        Ok(rows_updated)
+    }
+
+    // rows written before the added_millis column existed: when their bookmark was added in Firefox if known
+    // (see model_sqlite3_bookmark_origin), else their last update; returns number of rows updated
+    fn backfill_added_millis(conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "UPDATE manga SET added_millis = COALESCE(
+                    (SELECT MIN(o.date_added_millis) FROM bookmark_origin AS o WHERE o.manga_id = manga.id),
+                    last_update_millis,
+                    ?1)
+                WHERE added_millis IS NULL",
+            params![chrono::Local::now().timestamp_millis()],
+        )
+    }
+
+    // NULL rather than "{}" when there are none
+    fn user_fields_json(manga: &MangaModel) -> Option<String> {
+        match manga.user_fields().is_empty() {
+            true => None,
+            false => serde_json::to_string(manga.user_fields()).ok(),
+        }
+    }
+
+    // values written by hand (i.e. json_set(user_fields, '$.rating', 9)) need not be strings, they are read as
+    // their JSON text; anything but a JSON object is taken as no fields
+    fn user_fields_from_json(possible_json: Option<String>) -> BTreeMap<String, String> {
+        let object = possible_json
+            .and_then(|json| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&json).ok())
+            .unwrap_or_default();
+        object
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(text) => (key, text),
+                other => (key, other.to_string()),
+            })
+            .collect()
+    }
+
+    // JSON1 path of a field, i.e. "rating" => "$.\"rating\""; keys are quoted so that "my list" or "a.b" are a
+    // single field, hence cannot contain '"' themselves
+    fn user_field_path(key: &str) -> Result<String> {
+        let key = key.trim();
+        if key.is_empty() || key.contains('"') || key.chars().any(|c| c.is_control()) {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "invalid field name '{}'",
+                key
+            )));
+        }
+        Ok(format!("$.\"{}\"", key))
+    }
+
+    // NULL rather than "[]" when there are none
+    fn alt_titles_json(manga: &MangaModel) -> Option<String> {
+        match manga.alt_titles().is_empty() {
+            true => None,
+            false => serde_json::to_string(manga.alt_titles()).ok(),
+        }
+    }
+
+    // anything but a JSON array of strings is taken as none
+    fn alt_titles_from_json(possible_json: Option<String>) -> Vec<String> {
+        possible_json
+            .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+            .unwrap_or_default()
+    }
+
+    // rows written before the updated_at column existed: their last update, else when they were added
+    fn backfill_updated_at(conn: &Connection) -> Result<usize> {
+        conn.execute(
+            "UPDATE manga SET updated_at = COALESCE(last_update_millis, added_millis, ?1) WHERE updated_at IS NULL",
+            params![chrono::Local::now().timestamp_millis()],
+        )
+    }
+
     pub fn create_tables(db_full_paths: &str) -> Result<()> {
         println!("> create_tables('{}')", db_full_paths);
         create_manga_table(db_full_paths)?;
+        add_missing_manga_columns(db_full_paths)?;
+        create_manga_indexes(db_full_paths)?;
+        backfill_domains(&model_sqlite3_connection::open(Path::new(db_full_paths))?)?;
+        backfill_chapter_numerics(&model_sqlite3_connection::open(Path::new(db_full_paths))?)?;
         create_manga_to_tags_map_table(db_full_paths)?;
         create_tags_table(db_full_paths)?;
+        model_sqlite3_reading_sessions::create_reading_sessions_table(db_full_paths)?;
+        model_sqlite3_url_history::create_url_history_table(db_full_paths)?;
+        model_sqlite3_link_checks::create_link_checks_table(db_full_paths)?;
+        model_sqlite3_manga_metadata::create_manga_metadata_table(db_full_paths)?;
+        model_sqlite3_manga_fts::create_manga_fts_table(db_full_paths)?;
+        model_sqlite3_sync_state::create_sync_tables(db_full_paths)?;
+        model_sqlite3_change_log::create_change_log_tables(db_full_paths)?;
+        model_sqlite3_manga_archive::create_manga_archive_table(db_full_paths)?;
+        model_sqlite3_bookmark_origin::create_bookmark_origin_table(db_full_paths)?;
+        model_sqlite3_import_log::create_import_log_table(db_full_paths)?;
+        backfill_added_millis(&model_sqlite3_connection::open(Path::new(db_full_paths))?)?;
+        backfill_updated_at(&model_sqlite3_connection::open(Path::new(db_full_paths))?)?;
 
         Ok(())
     }
 
-    // sql_where_clause - example: "WHERE title LIKE ?1 AND url LIKE ?2"
-    fn select_manga(
+    // the CREATE TABLE/INDEX/TRIGGER/VIEW statements of a database, in the order they were made (so that each one
+    // only refers to what comes before it); columns added by ALTER TABLE are part of their CREATE TABLE, SQLite's
+    // own tables and the shadow tables of virtual tables (made by the CREATE VIRTUAL TABLE) are left out
+    pub fn select_schema_ddl(db_full_paths: &str) -> Result<Vec<String>> {
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let mut stmt = conn.prepare(
+            "SELECT s.sql FROM sqlite_master AS s
+                WHERE s.sql IS NOT NULL
+                    AND s.name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+                    AND NOT EXISTS (
+                        SELECT 1 FROM sqlite_master AS v
+                            WHERE v.type = 'table' AND v.sql LIKE 'CREATE VIRTUAL TABLE%'
+                                AND s.name LIKE v.name || '\\_%' ESCAPE '\\'
+                    )
+                ORDER BY s.rowid",
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<usize, String>(0))?;
+        rows.collect()
+    }
+
+    // what create_tables() makes of an empty database (i.e. `manga schema`), built in the temp directory so that
+    // it is the schema of this version rather than of whichever database is at hand
+    pub fn schema_ddl() -> Result<Vec<String>> {
+        let db_full_paths = std::env::temp_dir()
+            .join(format!("manga-schema-{}.sqlite3", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        std::fs::remove_file(&db_full_paths).unwrap_or_default();
+        let ddl = create_tables(&db_full_paths).and_then(|()| select_schema_ddl(&db_full_paths));
+        std::fs::remove_file(&db_full_paths).unwrap_or_default();
+        ddl
+    }
+
+    // what select_manga() reads besides the manga columns; listings that never display tags (i.e. the update
+    // checker) can skip them, and ask tags_for()/tags_for_many() later if they turn out to be needed
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SelectOptions {
+        pub with_tags: bool,
+    }
+
+    impl Default for SelectOptions {
+        fn default() -> Self {
+            SelectOptions { with_tags: true }
+        }
+    }
+
+    // how a MangaFilter compares a text column: Exact is "=", Like is SQLite LIKE ('%' and '_' are wildcards)
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum TextMatch {
+        Exact(String),
+        Like(String),
+    }
+
+    // which rows select_manga() returns: those for which every condition given holds (all rows when none is).
+    // The values are bound as parameters of the statement rather than written into the SQL, so that a title
+    // such as "Frieren's Journey" is just a title
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct MangaFilter {
+        pub possible_title: Option<TextMatch>,
+        pub possible_url: Option<TextMatch>,
+        pub possible_tag: Option<String>, // compared as tag expressions do: trimmed, ignoring case
+        pub possible_min_id: Option<i64>, // inclusive
+        pub possible_max_id: Option<i64>, // inclusive
+        pub possible_ids: Option<Vec<i64>>, // any of them
+    }
+
+    impl MangaFilter {
+        // "WHERE m.title = ?1 AND ..." (empty when there is no condition), with the values of ?1, ?2, .. in order
+        fn to_where_clause(&self) -> (String, Vec<Value>) {
+            let mut conditions: Vec<String> = Vec::new();
+            let mut sql_params: Vec<Value> = Vec::new();
+            for (column, possible_match) in [("m.title", &self.possible_title), ("m.url", &self.possible_url)] {
+                let (operator, text) = match possible_match {
+                    Some(TextMatch::Exact(text)) => ("=", text),
+                    Some(TextMatch::Like(pattern)) => ("LIKE", pattern),
+                    None => continue,
+                };
+                sql_params.push(Value::Text(text.clone()));
+                conditions.push(format!("{} {} ?{}", column, operator, sql_params.len()));
+            }
+            if let Some(tag) = &self.possible_tag {
+                sql_params.push(Value::Text(tag.trim().to_string()));
+                conditions.push(format!(
+                    "EXISTS (SELECT 1 FROM manga_to_tags_map AS filter_mt JOIN tags AS filter_t ON filter_t.id = filter_mt.tag_id \
+                     WHERE filter_mt.manga_id = m.id AND TRIM(filter_t.tag) = ?{} COLLATE NOCASE)",
+                    sql_params.len()
+                ));
+            }
+            for (operator, possible_id) in [(">=", self.possible_min_id), ("<=", self.possible_max_id)] {
+                if let Some(id) = possible_id {
+                    sql_params.push(Value::Integer(id));
+                    conditions.push(format!("m.id {} ?{}", operator, sql_params.len()));
+                }
+            }
+            if let Some(ids) = &self.possible_ids {
+                let mut placeholders = Vec::new();
+                for id in ids {
+                    sql_params.push(Value::Integer(*id));
+                    placeholders.push(format!("?{}", sql_params.len()));
+                }
+                conditions.push(format!("m.id IN ({})", placeholders.join(",")));
+            }
+            match conditions.is_empty() {
+                true => (String::new(), sql_params),
+                false => (format!("WHERE {}", conditions.join(" AND ")), sql_params),
+            }
+        }
+    }
+
+    // tags of the rows matching sql_where_clause (same as select_manga_where()), keyed by manga id, in a single
+    // query rather than a correlated subquery per row; rows without tags are not in the map
+    fn select_tags_by_manga_id(
+        conn: &Connection,
+        sql_where_clause: &str,
+        sql_params: &[Value],
+    ) -> Result<HashMap<i64, Vec<String>>> {
+        let select_stmt = format!(
+            "SELECT mt.manga_id, GROUP_CONCAT(t.tag, ', ')
+                FROM manga_to_tags_map AS mt
+                JOIN tags AS t ON mt.tag_id = t.id
+                WHERE mt.manga_id IN (SELECT m.id FROM manga AS m {})
+                GROUP BY mt.manga_id ;",
+            sql_where_clause
+        );
+        let mut stmt = conn.prepare(select_stmt.as_str())?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(sql_params), |row| {
+            Ok((
+                row.get::<usize, i64>(0)?,
+                row.get::<usize, String>(1)?, // i.e. "isekai, seinen" (hence " seinen" once split)
+            ))
+        })?;
+        let mut tags_by_manga_id = HashMap::new();
+        for row in rows {
+            let (manga_id, tags) = row?;
+            tags_by_manga_id.insert(manga_id, tags.split(",").map(|s| s.to_string()).collect());
+        }
+        Ok(tags_by_manga_id)
+    }
+
+    // rows matching filter (see MangaFilter), in no particular order; 0 rows is NOT an error
+    pub fn select_manga(
+        db_full_paths: &str,
+        filter: &MangaFilter,
+        options: SelectOptions,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let (sql_where_clause, sql_params) = filter.to_where_clause();
+        Ok(select_manga_where(
+            db_full_paths,
+            sql_where_clause.as_str(),
+            &sql_params,
+            options,
+        )?)
+    }
+
+    // sql_where_clause - example: "WHERE m.title LIKE ?1 AND m.url LIKE ?2", with sql_params the values of ?1 and ?2;
+    // whatever comes from the user goes in sql_params, never in sql_where_clause
+    fn select_manga_where(
         db_full_paths: &str,
-        sql_where_clause: &str, /* , args: &[&str] */
+        sql_where_clause: &str,
+        sql_params: &[Value],
+        options: SelectOptions,
     ) -> Result<Vec<MangaModel>> {
         let select_stmt =
         format!(
@@ -147,14 +595,18 @@ pub mod model_sqlite3_manga {
             // 7: m.last_update_millis, // NOTE: This is i64, but we'll convert it to string because rusqlite doesn't support i64 in params! macro 
             // 8: m.notes, 
             // 9: m.my_anime_list, 
-            // 10: tag
+            // 10: m.feed_url
+            // 11: m.latest_available_chapter
+            // 12: m.last_checked_millis
+            // 13: m.bookmark_guid
+            // 14: m.user_fields
+            // 15: m.updated_at
+            // 16: m.alt_titles
+            // (tags are not a column, see select_tags_by_manga_id())
             "SELECT m.id, m.title, m.title_romanized, m.url, m.url_with_chapter, m.chapter, m.last_update, m.last_update_millis, m.notes, m.my_anime_list,
-                    (SELECT GROUP_CONCAT(t.tag, ', ')
-                        FROM manga_to_tags_map AS mt
-                        JOIN tags AS t ON mt.tag_id = t.id
-                        WHERE mt.manga_id = m.id) AS tags
+                    m.feed_url, m.latest_available_chapter, m.last_checked_millis, m.bookmark_guid, m.user_fields, m.updated_at, m.alt_titles
                 FROM manga AS m {} ;", sql_where_clause);   // two ways to return ALL row-sets, either set sql_where_clause="", or set it to sql_where_clause="WHERE m.title LIKE '%" or something like that
-        match Connection::open(db_full_paths) {
+        match model_sqlite3_connection::open(db_full_paths) {
             Ok(conn) => {
                 #[cfg(debug_assertions)]
                 {
@@ -162,10 +614,7 @@ pub mod model_sqlite3_manga {
                 }
                 match conn.prepare(select_stmt.as_str()) {
                     Ok(mut stmt) => {
-                        //let args_joined = args.join(",");   // Join the strings with a delimiter (as params)
-                        //let sql_params = params![&args_joined]; // Now you can use args_joined in params!
-                        let sql_params_empty = params![]; // Empty because it was decided it's EASIER to pass in the sql_where_clause as a string (preconstructed with assumptions that table is referenced as 'm.*')
-                        match stmt.query(sql_params_empty) {
+                        match stmt.query(rusqlite::params_from_iter(sql_params)) {
                             Ok(mut rowsets) => {
                                 #[cfg(debug_assertions)]
                                 {
@@ -207,9 +656,11 @@ pub mod model_sqlite3_manga {
                                         }
                                     }
                                 } ;
-                                let transform_row = |row: &Row| -> Result<MangaModel> {
-                                    Ok(MangaModel::with_values(
-                                        row.get(0)?,
+                                // a row that is not a valid manga (i.e. its url does not parse) is skipped with a
+                                // warning rather than failing the whole select, a database error still fails it
+                                let transform_row = |row: &Row| -> Result<Option<MangaModel>> {
+                                    let mut manga = match MangaModel::with_values(
+                                        row.get::<usize, i64>(0)?,
                                         row.get(1)?,
                                         transform_column_str(row.get(2))?,
                                         row.get(3)?,
@@ -218,15 +669,28 @@ pub mod model_sqlite3_manga {
                                         transform_column_str(row.get(6))?,  // 6: m.last_update
                                         transform_column_i64(row.get(7))?,  // 7: m.last_update_millis - note, unsure how, but it knows to dynamically cast this as i64...
                                         transform_column_str(row.get(8))?,  // 8: m.notes
-                                        match row.get::<usize, String>(10) {
-                                            Ok(t) => t.split(",").map(|s| s.to_string()).collect(),
-                                            Err(_) => Vec::new(),
-                                        },
+                                        Vec::new(), // tags are set below, all at once
                                         transform_column_str(row.get(9))?,  // 9: m.my_anime_list
-                                    ))
+                                    ) {
+                                        Ok(manga) => manga,
+                                        Err(e) => {
+                                            println!("WARNING: select_manga - skipping malformed row id={}: {}", row.get::<usize, i64>(0)?, e);
+                                            return Ok(None);
+                                        }
+                                    };
+                                    manga.set_feed_url(transform_column_str(row.get(10))?);  // 10: m.feed_url
+                                    manga.set_latest_available_chapter(transform_column_str(row.get(11))?);  // 11: m.latest_available_chapter
+                                    manga.set_last_checked_millis(transform_column_i64(row.get(12))?);  // 12: m.last_checked_millis
+                                    manga.set_bookmark_guid(transform_column_str(row.get(13))?);  // 13: m.bookmark_guid
+                                    manga.set_user_fields(user_fields_from_json(row.get(14)?));  // 14: m.user_fields
+                                    manga.set_updated_at_millis(transform_column_i64(row.get(15))?);  // 15: m.updated_at
+                                    manga.set_alt_titles(alt_titles_from_json(row.get(16)?));  // 16: m.alt_titles
+                                    Ok(Some(manga))
                                 };
                                 while let Some(row) = possible_next_row {
-                                    manga_data.push(transform_row(&row)?);
+                                    if let Some(manga) = transform_row(&row)? {
+                                        manga_data.push(manga);
+                                    }
                                     possible_next_row = match rowsets.next() {
                                         Ok(r) => r,
                                         Err(e) => {
@@ -235,6 +699,14 @@ pub mod model_sqlite3_manga {
                                         }
                                     };
                                 }
+                                if options.with_tags && !manga_data.is_empty() {
+                                    let mut tags_by_manga_id = select_tags_by_manga_id(&conn, sql_where_clause, sql_params)?;
+                                    for manga in manga_data.iter_mut() {
+                                        if let Some(tags) = tags_by_manga_id.remove(&manga.id()) {
+                                            manga.set_tags(tags);
+                                        }
+                                    }
+                                }
                                 // NOTE: It is NOT AN ERROR if we get 0 rows, so we'll just return empty Vec
                                 // but it is up to the caller to treat it as Error::QueryReturnedNoRows (i.e. get_id(ID) where ID SHOULD have existed)
                                 Ok(manga_data)
@@ -260,13 +732,16 @@ pub mod model_sqlite3_manga {
     }
 
     // Insert MangaModel (without id field, id=0) and associate tags if any, and return new MangaModel with real/valid id
-    pub fn insert_manga(db_full_paths: &str, manga_no_id: &MangaModel) -> Result<MangaModel> {
+    pub fn insert_manga(
+        db_full_paths: &str,
+        manga_no_id: &MangaModel,
+    ) -> std::result::Result<MangaModel, Error> {
         #[cfg(debug_assertions)]
         {
             println!("INSERT manga (no ID): {:?}", manga_no_id);
         }
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         // Option based vars needs to become concrete before we can use them in query
         // first, insert MangaModel so that we can get the id
@@ -275,7 +750,7 @@ pub mod model_sqlite3_manga {
         let current_time_as_millis = chrono::Local::now().timestamp_millis();
 
         conn.execute(
-            "INSERT OR IGNORE INTO manga (title, title_romanized, url, url_with_chapter, chapter, last_update, last_update_millis, notes, tags, my_anime_list) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT OR IGNORE INTO manga (title, title_romanized, url, url_with_chapter, chapter, last_update, last_update_millis, notes, tags, my_anime_list, feed_url, latest_available_chapter, bookmark_guid, domain, added_millis, chapter_numeric, user_fields, alt_titles) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, NULLIF(?16, ''), NULLIF(?17, ''), NULLIF(?18, ''))",
             &[
                 &manga_no_id.title(),   // ?1
                 match &manga_no_id.title_romanized() { Some(t) => &t.as_str(), None => "" },  // ?2
@@ -287,11 +762,19 @@ pub mod model_sqlite3_manga {
                 match &manga_no_id.notes() { Some(t) => &t.as_str(), None => "" },    // ?8
                 &manga_no_id.tags().join(","),  // ?9
                 match &manga_no_id.my_anime_list (){ Some(t) => &t.as_str(), None => "" },    // ?10
+                match &manga_no_id.feed_url() { Some(t) => &t.as_str(), None => "" },    // ?11
+                match &manga_no_id.latest_available_chapter() { Some(t) => &t.as_str(), None => "" },    // ?12
+                match &manga_no_id.bookmark_guid() { Some(t) => &t.as_str(), None => "" },    // ?13
+                &url_domain(manga_no_id.url()),    // ?14
+                current_time_as_millis.to_string().as_str(),  // ?15
+                chapter_numeric(&manga_no_id.chapter().unwrap_or("0".to_string())).map(|numeric| numeric.to_string()).unwrap_or_default().as_str(),  // ?16 - "" is NULL
+                user_fields_json(manga_no_id).unwrap_or_default().as_str(),  // ?17 - "" is NULL
+                alt_titles_json(manga_no_id).unwrap_or_default().as_str(),  // ?18 - "" is NULL
                 ],
         )?; //bail on error
 
         // update MangaModel with the id
-        let id = conn.last_insert_rowid() as u32;
+        let id = conn.last_insert_rowid();
         let mut manga = manga_no_id.clone();
         manga.set_id(id);
 
@@ -309,10 +792,10 @@ pub mod model_sqlite3_manga {
                 // insert tag if not exists (case insensitive)
                 conn.execute("INSERT OR IGNORE INTO tags (tag) VALUES (?1)", &[&tag])?;
                 // get tag id
-                let mut stmt = conn.prepare("SELECT id FROM tags WHERE tag = ?1")?;
-                let mut tag_iter = stmt.query_map(&[&tag], |row| Ok(row.get(0)?))?;
-
-                let tag_id = tag_iter.next().unwrap().unwrap();
+                let tag_id: i64 =
+                    conn.query_row("SELECT id FROM tags WHERE tag = ?1", [&tag], |row| {
+                        row.get(0)
+                    })?;
                 // insert tag id and manga id into manga_to_tags_map table if the pair does not yet exists (shouldn't exists, but just in case)
                 match conn.execute(
                     "INSERT OR IGNORE INTO manga_to_tags_map (manga_id, tag_id) VALUES (?1, ?2)",
@@ -342,13 +825,12 @@ pub mod model_sqlite3_manga {
         Ok(manga.clone())
     }
 
-    // update based on id field - note that return is stdError rather than updated MangaModel, since what's being
+    // update based on id field - note that return is Error rather than updated MangaModel, since what's being
     // updated was/is what was passed in here
     pub fn update_manga(
         db_full_paths: &str,
         manga: &MangaModel,
-        //) -> Result<(), Box<dyn std::error::Error + 'static>> {
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> std::result::Result<(), Error> {
         #[cfg(debug_assertions)]
         {
             println!(
@@ -356,30 +838,42 @@ pub mod model_sqlite3_manga {
                 db_full_paths, manga
             )
         }
-        // fail if id (u32) is 0
+        // fail if id is 0
         if manga.id() == 0 {
-            return Err("id cannot be 0".into());
+            return Err(Error::InvalidId(manga.id()));
         }
 
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         // if we cannot locate id in manga table during update, return error (most likely got deleted)
         // query for id and title (just in case we need to return the title)
         let mut stmt = conn.prepare("SELECT id FROM manga WHERE id = ?1")?; // returns either 0 or 1 row
         let manga_iter = stmt.query_map(&[&manga.id()], |row| {
-            row.get::<usize, i32>(0) // id is i32 type...
+            row.get::<usize, i64>(0) // rowid
         })?;
         if manga_iter.count() == 0 {
-            return Err("id not found".into());
+            return Err(Error::NotFound(manga.id()));
+        }
+
+        // OK, id exists, so proceed with update
+        update_manga_with_connection(&conn, manga)?;
+
+        #[cfg(debug_assertions)]
+        {
+            println!("> UPDATE succeeded for ID: {}", manga.id());
         }
+        Ok(())
+    }
 
+    // the UPDATE of update_manga(), on the caller's connection (or transaction) without checking the id first;
+    // returns the number of rows updated, 0 when there is no row of that id
+    fn update_manga_with_connection(conn: &Connection, manga: &MangaModel) -> Result<usize> {
         let current_time_as_yyyymmddhhmmss =
             chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let current_time_as_millis = chrono::Local::now().timestamp_millis();
-        // OK, id exists, so proceed with update
         conn.execute(
-            "UPDATE manga SET title = ?1, title_romanized = ?2, url = ?3, url_with_chapter = ?4, chapter = ?5, last_update = ?6, last_update_millis = ?7, notes = ?8, tags = ?9, my_anime_list = ?10 WHERE id = ?11",
+            "UPDATE manga SET title = ?1, title_romanized = ?2, url = ?3, url_with_chapter = ?4, chapter = ?5, last_update = ?6, last_update_millis = ?7, notes = ?8, tags = ?9, my_anime_list = ?10, feed_url = ?11, latest_available_chapter = ?12, domain = ?13, chapter_numeric = NULLIF(?14, ''), user_fields = NULLIF(?15, ''), alt_titles = NULLIF(?16, '') WHERE id = ?17",
             &[
                 &manga.title(), // ?1
                 match &manga.title_romanized() { Some(t) => &t.as_str(), None => "" },  // ?2
@@ -391,15 +885,15 @@ pub mod model_sqlite3_manga {
                 match &manga.notes() { Some(t) => &t.as_str(), None => "" }, // ?8
                 &manga.tags().join(","),    // ?9
                 match &manga.my_anime_list() { Some(t) => &t.as_str(), None => "" },    // ?10
-                &manga.id().to_string(),    // ?11
+                match &manga.feed_url() { Some(t) => &t.as_str(), None => "" },    // ?11
+                match &manga.latest_available_chapter() { Some(t) => &t.as_str(), None => "" },    // ?12
+                &url_domain(manga.url()),    // ?13
+                manga.chapter().and_then(|chapter| chapter_numeric(&chapter)).map(|numeric| numeric.to_string()).unwrap_or_default().as_str(),  // ?14 - "" is NULL
+                user_fields_json(manga).unwrap_or_default().as_str(),  // ?15 - "" is NULL
+                alt_titles_json(manga).unwrap_or_default().as_str(),  // ?16 - "" is NULL
+                &manga.id().to_string(),    // ?17
                 ],
-        )?;
-
-        #[cfg(debug_assertions)]
-        {
-            println!("> UPDATE succeeded for ID: {}", manga.id());
-        }
-        Ok(())
+        )
     }
 
     // Scans the chapter field, increments it by 1 (unless it's format is 1-1 or 1.1, then incremnt the tail number by 1)
@@ -446,15 +940,16 @@ pub mod model_sqlite3_manga {
         };
 
         // now seeek for the last occurence of chapter field in url_with_chapter and replace it with next_chapter
-        let next_chapter_in_url = match manga.url_with_chapter() {
-            Some(t) => {
-                let _url_with_chapter = t.clone();
-
-                "".to_string() // TEMP!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
-            }
+        let next_chapter_in_url = match manga.url_for_chapter(&next_chapter) {
+            Some(t) => t, // i.e. ".../5-star-story-chapter-5/" -> ".../5-star-story-chapter-6/"
             None => {
-                // use BASE url, append "-chapter-1" to it
-                manga.url().to_string() + &next_chapter
+                // use BASE url, append "-chapter-N" to it (reverse of MangaModel::url_and_chapter())
+                // i.e. "https://mangadex.org/5-star-story/" -> "https://mangadex.org/5-star-story-chapter-1/"
+                format!(
+                    "{}-chapter-{}/",
+                    manga.url().trim_end_matches('/'),
+                    next_chapter.replace(".", "-")
+                )
             }
         };
         return (next_chapter, next_chapter_in_url);
@@ -469,7 +964,7 @@ pub mod model_sqlite3_manga {
                 db_full_paths, manga
             )
         }
-        // fail if id (u32) is 0
+        // fail if id is 0
         if manga.id() == 0 {
             // opt out early, no use continuing if ID is 0...
             return Err(rusqlite::Error::InvalidParameterName(
@@ -478,13 +973,13 @@ pub mod model_sqlite3_manga {
         }
 
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         // if we cannot locate id in manga table during update, return error (most likely got deleted)
         // query for id and title (just in case we need to return the title)
         let mut stmt = conn.prepare("SELECT id FROM manga WHERE id = ?1")?; // returns either 0 or 1 row
         let manga_iter = stmt.query_map(&[&manga.id()], |row| {
-            row.get::<usize, i32>(0) // id is i32 type...
+            row.get::<usize, i64>(0) // rowid
         })?;
         if manga_iter.count() == 0 {
             // no use continuing if no ID was found on the manga table...
@@ -497,8 +992,9 @@ pub mod model_sqlite3_manga {
         conn.execute(
             "UPDATE manga 
                     SET url_with_chapter = ?1, 
-                        chapter = ?2 
-                    WHERE id = ?3",
+                        chapter = ?2, 
+                        chapter_numeric = NULLIF(?3, '') 
+                    WHERE id = ?4",
             &[
                 match &manga.url_with_chapter() {
                     Some(t) => &t.as_str(),
@@ -508,6 +1004,7 @@ pub mod model_sqlite3_manga {
                     Some(t) => &t.as_str(),
                     None => "", // reset to NO chapter
                 },
+                manga.chapter().and_then(|chapter| chapter_numeric(&chapter)).map(|numeric| numeric.to_string()).unwrap_or_default().as_str(),
                 &manga.id().to_string(),
             ],
         )?;
@@ -519,16 +1016,244 @@ pub mod model_sqlite3_manga {
         Ok(())
     }
 
+    // set (or clear, if None) the per-series feed which the update checker will prefer over scraping the url
+    pub fn update_feed_url(db_full_paths: &str, id: i64, feed_url: Option<&str>) -> Result<bool> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let rows_updated = conn.execute(
+            "UPDATE manga SET feed_url = ?1 WHERE id = ?2",
+            params![feed_url.unwrap_or(""), id],
+        )?;
+        Ok(rows_updated > 0)
+    }
+
+    // set by the enrichment integrations, see enrichment::apply_match()
+    pub fn update_my_anime_list(db_full_paths: &str, id: i64, my_anime_list: &str) -> Result<bool> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let rows_updated = conn.execute(
+            "UPDATE manga SET my_anime_list = ?1 WHERE id = ?2",
+            params![my_anime_list, id],
+        )?;
+        Ok(rows_updated > 0)
+    }
+
+    // record what the update checker found; note that this intentionally does NOT touch last_update
+    // since nothing was read by the user, the site merely has a newer chapter
+    pub fn update_latest_available_chapter(
+        db_full_paths: &str,
+        id: i64,
+        latest_available_chapter: &str,
+    ) -> Result<bool> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let rows_updated = conn.execute(
+            "UPDATE manga SET latest_available_chapter = ?1 WHERE id = ?2",
+            params![latest_available_chapter, id],
+        )?;
+        Ok(rows_updated > 0)
+    }
+
+    // writes chapter, url_with_chapter and last_update(_millis) of each manga AND logs a reading session for each,
+    // all in a single transaction so that a bulk mark-read is either fully applied or not at all;
+    // returns number of manga rows updated
+    pub fn update_read_chapters(db_full_paths: &str, mangas: &[MangaModel]) -> Result<usize> {
+        let path = Path::new(db_full_paths);
+        let mut conn = model_sqlite3_connection::open(path)?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let mut rows_updated = 0;
+            for manga in mangas {
+                let chapter = match manga.chapter() {
+                    Some(chapter) => chapter,
+                    None => continue, // nothing was read...
+                };
+                let read_at_millis = manga
+                    .last_update_millis()
+                    .unwrap_or_else(|| chrono::Local::now().timestamp_millis());
+                rows_updated += transaction.execute(
+                    "UPDATE manga SET chapter = ?1, url_with_chapter = ?2, last_update = ?3, last_update_millis = ?4, chapter_numeric = ?5 WHERE id = ?6",
+                    params![
+                        chapter,
+                        manga.url_with_chapter().unwrap_or_default(),
+                        manga.last_update().unwrap_or_default(),
+                        read_at_millis,
+                        chapter_numeric(&chapter),
+                        manga.id()
+                    ],
+                )?;
+                model_sqlite3_reading_sessions::insert_reading_session_with_connection(
+                    &transaction,
+                    manga.id(),
+                    &chapter,
+                    read_at_millis,
+                )?;
+            }
+            transaction.commit()?;
+            Ok(rows_updated)
+        })
+    }
+
+    // replaces manga.url and keeps the old url in url_history (single transaction); reason is i.e. "migrate-site"
+    // returns Ok(false) if id was not found or the url is unchanged
+    pub fn update_url(db_full_paths: &str, id: i64, new_url: &str, reason: &str) -> Result<bool> {
+        let path = Path::new(db_full_paths);
+        let mut conn = model_sqlite3_connection::open(path)?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let old_url = match transaction.query_row(
+                "SELECT url FROM manga WHERE id = ?1",
+                params![id],
+                |row| row.get::<usize, String>(0),
+            ) {
+                Ok(old_url) => old_url,
+                Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+                Err(e) => return Err(e),
+            };
+            if old_url == new_url {
+                return Ok(false);
+            }
+            transaction.execute(
+                "UPDATE manga SET url = ?1, domain = ?2 WHERE id = ?3",
+                params![new_url, url_domain(new_url), id],
+            )?;
+            model_sqlite3_url_history::insert_url_history_with_connection(
+                &transaction,
+                id,
+                &old_url,
+                new_url,
+                reason,
+                chrono::Utc::now().timestamp_millis(),
+            )?;
+            transaction.commit()?;
+            Ok(true)
+        })
+    }
+
+    // tags are read back through manga_to_tags_map (not the tags column update_manga() writes), so
+    // replacing the tags of a manga means replacing its mapping; returns number of tags attached
+    pub fn replace_tags_of_manga(db_full_paths: &str, id: i64, tags: &[String]) -> Result<usize> {
+        let path = Path::new(db_full_paths);
+        let mut conn = model_sqlite3_connection::open(path)?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            transaction.execute(
+                "DELETE FROM manga_to_tags_map WHERE manga_id = ?1",
+                params![id],
+            )?;
+            let mut attached_count = 0;
+            for tag in tags {
+                transaction.execute("INSERT OR IGNORE INTO tags (tag) VALUES (?1)", params![tag])?;
+                let tag_id = transaction.query_row(
+                    "SELECT id FROM tags WHERE tag = ?1",
+                    params![tag],
+                    |row| row.get::<usize, i64>(0),
+                )?;
+                attached_count += transaction.execute(
+                    "INSERT OR IGNORE INTO manga_to_tags_map (manga_id, tag_id) VALUES (?1, ?2)",
+                    params![id, tag_id],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(attached_count)
+        })
+    }
+
+    // attaches tags through tags/manga_to_tags_map (single transaction), leaving the tags it already has alone;
+    // unlike update_manga(), this does not need the whole MangaModel.  Returns number of tags newly attached
+    pub fn add_tags_to_manga(db_full_paths: &str, id: i64, tags: &[String]) -> Result<usize> {
+        let path = Path::new(db_full_paths);
+        let mut conn = model_sqlite3_connection::open(path)?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let attached_count = add_tags_with_connection(&transaction, id, tags)?;
+            transaction.commit()?;
+            Ok(attached_count)
+        })
+    }
+
+    // add_tags_to_manga() within the caller's transaction
+    fn add_tags_with_connection(conn: &Connection, id: i64, tags: &[String]) -> Result<usize> {
+        let mut attached_count = 0;
+        for tag in tags {
+            conn.execute("INSERT OR IGNORE INTO tags (tag) VALUES (?1)", params![tag])?;
+            let tag_id =
+                conn.query_row("SELECT id FROM tags WHERE tag = ?1", params![tag], |row| {
+                    row.get::<usize, i64>(0)
+                })?;
+            attached_count += conn.execute(
+                "INSERT OR IGNORE INTO manga_to_tags_map (manga_id, tag_id) VALUES (?1, ?2)",
+                params![id, tag_id],
+            )?;
+        }
+        Ok(attached_count)
+    }
+
+    // unlike the other columns, last_checked_millis is never written by insert_manga()/update_manga(), it is
+    // owned by the update checker so that re-importing bookmarks does not reset it
+    pub fn update_last_checked_millis(
+        db_full_paths: &str,
+        id: i64,
+        last_checked_millis: i64,
+    ) -> Result<bool> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let rows_updated = conn.execute(
+            "UPDATE manga SET last_checked_millis = ?1 WHERE id = ?2",
+            params![last_checked_millis, id],
+        )?;
+        Ok(rows_updated > 0)
+    }
+
+    // bookmarks/CSV do not carry feed_url nor what the update checker found, so keep what's in the DB, nor what
+    // the enrichment integrations found, nor the user's own fields, nor the titles the row had before
+    fn keep_stored_fields(manga: &mut MangaModel, stored: &MangaModel) {
+        manga.set_id(stored.id());
+        if manga.feed_url().is_none() {
+            manga.set_feed_url(stored.feed_url());
+        }
+        if manga.latest_available_chapter().is_none() {
+            manga.set_latest_available_chapter(stored.latest_available_chapter());
+        }
+        if manga.my_anime_list().is_none() {
+            manga.set_my_anime_list(stored.my_anime_list());
+        }
+        if manga.user_fields().is_empty() {
+            manga.set_user_fields(stored.user_fields().clone());
+        }
+        let incoming_alt_titles = manga.alt_titles().to_vec();
+        manga.set_alt_titles(Vec::new());
+        for alt_title in stored.alt_titles().iter().chain(incoming_alt_titles.iter()) {
+            manga.add_alt_title(alt_title);
+        }
+        manga.add_alt_title(stored.title()); // a row renamed by the manga keeps its old title
+    }
+
     // we can use this to insert manga if it doesn't exist, and if it does exist, we can update it
     // however, we need to make sure that the title and url are unique, so we need to check if it exists first
     // if it does exist, we can update it, if it doesn't exist, we can insert it
-    pub fn upsert_manga(db_full_paths: &str, manga_no_id: &MangaModel) -> Result<MangaModel> {
+    pub fn upsert_manga(
+        db_full_paths: &str,
+        manga_no_id: &MangaModel,
+    ) -> std::result::Result<MangaModel, Error> {
+        upsert_manga_with(db_full_paths, manga_no_id, &mut |_, _, _| false)
+    }
+
+    // same, and when no row has its title and url but some have its url under another title (i.e. the bookmark
+    // was renamed, or the site changed its title), on_title_collision(id, title, manga) is asked for each of them:
+    // true renames that row (its old title kept in alt_titles), false for all of them inserts a row of its own
+    // next to them, as UNIQUE(title, url) always allowed
+    pub fn upsert_manga_with(
+        db_full_paths: &str,
+        manga_no_id: &MangaModel,
+        on_title_collision: &mut dyn FnMut(i64, &str, &MangaModel) -> bool,
+    ) -> std::result::Result<MangaModel, Error> {
         #[cfg(debug_assertions)]
         {
             println!("# upsert_manga('{}', {:?})", db_full_paths, manga_no_id);
         }
         // first, check if title+url unique exists and if so, update rather than insert
-        let manga = match select_manga_from_url_and_title(
+        let possible_stored = match select_manga_from_url_and_title(
             db_full_paths,
             &manga_no_id.url(),
             &manga_no_id.title(),
@@ -541,68 +1266,367 @@ pub mod model_sqlite3_manga {
                 // because we're using exact title and url, we should only get 1 row
                 if found_model.len() > 1 {
                     // if here, it means we got more than 1 row, which is not good, so return error
-                    return Err(rusqlite::Error::InvalidParameterName(
+                    return Err(Error::Sqlite(rusqlite::Error::InvalidParameterName(
                         "more than 1 row found".to_string(),
-                    ));
+                    )));
                 }
                 // if here, we can now assume that we got 1 row, so use the id from the found row and use the data of what was passed
-                let top_row = found_model.get(0).unwrap();
-
+                found_model.into_iter().next()
+            }
+            // depending on type of error, look for the url under another title (i.e. not found) or return error
+            Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => {
+                #[cfg(debug_assertions)]
+                {
+                    println!("# SELECT returned 0 rows while searching for title='{}'+url='{}'; looking for url alone", manga_no_id.title(), manga_no_id.url());
+                }
+                match select_manga_from_url_and_title(db_full_paths, manga_no_id.url(), "%") {
+                    Ok(same_url_rows) => same_url_rows
+                        .into_iter()
+                        .find(|row| on_title_collision(row.id(), row.title(), manga_no_id)),
+                    Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => None,
+                    Err(select_error) => return Err(select_error),
+                }
+            }
+            Err(select_error) => {
+                #[cfg(debug_assertions)]
+                {
+                    println!("ERROR: upsert_manga: failed calling select_manga_from_url_and_title: {:?}", select_error);
+                }
+                return Err(select_error);
+            }
+        };
+        match possible_stored {
+            Some(stored) => {
                 // row exists, use the ID from the found row and use the data of what was passed
                 let mut manga = manga_no_id.clone();
-                manga.set_id(top_row.id());
+                keep_stored_fields(&mut manga, &stored);
 
                 // if here, it means we found manga based on title and url, so update it
-                match update_manga(db_full_paths, &manga) {
-                    Ok(()) => {
-                        // if here, it means we successfully updated manga, so return it
-                        Ok(manga)
-                    }
-                    Err(update_error) => {
-                        // if here, it means we cannot update manga, so return error
-                        // return anything other than Err(rusqlite::Error::QueryReturnedNoRows)
-                        let str_error = update_error.to_string();
-                        Err(rusqlite::Error::InvalidParameterName(str_error)) // for now, we'll just use this error type
+                update_manga(db_full_paths, &manga)?;
+                Ok(manga)
+            }
+            // if here, it means we cannot find manga based on title and url, so insert it
+            None => insert_manga(db_full_paths, manga_no_id),
+        }
+    }
+
+    // result of bulk_insert_manga()/bulk_upsert_manga(): mangas are in the order given, with their ids
+    #[derive(Debug, Clone)]
+    pub struct BulkWriteSummary {
+        pub mangas: Vec<MangaModel>,
+        pub inserted: usize,
+        pub updated: usize,
+        pub renamed: usize, // rows which took the title of a manga of their url (see upsert_manga_with())
+    }
+
+    // same as insert_manga() for each manga, but in a single transaction with prepared statements (instead of
+    // a connection per row): new (title, url) are inserted, existing ones are left as they are; either way
+    // the tags of the manga are attached to the row
+    pub fn bulk_insert_manga(conn: &mut Connection, mangas: &[MangaModel]) -> Result<BulkWriteSummary> {
+        bulk_insert_manga_with(conn, mangas, &mut |_, _, _| false)
+    }
+
+    // same, with on_title_collision as for upsert_manga_with(): a row renamed only takes the title (and the old
+    // one in alt_titles), the rest of it is left as it is
+    pub fn bulk_insert_manga_with(
+        conn: &mut Connection,
+        mangas: &[MangaModel],
+        on_title_collision: &mut dyn FnMut(i64, &str, &MangaModel) -> bool,
+    ) -> Result<BulkWriteSummary> {
+        model_sqlite3_connection::with_busy_retry(|| {
+            bulk_write_manga(conn, mangas, false, &mut *on_title_collision)
+        })
+    }
+
+    // same as upsert_manga() for each manga, in a single transaction: existing (title, url) are updated (keeping
+    // feed_url, latest_available_chapter, my_anime_list, user_fields and alt_titles when the manga has none),
+    // others inserted; tags are attached, never detached
+    pub fn bulk_upsert_manga(conn: &mut Connection, mangas: &[MangaModel]) -> Result<BulkWriteSummary> {
+        model_sqlite3_connection::with_busy_retry(|| {
+            bulk_write_manga(conn, mangas, true, &mut |_, _, _| false)
+        })
+    }
+
+    // one connection for a run of batches (i.e. every input of an import), rather than a connection per row as
+    // insert_manga()/upsert_manga() have; each batch is its own transaction
+    pub struct MangaStore {
+        conn: Connection,
+    }
+
+    impl MangaStore {
+        pub fn open(db_full_paths: &str) -> Result<MangaStore> {
+            Ok(MangaStore {
+                conn: model_sqlite3_connection::open(db_full_paths)?,
+            })
+        }
+
+        // see bulk_insert_manga()
+        pub fn insert_batch(&mut self, mangas: &[MangaModel]) -> Result<BulkWriteSummary> {
+            bulk_insert_manga(&mut self.conn, mangas)
+        }
+
+        // see bulk_insert_manga_with()
+        pub fn insert_batch_with(
+            &mut self,
+            mangas: &[MangaModel],
+            on_title_collision: &mut dyn FnMut(i64, &str, &MangaModel) -> bool,
+        ) -> Result<BulkWriteSummary> {
+            bulk_insert_manga_with(&mut self.conn, mangas, on_title_collision)
+        }
+
+        // for the tables written along with the batches, i.e. bookmark_origins
+        pub fn connection(&mut self) -> &mut Connection {
+            &mut self.conn
+        }
+    }
+
+    // a row as bulk_write_manga() needs it to decide between insert, update and rename
+    struct StoredRow {
+        id: i64,
+        url: String,
+        title: String,
+        alt_titles: Vec<String>,
+        feed_url: Option<String>,
+        latest_available_chapter: Option<String>,
+        my_anime_list: Option<String>,
+    }
+
+    fn bulk_write_manga(
+        conn: &mut Connection,
+        mangas: &[MangaModel],
+        update_existing: bool,
+        on_title_collision: &mut dyn FnMut(i64, &str, &MangaModel) -> bool,
+    ) -> Result<BulkWriteSummary> {
+        let current_time_as_yyyymmddhhmmss =
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let current_time_as_millis = chrono::Local::now().timestamp_millis();
+        let transaction = conn.transaction()?;
+        // rows written by others since the last import have no checksum yet
+        backfill_url_checksums(&transaction)?;
+        backfill_domains(&transaction)?;
+        backfill_chapter_numerics(&transaction)?;
+        let mut summary = BulkWriteSummary {
+            mangas: Vec::with_capacity(mangas.len()),
+            inserted: 0,
+            updated: 0,
+            renamed: 0,
+        };
+
+        // tags first, all of them at once: tag -> tags.id
+        let mut tag_ids: HashMap<String, i64> = HashMap::new();
+        {
+            let mut insert_tag_stmt =
+                transaction.prepare_cached("INSERT OR IGNORE INTO tags (tag) VALUES (?1)")?;
+            for tag in mangas.iter().flat_map(|manga| manga.tags()) {
+                if !tag_ids.contains_key(tag) {
+                    insert_tag_stmt.execute(params![tag])?;
+                    tag_ids.insert(tag.to_string(), 0);
+                }
+            }
+            if !tag_ids.is_empty() {
+                let mut select_tags_stmt = transaction.prepare_cached("SELECT id, tag FROM tags")?;
+                let rows = select_tags_stmt.query_map([], |row| {
+                    Ok((row.get::<usize, i64>(0)?, row.get::<usize, String>(1)?))
+                })?;
+                for row in rows {
+                    let (tag_id, tag) = row?;
+                    if let Some(id) = tag_ids.get_mut(&tag) {
+                        *id = tag_id;
                     }
                 }
             }
-            Err(select_error) => {
-                // depending on type of error, proceed to INSERT it (i.e. not found) or return error
-                match select_error {
-                    rusqlite::Error::QueryReturnedNoRows => {
-                        #[cfg(debug_assertions)]
-                        {
-                            println!("# SELECT returned 0 rows while searching for title='{}'+url='{}'; inserting instead", manga_no_id.title(), manga_no_id.url());
-                        }
-                        // if here, it means we cannot find manga based on title and url, so insert it
-                        insert_manga(db_full_paths, manga_no_id)
+        }
+
+        {
+            // same title and same normalized url is the same manga, i.e. the http:// and https:// bookmarks of it;
+            // the exact url first, then by id
+            let mut select_stmt = transaction.prepare_cached(
+                "SELECT id, url, feed_url, latest_available_chapter, my_anime_list, title, alt_titles FROM manga WHERE url_checksum = ?1 AND title = ?2 ORDER BY url = ?3 DESC, id",
+            )?;
+            // otherwise, the row of the same bookmark before it was renamed or moved
+            let mut select_by_guid_stmt = transaction.prepare_cached(
+                "SELECT id, url, feed_url, latest_available_chapter, my_anime_list, title, alt_titles FROM manga WHERE bookmark_guid = ?1 ORDER BY id",
+            )?;
+            // otherwise, the rows of the same normalized url under another title, if on_title_collision says so
+            let mut select_by_url_stmt = transaction.prepare_cached(
+                "SELECT id, url, feed_url, latest_available_chapter, my_anime_list, title, alt_titles FROM manga WHERE url_checksum = ?1 AND title != ?2 ORDER BY url = ?3 DESC, id",
+            )?;
+            let mut insert_stmt = transaction.prepare_cached(
+                "INSERT INTO manga (title, title_romanized, url, url_with_chapter, chapter, last_update, last_update_millis, notes, tags, my_anime_list, feed_url, latest_available_chapter, url_checksum, bookmark_guid, domain, added_millis, chapter_numeric, user_fields, alt_titles) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+            )?;
+            let mut update_stmt = transaction.prepare_cached(
+                "UPDATE manga SET title = ?1, title_romanized = ?2, url = ?3, url_with_chapter = ?4, chapter = ?5, last_update = ?6, last_update_millis = ?7, notes = ?8, tags = ?9, my_anime_list = ?10, feed_url = ?11, latest_available_chapter = ?12, url_checksum = ?13, bookmark_guid = COALESCE(?14, bookmark_guid), domain = ?15, chapter_numeric = ?16, user_fields = COALESCE(?17, user_fields), alt_titles = ?18 WHERE id = ?19",
+            )?;
+            // insert only: a row renamed keeps everything else
+            let mut rename_stmt = transaction
+                .prepare_cached("UPDATE manga SET title = ?1, alt_titles = ?2 WHERE id = ?3")?;
+            // a row found by title and url learns the GUID of its bookmark, but never loses the one it has
+            let mut attach_guid_stmt = transaction.prepare_cached(
+                "UPDATE manga SET bookmark_guid = ?1 WHERE id = ?2 AND (bookmark_guid IS NULL OR bookmark_guid = '')",
+            )?;
+            let mut map_tag_stmt = transaction.prepare_cached(
+                "INSERT OR IGNORE INTO manga_to_tags_map (manga_id, tag_id) VALUES (?1, ?2)",
+            )?;
+            for manga_no_id in mangas {
+                let checksum = url_checksum(manga_no_id.url());
+                let normalized = normalized_url(manga_no_id.url());
+                let possible_guid = manga_no_id.bookmark_guid();
+                let domain = url_domain(manga_no_id.url());
+                let to_stored = |row: &Row| {
+                    Ok(StoredRow {
+                        id: row.get(0)?,
+                        url: row.get(1)?,
+                        feed_url: make_none_if_empty(row.get::<usize, Option<String>>(2)?),
+                        latest_available_chapter: make_none_if_empty(
+                            row.get::<usize, Option<String>>(3)?,
+                        ),
+                        my_anime_list: make_none_if_empty(row.get::<usize, Option<String>>(4)?),
+                        title: row.get(5)?,
+                        alt_titles: alt_titles_from_json(row.get(6)?),
+                    })
+                };
+                let same_url = |stored: &StoredRow| normalized_url(&stored.url) == normalized;
+                let mut possible_existing = select_stmt
+                    .query_map(
+                        params![checksum, manga_no_id.title(), manga_no_id.url()],
+                        to_stored,
+                    )?
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    // checksums can collide
+                    .find(same_url);
+                if possible_existing.is_none() {
+                    if let Some(guid) = possible_guid.as_ref() {
+                        possible_existing = select_by_guid_stmt
+                            .query_map(params![guid], to_stored)?
+                            .next()
+                            .transpose()?;
                     }
-                    _ => {
-                        #[cfg(debug_assertions)]
-                        {
-                            println!("ERROR: upsert_manga: failed calling select_manga_from_url_and_title: {:?}", select_error);
+                }
+                let mut is_renamed = false;
+                if possible_existing.is_none() {
+                    possible_existing = select_by_url_stmt
+                        .query_map(
+                            params![checksum, manga_no_id.title(), manga_no_id.url()],
+                            to_stored,
+                        )?
+                        .collect::<Result<Vec<_>>>()?
+                        .into_iter()
+                        .filter(same_url)
+                        .find(|stored| on_title_collision(stored.id, &stored.title, manga_no_id));
+                    is_renamed = possible_existing.is_some();
+                }
+                let mut manga = manga_no_id.clone();
+                match possible_existing {
+                    Some(stored) => {
+                        let id = stored.id;
+                        manga.set_id(id);
+                        // a row found by its GUID or renamed keeps the titles it had
+                        let mut alt_titles = manga.alt_titles().to_vec();
+                        alt_titles.extend(stored.alt_titles);
+                        manga.set_alt_titles(Vec::new());
+                        for alt_title in alt_titles.iter().chain(std::iter::once(&stored.title)) {
+                            manga.add_alt_title(alt_title);
+                        }
+                        if update_existing {
+                            // same as upsert_manga(): bookmarks/CSV do not carry these, so keep what's in the DB
+                            if manga.feed_url().is_none() {
+                                manga.set_feed_url(stored.feed_url);
+                            }
+                            if manga.latest_available_chapter().is_none() {
+                                manga.set_latest_available_chapter(stored.latest_available_chapter);
+                            }
+                            if manga.my_anime_list().is_none() {
+                                manga.set_my_anime_list(stored.my_anime_list);
+                            }
+                            update_stmt.execute(params![
+                                manga.title(),
+                                manga.title_romanized().unwrap_or_default(),
+                                manga.url(),
+                                manga.url_with_chapter().unwrap_or_default(),
+                                manga.chapter().unwrap_or_default(),
+                                manga
+                                    .last_update()
+                                    .unwrap_or_else(|| current_time_as_yyyymmddhhmmss.clone()),
+                                manga.last_update_millis().unwrap_or(current_time_as_millis),
+                                manga.notes().unwrap_or_default(),
+                                manga.tags().join(","),
+                                manga.my_anime_list().unwrap_or_default(),
+                                manga.feed_url().unwrap_or_default(),
+                                manga.latest_available_chapter().unwrap_or_default(),
+                                checksum,
+                                possible_guid,
+                                domain,
+                                manga.chapter().and_then(|chapter| chapter_numeric(&chapter)),
+                                user_fields_json(&manga),
+                                alt_titles_json(&manga),
+                                id,
+                            ])?;
+                            summary.updated += 1;
+                        } else if is_renamed {
+                            rename_stmt.execute(params![
+                                manga_no_id.title(),
+                                alt_titles_json(&manga),
+                                id
+                            ])?;
+                        } else if let Some(guid) = possible_guid.as_ref() {
+                            attach_guid_stmt.execute(params![guid, id])?;
                         }
-                        Err(select_error)
+                        if is_renamed {
+                            summary.renamed += 1;
+                        }
+                    }
+                    None => {
+                        insert_stmt.execute(params![
+                            manga.title(),
+                            manga.title_romanized().unwrap_or_default(),
+                            manga.url(),
+                            manga.url_with_chapter().unwrap_or_default(),
+                            manga.chapter().unwrap_or_else(|| "0".to_string()),
+                            manga
+                                .last_update()
+                                .unwrap_or_else(|| current_time_as_yyyymmddhhmmss.clone()),
+                            manga.last_update_millis().unwrap_or(current_time_as_millis),
+                            manga.notes().unwrap_or_default(),
+                            manga.tags().join(","),
+                            manga.my_anime_list().unwrap_or_default(),
+                            manga.feed_url().unwrap_or_default(),
+                            manga.latest_available_chapter().unwrap_or_default(),
+                            checksum,
+                            possible_guid,
+                            domain,
+                            current_time_as_millis,
+                            chapter_numeric(&manga.chapter().unwrap_or_else(|| "0".to_string())),
+                            user_fields_json(&manga),
+                            alt_titles_json(&manga),
+                        ])?;
+                        manga.set_id(transaction.last_insert_rowid());
+                        summary.inserted += 1;
                     }
                 }
+                for tag in manga.tags() {
+                    map_tag_stmt.execute(params![manga.id(), tag_ids[tag]])?;
+                }
+                summary.mangas.push(manga);
             }
-        };
-        manga
+        }
+        transaction.commit()?;
+        Ok(summary)
     }
 
     // delete the row based on id field
-    pub fn delete_manga(db_full_paths: &str, id: u32) -> Result<bool> {
+    pub fn delete_manga(db_full_paths: &str, id: i64) -> std::result::Result<bool, Error> {
         println!("DELETE: delete_manga('{}', {})", db_full_paths, id);
 
         let path = Path::new(db_full_paths);
-        let conn = Connection::open(path)?;
+        let conn = model_sqlite3_connection::open(path)?;
 
         // if we cannot locate id in manga table during delete, just return Ok(false) (most likely got deleted)
         // should only return single row since we're using id as primary key
         // haven't had time to investigate, but if I just return single column (SELECT id) I'd get an error, so I'm returning 2 columns (id and title) and just ignore the title
         let mut stmt = conn.prepare("SELECT id FROM manga WHERE id = ?1")?; // returns either 0 or 1 row
         let manga_iter = stmt.query_map(&[&id], |row| {
-            row.get::<usize, i32>(0) // id is i32 type...
+            row.get::<usize, i64>(0) // rowid
         })?;
         if manga_iter.count() == 0 {
             #[cfg(debug_assertions)]
@@ -611,27 +1635,37 @@ pub mod model_sqlite3_manga {
         }
 
         // if here, id existed, so proceed with delete
+        delete_manga_with_connection(&conn, id)?;
+
+        Ok(true)
+    }
+
+    // the row and everything referencing it (history first, it references manga.id); called within the
+    // caller's transaction when the row goes somewhere else, i.e. the archive (see model_sqlite3_manga_archive)
+    pub fn delete_manga_with_connection(conn: &Connection, id: i64) -> Result<()> {
+        model_sqlite3_reading_sessions::delete_reading_sessions_of_manga(conn, id)?;
+        model_sqlite3_url_history::delete_url_history_of_manga(conn, id)?;
+        model_sqlite3_link_checks::delete_link_checks_of_manga(conn, id)?;
+        model_sqlite3_manga_metadata::delete_manga_metadata(conn, id)?;
+        model_sqlite3_bookmark_origin::delete_bookmark_origins_of_manga(conn, id)?;
+        // tags mapping also references manga.id, so it goes before the manga row as well (again, if
+        // cannot find, it's OK)
+        match conn.execute("DELETE FROM manga_to_tags_map WHERE manga_id = ?1", &[&id]) {
+            Ok(_) => {}
+            Err(e) => {
+                // if no rows were found in tags map table, it should still be considered as a success
+                if e != rusqlite::Error::QueryReturnedNoRows {
+                    println!(
+                        "ERROR: delete_manga(id={}): failed deleting from manga_to_tags_map: {}",
+                        id, e
+                    );
+                    return Err(e.into());
+                }
+            }
+        }
         match conn.execute("DELETE FROM manga WHERE id = ?1", &[&id]) {
             Ok(_) => {
-                // delete tags ONLY if we were able to delete from manga table
-                // also prune tags group table (again, if cannot find, it's OK)
-                match conn.execute("DELETE FROM manga_to_tags_map WHERE manga_id = ?1", &[&id]) {
-                    Ok(_) => {
-                        println!("> DELETE succeeded for ID={}", id);
-                    }
-                    Err(e) => {
-                        // if no rows were found in tags map table, it should still be considered as a success
-                        if e != rusqlite::Error::QueryReturnedNoRows {
-                            println!( "ERROR: delete_manga(id={}): failed deleting from manga_to_tags_map: {}", id, e);
-                            return Err(e.into());
-                        } else {
-                            println!(
-                                "> DELETE succeeded for ID={} (no tags mapping found for this ID)",
-                                id
-                            );
-                        }
-                    }
-                }
+                println!("> DELETE succeeded for ID={}", id);
             }
             Err(e) => {
                 println!(
@@ -642,26 +1676,34 @@ pub mod model_sqlite3_manga {
             }
         }
 
-        Ok(true)
+        Ok(())
     }
 
     // get ID based on title and url (as it's unique combination) and return in manga struct WITH the ID
-    pub fn get_id(db_full_paths: &str, title: &str, url: &str) -> Result<MangaModel> {
+    pub fn get_id(
+        db_full_paths: &str,
+        title: &str,
+        url: &str,
+    ) -> std::result::Result<MangaModel, Error> {
         // fail if title or url is empty, or has/contains "%" wildcards
         if title.len() == 0 || url.len() == 0 || title.contains("%") || url.contains("%") {
-            return Err(rusqlite::Error::InvalidParameterName(format!(
+            return Err(Error::InvalidArgument(format!(
                 "get_id(): title '{}' or url '{}' is empty, or has/contains '%' wildcards",
                 title, url
             )));
         }
 
         // title+url is the unique constraints which resolves to a single row (unique ID), note that we DO NOT use LIKE clause here
-        let where_clause = format!("WHERE m.title = '{}' AND m.url = '{}'", title, url);
-        let row_sets_result = select_manga(db_full_paths, where_clause.as_str());
+        let filter = MangaFilter {
+            possible_title: Some(TextMatch::Exact(title.to_string())),
+            possible_url: Some(TextMatch::Exact(url.to_string())),
+            ..MangaFilter::default()
+        };
+        let row_sets_result = select_manga(db_full_paths, &filter, SelectOptions::default());
         match row_sets_result {
             Ok(row_sets) => {
                 if row_sets.len() == 0 {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                    return Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows));
                 }
                 if row_sets.len() > 1 {
                     // title+url SHOULD be unique, hence we should only get 1 row, something is wrong if we get more than 1 row
@@ -677,30 +1719,37 @@ pub mod model_sqlite3_manga {
                 }
                 match row_sets.get(0) {
                     Some(row_manga) => Ok(row_manga.clone()),
-                    None => Err(rusqlite::Error::QueryReturnedNoRows),
+                    None => Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)),
                 }
             }
             Err(e) => Err(e),
         }
     }
 
-    // return in manga struct based on ID
-    pub fn select_manga_by_id(db_full_paths: &str, id: u32) -> Result<MangaModel> {
+    // return in manga struct based on ID; Err(NotFound) if there is no such row
+    pub fn select_manga_by_id(
+        db_full_paths: &str,
+        id: i64,
+    ) -> std::result::Result<MangaModel, Error> {
         // ID is unique, so we should only get 1 row
-        let where_clause = format!("WHERE m.id = {}", id);
-        let row_sets_result = select_manga(db_full_paths, where_clause.as_str());
+        let filter = MangaFilter {
+            possible_min_id: Some(id),
+            possible_max_id: Some(id),
+            ..MangaFilter::default()
+        };
+        let row_sets_result = select_manga(db_full_paths, &filter, SelectOptions::default());
         match row_sets_result {
             Ok(row_sets) => {
                 if row_sets.len() == 0 {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                    return Err(Error::NotFound(id));
                 }
                 if row_sets.len() > 1 {
-                    // ID should be unique, should NEVER happen, so panic!() here (this is serious)
-                    panic!("select_manga_by_id(): ID='{}' returned more than 1 row, using the first row for database '{}'", id, db_full_paths);
+                    // ID is the primary key, so this should NEVER happen; warn as get_id() does, but take the first row
+                    println!("WARNING: select_manga_by_id(): ID='{}' returned more than 1 row, using the first row for database '{}'", id, db_full_paths);
                 }
                 match row_sets.get(0) {
                     Some(row_manga) => Ok(row_manga.clone()),
-                    None => Err(rusqlite::Error::QueryReturnedNoRows),
+                    None => Err(Error::NotFound(id)),
                 }
             }
             Err(e) => Err(e),
@@ -708,14 +1757,25 @@ pub mod model_sqlite3_manga {
     }
 
     // return in manga struct array
-    pub fn select_all_manga(db_full_paths: &str) -> Result<Vec<MangaModel>> {
+    pub fn select_all_manga(db_full_paths: &str) -> std::result::Result<Vec<MangaModel>, Error> {
+        select_all_manga_with_options(db_full_paths, SelectOptions::default())
+    }
+
+    // same as select_all_manga(), but i.e. without tags (manga.tags() is then empty)
+    pub fn select_all_manga_with_options(
+        db_full_paths: &str,
+        options: SelectOptions,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
         // just seek/query for all rows where ID > 0 (i.e. all rows)
-        let where_clause = format!("WHERE m.id > 0");
-        let row_sets_result = select_manga(db_full_paths, where_clause.as_str());
+        let filter = MangaFilter {
+            possible_min_id: Some(1),
+            ..MangaFilter::default()
+        };
+        let row_sets_result = select_manga(db_full_paths, &filter, options);
         match row_sets_result {
             Ok(row_sets) => {
                 if row_sets.len() == 0 {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                    return Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows));
                 }
                 Ok(row_sets)
             }
@@ -723,6 +1783,177 @@ pub mod model_sqlite3_manga {
         }
     }
 
+    // return row-sets that were modified on or after since_millis (epoch in milliseconds), newest first;
+    // unlike select_all_manga(), 0 rows is NOT an error here, it just means nothing changed recently
+    pub fn select_manga_updated_since(
+        db_full_paths: &str,
+        since_millis: i64,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let where_clause = format!(
+            "WHERE m.last_update_millis >= {} ORDER BY m.last_update_millis DESC",
+            since_millis
+        );
+        Ok(select_manga_where(
+            db_full_paths,
+            where_clause.as_str(),
+            &[],
+            SelectOptions::default(),
+        )?)
+    }
+
+    // rows whose millis_column is in [possible_from, possible_to), newest first; None leaves that end open.
+    // Compares the epoch millis, never the last_update text.  0 rows is NOT an error
+    fn select_between(
+        db_full_paths: &str,
+        millis_column: &str,
+        possible_from: Option<chrono::DateTime<chrono::Utc>>,
+        possible_to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<MangaModel>> {
+        let mut conditions = vec![format!("m.{} IS NOT NULL", millis_column)];
+        if let Some(from) = possible_from {
+            conditions.push(format!("m.{} >= {}", millis_column, from.timestamp_millis()));
+        }
+        if let Some(to) = possible_to {
+            conditions.push(format!("m.{} < {}", millis_column, to.timestamp_millis()));
+        }
+        let where_clause = format!(
+            "WHERE {} ORDER BY m.{} DESC",
+            conditions.join(" AND "),
+            millis_column
+        );
+        select_manga_where(db_full_paths, where_clause.as_str(), &[], SelectOptions::default())
+    }
+
+    // last updated (i.e. chapter read) within the range, see select_between()
+    pub fn updated_between(
+        db_full_paths: &str,
+        possible_from: Option<chrono::DateTime<chrono::Utc>>,
+        possible_to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        Ok(select_between(
+            db_full_paths,
+            "last_update_millis",
+            possible_from,
+            possible_to,
+        )?)
+    }
+
+    // written in any way (added, read, edited) within the range, see select_between(); what a delta export sends
+    pub fn changed_between(
+        db_full_paths: &str,
+        possible_from: Option<chrono::DateTime<chrono::Utc>>,
+        possible_to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        Ok(select_between(
+            db_full_paths,
+            "updated_at",
+            possible_from,
+            possible_to,
+        )?)
+    }
+
+    // first written to the database within the range, see select_between()
+    pub fn added_between(
+        db_full_paths: &str,
+        possible_from: Option<chrono::DateTime<chrono::Utc>>,
+        possible_to: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        Ok(select_between(
+            db_full_paths,
+            "added_millis",
+            possible_from,
+            possible_to,
+        )?)
+    }
+
+    // rows whose chapter is in [possible_min, possible_max] (both inclusive, None leaves that end open), furthest
+    // read first; rows whose chapter is not a number are never in range.  0 rows is NOT an error
+    pub fn select_by_chapter_range(
+        db_full_paths: &str,
+        possible_min: Option<&Chapter>,
+        possible_max: Option<&Chapter>,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let mut conditions = vec!["m.chapter_numeric IS NOT NULL".to_string()];
+        if let Some(min) = possible_min {
+            conditions.push(format!("m.chapter_numeric >= {}", min.numeric()));
+        }
+        if let Some(max) = possible_max {
+            conditions.push(format!("m.chapter_numeric <= {}", max.numeric()));
+        }
+        let where_clause = format!(
+            "WHERE {} ORDER BY m.chapter_numeric DESC, m.title",
+            conditions.join(" AND ")
+        );
+        Ok(select_manga_where(
+            db_full_paths,
+            where_clause.as_str(),
+            &[],
+            SelectOptions::default(),
+        )?)
+    }
+
+    // a single user field of the row; Ok(None) if it has no such field, Err(QueryReturnedNoRows) if id is not found
+    pub fn get_field(db_full_paths: &str, id: i64, key: &str) -> Result<Option<String>> {
+        let path = user_field_path(key)?;
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let possible_value = conn.query_row(
+            "SELECT json_extract(user_fields, ?1) FROM manga WHERE id = ?2",
+            params![path, id],
+            |row| row.get::<usize, Option<rusqlite::types::Value>>(0),
+        )?;
+        Ok(match possible_value {
+            Some(rusqlite::types::Value::Text(text)) => Some(text),
+            Some(rusqlite::types::Value::Integer(number)) => Some(number.to_string()),
+            Some(rusqlite::types::Value::Real(number)) => Some(number.to_string()),
+            _ => None,
+        })
+    }
+
+    // sets (or with None, removes) a single user field in place, leaving the other fields of the row alone;
+    // returns Ok(false) if id was not found
+    pub fn set_field(
+        db_full_paths: &str,
+        id: i64,
+        key: &str,
+        possible_value: Option<&str>,
+    ) -> Result<bool> {
+        let path = user_field_path(key)?;
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let rows_updated = match possible_value {
+            Some(value) => conn.execute(
+                "UPDATE manga SET user_fields = json_set(COALESCE(user_fields, '{}'), ?1, ?2) WHERE id = ?3",
+                params![path, value, id],
+            )?,
+            None => conn.execute(
+                "UPDATE manga SET user_fields = NULLIF(json_remove(COALESCE(user_fields, '{}'), ?1), '{}') WHERE id = ?2",
+                params![path, id],
+            )?,
+        };
+        Ok(rows_updated > 0)
+    }
+
+    // rows whose user field equals value, or with None, rows which have the field at all; by title
+    pub fn select_by_field(
+        db_full_paths: &str,
+        key: &str,
+        possible_value: Option<&str>,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let mut sql_params = vec![Value::Text(user_field_path(key)?)];
+        let where_clause = match possible_value {
+            Some(value) => {
+                sql_params.push(Value::Text(value.to_string()));
+                "WHERE json_extract(m.user_fields, ?1) = ?2 ORDER BY m.title"
+            }
+            None => "WHERE json_extract(m.user_fields, ?1) IS NOT NULL ORDER BY m.title",
+        };
+        Ok(select_manga_where(
+            db_full_paths,
+            where_clause,
+            &sql_params,
+            SelectOptions::default(),
+        )?)
+    }
+
     // in most cases, we do not need to specialize a method since all one has to do is setup their WHERE clause
     // to their likings as query fits their needs, but since it's mostly common to seek/query for row-sets based
     // on either/or title and/or url, we'll provide a specialized method for that here with boiler plate error
@@ -732,23 +1963,27 @@ pub mod model_sqlite3_manga {
         db_full_paths: &str,
         url: &str,
         title: &str,
-    ) -> Result<Vec<MangaModel>> {
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
         // unlike get_id(), this method allows wildcards in title and url, BUT neither can be empty (caller should
         // opt to set it to "%" wildcard ir only care about one or the other)
         if url.len() == 0 || title.len() == 0 {
-            return Err(rusqlite::Error::InvalidParameterName(format!(
+            return Err(Error::InvalidArgument(format!(
                 "select_manga_from_url_and_title: url '{}' or title '{}' is empty",
                 url, title
             )));
         }
         // Note that we'll use LIKE clause here instead of "=" in case caller wants to use wildcards
-        let where_clause = format!("WHERE m.url LIKE '{}' AND m.title LIKE '{}'", url, title);
-        let row_set_result = select_manga(db_full_paths, where_clause.as_str());
+        let filter = MangaFilter {
+            possible_title: Some(TextMatch::Like(title.to_string())),
+            possible_url: Some(TextMatch::Like(url.to_string())),
+            ..MangaFilter::default()
+        };
+        let row_set_result = select_manga(db_full_paths, &filter, SelectOptions::default());
         match row_set_result {
             Ok(row_sets) => {
                 // NOTE: Though it is NOT an error to get 0 rows, we'll return it as error anyway so that calling method doesn't need to check for 0 rows
                 if row_sets.len() == 0 {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                    return Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows));
                 }
                 Ok(row_sets)
             }
@@ -756,19 +1991,400 @@ pub mod model_sqlite3_manga {
         }
     }
 
-    // locate titles that are in JA_JP and see if it can find rows that have same URL but title is in
-    // romanized or EN_US; and if so, drop/delete the EN_US row and report that it's removed it
-    // from the database
-    //    pub fn prune_duplicates(db_full_paths: &str) -> Result<Vec<MangaModel>> // returns lists of deleted rows
-    //    {
-    //        // for each rows that has title in JA_JP, locate rows (manga.id) which has same BASE url or romanized title
-    //        // and map manga_id to manga_ids (i.e. single ID mapped to 1 or more IDs)
-    //
-    //    }
+    // rows on domain or any of its subdomains, same as site_migration::is_on_domain() ("example.com" also
+    // finds "www.example.com"); the distinct domains come from the index, so no url is parsed nor LIKE'd.
+    // 0 rows is NOT an error
+    pub fn select_by_domain(
+        db_full_paths: &str,
+        domain: &str,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let domain = domain.trim().to_lowercase();
+        let subdomain_suffix = format!(".{}", domain);
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let mut stmt = conn.prepare("SELECT DISTINCT domain FROM manga WHERE domain IS NOT NULL AND domain != ''")?;
+        let matching_domains = stmt
+            .query_map([], |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<String>>>()?
+            .into_iter()
+            .filter(|stored| *stored == domain || stored.ends_with(&subdomain_suffix))
+            .map(Value::Text)
+            .collect::<Vec<Value>>();
+        if matching_domains.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders: Vec<String> = (1..=matching_domains.len()).map(|i| format!("?{}", i)).collect();
+        let where_clause = format!("WHERE m.domain IN ({})", placeholders.join(","));
+        Ok(select_manga_where(
+            db_full_paths,
+            where_clause.as_str(),
+            &matching_domains,
+            SelectOptions::default(),
+        )?)
+    }
+
+    // rows whose tags satisfy expression, i.e. "isekai AND NOT dropped OR (seinen AND ongoing)" (see
+    // tag_expression), filtered by SQLite through one EXISTS subquery per tag; 0 rows is NOT an error
+    pub fn select_by_tag_expr(
+        db_full_paths: &str,
+        expression: &str,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let tag_expr =
+            tag_expression::parse(expression).map_err(|e| Error::InvalidArgument(e.to_string()))?;
+        let (sql_condition, sql_params) = tag_expr.to_sql("m");
+        let where_clause = format!("WHERE {}", sql_condition);
+        Ok(select_manga_where(
+            db_full_paths,
+            where_clause.as_str(),
+            &sql_params,
+            SelectOptions::default(),
+        )?)
+    }
+
+    // rows tagged tag (trimmed, ignoring ASCII case, as tag expressions compare it), joined through
+    // manga_to_tags_map, by title; 0 rows is NOT an error.  Combinations of tags are select_by_tag_expr()
+    pub fn select_manga_by_tag(
+        db_full_paths: &str,
+        tag: &str,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let filter = MangaFilter {
+            possible_tag: Some(tag.to_string()),
+            ..MangaFilter::default()
+        };
+        let mut mangas = select_manga(db_full_paths, &filter, SelectOptions::default())?;
+        mangas.sort_by(|a, b| a.title().cmp(b.title()));
+        Ok(mangas)
+    }
+
+    // number of rows per domain, most rows first (then by domain); rows without a domain are not counted
+    pub fn count_by_domain(db_full_paths: &str) -> Result<Vec<(String, usize)>> {
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let mut stmt = conn.prepare(
+            "SELECT domain, COUNT(*) FROM manga WHERE domain IS NOT NULL AND domain != ''
+                GROUP BY domain ORDER BY COUNT(*) DESC, domain",
+        )?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<usize, String>(0)?, row.get::<usize, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<(String, usize)>>>()?;
+        Ok(counts)
+    }
+
+    // tags of a single manga, for callers which selected without them; no tags is not an error
+    pub fn tags_for(db_full_paths: &str, manga_id: i64) -> Result<Vec<String>> {
+        let mut tags_by_manga_id = tags_for_many(db_full_paths, &[manga_id])?;
+        Ok(tags_by_manga_id.remove(&manga_id).unwrap_or_default())
+    }
+
+    // tags of several mangas in one query, keyed by manga id; ids without tags (or unknown ids) are not in the map
+    pub fn tags_for_many(db_full_paths: &str, ids: &[i64]) -> Result<HashMap<i64, Vec<String>>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let id_list: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        let where_clause = format!("WHERE m.id IN ({})", id_list.join(","));
+        select_tags_by_manga_id(&conn, where_clause.as_str(), &[])
+    }
+
+    // each tag with the number of manga it is attached to, most used first (then by tag); tags attached to none
+    // (left behind by deleted rows or replaced tags) are listed with 0, see delete_orphaned_tags()
+    pub fn tag_counts(db_full_paths: &str) -> Result<Vec<(String, usize)>> {
+        let conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let mut stmt = conn.prepare(
+            "SELECT TRIM(t.tag), COUNT(DISTINCT mt.manga_id) FROM tags AS t
+                LEFT JOIN manga_to_tags_map AS mt ON mt.tag_id = t.id
+                GROUP BY TRIM(t.tag) ORDER BY COUNT(DISTINCT mt.manga_id) DESC, TRIM(t.tag)",
+        )?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<usize, String>(0)?,
+                    row.get::<usize, i64>(1)? as usize,
+                ))
+            })?
+            .collect::<Result<Vec<(String, usize)>>>()?;
+        Ok(counts)
+    }
+
+    // the manga tagged from_tag are tagged into_tag instead (created if need be, kept if they have both) and
+    // from_tag is deleted, in a single transaction; tags are compared trimmed, as they are read.  Returns the
+    // number of manga which were tagged from_tag, None when there is no such tag
+    pub fn merge_tags(
+        db_full_paths: &str,
+        from_tag: &str,
+        into_tag: &str,
+    ) -> std::result::Result<Option<usize>, Error> {
+        let (from_tag, into_tag) = (from_tag.trim(), into_tag.trim());
+        if into_tag.is_empty() {
+            return Err(Error::InvalidArgument("tag is empty".to_string()));
+        }
+        let mut conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let from_tag_ids = transaction
+                .prepare("SELECT id FROM tags WHERE TRIM(tag) = ?1 AND tag != ?2")?
+                .query_map(params![from_tag, into_tag], |row| row.get::<usize, i64>(0))?
+                .collect::<Result<Vec<i64>>>()?;
+            if from_tag_ids.is_empty() {
+                return Ok(None);
+            }
+            transaction.execute(
+                "INSERT OR IGNORE INTO tags (tag) VALUES (?1)",
+                params![into_tag],
+            )?;
+            let into_tag_id = transaction.query_row(
+                "SELECT id FROM tags WHERE tag = ?1",
+                params![into_tag],
+                |row| row.get::<usize, i64>(0),
+            )?;
+            let mut manga_ids: HashSet<i64> = HashSet::new();
+            for from_tag_id in from_tag_ids {
+                let tagged_ids = transaction
+                    .prepare("SELECT manga_id FROM manga_to_tags_map WHERE tag_id = ?1")?
+                    .query_map(params![from_tag_id], |row| row.get::<usize, i64>(0))?
+                    .collect::<Result<Vec<i64>>>()?;
+                manga_ids.extend(tagged_ids);
+                transaction.execute(
+                    "INSERT OR IGNORE INTO manga_to_tags_map (manga_id, tag_id)
+                        SELECT manga_id, ?1 FROM manga_to_tags_map WHERE tag_id = ?2",
+                    params![into_tag_id, from_tag_id],
+                )?;
+                transaction.execute(
+                    "DELETE FROM manga_to_tags_map WHERE tag_id = ?1",
+                    params![from_tag_id],
+                )?;
+                transaction.execute("DELETE FROM tags WHERE id = ?1", params![from_tag_id])?;
+            }
+            transaction.commit()?;
+            Ok(Some(manga_ids.len()))
+        })
+        .map_err(Error::from)
+    }
+
+    // old_tag is new_tag on every manga it is attached to; same as merge_tags(), except that new_tag must not be a
+    // tag yet (merging into it is a different thing to ask for).  Returns the number of manga tagged, None when
+    // there is no such tag
+    pub fn rename_tag(
+        db_full_paths: &str,
+        old_tag: &str,
+        new_tag: &str,
+    ) -> std::result::Result<Option<usize>, Error> {
+        let is_existing_tag = tag_counts(db_full_paths)?
+            .iter()
+            .any(|(tag, _)| tag == new_tag.trim() && tag != old_tag.trim());
+        if is_existing_tag {
+            return Err(Error::InvalidArgument(format!(
+                "tag '{}' already exists, merge '{}' into it instead",
+                new_tag.trim(),
+                old_tag.trim()
+            )));
+        }
+        merge_tags(db_full_paths, old_tag, new_tag)
+    }
+
+    // deletes the tags attached to no manga, returning them
+    pub fn delete_orphaned_tags(db_full_paths: &str) -> Result<Vec<String>> {
+        let mut conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        model_sqlite3_connection::with_busy_retry(|| {
+            let transaction = conn.transaction()?;
+            let orphaned_tags = transaction
+                .prepare(
+                    "SELECT tag FROM tags WHERE id NOT IN (SELECT tag_id FROM manga_to_tags_map WHERE tag_id IS NOT NULL)
+                        ORDER BY tag",
+                )?
+                .query_map([], |row| row.get::<usize, String>(0))?
+                .collect::<Result<Vec<String>>>()?;
+            transaction.execute(
+                "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM manga_to_tags_map WHERE tag_id IS NOT NULL)",
+                [],
+            )?;
+            transaction.commit()?;
+            Ok(orphaned_tags)
+        })
+    }
+
+    // groups of rows that look like the same series: same base url (ignoring scheme, "www." and trailing "/")
+    // or same romanized title (ignoring case and punctuation); rows in a group are ordered by id, and rows
+    // without any look-alike are not returned.  Nothing is deleted, callers (i.e. `manga tui`) decide.
+    pub fn duplicate_groups(mangas: &[MangaModel]) -> Vec<Vec<MangaModel>> {
+        fn title_key(manga: &MangaModel) -> String {
+            manga
+                .title_romanized()
+                .unwrap_or_else(|| manga.title().to_string())
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect()
+        }
+        fn root(parents: &mut Vec<usize>, i: usize) -> usize {
+            let mut i = i;
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        // union-find over the row indices, joining rows that share either key
+        let mut parents: Vec<usize> = (0..mangas.len()).collect();
+        let mut first_index_of_key: std::collections::HashMap<String, usize> =
+            std::collections::HashMap::new();
+        for (i, manga) in mangas.iter().enumerate() {
+            let keys = [
+                format!("url:{}", normalized_url(manga.url())),
+                format!("title:{}", title_key(manga)),
+            ];
+            for key in keys.iter().filter(|key| !key.ends_with(':')) {
+                match first_index_of_key.get(key) {
+                    Some(&j) => {
+                        let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+                        parents[root_i] = root_j;
+                    }
+                    None => {
+                        first_index_of_key.insert(key.clone(), i);
+                    }
+                }
+            }
+        }
+
+        let mut groups: std::collections::BTreeMap<usize, Vec<MangaModel>> =
+            std::collections::BTreeMap::new();
+        for (i, manga) in mangas.iter().enumerate() {
+            let root_i = root(&mut parents, i);
+            groups.entry(root_i).or_default().push(manga.clone());
+        }
+        let mut groups: Vec<Vec<MangaModel>> = groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by_key(|manga| manga.id());
+                group
+            })
+            .collect();
+        groups.sort_by_key(|group| group[0].id());
+        groups
+    }
+
+    // result of prune_duplicates(): the row kept (as merged) and the rows deleted into it
+    #[derive(Debug, Clone)]
+    pub struct PrunedGroup {
+        pub kept: MangaModel,
+        pub deleted: Vec<MangaModel>,
+    }
+
+    // keeper with what the others have over it: the furthest read chapter (with its url and last update), each
+    // distinct note (one per line), tags, and the titles of the others as alt titles; feed url, MyAnimeList and
+    // latest available chapter only when the keeper has none
+    pub fn merge_into(keeper: &MangaModel, others: &[MangaModel]) -> MangaModel {
+        let mut merged = keeper.clone();
+        let chapter_of =
+            |manga: &MangaModel| manga.chapter().and_then(|chapter| Chapter::parse(&chapter));
+        for other in others {
+            if chapter_of(other) > chapter_of(&merged) {
+                merged.set_chapter(other.chapter());
+                merged.set_url_with_chapter(other.url_with_chapter());
+                merged.set_last_update(other.last_update());
+                merged.set_last_update_millis(other.last_update_millis());
+            }
+            if merged.feed_url().is_none() {
+                merged.set_feed_url(other.feed_url());
+            }
+            if merged.my_anime_list().is_none() {
+                merged.set_my_anime_list(other.my_anime_list());
+            }
+            if merged.latest_available_chapter().is_none() {
+                merged.set_latest_available_chapter(other.latest_available_chapter());
+            }
+        }
+        let mut notes: Vec<String> = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        let mut alt_titles: Vec<String> = keeper.alt_titles().to_vec();
+        for manga in std::iter::once(keeper).chain(others.iter()) {
+            if let Some(note) = manga.notes().filter(|note| !notes.contains(note)) {
+                notes.push(note);
+            }
+            for tag in manga.tags().iter().map(|tag| tag.trim().to_string()) {
+                if !tag.is_empty() && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        for title in others.iter().flat_map(|other| {
+            std::iter::once(other.title())
+                .chain(other.alt_titles().iter().map(|title| title.as_str()))
+        }) {
+            if title != keeper.title() && !alt_titles.iter().any(|alt_title| alt_title == title) {
+                alt_titles.push(title.to_string());
+            }
+        }
+        merged.set_notes(make_none_if_empty(Some(notes.join("\n"))));
+        merged.set_tags(tags);
+        merged.set_alt_titles(alt_titles);
+        merged
+    }
+
+    // rows of the same series (see duplicate_groups()) of which some are titled in Japanese and the others in
+    // romaji or English: the first Japanese one is kept, the romaji/English ones are merged into it (see
+    // merge_into()) and deleted.  Groups with no Japanese title, or nothing but Japanese titles, are left as
+    // they are.  Each group is merged in a single transaction, so that a failure leaves it as it was.  With
+    // dry_run nothing is written, the groups are only reported
+    pub fn prune_duplicates(
+        db_full_paths: &str,
+        dry_run: bool,
+    ) -> std::result::Result<Vec<PrunedGroup>, Error> {
+        let is_japanese = |manga: &MangaModel| {
+            !matches!(
+                kakasi::is_japanese(manga.title()),
+                kakasi::IsJapanese::False
+            )
+        };
+        let mangas = match select_all_manga(db_full_paths) {
+            Ok(mangas) => mangas,
+            Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let mut pruned = Vec::new();
+        for group in duplicate_groups(&mangas) {
+            let (japanese, others): (Vec<MangaModel>, Vec<MangaModel>) =
+                group.into_iter().partition(|manga| is_japanese(manga));
+            if japanese.is_empty() || others.is_empty() {
+                continue;
+            }
+            let kept = merge_into(&japanese[0], &others);
+            if !dry_run {
+                let tags: Vec<String> = kept.tags().iter().map(|tag| tag.to_string()).collect();
+                let is_merged = model_sqlite3_connection::with_busy_retry(|| {
+                    let transaction = conn.transaction()?;
+                    // the row kept is gone (deleted since it was read): the transaction rolls back
+                    if update_manga_with_connection(&transaction, &kept)? == 0 {
+                        return Ok(false);
+                    }
+                    add_tags_with_connection(&transaction, kept.id(), &tags)?;
+                    for other in others.iter() {
+                        delete_manga_with_connection(&transaction, other.id())?;
+                    }
+                    transaction.commit()?;
+                    Ok(true)
+                })?;
+                if !is_merged {
+                    return Err(Error::NotFound(kept.id()));
+                }
+            }
+            pruned.push(PrunedGroup {
+                kept,
+                deleted: others,
+            });
+        }
+        Ok(pruned)
+    }
 
     #[cfg(test)]
     mod tests {
         use crate::model_manga::{self, model_manga::MangaModel};
+        use std::collections::BTreeMap;
         const K_MANGA_TITLE: &str = "ゲート―自衛隊彼の地にて、斯く戦えり";
         const K_MANGA_URL_WITH_CHAPTERS: &str = "https://example.com/manga/gate-chapter-10/";
 
@@ -800,6 +2416,904 @@ pub mod model_sqlite3_manga {
             std::fs::remove_file(db_full_paths).unwrap();
         }
 
+        // databases created before feed_url existed should get the column appended by create_tables()
+        #[test]
+        fn test_add_missing_manga_columns() {
+            let db_full_paths = "samples/test_add_missing_manga_columns.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            {
+                let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+                conn.execute(
+                    "CREATE TABLE manga (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, title_romanized TEXT, url TEXT NOT NULL, url_with_chapter TEXT, chapter TEXT, last_update TEXT, last_update_millis INTEGER, notes TEXT, tags TEXT, my_anime_list TEXT, UNIQUE(title, url))",
+                    [],
+                )
+                .unwrap();
+            }
+            super::create_tables(db_full_paths).unwrap();
+
+            let mut manga_no_id = make_sample_row();
+            manga_no_id.set_feed_url(Some("https://example.com/manga/gate/feed/".to_string()));
+            let manga_inserted = super::insert_manga(db_full_paths, &manga_no_id).unwrap();
+            super::update_latest_available_chapter(db_full_paths, manga_inserted.id(), "12").unwrap();
+
+            let manga_selected =
+                super::select_manga_by_id(db_full_paths, manga_inserted.id()).unwrap();
+            assert_eq!(
+                manga_selected.feed_url(),
+                Some("https://example.com/manga/gate/feed/".to_string())
+            );
+            assert_eq!(manga_selected.latest_available_chapter(), Some("12".to_string()));
+
+            // re-importing the bookmark (which has no feed_url) should not wipe it
+            let manga_upserted = super::upsert_manga(db_full_paths, &make_sample_row()).unwrap();
+            assert_eq!(manga_upserted.feed_url(), manga_selected.feed_url());
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_bulk_upsert_manga() {
+            let db_full_paths = "samples/test_bulk_upsert_manga.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let existing = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            super::update_latest_available_chapter(db_full_paths, existing.id(), "12").unwrap();
+
+            let mut read_further = make_sample_row();
+            read_further.set_chapter(Some("5".to_string()));
+            read_further.set_tags(vec!["isekai".to_string()]);
+            let mut others: Vec<MangaModel> = (0..100)
+                .map(|i| {
+                    MangaModel::new_from_required_elements(
+                        &format!("Manga {}", i),
+                        &format!("https://example.com/manga/{}/", i),
+                        i + 1, // ignored, the DB assigns the id
+                    )
+                    .unwrap()
+                })
+                .collect();
+            others[0].set_tags(vec!["isekai".to_string(), "seinen".to_string()]);
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+
+            // insert only: the existing row is left alone, but tagged
+            let mut mangas = vec![read_further.clone()];
+            mangas.extend(others.iter().cloned());
+            let summary = super::bulk_insert_manga(&mut conn, &mangas).unwrap();
+            assert_eq!((summary.inserted, summary.updated), (100, 0));
+            assert_eq!(summary.mangas[0].id(), existing.id());
+            let manga_selected = super::select_manga_by_id(db_full_paths, existing.id()).unwrap();
+            assert_eq!(manga_selected.chapter(), existing.chapter());
+            assert_eq!(manga_selected.tags().len(), 1);
+
+            // upsert: the existing row is updated, keeping what the update checker found
+            let summary = super::bulk_upsert_manga(&mut conn, &[read_further]).unwrap();
+            assert_eq!((summary.inserted, summary.updated), (0, 1));
+            let manga_selected = super::select_manga_by_id(db_full_paths, existing.id()).unwrap();
+            assert_eq!(manga_selected.chapter(), Some("5".to_string()));
+            assert_eq!(manga_selected.latest_available_chapter(), Some("12".to_string()));
+            let tagged = super::select_manga_by_id(db_full_paths, summary.mangas[0].id()).unwrap();
+            assert_eq!(tagged.tags().len(), 1);
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 101);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_manga_store() {
+            let db_full_paths = "samples/test_manga_store.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mangas: Vec<MangaModel> = (0..3)
+                .map(|i| MangaModel::new_from_required_elements(&format!("Manga {}", i), &format!("https://example.com/manga/{}/", i), 1).unwrap())
+                .collect();
+            let mut store = super::MangaStore::open(db_full_paths).unwrap();
+            let summary = store.insert_batch(&mangas).unwrap();
+            assert_eq!(summary.inserted, 3);
+            // same connection, the next batch sees the rows of the previous one
+            let summary = store.insert_batch(&mangas[1..]).unwrap();
+            assert_eq!(summary.inserted, 0);
+            assert_eq!(summary.mangas[0].id(), store.insert_batch(&mangas[1..2]).unwrap().mangas[0].id());
+            drop(store);
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 3);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_bulk_insert_url_checksum() {
+            let db_full_paths = "samples/test_bulk_insert_url_checksum.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            assert_eq!(super::normalized_url("https://www.Example.com/gate/"), "example.com/gate");
+            assert_eq!(
+                super::url_checksum("http://example.com/gate"),
+                super::url_checksum("https://www.example.com/gate/")
+            );
+            // written without a checksum (i.e. by an older version)
+            let gate = MangaModel::new_from_required_elements("Gate", "https://www.example.com/gate/", 1).unwrap();
+            let stored = super::insert_manga(db_full_paths, &gate).unwrap();
+
+            let same_gate = MangaModel::new_from_required_elements("Gate", "http://example.com/gate", 1).unwrap();
+            let other_title = MangaModel::new_from_required_elements("Akira", "http://example.com/gate", 2).unwrap();
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            let summary = super::bulk_insert_manga(&mut conn, &[same_gate.clone(), other_title, same_gate]).unwrap();
+            assert_eq!(summary.inserted, 1);
+            assert_eq!(summary.mangas[0].id(), stored.id());
+            assert_eq!(summary.mangas[2].id(), stored.id());
+            let query_plan: String = conn
+                .query_row(
+                    "EXPLAIN QUERY PLAN SELECT id FROM manga WHERE url_checksum = 1 AND title = 'Gate'",
+                    [],
+                    |row| row.get(3),
+                )
+                .unwrap();
+            assert!(query_plan.contains("manga_url_checksum"));
+
+            // a url changed elsewhere loses its checksum, until the next import
+            super::update_url(db_full_paths, stored.id(), "https://example.com/gate-2/", "moved").unwrap();
+            let possible_checksum: Option<u32> = conn
+                .query_row("SELECT url_checksum FROM manga WHERE id = ?1", [stored.id()], |row| row.get(0))
+                .unwrap();
+            assert_eq!(possible_checksum, None);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_manga_filter() {
+            let db_full_paths = "samples/test_select_manga_filter.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mut frieren = MangaModel::new_from_required_elements("Frieren's Journey", "https://example.com/frieren'/", 1).unwrap();
+            frieren.set_tags(vec!["fantasy".to_string()]);
+            let frieren = super::insert_manga(db_full_paths, &frieren).unwrap();
+            let gate = super::insert_manga(db_full_paths, &MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 2).unwrap()).unwrap();
+
+            // MangaModel turns "'" into "’", but rows written otherwise (i.e. by hand) may have it as is: quotes are
+            // part of the title and url, not of the SQL
+            let filter = super::MangaFilter { possible_title: Some(super::TextMatch::Exact(frieren.title().to_string())), ..super::MangaFilter::default() };
+            assert_eq!(super::select_manga(db_full_paths, &filter, super::SelectOptions::default()).unwrap()[0].id(), frieren.id());
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("INSERT INTO manga (title, url) VALUES ('Berserk''s Guts', 'https://example.com/berserk''/')", []).unwrap();
+            let berserk_id = conn.last_insert_rowid();
+            assert_eq!(super::get_id(db_full_paths, "Berserk's Guts", "https://example.com/berserk'/").unwrap().id(), berserk_id);
+            assert_eq!(super::select_manga_from_url_and_title(db_full_paths, "%", "Berserk'%").unwrap().len(), 1);
+            assert!(super::get_id(db_full_paths, "' OR '1'='1", "' OR '1'='1").is_err());
+
+            let filter = super::MangaFilter { possible_tag: Some(" Fantasy".to_string()), ..super::MangaFilter::default() };
+            let tagged = super::select_manga(db_full_paths, &filter, super::SelectOptions::default()).unwrap();
+            assert_eq!(tagged.len(), 1);
+            assert_eq!(tagged[0].id(), frieren.id());
+            let filter = super::MangaFilter {
+                possible_url: Some(super::TextMatch::Like("https://example.com/%".to_string())),
+                possible_min_id: Some(gate.id()),
+                possible_max_id: Some(gate.id()),
+                ..super::MangaFilter::default()
+            };
+            let selected = super::select_manga(db_full_paths, &filter, super::SelectOptions::default()).unwrap();
+            assert_eq!(selected.iter().map(|manga| manga.id()).collect::<Vec<i64>>(), vec![gate.id()]);
+            assert_eq!(super::select_manga(db_full_paths, &super::MangaFilter::default(), super::SelectOptions::default()).unwrap().len(), 3);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_prune_duplicates() {
+            let db_full_paths = "samples/test_prune_duplicates.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mut japanese = MangaModel::new_from_required_elements(
+                "ドクターストーン",
+                "https://example.com/dr-stone-chapter-3/",
+                1,
+            )
+            .unwrap();
+            japanese.set_notes(Some("Senku".to_string()));
+            japanese.set_tags(vec!["shounen".to_string()]);
+            let japanese = super::insert_manga(db_full_paths, &japanese).unwrap();
+            let mut romaji = MangaModel::new_from_required_elements(
+                "Dr. Stone",
+                "http://www.example.com/dr-stone-chapter-12/",
+                2,
+            )
+            .unwrap();
+            romaji.set_notes(Some("Chrome".to_string()));
+            romaji.set_tags(vec!["sci-fi".to_string()]);
+            let romaji = super::insert_manga(db_full_paths, &romaji).unwrap();
+            // look-alikes, but neither is Japanese
+            super::insert_manga(
+                db_full_paths,
+                &MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 3)
+                    .unwrap(),
+            )
+            .unwrap();
+            super::insert_manga(
+                db_full_paths,
+                &MangaModel::new_from_required_elements("GATE", "https://example.org/gate/", 4)
+                    .unwrap(),
+            )
+            .unwrap();
+
+            let pruned = super::prune_duplicates(db_full_paths, true).unwrap();
+            assert_eq!(pruned.len(), 1);
+            assert_eq!(pruned[0].kept.id(), japanese.id());
+            assert_eq!(
+                pruned[0]
+                    .deleted
+                    .iter()
+                    .map(|manga| manga.id())
+                    .collect::<Vec<i64>>(),
+                vec![romaji.id()]
+            );
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 4);
+
+            super::prune_duplicates(db_full_paths, false).unwrap();
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 3);
+            assert!(super::select_manga_by_id(db_full_paths, romaji.id()).is_err());
+            let kept = super::select_manga_by_id(db_full_paths, japanese.id()).unwrap();
+            assert_eq!(kept.title(), "ドクターストーン");
+            assert_eq!(kept.chapter(), Some("12".to_string()));
+            assert_eq!(kept.notes(), Some("Senku\nChrome".to_string()));
+            assert_eq!(kept.alt_titles(), ["Dr. Stone".to_string()]);
+            let mut tags: Vec<String> = kept
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .collect();
+            tags.sort();
+            assert_eq!(tags, vec!["sci-fi", "shounen"]);
+            // nothing left to prune
+            assert!(super::prune_duplicates(db_full_paths, false)
+                .unwrap()
+                .is_empty());
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_by_domain() {
+            let db_full_paths = "samples/test_select_by_domain.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            assert_eq!(super::url_domain("https://www.Example.com/gate/"), "www.example.com");
+            assert_eq!(super::url_domain("not a url"), "");
+            let gate = super::insert_manga(db_full_paths, &MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap()).unwrap();
+            super::insert_manga(db_full_paths, &MangaModel::new_from_required_elements("Akira", "https://m.example.com/akira/", 2).unwrap()).unwrap();
+            super::insert_manga(db_full_paths, &MangaModel::new_from_required_elements("Monster", "https://notexample.com/monster/", 3).unwrap()).unwrap();
+            // written without a domain (i.e. by an older version), filled in by create_tables()
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("INSERT INTO manga (title, url) VALUES ('Berserk', 'https://example.com/berserk/')", []).unwrap();
+            super::create_tables(db_full_paths).unwrap();
+
+            let mut titles: Vec<String> = super::select_by_domain(db_full_paths, "Example.com").unwrap().iter().map(|manga| manga.title().to_string()).collect();
+            titles.sort();
+            assert_eq!(titles, vec!["Akira", "Berserk", "Gate"]);
+            assert!(super::select_by_domain(db_full_paths, "example.org").unwrap().is_empty());
+            assert_eq!(
+                super::count_by_domain(db_full_paths).unwrap(),
+                vec![("example.com".to_string(), 2), ("m.example.com".to_string(), 1), ("notexample.com".to_string(), 1)]
+            );
+            let query_plan: String = conn
+                .query_row("EXPLAIN QUERY PLAN SELECT id FROM manga WHERE domain = 'example.com'", [], |row| row.get(3))
+                .unwrap();
+            assert!(query_plan.contains("manga_domain"));
+
+            // moved to another site: the domain follows the url
+            super::update_url(db_full_paths, gate.id(), "https://example.org/gate/", "migrate-site").unwrap();
+            assert_eq!(super::select_by_domain(db_full_paths, "example.org").unwrap().len(), 1);
+            assert_eq!(super::select_by_domain(db_full_paths, "example.com").unwrap().len(), 2);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_by_tag_expr() {
+            let db_full_paths = "samples/test_select_by_tag_expr.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let with_tags = |title: &str, id: u32, tags: &[&str]| {
+                let mut manga = MangaModel::new_from_required_elements(
+                    title,
+                    &format!("https://example.com/{}/", id),
+                    id,
+                )
+                .unwrap();
+                manga.set_tags(tags.iter().map(|tag| tag.to_string()).collect());
+                manga
+            };
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            super::bulk_insert_manga(
+                &mut conn,
+                &[
+                    with_tags("Mushoku Tensei", 1, &["isekai", "ongoing"]),
+                    with_tags("Overlord", 2, &["Isekai", "dropped"]),
+                    with_tags("Berserk", 3, &["seinen", "ongoing"]),
+                    with_tags("Vagabond", 4, &["seinen", "hiatus"]),
+                    with_tags("Yotsuba", 5, &[]),
+                ],
+            )
+            .unwrap();
+
+            let titles = |expression: &str| {
+                let mut titles: Vec<String> = super::select_by_tag_expr(db_full_paths, expression)
+                    .unwrap()
+                    .iter()
+                    .map(|manga| manga.title().to_string())
+                    .collect();
+                titles.sort();
+                titles
+            };
+            assert_eq!(
+                titles("isekai AND NOT dropped OR (seinen AND ongoing)"),
+                vec!["Berserk", "Mushoku Tensei"]
+            );
+            assert_eq!(titles("ISEKAI"), vec!["Mushoku Tensei", "Overlord"]);
+            assert_eq!(titles("NOT (isekai OR seinen)"), vec!["Yotsuba"]);
+            assert!(titles("\"no such tag\"").is_empty());
+            // tags are still all there, not only the ones asked for
+            let berserk = super::select_by_tag_expr(db_full_paths, "seinen AND ongoing").unwrap();
+            assert_eq!(berserk[0].tags().len(), 2);
+            assert!(super::select_by_tag_expr(db_full_paths, "isekai AND").is_err());
+
+            let by_tag = |tag: &str| super::select_manga_by_tag(db_full_paths, tag).unwrap().iter().map(|manga| manga.title().to_string()).collect::<Vec<String>>();
+            assert_eq!(by_tag(" ISEKAI"), vec!["Mushoku Tensei", "Overlord"]);
+            assert_eq!(by_tag("hiatus"), vec!["Vagabond"]);
+            assert!(by_tag("isekai AND seinen").is_empty());
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_updated_and_added_between() {
+            let db_full_paths = "samples/test_updated_and_added_between.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let millis = |date: &str| chrono::DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&chrono::Utc);
+            let updated_at = |title: &str, id: u32, date: &str| {
+                let mut manga = MangaModel::new_from_required_elements(title, &format!("https://example.com/{}/", id), id).unwrap();
+                manga.set_last_update_millis(Some(millis(date).timestamp_millis()));
+                super::insert_manga(db_full_paths, &manga).unwrap()
+            };
+            updated_at("Gate", 1, "2023-09-01T00:00:00Z");
+            updated_at("Akira", 2, "2023-09-15T12:00:00Z");
+            updated_at("Monster", 3, "2023-10-01T00:00:00Z");
+            // last_update text says otherwise, only the millis count
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("UPDATE manga SET last_update = '2099-01-01 00:00:00'", []).unwrap();
+            // written by an older version: added when last updated
+            conn.execute(
+                "INSERT INTO manga (title, url, last_update_millis) VALUES ('Berserk', 'https://example.com/berserk/', ?1)",
+                [millis("2020-01-01T00:00:00Z").timestamp_millis()],
+            )
+            .unwrap();
+            super::create_tables(db_full_paths).unwrap();
+
+            let titles = |mangas: Vec<MangaModel>| mangas.iter().map(|manga| manga.title().to_string()).collect::<Vec<String>>();
+            assert_eq!(
+                titles(super::updated_between(db_full_paths, Some(millis("2023-09-01T00:00:00Z")), Some(millis("2023-10-01T00:00:00Z"))).unwrap()),
+                vec!["Akira", "Gate"]
+            );
+            assert_eq!(titles(super::updated_between(db_full_paths, None, Some(millis("2023-01-01T00:00:00Z"))).unwrap()), vec!["Berserk"]);
+            assert_eq!(titles(super::added_between(db_full_paths, None, Some(millis("2023-01-01T00:00:00Z"))).unwrap()), vec!["Berserk"]);
+            assert_eq!(super::added_between(db_full_paths, Some(chrono::Utc::now() - chrono::Duration::hours(1)), None).unwrap().len(), 3);
+            let query_plan: String = conn
+                .query_row("EXPLAIN QUERY PLAN SELECT id FROM manga WHERE added_millis >= 0", [], |row| row.get(3))
+                .unwrap();
+            assert!(query_plan.contains("manga_added_millis"));
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_by_chapter_range() {
+            let db_full_paths = "samples/test_select_by_chapter_range.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            assert_eq!(super::chapter_numeric("12-1"), Some(12.001));
+            assert_eq!(super::chapter_numeric("extra"), None);
+            let read_until = |title: &str, id: u32, chapter: &str| {
+                let mut manga = MangaModel::new_from_required_elements(title, &format!("https://example.com/{}/", id), id).unwrap();
+                manga.set_chapter(Some(chapter.to_string()));
+                super::insert_manga(db_full_paths, &manga).unwrap()
+            };
+            read_until("Gate", 1, "9");
+            read_until("Akira", 2, "100");
+            let mut monster = read_until("Monster", 3, "12.9");
+            read_until("Berserk", 4, "extra");
+            // written by an older version (as text "9" < "12" < "100"), filled in by create_tables()
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("INSERT INTO manga (title, url, chapter) VALUES ('Dr. Stone', 'https://example.com/5/', '12')", []).unwrap();
+            super::create_tables(db_full_paths).unwrap();
+
+            let titles = |mangas: Vec<MangaModel>| mangas.iter().map(|manga| manga.title().to_string()).collect::<Vec<String>>();
+            let chapter = |chapter: &str| model_manga::model_manga::Chapter::parse(chapter).unwrap();
+            assert_eq!(titles(super::select_by_chapter_range(db_full_paths, None, None).unwrap()), vec!["Akira", "Monster", "Dr. Stone", "Gate"]);
+            assert_eq!(titles(super::select_by_chapter_range(db_full_paths, Some(&chapter("100")), None).unwrap()), vec!["Akira"]);
+            assert_eq!(titles(super::select_by_chapter_range(db_full_paths, Some(&chapter("10")), Some(&chapter("12.9"))).unwrap()), vec!["Monster", "Dr. Stone"]);
+            // every writer of chapter keeps it current
+            monster.set_chapter(Some("12.10".to_string()));
+            super::update_manga(db_full_paths, &monster).unwrap();
+            assert_eq!(titles(super::select_by_chapter_range(db_full_paths, Some(&chapter("12.10")), Some(&chapter("99"))).unwrap()), vec!["Monster"]);
+            monster.set_chapter(Some("extra".to_string()));
+            super::update_url_and_chapter(db_full_paths, &monster).unwrap();
+            assert_eq!(titles(super::select_by_chapter_range(db_full_paths, Some(&chapter("10")), None).unwrap()), vec!["Akira", "Dr. Stone"]);
+            let query_plan: String = conn
+                .query_row("EXPLAIN QUERY PLAN SELECT id FROM manga WHERE chapter_numeric >= 100", [], |row| row.get(3))
+                .unwrap();
+            assert!(query_plan.contains("manga_chapter_numeric"));
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_user_fields() {
+            let db_full_paths = "samples/test_user_fields.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mut gate = MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap();
+            gate.set_user_fields(BTreeMap::from([
+                ("rating".to_string(), "9".to_string()),
+                ("format".to_string(), "webtoon".to_string()),
+            ]));
+            let gate = super::insert_manga(db_full_paths, &gate).unwrap();
+            let akira = super::insert_manga(db_full_paths, &MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2).unwrap()).unwrap();
+
+            assert_eq!(super::select_manga_by_id(db_full_paths, gate.id()).unwrap().user_fields(), gate.user_fields());
+            assert_eq!(super::get_field(db_full_paths, gate.id(), "format").unwrap(), Some("webtoon".to_string()));
+            assert_eq!(super::get_field(db_full_paths, akira.id(), "format").unwrap(), None);
+            assert!(super::get_field(db_full_paths, 99, "format").is_err());
+            assert!(super::get_field(db_full_paths, gate.id(), "say \"cheese\"").is_err());
+
+            // a single field, the others are left alone
+            assert!(super::set_field(db_full_paths, akira.id(), "my list", Some("it's on")).unwrap());
+            assert!(!super::set_field(db_full_paths, 99, "rating", Some("1")).unwrap());
+            assert!(super::set_field(db_full_paths, gate.id(), "rating", Some("10")).unwrap());
+            let titles = |mangas: Vec<MangaModel>| mangas.iter().map(|manga| manga.title().to_string()).collect::<Vec<String>>();
+            assert_eq!(titles(super::select_by_field(db_full_paths, "rating", Some("10")).unwrap()), vec!["Gate"]);
+            assert_eq!(titles(super::select_by_field(db_full_paths, "my list", Some("it's on")).unwrap()), vec!["Akira"]);
+            assert_eq!(titles(super::select_by_field(db_full_paths, "format", None).unwrap()), vec!["Gate"]);
+            assert!(super::set_field(db_full_paths, akira.id(), "my list", None).unwrap());
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            let possible_json: Option<String> = conn.query_row("SELECT user_fields FROM manga WHERE id = ?1", [akira.id()], |row| row.get(0)).unwrap();
+            assert_eq!(possible_json, None);
+
+            // bookmarks carry no fields, re-importing keeps them
+            let reimported = MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap();
+            super::upsert_manga(db_full_paths, &reimported).unwrap();
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            super::bulk_upsert_manga(&mut conn, &[reimported]).unwrap();
+            let gate = super::select_manga_by_id(db_full_paths, gate.id()).unwrap();
+            assert_eq!(gate.user_fields().len(), 2);
+            assert_eq!(gate.user_fields().get("rating"), Some(&"10".to_string()));
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_updated_at() {
+            let db_full_paths = "samples/test_updated_at.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let before_millis = chrono::Utc::now().timestamp_millis() - 1000;
+            let gate = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            let updated_at = |id: i64| super::select_manga_by_id(db_full_paths, id).unwrap().updated_at_millis().unwrap();
+            assert!(updated_at(gate.id()) >= before_millis);
+
+            // whoever writes the row, and however, without having to say so
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("UPDATE manga SET updated_at = 1 WHERE id = ?1", [gate.id()]).unwrap();
+            assert_eq!(updated_at(gate.id()), 1);
+            conn.execute("UPDATE manga SET notes = 'by hand' WHERE id = ?1", [gate.id()]).unwrap();
+            assert!(updated_at(gate.id()) >= before_millis);
+            conn.execute("UPDATE manga SET updated_at = 1 WHERE id = ?1", [gate.id()]).unwrap();
+            super::set_field(db_full_paths, gate.id(), "rating", Some("9")).unwrap();
+            assert!(updated_at(gate.id()) >= before_millis);
+
+            // written by an older version: as of its last update
+            conn.execute("INSERT INTO manga (title, url, last_update_millis) VALUES ('Akira', 'https://example.com/akira/', 1234)", []).unwrap();
+            let akira_id = conn.last_insert_rowid();
+            conn.execute("UPDATE manga SET updated_at = NULL WHERE id = ?1", [akira_id]).unwrap();
+            super::create_tables(db_full_paths).unwrap();
+            assert_eq!(updated_at(akira_id), 1234);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_bulk_upsert_bookmark_guid() {
+            let db_full_paths = "samples/test_bulk_upsert_bookmark_guid.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mut gate = MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap();
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            // imported before GUIDs were kept: the row learns it on the next import
+            let stored = super::bulk_insert_manga(&mut conn, &[gate.clone()]).unwrap().mangas[0].clone();
+            gate.set_bookmark_guid(Some("a1B2c3D4e5F6".to_string()));
+            super::bulk_insert_manga(&mut conn, &[gate.clone()]).unwrap();
+            let selected = super::select_manga_by_id(db_full_paths, stored.id()).unwrap();
+            assert_eq!(selected.bookmark_guid(), Some("a1B2c3D4e5F6".to_string()));
+
+            // renamed and moved in Firefox: same row, title and url follow the bookmark
+            let mut renamed = MangaModel::new_from_required_elements("ゲート", "https://example.org/gate/", 1).unwrap();
+            renamed.set_bookmark_guid(Some("a1B2c3D4e5F6".to_string()));
+            let summary = super::bulk_upsert_manga(&mut conn, &[renamed]).unwrap();
+            assert_eq!((summary.inserted, summary.updated), (0, 1));
+            let selected = super::select_manga_by_id(db_full_paths, stored.id()).unwrap();
+            assert_eq!(selected.title(), "ゲート");
+            assert_eq!(selected.url(), "https://example.org/gate/");
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 1);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_title_collision() {
+            let db_full_paths = "samples/test_title_collision.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let mut gate = MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1).unwrap();
+            gate.set_notes(Some("JSDF".to_string()));
+            let mut conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            let stored = super::bulk_insert_manga(&mut conn, &[gate]).unwrap().mangas[0].clone();
+
+            // keep-both: a row of its own, as UNIQUE(title, url) allows
+            let renamed = MangaModel::new_from_required_elements("Gate: Jieitai", "http://example.com/gate", 1).unwrap();
+            let summary = super::bulk_insert_manga(&mut conn, std::slice::from_ref(&renamed)).unwrap();
+            assert_eq!((summary.inserted, summary.renamed), (1, 0));
+            super::delete_manga(db_full_paths, summary.mangas[0].id()).unwrap();
+
+            // update-title: the row takes the new title, the rest of it is left as it was
+            let mut asked = Vec::new();
+            let summary = super::bulk_insert_manga_with(&mut conn, &[renamed], &mut |id, title, _| {
+                asked.push((id, title.to_string()));
+                true
+            })
+            .unwrap();
+            assert_eq!(asked, vec![(stored.id(), "Gate".to_string())]);
+            assert_eq!((summary.inserted, summary.renamed), (0, 1));
+            let selected = super::select_manga_by_id(db_full_paths, stored.id()).unwrap();
+            assert_eq!(selected.title(), "Gate: Jieitai");
+            assert_eq!(selected.url(), "https://example.com/gate/");
+            assert_eq!(selected.notes(), Some("JSDF".to_string()));
+            assert_eq!(selected.alt_titles(), ["Gate"]);
+
+            // and once more through upsert_manga_with(), the older titles are kept
+            let upserted = super::upsert_manga_with(
+                db_full_paths,
+                &MangaModel::new_from_required_elements("ゲート", "https://example.com/gate/", 1).unwrap(),
+                &mut |_, _, _| true,
+            )
+            .unwrap();
+            assert_eq!(upserted.id(), stored.id());
+            let selected = super::select_manga_by_id(db_full_paths, stored.id()).unwrap();
+            assert_eq!(selected.title(), "ゲート");
+            assert_eq!(selected.alt_titles(), ["Gate", "Gate: Jieitai"]);
+            assert_eq!(super::select_all_manga(db_full_paths).unwrap().len(), 1);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_without_tags() {
+            let db_full_paths = "samples/test_select_without_tags.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let tagged = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            super::add_tags_to_manga(db_full_paths, tagged.id(), &["isekai".to_string()]).unwrap();
+
+            let without_tags = super::select_all_manga_with_options(
+                db_full_paths,
+                super::SelectOptions { with_tags: false },
+            )
+            .unwrap();
+            assert!(without_tags[0].tags().is_empty());
+            let with_tags = super::select_all_manga(db_full_paths).unwrap();
+            assert!(!with_tags[0].tags().is_empty());
+            assert_eq!(
+                super::tags_for(db_full_paths, tagged.id()).unwrap(),
+                with_tags[0].tags().iter().map(|tag| tag.to_string()).collect::<Vec<String>>()
+            );
+            let tags_by_manga_id = super::tags_for_many(db_full_paths, &[tagged.id(), 9999]).unwrap();
+            assert_eq!(tags_by_manga_id.len(), 1);
+            assert!(super::tags_for(db_full_paths, 9999).unwrap().is_empty());
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_select_malformed_row() {
+            let db_full_paths = "samples/test_select_malformed_row.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+            let valid = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            // i.e. edited by hand, with_values() does not take it
+            rusqlite::Connection::open(db_full_paths)
+                .unwrap()
+                .execute(
+                    "INSERT INTO manga (title, url) VALUES ('Broken', 'not a url')",
+                    [],
+                )
+                .unwrap();
+
+            let mangas = super::select_all_manga(db_full_paths).unwrap();
+            assert_eq!(mangas.len(), 1);
+            assert_eq!(mangas[0].id(), valid.id());
+            assert!(matches!(
+                super::select_manga_by_id(db_full_paths, 9999),
+                Err(super::Error::NotFound(9999))
+            ));
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_update_read_chapters() {
+            let db_full_paths = "samples/test_update_read_chapters.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+
+            let mut manga = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            manga.set_chapter(Some("12".to_string()));
+            manga.set_url_with_chapter(Some("https://example.com/manga/gate-chapter-12/".to_string()));
+            manga.set_last_update_millis(Some(1_694_008_642_000));
+            assert_eq!(super::update_read_chapters(db_full_paths, &[manga.clone()]).unwrap(), 1);
+
+            let manga_selected = super::select_manga_by_id(db_full_paths, manga.id()).unwrap();
+            assert_eq!(manga_selected.chapter(), Some("12".to_string()));
+            assert_eq!(manga_selected.url_with_chapter(), manga.url_with_chapter());
+            let sessions = crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions::select_reading_sessions(db_full_paths, 0, i64::MAX).unwrap();
+            assert_eq!(sessions.len(), 1);
+            assert_eq!(sessions[0].read_at_millis, 1_694_008_642_000);
+
+            // the chapter and its reading session are written together, or not at all
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute("DROP TABLE reading_sessions", []).unwrap();
+            manga.set_chapter(Some("13".to_string()));
+            assert!(super::update_read_chapters(db_full_paths, &[manga.clone()]).is_err());
+            assert_eq!(
+                super::select_manga_by_id(db_full_paths, manga.id()).unwrap().chapter(),
+                Some("12".to_string())
+            );
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_update_url() {
+            let db_full_paths = "samples/test_update_url.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+
+            let manga = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            let new_url = "https://example.org/manga/gate/";
+            assert!(super::update_url(db_full_paths, manga.id(), new_url, "permanent redirect").unwrap());
+            // same url again is a no-op, and is not recorded twice
+            assert!(!super::update_url(db_full_paths, manga.id(), new_url, "permanent redirect").unwrap());
+            assert!(!super::update_url(db_full_paths, 9999, new_url, "permanent redirect").unwrap());
+
+            assert_eq!(super::select_manga_by_id(db_full_paths, manga.id()).unwrap().url(), new_url);
+            let history = crate::model_sqlite3_url_history::model_sqlite3_url_history::select_url_history_of_manga(db_full_paths, manga.id()).unwrap();
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].old_url, manga.url());
+            assert_eq!(history[0].reason, "permanent redirect");
+            // history must not block deleting the manga
+            assert!(super::delete_manga(db_full_paths, manga.id()).unwrap());
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_add_tags_to_manga() {
+            let db_full_paths = "samples/test_add_tags_to_manga.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+
+            let manga = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            let tags = vec!["genre:action".to_string(), "genre:isekai".to_string()];
+            assert_eq!(super::add_tags_to_manga(db_full_paths, manga.id(), &tags).unwrap(), 2);
+            // already attached, so nothing new
+            assert_eq!(super::add_tags_to_manga(db_full_paths, manga.id(), &tags).unwrap(), 0);
+
+            let manga_selected = super::select_manga_by_id(db_full_paths, manga.id()).unwrap();
+            for tag in tags.iter() {
+                assert!(manga_selected.tags().iter().any(|t| t.trim() == tag));
+            }
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_replace_tags_of_manga() {
+            let db_full_paths = "samples/test_replace_tags_of_manga.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+
+            let manga = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            let tags = vec!["seinen".to_string(), "isekai".to_string()];
+            assert_eq!(super::add_tags_to_manga(db_full_paths, manga.id(), &tags).unwrap(), 2);
+            let replacing_tags = vec!["shoujo".to_string()];
+            assert_eq!(super::replace_tags_of_manga(db_full_paths, manga.id(), &replacing_tags).unwrap(), 1);
+
+            let manga_selected = super::select_manga_by_id(db_full_paths, manga.id()).unwrap();
+            assert!(manga_selected.tags().iter().any(|t| t.trim() == "shoujo"));
+            assert!(!manga_selected.tags().iter().any(|t| t.trim() == "isekai"));
+            // and deleting a tagged manga is not stopped by the tags mapping
+            assert!(super::delete_manga(db_full_paths, manga.id()).unwrap());
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_manage_tags() {
+            let db_full_paths = "samples/test_manage_tags.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            super::create_tables(db_full_paths).unwrap();
+
+            let gate = super::insert_manga(db_full_paths, &make_sample_row()).unwrap();
+            let akira = super::insert_manga(
+                db_full_paths,
+                &MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap(),
+            )
+            .unwrap();
+            super::add_tags_to_manga(
+                db_full_paths,
+                gate.id(),
+                &["isekai".to_string(), "Seinen".to_string()],
+            )
+            .unwrap();
+            super::add_tags_to_manga(
+                db_full_paths,
+                akira.id(),
+                &["seinen".to_string(), " cyberpunk".to_string()],
+            )
+            .unwrap();
+            assert_eq!(
+                super::tag_counts(db_full_paths).unwrap(),
+                vec![
+                    ("Seinen".to_string(), 1),
+                    ("cyberpunk".to_string(), 1),
+                    ("isekai".to_string(), 1),
+                    ("seinen".to_string(), 1)
+                ]
+            );
+
+            assert_eq!(
+                super::merge_tags(db_full_paths, "Seinen", "seinen").unwrap(),
+                Some(1)
+            );
+            assert_eq!(
+                super::merge_tags(db_full_paths, "Seinen", "seinen").unwrap(),
+                None
+            );
+            assert!(matches!(
+                super::rename_tag(db_full_paths, "isekai", "seinen"),
+                Err(super::Error::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                super::merge_tags(db_full_paths, "isekai", " "),
+                Err(super::Error::InvalidArgument(_))
+            ));
+            assert_eq!(
+                super::rename_tag(db_full_paths, "cyberpunk", "SF").unwrap(),
+                Some(1)
+            );
+            assert_eq!(
+                super::tag_counts(db_full_paths).unwrap(),
+                vec![
+                    ("seinen".to_string(), 2),
+                    ("SF".to_string(), 1),
+                    ("isekai".to_string(), 1)
+                ]
+            );
+            let akira_tags: Vec<String> = super::tags_for(db_full_paths, akira.id())
+                .unwrap()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .collect();
+            assert!(
+                akira_tags.contains(&"SF".to_string())
+                    && akira_tags.contains(&"seinen".to_string())
+            );
+
+            assert!(super::delete_orphaned_tags(db_full_paths)
+                .unwrap()
+                .is_empty());
+            super::replace_tags_of_manga(db_full_paths, gate.id(), &["seinen".to_string()])
+                .unwrap();
+            assert_eq!(
+                super::delete_orphaned_tags(db_full_paths).unwrap(),
+                vec!["isekai".to_string()]
+            );
+            assert_eq!(super::tag_counts(db_full_paths).unwrap().len(), 2);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_duplicate_groups() {
+            let gate = MangaModel::new_from_required_elements(
+                K_MANGA_TITLE,
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            let gate_www = MangaModel::new_from_required_elements(
+                "Gate",
+                "http://www.example.com/manga/gate",
+                2,
+            )
+            .unwrap();
+            let akira = MangaModel::new_from_required_elements(
+                "Akira",
+                "https://example.com/manga/akira/",
+                3,
+            )
+            .unwrap();
+            let akira_elsewhere = MangaModel::new_from_required_elements(
+                "AKIRA!",
+                "https://other.com/akira/",
+                4,
+            )
+            .unwrap();
+            let groups = super::duplicate_groups(&[
+                akira_elsewhere,
+                gate,
+                akira,
+                gate_www,
+            ]);
+            let ids: Vec<Vec<i64>> = groups
+                .iter()
+                .map(|group| group.iter().map(|manga| manga.id()).collect())
+                .collect();
+            assert_eq!(ids, vec![vec![1, 2], vec![3, 4]]);
+            assert!(super::duplicate_groups(&[make_sample_row()]).is_empty());
+        }
+
+        #[test]
+        fn test_increment_chapter() {
+            let mut manga = make_sample_row(); // chapter 10 of ".../gate-chapter-10/"
+            assert_eq!(
+                super::increment_chapter(&manga),
+                (
+                    "11".to_string(),
+                    "https://example.com/manga/gate-chapter-11/".to_string()
+                )
+            );
+            manga.set_chapter(Some("10.1".to_string()));
+            assert_eq!(
+                super::increment_chapter(&manga),
+                (
+                    "10-2".to_string(),
+                    "https://example.com/manga/gate-chapter-10-2/".to_string()
+                )
+            );
+            // no url_with_chapter to go by, so it's derived from the base url
+            manga.set_url_with_chapter(None);
+            manga.set_chapter(None);
+            assert_eq!(
+                super::increment_chapter(&manga),
+                (
+                    "1".to_string(),
+                    "https://example.com/manga/gate-chapter-1/".to_string()
+                )
+            );
+        }
+
+        #[test]
+        fn test_schema_ddl() {
+            let ddl = super::schema_ddl().unwrap();
+            assert!(ddl.iter().any(|sql| sql.starts_with("CREATE TABLE manga (")));
+            assert!(ddl.iter().any(|sql| sql.starts_with("CREATE VIRTUAL TABLE manga_fts")));
+            assert!(!ddl.iter().any(|sql| sql.contains("manga_fts_data")));
+
+            // replayed on an empty database, it is the same schema all over again
+            let db_full_paths = "samples/test_schema_ddl.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let conn = super::model_sqlite3_connection::open(db_full_paths).unwrap();
+            conn.execute_batch(&format!("{};", ddl.join(";\n"))).unwrap();
+            drop(conn);
+            assert_eq!(super::select_schema_ddl(db_full_paths).unwrap(), ddl);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
         // test for insert_manga
         #[test]
         fn test_insert_manga() {
@@ -912,8 +3426,12 @@ pub mod model_sqlite3_manga {
         fn test_select_manga() {
             let db_file_path = "samples/test_select_manga.sqlite3"; // Replace with your actual database file path
                                                                   //let sql_where_clause = format!("WHERE m.url LIKE '{}' AND m.title LIKE '{}'", "%", "%フロンティア%");
-            let sql_where_clause = format!("WHERE m.url LIKE '{}' AND m.title LIKE '{}'", "%", "%");
-            match super::select_manga(db_file_path, sql_where_clause.as_str()) {
+            let filter = super::MangaFilter {
+                possible_title: Some(super::TextMatch::Like("%".to_string())),
+                possible_url: Some(super::TextMatch::Like("%".to_string())),
+                ..super::MangaFilter::default()
+            };
+            match super::select_manga(db_file_path, &filter, super::SelectOptions::default()) {
                 Ok(manga_data) => {
                     for manga in manga_data {
                         println!("{}|{}|{}", manga.id(), manga.title(), manga.url());
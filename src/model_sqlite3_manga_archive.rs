@@ -0,0 +1,223 @@
+// SQLite3 archive of finished or dropped series (`manga archive <id>`): the row is moved out of the manga table,
+// together with its history, so that listings and the update checker only ever see the active working set;
+// archived series are still found by `list`/`search` given --include-archived
+// TABLE manga_archive:
+// 0: manga_id (PRIMARY KEY) - id the row had in the manga table
+// 1: title (NOT NULL)
+// 2: url (NOT NULL)
+// 3: reason - i.e. "finished", "dropped"
+// 4: archived_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// 5: manga (NOT NULL) - the whole row as JSON (MangaModel, tags included)
+// 6: history (NOT NULL) - JSON of its reading sessions, url history and metadata (see ArchivedHistory)
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_manga_archive {
+    use rusqlite::{params, Result};
+    use serde::{Deserialize, Serialize};
+    use std::path::Path;
+
+    use crate::error::error::Error;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::{self, MangaMetadata};
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions::{
+        self, ReadingSession,
+    };
+    use crate::model_sqlite3_url_history::model_sqlite3_url_history::{self, UrlHistory};
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ArchivedHistory {
+        pub reading_sessions: Vec<ReadingSession>,
+        pub url_history: Vec<UrlHistory>,
+        pub metadata: Option<MangaMetadata>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct ArchivedManga {
+        pub manga: MangaModel,
+        pub reason: Option<String>,
+        pub archived_at_millis: i64,
+        pub history: ArchivedHistory,
+    }
+
+    // after the manga table is created
+    pub fn create_manga_archive_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_manga_archive_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS manga_archive (
+                manga_id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                url TEXT NOT NULL,
+                reason TEXT,
+                archived_at_millis INTEGER NOT NULL,
+                manga TEXT NOT NULL,
+                history TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn to_json_error(e: serde_json::Error) -> rusqlite::Error {
+        rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+    }
+
+    // moves the row and its history into the archive, in a single transaction; Err(NotFound) if id is not found
+    pub fn archive_manga(
+        db_full_paths: &str,
+        id: i64,
+        possible_reason: Option<&str>,
+    ) -> std::result::Result<ArchivedManga, Error> {
+        let archived = ArchivedManga {
+            manga: model_sqlite3_manga::select_manga_by_id(db_full_paths, id)?,
+            reason: possible_reason.map(|reason| reason.to_string()),
+            archived_at_millis: chrono::Utc::now().timestamp_millis(),
+            history: ArchivedHistory {
+                reading_sessions: model_sqlite3_reading_sessions::select_reading_sessions_of_manga(
+                    db_full_paths,
+                    id,
+                )?,
+                url_history: model_sqlite3_url_history::select_url_history_of_manga(
+                    db_full_paths,
+                    id,
+                )?,
+                metadata: model_sqlite3_manga_metadata::select_manga_metadata(db_full_paths, id)?,
+            },
+        };
+
+        let mut conn = model_sqlite3_connection::open(Path::new(db_full_paths))?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO manga_archive (manga_id, title, url, reason, archived_at_millis, manga, history)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                id,
+                archived.manga.title(),
+                archived.manga.url(),
+                archived.reason,
+                archived.archived_at_millis,
+                serde_json::to_string(&archived.manga).map_err(to_json_error)?,
+                serde_json::to_string(&archived.history).map_err(to_json_error)?,
+            ],
+        )?;
+        model_sqlite3_manga::delete_manga_with_connection(&tx, id)?;
+        tx.commit()?;
+        Ok(archived)
+    }
+
+    // archived rows whose whole row (title, romanized title, url, notes, tags...) contains every word of query,
+    // case-insensitively for ASCII; most recently archived first, all of them when query has no word
+    pub fn select_archived_manga(db_full_paths: &str, query: &str) -> Result<Vec<ArchivedManga>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let patterns: Vec<String> = query
+            .split_whitespace()
+            .map(|word| {
+                format!(
+                    "%{}%",
+                    word.replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                )
+            })
+            .collect();
+        let conditions = match patterns.is_empty() {
+            true => String::new(),
+            false => format!(
+                "WHERE {}",
+                (1..=patterns.len())
+                    .map(|index| format!("manga LIKE ?{} ESCAPE '\\'", index))
+                    .collect::<Vec<String>>()
+                    .join(" AND ")
+            ),
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT reason, archived_at_millis, manga, history FROM manga_archive {} ORDER BY archived_at_millis DESC, manga_id",
+            conditions
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(patterns.iter()), |row| {
+            let manga_json: String = row.get(2)?;
+            let history_json: String = row.get(3)?;
+            let from_json_error = |e: serde_json::Error| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            };
+            Ok(ArchivedManga {
+                reason: row.get(0)?,
+                archived_at_millis: row.get(1)?,
+                manga: serde_json::from_str(&manga_json).map_err(from_json_error)?,
+                history: serde_json::from_str(&history_json).map_err(from_json_error)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_archive_manga() {
+            let db_full_paths = "samples/test_archive_manga.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let mut gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            gate.set_notes(Some("JSDF".to_string()));
+            gate.set_tags(vec!["isekai".to_string()]);
+            let gate = model_sqlite3_manga::insert_manga(db_full_paths, &gate).unwrap();
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            model_sqlite3_manga::insert_manga(db_full_paths, &akira).unwrap();
+            model_sqlite3_reading_sessions::insert_reading_session(
+                db_full_paths,
+                gate.id(),
+                "12",
+                1_694_008_642_000,
+            )
+            .unwrap();
+
+            let archived = archive_manga(db_full_paths, gate.id(), Some("finished")).unwrap();
+            assert_eq!(archived.history.reading_sessions.len(), 1);
+            assert!(model_sqlite3_manga::select_manga_by_id(db_full_paths, gate.id()).is_err());
+            assert!(
+                model_sqlite3_reading_sessions::select_reading_sessions_of_manga(
+                    db_full_paths,
+                    gate.id()
+                )
+                .unwrap()
+                .is_empty()
+            );
+            assert_eq!(
+                model_sqlite3_manga::select_all_manga(db_full_paths)
+                    .unwrap()
+                    .len(),
+                1
+            );
+            assert!(archive_manga(db_full_paths, 99, None).is_err());
+
+            // found by what the row held, notes and tags included
+            let found = select_archived_manga(db_full_paths, "jsdf ISEKAI").unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].manga.title(), "Gate");
+            assert_eq!(found[0].manga.tags(), vec!["isekai"]);
+            assert_eq!(found[0].reason, Some("finished".to_string()));
+            assert_eq!(found[0].history, archived.history);
+            assert!(select_archived_manga(db_full_paths, "akira")
+                .unwrap()
+                .is_empty());
+            assert_eq!(select_archived_manga(db_full_paths, "").unwrap().len(), 1);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
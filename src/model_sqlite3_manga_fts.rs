@@ -0,0 +1,154 @@
+// SQLite3 FTS5 full-text index over the searchable text of the manga table, used by ranked_search
+// VIRTUAL TABLE manga_fts (external content, the text itself stays in the manga table):
+// rowid - same as manga.id
+// 0: title
+// 1: title_romanized
+// 2: notes
+// Triggers on manga keep it in sync with every INSERT/UPDATE/DELETE done anywhere, so nothing else has to
+// know about it; a database created before this table existed gets indexed once, when the table is created.
+pub mod model_sqlite3_manga_fts {
+    use rusqlite::{params, OptionalExtension, Result};
+    use std::path::Path;
+
+    use crate::error::error::Error;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga::{self, MangaFilter, SelectOptions};
+
+    pub fn create_manga_fts_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_manga_fts_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        let already_exists = conn
+            .query_row(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'manga_fts'",
+                [],
+                |row| row.get::<usize, String>(0),
+            )
+            .optional()?
+            .is_some();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS manga_fts USING fts5(
+                title, title_romanized, notes,
+                content = 'manga', content_rowid = 'id',
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER IF NOT EXISTS manga_fts_after_insert AFTER INSERT ON manga BEGIN
+                INSERT INTO manga_fts (rowid, title, title_romanized, notes)
+                    VALUES (new.id, new.title, new.title_romanized, new.notes);
+            END;
+            CREATE TRIGGER IF NOT EXISTS manga_fts_after_delete AFTER DELETE ON manga BEGIN
+                INSERT INTO manga_fts (manga_fts, rowid, title, title_romanized, notes)
+                    VALUES ('delete', old.id, old.title, old.title_romanized, old.notes);
+            END;
+            CREATE TRIGGER IF NOT EXISTS manga_fts_after_update
+                AFTER UPDATE OF title, title_romanized, notes ON manga BEGIN
+                INSERT INTO manga_fts (manga_fts, rowid, title, title_romanized, notes)
+                    VALUES ('delete', old.id, old.title, old.title_romanized, old.notes);
+                INSERT INTO manga_fts (rowid, title, title_romanized, notes)
+                    VALUES (new.id, new.title, new.title_romanized, new.notes);
+            END;",
+        )?;
+        if !already_exists {
+            conn.execute("INSERT INTO manga_fts (manga_fts) VALUES ('rebuild')", [])?;
+        }
+
+        Ok(())
+    }
+
+    // "dr sto" => "\"dr\"* OR \"sto\"*", every word as a prefix; None when there is no word to search for
+    pub fn fts_query(query: &str) -> Option<String> {
+        let terms: Vec<String> = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        match terms.is_empty() {
+            true => None,
+            false => Some(terms.join(" OR ")),
+        }
+    }
+
+    // ids of the manga matching query, best (bm25) first
+    pub fn select_fts_matches(db_full_paths: &str, query: &str) -> Result<Vec<i64>> {
+        let fts_query = match fts_query(query) {
+            Some(fts_query) => fts_query,
+            None => return Ok(Vec::new()),
+        };
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT rowid FROM manga_fts WHERE manga_fts MATCH ?1 ORDER BY bm25(manga_fts)",
+        )?;
+        let ids = stmt
+            .query_map(params![fts_query], |row| row.get::<usize, i64>(0))?
+            .collect();
+        ids
+    }
+
+    // the manga matching query, best (bm25) first; 0 rows is NOT an error
+    pub fn search_manga_fts(
+        db_full_paths: &str,
+        query: &str,
+    ) -> std::result::Result<Vec<MangaModel>, Error> {
+        let ids = select_fts_matches(db_full_paths, query)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let filter = MangaFilter {
+            possible_ids: Some(ids.clone()),
+            ..MangaFilter::default()
+        };
+        let mut mangas =
+            model_sqlite3_manga::select_manga(db_full_paths, &filter, SelectOptions::default())?;
+        mangas.sort_by_key(|manga| ids.iter().position(|&id| id == manga.id()));
+        Ok(mangas)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_select_fts_matches() {
+            assert_eq!(
+                fts_query("Dr. Sto"),
+                Some("\"Dr\"* OR \"Sto\"*".to_string())
+            );
+            assert_eq!(fts_query(" .. "), None);
+
+            let db_full_paths = "samples/test_manga_fts.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            crate::model_sqlite3_manga::model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute_batch(
+                "INSERT INTO manga (id, title, title_romanized, url) VALUES
+                    (1, 'ドクターストーン', 'Dr. Stone', 'https://example.com/dr-stone/'),
+                    (2, 'Stone Ocean', NULL, 'https://example.com/stone-ocean/');",
+            )
+            .unwrap();
+            assert_eq!(
+                select_fts_matches(db_full_paths, "dr stone").unwrap(),
+                vec![1, 2]
+            );
+            let titles: Vec<String> = search_manga_fts(db_full_paths, "dr stone")
+                .unwrap()
+                .iter()
+                .map(|manga| manga.title().to_string())
+                .collect();
+            assert_eq!(titles, vec!["ドクターストーン", "Stone Ocean"]);
+            assert!(search_manga_fts(db_full_paths, "..").unwrap().is_empty());
+            // kept in sync by the triggers
+            conn.execute("UPDATE manga SET title = 'Steel Ball Run' WHERE id = 2", [])
+                .unwrap();
+            conn.execute("DELETE FROM manga WHERE id = 1", []).unwrap();
+            assert!(select_fts_matches(db_full_paths, "stone")
+                .unwrap()
+                .is_empty());
+            assert_eq!(select_fts_matches(db_full_paths, "ste").unwrap(), vec![2]);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
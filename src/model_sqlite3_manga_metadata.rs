@@ -0,0 +1,173 @@
+// SQLite3 metadata found by the enrichment integrations (MyAnimeList, ...), kept apart from the manga
+// table since none of it comes from (nor goes back to) bookmarks or CSV; one row per manga, the latest
+// integration to find the series overwrites what an earlier one found
+// TABLE manga_metadata:
+// 0: manga_id (PRIMARY KEY) - foreign key to manga table
+// 1: source (NOT NULL) - i.e. "myanimelist"
+// 2: source_url - page of the series on that source
+// 3: title_romaji
+// 4: title_native - i.e. in Japanese
+// 5: authors - "; " separated (commas are sanitized away everywhere else)
+// 6: publication_status - as the source spells it, i.e. "Publishing", "Finished"
+// 7: synopsis
+// 8: cover_url
+// 9: updated_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_manga_metadata {
+    use rusqlite::{params, Connection, OptionalExtension, Result};
+    use serde::{Deserialize, Serialize};
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct MangaMetadata {
+        pub manga_id: i64,
+        pub source: String,
+        pub source_url: Option<String>,
+        pub title_romaji: Option<String>,
+        pub title_native: Option<String>,
+        pub authors: Vec<String>,
+        pub publication_status: Option<String>,
+        pub synopsis: Option<String>,
+        pub cover_url: Option<String>,
+        pub updated_at_millis: i64,
+    }
+
+    pub fn create_manga_metadata_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_manga_metadata_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS manga_metadata (
+                manga_id INTEGER PRIMARY KEY,
+                source TEXT NOT NULL,
+                source_url TEXT,
+                title_romaji TEXT,
+                title_native TEXT,
+                authors TEXT,
+                publication_status TEXT,
+                synopsis TEXT,
+                cover_url TEXT,
+                updated_at_millis INTEGER NOT NULL,
+                FOREIGN KEY(manga_id) REFERENCES manga(id)
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn upsert_manga_metadata(db_full_paths: &str, metadata: &MangaMetadata) -> Result<()> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO manga_metadata (manga_id, source, source_url, title_romaji, title_native, authors, publication_status, synopsis, cover_url, updated_at_millis)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                metadata.manga_id,
+                metadata.source,
+                metadata.source_url,
+                metadata.title_romaji,
+                metadata.title_native,
+                metadata.authors.join("; "),
+                metadata.publication_status,
+                metadata.synopsis,
+                metadata.cover_url,
+                metadata.updated_at_millis
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Ok(None) when no integration has found this manga (yet)
+    pub fn select_manga_metadata(
+        db_full_paths: &str,
+        manga_id: i64,
+    ) -> Result<Option<MangaMetadata>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.query_row(
+            "SELECT manga_id, source, source_url, title_romaji, title_native, authors, publication_status, synopsis, cover_url, updated_at_millis
+                FROM manga_metadata WHERE manga_id = ?1",
+            params![manga_id],
+            |row| {
+                Ok(MangaMetadata {
+                    manga_id: row.get(0)?,
+                    source: row.get(1)?,
+                    source_url: row.get(2)?,
+                    title_romaji: row.get(3)?,
+                    title_native: row.get(4)?,
+                    authors: row
+                        .get::<usize, Option<String>>(5)?
+                        .unwrap_or_default()
+                        .split("; ")
+                        .filter(|author| !author.is_empty())
+                        .map(|author| author.to_string())
+                        .collect(),
+                    publication_status: row.get(6)?,
+                    synopsis: row.get(7)?,
+                    cover_url: row.get(8)?,
+                    updated_at_millis: row.get(9)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    // called by delete_manga() prior to deleting the manga row, otherwise the foreign key constraint fails
+    pub fn delete_manga_metadata(conn: &Connection, manga_id: i64) -> Result<usize> {
+        conn.execute(
+            "DELETE FROM manga_metadata WHERE manga_id = ?1",
+            params![manga_id],
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_upsert_and_select_manga_metadata() {
+            let db_full_paths = "samples/test_manga_metadata.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            // manga_metadata references manga table, so create all of them
+            crate::model_sqlite3_manga::model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            Connection::open(db_full_paths)
+                .unwrap()
+                .execute(
+                    "INSERT INTO manga (id, title, url) VALUES (1, 'a', 'https://example.com/a/')",
+                    [],
+                )
+                .unwrap();
+
+            assert_eq!(select_manga_metadata(db_full_paths, 1).unwrap(), None);
+            let mut metadata = MangaMetadata {
+                manga_id: 1,
+                source: "myanimelist".to_string(),
+                authors: vec!["Yanai, Takumi".to_string(), "Sao, Satoru".to_string()],
+                publication_status: Some("Publishing".to_string()),
+                updated_at_millis: 1_000,
+                ..MangaMetadata::default()
+            };
+            upsert_manga_metadata(db_full_paths, &metadata).unwrap();
+            assert_eq!(
+                select_manga_metadata(db_full_paths, 1).unwrap(),
+                Some(metadata.clone())
+            );
+            // second upsert replaces the first
+            metadata.publication_status = Some("Finished".to_string());
+            upsert_manga_metadata(db_full_paths, &metadata).unwrap();
+            assert_eq!(
+                select_manga_metadata(db_full_paths, 1)
+                    .unwrap()
+                    .unwrap()
+                    .publication_status,
+                Some("Finished".to_string())
+            );
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
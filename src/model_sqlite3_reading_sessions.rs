@@ -0,0 +1,178 @@
+// SQLite3 reading session log: one row each time a chapter is marked read (i.e. `manga mark-read`),
+// so that the manga table holds only the CURRENT state while this table holds the history
+// TABLE reading_sessions:
+// 0: id (PRIMARY KEY)
+// 1: manga_id - foreign key to manga table
+// 2: chapter (NOT NULL) - i.e. "12.1"
+// 3: read_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_reading_sessions {
+    use rusqlite::{params, Connection, Result};
+    use serde::{Deserialize, Serialize};
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct ReadingSession {
+        pub id: i64,
+        pub manga_id: i64,
+        pub chapter: String,
+        pub read_at_millis: i64,
+    }
+
+    pub fn create_reading_sessions_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_reading_sessions_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS reading_sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                manga_id INTEGER NOT NULL,
+                chapter TEXT NOT NULL,
+                read_at_millis INTEGER NOT NULL,
+                FOREIGN KEY(manga_id) REFERENCES manga(id)
+            )",
+            [],
+        )?;
+        // most queries are "what was read between X and Y"
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS reading_sessions_read_at ON reading_sessions(read_at_millis)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // returns the new session id
+    pub fn insert_reading_session(
+        db_full_paths: &str,
+        manga_id: i64,
+        chapter: &str,
+        read_at_millis: i64,
+    ) -> Result<i64> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        insert_reading_session_with_connection(&conn, manga_id, chapter, read_at_millis)
+    }
+
+    // same as insert_reading_session(), but for callers that are already inside a transaction
+    pub fn insert_reading_session_with_connection(
+        conn: &Connection,
+        manga_id: i64,
+        chapter: &str,
+        read_at_millis: i64,
+    ) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO reading_sessions (manga_id, chapter, read_at_millis) VALUES (?1, ?2, ?3)",
+            params![manga_id, chapter, read_at_millis],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // called by delete_manga() prior to deleting the manga row, otherwise the foreign key constraint fails
+    pub fn delete_reading_sessions_of_manga(conn: &Connection, manga_id: i64) -> Result<usize> {
+        conn.execute(
+            "DELETE FROM reading_sessions WHERE manga_id = ?1",
+            params![manga_id],
+        )
+    }
+
+    // sessions where since_millis <= read_at_millis < until_millis, oldest first; 0 rows is NOT an error
+    pub fn select_reading_sessions(
+        db_full_paths: &str,
+        since_millis: i64,
+        until_millis: i64,
+    ) -> Result<Vec<ReadingSession>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, manga_id, chapter, read_at_millis FROM reading_sessions
+                WHERE read_at_millis >= ?1 AND read_at_millis < ?2
+                ORDER BY read_at_millis ASC, id ASC",
+        )?;
+        let sessions = stmt
+            .query_map(params![since_millis, until_millis], |row| {
+                Ok(ReadingSession {
+                    id: row.get(0)?,
+                    manga_id: row.get(1)?,
+                    chapter: row.get(2)?,
+                    read_at_millis: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<ReadingSession>>>()?;
+        Ok(sessions)
+    }
+
+    // oldest first; 0 rows is NOT an error
+    pub fn select_reading_sessions_of_manga(
+        db_full_paths: &str,
+        manga_id: i64,
+    ) -> Result<Vec<ReadingSession>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, manga_id, chapter, read_at_millis FROM reading_sessions
+                WHERE manga_id = ?1
+                ORDER BY read_at_millis ASC, id ASC",
+        )?;
+        let sessions = stmt
+            .query_map(params![manga_id], |row| {
+                Ok(ReadingSession {
+                    id: row.get(0)?,
+                    manga_id: row.get(1)?,
+                    chapter: row.get(2)?,
+                    read_at_millis: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<ReadingSession>>>()?;
+        Ok(sessions)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_insert_and_select_reading_sessions() {
+            let db_full_paths = "samples/test_reading_sessions.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            // reading_sessions references manga table, so create all of them
+            crate::model_sqlite3_manga::model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            Connection::open(db_full_paths)
+                .unwrap()
+                .execute(
+                    "INSERT INTO manga (id, title, url) VALUES (1, 'a', 'https://example.com/a/'), (2, 'b', 'https://example.com/b/')",
+                    [],
+                )
+                .unwrap();
+
+            insert_reading_session(db_full_paths, 1, "10", 1_000).unwrap();
+            insert_reading_session(db_full_paths, 1, "11", 3_000).unwrap();
+            insert_reading_session(db_full_paths, 2, "5.1", 2_000).unwrap();
+
+            let sessions = select_reading_sessions(db_full_paths, 0, i64::MAX).unwrap();
+            assert_eq!(
+                sessions
+                    .iter()
+                    .map(|s| s.chapter.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["10", "5.1", "11"]
+            );
+            // until is exclusive
+            let sessions = select_reading_sessions(db_full_paths, 1_000, 3_000).unwrap();
+            assert_eq!(sessions.len(), 2);
+            let sessions = select_reading_sessions_of_manga(db_full_paths, 1).unwrap();
+            assert_eq!(
+                sessions
+                    .iter()
+                    .map(|s| s.chapter.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["10", "11"]
+            );
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
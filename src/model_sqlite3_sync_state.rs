@@ -0,0 +1,151 @@
+// SQLite3 bookkeeping for `manga sync push/pull`, so that a push never overwrites someone else's changes and a
+// pull never discards local ones without asking (see remote_sync)
+// TABLE sync_state:
+// 0: remote (PRIMARY KEY) - where the database is synced to, i.e. "https://dav.example.com/manga/漫画.sqlite3"
+// 1: revision (NOT NULL) - revision of the remote copy at the last push or pull
+// 2: synced_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// TABLE sync_changes:
+// 0: id (PRIMARY KEY) - always 1, single row
+// 1: changes (NOT NULL) - rows written to the collection tables since the last push or pull, counted by triggers
+//    so that every writer (CLI, servers, daemon) is accounted for without knowing about sync
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_sync_state {
+    use rusqlite::{params, OptionalExtension, Result};
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    // tables whose changes are worth syncing; the caches (FTS, link checks, url history) follow from these
+    const K_TRACKED_TABLES: [&str; 4] = [
+        "manga",
+        "manga_to_tags_map",
+        "reading_sessions",
+        "manga_metadata",
+    ];
+
+    // after the tables in K_TRACKED_TABLES are created
+    pub fn create_sync_tables(db_full_paths: &str) -> Result<()> {
+        println!(">> create_sync_tables('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                remote TEXT PRIMARY KEY,
+                revision INTEGER NOT NULL,
+                synced_at_millis INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS sync_changes (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                changes INTEGER NOT NULL
+            );
+            INSERT OR IGNORE INTO sync_changes (id, changes) VALUES (1, 0);",
+        )?;
+        // the manga row an UPDATE wrote is written once more by the trigger keeping its updated_at (see
+        // model_sqlite3_manga), which is not a change of its own; databases of older versions have the
+        // trigger without the WHEN
+        let has_outdated_manga_trigger = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = 'sync_changes_after_update_manga'",
+                [],
+                |row| row.get::<usize, String>(0),
+            )
+            .optional()?
+            .is_some_and(|sql| !sql.contains("updated_at"));
+        if has_outdated_manga_trigger {
+            conn.execute("DROP TRIGGER sync_changes_after_update_manga", [])?;
+        }
+        for table in K_TRACKED_TABLES.iter() {
+            for event in ["INSERT", "UPDATE", "DELETE"] {
+                let condition = match (*table, event) {
+                    ("manga", "UPDATE") => "WHEN NEW.updated_at IS OLD.updated_at ",
+                    _ => "",
+                };
+                conn.execute_batch(&format!(
+                    "CREATE TRIGGER IF NOT EXISTS sync_changes_after_{1}_{0} AFTER {1} ON {0} {2}BEGIN
+                        UPDATE sync_changes SET changes = changes + 1 WHERE id = 1;
+                    END;",
+                    table,
+                    event.to_lowercase(),
+                    condition
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn local_changes(db_full_paths: &str) -> Result<u64> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        Ok(conn
+            .query_row("SELECT changes FROM sync_changes WHERE id = 1", [], |row| {
+                row.get::<usize, i64>(0)
+            })
+            .optional()?
+            .unwrap_or(0) as u64)
+    }
+
+    // None when never synced with that remote
+    pub fn select_synced_revision(db_full_paths: &str, remote: &str) -> Result<Option<u64>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        conn.query_row(
+            "SELECT revision FROM sync_state WHERE remote = ?1",
+            params![remote],
+            |row| row.get::<usize, i64>(0).map(|revision| revision as u64),
+        )
+        .optional()
+    }
+
+    // after a push or pull: the local database is now the same as revision of the remote
+    pub fn mark_synced(db_full_paths: &str, remote: &str, revision: u64) -> Result<()> {
+        let path = Path::new(db_full_paths);
+        let mut conn = model_sqlite3_connection::open(path)?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_state (remote, revision, synced_at_millis) VALUES (?1, ?2, ?3)",
+            params![
+                remote,
+                revision as i64,
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )?;
+        tx.execute("UPDATE sync_changes SET changes = 0 WHERE id = 1", [])?;
+        tx.commit()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_sync_state() {
+            let db_full_paths = "samples/test_sync_state.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            crate::model_sqlite3_manga::model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let remote = "https://dav.example.com/manga/test.sqlite3";
+            assert_eq!(select_synced_revision(db_full_paths, remote).unwrap(), None);
+            assert_eq!(local_changes(db_full_paths).unwrap(), 0);
+
+            let conn = rusqlite::Connection::open(db_full_paths).unwrap();
+            conn.execute(
+                "INSERT INTO manga (id, title, url) VALUES (1, 'Gate', 'https://example.com/gate/')",
+                [],
+            )
+            .unwrap();
+            conn.execute("UPDATE manga SET chapter = '12' WHERE id = 1", [])
+                .unwrap();
+            assert_eq!(local_changes(db_full_paths).unwrap(), 2);
+
+            mark_synced(db_full_paths, remote, 3).unwrap();
+            assert_eq!(
+                select_synced_revision(db_full_paths, remote).unwrap(),
+                Some(3)
+            );
+            assert_eq!(local_changes(db_full_paths).unwrap(), 0);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// SQLite3 url history: every time manga.url is replaced (the site answered 301/308 with a new location,
+// or the whole site was migrated to another aggregator), the previous url is kept here
+// TABLE url_history:
+// 0: id (PRIMARY KEY)
+// 1: manga_id - foreign key to manga table
+// 2: old_url (NOT NULL)
+// 3: new_url (NOT NULL)
+// 4: reason (NOT NULL) - i.e. "permanent redirect", "migrate-site"
+// 5: changed_at_millis (NOT NULL) - epoch time i64 in milliseconds
+// append new columns to the end of the list, never between
+pub mod model_sqlite3_url_history {
+    use rusqlite::{params, Connection, Result};
+    use serde::{Deserialize, Serialize};
+    use std::path::Path;
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct UrlHistory {
+        pub id: i64,
+        pub manga_id: i64,
+        pub old_url: String,
+        pub new_url: String,
+        pub reason: String,
+        pub changed_at_millis: i64,
+    }
+
+    pub fn create_url_history_table(db_full_paths: &str) -> Result<()> {
+        println!(">> create_url_history_table('{}')", db_full_paths);
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS url_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                manga_id INTEGER NOT NULL,
+                old_url TEXT NOT NULL,
+                new_url TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                changed_at_millis INTEGER NOT NULL,
+                FOREIGN KEY(manga_id) REFERENCES manga(id)
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // called within the same transaction as the UPDATE of manga.url (see model_sqlite3_manga::update_url())
+    pub fn insert_url_history_with_connection(
+        conn: &Connection,
+        manga_id: i64,
+        old_url: &str,
+        new_url: &str,
+        reason: &str,
+        changed_at_millis: i64,
+    ) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO url_history (manga_id, old_url, new_url, reason, changed_at_millis) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![manga_id, old_url, new_url, reason, changed_at_millis],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // called by delete_manga() prior to deleting the manga row, otherwise the foreign key constraint fails
+    pub fn delete_url_history_of_manga(conn: &Connection, manga_id: i64) -> Result<usize> {
+        conn.execute(
+            "DELETE FROM url_history WHERE manga_id = ?1",
+            params![manga_id],
+        )
+    }
+
+    // oldest first; 0 rows is NOT an error
+    pub fn select_url_history_of_manga(
+        db_full_paths: &str,
+        manga_id: i64,
+    ) -> Result<Vec<UrlHistory>> {
+        let path = Path::new(db_full_paths);
+        let conn = model_sqlite3_connection::open(path)?;
+        let mut stmt = conn.prepare(
+            "SELECT id, manga_id, old_url, new_url, reason, changed_at_millis FROM url_history
+                WHERE manga_id = ?1
+                ORDER BY changed_at_millis ASC, id ASC",
+        )?;
+        let history = stmt
+            .query_map(params![manga_id], |row| {
+                Ok(UrlHistory {
+                    id: row.get(0)?,
+                    manga_id: row.get(1)?,
+                    old_url: row.get(2)?,
+                    new_url: row.get(3)?,
+                    reason: row.get(4)?,
+                    changed_at_millis: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<UrlHistory>>>()?;
+        Ok(history)
+    }
+}
@@ -0,0 +1,129 @@
+// Excel workbook (.xlsx) of the collection (`manga export-xlsx --out manga.xlsx`), formatted the way one would
+// by hand: bold header row frozen in place with filters, sized columns, urls as clickable links and the last
+// update as a real date cell (sortable, filterable by month), so nothing has to survive a CSV round trip.
+// Write-only, the CSV remains the format to edit and import back.
+pub mod model_xlsx_manga {
+    use chrono::TimeZone;
+    use rust_xlsxwriter::{
+        ExcelDateTime, Format, FormatAlign, FormatBorder, Url, Workbook, XlsxError,
+    };
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    const K_SHEET_NAME: &str = "漫画";
+    const K_DATETIME_FORMAT: &str = "yyyy-mm-dd hh:mm";
+    const K_MAX_URL_CHARS: usize = 2079; // longer hyperlinks are rejected by Excel, written as text instead
+
+    // (header, width in characters)
+    const K_COLUMNS: [(&str, f64); 10] = [
+        ("ID", 6.0),
+        ("Title", 40.0),
+        ("Romanized", 40.0),
+        ("Chapter", 9.0),
+        ("Latest", 9.0),
+        ("Unread", 8.0),
+        ("Last update", 17.0),
+        ("Url", 60.0),
+        ("Tags", 30.0),
+        ("Notes", 50.0),
+    ];
+
+    // Excel has no time zones, so the local time (same as manga.last_update) is what is shown
+    fn excel_local_datetime(millis: i64) -> Option<ExcelDateTime> {
+        let local_seconds = chrono::Local
+            .timestamp_millis_opt(millis)
+            .single()?
+            .naive_local()
+            .and_utc()
+            .timestamp();
+        ExcelDateTime::from_timestamp(local_seconds).ok()
+    }
+
+    // mangas are written in the order given, one row each below the header
+    pub fn to_xlsx(mangas: &[MangaModel]) -> Result<Vec<u8>, XlsxError> {
+        let header_format = Format::new()
+            .set_bold()
+            .set_background_color("#DDEBF7")
+            .set_border_bottom(FormatBorder::Thin);
+        let datetime_format = Format::new()
+            .set_num_format(K_DATETIME_FORMAT)
+            .set_align(FormatAlign::Left);
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(K_SHEET_NAME)?;
+        for (col, (header, width)) in K_COLUMNS.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, *header, &header_format)?;
+            worksheet.set_column_width(col as u16, *width)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        worksheet.autofilter(0, 0, mangas.len() as u32, K_COLUMNS.len() as u16 - 1)?;
+
+        for (index, manga) in mangas.iter().enumerate() {
+            let row = index as u32 + 1;
+            worksheet.write_number(row, 0, manga.id() as f64)?; // exact below 2^53
+            worksheet.write_string(row, 1, manga.title())?;
+            worksheet.write_string(row, 2, manga.title_romanized().unwrap_or_default())?;
+            worksheet.write_string(row, 3, manga.chapter().unwrap_or_default())?;
+            worksheet.write_string(row, 4, manga.latest_available_chapter().unwrap_or_default())?;
+            if let Some(unread) = manga.unread_count() {
+                worksheet.write_number(row, 5, unread)?;
+            }
+            match manga.last_update_millis().and_then(excel_local_datetime) {
+                Some(datetime) => {
+                    worksheet.write_datetime_with_format(row, 6, &datetime, &datetime_format)?;
+                }
+                None => {
+                    worksheet.write_string(row, 6, manga.last_update().unwrap_or_default())?;
+                }
+            }
+            let link = manga
+                .url_with_chapter()
+                .filter(|url_with_chapter| !url_with_chapter.is_empty())
+                .unwrap_or_else(|| manga.url().to_string());
+            match link.chars().count() <= K_MAX_URL_CHARS {
+                true => worksheet.write_url_with_text(row, 7, Url::new(link.as_str()), &link)?,
+                false => worksheet.write_string(row, 7, &link)?,
+            };
+            let tags: Vec<&str> = manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            worksheet.write_string(row, 8, tags.join(", "))?;
+            worksheet.write_string(row, 9, manga.notes().unwrap_or_default())?;
+        }
+        workbook.save_to_buffer()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_to_xlsx() {
+            let mut gate = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://example.com/manga/gate/",
+                1,
+            )
+            .unwrap();
+            gate.set_url_with_chapter(Some(
+                "https://example.com/manga/gate/chapter-12".to_string(),
+            ));
+            gate.set_last_update_millis(Some(1694008642000));
+            gate.set_tags(vec![" isekai".to_string()]);
+            let akira = MangaModel::new_from_required_elements(
+                "Akira",
+                &format!("https://example.com/{}/", "a".repeat(K_MAX_URL_CHARS)),
+                2,
+            )
+            .unwrap();
+
+            let xlsx = to_xlsx(&[gate, akira]).unwrap();
+            // an xlsx file is a zip archive
+            assert!(xlsx.starts_with(b"PK\x03\x04"));
+            assert!(excel_local_datetime(1694008642000).is_some());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+// YAML form of the collection, for keeping the list in git and editing it by hand (`manga export-yaml`, then
+// `manga import-yaml manga.yaml`): a single document, one entry per manga, empty fields left out:
+//      - id: 1
+//        title: ゲート
+//        title_romanized: Gate
+//        url: https://example.com/manga/gate/
+//        url_with_chapter: https://example.com/manga/gate/chapter-12
+//        chapter: '12'
+//        last_update: 2023-09-06 22:57:22
+//        tags:
+//        - isekai
+//        - seinen
+// Importing it back replaces the manga of the same id with the entry (a field left out is cleared), entries
+// without an id (or with an id no longer in the database) are added, or update the manga with the same title
+// and url.  last_update is informational, the database stamps it whenever a manga is saved, and entries whose
+// only difference is last_update are not saved at all, so re-importing an untouched file changes nothing.
+pub mod model_yaml_manga {
+    use serde::{Deserialize, Serialize};
+
+    use crate::model_manga::model_manga::{MangaCreateRequest, MangaModel, MangaRequest};
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct YamlManga {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub id: Option<i64>,
+        pub title: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub title_romanized: Option<String>,
+        pub url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub url_with_chapter: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub chapter: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub last_update: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub notes: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub tags: Vec<String>,
+    }
+
+    impl YamlManga {
+        // same manga as far as saving it goes: last_update aside, and tags in any order
+        pub fn same_content(&self, other: &YamlManga) -> bool {
+            let comparable = |entry: &YamlManga| {
+                let mut tags = entry.tags.clone();
+                tags.sort();
+                YamlManga {
+                    last_update: None,
+                    tags,
+                    ..entry.clone()
+                }
+            };
+            comparable(self) == comparable(other)
+        }
+
+        fn tags_csv(&self) -> Option<String> {
+            Some(self.tags.join(", "))
+        }
+
+        pub fn to_request(&self) -> MangaRequest {
+            MangaRequest {
+                title: self.title.clone(),
+                title_romanized: self.title_romanized.clone(),
+                url: self.url.clone(),
+                url_with_chapter: self.url_with_chapter.clone(),
+                chapter: self.chapter.clone(),
+                last_update: self.last_update.clone(),
+                notes: self.notes.clone(),
+                tags: self.tags_csv(),
+            }
+        }
+
+        pub fn to_create_request(&self) -> MangaCreateRequest {
+            MangaCreateRequest {
+                title: self.title.clone(),
+                title_romanized: self.title_romanized.clone(),
+                url: self.url.clone(),
+                url_with_chapter: self.url_with_chapter.clone(),
+                chapter: self.chapter.clone(),
+                last_update: self.last_update.clone(),
+                notes: self.notes.clone(),
+                tags: self.tags_csv(),
+            }
+        }
+    }
+
+    pub fn to_yaml_manga(manga: &MangaModel) -> YamlManga {
+        let non_empty =
+            |possible_text: Option<String>| possible_text.filter(|text| !text.trim().is_empty());
+        YamlManga {
+            id: Some(manga.id()),
+            title: manga.title().to_string(),
+            title_romanized: non_empty(manga.title_romanized()),
+            url: manga.url().to_string(),
+            url_with_chapter: non_empty(manga.url_with_chapter())
+                .filter(|url_with_chapter| url_with_chapter != manga.url()),
+            chapter: non_empty(manga.chapter()),
+            last_update: non_empty(manga.last_update()),
+            notes: non_empty(manga.notes()),
+            tags: manga
+                .tags()
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        }
+    }
+
+    // sorted by id, so that re-exports of an unchanged database are identical (diffs stay small)
+    pub fn to_yaml(mangas: &[MangaModel]) -> Result<String, serde_yaml::Error> {
+        let mut entries: Vec<YamlManga> = mangas.iter().map(to_yaml_manga).collect();
+        entries.sort_by_key(|entry| entry.id);
+        serde_yaml::to_string(&entries)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Vec<YamlManga>, serde_yaml::Error> {
+        match yaml.trim().is_empty() {
+            true => Ok(Vec::new()),
+            false => serde_yaml::from_str::<Vec<YamlManga>>(yaml),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_to_yaml() {
+            let mut gate = MangaModel::new_from_required_elements(
+                "ゲート",
+                "https://example.com/manga/gate/chapter-12",
+                1,
+            )
+            .unwrap();
+            gate.set_url("https://example.com/manga/gate/".to_string());
+            gate.set_chapter(Some("12".to_string()));
+            gate.set_tags(vec![" isekai".to_string(), " seinen".to_string()]);
+            let akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+
+            let yaml = to_yaml(&[akira, gate]).unwrap();
+            assert!(yaml.starts_with("- id: 1\n  title: ゲート\n"));
+            assert!(yaml.contains("  chapter: '12'\n  tags:\n  - isekai\n  - seinen\n- id: 2\n"));
+            let entries = from_yaml(&yaml).unwrap();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].tags, vec!["isekai", "seinen"]);
+            assert_eq!(entries[1].notes, None);
+
+            // hand written: no id, and only what is needed
+            let entries =
+                from_yaml("- title: Dr. Stone\n  url: https://example.com/dr-stone/\n").unwrap();
+            assert_eq!(entries[0].id, None);
+            assert_eq!(entries[0].to_request().tags, Some(String::new()));
+            assert!(from_yaml("- title: no url\n").is_err());
+            assert!(from_yaml("").unwrap().is_empty());
+            let mut touched = entries[0].clone();
+            touched.last_update = Some("2023-09-06 22:57:22".to_string());
+            touched.tags = vec!["b".to_string(), "a".to_string()];
+            let mut reordered = entries[0].clone();
+            reordered.tags = vec!["a".to_string(), "b".to_string()];
+            assert!(touched.same_content(&reordered));
+            assert!(!touched.same_content(&entries[0]));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+// MyAnimeList lookups through the Jikan REST API (https://docs.api.jikan.moe/), which needs no API key
+// but is rate limited (3 requests per second, 60 per minute), hence the pause before each request.
+// Search results are turned into enrichment::EnrichmentMatch so they are stored the same way as any
+// other metadata integration.
+pub mod myanimelist {
+    use serde::Deserialize;
+    use std::{thread, time::Duration};
+
+    use crate::enrichment::enrichment::EnrichmentMatch;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::MangaMetadata;
+    use crate::update_checker::update_checker;
+
+    pub const K_SOURCE: &str = "myanimelist";
+    const K_JIKAN_MANGA_SEARCH_URL: &str = "https://api.jikan.moe/v4/manga";
+    const K_SEARCH_LIMIT: usize = 5;
+    const K_RATE_LIMIT_PAUSE_MILLIS: u64 = 1_100;
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanName {
+        #[serde(default)]
+        name: String,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanTitle {
+        #[serde(default)]
+        title: String,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanImage {
+        image_url: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanImages {
+        #[serde(default)]
+        jpg: JikanImage,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanManga {
+        url: Option<String>,
+        #[serde(default)]
+        title: String, // romaji, i.e. "Gate: Jieitai Kanochi nite, Kaku Tatakaeri"
+        title_english: Option<String>,
+        title_japanese: Option<String>,
+        #[serde(default)]
+        titles: Vec<JikanTitle>, // every synonym, including the ones above
+        #[serde(default)]
+        authors: Vec<JikanName>,
+        status: Option<String>,
+        synopsis: Option<String>,
+        #[serde(default)]
+        images: JikanImages,
+        #[serde(default)]
+        genres: Vec<JikanName>,
+        #[serde(default)]
+        demographics: Vec<JikanName>,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct JikanSearchResponse {
+        #[serde(default)]
+        data: Vec<JikanManga>,
+    }
+
+    fn to_enrichment_match(manga_id: i64, found: JikanManga) -> EnrichmentMatch {
+        let mut titles = vec![found.title.clone()];
+        titles.extend(found.title_english.iter().cloned());
+        titles.extend(found.title_japanese.iter().cloned());
+        titles.extend(found.titles.iter().map(|t| t.title.clone()));
+        titles.retain(|title| !title.is_empty());
+        titles.dedup();
+
+        EnrichmentMatch {
+            possible_my_anime_list: found.url.clone(),
+            metadata: MangaMetadata {
+                manga_id,
+                source: K_SOURCE.to_string(),
+                source_url: found.url,
+                title_romaji: Some(found.title).filter(|title| !title.is_empty()),
+                title_native: found.title_japanese,
+                authors: found
+                    .authors
+                    .into_iter()
+                    .map(|author| author.name)
+                    .collect(),
+                publication_status: found.status,
+                synopsis: found.synopsis,
+                cover_url: found.images.jpg.image_url,
+                updated_at_millis: chrono::Utc::now().timestamp_millis(),
+            },
+            titles,
+            genres: found.genres.into_iter().map(|genre| genre.name).collect(),
+            demographics: found
+                .demographics
+                .into_iter()
+                .map(|demographic| demographic.name)
+                .collect(),
+        }
+    }
+
+    pub fn parse_search_response(
+        manga_id: i64,
+        json: &str,
+    ) -> Result<Vec<EnrichmentMatch>, Box<dyn std::error::Error>> {
+        let response: JikanSearchResponse = serde_json::from_str(json)?;
+        Ok(response
+            .data
+            .into_iter()
+            .map(|found| to_enrichment_match(manga_id, found))
+            .collect())
+    }
+
+    // best matches first (as ranked by Jikan), at most K_SEARCH_LIMIT of them
+    pub fn search(
+        manga_id: i64,
+        title: &str,
+    ) -> Result<Vec<EnrichmentMatch>, Box<dyn std::error::Error>> {
+        thread::sleep(Duration::from_millis(K_RATE_LIMIT_PAUSE_MILLIS));
+        let query: String = url::form_urlencoded::byte_serialize(title.as_bytes()).collect();
+        let json = update_checker::fetch_text(&format!(
+            "{}?q={}&limit={}",
+            K_JIKAN_MANGA_SEARCH_URL, query, K_SEARCH_LIMIT
+        ))?;
+        parse_search_response(manga_id, &json)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_search_response() {
+            let json = r#"{"pagination":{},"data":[{
+                "mal_id": 27399,
+                "url": "https://myanimelist.net/manga/27399/Gate",
+                "images": {"jpg": {"image_url": "https://cdn.myanimelist.net/images/manga/1/1.jpg"}},
+                "title": "Gate: Jieitai Kanochi nite, Kaku Tatakaeri",
+                "title_english": "Gate",
+                "title_japanese": "ゲート 自衛隊 彼の地にて、斯く戦えり",
+                "titles": [{"type": "Synonym", "title": "GATE"}],
+                "status": "Publishing",
+                "synopsis": null,
+                "authors": [{"mal_id": 1, "name": "Yanai, Takumi"}],
+                "genres": [{"name": "Action"}, {"name": "Fantasy"}],
+                "demographics": [{"name": "Seinen"}]
+            }]}"#;
+            let matches = parse_search_response(7, json).unwrap();
+            assert_eq!(matches.len(), 1);
+            let found = &matches[0];
+            assert_eq!(found.metadata.manga_id, 7);
+            assert_eq!(found.metadata.source, K_SOURCE);
+            assert_eq!(
+                found.metadata.source_url,
+                Some("https://myanimelist.net/manga/27399/Gate".to_string())
+            );
+            assert_eq!(found.metadata.authors, vec!["Yanai, Takumi"]);
+            assert_eq!(
+                found.metadata.publication_status,
+                Some("Publishing".to_string())
+            );
+            assert!(found.titles.contains(&"GATE".to_string()));
+            assert_eq!(found.genres, vec!["Action", "Fantasy"]);
+            assert_eq!(found.demographics, vec!["Seinen"]);
+
+            assert!(parse_search_response(7, r#"{"data": []}"#)
+                .unwrap()
+                .is_empty());
+        }
+    }
+}
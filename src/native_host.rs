@@ -0,0 +1,220 @@
+// Native messaging host (`manga native-host`) for a companion WebExtension, so that "bookmark this chapter" lands
+// in the database as it happens rather than at the next export/import of the bookmarks.  The browser starts the
+// host itself, as its manifest says (see `manga native-host --manifest`), and each message either way is a JSON
+// object preceded by its length in bytes (u32, native byte order):
+//      {"type":"bookmark","title":"Gate","url":"https://example.com/gate-chapter-12/"}
+//          => {"ok":true,"id":7,"title":"Gate","chapter":"12","created":false}
+//      {"type":"ping"}
+//          => {"ok":true,"version":"0.1.0"}
+// A bookmark goes through the same normalization as imports ([normalize] and [[title_rules]] of the config); a
+// manga of the same url is marked read at the chapter of the url (a reading session, same as `mark-read`), any
+// other is added.  Failures are {"ok":false,"error":".."}, the host keeps running until the browser closes stdin.
+// The path of the manifest cannot carry arguments, so it usually is a script such as
+//      #!/bin/sh
+//      exec manga native-host -d /home/me/漫画.sqlite3
+// and Firefox looks for the manifest (named after K_HOST_NAME) in
+//      ~/.mozilla/native-messaging-hosts/manga_bookmarks.json                          (Linux)
+//      ~/Library/Application Support/Mozilla/NativeMessagingHosts/manga_bookmarks.json  (macOS)
+// The host is unix only: the responses share stdout with the database layer's println!, which only unix lets us
+// move to stderr (see json_rpc::protocol_writer()), so on Windows it refuses to start and there is no registry
+// entry (HKEY_CURRENT_USER\Software\Mozilla\NativeMessagingHosts) to make.
+pub mod native_host {
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::io::{self, Read, Write};
+
+    use crate::error::error::Error;
+    use crate::model_manga;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::normalize_pipeline::normalize_pipeline::Pipeline;
+    use crate::service::service::MangaService;
+
+    pub const K_HOST_NAME: &str = "manga_bookmarks";
+    const K_MAX_MESSAGE_BYTES: usize = 1024 * 1024; // the most a browser accepts from a host
+
+    #[derive(Debug, Deserialize)]
+    #[serde(tag = "type", rename_all = "kebab-case")]
+    enum HostMessage {
+        Ping,
+        Bookmark { title: String, url: String },
+    }
+
+    // None at end of input, i.e. the browser closed the pipe
+    pub fn read_message(reader: &mut dyn Read) -> io::Result<Option<Vec<u8>>> {
+        let mut length_bytes = [0u8; 4];
+        match reader.read_exact(&mut length_bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let length = u32::from_ne_bytes(length_bytes) as usize;
+        if length > K_MAX_MESSAGE_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("native-host: message of {} bytes is too long", length),
+            ));
+        }
+        let mut message = vec![0u8; length];
+        reader.read_exact(&mut message)?;
+        Ok(Some(message))
+    }
+
+    pub fn write_message(writer: &mut dyn Write, message: &Value) -> io::Result<()> {
+        let bytes = message.to_string().into_bytes();
+        if bytes.len() > K_MAX_MESSAGE_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("native-host: response of {} bytes is too long", bytes.len()),
+            ));
+        }
+        writer.write_all(&(bytes.len() as u32).to_ne_bytes())?;
+        writer.write_all(&bytes)?;
+        writer.flush()
+    }
+
+    // the row of the same url, preferably under the same title
+    fn known_manga(db_full_paths: &str, manga: &MangaModel) -> Result<Option<MangaModel>, Error> {
+        match model_sqlite3_manga::select_manga_from_url_and_title(db_full_paths, manga.url(), "%")
+        {
+            Ok(rows) => {
+                let index = rows
+                    .iter()
+                    .position(|row| row.title() == manga.title())
+                    .unwrap_or(0);
+                Ok(rows.into_iter().nth(index))
+            }
+            Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn bookmark(
+        service: &MangaService,
+        pipeline: &Pipeline,
+        title: &str,
+        url: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let possible_manga = match pipeline.skips(url) {
+            true => Err(format!("'{}' is not the url of a manga", url).into()),
+            false => {
+                pipeline.to_manga(title, url, model_manga::CASTAGNOLI.checksum(url.as_bytes()))
+            }
+        };
+        // each bookmark is answered on its own, nothing is left for a report
+        pipeline.take_skipped();
+        pipeline.take_rejections();
+        let manga = possible_manga?;
+
+        let (saved, created) = match known_manga(service.db_full_paths(), &manga)? {
+            Some(known) => (known, false),
+            None => (
+                model_sqlite3_manga::insert_manga(service.db_full_paths(), &manga)?,
+                true,
+            ),
+        };
+        let saved = match manga.chapter() {
+            Some(chapter) => service.mark_read(saved.id(), Some(&chapter))?,
+            None => saved,
+        };
+        Ok(json!({
+            "ok": true,
+            "id": saved.id(),
+            "title": saved.title(),
+            "chapter": saved.chapter(),
+            "created": created,
+        }))
+    }
+
+    pub fn handle_message(service: &MangaService, pipeline: &Pipeline, message: &[u8]) -> Value {
+        let result = match serde_json::from_slice::<HostMessage>(message) {
+            Ok(HostMessage::Ping) => {
+                Ok(json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") }))
+            }
+            Ok(HostMessage::Bookmark { title, url }) => bookmark(service, pipeline, &title, &url),
+            Err(e) => Err(format!("invalid message: {}", e).into()),
+        };
+        result.unwrap_or_else(|e| json!({ "ok": false, "error": e.to_string() }))
+    }
+
+    // blocks until stdin is closed; writer is where the responses go (see json_rpc::protocol_writer())
+    pub fn run(
+        writer: &mut dyn Write,
+        db_full_paths: &str,
+        pipeline: &Pipeline,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(db_full_paths)?;
+        let mut reader = io::stdin().lock();
+        while let Some(message) = read_message(&mut reader)? {
+            write_message(writer, &handle_message(&service, pipeline, &message))?;
+        }
+        Ok(())
+    }
+
+    // host_path is the script (or binary) the browser starts, extension_id the only extension allowed to
+    pub fn manifest(host_path: &str, extension_id: &str) -> Value {
+        json!({
+            "name": K_HOST_NAME,
+            "description": "Manga bookmarks straight into the SQLite database",
+            "path": host_path,
+            "type": "stdio",
+            "allowed_extensions": [extension_id],
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_messages() {
+            let mut buffer = Vec::new();
+            write_message(&mut buffer, &json!({ "type": "ping" })).unwrap();
+            assert_eq!(&buffer[..4], &15u32.to_ne_bytes());
+            let mut reader = io::Cursor::new(buffer);
+            assert_eq!(
+                read_message(&mut reader).unwrap(),
+                Some(br#"{"type":"ping"}"#.to_vec())
+            );
+            assert_eq!(read_message(&mut reader).unwrap(), None);
+            let mut too_long = io::Cursor::new(u32::MAX.to_ne_bytes().to_vec());
+            assert!(read_message(&mut too_long).is_err());
+        }
+
+        #[test]
+        fn test_handle_message() {
+            let db_full_paths = "samples/test_native_host.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            let pipeline = Pipeline::default();
+            let bookmark = |url: &str| {
+                handle_message(
+                    &service,
+                    &pipeline,
+                    json!({ "type": "bookmark", "title": "Gate", "url": url })
+                        .to_string()
+                        .as_bytes(),
+                )
+            };
+
+            let added = bookmark("https://example.com/gate-chapter-12/");
+            assert_eq!(added["ok"], true);
+            assert_eq!(added["created"], true);
+            assert_eq!(added["chapter"], "12");
+            let read = bookmark("https://example.com/gate-chapter-13/");
+            assert_eq!(read["created"], false);
+            assert_eq!(read["id"], added["id"]);
+            assert_eq!(read["chapter"], "13");
+            assert_eq!(service.list().unwrap().len(), 1);
+
+            assert_eq!(bookmark("about:downloads")["ok"], false);
+            assert_eq!(
+                handle_message(&service, &pipeline, br#"{"type":"ping"}"#)["ok"],
+                true
+            );
+            assert_eq!(handle_message(&service, &pipeline, b"{")["ok"], false);
+
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+    }
+}
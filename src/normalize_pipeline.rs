@@ -0,0 +1,497 @@
+// The transformations a bookmark goes through on import, as an ordered list of named stages, so that a run can
+// disable or reorder them (`--stages`, `--skip-stages`, or [normalize] stages in the config) and `--explain`
+// what each of them changed.  The default order is the one imports always had:
+//      trim-quotes      title, url: surrounding whitespace and quotes
+//      sanitize         title, url: ',' => '、', quotes => '’' (see my_libs::sanitize_string), or the
+//                       substitutions of [normalize] sanitize in the config
+//      url-normalize    url: as parsed (i.e. lowercase host, default port dropped)
+//      chapter-extract  url: "https://example.com/gate-chapter-12-1/" => "https://example.com/gate/",
+//                       chapter "12.1", and url_with_chapter the url as it was
+//      title-clean      title: the [[title_rules]] of the domain (see title_repair::strip_boilerplate)
+//      romanize         title_romanized: romaji of a Japanese title, in the romanization style
+//                       (and with the romanization dictionary) of the config
+// Whatever the stages, the url must parse in the end.  Bookmarks of another scheme than the allowed ones (http and
+// https unless [normalize] allowed_schemes says otherwise), i.e. "about:downloads", are imported as any other,
+// warned about or rejected, as scheme_policy says; to_manga() keeps the rejected ones, and why, for the report.
+// Before any of that, bookmarks of the browser's own schemes (K_DEFAULT_SKIP_SCHEMES, or [normalize] skip_schemes)
+// are skipped while the bookmarks are traversed, only counted (see skips()).
+pub mod normalize_pipeline {
+    use serde::Serialize;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use url::Url;
+
+    use crate::config::config::{RomanizationOptions, SchemePolicy, TitleRuleConfig};
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::my_libs::{
+        default_sanitize_substitutions, make_none_if_empty, sanitize_string_with, trim_quotes,
+    };
+    use crate::title_repair::title_repair;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum Stage {
+        TrimQuotes,
+        Sanitize,
+        UrlNormalize,
+        ChapterExtract,
+        TitleClean,
+        Romanize,
+    }
+
+    pub const K_DEFAULT_STAGES: [Stage; 6] = [
+        Stage::TrimQuotes,
+        Stage::Sanitize,
+        Stage::UrlNormalize,
+        Stage::ChapterExtract,
+        Stage::TitleClean,
+        Stage::Romanize,
+    ];
+
+    impl Stage {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Stage::TrimQuotes => "trim-quotes",
+                Stage::Sanitize => "sanitize",
+                Stage::UrlNormalize => "url-normalize",
+                Stage::ChapterExtract => "chapter-extract",
+                Stage::TitleClean => "title-clean",
+                Stage::Romanize => "romanize",
+            }
+        }
+    }
+
+    impl std::str::FromStr for Stage {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            K_DEFAULT_STAGES
+                .iter()
+                .find(|stage| stage.name() == s.trim().to_lowercase())
+                .copied()
+                .ok_or_else(|| {
+                    format!(
+                        "unknown normalization stage '{}', expected one of {}",
+                        s,
+                        K_DEFAULT_STAGES
+                            .iter()
+                            .map(|stage| stage.name())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )
+                })
+        }
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+    pub struct NormalizedRow {
+        pub title: String,
+        pub title_romanized: Option<String>,
+        pub url: String,
+        pub url_with_chapter: Option<String>,
+        pub chapter: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct StageChange {
+        pub stage: Stage,
+        pub field: &'static str,
+        pub before: Option<String>,
+        pub after: Option<String>,
+    }
+
+    pub const K_DEFAULT_ALLOWED_SCHEMES: [&str; 2] = ["http", "https"];
+    pub const K_DEFAULT_SKIP_SCHEMES: [&str; 9] = [
+        "about",
+        "chrome",
+        "data",
+        "file",
+        "javascript",
+        "moz-extension",
+        "place",
+        "resource",
+        "view-source",
+    ];
+
+    // a bookmark to_manga() refused, as given
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Rejection {
+        pub title: String,
+        pub url: String,
+        pub reason: String,
+    }
+
+    impl std::fmt::Display for Rejection {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "'{}' {}: {}", self.title, self.url, self.reason)
+        }
+    }
+
+    impl std::error::Error for Rejection {}
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Pipeline {
+        pub stages: Vec<Stage>,
+        pub title_rules: Vec<TitleRuleConfig>,
+        pub sanitize_substitutions: BTreeMap<String, String>, // of the sanitize stage
+        pub scheme_policy: SchemePolicy,
+        pub allowed_schemes: Vec<String>,
+        pub skip_schemes: Vec<String>,
+        pub romanization: RomanizationOptions, // of the romanize stage
+        pub explain: bool,                     // to_manga() prints what each stage changed
+        rejections: RefCell<Vec<Rejection>>,   // see take_rejections()
+        skipped: RefCell<BTreeMap<String, usize>>, // by scheme, see take_skipped()
+    }
+
+    impl Default for Pipeline {
+        fn default() -> Self {
+            Pipeline::new(None, &[], Vec::new())
+        }
+    }
+
+    // "trim-quotes, sanitize" => [TrimQuotes, Sanitize]; a stage named twice is an error, as an unknown one
+    pub fn parse_stages(names: &[String]) -> Result<Vec<Stage>, String> {
+        let mut stages = Vec::new();
+        for name in names
+            .iter()
+            .flat_map(|names| names.split(','))
+            .filter(|name| !name.trim().is_empty())
+        {
+            let stage = name.parse::<Stage>()?;
+            if stages.contains(&stage) {
+                return Err(format!("normalization stage '{}' is listed twice", name));
+            }
+            stages.push(stage);
+        }
+        Ok(stages)
+    }
+
+    fn parsed_url(url: &str) -> Result<Url, String> {
+        Url::parse(url).map_err(|e| format!("invalid url '{}': {}", url, e))
+    }
+
+    impl Pipeline {
+        // stages in the given order (the default ones when None), less the skipped ones
+        pub fn new(
+            possible_stages: Option<Vec<Stage>>,
+            skipped_stages: &[Stage],
+            title_rules: Vec<TitleRuleConfig>,
+        ) -> Pipeline {
+            Pipeline {
+                stages: possible_stages
+                    .unwrap_or_else(|| K_DEFAULT_STAGES.to_vec())
+                    .into_iter()
+                    .filter(|stage| !skipped_stages.contains(stage))
+                    .collect(),
+                title_rules,
+                sanitize_substitutions: default_sanitize_substitutions(),
+                scheme_policy: SchemePolicy::Allow,
+                allowed_schemes: K_DEFAULT_ALLOWED_SCHEMES
+                    .iter()
+                    .map(|scheme| scheme.to_string())
+                    .collect(),
+                skip_schemes: K_DEFAULT_SKIP_SCHEMES
+                    .iter()
+                    .map(|scheme| scheme.to_string())
+                    .collect(),
+                romanization: RomanizationOptions::default(),
+                explain: false,
+                rejections: RefCell::new(Vec::new()),
+                skipped: RefCell::new(BTreeMap::new()),
+            }
+        }
+
+        // true (and counted) when the scheme of url is one of skip_schemes; the scheme is what precedes the first
+        // ':', so that urls which Url::parse() would refuse are skipped all the same
+        pub fn skips(&self, url: &str) -> bool {
+            let url = trim_quotes(url);
+            let possible_scheme = url
+                .split_once(':')
+                .map(|(scheme, _)| scheme.to_lowercase())
+                .filter(|scheme| self.skip_schemes.contains(scheme));
+            match possible_scheme {
+                Some(scheme) => {
+                    *self.skipped.borrow_mut().entry(scheme).or_insert(0) += 1;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        // how many bookmarks skips() skipped so far, by scheme, which are forgotten
+        pub fn take_skipped(&self) -> BTreeMap<String, usize> {
+            std::mem::take(&mut *self.skipped.borrow_mut())
+        }
+
+        // why url is not allowed, whatever scheme_policy is; None for urls which do not parse, as run() tells
+        pub fn scheme_rejection(&self, url: &str) -> Option<String> {
+            let scheme = Url::parse(&trim_quotes(url)).ok()?.scheme().to_string();
+            match self
+                .allowed_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&scheme))
+            {
+                true => None,
+                false => Some(format!(
+                    "scheme '{}' is not one of {}",
+                    scheme,
+                    self.allowed_schemes.join(", ")
+                )),
+            }
+        }
+
+        // the bookmarks rejected by to_manga() so far, oldest first, which are forgotten
+        pub fn take_rejections(&self) -> Vec<Rejection> {
+            std::mem::take(&mut *self.rejections.borrow_mut())
+        }
+
+        fn apply(&self, stage: Stage, row: &mut NormalizedRow) -> Result<(), String> {
+            match stage {
+                Stage::TrimQuotes => {
+                    row.title = trim_quotes(&row.title);
+                    row.url = trim_quotes(&row.url);
+                }
+                Stage::Sanitize => {
+                    row.title = sanitize_string_with(&row.title, &self.sanitize_substitutions);
+                    row.url = sanitize_string_with(&row.url, &self.sanitize_substitutions);
+                }
+                Stage::UrlNormalize => row.url = parsed_url(&row.url)?.to_string(),
+                Stage::ChapterExtract => {
+                    let (url_as_is, possible_base_url, possible_chapter) =
+                        MangaModel::url_and_chapter(parsed_url(&row.url)?);
+                    row.url = possible_base_url.unwrap_or(url_as_is.clone());
+                    row.url_with_chapter = make_none_if_empty(Some(url_as_is));
+                    row.chapter = possible_chapter;
+                }
+                Stage::TitleClean => {
+                    row.title =
+                        title_repair::strip_boilerplate(&row.title, &row.url, &self.title_rules)
+                }
+                Stage::Romanize => {
+                    row.title_romanized =
+                        MangaModel::romanize_title_with(&row.title, &self.romanization)
+                }
+            }
+            Ok(())
+        }
+
+        // the row after every stage, and what each stage changed (in the order they ran)
+        pub fn run(
+            &self,
+            title: &str,
+            url: &str,
+        ) -> Result<(NormalizedRow, Vec<StageChange>), String> {
+            let mut row = NormalizedRow {
+                title: title.to_string(),
+                url: url.to_string(),
+                ..NormalizedRow::default()
+            };
+            let mut changes = Vec::new();
+            for stage in self.stages.iter() {
+                let before = row.clone();
+                self.apply(*stage, &mut row)?;
+                let fields = [
+                    ("title", Some(before.title), Some(row.title.clone())),
+                    (
+                        "title_romanized",
+                        before.title_romanized,
+                        row.title_romanized.clone(),
+                    ),
+                    ("url", Some(before.url), Some(row.url.clone())),
+                    (
+                        "url_with_chapter",
+                        before.url_with_chapter,
+                        row.url_with_chapter.clone(),
+                    ),
+                    ("chapter", before.chapter, row.chapter.clone()),
+                ];
+                for (field, before, after) in fields {
+                    if before != after {
+                        changes.push(StageChange {
+                            stage: *stage,
+                            field,
+                            before,
+                            after,
+                        });
+                    }
+                }
+            }
+            parsed_url(&row.url)?;
+            if row.title.trim().is_empty() {
+                return Err(format!("title of '{}' is empty", url));
+            }
+            Ok((row, changes))
+        }
+
+        pub fn to_manga(
+            &self,
+            title: &str,
+            url: &str,
+            id: impl Into<i64>,
+        ) -> Result<MangaModel, Box<dyn std::error::Error>> {
+            if let Some(reason) = self.scheme_rejection(url) {
+                match self.scheme_policy {
+                    SchemePolicy::Allow => {}
+                    SchemePolicy::Warn => eprintln!("warning: '{}' {}: {}", title, url, reason),
+                    SchemePolicy::Reject => {
+                        let rejection = Rejection {
+                            title: title.to_string(),
+                            url: url.to_string(),
+                            reason,
+                        };
+                        self.rejections.borrow_mut().push(rejection.clone());
+                        return Err(Box::new(rejection));
+                    }
+                }
+            }
+            let (row, changes) = self.run(title, url)?;
+            if self.explain {
+                print!("{}", explain(title, url, &changes));
+            }
+            Ok(MangaModel::with_values(
+                id,
+                row.title,
+                make_none_if_empty(row.title_romanized),
+                row.url.clone(),
+                make_none_if_empty(Some(row.url_with_chapter.unwrap_or(row.url))),
+                row.chapter,
+                None,
+                None, // last_update_millis
+                None,
+                Vec::new(),
+                None,
+            )?)
+        }
+    }
+
+    // "about (3), place (1)"
+    pub fn skip_report(skipped: &BTreeMap<String, usize>) -> String {
+        skipped
+            .iter()
+            .map(|(scheme, count)| format!("{} ({})", scheme, count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    //  '"Gate"' https://example.com/gate-chapter-5/
+    //      trim-quotes      title: '"Gate"' => 'Gate'
+    //      chapter-extract  chapter: - => '5'
+    pub fn explain(title: &str, url: &str, changes: &[StageChange]) -> String {
+        let value = |possible_value: &Option<String>| match possible_value {
+            Some(value) => format!("'{}'", value),
+            None => "-".to_string(),
+        };
+        let mut text = format!("'{}' {}\n", title, url);
+        if changes.is_empty() {
+            text.push_str("    (unchanged)\n");
+        }
+        for change in changes {
+            text.push_str(&format!(
+                "    {:<16} {}: {} => {}\n",
+                change.stage.name(),
+                change.field,
+                value(&change.before),
+                value(&change.after)
+            ));
+        }
+        text
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_default_pipeline_matches_new_from_required_elements() {
+            let cases = [
+                (
+                    "\"Gate, Jieitai\"",
+                    "https://example.com/gate-chapter-12-1/",
+                ),
+                ("ゲート", "https://Example.com/manga/gate/"),
+            ];
+            for (title, url) in cases {
+                let expected = MangaModel::new_from_required_elements(title, url, 1).unwrap();
+                let manga = Pipeline::default().to_manga(title, url, 1).unwrap();
+                assert_eq!(manga, expected);
+            }
+        }
+
+        #[test]
+        fn test_stages() {
+            assert_eq!(
+                parse_stages(&["sanitize, romanize".to_string()]).unwrap(),
+                vec![Stage::Sanitize, Stage::Romanize]
+            );
+            assert!(parse_stages(&["sanitize,sanitize".to_string()]).is_err());
+            assert!(parse_stages(&["shout".to_string()]).is_err());
+
+            let url = "https://rawkuma.com/gate-chapter-5/";
+            let pipeline = Pipeline::new(
+                None,
+                &[Stage::ChapterExtract, Stage::Sanitize],
+                vec![TitleRuleConfig {
+                    domain: "rawkuma.com".to_string(),
+                    strip: vec![r"\s*–\s*Rawkuma$".to_string()],
+                }],
+            );
+            let (row, changes) = pipeline.run(" Gate, Jieitai – Rawkuma ", url).unwrap();
+            assert_eq!(row.title, "Gate, Jieitai");
+            assert_eq!(row.url, url);
+            assert_eq!(row.chapter, None);
+            assert_eq!(
+                changes
+                    .iter()
+                    .map(|change| (change.stage.name(), change.field))
+                    .collect::<Vec<(&str, &str)>>(),
+                vec![("trim-quotes", "title"), ("title-clean", "title")]
+            );
+            assert!(explain(" Gate ", url, &changes).contains("title-clean      title:"));
+
+            // punctuation kept as is when there is nothing to substitute
+            let pipeline = Pipeline {
+                sanitize_substitutions: BTreeMap::new(),
+                ..Pipeline::default()
+            };
+            let (row, _) = pipeline.run(" Gate, it's ", url).unwrap();
+            assert_eq!(row.title, "Gate, it's");
+
+            // other schemes than http(s) only rejected when told to, and remembered until taken
+            assert!(Pipeline::default()
+                .to_manga("Downloads", "about:downloads", 1)
+                .is_ok());
+            let pipeline = Pipeline {
+                scheme_policy: SchemePolicy::Reject,
+                ..Pipeline::default()
+            };
+            assert!(pipeline.to_manga("Gate", url, 1).is_ok());
+            let e = pipeline
+                .to_manga("Downloads", "about:downloads", 2)
+                .unwrap_err();
+            assert!(e.is::<Rejection>());
+            assert_eq!(
+                pipeline.take_rejections(),
+                vec![Rejection {
+                    title: "Downloads".to_string(),
+                    url: "about:downloads".to_string(),
+                    reason: "scheme 'about' is not one of http, https".to_string(),
+                }]
+            );
+            assert!(pipeline.take_rejections().is_empty());
+
+            // the browser's own urls are skipped and counted, even those which do not parse
+            let pipeline = Pipeline::default();
+            assert!(pipeline.skips("about:downloads"));
+            assert!(pipeline.skips(" JavaScript:alert('x') "));
+            assert!(pipeline.skips("about:"));
+            assert!(!pipeline.skips(url));
+            let skipped = pipeline.take_skipped();
+            assert_eq!(skip_report(&skipped), "about (2), javascript (1)");
+            assert!(pipeline.take_skipped().is_empty());
+
+            // the url has to be valid whatever the stages
+            assert!(Pipeline::new(Some(Vec::new()), &[], Vec::new())
+                .run("Gate", " not a url")
+                .is_err());
+        }
+    }
+}
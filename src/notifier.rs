@@ -0,0 +1,118 @@
+// Posts a message to each configured Discord/Slack webhook whenever the update checker finds unread
+// chapters, so that a shared channel can follow along.  Both services accept a small JSON payload
+// via HTTP POST; they only differ in the name of the text field and the link markup.
+pub mod notifier {
+    use crate::config::config::{WebhookConfig, WebhookKind};
+    use crate::update_checker::update_checker::ChapterUpdate;
+
+    // Discord rejects "content" longer than 2000 characters, so we'll send in batches
+    const K_MAX_UPDATES_PER_MESSAGE: usize = 10;
+
+    fn format_update(kind: WebhookKind, update: &ChapterUpdate) -> String {
+        match kind {
+            // Discord: markdown bold, and wrapping url in <> suppresses the (huge) link preview
+            WebhookKind::Discord => format!(
+                "**{}** - new chapter {}\n<{}>",
+                update.title, update.chapter, update.url
+            ),
+            // Slack: mrkdwn link syntax <url|text>
+            WebhookKind::Slack => format!(
+                "<{}|{}> - new chapter {}",
+                update.url, update.title, update.chapter
+            ),
+        }
+    }
+
+    pub fn format_message(kind: WebhookKind, updates: &[ChapterUpdate]) -> String {
+        updates
+            .iter()
+            .map(|update| format_update(kind, update))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn to_payload(kind: WebhookKind, message: &str) -> serde_json::Value {
+        match kind {
+            WebhookKind::Discord => serde_json::json!({ "content": message }),
+            WebhookKind::Slack => serde_json::json!({ "text": message }),
+        }
+    }
+
+    fn post(webhook: &WebhookConfig, message: &str) -> Result<(), Box<dyn std::error::Error>> {
+        ureq::post(&webhook.url).send_json(to_payload(webhook.kind, message))?;
+        Ok(())
+    }
+
+    // a failing webhook should not prevent the others from being notified, so errors are
+    // reported and counted rather than returned early; returns number of webhooks that failed
+    pub fn notify(webhooks: &[WebhookConfig], updates: &[ChapterUpdate]) -> usize {
+        if updates.is_empty() {
+            return 0;
+        }
+        let mut failed_count = 0;
+        for webhook in webhooks {
+            for batch in updates.chunks(K_MAX_UPDATES_PER_MESSAGE) {
+                if let Err(e) = post(webhook, &format_message(webhook.kind, batch)) {
+                    println!(
+                        "ERROR: notify({:?}): failed posting to webhook: {}",
+                        webhook.kind, e
+                    );
+                    failed_count += 1;
+                    break; // no use sending the remaining batches to the same broken webhook
+                }
+            }
+        }
+        failed_count
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::update_checker::update_checker::UpdateSource;
+
+        fn make_sample_update() -> ChapterUpdate {
+            ChapterUpdate {
+                manga_id: 1,
+                title: "ゲート".to_string(),
+                chapter: "12".to_string(),
+                url: "https://example.com/gate-chapter-12/".to_string(),
+                source: UpdateSource::Feed,
+            }
+        }
+
+        #[test]
+        fn test_format_message() {
+            let updates = vec![make_sample_update()];
+            assert_eq!(
+                format_message(WebhookKind::Discord, &updates),
+                "**ゲート** - new chapter 12\n<https://example.com/gate-chapter-12/>"
+            );
+            assert_eq!(
+                format_message(WebhookKind::Slack, &updates),
+                "<https://example.com/gate-chapter-12/|ゲート> - new chapter 12"
+            );
+        }
+
+        #[test]
+        fn test_to_payload() {
+            assert_eq!(
+                to_payload(WebhookKind::Discord, "hi").to_string(),
+                r#"{"content":"hi"}"#
+            );
+            assert_eq!(
+                to_payload(WebhookKind::Slack, "hi").to_string(),
+                r#"{"text":"hi"}"#
+            );
+        }
+
+        #[test]
+        fn test_notify_nothing_to_send() {
+            // no updates means no HTTP calls at all, even with a bogus url
+            let webhooks = vec![WebhookConfig {
+                kind: WebhookKind::Discord,
+                url: "http://invalid.invalid/".to_string(),
+            }];
+            assert_eq!(notify(&webhooks, &[]), 0);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+// One Markdown note per manga, for keeping the collection inside an Obsidian vault
+// (`manga export-obsidian <vault>/Manga`): the fields go in the YAML frontmatter, where Obsidian's
+// properties view and Dataview queries can use them, and the notes of the manga are the body:
+//      ---
+//      manga_id: 1
+//      title: "ゲート"
+//      aliases:
+//        - "Gate"
+//      url: "https://example.com/manga/gate/"
+//      chapter: "12"
+//      tags:
+//        - "genre/Action"
+//      status: "Publishing"
+//      ---
+//      # ゲート
+//
+//      notes...
+// Files are named "<title> (<id>).md", readable in the file explorer and linkable as [[ゲート (1)]], the id
+// keeping series with the same title apart.  Re-exporting only rewrites the notes that changed, and the
+// note of a retitled series is found by its manga_id and renamed rather than left behind, so the export
+// can be re-run as often as one likes.  Edits made in the vault are overwritten: the database is the source.
+pub mod obsidian_export {
+    use serde::Serialize;
+    use std::{
+        collections::{HashMap, HashSet},
+        fs, io,
+        path::{Path, PathBuf},
+    };
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::MangaMetadata;
+
+    const K_NOTE_EXTENSION: &str = "md";
+    const K_MANGA_ID_KEY: &str = "manga_id: ";
+    const K_MAX_FILE_NAME_CHARS: usize = 100; // of the title part, well under what any file system allows
+
+    // cannot be in a file name on some OS, or break [[links]] in Obsidian
+    const K_FILE_NAME_UNSAFE_CHARS: [char; 13] = [
+        '\\', '/', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']',
+    ];
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct VaultNote {
+        pub manga_id: i64,
+        pub file_name: String,
+        pub content: String,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+    pub struct VaultExportSummary {
+        pub written: usize,
+        pub unchanged: usize,
+        pub removed: usize, // renamed series' previous notes, plus notes of deleted manga when pruning
+    }
+
+    // i.e. ("Re:Zero / Side: 1?", 7) => "Re Zero Side 1 (7).md"
+    pub fn note_file_name(title: &str, id: i64) -> String {
+        let cleaned: String = title
+            .chars()
+            .map(
+                |c| match c.is_control() || K_FILE_NAME_UNSAFE_CHARS.contains(&c) {
+                    true => ' ',
+                    false => c,
+                },
+            )
+            .collect();
+        let words: String = cleaned
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+            .chars()
+            .take(K_MAX_FILE_NAME_CHARS)
+            .collect();
+        // Windows drops trailing dots, and a leading dot hides the file
+        let name = words.trim_matches(|c: char| c == '.' || c.is_whitespace());
+        match name.is_empty() {
+            true => format!("{}.{}", id, K_NOTE_EXTENSION),
+            false => format!("{} ({}).{}", name, id, K_NOTE_EXTENSION),
+        }
+    }
+
+    // Obsidian tags cannot contain spaces and use '/' for nesting, i.e. "genre:Slice of Life" =>
+    // "genre/Slice-of-Life"; None when nothing usable is left
+    pub fn obsidian_tag(tag: &str) -> Option<String> {
+        let converted: String = tag
+            .trim()
+            .chars()
+            .filter_map(|c| match c {
+                ':' | '/' => Some('/'),
+                '-' | '_' => Some(c),
+                c if c.is_whitespace() => Some('-'),
+                c if c.is_alphanumeric() => Some(c),
+                _ => None,
+            })
+            .collect();
+        let converted = converted.trim_matches('/').to_string();
+        match converted.chars().any(|c| c.is_alphabetic()) {
+            true => Some(converted),
+            false => None, // Obsidian ignores purely numeric tags
+        }
+    }
+
+    // a JSON string is a valid YAML double-quoted scalar, escapes included
+    fn yaml_string(text: &str) -> String {
+        serde_json::to_string(text).unwrap_or_default()
+    }
+
+    fn push_yaml_list(frontmatter: &mut Vec<String>, key: &str, items: &[String]) {
+        if items.is_empty() {
+            return;
+        }
+        frontmatter.push(format!("{}:", key));
+        for item in items.iter() {
+            frontmatter.push(format!("  - {}", yaml_string(item)));
+        }
+    }
+
+    pub fn to_note(manga: &MangaModel, possible_metadata: Option<&MangaMetadata>) -> VaultNote {
+        let non_empty =
+            |possible_text: Option<String>| possible_text.filter(|text| !text.trim().is_empty());
+        let mut frontmatter = vec![
+            "---".to_string(),
+            format!("{}{}", K_MANGA_ID_KEY, manga.id()),
+            format!("title: {}", yaml_string(manga.title())),
+        ];
+        let aliases: Vec<String> = non_empty(manga.title_romanized())
+            .filter(|romanized| romanized != manga.title())
+            .into_iter()
+            .collect();
+        push_yaml_list(&mut frontmatter, "aliases", &aliases);
+        frontmatter.push(format!("url: {}", yaml_string(manga.url())));
+        let optional_fields = [
+            (
+                "url_with_chapter",
+                non_empty(manga.url_with_chapter()).filter(|url| url != manga.url()),
+            ),
+            ("chapter", non_empty(manga.chapter())),
+            (
+                "latest_chapter",
+                non_empty(manga.latest_available_chapter()),
+            ),
+            ("last_update", non_empty(manga.last_update())),
+            ("my_anime_list", non_empty(manga.my_anime_list())),
+        ];
+        for (key, possible_value) in optional_fields.iter() {
+            if let Some(value) = possible_value {
+                frontmatter.push(format!("{}: {}", key, yaml_string(value)));
+            }
+        }
+        let tags: Vec<String> = manga
+            .tags()
+            .iter()
+            .filter_map(|tag| obsidian_tag(tag))
+            .collect();
+        push_yaml_list(&mut frontmatter, "tags", &tags);
+        if let Some(status) =
+            non_empty(possible_metadata.and_then(|metadata| metadata.publication_status.clone()))
+        {
+            frontmatter.push(format!("status: {}", yaml_string(&status)));
+        }
+        frontmatter.push("---".to_string());
+
+        let mut content = frontmatter.join("\n");
+        content.push_str(&format!("\n# {}\n", manga.title()));
+        if let Some(notes) = non_empty(manga.notes()) {
+            content.push_str(&format!("\n{}\n", notes.trim()));
+        }
+        VaultNote {
+            manga_id: manga.id(),
+            file_name: note_file_name(manga.title(), manga.id()),
+            content,
+        }
+    }
+
+    // manga_id of a note written by to_note(), None for any other note of the vault
+    fn note_manga_id(content: &str) -> Option<i64> {
+        let mut lines = content.lines();
+        if lines.next() != Some("---") {
+            return None;
+        }
+        lines
+            .take_while(|line| *line != "---")
+            .find_map(|line| line.strip_prefix(K_MANGA_ID_KEY))
+            .and_then(|id| id.trim().parse::<i64>().ok())
+    }
+
+    // notes already exported into vault_dir, by manga_id
+    fn exported_notes(vault_dir: &Path) -> io::Result<HashMap<i64, Vec<PathBuf>>> {
+        let mut notes: HashMap<i64, Vec<PathBuf>> = HashMap::new();
+        for entry in fs::read_dir(vault_dir)? {
+            let path = entry?.path();
+            if !path.is_file()
+                || path.extension().and_then(|extension| extension.to_str())
+                    != Some(K_NOTE_EXTENSION)
+            {
+                continue;
+            }
+            if let Some(id) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| note_manga_id(&content))
+            {
+                notes.entry(id).or_default().push(path);
+            }
+        }
+        Ok(notes)
+    }
+
+    // with prune, notes of manga which are no longer in notes are removed as well; other files are never touched
+    pub fn write_vault(
+        vault_dir: &Path,
+        notes: &[VaultNote],
+        prune: bool,
+    ) -> io::Result<VaultExportSummary> {
+        fs::create_dir_all(vault_dir)?;
+        let mut previous_notes = exported_notes(vault_dir)?;
+        let mut summary = VaultExportSummary::default();
+        for note in notes.iter() {
+            let path = vault_dir.join(&note.file_name);
+            for previous_path in previous_notes.remove(&note.manga_id).unwrap_or_default() {
+                if previous_path != path {
+                    fs::remove_file(&previous_path)?;
+                    summary.removed += 1;
+                }
+            }
+            match fs::read_to_string(&path) {
+                Ok(existing_content) if existing_content == note.content => summary.unchanged += 1,
+                _ => {
+                    fs::write(&path, &note.content)?;
+                    summary.written += 1;
+                }
+            }
+        }
+        if prune {
+            let exported_ids: HashSet<i64> = notes.iter().map(|note| note.manga_id).collect();
+            for (id, paths) in previous_notes.iter() {
+                if exported_ids.contains(id) {
+                    continue;
+                }
+                for path in paths.iter() {
+                    fs::remove_file(path)?;
+                    summary.removed += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_to_note() {
+            assert_eq!(
+                note_file_name("Re:Zero / Side: 1?", 7),
+                "Re Zero Side 1 (7).md"
+            );
+            assert_eq!(note_file_name(" ... ", 7), "7.md");
+            assert_eq!(
+                obsidian_tag(" genre:Slice of Life"),
+                Some("genre/Slice-of-Life".to_string())
+            );
+            assert_eq!(obsidian_tag("2023"), None);
+
+            let mut manga =
+                MangaModel::new_from_required_elements("ゲート", "https://example.com/gate/", 1)
+                    .unwrap();
+            manga.set_title_romanized(Some("Gate".to_string()));
+            manga.set_chapter(Some("12".to_string()));
+            manga.set_tags(vec![" genre:Action".to_string()]);
+            manga.set_notes(Some("Isekai with the JSDF".to_string()));
+            let metadata = MangaMetadata {
+                manga_id: 1,
+                publication_status: Some("Publishing".to_string()),
+                ..Default::default()
+            };
+            let note = to_note(&manga, Some(&metadata));
+            assert_eq!(note.file_name, "ゲート (1).md");
+            assert_eq!(
+                note.content,
+                "---\nmanga_id: 1\ntitle: \"ゲート\"\naliases:\n  - \"Gate\"\nurl: \"https://example.com/gate/\"\n\
+                 chapter: \"12\"\ntags:\n  - \"genre/Action\"\nstatus: \"Publishing\"\n---\n# ゲート\n\nIsekai with the JSDF\n"
+            );
+            assert_eq!(note_manga_id(&note.content), Some(1));
+        }
+
+        #[test]
+        fn test_write_vault() {
+            let vault_dir = Path::new("samples/test_obsidian_vault");
+            fs::remove_dir_all(vault_dir).unwrap_or_default();
+            let gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            let mut akira =
+                MangaModel::new_from_required_elements("Akira", "https://example.com/akira/", 2)
+                    .unwrap();
+            fs::create_dir_all(vault_dir).unwrap();
+            fs::write(vault_dir.join("Reading list.md"), "not exported").unwrap();
+
+            let notes = vec![to_note(&gate, None), to_note(&akira, None)];
+            let summary = write_vault(vault_dir, &notes, false).unwrap();
+            assert_eq!(
+                (summary.written, summary.unchanged, summary.removed),
+                (2, 0, 0)
+            );
+            let summary = write_vault(vault_dir, &notes, false).unwrap();
+            assert_eq!(
+                (summary.written, summary.unchanged, summary.removed),
+                (0, 2, 0)
+            );
+
+            // retitled: same note under its new name; pruned: gone, everything else left alone
+            akira.set_title("AKIRA".to_string());
+            let summary = write_vault(vault_dir, &[to_note(&akira, None)], true).unwrap();
+            assert_eq!(
+                (summary.written, summary.unchanged, summary.removed),
+                (1, 0, 2)
+            );
+            assert!(vault_dir.join("AKIRA (2).md").is_file());
+            assert!(!vault_dir.join("Akira (2).md").exists());
+            assert!(!vault_dir.join("Gate (1).md").exists());
+            assert!(vault_dir.join("Reading list.md").is_file());
+
+            fs::remove_dir_all(vault_dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Ranked search by (part of) a title: every manga found by any of the matchers below gets the score of the
+// best one, plus a bonus for each other matcher that agrees, so the most likely series comes first:
+//      exact       title or romanized title equals the query               100
+//      prefix      title or romanized title starts with the query           80
+//      contains    the query is somewhere in the title or romanized title   65
+//                  (i.e. "ストーン" finds "ドクターストーン", which FTS5 has as a single word)
+//      full text   FTS5 hit on title, romanized title or notes (any word)   50..60, by bm25 order
+//      fuzzy       within a few typos of the title, one of its words, or     up to 40
+//                  its beginning (i.e. "shingki" finds "Shingeki no Kyojin"), unless exact or prefix
+// Comparisons ignore case, spaces and punctuation ("dr stone" == "Dr. Stone").
+pub mod ranked_search {
+    use serde::Serialize;
+    use std::fmt;
+
+    use crate::model_manga::model_manga::MangaModel;
+
+    const K_EXACT_SCORE: f64 = 100.0;
+    const K_PREFIX_SCORE: f64 = 80.0;
+    const K_CONTAINS_SCORE: f64 = 65.0;
+    const K_CONTAINS_MIN_QUERY_CHARS: usize = 2; // i.e. "巨人"
+    const K_FULL_TEXT_SCORE: f64 = 50.0;
+    const K_FULL_TEXT_ORDER_SCORE: f64 = 10.0; // added to the best FTS hit, less for the next ones
+    const K_FUZZY_SCORE: f64 = 40.0;
+    const K_AGREEMENT_BONUS: f64 = 5.0;
+    const K_FUZZY_MIN_QUERY_CHARS: usize = 3; // shorter ones are a few typos away from anything
+    const K_FUZZY_CHARS_PER_TYPO: usize = 4;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MatchKind {
+        Exact,
+        Prefix,
+        Contains,
+        FullText,
+        Fuzzy,
+    }
+
+    impl fmt::Display for MatchKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let name = match self {
+                MatchKind::Exact => "exact",
+                MatchKind::Prefix => "prefix",
+                MatchKind::Contains => "contains",
+                MatchKind::FullText => "full_text",
+                MatchKind::Fuzzy => "fuzzy",
+            };
+            write!(f, "{}", name)
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize)]
+    pub struct SearchHit {
+        pub score: f64,
+        pub matched_by: Vec<MatchKind>,
+        pub manga: MangaModel,
+    }
+
+    // lowercase letters and digits only
+    fn normalized(text: &str) -> Vec<char> {
+        text.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(|c| c.to_lowercase())
+            .collect()
+    }
+
+    pub fn levenshtein(a: &[char], b: &[char]) -> usize {
+        let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+        for (i, a_char) in a.iter().enumerate() {
+            let mut row = vec![i + 1];
+            for (j, b_char) in b.iter().enumerate() {
+                let substitution = previous_row[j] + (a_char != b_char) as usize;
+                row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+            }
+            previous_row = row;
+        }
+        previous_row[b.len()]
+    }
+
+    fn contains(text: &[char], query: &[char]) -> bool {
+        text.windows(query.len()).any(|window| window == query)
+    }
+
+    // 0.0..1.0 (1.0 being no typo at all), None when too far off
+    fn fuzzy_similarity(query: &[char], text: &str) -> Option<f64> {
+        if query.len() < K_FUZZY_MIN_QUERY_CHARS {
+            return None;
+        }
+        let whole = normalized(text);
+        let beginning: Vec<char> = whole.iter().take(query.len()).cloned().collect();
+        let mut candidates: Vec<Vec<char>> = text
+            .split(|c: char| c.is_whitespace())
+            .map(normalized)
+            .filter(|word| !word.is_empty())
+            .collect();
+        candidates.push(beginning);
+        candidates.push(whole);
+        let distance = candidates
+            .iter()
+            .map(|candidate| levenshtein(query, candidate))
+            .min()?;
+        match distance <= (query.len() / K_FUZZY_CHARS_PER_TYPO).max(1) {
+            true => Some(1.0 - distance as f64 / (query.len() + 1) as f64),
+            false => None,
+        }
+    }
+
+    // fts_ids_best_first are the FTS5 hits for the same query (see model_sqlite3_manga_fts)
+    pub fn rank(
+        mangas: &[MangaModel],
+        fts_ids_best_first: &[i64],
+        query: &str,
+        limit: usize,
+    ) -> Vec<SearchHit> {
+        let normalized_query = normalized(query);
+        if normalized_query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<SearchHit> = mangas
+            .iter()
+            .filter_map(|manga| {
+                let texts: Vec<String> = [Some(manga.title().to_string()), manga.title_romanized()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let mut scores: Vec<(MatchKind, f64)> = Vec::new();
+                if texts
+                    .iter()
+                    .any(|text| normalized(text) == normalized_query)
+                {
+                    scores.push((MatchKind::Exact, K_EXACT_SCORE));
+                } else if texts
+                    .iter()
+                    .any(|text| normalized(text).starts_with(&normalized_query))
+                {
+                    scores.push((MatchKind::Prefix, K_PREFIX_SCORE));
+                } else if normalized_query.len() >= K_CONTAINS_MIN_QUERY_CHARS
+                    && texts
+                        .iter()
+                        .any(|text| contains(&normalized(text), &normalized_query))
+                {
+                    scores.push((MatchKind::Contains, K_CONTAINS_SCORE));
+                }
+                if let Some(position) = fts_ids_best_first.iter().position(|&id| id == manga.id()) {
+                    let order = 1.0 - position as f64 / fts_ids_best_first.len() as f64;
+                    scores.push((
+                        MatchKind::FullText,
+                        K_FULL_TEXT_SCORE + K_FULL_TEXT_ORDER_SCORE * order,
+                    ));
+                }
+                // only a fallback, an exact or prefix match is no typo
+                let possible_similarity = match scores.is_empty() {
+                    true => texts
+                        .iter()
+                        .filter_map(|text| fuzzy_similarity(&normalized_query, text))
+                        .reduce(f64::max),
+                    false => None,
+                };
+                if let Some(similarity) = possible_similarity {
+                    scores.push((MatchKind::Fuzzy, K_FUZZY_SCORE * similarity));
+                }
+                let best_score = scores.iter().map(|(_, score)| *score).reduce(f64::max)?;
+                Some(SearchHit {
+                    score: best_score + K_AGREEMENT_BONUS * (scores.len() - 1) as f64,
+                    matched_by: scores.iter().map(|(kind, _)| *kind).collect(),
+                    manga: manga.clone(),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.manga.title().cmp(b.manga.title()))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn manga(id: i64, title: &str, possible_romanized: Option<&str>) -> MangaModel {
+            let mut manga = MangaModel::new_from_required_elements(
+                title,
+                &format!("https://example.com/manga/{}/", id),
+                id,
+            )
+            .unwrap();
+            manga.set_title_romanized(possible_romanized.map(|romanized| romanized.to_string()));
+            manga
+        }
+
+        #[test]
+        fn test_rank() {
+            assert_eq!(levenshtein(&normalized("gate"), &normalized("gaet")), 2);
+            let mangas = vec![
+                manga(1, "進撃の巨人", Some("Shingeki no Kyojin")),
+                manga(2, "Dr. Stone", None),
+                manga(3, "Stone Ocean", None),
+                manga(4, "Gate", None),
+                manga(5, "ドクターストーン", Some("Dokutaa Suton")),
+            ];
+            let ids = |hits: &[SearchHit]| -> Vec<i64> {
+                hits.iter().map(|hit| hit.manga.id()).collect()
+            };
+
+            // exact beats a full-text hit on one of its words
+            let hits = rank(&mangas, &[2, 3], "dr stone", 10);
+            assert_eq!(ids(&hits), vec![2, 3]);
+            assert_eq!(
+                hits[0].matched_by,
+                vec![MatchKind::Exact, MatchKind::FullText]
+            );
+            // prefix on the romanized title, and typos
+            assert_eq!(ids(&rank(&mangas, &[], "shingeki", 10)), vec![1]);
+            assert_eq!(ids(&rank(&mangas, &[], "shingki no kyojin", 10)), vec![1]);
+            assert_eq!(ids(&rank(&mangas, &[], "stome", 10)), vec![2, 3]);
+            assert!(rank(&mangas, &[], "zzz", 10).is_empty());
+            // partial Japanese and romaji, in the middle of the title
+            let hits = rank(&mangas, &[], "ストーン", 10);
+            assert_eq!(ids(&hits), vec![5]);
+            assert_eq!(hits[0].matched_by, vec![MatchKind::Contains]);
+            assert_eq!(ids(&rank(&mangas, &[], "の巨人", 10)), vec![1]);
+            assert_eq!(ids(&rank(&mangas, &[], "no kyojin", 10)), vec![1]);
+            assert_eq!(rank(&mangas, &[2, 3], "stone", 1).len(), 1);
+        }
+    }
+}
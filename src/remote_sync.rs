@@ -0,0 +1,458 @@
+// `manga sync push|pull|status`: keeps the database of two (or more) machines in sync through a WebDAV
+// directory or an S3 bucket, by copying the whole sqlite file.  Next to it lives "<file>.revision", a small
+// JSON document bumped by every push:
+//      {"revision":7,"pushed_at_millis":1694008642000,"pushed_by":"laptop"}
+// Each database remembers the revision it last pushed or pulled (sync_state) and counts its own changes since
+// (sync_changes), which is enough to detect both kinds of conflict:
+//      push    the remote moved on since our last sync, someone else pushed: pull first (or --force)
+//      pull    we have changes of our own which the remote copy would overwrite: push first (or --force)
+// A pull keeps the replaced database as "<db>.bak".  Config (TOML), credentials included:
+//      [sync]
+//      kind = "webdav"                                 # or "s3"
+//      url = "https://dav.example.com/manga/"          # webdav: an existing directory; s3: the endpoint
+//      username = "me"                                 # webdav: basic auth; s3: access key id
+//      password = "secret"                             # webdav: basic auth; s3: secret access key
+//      bucket = "my-bucket"                            # s3 only (path-style requests, so MinIO etc. work too)
+//      region = "eu-west-1"                            # s3 only, default "us-east-1"
+//      file_name = "漫画.sqlite3"                      # default: the name of the local database file
+pub mod remote_sync {
+    use base64::Engine;
+    use serde::{Deserialize, Serialize};
+    use std::{
+        fs,
+        io::Read,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+
+    use crate::config::config::{SyncConfig, SyncKind};
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_sync_state::model_sqlite3_sync_state;
+
+    const K_REVISION_SUFFIX: &str = ".revision";
+    const K_DEFAULT_S3_REGION: &str = "us-east-1";
+    const K_TIMEOUT_SECONDS: u64 = 120; // whole database files, so well above what page fetches get
+    const K_MAX_DOWNLOAD_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+    const K_EMPTY_PAYLOAD_SHA256: &str =
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct RemoteRevision {
+        pub revision: u64,
+        pub pushed_at_millis: i64,
+        pub pushed_by: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum SyncOutcome {
+        Pushed(u64),
+        Pulled(u64),
+        UpToDate(u64),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SyncStatus {
+        pub remote: String,
+        pub local_changes: u64,
+        pub synced_revision: Option<u64>,
+        pub possible_remote_revision: Option<RemoteRevision>,
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn sha256_hex(bytes: &[u8]) -> String {
+        hex(&hmac_sha256::Hash::hash(bytes))
+    }
+
+    // RFC 3986 unreserved characters are kept, everything else is %XX (and '/' too unless keep_slash)
+    pub fn uri_encode(text: &str, keep_slash: bool) -> String {
+        text.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    (byte as char).to_string()
+                }
+                b'/' if keep_slash => "/".to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect()
+    }
+
+    // AWS Signature Version 4 of a request without query string; headers are (lowercase name, value),
+    // sorted by name, and must include host, x-amz-content-sha256 and x-amz-date; returns the signature
+    pub fn sigv4_signature(
+        method: &str,
+        canonical_uri: &str,
+        headers: &[(String, String)],
+        payload_sha256: &str,
+        amz_datetime: &str, // i.e. "20130524T000000Z"
+        region: &str,
+        secret_access_key: &str,
+    ) -> String {
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method,
+            canonical_uri,
+            canonical_headers,
+            signed_headers(headers),
+            payload_sha256
+        );
+        let date = &amz_datetime[..8];
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}/{}/s3/aws4_request\n{}",
+            amz_datetime,
+            date,
+            region,
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let date_key = hmac_sha256::HMAC::mac(date, format!("AWS4{}", secret_access_key));
+        let region_key = hmac_sha256::HMAC::mac(region, date_key);
+        let service_key = hmac_sha256::HMAC::mac("s3", region_key);
+        let signing_key = hmac_sha256::HMAC::mac("aws4_request", service_key);
+        hex(&hmac_sha256::HMAC::mac(string_to_sign, signing_key))
+    }
+
+    fn signed_headers(headers: &[(String, String)]) -> String {
+        headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>()
+            .join(";")
+    }
+
+    fn remote_file_name(db_full_paths: &str, config: &SyncConfig) -> String {
+        config.file_name.clone().unwrap_or_else(|| {
+            Path::new(db_full_paths)
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_else(|| db_full_paths.to_string())
+        })
+    }
+
+    fn object_url(config: &SyncConfig, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let base_url = config.url.trim_end_matches('/');
+        match config.kind {
+            SyncKind::WebDav => Ok(format!("{}/{}", base_url, uri_encode(name, false))),
+            SyncKind::S3 => match &config.bucket {
+                Some(bucket) => Ok(format!(
+                    "{}/{}/{}",
+                    base_url,
+                    uri_encode(bucket, false),
+                    uri_encode(name, false)
+                )),
+                None => Err("sync: kind = \"s3\" needs a bucket".into()),
+            },
+        }
+    }
+
+    fn request(
+        config: &SyncConfig,
+        method: &str,
+        url: &str,
+        payload: &[u8],
+    ) -> Result<ureq::Request, Box<dyn std::error::Error>> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(K_TIMEOUT_SECONDS))
+            .build();
+        let request = agent.request(method, url);
+        match config.kind {
+            SyncKind::WebDav => match (&config.username, &config.password) {
+                (Some(username), Some(password)) => Ok(request.set(
+                    "Authorization",
+                    &format!(
+                        "Basic {}",
+                        base64::engine::general_purpose::STANDARD
+                            .encode(format!("{}:{}", username, password))
+                    ),
+                )),
+                _ => Ok(request),
+            },
+            SyncKind::S3 => {
+                let (access_key_id, secret_access_key) =
+                    match (&config.username, &config.password) {
+                        (Some(username), Some(password)) => (username, password),
+                        _ => {
+                            return Err(
+                                "sync: kind = \"s3\" needs username (access key id) and password (secret access key)"
+                                    .into(),
+                            )
+                        }
+                    };
+                let parsed_url = url::Url::parse(url)?;
+                let host = match parsed_url.port() {
+                    Some(port) => format!("{}:{}", parsed_url.host_str().unwrap_or_default(), port),
+                    None => parsed_url.host_str().unwrap_or_default().to_string(),
+                };
+                let payload_sha256 = match payload.is_empty() {
+                    true => K_EMPTY_PAYLOAD_SHA256.to_string(),
+                    false => sha256_hex(payload),
+                };
+                let amz_datetime = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+                let region = config.region.as_deref().unwrap_or(K_DEFAULT_S3_REGION);
+                let headers = vec![
+                    ("host".to_string(), host),
+                    ("x-amz-content-sha256".to_string(), payload_sha256.clone()),
+                    ("x-amz-date".to_string(), amz_datetime.clone()),
+                ];
+                let signature = sigv4_signature(
+                    method,
+                    parsed_url.path(),
+                    &headers,
+                    &payload_sha256,
+                    &amz_datetime,
+                    region,
+                    secret_access_key,
+                );
+                Ok(request
+                    .set("x-amz-content-sha256", &payload_sha256)
+                    .set("x-amz-date", &amz_datetime)
+                    .set(
+                        "Authorization",
+                        &format!(
+                            "AWS4-HMAC-SHA256 Credential={}/{}/{}/s3/aws4_request, SignedHeaders={}, Signature={}",
+                            access_key_id,
+                            &amz_datetime[..8],
+                            region,
+                            signed_headers(&headers),
+                            signature
+                        ),
+                    ))
+            }
+        }
+    }
+
+    // Ok(None) when there is no such file (yet)
+    fn remote_get(
+        config: &SyncConfig,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        let url = object_url(config, name)?;
+        match request(config, "GET", &url, &[])?.call() {
+            Ok(response) => {
+                let mut bytes = Vec::new();
+                response
+                    .into_reader()
+                    .take(K_MAX_DOWNLOAD_BYTES)
+                    .read_to_end(&mut bytes)?;
+                Ok(Some(bytes))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(format!("sync: GET {} failed: {}", url, e).into()),
+        }
+    }
+
+    fn remote_put(
+        config: &SyncConfig,
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let url = object_url(config, name)?;
+        match request(config, "PUT", &url, bytes)?.send_bytes(bytes) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("sync: PUT {} failed: {}", url, e).into()),
+        }
+    }
+
+    fn remote_revision(
+        config: &SyncConfig,
+        file_name: &str,
+    ) -> Result<Option<RemoteRevision>, Box<dyn std::error::Error>> {
+        match remote_get(config, &format!("{}{}", file_name, K_REVISION_SUFFIX))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice::<RemoteRevision>(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn describe(revision: &RemoteRevision) -> String {
+        let pushed_at =
+            chrono::TimeZone::timestamp_millis_opt(&chrono::Local, revision.pushed_at_millis)
+                .single()
+                .map(|datetime| datetime.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+        format!(
+            "revision {} (pushed by {} at {})",
+            revision.revision, revision.pushed_by, pushed_at
+        )
+    }
+
+    fn machine_name() -> String {
+        std::env::var("HOSTNAME")
+            .or_else(|_| std::env::var("COMPUTERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    fn aside_path(db_full_paths: &str, suffix: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}", db_full_paths, suffix))
+    }
+
+    pub fn status(
+        db_full_paths: &str,
+        config: &SyncConfig,
+    ) -> Result<SyncStatus, Box<dyn std::error::Error>> {
+        let file_name = remote_file_name(db_full_paths, config);
+        let remote = object_url(config, &file_name)?;
+        Ok(SyncStatus {
+            local_changes: model_sqlite3_sync_state::local_changes(db_full_paths)?,
+            synced_revision: model_sqlite3_sync_state::select_synced_revision(
+                db_full_paths,
+                &remote,
+            )?,
+            possible_remote_revision: remote_revision(config, &file_name)?,
+            remote,
+        })
+    }
+
+    pub fn push(
+        db_full_paths: &str,
+        config: &SyncConfig,
+        force: bool,
+    ) -> Result<SyncOutcome, Box<dyn std::error::Error>> {
+        let file_name = remote_file_name(db_full_paths, config);
+        let status = status(db_full_paths, config)?;
+        let remote_revision_number = status
+            .possible_remote_revision
+            .as_ref()
+            .map_or(0, |revision| revision.revision);
+        if let Some(revision) = &status.possible_remote_revision {
+            if status.synced_revision != Some(revision.revision) && !force {
+                return Err(format!(
+                    "sync: {} has {} which this database has not pulled; pull first, or push --force to overwrite it",
+                    status.remote,
+                    describe(revision)
+                )
+                .into());
+            }
+            if status.local_changes == 0 && status.synced_revision == Some(revision.revision) {
+                return Ok(SyncOutcome::UpToDate(revision.revision));
+            }
+        }
+
+        // a consistent copy (even while a server writes to the database), which already says it is in sync
+        let revision = remote_revision_number + 1;
+        let snapshot_path = aside_path(db_full_paths, ".sync");
+        fs::remove_file(&snapshot_path).unwrap_or_default();
+        model_sqlite3_connection::open(db_full_paths)?.execute(
+            "VACUUM INTO ?1",
+            rusqlite::params![snapshot_path.to_string_lossy()],
+        )?;
+        let snapshot_full_paths = snapshot_path.to_string_lossy().to_string();
+        model_sqlite3_sync_state::mark_synced(&snapshot_full_paths, &status.remote, revision)?;
+        let snapshot = fs::read(&snapshot_path)?;
+        fs::remove_file(&snapshot_path)?;
+
+        // database first: whoever sees the new revision can download the matching database
+        remote_put(config, &file_name, &snapshot)?;
+        let remote_revision = RemoteRevision {
+            revision,
+            pushed_at_millis: chrono::Utc::now().timestamp_millis(),
+            pushed_by: machine_name(),
+        };
+        remote_put(
+            config,
+            &format!("{}{}", file_name, K_REVISION_SUFFIX),
+            &serde_json::to_vec(&remote_revision)?,
+        )?;
+        model_sqlite3_sync_state::mark_synced(db_full_paths, &status.remote, revision)?;
+        Ok(SyncOutcome::Pushed(revision))
+    }
+
+    pub fn pull(
+        db_full_paths: &str,
+        config: &SyncConfig,
+        force: bool,
+    ) -> Result<SyncOutcome, Box<dyn std::error::Error>> {
+        let file_name = remote_file_name(db_full_paths, config);
+        let status = status(db_full_paths, config)?;
+        let revision = match status.possible_remote_revision {
+            Some(revision) => revision,
+            None => return Err(format!("sync: nothing pushed to {} yet", status.remote).into()),
+        };
+        if status.synced_revision == Some(revision.revision) {
+            return Ok(SyncOutcome::UpToDate(revision.revision));
+        }
+        if status.local_changes > 0 && !force {
+            return Err(format!(
+                "sync: {} local change(s) not pushed yet would be lost by pulling {}; push first, or pull --force to discard them",
+                status.local_changes,
+                describe(&revision)
+            )
+            .into());
+        }
+
+        let bytes = match remote_get(config, &file_name)? {
+            Some(bytes) => bytes,
+            None => return Err(format!("sync: {} is missing", status.remote).into()),
+        };
+        let download_path = aside_path(db_full_paths, ".sync");
+        fs::write(&download_path, bytes)?;
+        // make sure it is a database before it replaces ours
+        let download_full_paths = download_path.to_string_lossy().to_string();
+        if let Err(e) = rusqlite::Connection::open(&download_full_paths)
+            .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM manga", [], |_| Ok(())))
+        {
+            fs::remove_file(&download_path).unwrap_or_default();
+            return Err(format!("sync: {} is not a manga database: {}", status.remote, e).into());
+        }
+        if Path::new(db_full_paths).exists() {
+            fs::copy(db_full_paths, aside_path(db_full_paths, ".bak"))?;
+        }
+        fs::rename(&download_path, db_full_paths)?;
+        model_sqlite3_sync_state::mark_synced(db_full_paths, &status.remote, revision.revision)?;
+        Ok(SyncOutcome::Pulled(revision.revision))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_sigv4_signature() {
+            // "GET Object" example of the AWS Signature Version 4 documentation
+            let headers: Vec<(String, String)> = [
+                ("host", "examplebucket.s3.amazonaws.com"),
+                ("range", "bytes=0-9"),
+                ("x-amz-content-sha256", K_EMPTY_PAYLOAD_SHA256),
+                ("x-amz-date", "20130524T000000Z"),
+            ]
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+            assert_eq!(
+                sigv4_signature(
+                    "GET",
+                    "/test.txt",
+                    &headers,
+                    K_EMPTY_PAYLOAD_SHA256,
+                    "20130524T000000Z",
+                    "us-east-1",
+                    "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY"
+                ),
+                "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+            );
+            assert_eq!(sha256_hex(b""), K_EMPTY_PAYLOAD_SHA256);
+            assert_eq!(
+                uri_encode("漫画 1/a.db", true),
+                "%E6%BC%AB%E7%94%BB%201/a.db"
+            );
+
+            let config = SyncConfig {
+                kind: SyncKind::S3,
+                url: "https://s3.example.com/".to_string(),
+                bucket: Some("manga".to_string()),
+                region: None,
+                username: None,
+                password: None,
+                file_name: None,
+            };
+            assert_eq!(
+                object_url(&config, &remote_file_name("/home/me/漫画.sqlite3", &config)).unwrap(),
+                "https://s3.example.com/manga/%E6%BC%AB%E7%94%BB.sqlite3"
+            );
+        }
+    }
+}
@@ -0,0 +1,374 @@
+// HTTP/JSON access to the collection: `manga serve [--bind 127.0.0.1:8080]`
+// Uses the request/response structs of model_manga as the wire format, so other apps and scripts can
+// read and edit the database without linking against this crate.  There is no authentication, hence the
+// default is to bind to localhost only.
+//      GET    /manga                       => MangaList
+//      POST   /manga          MangaCreateRequest => MangaCreateResponse (201)
+//      GET    /manga/:id                   => MangaResponse
+//      PUT    /manga/:id      MangaRequest        => MangaUpdateResponse (replaces every field)
+//      PATCH  /manga/:id      MangaUpdateRequest  => MangaUpdateResponse (only the fields given)
+//      DELETE /manga/:id                   => MangaDeleteResponse
+//      POST   /manga/search   MangaSearchRequest  => MangaSearchResponse
+//      GET    /manga/search?title=<title>  => MangaSearchByTitleResponse
+//      POST   /manga/:id/read MangaMarkReadRequest => MangaMarkReadResponse (logged like `mark-read`)
+// Errors are {"error": "<message>"} with 400 (bad request), 404 (no such id) or 500.
+// The OpenAPI document (derived from the handlers below by utoipa) is served at /openapi.json, browsable
+// at /swagger-ui, and can be written out without a server via `manga openapi`.
+// Read-only GraphQL (see graphql_api) is at POST /graphql, with the GraphiQL explorer at GET /graphql.
+// A small dashboard (assets/dashboard, compiled into the binary) is served at /, for browsing and marking
+// chapters read from a phone; bind to i.e. 0.0.0.0:8080 to reach it from the LAN.
+pub mod rest_server {
+    use axum::{
+        extract::{Path, Query, State},
+        http::{header, StatusCode},
+        response::{Html, IntoResponse, Response},
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde::Serialize;
+    use utoipa::{OpenApi, ToSchema};
+    use utoipa_swagger_ui::SwaggerUi;
+
+    use crate::graphql_api::graphql_api;
+    use crate::model_manga::model_manga::{
+        MangaCreateRequest, MangaCreateResponse, MangaDeleteResponse, MangaList,
+        MangaMarkReadRequest, MangaMarkReadResponse, MangaRequest, MangaResponse,
+        MangaSearchByTitleRequest, MangaSearchByTitleResponse, MangaSearchRequest,
+        MangaSearchResponse, MangaUpdateRequest, MangaUpdateResponse,
+    };
+    use crate::service::service::{self, MangaService, ServiceError};
+
+    pub const K_DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+    pub struct ServerOptions {
+        pub db_full_paths: String,
+        pub bind: String,
+    }
+
+    #[derive(Clone)]
+    struct ServerState {
+        service: MangaService,
+        graphql_schema: graphql_api::MangaSchema,
+    }
+
+    #[derive(OpenApi)]
+    #[openapi(
+        info(title = "manga", description = "Manga bookmarks collection"),
+        paths(
+            list_manga,
+            create_manga,
+            get_manga,
+            replace_manga,
+            patch_manga,
+            delete_manga,
+            search_manga,
+            search_manga_by_title,
+            mark_read
+        ),
+        components(schemas(ErrorResponse))
+    )]
+    struct ApiDoc;
+
+    #[derive(Debug, Serialize, ToSchema)]
+    struct ErrorResponse {
+        error: String,
+    }
+
+    #[derive(Debug)]
+    struct ApiError {
+        status: StatusCode,
+        message: String,
+    }
+
+    impl From<ServiceError> for ApiError {
+        fn from(e: ServiceError) -> Self {
+            let status = match e {
+                ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+                ServiceError::AlreadyExists(_) => StatusCode::CONFLICT,
+                ServiceError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+                ServiceError::DatabaseBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
+                ServiceError::Database(_) | ServiceError::Io(_) => {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            ApiError {
+                status,
+                message: e.to_string(),
+            }
+        }
+    }
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> Response {
+            println!("ERROR: rest_server: {} {}", self.status, self.message);
+            (
+                self.status,
+                Json(ErrorResponse {
+                    error: self.message,
+                }),
+            )
+                .into_response()
+        }
+    }
+
+    // the database layer is synchronous (a Connection per call), so keep it off the async workers
+    async fn blocking<T, F>(f: F) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, ServiceError> + Send + 'static,
+    {
+        match tokio::task::spawn_blocking(f).await {
+            Ok(result) => Ok(result?),
+            Err(e) => Err(ApiError {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    #[utoipa::path(get, path = "/manga", responses(
+        (status = 200, description = "every manga", body = MangaList),
+        (status = 500, body = ErrorResponse)
+    ))]
+    async fn list_manga(State(state): State<ServerState>) -> Result<Json<MangaList>, ApiError> {
+        let data = blocking(move || state.service.list()).await?;
+        Ok(Json(MangaList { data }))
+    }
+
+    #[utoipa::path(get, path = "/manga/{id}", params(("id" = i64, Path, description = "manga id")), responses(
+        (status = 200, body = MangaResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
+    async fn get_manga(
+        State(state): State<ServerState>,
+        Path(id): Path<i64>,
+    ) -> Result<Json<MangaResponse>, ApiError> {
+        let data = blocking(move || state.service.get(id)).await?;
+        Ok(Json(MangaResponse { data }))
+    }
+
+    #[utoipa::path(post, path = "/manga", request_body = MangaCreateRequest, responses(
+        (status = 201, description = "id of the new manga", body = MangaCreateResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "same title and url already exists", body = ErrorResponse)
+    ))]
+    async fn create_manga(
+        State(state): State<ServerState>,
+        Json(request): Json<MangaCreateRequest>,
+    ) -> Result<(StatusCode, Json<MangaCreateResponse>), ApiError> {
+        let inserted = blocking(move || state.service.create(request)).await?;
+        Ok((
+            StatusCode::CREATED,
+            Json(MangaCreateResponse { id: inserted.id() }),
+        ))
+    }
+
+    #[utoipa::path(put, path = "/manga/{id}", params(("id" = i64, Path, description = "manga id")),
+        request_body = MangaRequest, responses(
+        (status = 200, body = MangaUpdateResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
+    async fn replace_manga(
+        State(state): State<ServerState>,
+        Path(id): Path<i64>,
+        Json(request): Json<MangaRequest>,
+    ) -> Result<Json<MangaUpdateResponse>, ApiError> {
+        blocking(move || state.service.replace(id, request)).await?;
+        Ok(Json(MangaUpdateResponse { id: id }))
+    }
+
+    #[utoipa::path(patch, path = "/manga/{id}", params(("id" = i64, Path, description = "manga id")),
+        request_body = MangaUpdateRequest, responses(
+        (status = 200, body = MangaUpdateResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
+    async fn patch_manga(
+        State(state): State<ServerState>,
+        Path(id): Path<i64>,
+        Json(request): Json<MangaUpdateRequest>,
+    ) -> Result<Json<MangaUpdateResponse>, ApiError> {
+        blocking(move || state.service.update(id, request)).await?;
+        Ok(Json(MangaUpdateResponse { id: id }))
+    }
+
+    #[utoipa::path(delete, path = "/manga/{id}", params(("id" = i64, Path, description = "manga id")), responses(
+        (status = 200, body = MangaDeleteResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
+    async fn delete_manga(
+        State(state): State<ServerState>,
+        Path(id): Path<i64>,
+    ) -> Result<Json<MangaDeleteResponse>, ApiError> {
+        blocking(move || state.service.delete(id)).await?;
+        Ok(Json(MangaDeleteResponse { id: id }))
+    }
+
+    #[utoipa::path(post, path = "/manga/search", request_body = MangaSearchRequest, responses(
+        (status = 200, body = MangaSearchResponse)
+    ))]
+    async fn search_manga(
+        State(state): State<ServerState>,
+        Json(request): Json<MangaSearchRequest>,
+    ) -> Result<Json<MangaSearchResponse>, ApiError> {
+        let data = blocking(move || state.service.search(&request)).await?;
+        Ok(Json(MangaSearchResponse { data }))
+    }
+
+    // matches either the title or the romanized title
+    #[utoipa::path(get, path = "/manga/search", params(
+        ("title" = String, Query, description = "part of the title or romanized title, case insensitive")
+    ), responses(
+        (status = 200, body = MangaSearchByTitleResponse)
+    ))]
+    async fn search_manga_by_title(
+        State(state): State<ServerState>,
+        Query(request): Query<MangaSearchByTitleRequest>,
+    ) -> Result<Json<MangaSearchByTitleResponse>, ApiError> {
+        let data = blocking(move || {
+            Ok(state
+                .service
+                .list()?
+                .into_iter()
+                .filter(|manga| {
+                    service::contains_ignore_case(Some(manga.title()), &request.title)
+                        || service::contains_ignore_case(
+                            manga.title_romanized().as_deref(),
+                            &request.title,
+                        )
+                })
+                .collect())
+        })
+        .await?;
+        Ok(Json(MangaSearchByTitleResponse { data }))
+    }
+
+    #[utoipa::path(post, path = "/manga/{id}/read", params(("id" = i64, Path, description = "manga id")),
+        request_body = MangaMarkReadRequest, responses(
+        (status = 200, body = MangaMarkReadResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 404, body = ErrorResponse)
+    ))]
+    async fn mark_read(
+        State(state): State<ServerState>,
+        Path(id): Path<i64>,
+        Json(request): Json<MangaMarkReadRequest>,
+    ) -> Result<Json<MangaMarkReadResponse>, ApiError> {
+        let manga_read =
+            blocking(move || state.service.mark_read(id, request.chapter.as_deref())).await?;
+        Ok(Json(MangaMarkReadResponse {
+            id: id,
+            chapter: manga_read.chapter().unwrap_or_default(),
+        }))
+    }
+
+    async fn dashboard_index() -> Html<&'static str> {
+        Html(include_str!("../assets/dashboard/index.html"))
+    }
+
+    async fn dashboard_script() -> impl IntoResponse {
+        (
+            [(header::CONTENT_TYPE, "text/javascript; charset=utf-8")],
+            include_str!("../assets/dashboard/app.js"),
+        )
+    }
+
+    async fn dashboard_style() -> impl IntoResponse {
+        (
+            [(header::CONTENT_TYPE, "text/css; charset=utf-8")],
+            include_str!("../assets/dashboard/style.css"),
+        )
+    }
+
+    // the resolvers read the database synchronously, so the whole query runs on a blocking thread
+    async fn graphql(
+        State(state): State<ServerState>,
+        Json(request): Json<async_graphql::Request>,
+    ) -> Result<Json<async_graphql::Response>, ApiError> {
+        let response = blocking(move || {
+            let runtime = tokio::runtime::Handle::current();
+            Ok(runtime.block_on(state.graphql_schema.execute(request)))
+        })
+        .await?;
+        Ok(Json(response))
+    }
+
+    async fn graphiql() -> Html<String> {
+        Html(
+            async_graphql::http::GraphiQLSource::build()
+                .endpoint("/graphql")
+                .finish(),
+        )
+    }
+
+    fn router(service: MangaService) -> Router {
+        Router::new()
+            .route("/manga", get(list_manga).post(create_manga))
+            .route(
+                "/manga/search",
+                get(search_manga_by_title).post(search_manga),
+            )
+            .route(
+                "/manga/:id",
+                get(get_manga)
+                    .put(replace_manga)
+                    .patch(patch_manga)
+                    .delete(delete_manga),
+            )
+            .route("/manga/:id/read", post(mark_read))
+            .route("/graphql", get(graphiql).post(graphql))
+            .route("/", get(dashboard_index))
+            .route("/assets/app.js", get(dashboard_script))
+            .route("/assets/style.css", get(dashboard_style))
+            .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", ApiDoc::openapi()))
+            .with_state(ServerState {
+                graphql_schema: graphql_api::build_schema(service.db_full_paths()),
+                service,
+            })
+    }
+
+    // same document as served at /openapi.json
+    pub fn openapi_json() -> Result<String, Box<dyn std::error::Error>> {
+        Ok(ApiDoc::openapi().to_pretty_json()?)
+    }
+
+    // blocks until the process is stopped
+    pub fn run(options: ServerOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&options.db_full_paths)?;
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind(&options.bind).await?;
+            println!(
+                "serve: listening on http://{} (database '{}')",
+                listener.local_addr()?,
+                options.db_full_paths
+            );
+            axum::serve(listener, router(service)).await?;
+            Ok(())
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_openapi_json() {
+            let openapi: serde_json::Value =
+                serde_json::from_str(&openapi_json().unwrap()).unwrap();
+            let paths = &openapi["paths"];
+            for method in ["get", "post"] {
+                assert!(paths["/manga"][method].is_object());
+                assert!(paths["/manga/search"][method].is_object());
+            }
+            for method in ["get", "put", "patch", "delete"] {
+                assert!(paths["/manga/{id}"][method].is_object());
+            }
+            let schemas = &openapi["components"]["schemas"];
+            assert!(schemas["MangaModel"].is_object());
+            assert!(schemas["MangaCreateRequest"].is_object());
+            assert!(schemas["ErrorResponse"].is_object());
+        }
+    }
+}
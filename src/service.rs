@@ -0,0 +1,1879 @@
+// The operations behind the CLI, `serve`, `serve-grpc`, `--rpc` and `tui` (import, list/search, create/update/
+// delete, mark read, export) as plain typed calls, so that a desktop front-end (Tauri commands, egui) can call
+// them directly instead of going through a server or parsing printed output:
+//      let service = MangaService::open("漫画.sqlite3")?;
+//      let unread = service.search(&MangaSearchRequest { title: Some("gate".into()), ..Default::default() })?;
+//      service.mark_read(unread[0].id(), None)?; // latest available chapter
+// Nothing here prints or exits; every failure is a ServiceError the caller decides how to show.  (The
+// database layer underneath still logs its progress on stdout, which a GUI without a console never sees.)
+pub mod service {
+    use regex::Regex;
+    use std::{
+        collections::HashMap,
+        fmt,
+        hash::Hash,
+        io::{Read, Write},
+        sync::{Arc, Mutex, MutexGuard},
+    };
+    use url::Url;
+
+    pub use crate::config::config::{RomanizationOptions, TitleCollisionPolicy};
+    pub use crate::history_export::history_export::{HistoryFormat, HistoryRow};
+    pub use crate::html_report::html_report::ReportEntry;
+    pub use crate::model_json_chrome_bookmarks::model_json_chrome_bookmarks::ChromeBookmarks;
+    pub use crate::model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::BookmarkRootFolder;
+    pub use crate::model_manga::model_manga::{
+        Chapter, MangaCreateRequest, MangaMarkReadRequest, MangaModel, MangaRequest,
+        MangaSearchRequest, MangaUpdateRequest,
+    };
+    pub use crate::model_netscape_bookmarks::model_netscape_bookmarks::{
+        BookmarkEntry, FolderGrouping, NetscapeBookmark,
+    };
+    pub use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::BookmarkOrigin;
+    pub use crate::model_sqlite3_manga::model_sqlite3_manga::PrunedGroup;
+    pub use crate::model_yaml_manga::model_yaml_manga::YamlManga;
+    pub use crate::obsidian_export::obsidian_export::VaultExportSummary;
+    pub use crate::ranked_search::ranked_search::SearchHit;
+
+    use crate::cover_cache::cover_cache;
+    use crate::error::error::Error;
+    use crate::history_export::history_export;
+    use crate::html_report::html_report;
+    use crate::model_json_mozilla_bookmarks::model_json_mozilla_bookmarks::{self, BookmarkNodes};
+    use crate::model_manga;
+    use crate::model_netscape_bookmarks::model_netscape_bookmarks;
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin;
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_fts::model_sqlite3_manga_fts;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata;
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions;
+    use crate::model_sqlite3_sync_state::model_sqlite3_sync_state;
+    use crate::model_yaml_manga::model_yaml_manga;
+    use crate::my_libs;
+    use crate::normalize_pipeline::normalize_pipeline::{Pipeline, Rejection};
+    use crate::obsidian_export::obsidian_export;
+    use crate::ranked_search::ranked_search;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ServiceError {
+        NotFound(i64),
+        AlreadyExists(i64), // id of the manga with the same title and url
+        InvalidInput(String),
+        Database(String),
+        DatabaseBusy(String), // another process kept the database locked past the busy timeout, worth retrying
+        Io(String),
+    }
+
+    impl fmt::Display for ServiceError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ServiceError::NotFound(id) => write!(f, "manga id={} not found", id),
+                ServiceError::AlreadyExists(id) => write!(f, "already exists as id={}", id),
+                ServiceError::InvalidInput(message) => write!(f, "{}", message),
+                ServiceError::Database(message) => write!(f, "database error: {}", message),
+                ServiceError::DatabaseBusy(message) => write!(
+                    f,
+                    "database is busy, another process is writing to it (try again): {}",
+                    message
+                ),
+                ServiceError::Io(message) => write!(f, "{}", message),
+            }
+        }
+    }
+
+    impl std::error::Error for ServiceError {}
+
+    impl From<rusqlite::Error> for ServiceError {
+        fn from(e: rusqlite::Error) -> Self {
+            match model_sqlite3_connection::is_busy(&e) {
+                true => ServiceError::DatabaseBusy(e.to_string()),
+                false => ServiceError::Database(e.to_string()),
+            }
+        }
+    }
+
+    impl From<Error> for ServiceError {
+        fn from(e: Error) -> Self {
+            match e {
+                Error::Sqlite(e) => ServiceError::from(e),
+                Error::Io(e) => ServiceError::from(e),
+                Error::NotFound(id) => ServiceError::NotFound(id),
+                e => ServiceError::InvalidInput(e.to_string()),
+            }
+        }
+    }
+
+    // the rest of the model_sqlite3_* helpers report with Box<dyn Error>
+    impl From<Box<dyn std::error::Error>> for ServiceError {
+        fn from(e: Box<dyn std::error::Error>) -> Self {
+            match e.downcast::<rusqlite::Error>() {
+                Ok(e) => ServiceError::from(*e),
+                Err(e) => ServiceError::Database(e.to_string()),
+            }
+        }
+    }
+
+    impl From<std::io::Error> for ServiceError {
+        fn from(e: std::io::Error) -> Self {
+            ServiceError::Io(e.to_string())
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ImportSummary {
+        pub bookmarks_count: usize,
+        pub failures: Vec<String>, // why the bookmarks could not be written
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct YamlImportSummary {
+        pub added: usize,
+        pub updated: usize,
+        pub unchanged: usize,
+        pub failures: Vec<String>, // one message per entry which could not be written
+    }
+
+    fn validated_url(url: &str) -> Result<String, String> {
+        match Url::parse(url.trim()) {
+            Ok(parsed_url) => Ok(parsed_url.to_string()),
+            Err(e) => Err(format!("invalid url '{}': {}", url, e)),
+        }
+    }
+
+    fn validated_title(title: &str) -> Result<String, String> {
+        match title.trim().is_empty() {
+            true => Err("title cannot be empty".to_string()),
+            false => Ok(title.trim().to_string()),
+        }
+    }
+
+    // title and url(s) make the manga (chapter is derived from the url, same as for bookmarks), the
+    // rest of the request is applied on top of it as an update; titles are romanized in the given style
+    pub fn manga_from_create_request(
+        request: MangaCreateRequest,
+        romanization: &RomanizationOptions,
+    ) -> Result<MangaModel, String> {
+        let title = validated_title(&request.title)?;
+        let url = validated_url(&request.url)?;
+        let url_with_possible_chapter = match &request.url_with_chapter {
+            Some(url_with_chapter) => validated_url(url_with_chapter)?,
+            None => url.clone(),
+        };
+        // same temporary (non-zero) id as bookmarks and CSV rows get, insert_manga() assigns the real one
+        let mut manga = MangaModel::new_from_required_elements(
+            &title,
+            &url_with_possible_chapter,
+            model_manga::CASTAGNOLI.checksum(url.as_bytes()),
+        )
+        .map_err(|e| e.to_string())?;
+        manga.set_url(url);
+        manga.set_title_romanized(MangaModel::romanize_title_with(manga.title(), romanization));
+        update_from_request(
+            &manga,
+            MangaUpdateRequest {
+                title: None,
+                title_romanized: request.title_romanized,
+                url: None,
+                url_with_chapter: None,
+                chapter: request.chapter,
+                last_update: request.last_update,
+                notes: request.notes,
+                tags: request.tags,
+            },
+            romanization,
+        )
+    }
+
+    // every field of the request replaces the one in manga (None clears it); title_romanized is derived
+    // from the title when not given, same as for bookmarks.  Fields the request does not carry (i.e.
+    // feed_url, my_anime_list) are kept
+    pub fn replace_from_request(
+        manga: &MangaModel,
+        request: MangaRequest,
+        romanization: &RomanizationOptions,
+    ) -> Result<MangaModel, String> {
+        let title = validated_title(&request.title)?;
+        let url = validated_url(&request.url)?;
+        let possible_url_with_chapter = match request.url_with_chapter {
+            Some(url_with_chapter) => Some(validated_url(&url_with_chapter)?),
+            None => None,
+        };
+        let mut replaced = manga.clone();
+        replaced.set_title_romanized(
+            request
+                .title_romanized
+                .or_else(|| MangaModel::romanize_title_with(&title, romanization)),
+        );
+        replaced.set_title(title);
+        replaced.set_url(url);
+        replaced.set_url_with_chapter(possible_url_with_chapter);
+        replaced.set_chapter(request.chapter);
+        replaced.set_last_update(request.last_update);
+        replaced.set_last_update_millis(None); // update_manga() stamps it
+        replaced.set_notes(request.notes);
+        replaced.set_tags(
+            request
+                .tags
+                .map(|tags| request_tags(&tags))
+                .unwrap_or_default(),
+        );
+        Ok(replaced)
+    }
+
+    // only the fields given (Some) replace the ones in manga
+    pub fn update_from_request(
+        manga: &MangaModel,
+        request: MangaUpdateRequest,
+        romanization: &RomanizationOptions,
+    ) -> Result<MangaModel, String> {
+        let mut updated = manga.clone();
+        if let Some(title) = request.title {
+            let title = validated_title(&title)?;
+            if request.title_romanized.is_none() {
+                updated.set_title_romanized(MangaModel::romanize_title_with(&title, romanization));
+            }
+            updated.set_title(title);
+        }
+        if let Some(title_romanized) = request.title_romanized {
+            updated.set_title_romanized(Some(title_romanized));
+        }
+        if let Some(url) = request.url {
+            updated.set_url(validated_url(&url)?);
+        }
+        if let Some(url_with_chapter) = request.url_with_chapter {
+            updated.set_url_with_chapter(Some(validated_url(&url_with_chapter)?));
+        }
+        if let Some(chapter) = request.chapter {
+            updated.set_chapter(Some(chapter));
+        }
+        if let Some(last_update) = request.last_update {
+            updated.set_last_update(Some(last_update));
+        }
+        if let Some(notes) = request.notes {
+            updated.set_notes(Some(notes));
+        }
+        if let Some(tags) = request.tags {
+            updated.set_tags(request_tags(&tags));
+        }
+        updated.set_last_update_millis(None); // update_manga() stamps it
+        Ok(updated)
+    }
+
+    // "seinen, isekai" => ["seinen", "isekai"]
+    fn request_tags(tags: &str) -> Vec<String> {
+        tags.split(',')
+            .map(my_libs::sanitize_string)
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    pub fn contains_ignore_case(possible_haystack: Option<&str>, needle: &str) -> bool {
+        possible_haystack
+            .map(|haystack| haystack.to_lowercase().contains(&needle.to_lowercase()))
+            .unwrap_or(false)
+    }
+
+    // every field given must match: text fields by case-insensitive substring, tags (comma separated)
+    // must all be on the manga
+    pub fn matches_search_request(manga: &MangaModel, request: &MangaSearchRequest) -> bool {
+        let text_fields = [
+            (&request.title, Some(manga.title().to_string())),
+            (&request.title_romanized, manga.title_romanized()),
+            (&request.url, Some(manga.url().to_string())),
+            (&request.url_with_chapter, manga.url_with_chapter()),
+            (&request.chapter, manga.chapter()),
+            (&request.last_update, manga.last_update()),
+            (&request.notes, manga.notes()),
+        ];
+        let text_fields_match =
+            text_fields.iter().all(
+                |(possible_needle, possible_haystack)| match possible_needle {
+                    Some(needle) => contains_ignore_case(possible_haystack.as_deref(), needle),
+                    None => true,
+                },
+            );
+        let tags_match = match &request.tags {
+            Some(tags) => request_tags(tags).iter().all(|tag| {
+                manga
+                    .tags()
+                    .iter()
+                    .any(|own_tag| own_tag.trim().eq_ignore_ascii_case(tag))
+            }),
+            None => true,
+        };
+        text_fields_match && tags_match
+    }
+
+    // same rules as `mark-read`: "12-1" (as it appears in urls) is stored as "12.1", and no chapter means
+    // the latest available one, which is only known once `check` found it
+    pub fn chapter_to_mark_read(
+        manga: &MangaModel,
+        request: &MangaMarkReadRequest,
+    ) -> Result<String, String> {
+        match &request.chapter {
+            Some(chapter) => match Chapter::parse(chapter.trim()) {
+                Some(chapter) => Ok(chapter.to_string()),
+                None => Err(format!("invalid chapter '{}'", chapter)),
+            },
+            None => manga.latest_available_chapter().ok_or_else(|| {
+                format!(
+                    "latest chapter of id={} is not known (yet), run check first",
+                    manga.id()
+                )
+            }),
+        }
+    }
+
+    // copy of manga with chapter (and url_with_chapter, when it can be derived) marked as read at given time;
+    // last_update is "%Y-%m-%dT%H:%M:%S" as the CSV files have it (see my_libs::str_to_epoch_micros())
+    pub fn as_read(
+        manga: &MangaModel,
+        chapter: &str,
+        read_at: &chrono::DateTime<chrono::Local>,
+    ) -> MangaModel {
+        let mut manga = manga.clone();
+        if let Some(url_with_chapter) = manga.url_for_chapter(chapter) {
+            manga.set_url_with_chapter(Some(url_with_chapter));
+        }
+        manga.set_chapter(Some(chapter.to_string()));
+        manga.set_last_update(Some(read_at.format("%Y-%m-%dT%H:%M:%S").to_string()));
+        manga.set_last_update_millis(Some(read_at.timestamp_millis()));
+        manga
+    }
+
+    // every bookmark (not containers nor separators) of the Firefox "Backup..." JSON, sorted by url, less those
+    // the pipeline skipped or rejected
+    pub fn bookmarks_to_manga(
+        bookmark_folders: &BookmarkRootFolder,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        fn traverse_children(children: &Vec<BookmarkNodes>, bookmarks: &mut Vec<BookmarkNodes>) {
+            for child in children {
+                if child.is_bookmark() {
+                    bookmarks.push(child.clone());
+                } else if let Some(children) = &child.possible_children() {
+                    traverse_children(children, bookmarks);
+                }
+                // else, it's a separator, so we'll ignore it...
+            }
+        }
+        let mut bookmarks = Vec::new();
+        traverse_children(bookmark_folders.children(), &mut bookmarks);
+
+        let mut mangas = Vec::new();
+        for bookmark in bookmarks {
+            if let Some(manga) = bookmark_to_manga(
+                pipeline,
+                bookmark.title(),
+                bookmark.uri().as_str(),
+                *bookmark.last_modified(),
+                Some(bookmark.guid().as_str()),
+            )? {
+                mangas.push(manga);
+            }
+        }
+        mangas.sort_by(|a, b| a.url().cmp(b.url()));
+        Ok(mangas)
+    }
+
+    // where each bookmark of bookmarks_to_manga() is: its folder, i.e. "toolbar/Manga", and when it was added;
+    // manga_id is left 0 for upsert_bookmark_origins() to match
+    pub fn bookmark_origins(
+        bookmark_folders: &BookmarkRootFolder,
+        profile: &str,
+    ) -> Vec<BookmarkOrigin> {
+        fn traverse_children(
+            children: &Vec<BookmarkNodes>,
+            folder_path: &str,
+            profile: &str,
+            origins: &mut Vec<BookmarkOrigin>,
+        ) {
+            for child in children {
+                if child.is_bookmark() {
+                    origins.push(bookmark_origin(child, folder_path, profile));
+                } else if let Some(children) = &child.possible_children() {
+                    let child_path = match folder_path.is_empty() {
+                        true => child.title().clone(),
+                        false => format!("{}/{}", folder_path, child.title()),
+                    };
+                    traverse_children(children, &child_path, profile, origins);
+                }
+            }
+        }
+        let mut origins = Vec::new();
+        traverse_children(bookmark_folders.children(), "", profile, &mut origins);
+        origins
+    }
+
+    // folders the browsers put every bookmark under, which say nothing about the manga
+    const K_ROOT_FOLDERS: [&str; 8] = [
+        "toolbar",
+        "menu",
+        "unfiled",
+        "mobile",
+        "Bookmarks bar",
+        "Bookmarks Toolbar",
+        "Other bookmarks",
+        "Mobile bookmarks",
+    ];
+
+    // "toolbar/Manga/連載中" => ["Manga", "連載中"]: each folder a bookmark is in, less the root of the browser
+    // (see K_ROOT_FOLDERS); a ',' in a folder name would split the tag, so it becomes '，'
+    pub fn folder_tags(folder_path: &str) -> Vec<String> {
+        folder_path
+            .split('/')
+            .enumerate()
+            .filter(|(i, folder)| {
+                *i > 0
+                    || !K_ROOT_FOLDERS
+                        .iter()
+                        .any(|root| root.eq_ignore_ascii_case(folder.trim()))
+            })
+            .map(|(_, folder)| folder.trim().replace(',', "，"))
+            .filter(|folder| !folder.is_empty())
+            .collect()
+    }
+
+    // adds the folder_tags() of the bookmark(s) of each manga to its tags, matched by the GUID of the bookmark;
+    // manga without a GUID (i.e. of a bookmarks.html) are left as they are
+    pub fn tag_with_folders(mangas: &mut [MangaModel], origins: &[BookmarkOrigin]) {
+        let mut folder_tags_by_guid: HashMap<&str, Vec<String>> = HashMap::new();
+        for origin in origins {
+            let tags = folder_tags_by_guid.entry(origin.guid.as_str()).or_default();
+            for tag in folder_tags(&origin.folder_path) {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        for manga in mangas.iter_mut() {
+            let possible_folder_tags = manga
+                .bookmark_guid()
+                .and_then(|guid| folder_tags_by_guid.get(guid.as_str()));
+            if let Some(folder_tags) = possible_folder_tags {
+                let mut tags: Vec<String> =
+                    manga.tags().iter().map(|tag| tag.to_string()).collect();
+                for tag in folder_tags {
+                    if !tags
+                        .iter()
+                        .any(|own_tag| own_tag.trim().eq_ignore_ascii_case(tag))
+                    {
+                        tags.push(tag.clone());
+                    }
+                }
+                manga.set_tags(tags);
+            }
+        }
+    }
+
+    // which bookmarks an import keeps (`--include-folder`, `--exclude-folder`, `--url-filter`), the default keeps all
+    // of them.  A folder is a name or a path ("Manga", "toolbar/Manga"), matched ignoring case against whole folders
+    // anywhere in the folder path of a bookmark, so that the folders below it are in it as well
+    #[derive(Debug, Clone, Default)]
+    pub struct BookmarkFilter {
+        pub include_folders: Vec<String>, // when any, only the bookmarks in one of them
+        pub exclude_folders: Vec<String>,
+        pub url_patterns: Vec<Regex>, // when any, only the bookmarks whose url matches one of them
+    }
+
+    impl BookmarkFilter {
+        // pattern is a regex ("mangadex\.org/title/"), found anywhere in the url, or else a glob of the whole
+        // url ("*://mangadex.org/*"): one with a '*' but none of the characters which only a regex has
+        pub fn add_url_pattern(&mut self, pattern: &str) -> Result<(), String> {
+            let is_glob = pattern.contains('*')
+                && !pattern.contains(".*")
+                && !pattern.contains(|c| "^$()[]{}|+\\".contains(c));
+            let regex = match is_glob {
+                true => format!(
+                    "^{}$",
+                    pattern
+                        .chars()
+                        .map(|c| match c {
+                            '*' => ".*".to_string(),
+                            '?' => ".".to_string(),
+                            c => regex::escape(&c.to_string()),
+                        })
+                        .collect::<String>()
+                ),
+                false => pattern.to_string(),
+            };
+            let url_pattern = Regex::new(&regex)
+                .map_err(|e| format!("invalid url filter '{}': {}", pattern, e))?;
+            self.url_patterns.push(url_pattern);
+            Ok(())
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.include_folders.is_empty()
+                && self.exclude_folders.is_empty()
+                && self.url_patterns.is_empty()
+        }
+
+        // possible_folder_path is None when where the bookmark is filed is not known: only the url patterns apply
+        pub fn keeps(&self, possible_folder_path: Option<&str>, url: &str) -> bool {
+            let url_matches = self.url_patterns.is_empty()
+                || self
+                    .url_patterns
+                    .iter()
+                    .any(|url_pattern| url_pattern.is_match(url));
+            let folders_match = match possible_folder_path {
+                Some(folder_path) => {
+                    (self.include_folders.is_empty()
+                        || self
+                            .include_folders
+                            .iter()
+                            .any(|folder| is_in_folder(folder_path, folder)))
+                        && !self
+                            .exclude_folders
+                            .iter()
+                            .any(|folder| is_in_folder(folder_path, folder))
+                }
+                None => true,
+            };
+            url_matches && folders_match
+        }
+    }
+
+    fn is_in_folder(folder_path: &str, folder: &str) -> bool {
+        let path: Vec<String> = folder_path
+            .split('/')
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        let folder: Vec<String> = folder
+            .trim_matches('/')
+            .split('/')
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        path.windows(folder.len())
+            .any(|names| names == folder.as_slice())
+    }
+
+    // drops the manga of the bookmarks filter does not keep, and their origins; the folder of a manga is that of
+    // the origin of its bookmark (by GUID), manga without one only go through the url patterns.  Returns how many
+    // were dropped
+    pub fn retain_bookmarks(
+        filter: &BookmarkFilter,
+        mangas: &mut Vec<MangaModel>,
+        origins: &mut Vec<BookmarkOrigin>,
+    ) -> usize {
+        if filter.is_empty() {
+            return 0;
+        }
+        let folder_by_guid: HashMap<&str, &str> = origins
+            .iter()
+            .map(|origin| (origin.guid.as_str(), origin.folder_path.as_str()))
+            .collect();
+        let mangas_count = mangas.len();
+        mangas.retain(|manga| {
+            let possible_folder_path = manga
+                .bookmark_guid()
+                .and_then(|guid| folder_by_guid.get(guid.as_str()).copied());
+            filter.keeps(
+                possible_folder_path,
+                manga.url_with_chapter().as_deref().unwrap_or(manga.url()),
+            )
+        });
+        let kept_guids: Vec<String> = mangas
+            .iter()
+            .filter_map(|manga| manga.bookmark_guid())
+            .collect();
+        origins.retain(|origin| kept_guids.contains(&origin.guid));
+        mangas_count - mangas.len()
+    }
+
+    fn bookmark_origin(
+        bookmark: &BookmarkNodes,
+        folder_path: &str,
+        profile: &str,
+    ) -> BookmarkOrigin {
+        BookmarkOrigin {
+            id: 0,
+            manga_id: 0,
+            profile: profile.to_string(),
+            folder_path: folder_path.to_string(),
+            guid: bookmark.guid().clone(),
+            date_added_millis: *bookmark.date_added() / 1000,
+        }
+    }
+
+    // bookmarks_to_manga() and bookmark_origins() of a Firefox JSON too large to read whole: the bookmarks are
+    // handed to on_batch batch_size at a time as they are read (in the order of the JSON, not sorted), with the
+    // origins of the same bookmarks.  Returns how many bookmarks became manga
+    pub fn stream_bookmarks_to_manga<R, F>(
+        bookmarks_reader: R,
+        pipeline: &Pipeline,
+        profile: &str,
+        batch_size: usize,
+        on_batch: &mut F,
+    ) -> Result<usize, Box<dyn std::error::Error>>
+    where
+        R: Read,
+        F: FnMut(Vec<MangaModel>, Vec<BookmarkOrigin>) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        let mut mangas = Vec::new();
+        let mut origins = Vec::new();
+        let mut mangas_count = 0;
+        model_json_mozilla_bookmarks::for_each_bookmark(
+            bookmarks_reader,
+            &mut |bookmark, folder_path| {
+                origins.push(bookmark_origin(&bookmark, folder_path, profile));
+                if let Some(manga) = bookmark_to_manga(
+                    pipeline,
+                    bookmark.title(),
+                    bookmark.uri().as_str(),
+                    *bookmark.last_modified(),
+                    Some(bookmark.guid().as_str()),
+                )? {
+                    mangas.push(manga);
+                    mangas_count += 1;
+                }
+                if mangas.len() >= batch_size {
+                    on_batch(std::mem::take(&mut mangas), std::mem::take(&mut origins))?;
+                }
+                Ok(())
+            },
+        )?;
+        if !mangas.is_empty() {
+            on_batch(mangas, origins)?;
+        }
+        Ok(mangas_count)
+    }
+
+    // same as bookmarks_to_manga(), for the `Bookmarks` file of Chrome/Chromium (`--format chrome`)
+    pub fn chrome_bookmarks_to_manga(
+        chrome_bookmarks: &ChromeBookmarks,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        let mut mangas = Vec::new();
+        for (_folder_path, bookmark) in chrome_bookmarks.bookmarks() {
+            if let Some(manga) = bookmark_to_manga(
+                pipeline,
+                bookmark.name(),
+                bookmark.url().as_str(),
+                bookmark.last_modified_micros(),
+                Some(bookmark.guid().as_str()),
+            )? {
+                mangas.push(manga);
+            }
+        }
+        mangas.sort_by(|a, b| a.url().cmp(b.url()));
+        Ok(mangas)
+    }
+
+    // same as bookmark_origins(); folders start with the root they are in, i.e. "Bookmarks bar/Manga"
+    pub fn chrome_bookmark_origins(
+        chrome_bookmarks: &ChromeBookmarks,
+        profile: &str,
+    ) -> Vec<BookmarkOrigin> {
+        chrome_bookmarks
+            .bookmarks()
+            .into_iter()
+            .map(|(folder_path, bookmark)| BookmarkOrigin {
+                id: 0,
+                manga_id: 0,
+                profile: profile.to_string(),
+                folder_path,
+                guid: bookmark.guid().clone(),
+                date_added_millis: bookmark.date_added_micros() / 1000,
+            })
+            .collect()
+    }
+
+    // same as bookmarks_to_manga(), for a Netscape bookmarks.html (`--format html`); its TAGS become the tags of
+    // the manga.  There are no GUIDs in it, so no bookmark origins either
+    pub fn netscape_bookmarks_to_manga(
+        bookmarks: &[NetscapeBookmark],
+        pipeline: &Pipeline,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        let mut mangas = Vec::new();
+        for bookmark in bookmarks {
+            if let Some(mut manga) = bookmark_to_manga(
+                pipeline,
+                &bookmark.title,
+                &bookmark.href,
+                bookmark.last_modified_micros(),
+                None,
+            )? {
+                if !bookmark.tags.is_empty() {
+                    manga.set_tags(bookmark.tags.clone());
+                }
+                mangas.push(manga);
+            }
+        }
+        mangas.sort_by(|a, b| a.url().cmp(b.url()));
+        Ok(mangas)
+    }
+
+    // same for every source of Firefox bookmarks (backup JSON, places.sqlite): title and url go through the
+    // normalization pipeline, last_modified is unix epoch time in microseconds, the id is a checksum of the url
+    // until the database assigns one, and the GUID of the bookmark is kept so that the row can be found again
+    // after the bookmark is renamed or moved; None when the pipeline skipped it (i.e. "about:", "place:") or
+    // rejected it (see Pipeline::take_skipped() and take_rejections())
+    pub fn bookmark_to_manga(
+        pipeline: &Pipeline,
+        title: &str,
+        uri: &str,
+        last_modified_micros: i64,
+        possible_guid: Option<&str>,
+    ) -> Result<Option<MangaModel>, Box<dyn std::error::Error>> {
+        if pipeline.skips(uri) {
+            return Ok(None);
+        }
+        let mut manga =
+            match pipeline.to_manga(title, uri, model_manga::CASTAGNOLI.checksum(uri.as_bytes())) {
+                Err(e) if e.is::<Rejection>() => return Ok(None),
+                result => result?,
+            };
+        manga.set_last_update(Some(my_libs::from_epoch_to_str(last_modified_micros)));
+        manga.set_bookmark_guid(possible_guid.map(|guid| guid.to_string()));
+        Ok(Some(manga))
+    }
+
+    const K_READ_CACHE_CAPACITY: usize = 256; // rows, per kind of lookup
+    pub const K_STREAM_BATCH_SIZE: usize = 500; // bookmarks per transaction of import_bookmarks_streaming()
+
+    // least recently used entry goes first when full; capacity is small enough that a scan to evict is fine
+    #[derive(Debug)]
+    struct LruCache<K, V> {
+        capacity: usize,
+        tick: u64,
+        entries: HashMap<K, (V, u64)>, // value, tick of last use
+    }
+
+    impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+        fn new(capacity: usize) -> LruCache<K, V> {
+            LruCache {
+                capacity,
+                tick: 0,
+                entries: HashMap::new(),
+            }
+        }
+
+        fn get(&mut self, key: &K) -> Option<V> {
+            self.tick += 1;
+            let tick = self.tick;
+            self.entries.get_mut(key).map(|(value, last_used)| {
+                *last_used = tick;
+                value.clone()
+            })
+        }
+
+        fn put(&mut self, key: K, value: V) {
+            if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+                let possible_oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (_, last_used))| *last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = possible_oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.tick += 1;
+            self.entries.insert(key, (value, self.tick));
+        }
+
+        fn clear(&mut self) {
+            self.entries.clear();
+        }
+    }
+
+    // rows read by get()/find(), valid for as long as the change counter of the database (bumped by triggers on
+    // every write, whichever process makes it, see model_sqlite3_sync_state) stays the same
+    #[derive(Debug)]
+    struct ReadCache {
+        changes: Option<u64>,
+        by_id: LruCache<i64, MangaModel>,
+        ids_by_title_and_url: LruCache<(String, String), i64>,
+    }
+
+    impl ReadCache {
+        fn new() -> ReadCache {
+            ReadCache {
+                changes: None,
+                by_id: LruCache::new(K_READ_CACHE_CAPACITY),
+                ids_by_title_and_url: LruCache::new(K_READ_CACHE_CAPACITY),
+            }
+        }
+    }
+
+    // cheap to clone: it only holds where the database is (each call opens its own connection) and the read
+    // cache, which clones share
+    #[derive(Debug, Clone)]
+    pub struct MangaService {
+        db_full_paths: String,
+        read_cache: Arc<Mutex<ReadCache>>,
+        title_collision: TitleCollisionPolicy,
+        romanization: RomanizationOptions,
+        folder_tags: bool,
+        bookmark_filter: BookmarkFilter,
+    }
+
+    impl MangaService {
+        // creates the tables when the database is new
+        pub fn open(db_full_paths: &str) -> Result<MangaService, ServiceError> {
+            model_sqlite3_manga::create_tables(db_full_paths)?;
+            Ok(MangaService {
+                db_full_paths: db_full_paths.to_string(),
+                read_cache: Arc::new(Mutex::new(ReadCache::new())),
+                title_collision: TitleCollisionPolicy::default(),
+                romanization: RomanizationOptions::default(),
+                folder_tags: false,
+                bookmark_filter: BookmarkFilter::default(),
+            })
+        }
+
+        // what upsert() and imports do to the row of a url given under another title; nothing here can ask, so
+        // Ask keeps both as KeepBoth does
+        pub fn with_title_collision(
+            mut self,
+            title_collision: TitleCollisionPolicy,
+        ) -> MangaService {
+            self.title_collision = title_collision;
+            self
+        }
+
+        // style of the titles romanized by create/upsert/replace/update and imports
+        pub fn with_romanization(mut self, romanization: RomanizationOptions) -> MangaService {
+            self.romanization = romanization;
+            self
+        }
+
+        // which bookmarks imports keep (see BookmarkFilter)
+        pub fn with_bookmark_filter(mut self, bookmark_filter: BookmarkFilter) -> MangaService {
+            self.bookmark_filter = bookmark_filter;
+            self
+        }
+
+        // whether imports tag each manga with the folders of its bookmark (see tag_with_folders())
+        pub fn with_folder_tags(mut self, folder_tags: bool) -> MangaService {
+            self.folder_tags = folder_tags;
+            self
+        }
+
+        fn renames_on_title_collision(&self) -> bool {
+            self.title_collision == TitleCollisionPolicy::UpdateTitle
+        }
+
+        pub fn db_full_paths(&self) -> &str {
+            &self.db_full_paths
+        }
+
+        // an empty database is an empty list, not an error
+        pub fn list(&self) -> Result<Vec<MangaModel>, ServiceError> {
+            match model_sqlite3_manga::select_all_manga(&self.db_full_paths) {
+                Ok(mangas) => Ok(mangas),
+                Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Ok(Vec::new()),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        pub fn get(&self, id: i64) -> Result<MangaModel, ServiceError> {
+            let (changes, possible_cached) = {
+                let mut read_cache = self.read_cache()?;
+                (read_cache.changes, read_cache.by_id.get(&id))
+            };
+            if let Some(manga) = possible_cached {
+                return Ok(manga);
+            }
+            match model_sqlite3_manga::select_manga_by_id(&self.db_full_paths, id) {
+                Ok(manga) => {
+                    self.remember(changes, &manga);
+                    Ok(manga)
+                }
+                Err(Error::NotFound(_)) => Err(ServiceError::NotFound(id)),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        // by exact title and url (the unique key), None when there is no such manga
+        pub fn find(&self, title: &str, url: &str) -> Result<Option<MangaModel>, ServiceError> {
+            let key = (title.to_string(), url.to_string());
+            let (changes, possible_cached_id) = {
+                let mut read_cache = self.read_cache()?;
+                (
+                    read_cache.changes,
+                    read_cache.ids_by_title_and_url.get(&key),
+                )
+            };
+            if let Some(id) = possible_cached_id {
+                return self.get(id).map(Some);
+            }
+            match model_sqlite3_manga::get_id(&self.db_full_paths, title, url) {
+                Ok(manga) => {
+                    self.remember(changes, &manga);
+                    Ok(Some(manga))
+                }
+                Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        pub fn search(
+            &self,
+            request: &MangaSearchRequest,
+        ) -> Result<Vec<MangaModel>, ServiceError> {
+            // a tag expression is filtered by the database, the rest of the request on what it returns
+            let candidates = match &request.tag_expr {
+                Some(expression) => {
+                    model_sqlite3_manga::select_by_tag_expr(&self.db_full_paths, expression)?
+                }
+                None => self.list()?,
+            };
+            Ok(candidates
+                .into_iter()
+                .filter(|manga| matches_search_request(manga, request))
+                .collect())
+        }
+
+        // by (part of, or slightly misspelled) title, best match first; see ranked_search
+        pub fn ranked_search(
+            &self,
+            query: &str,
+            limit: usize,
+        ) -> Result<Vec<SearchHit>, ServiceError> {
+            if query.trim().is_empty() {
+                return Err(ServiceError::InvalidInput(
+                    "nothing to search for".to_string(),
+                ));
+            }
+            let fts_ids = model_sqlite3_manga_fts::select_fts_matches(&self.db_full_paths, query)?;
+            Ok(ranked_search::rank(&self.list()?, &fts_ids, query, limit))
+        }
+
+        pub fn create(&self, request: MangaCreateRequest) -> Result<MangaModel, ServiceError> {
+            let manga_no_id = manga_from_create_request(request, &self.romanization)
+                .map_err(ServiceError::InvalidInput)?;
+            // insert_manga() is INSERT OR IGNORE, so tell apart a duplicate (title, url) rather than
+            // silently handing back the existing row
+            if let Ok(Some(existing)) = self.find(manga_no_id.title(), manga_no_id.url()) {
+                return Err(ServiceError::AlreadyExists(existing.id()));
+            }
+            Ok(model_sqlite3_manga::insert_manga(
+                &self.db_full_paths,
+                &manga_no_id,
+            )?)
+        }
+
+        // creates, or updates the manga with the same title and url (or only the same url, see
+        // with_title_collision())
+        pub fn upsert(&self, request: MangaCreateRequest) -> Result<MangaModel, ServiceError> {
+            let tags_given = request.tags.is_some();
+            let manga_no_id = manga_from_create_request(request, &self.romanization)
+                .map_err(ServiceError::InvalidInput)?;
+            let renames = self.renames_on_title_collision();
+            let upserted = model_sqlite3_manga::upsert_manga_with(
+                &self.db_full_paths,
+                &manga_no_id,
+                &mut |_, _, _| renames,
+            )?;
+            // upsert_manga() only attaches tags when it inserts
+            if tags_given {
+                self.save_tags(upserted.id(), &manga_no_id)?;
+            }
+            Ok(upserted)
+        }
+
+        pub fn replace(&self, id: i64, request: MangaRequest) -> Result<MangaModel, ServiceError> {
+            let manga = self.get(id)?;
+            let replaced = replace_from_request(&manga, request, &self.romanization)
+                .map_err(ServiceError::InvalidInput)?;
+            self.save(&replaced, true)?;
+            Ok(replaced)
+        }
+
+        pub fn update(
+            &self,
+            id: i64,
+            request: MangaUpdateRequest,
+        ) -> Result<MangaModel, ServiceError> {
+            let manga = self.get(id)?;
+            let tags_given = request.tags.is_some();
+            let updated = update_from_request(&manga, request, &self.romanization)
+                .map_err(ServiceError::InvalidInput)?;
+            self.save(&updated, tags_given)?;
+            Ok(updated)
+        }
+
+        pub fn delete(&self, id: i64) -> Result<(), ServiceError> {
+            match model_sqlite3_manga::delete_manga(&self.db_full_paths, id)? {
+                true => Ok(()),
+                false => Err(ServiceError::NotFound(id)),
+            }
+        }
+
+        // keeps the tags it already has; returns number of tags newly attached
+        pub fn add_tags(&self, id: i64, tags: &[String]) -> Result<usize, ServiceError> {
+            Ok(model_sqlite3_manga::add_tags_to_manga(
+                &self.db_full_paths,
+                id,
+                tags,
+            )?)
+        }
+
+        // each tag with the number of manga it is on, most used first, unused ones with 0
+        pub fn tag_counts(&self) -> Result<Vec<(String, usize)>, ServiceError> {
+            Ok(model_sqlite3_manga::tag_counts(&self.db_full_paths)?)
+        }
+
+        // returns the number of manga retagged; new_tag must not be a tag yet, see merge_tags()
+        pub fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize, ServiceError> {
+            let renamed = model_sqlite3_manga::rename_tag(&self.db_full_paths, old_tag, new_tag)?;
+            renamed.ok_or_else(|| ServiceError::InvalidInput(format!("no tag '{}'", old_tag)))
+        }
+
+        // the manga of from_tag get into_tag instead, and from_tag is gone; returns the number of manga retagged
+        pub fn merge_tags(&self, from_tag: &str, into_tag: &str) -> Result<usize, ServiceError> {
+            let merged = model_sqlite3_manga::merge_tags(&self.db_full_paths, from_tag, into_tag)?;
+            merged.ok_or_else(|| ServiceError::InvalidInput(format!("no tag '{}'", from_tag)))
+        }
+
+        // returns the tags deleted, those on no manga
+        pub fn delete_orphaned_tags(&self) -> Result<Vec<String>, ServiceError> {
+            Ok(model_sqlite3_manga::delete_orphaned_tags(
+                &self.db_full_paths,
+            )?)
+        }
+
+        // one of a group of duplicate_groups() kept, taking over the tags of the others, which are deleted; the rest
+        // of their fields are dropped
+        pub fn merge_duplicates(
+            &self,
+            keeper_id: i64,
+            others: &[MangaModel],
+        ) -> Result<(), ServiceError> {
+            let other_tags: Vec<String> = others
+                .iter()
+                .flat_map(|manga| manga.tags())
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            self.add_tags(keeper_id, &other_tags)?;
+            for other in others.iter().filter(|manga| manga.id() != keeper_id) {
+                self.delete(other.id())?;
+            }
+            Ok(())
+        }
+
+        // the Japanese titled row of each group kept, taking over the chapter, notes and tags of its romaji or
+        // English look-alikes, which are deleted; see model_sqlite3_manga::prune_duplicates()
+        pub fn prune_duplicates(&self, dry_run: bool) -> Result<Vec<PrunedGroup>, ServiceError> {
+            Ok(model_sqlite3_manga::prune_duplicates(
+                &self.db_full_paths,
+                dry_run,
+            )?)
+        }
+
+        // logged as a reading session, same as `mark-read`; None marks the latest available chapter
+        pub fn mark_read(
+            &self,
+            id: i64,
+            possible_chapter: Option<&str>,
+        ) -> Result<MangaModel, ServiceError> {
+            let manga = self.get(id)?;
+            let chapter = chapter_to_mark_read(
+                &manga,
+                &MangaMarkReadRequest {
+                    chapter: possible_chapter.map(|chapter| chapter.to_string()),
+                },
+            )
+            .map_err(ServiceError::InvalidInput)?;
+            let manga_read = as_read(&manga, &chapter, &chrono::Local::now());
+            model_sqlite3_manga::update_read_chapters(
+                &self.db_full_paths,
+                std::slice::from_ref(&manga_read),
+            )?;
+            Ok(manga_read)
+        }
+
+        // bookmarks already in the database (same title and normalized url) are left as they are
+        pub fn import_bookmarks<R: Read>(
+            &self,
+            bookmarks_reader: R,
+        ) -> Result<ImportSummary, ServiceError> {
+            self.import_bookmarks_with(bookmarks_reader, |_| ())
+        }
+
+        // same, prepare is given the bookmarks before they are written (i.e. to clean up their titles)
+        pub fn import_bookmarks_with<R: Read, F: FnOnce(&mut [MangaModel])>(
+            &self,
+            bookmarks_reader: R,
+            prepare: F,
+        ) -> Result<ImportSummary, ServiceError> {
+            let bookmark_folders: BookmarkRootFolder = serde_json::from_reader(bookmarks_reader)
+                .map_err(|e| {
+                    ServiceError::InvalidInput(format!("invalid bookmarks JSON: {}", e))
+                })?;
+            let mut pipeline = Pipeline::new(None, &[], Vec::new());
+            pipeline.romanization = self.romanization.clone();
+            let mut mangas = bookmarks_to_manga(&bookmark_folders, &pipeline)
+                .map_err(|e| ServiceError::InvalidInput(e.to_string()))?;
+            let mut origins = bookmark_origins(&bookmark_folders, "");
+            retain_bookmarks(&self.bookmark_filter, &mut mangas, &mut origins);
+            if self.folder_tags {
+                tag_with_folders(&mut mangas, &origins);
+            }
+            prepare(&mut mangas);
+            let mut conn = model_sqlite3_connection::open(&self.db_full_paths)?;
+            // single transaction, so either all of them are written or none
+            let renames = self.renames_on_title_collision();
+            let failures = match model_sqlite3_manga::bulk_insert_manga_with(
+                &mut conn,
+                &mangas,
+                &mut |_, _, _| renames,
+            ) {
+                Ok(written) => match model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+                    &mut conn,
+                    &origins,
+                    &written.mangas,
+                ) {
+                    Ok(_) => Vec::new(),
+                    Err(e) => vec![format!("bookmark origins not written: {}", e)],
+                },
+                Err(e) => vec![format!("{} bookmarks not written: {}", mangas.len(), e)],
+            };
+            Ok(ImportSummary {
+                bookmarks_count: mangas.len(),
+                failures,
+            })
+        }
+
+        // same as import_bookmarks(), for an export too large to read whole: bookmarks are written as they are
+        // read, K_STREAM_BATCH_SIZE at a time, each batch in a transaction of its own
+        pub fn import_bookmarks_streaming<R: Read>(
+            &self,
+            bookmarks_reader: R,
+        ) -> Result<ImportSummary, ServiceError> {
+            let mut pipeline = Pipeline::new(None, &[], Vec::new());
+            pipeline.romanization = self.romanization.clone();
+            let mut conn = model_sqlite3_connection::open(&self.db_full_paths)?;
+            let renames = self.renames_on_title_collision();
+            let mut failures = Vec::new();
+            let bookmarks_count = stream_bookmarks_to_manga(
+                bookmarks_reader,
+                &pipeline,
+                "",
+                K_STREAM_BATCH_SIZE,
+                &mut |mut mangas, mut origins| {
+                    retain_bookmarks(&self.bookmark_filter, &mut mangas, &mut origins);
+                    if self.folder_tags {
+                        tag_with_folders(&mut mangas, &origins);
+                    }
+                    match model_sqlite3_manga::bulk_insert_manga_with(
+                        &mut conn,
+                        &mangas,
+                        &mut |_, _, _| renames,
+                    ) {
+                        Ok(written) => {
+                            if let Err(e) = model_sqlite3_bookmark_origin::upsert_bookmark_origins(
+                                &mut conn,
+                                &origins,
+                                &written.mangas,
+                            ) {
+                                failures.push(format!("bookmark origins not written: {}", e));
+                            }
+                        }
+                        Err(e) => {
+                            failures.push(format!("{} bookmarks not written: {}", mangas.len(), e))
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .map_err(|e| ServiceError::InvalidInput(format!("invalid bookmarks JSON: {}", e)))?;
+            Ok(ImportSummary {
+                bookmarks_count,
+                failures,
+            })
+        }
+
+        // reading history since since_millis, oldest first
+        pub fn history(&self, since_millis: i64) -> Result<Vec<HistoryRow>, ServiceError> {
+            let titles = self
+                .list()?
+                .iter()
+                .map(|manga| (manga.id(), manga.title().to_string()))
+                .collect();
+            let sessions = model_sqlite3_reading_sessions::select_reading_sessions(
+                &self.db_full_paths,
+                since_millis,
+                i64::MAX,
+            )?;
+            Ok(history_export::to_history_rows(&sessions, &titles))
+        }
+
+        // returns number of rows written
+        pub fn export_history(
+            &self,
+            output_writer: &mut dyn Write,
+            format: HistoryFormat,
+            since_millis: i64,
+        ) -> Result<usize, ServiceError> {
+            let rows = self.history(since_millis)?;
+            history_export::write_history(output_writer, format, &rows)
+                .map_err(|e| ServiceError::Io(e.to_string()))?;
+            Ok(rows.len())
+        }
+
+        // with covers from the cover cache embedded (data: urls) when a cache directory is given
+        pub fn report_entries(
+            &self,
+            possible_cover_cache_dir: Option<&str>,
+        ) -> Result<Vec<ReportEntry>, ServiceError> {
+            let mut entries = Vec::new();
+            for manga in self.list()?.iter() {
+                let possible_cover = match possible_cover_cache_dir {
+                    Some(cache_dir) => model_sqlite3_manga_metadata::select_manga_metadata(
+                        &self.db_full_paths,
+                        manga.id(),
+                    )?
+                    .and_then(|metadata| metadata.cover_url)
+                    .and_then(|cover_url| cover_cache::cached_cover_path(cache_dir, &cover_url))
+                    .and_then(|cover_path| html_report::cover_data_url(&cover_path)),
+                    None => None,
+                };
+                entries.push(html_report::to_report_entry(manga, possible_cover));
+            }
+            Ok(entries)
+        }
+
+        // returns number of entries written
+        pub fn export_yaml(&self, output_writer: &mut dyn Write) -> Result<usize, ServiceError> {
+            let mangas = self.list()?;
+            let yaml =
+                model_yaml_manga::to_yaml(&mangas).map_err(|e| ServiceError::Io(e.to_string()))?;
+            output_writer.write_all(yaml.as_bytes())?;
+            output_writer.flush()?;
+            Ok(mangas.len())
+        }
+
+        // see model_yaml_manga for what happens to each entry; a file which is not valid YAML fails as a
+        // whole before anything is written, an invalid entry only fails itself
+        pub fn import_yaml<R: Read>(
+            &self,
+            mut yaml_reader: R,
+        ) -> Result<YamlImportSummary, ServiceError> {
+            let mut yaml = String::new();
+            yaml_reader.read_to_string(&mut yaml)?;
+            let entries = model_yaml_manga::from_yaml(&yaml)
+                .map_err(|e| ServiceError::InvalidInput(format!("invalid YAML: {}", e)))?;
+            let mut summary = YamlImportSummary::default();
+            for entry in entries.iter() {
+                if let Err(e) = self.import_yaml_entry(entry, &mut summary) {
+                    summary
+                        .failures
+                        .push(format!("'{}' ({}): {}", entry.title, entry.url, e));
+                }
+            }
+            Ok(summary)
+        }
+
+        fn import_yaml_entry(
+            &self,
+            entry: &YamlManga,
+            summary: &mut YamlImportSummary,
+        ) -> Result<(), ServiceError> {
+            let possible_existing = match entry.id {
+                Some(id) => match self.get(id) {
+                    Ok(existing) => Some(existing),
+                    Err(ServiceError::NotFound(_)) => None,
+                    Err(e) => return Err(e),
+                },
+                None => None,
+            };
+            // otherwise the same title and url is the same manga
+            let possible_existing = match possible_existing {
+                Some(existing) => Some(existing),
+                None => {
+                    let manga_no_id =
+                        manga_from_create_request(entry.to_create_request(), &self.romanization)
+                            .map_err(ServiceError::InvalidInput)?;
+                    model_sqlite3_manga::get_id(
+                        &self.db_full_paths,
+                        manga_no_id.title(),
+                        manga_no_id.url(),
+                    )
+                    .ok()
+                }
+            };
+            match possible_existing {
+                Some(existing) => {
+                    // compared as it would be saved (i.e. romanized title derived when not given)
+                    let replaced =
+                        replace_from_request(&existing, entry.to_request(), &self.romanization)
+                            .map_err(ServiceError::InvalidInput)?;
+                    match model_yaml_manga::to_yaml_manga(&existing)
+                        .same_content(&model_yaml_manga::to_yaml_manga(&replaced))
+                    {
+                        true => summary.unchanged += 1,
+                        false => {
+                            self.save(&replaced, true)?;
+                            summary.updated += 1;
+                        }
+                    }
+                }
+                None => {
+                    // stored as written, create() would otherwise derive a chapter from the url
+                    let created = self.create(entry.to_create_request())?;
+                    let replaced =
+                        replace_from_request(&created, entry.to_request(), &self.romanization)
+                            .map_err(ServiceError::InvalidInput)?;
+                    self.save(&replaced, true)?;
+                    summary.added += 1;
+                }
+            }
+            Ok(())
+        }
+
+        // every manga with the browser bookmark folder it goes in (see model_netscape_bookmarks)
+        pub fn bookmark_entries(
+            &self,
+            grouping: FolderGrouping,
+        ) -> Result<Vec<BookmarkEntry>, ServiceError> {
+            let mut entries = Vec::new();
+            for manga in self.list()?.iter() {
+                let possible_status = match grouping {
+                    FolderGrouping::Status => model_sqlite3_manga_metadata::select_manga_metadata(
+                        &self.db_full_paths,
+                        manga.id(),
+                    )?
+                    .and_then(|metadata| metadata.publication_status),
+                    _ => None,
+                };
+                entries.push(model_netscape_bookmarks::to_bookmark_entry(
+                    manga,
+                    grouping,
+                    possible_status,
+                ));
+            }
+            Ok(entries)
+        }
+
+        // one note per manga in vault_dir (see obsidian_export), only the ones which changed are written
+        pub fn export_obsidian(
+            &self,
+            vault_dir: &str,
+            prune: bool,
+        ) -> Result<VaultExportSummary, ServiceError> {
+            let mut notes = Vec::new();
+            for manga in self.list()?.iter() {
+                let possible_metadata = model_sqlite3_manga_metadata::select_manga_metadata(
+                    &self.db_full_paths,
+                    manga.id(),
+                )?;
+                notes.push(obsidian_export::to_note(manga, possible_metadata.as_ref()));
+            }
+            Ok(obsidian_export::write_vault(
+                std::path::Path::new(vault_dir),
+                &notes,
+                prune,
+            )?)
+        }
+
+        // emptied first when the database changed since the cached rows were read
+        fn read_cache(&self) -> Result<MutexGuard<'_, ReadCache>, ServiceError> {
+            let changes = model_sqlite3_sync_state::local_changes(&self.db_full_paths)?;
+            let mut read_cache = self
+                .read_cache
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if read_cache.changes != Some(changes) {
+                read_cache.by_id.clear();
+                read_cache.ids_by_title_and_url.clear();
+                read_cache.changes = Some(changes);
+            }
+            Ok(read_cache)
+        }
+
+        // only when nothing was written since changes was read (the row could be older than the cache otherwise)
+        fn remember(&self, changes: Option<u64>, manga: &MangaModel) {
+            let mut read_cache = self
+                .read_cache
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if read_cache.changes == changes {
+                read_cache.by_id.put(manga.id(), manga.clone());
+                read_cache.ids_by_title_and_url.put(
+                    (manga.title().to_string(), manga.url().to_string()),
+                    manga.id(),
+                );
+            }
+        }
+
+        fn save(&self, manga: &MangaModel, tags_given: bool) -> Result<(), ServiceError> {
+            model_sqlite3_manga::update_manga(&self.db_full_paths, manga)?;
+            if tags_given {
+                self.save_tags(manga.id(), manga)?;
+            }
+            Ok(())
+        }
+
+        // tags are read back through manga_to_tags_map, which update_manga() does not touch
+        fn save_tags(&self, id: i64, manga_with_tags: &MangaModel) -> Result<(), ServiceError> {
+            let tags: Vec<String> = manga_with_tags
+                .tags()
+                .iter()
+                .map(|tag| tag.to_string())
+                .collect();
+            model_sqlite3_manga::replace_tags_of_manga(&self.db_full_paths, id, &tags)?;
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn sample_manga() -> MangaModel {
+            let mut manga = MangaModel::new_from_required_elements(
+                "Gate",
+                "https://example.com/manga/gate/chapter-58",
+                1,
+            )
+            .unwrap();
+            manga.set_tags(vec!["seinen".to_string(), "isekai".to_string()]);
+            manga.set_feed_url(Some("https://example.com/manga/gate/feed".to_string()));
+            manga
+        }
+
+        fn create_request(title: &str, url: &str, tags: Option<&str>) -> MangaCreateRequest {
+            MangaCreateRequest {
+                title: title.to_string(),
+                title_romanized: None,
+                url: url.to_string(),
+                url_with_chapter: None,
+                chapter: None,
+                last_update: None,
+                notes: None,
+                tags: tags.map(|tags| tags.to_string()),
+            }
+        }
+
+        #[test]
+        fn test_update_from_request() {
+            let manga = sample_manga();
+            let updated = update_from_request(
+                &manga,
+                MangaUpdateRequest {
+                    title: None,
+                    title_romanized: None,
+                    url: None,
+                    url_with_chapter: None,
+                    chapter: Some("59".to_string()),
+                    last_update: None,
+                    notes: Some("good".to_string()),
+                    tags: None,
+                },
+                &RomanizationOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(updated.title(), "Gate");
+            assert_eq!(updated.chapter(), Some("59".to_string()));
+            assert_eq!(updated.notes(), Some("good".to_string()));
+            assert_eq!(updated.tags(), vec!["seinen", "isekai"]);
+
+            let invalid = MangaUpdateRequest {
+                title: None,
+                title_romanized: None,
+                url: Some("not a url".to_string()),
+                url_with_chapter: None,
+                chapter: None,
+                last_update: None,
+                notes: None,
+                tags: None,
+            };
+            assert!(update_from_request(&manga, invalid, &RomanizationOptions::default()).is_err());
+        }
+
+        #[test]
+        fn test_replace_from_request() {
+            let manga = sample_manga();
+            let replaced = replace_from_request(
+                &manga,
+                MangaRequest {
+                    title: "Gate 2".to_string(),
+                    title_romanized: None,
+                    url: "https://example.com/manga/gate-2/".to_string(),
+                    url_with_chapter: None,
+                    chapter: None,
+                    last_update: None,
+                    notes: None,
+                    tags: Some("isekai".to_string()),
+                },
+                &RomanizationOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(replaced.id(), manga.id());
+            assert_eq!(replaced.title(), "Gate 2");
+            assert_eq!(replaced.url_with_chapter(), None);
+            assert_eq!(replaced.chapter(), None);
+            assert_eq!(replaced.tags(), vec!["isekai"]);
+            // not part of the request, so kept
+            assert_eq!(replaced.feed_url(), manga.feed_url());
+        }
+
+        #[test]
+        fn test_chapter_to_mark_read() {
+            let mut manga = sample_manga();
+            let request = |chapter: Option<&str>| MangaMarkReadRequest {
+                chapter: chapter.map(|chapter| chapter.to_string()),
+            };
+            assert_eq!(
+                chapter_to_mark_read(&manga, &request(Some("12-1"))),
+                Ok("12.1".to_string())
+            );
+            assert!(chapter_to_mark_read(&manga, &request(Some("next"))).is_err());
+            assert!(chapter_to_mark_read(&manga, &request(None)).is_err());
+            manga.set_latest_available_chapter(Some("60".to_string()));
+            assert_eq!(
+                chapter_to_mark_read(&manga, &request(None)),
+                Ok("60".to_string())
+            );
+        }
+
+        #[test]
+        fn test_matches_search_request() {
+            let manga = sample_manga();
+            let search = |title: Option<&str>, tags: Option<&str>| MangaSearchRequest {
+                title: title.map(|t| t.to_string()),
+                title_romanized: None,
+                url: None,
+                url_with_chapter: None,
+                chapter: None,
+                last_update: None,
+                notes: None,
+                tags: tags.map(|t| t.to_string()),
+                tag_expr: None,
+            };
+            assert!(matches_search_request(&manga, &search(None, None)));
+            assert!(matches_search_request(&manga, &search(Some("gAT"), None)));
+            assert!(matches_search_request(
+                &manga,
+                &search(Some("gate"), Some("Seinen,isekai"))
+            ));
+            assert!(!matches_search_request(
+                &manga,
+                &search(Some("gate"), Some("shoujo"))
+            ));
+            assert!(!matches_search_request(&manga, &search(Some("one"), None)));
+        }
+
+        #[test]
+        fn test_manga_service() {
+            let db_full_paths = "samples/test_service.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            assert!(service.list().unwrap().is_empty());
+
+            let created = service
+                .create(create_request(
+                    "Gate",
+                    "https://example.com/manga/gate/",
+                    Some("seinen"),
+                ))
+                .unwrap();
+            assert_eq!(
+                service.create(create_request(
+                    "Gate",
+                    "https://example.com/manga/gate/",
+                    None
+                )),
+                Err(ServiceError::AlreadyExists(created.id()))
+            );
+            assert!(matches!(
+                service.create(create_request(" ", "https://example.com/", None)),
+                Err(ServiceError::InvalidInput(_))
+            ));
+
+            let read = service.mark_read(created.id(), Some("12-1")).unwrap();
+            assert_eq!(read.chapter(), Some("12.1".to_string()));
+            assert_eq!(service.history(0).unwrap().len(), 1);
+            // what was marked read is written out as CSV like any other row
+            let mut csv_util = crate::model_csv_manga::model_csv_manga::Utils::new(
+                Some(Box::new(std::io::sink())),
+                Box::new(std::io::empty()),
+            );
+            csv_util.write_csv(&service.list().unwrap()).unwrap();
+            let mut csv = Vec::new();
+            assert_eq!(
+                service
+                    .export_history(&mut csv, HistoryFormat::Csv, 0)
+                    .unwrap(),
+                1
+            );
+
+            let upserted = service
+                .upsert(create_request(
+                    "Gate",
+                    "https://example.com/manga/gate/",
+                    Some("isekai"),
+                ))
+                .unwrap();
+            assert_eq!(upserted.id(), created.id());
+            assert_eq!(service.get(created.id()).unwrap().tags(), vec!["isekai"]);
+            let search_tag_expr = |tag_expr: &str| {
+                service.search(&MangaSearchRequest {
+                    title: Some("gate".to_string()),
+                    title_romanized: None,
+                    url: None,
+                    url_with_chapter: None,
+                    chapter: None,
+                    last_update: None,
+                    notes: None,
+                    tags: None,
+                    tag_expr: Some(tag_expr.to_string()),
+                })
+            };
+            assert_eq!(search_tag_expr("isekai AND NOT seinen").unwrap().len(), 1);
+            assert!(search_tag_expr("seinen").unwrap().is_empty());
+            assert!(matches!(
+                search_tag_expr("isekai AND"),
+                Err(ServiceError::InvalidInput(_))
+            ));
+
+            service.delete(created.id()).unwrap();
+            assert_eq!(
+                service.delete(created.id()),
+                Err(ServiceError::NotFound(created.id()))
+            );
+            assert_eq!(
+                service.get(created.id()),
+                Err(ServiceError::NotFound(created.id()))
+            );
+        }
+
+        #[test]
+        fn test_read_cache() {
+            let mut lru = LruCache::new(2);
+            lru.put(1, "one");
+            lru.put(2, "two");
+            assert_eq!(lru.get(&1), Some("one"));
+            lru.put(3, "three"); // 2 is the least recently used
+            assert_eq!(lru.get(&2), None);
+            assert_eq!(lru.get(&1), Some("one"));
+
+            let db_full_paths = "samples/test_service_read_cache.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            let created = service
+                .create(create_request(
+                    "Gate",
+                    "https://example.com/manga/gate/",
+                    None,
+                ))
+                .unwrap();
+            let stored = service.get(created.id()).unwrap();
+            assert_eq!(
+                service.get(created.id()).unwrap().chapter(),
+                stored.chapter()
+            );
+            assert_eq!(
+                service
+                    .find("Gate", "https://example.com/manga/gate/")
+                    .unwrap()
+                    .map(|manga| manga.id()),
+                Some(created.id())
+            );
+            // written behind the service's back (i.e. by the update checker), not served from the cache
+            let mut read = stored.clone();
+            read.set_chapter(Some("58".to_string()));
+            model_sqlite3_manga::update_manga(db_full_paths, &read).unwrap();
+            assert_eq!(
+                service.get(created.id()).unwrap().chapter(),
+                Some("58".to_string())
+            );
+            assert_eq!(
+                service.clone().get(created.id()).unwrap().chapter(),
+                Some("58".to_string())
+            );
+            assert_eq!(service.find("Gate", "https://example.com/").unwrap(), None);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_import_bookmarks() {
+            let db_full_paths = "samples/test_service_import.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            let bookmarks = std::fs::File::open("samples/bookmarks.json").unwrap();
+            let summary = service.import_bookmarks(bookmarks).unwrap();
+            assert!(summary.bookmarks_count > 0);
+            assert!(summary.failures.is_empty());
+            // the same bookmark filed in two folders is only stored once
+            let stored_count = service.list().unwrap().len();
+            assert!(stored_count > 0 && stored_count <= summary.bookmarks_count);
+            // ...but both bookmarks are its origins, and importing again does not add them twice
+            service
+                .import_bookmarks(std::fs::File::open("samples/bookmarks.json").unwrap())
+                .unwrap();
+            let kenja = service
+                .list()
+                .unwrap()
+                .into_iter()
+                .find(|manga| {
+                    manga.bookmark_guid() == Some("MklRrX5r5637".to_string())
+                        || manga.bookmark_guid() == Some("TdrB76YP7VLg".to_string())
+                })
+                .unwrap();
+            let origins = model_sqlite3_bookmark_origin::select_bookmark_origins_of_manga(
+                db_full_paths,
+                kenja.id(),
+            )
+            .unwrap();
+            let mut folder_paths: Vec<&str> = origins
+                .iter()
+                .map(|origin| origin.folder_path.as_str())
+                .collect();
+            folder_paths.sort();
+            assert_eq!(folder_paths, vec!["toolbar/Manga", "toolbar/Reading-now"]);
+            assert!(origins.iter().all(|origin| origin.date_added_millis > 0));
+            service.delete(kenja.id()).unwrap();
+            assert!(
+                model_sqlite3_bookmark_origin::select_bookmark_origins_of_manga(
+                    db_full_paths,
+                    kenja.id()
+                )
+                .unwrap()
+                .is_empty()
+            );
+            assert!(matches!(
+                service.import_bookmarks("{".as_bytes()),
+                Err(ServiceError::InvalidInput(_))
+            ));
+        }
+
+        #[test]
+        fn test_folder_tags() {
+            assert_eq!(folder_tags("toolbar/Manga/連載中"), vec!["Manga", "連載中"]);
+            assert_eq!(folder_tags("Bookmarks bar/Manga"), vec!["Manga"]);
+            assert_eq!(folder_tags("漫画/toolbar"), vec!["漫画", "toolbar"]);
+            assert_eq!(folder_tags("menu/A, B"), vec!["A， B"]);
+            assert!(folder_tags("").is_empty());
+            assert!(folder_tags("unfiled").is_empty());
+
+            let db_full_paths = "samples/test_service_folder_tags.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths)
+                .unwrap()
+                .with_folder_tags(true);
+            service
+                .import_bookmarks(std::fs::File::open("samples/bookmarks.json").unwrap())
+                .unwrap();
+            let mangas = service.list().unwrap();
+            let kenja = mangas
+                .iter()
+                .find(|manga| manga.bookmark_guid() == Some("MklRrX5r5637".to_string()))
+                .unwrap();
+            let tags: Vec<&str> = kenja.tags().iter().map(|tag| tag.trim()).collect();
+            assert!(tags.contains(&"Manga") || tags.contains(&"Reading-now"));
+            assert!(!tags.contains(&"toolbar"));
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_bookmark_filter() {
+            let mut filter = BookmarkFilter {
+                include_folders: vec!["manga".to_string()],
+                exclude_folders: vec!["Manga/完結".to_string()],
+                ..BookmarkFilter::default()
+            };
+            assert!(filter.keeps(Some("toolbar/Manga"), "https://example.com/"));
+            assert!(filter.keeps(Some("toolbar/Manga/連載中"), "https://example.com/"));
+            assert!(!filter.keeps(Some("toolbar/Manga/完結"), "https://example.com/"));
+            assert!(!filter.keeps(Some("toolbar/Mangas"), "https://example.com/"));
+            assert!(filter.keeps(None, "https://example.com/"));
+
+            filter.add_url_pattern("*://mangadex.org/*").unwrap();
+            filter.add_url_pattern(r"example\.com/title/\d+").unwrap();
+            assert!(filter.keeps(Some("Manga"), "https://mangadex.org/title/1/"));
+            assert!(!filter.keeps(Some("Manga"), "https://www.mangadex.org/title/1/"));
+            assert!(filter.keeps(None, "https://example.com/title/42/"));
+            assert!(!filter.keeps(None, "https://example.com/title/kenja/"));
+            assert!(filter.add_url_pattern("(unclosed").is_err());
+
+            let db_full_paths = "samples/test_service_bookmark_filter.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let import_with = |bookmark_filter: BookmarkFilter| {
+                let service = MangaService::open(db_full_paths)
+                    .unwrap()
+                    .with_bookmark_filter(bookmark_filter);
+                service
+                    .import_bookmarks(std::fs::File::open("samples/bookmarks.json").unwrap())
+                    .unwrap();
+                service.list().unwrap().len()
+            };
+            let reading_now_count = import_with(BookmarkFilter {
+                include_folders: vec!["reading-now".to_string()],
+                ..BookmarkFilter::default()
+            });
+            assert!(reading_now_count > 0 && reading_now_count <= 11);
+            let mut url_filter = BookmarkFilter::default();
+            url_filter
+                .add_url_pattern("*://other.example.com/*")
+                .unwrap();
+            assert_eq!(import_with(url_filter), reading_now_count);
+            let manga_count = import_with(BookmarkFilter {
+                exclude_folders: vec!["Reading-now".to_string()],
+                ..BookmarkFilter::default()
+            });
+            assert!(manga_count > reading_now_count);
+            std::fs::remove_file(db_full_paths).unwrap();
+        }
+
+        #[test]
+        fn test_import_bookmarks_streaming() {
+            let pipeline = Pipeline::new(None, &[], Vec::new());
+            let mut batch_sizes = Vec::new();
+            let streamed_count = stream_bookmarks_to_manga(
+                std::fs::File::open("samples/bookmarks.json").unwrap(),
+                &pipeline,
+                "",
+                10,
+                &mut |mangas, origins| {
+                    assert!(origins.len() >= mangas.len());
+                    batch_sizes.push(mangas.len());
+                    Ok(())
+                },
+            )
+            .unwrap();
+            let bookmark_folders: BookmarkRootFolder =
+                serde_json::from_reader(std::fs::File::open("samples/bookmarks.json").unwrap())
+                    .unwrap();
+            let mangas = bookmarks_to_manga(&bookmark_folders, &pipeline).unwrap();
+            assert_eq!(streamed_count, mangas.len());
+            assert_eq!(batch_sizes.iter().sum::<usize>(), mangas.len());
+            assert!(batch_sizes[..batch_sizes.len() - 1]
+                .iter()
+                .all(|batch_size| *batch_size == 10));
+
+            // same rows as reading the whole JSON first
+            let (db_full_paths, streamed_full_paths) = (
+                "samples/test_service_import_whole.db",
+                "samples/test_service_import_streaming.db",
+            );
+            let mut stored = Vec::new();
+            for (full_paths, streaming) in [(db_full_paths, false), (streamed_full_paths, true)] {
+                std::fs::remove_file(full_paths).unwrap_or_default();
+                let service = MangaService::open(full_paths).unwrap();
+                let bookmarks = std::fs::File::open("samples/bookmarks.json").unwrap();
+                let summary = match streaming {
+                    true => service.import_bookmarks_streaming(bookmarks).unwrap(),
+                    false => service.import_bookmarks(bookmarks).unwrap(),
+                };
+                assert!(summary.failures.is_empty());
+                let mut rows: Vec<(String, String)> = service
+                    .list()
+                    .unwrap()
+                    .iter()
+                    .map(|manga| (manga.title().to_string(), manga.url().to_string()))
+                    .collect();
+                rows.sort();
+                stored.push(rows);
+                std::fs::remove_file(full_paths).unwrap();
+            }
+            assert_eq!(stored[0], stored[1]);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// `--preview` of risky operations (migrate-site, the CSV import): the database is copied to "<db>.preview",
+// the operation runs on the copy, and the manga rows it added, deleted or changed are printed (see manga_diff)
+// before asking whether to commit.  Committing moves the copy in place of the database, the original kept as
+// "<db>.bak" the way sync pull does.  A commit is refused when the database was written to since it was
+// copied (i.e. by the daemon, or a server), since the copy would silently drop those writes.
+pub mod shadow_database {
+    use std::{fs, path::Path, time::SystemTime};
+
+    use crate::manga_diff::manga_diff::{self, MangaDiff};
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+
+    const K_SHADOW_SUFFIX: &str = ".preview";
+    const K_BACKUP_SUFFIX: &str = ".bak";
+
+    pub struct ShadowDatabase {
+        db_full_paths: String,
+        shadow_full_paths: String,
+        original_stamp: Option<(u64, SystemTime)>, // size and modified time of the database when copied
+    }
+
+    fn file_stamp(full_paths: &str) -> Option<(u64, SystemTime)> {
+        let metadata = fs::metadata(full_paths).ok()?;
+        Some((metadata.len(), metadata.modified().ok()?))
+    }
+
+    impl ShadowDatabase {
+        // a consistent copy, even while someone else writes to the database
+        pub fn create(db_full_paths: &str) -> Result<ShadowDatabase, Box<dyn std::error::Error>> {
+            let shadow_full_paths = format!("{}{}", db_full_paths, K_SHADOW_SUFFIX);
+            fs::remove_file(&shadow_full_paths).unwrap_or_default();
+            model_sqlite3_manga::create_tables(db_full_paths)?;
+            model_sqlite3_connection::open(db_full_paths)?
+                .execute("VACUUM INTO ?1", rusqlite::params![shadow_full_paths])?;
+            Ok(ShadowDatabase {
+                db_full_paths: db_full_paths.to_string(),
+                original_stamp: file_stamp(db_full_paths),
+                shadow_full_paths,
+            })
+        }
+
+        // where the operation is to be run
+        pub fn full_paths(&self) -> &str {
+            &self.shadow_full_paths
+        }
+
+        // what committing would change
+        pub fn diff(&self) -> Result<MangaDiff, Box<dyn std::error::Error>> {
+            Ok(manga_diff::diff(
+                &model_sqlite3_manga::select_all_manga(&self.db_full_paths)?,
+                &model_sqlite3_manga::select_all_manga(&self.shadow_full_paths)?,
+            ))
+        }
+
+        pub fn commit(self) -> Result<(), Box<dyn std::error::Error>> {
+            if file_stamp(&self.db_full_paths) != self.original_stamp {
+                return Err(format!(
+                    "'{}' was written to during the preview, nothing committed; run it again",
+                    self.db_full_paths
+                )
+                .into());
+            }
+            fs::copy(
+                &self.db_full_paths,
+                format!("{}{}", self.db_full_paths, K_BACKUP_SUFFIX),
+            )?;
+            fs::rename(&self.shadow_full_paths, &self.db_full_paths)?;
+            Ok(())
+        }
+    }
+
+    // a preview not committed is thrown away
+    impl Drop for ShadowDatabase {
+        fn drop(&mut self) {
+            if Path::new(&self.shadow_full_paths).exists() {
+                fs::remove_file(&self.shadow_full_paths).unwrap_or_default();
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::model_manga::model_manga::MangaModel;
+
+        #[test]
+        fn test_shadow_database() {
+            let db_full_paths = "samples/test_shadow_database.db";
+            for suffix in ["", K_SHADOW_SUFFIX, K_BACKUP_SUFFIX] {
+                fs::remove_file(format!("{}{}", db_full_paths, suffix)).unwrap_or_default();
+            }
+            model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let mut gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            gate.set_chapter(Some("12".to_string()));
+            model_sqlite3_manga::insert_manga(db_full_paths, &gate).unwrap();
+
+            // thrown away: the database is untouched
+            let shadow = ShadowDatabase::create(db_full_paths).unwrap();
+            let mut gate_read = gate.clone();
+            gate_read.set_chapter(Some("13".to_string()));
+            model_sqlite3_manga::update_manga(shadow.full_paths(), &gate_read).unwrap();
+            assert_eq!(
+                shadow.diff().unwrap().to_lines(),
+                vec!["~ id=1 'Gate'", "    chapter: '12' => '13'"]
+            );
+            drop(shadow);
+            assert!(!Path::new(&format!("{}{}", db_full_paths, K_SHADOW_SUFFIX)).exists());
+            assert_eq!(
+                model_sqlite3_manga::select_manga_by_id(db_full_paths, 1)
+                    .unwrap()
+                    .chapter(),
+                Some("12".to_string())
+            );
+
+            // committed: the database is the copy, the original kept aside
+            let shadow = ShadowDatabase::create(db_full_paths).unwrap();
+            model_sqlite3_manga::update_manga(shadow.full_paths(), &gate_read).unwrap();
+            shadow.commit().unwrap();
+            assert_eq!(
+                model_sqlite3_manga::select_manga_by_id(db_full_paths, 1)
+                    .unwrap()
+                    .chapter(),
+                Some("13".to_string())
+            );
+            assert!(Path::new(&format!("{}{}", db_full_paths, K_BACKUP_SUFFIX)).exists());
+
+            for suffix in ["", K_SHADOW_SUFFIX, K_BACKUP_SUFFIX] {
+                fs::remove_file(format!("{}{}", db_full_paths, suffix)).unwrap_or_default();
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+// Interactive shell over the collection: `manga shell [--history <file>]`
+// One process (and one load of the collection) for a whole curation session instead of one per edit:
+//      search <text>                     best matches first, typos allowed (see ranked_search)
+//      show <manga>                      every field of one manga
+//      set chapter <manga> <chapter>     logged like `mark-read` ("12-1" is stored as "12.1")
+//      tag <manga> <tag>[, <tag>...]     attach tags (the ones it already has are kept)
+//      open <manga>                      the last read chapter (or the series) in the browser
+// where <manga> is an id, or a title (double-quoted when it has spaces; a unique part of it is enough).
+// Tab completes commands and titles, Up/Down and Ctrl-R go through the history which is kept across sessions.
+// The database layer's progress messages are discarded so they do not scroll the answers away.
+pub mod shell {
+    use rustyline::{
+        completion::{Completer, Pair},
+        config::Behavior,
+        error::ReadlineError,
+        highlight::Highlighter,
+        hint::Hinter,
+        history::DefaultHistory,
+        validate::Validator,
+        Context, Editor, Helper,
+    };
+    use std::io::{self, Write};
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::service::service::{self, MangaService};
+    use crate::subcommands::subcommands;
+
+    pub const K_DEFAULT_HISTORY_FILE: &str = ".manga_shell_history";
+    const K_PROMPT: &str = "漫画> ";
+    const K_COMMANDS: [&str; 8] = [
+        "search", "show", "set", "tag", "open", "help", "quit", "exit",
+    ];
+    const K_AMBIGUOUS_LIST_COUNT: usize = 5;
+    const K_SEARCH_LIMIT: usize = 20;
+
+    pub struct ShellOptions {
+        pub db_full_paths: String,
+        pub history_full_paths: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Command {
+        Search(String),
+        Show(String),
+        SetChapter(String, String),
+        Tag(String, Vec<String>),
+        Open(String),
+        Help,
+        Quit,
+    }
+
+    // `set chapter "Dr. Stone" 12` => ["set", "chapter", "Dr. Stone", "12"]
+    fn split_words(line: &str) -> Result<Vec<String>, String> {
+        let mut words = Vec::new();
+        let mut possible_word: Option<String> = None;
+        let mut in_quotes = false;
+        for c in line.chars() {
+            match c {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    possible_word.get_or_insert_with(String::new);
+                }
+                c if c.is_whitespace() && !in_quotes => {
+                    if let Some(word) = possible_word.take() {
+                        words.push(word);
+                    }
+                }
+                c => possible_word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        if in_quotes {
+            return Err("missing closing '\"'".to_string());
+        }
+        words.extend(possible_word);
+        Ok(words)
+    }
+
+    fn parse_command(line: &str) -> Result<Option<Command>, String> {
+        let words = split_words(line)?;
+        let words: Vec<&str> = words.iter().map(|word| word.as_str()).collect();
+        let command = match words.as_slice() {
+            [] => return Ok(None),
+            ["search", text @ ..] if !text.is_empty() => Command::Search(text.join(" ")),
+            ["show", manga] => Command::Show(manga.to_string()),
+            ["set", "chapter", manga, chapter] => {
+                Command::SetChapter(manga.to_string(), chapter.to_string())
+            }
+            ["tag", manga, tags @ ..] if !tags.is_empty() => Command::Tag(
+                manga.to_string(),
+                tags.join(" ")
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect(),
+            ),
+            ["open", manga] => Command::Open(manga.to_string()),
+            ["help"] | ["?"] => Command::Help,
+            ["quit"] | ["exit"] => Command::Quit,
+            [command, ..] if K_COMMANDS.contains(command) => {
+                return Err(format!("usage: {}", usage_of(command)))
+            }
+            [unknown, ..] => return Err(format!("unknown command '{}', try 'help'", unknown)),
+        };
+        Ok(Some(command))
+    }
+
+    fn usage_of(command: &str) -> &'static str {
+        match command {
+            "search" => "search <text>",
+            "show" => "show <manga>",
+            "set" => "set chapter <manga> <chapter>",
+            "tag" => "tag <manga> <tag>[, <tag>...]",
+            "open" => "open <manga>",
+            _ => "quit",
+        }
+    }
+
+    fn matches_title(manga: &MangaModel, text: &str) -> bool {
+        service::contains_ignore_case(Some(manga.title()), text)
+            || service::contains_ignore_case(manga.title_romanized().as_deref(), text)
+    }
+
+    // an id, an exact title (or romanized title), or a part of exactly one title
+    fn resolve<'a>(mangas: &'a [MangaModel], reference: &str) -> Result<&'a MangaModel, String> {
+        if let Ok(id) = reference.parse::<i64>() {
+            return mangas
+                .iter()
+                .find(|manga| manga.id() == id)
+                .ok_or_else(|| format!("manga id={} not found", id));
+        }
+        let is_exact = |manga: &&MangaModel| {
+            manga.title().eq_ignore_ascii_case(reference)
+                || manga
+                    .title_romanized()
+                    .map_or(false, |romanized| romanized.eq_ignore_ascii_case(reference))
+        };
+        if let Some(manga) = mangas.iter().find(is_exact) {
+            return Ok(manga);
+        }
+        let candidates: Vec<&MangaModel> = mangas
+            .iter()
+            .filter(|manga| matches_title(manga, reference))
+            .collect();
+        match candidates.as_slice() {
+            [] => Err(format!("no manga matches '{}'", reference)),
+            [manga] => Ok(manga),
+            _ => Err(format!(
+                "'{}' matches {} manga, use the id: {}",
+                reference,
+                candidates.len(),
+                candidates
+                    .iter()
+                    .take(K_AMBIGUOUS_LIST_COUNT)
+                    .map(|manga| format!("{}={}", manga.id(), manga.title()))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    // (start of the word being completed, replacements) for the text left of the cursor
+    fn complete_line(line: &str, titles: &[(String, Option<String>)]) -> (usize, Vec<Pair>) {
+        let possible_open_quote = match line.matches('"').count() % 2 {
+            1 => line.rfind('"'),
+            _ => None,
+        };
+        let start = possible_open_quote.unwrap_or_else(|| {
+            line.char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map_or(0, |(i, c)| i + c.len_utf8())
+        });
+        let words = split_words(&line[..start]).unwrap_or_default();
+        let typed = line[start..].trim_start_matches('"').to_lowercase();
+        let words: Vec<&str> = words.iter().map(|word| word.as_str()).collect();
+        let pairs = match words.as_slice() {
+            [] => K_COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(&typed))
+                .map(|command| Pair {
+                    display: command.to_string(),
+                    replacement: format!("{} ", command),
+                })
+                .collect(),
+            ["set"] => vec![Pair {
+                display: "chapter".to_string(),
+                replacement: "chapter ".to_string(),
+            }],
+            ["show" | "open" | "tag"] | ["set", "chapter"] => titles
+                .iter()
+                .filter(|(title, possible_romanized)| {
+                    title.to_lowercase().contains(&typed)
+                        || possible_romanized
+                            .as_ref()
+                            .map_or(false, |romanized| romanized.to_lowercase().contains(&typed))
+                })
+                .map(|(title, _)| Pair {
+                    display: title.to_string(),
+                    replacement: format!("\"{}\" ", title),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        (start, pairs)
+    }
+
+    struct ShellHelper {
+        titles: Vec<(String, Option<String>)>,
+    }
+
+    impl Completer for ShellHelper {
+        type Candidate = Pair;
+
+        fn complete(
+            &self,
+            line: &str,
+            pos: usize,
+            _ctx: &Context<'_>,
+        ) -> rustyline::Result<(usize, Vec<Pair>)> {
+            Ok(complete_line(&line[..pos], &self.titles))
+        }
+    }
+
+    impl Hinter for ShellHelper {
+        type Hint = String;
+    }
+
+    impl Highlighter for ShellHelper {}
+
+    impl Validator for ShellHelper {}
+
+    impl Helper for ShellHelper {}
+
+    struct Shell {
+        service: MangaService,
+        mangas: Vec<MangaModel>,
+    }
+
+    impl Shell {
+        fn load(service: MangaService) -> Result<Shell, Box<dyn std::error::Error>> {
+            let mangas = service.list()?;
+            Ok(Shell { service, mangas })
+        }
+
+        fn titles(&self) -> Vec<(String, Option<String>)> {
+            let mut titles: Vec<(String, Option<String>)> = self
+                .mangas
+                .iter()
+                .map(|manga| (manga.title().to_string(), manga.title_romanized()))
+                .collect();
+            titles.sort();
+            titles.dedup();
+            titles
+        }
+
+        fn write_help(out: &mut dyn Write) -> io::Result<()> {
+            for command in ["search", "show", "set", "tag", "open"] {
+                writeln!(out, "  {}", usage_of(command))?;
+            }
+            writeln!(
+                out,
+                "  quit\n<manga> is an id or (part of) a title, \"quoted\" when it has spaces"
+            )
+        }
+
+        fn write_details(out: &mut dyn Write, manga: &MangaModel) -> io::Result<()> {
+            let fields = [
+                ("id", Some(manga.id().to_string())),
+                ("title", Some(manga.title().to_string())),
+                ("romanized", manga.title_romanized()),
+                ("url", Some(manga.url().to_string())),
+                ("chapter", manga.chapter()),
+                ("chapter url", manga.url_with_chapter()),
+                ("latest", manga.latest_available_chapter()),
+                (
+                    "unread",
+                    manga.unread_count().map(|unread| unread.to_string()),
+                ),
+                ("last read", manga.last_update()),
+                (
+                    "tags",
+                    Some(
+                        manga
+                            .tags()
+                            .iter()
+                            .map(|tag| tag.trim())
+                            .collect::<Vec<&str>>()
+                            .join(", "),
+                    ),
+                ),
+                ("notes", manga.notes()),
+            ];
+            for (name, possible_value) in fields {
+                if let Some(value) = possible_value.filter(|value| !value.trim().is_empty()) {
+                    writeln!(out, "{:>12}: {}", name, value.trim())?;
+                }
+            }
+            Ok(())
+        }
+
+        // returns false once the shell should end
+        fn execute(
+            &mut self,
+            line: &str,
+            out: &mut dyn Write,
+        ) -> Result<bool, Box<dyn std::error::Error>> {
+            let command = match parse_command(line)? {
+                Some(command) => command,
+                None => return Ok(true),
+            };
+            match command {
+                Command::Search(text) => {
+                    let hits = self.service.ranked_search(&text, K_SEARCH_LIMIT)?;
+                    for manga in hits.iter().map(|hit| &hit.manga) {
+                        writeln!(
+                            out,
+                            "{}\t{}\t{}\t{}",
+                            manga.id(),
+                            manga.title(),
+                            manga.chapter().unwrap_or_else(|| "-".to_string()),
+                            manga
+                                .latest_available_chapter()
+                                .unwrap_or_else(|| "-".to_string())
+                        )?;
+                    }
+                    writeln!(out, "{} found", hits.len())?;
+                }
+                Command::Show(reference) => {
+                    Self::write_details(out, resolve(&self.mangas, &reference)?)?;
+                }
+                Command::SetChapter(reference, chapter) => {
+                    let id = resolve(&self.mangas, &reference)?.id();
+                    let manga_read = self.service.mark_read(id, Some(&chapter))?;
+                    writeln!(
+                        out,
+                        "id={} '{}' chapter {}",
+                        id,
+                        manga_read.title(),
+                        manga_read.chapter().unwrap_or_default()
+                    )?;
+                    self.mangas = self.service.list()?;
+                }
+                Command::Tag(reference, tags) => {
+                    let id = resolve(&self.mangas, &reference)?.id();
+                    let attached_count = self.service.add_tags(id, &tags)?;
+                    writeln!(out, "id={} {} tag(s) attached", id, attached_count)?;
+                    self.mangas = self.service.list()?;
+                }
+                Command::Open(reference) => {
+                    let manga = resolve(&self.mangas, &reference)?;
+                    let url = manga
+                        .url_with_chapter()
+                        .unwrap_or_else(|| manga.url().to_string());
+                    subcommands::open_in_browser(&url)?;
+                }
+                Command::Help => Self::write_help(out)?,
+                Command::Quit => return Ok(false),
+            }
+            Ok(true)
+        }
+    }
+
+    // fd 1 is pointed at /dev/null for the database layer's println!, the shell writes to (a duplicate of)
+    // the original stdout, and the line editor talks to the terminal directly
+    #[cfg(unix)]
+    fn quiet_stdout() -> io::Result<Box<dyn Write>> {
+        use std::os::fd::{AsFd, AsRawFd};
+        io::stdout().flush()?;
+        let original_stdout = io::stdout().as_fd().try_clone_to_owned()?;
+        let null = std::fs::OpenOptions::new().write(true).open("/dev/null")?;
+        // SAFETY: both are open descriptors of this process; dup2 only replaces what fd 1 refers to
+        if unsafe { libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Box::new(std::fs::File::from(original_stdout)))
+    }
+
+    #[cfg(not(unix))]
+    fn quiet_stdout() -> io::Result<Box<dyn Write>> {
+        Ok(Box::new(io::stdout()))
+    }
+
+    // blocks until quit, Ctrl-D or end of input
+    pub fn run(options: ShellOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let mut out = quiet_stdout()?;
+        let mut shell = Shell::load(MangaService::open(&options.db_full_paths)?)?;
+        let config = rustyline::Config::builder()
+            .behavior(Behavior::PreferTerm)
+            .build();
+        let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::with_config(config)?;
+        editor.set_helper(Some(ShellHelper {
+            titles: shell.titles(),
+        }));
+        // there is none on the first run
+        let _ = editor.load_history(&options.history_full_paths);
+        writeln!(
+            out,
+            "{} manga loaded, 'help' lists the commands",
+            shell.mangas.len()
+        )?;
+        loop {
+            let line = match editor.readline(K_PROMPT) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue, // Ctrl-C only drops the line
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line.as_str())?;
+            // a failed command is reported, not fatal
+            match shell.execute(&line, &mut out) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => writeln!(out, "error: {}", e)?,
+            }
+            out.flush()?;
+            if let Some(helper) = editor.helper_mut() {
+                helper.titles = shell.titles();
+            }
+        }
+        editor.save_history(&options.history_full_paths)?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_and_complete() {
+            assert_eq!(
+                split_words(r#"set chapter "Dr. Stone" 12-1"#),
+                Ok(vec![
+                    "set".to_string(),
+                    "chapter".to_string(),
+                    "Dr. Stone".to_string(),
+                    "12-1".to_string()
+                ])
+            );
+            assert!(split_words(r#"show "Dr. Stone"#).is_err());
+            assert_eq!(
+                parse_command("tag 7 seinen, isekai"),
+                Ok(Some(Command::Tag(
+                    "7".to_string(),
+                    vec!["seinen".to_string(), "isekai".to_string()]
+                )))
+            );
+            assert!(parse_command("set chapter 7").is_err());
+            assert!(parse_command("frobnicate").is_err());
+
+            let titles = vec![
+                ("Dr. Stone".to_string(), None),
+                ("ゲート".to_string(), Some("geeto".to_string())),
+            ];
+            let (start, pairs) = complete_line("se", &titles);
+            assert_eq!(start, 0);
+            assert_eq!(pairs.len(), 2); // search, set
+            let (start, pairs) = complete_line("show \"dr", &titles);
+            assert_eq!(start, 5);
+            assert_eq!(pairs[0].replacement, "\"Dr. Stone\" ");
+            let (_, pairs) = complete_line("set chapter gee", &titles);
+            assert_eq!(pairs[0].replacement, "\"ゲート\" ");
+        }
+
+        #[test]
+        fn test_execute() {
+            let db_full_paths = "samples/test_shell.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            for (title, url) in [
+                ("Akira", "https://example.com/manga/akira/"),
+                ("Akira Club", "https://example.com/manga/akira-club/"),
+            ] {
+                service
+                    .upsert(service::MangaCreateRequest {
+                        title: title.to_string(),
+                        title_romanized: None,
+                        url: url.to_string(),
+                        url_with_chapter: None,
+                        chapter: None,
+                        last_update: None,
+                        notes: None,
+                        tags: None,
+                    })
+                    .unwrap();
+            }
+            let mut shell = Shell::load(service).unwrap();
+            let mut out = Vec::new();
+            assert!(shell.execute("search akira", &mut out).unwrap());
+            assert!(String::from_utf8_lossy(&out).contains("2 found"));
+            // "akira" is an exact title, "club" a unique part of one
+            shell.execute("set chapter akira 12-1", &mut out).unwrap();
+            shell.execute("tag club seinen, sci-fi", &mut out).unwrap();
+            let akira = resolve(&shell.mangas, "Akira").unwrap();
+            assert_eq!(akira.chapter(), Some("12.1".to_string()));
+            let club = resolve(&shell.mangas, "club").unwrap();
+            assert_eq!(club.tags().len(), 2);
+            assert!(shell.execute("show kira", &mut out).is_err());
+            assert!(!shell.execute("quit", &mut out).unwrap());
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Dead-site migration assistant: when a whole domain stops resolving, every manga hosted there is
+// looked up on the other known aggregators (the [[aggregators]] in the TOML config) by its romanized
+// title, and a replacement url is proposed.  Most aggregators use the romanized title as the slug
+// (i.e. "https://example.com/manga/tsuki-ga-michibiku-isekai-douchuu/"), so the slug is tried
+// directly first, and the aggregator's search page (if configured) is scraped as the fallback.
+pub mod site_migration {
+    use regex::Regex;
+    use url::Url;
+
+    use crate::config::config::AggregatorConfig;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::update_checker::update_checker;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct MigrationProposal {
+        pub manga_id: i64,
+        pub title: String,
+        pub old_url: String,
+        pub new_url: String,
+        pub aggregator: String,
+    }
+
+    // "Tsuki ga Michibiku Isekai Douchuu Chapter 12" => "tsuki-ga-michibiku-isekai-douchuu"
+    // (bookmark titles often carry the chapter, which is never part of the series slug)
+    pub fn slugify(title: &str) -> String {
+        let chapter_suffix_regex =
+            Regex::new(r"(?i)\s*(?:-\s*)?(?:chapter|ch\.?)\s*[\d.\-]+.*$").unwrap();
+        let title = chapter_suffix_regex.replace(title, "");
+        title
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>()
+            .join("-")
+    }
+
+    // host equals dead_domain, or is a subdomain of it
+    pub fn is_on_domain(url: &str, dead_domain: &str) -> bool {
+        match Url::parse(url) {
+            Ok(url) => url.host_str().map_or(false, |host| {
+                host == dead_domain || host.ends_with(&format!(".{}", dead_domain))
+            }),
+            Err(_) => false,
+        }
+    }
+
+    // slugs to look for, most likely first: romanized title (or title if it's already ASCII), then the old url's slug
+    pub fn candidate_slugs(manga: &MangaModel) -> Vec<String> {
+        let mut slugs = Vec::new();
+        let possible_romanized =
+            manga
+                .title_romanized()
+                .or_else(|| match manga.title().is_ascii() {
+                    true => Some(manga.title().to_string()),
+                    false => None,
+                });
+        if let Some(romanized) = possible_romanized {
+            slugs.push(slugify(&romanized));
+        }
+        if let Ok(old_url) = Url::parse(manga.url()) {
+            if let Some(old_slug) = old_url
+                .path_segments()
+                .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            {
+                slugs.push(slugify(old_slug));
+            }
+        }
+        slugs.retain(|slug| !slug.is_empty());
+        slugs.dedup();
+        slugs
+    }
+
+    // links on the search result page which point back into the aggregator and end with the slug
+    pub fn find_in_search_results(
+        html: &str,
+        aggregator_base_url: &str,
+        slug: &str,
+    ) -> Option<String> {
+        let href_regex = Regex::new(r#"href\s*=\s*["']([^"'#]+)["']"#).unwrap();
+        let base_url = Url::parse(aggregator_base_url).ok()?;
+        let possible_found = href_regex
+            .captures_iter(html)
+            .filter_map(|c| base_url.join(&c[1]).ok())
+            .filter(|url| url.host_str() == base_url.host_str())
+            .map(|url| url.to_string())
+            .find(|url| url.trim_end_matches('/').ends_with(&format!("/{}", slug)));
+        possible_found
+    }
+
+    fn find_on_aggregator(
+        aggregator: &AggregatorConfig,
+        slugs: &[String],
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        for slug in slugs {
+            let guessed_url = format!("{}/{}/", aggregator.base_url.trim_end_matches('/'), slug);
+            if update_checker::url_exists(&guessed_url)? {
+                return Ok(Some(guessed_url));
+            }
+            if let Some(search_url) = &aggregator.search_url {
+                let query: String =
+                    url::form_urlencoded::byte_serialize(slug.replace("-", " ").as_bytes())
+                        .collect();
+                let html = update_checker::fetch_text(&search_url.replace("{query}", &query))?;
+                if let Some(found_url) = find_in_search_results(&html, &aggregator.base_url, slug) {
+                    return Ok(Some(found_url));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // one proposal per manga at most (first aggregator that has it wins, in config order);
+    // an aggregator that is itself unreachable is reported and skipped
+    pub fn propose_migrations(
+        mangas: &[MangaModel],
+        dead_domain: &str,
+        aggregators: &[AggregatorConfig],
+    ) -> Vec<MigrationProposal> {
+        let alive_aggregators = aggregators
+            .iter()
+            .filter(|aggregator| !is_on_domain(&aggregator.base_url, dead_domain))
+            .collect::<Vec<&AggregatorConfig>>();
+        let mut proposals = Vec::new();
+        for manga in mangas
+            .iter()
+            .filter(|manga| is_on_domain(manga.url(), dead_domain))
+        {
+            let slugs = candidate_slugs(manga);
+            for aggregator in alive_aggregators.iter() {
+                match find_on_aggregator(aggregator, &slugs) {
+                    Ok(Some(new_url)) => {
+                        proposals.push(MigrationProposal {
+                            manga_id: manga.id(),
+                            title: manga.title().to_string(),
+                            old_url: manga.url().to_string(),
+                            new_url,
+                            aggregator: aggregator.name.clone(),
+                        });
+                        break;
+                    }
+                    Ok(None) => {}
+                    Err(e) => println!(
+                        "WARNING: propose_migrations(id={}): aggregator '{}' failed: {}",
+                        manga.id(),
+                        aggregator.name,
+                        e
+                    ),
+                }
+            }
+        }
+        proposals
+    }
+
+    // new base url, and url_with_chapter re-derived on top of it (the old chapter url is on the dead site)
+    pub fn apply_proposal(manga: &MangaModel, proposal: &MigrationProposal) -> MangaModel {
+        let mut migrated = manga.clone();
+        migrated.set_url(proposal.new_url.clone());
+        migrated.set_url_with_chapter(manga.chapter().map(|chapter| {
+            format!(
+                "{}-chapter-{}/",
+                proposal.new_url.trim_end_matches('/'),
+                chapter.replace(".", "-")
+            )
+        }));
+        migrated
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_slugify() {
+            assert_eq!(
+                slugify("Tsuki ga Michibiku Isekai Douchuu Chapter 12"),
+                "tsuki-ga-michibiku-isekai-douchuu"
+            );
+            assert_eq!(
+                slugify("Boukensha ni Natte mo、 Sekaijuu no"),
+                "boukensha-ni-natte-mo-sekaijuu-no"
+            );
+            assert_eq!(slugify("gate-chapter-12-1"), "gate");
+        }
+
+        #[test]
+        fn test_is_on_domain() {
+            assert!(is_on_domain(
+                "https://dead.example.com/gate/",
+                "example.com"
+            ));
+            assert!(is_on_domain("https://example.com/gate/", "example.com"));
+            assert!(!is_on_domain("https://notexample.com/gate/", "example.com"));
+        }
+
+        #[test]
+        fn test_find_in_search_results() {
+            let html = r#"<a href="/manga/gate-chapter-3/">3</a>
+                <a href="https://other.example.org/manga/gate/">elsewhere</a>
+                <a href="/manga/gate/">Gate</a>"#;
+            assert_eq!(
+                find_in_search_results(html, "https://example.com/manga/", "gate"),
+                Some("https://example.com/manga/gate/".to_string())
+            );
+        }
+
+        #[test]
+        fn test_apply_proposal() {
+            let manga = MangaModel::with_values(
+                1,
+                "Gate".to_owned(),
+                None,
+                "https://dead.example.com/gate/".to_owned(),
+                Some("https://dead.example.com/gate-chapter-12-1/".to_string()),
+                Some("12.1".to_string()),
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+            )
+            .unwrap();
+            assert_eq!(candidate_slugs(&manga), vec!["gate".to_string()]);
+            let proposal = MigrationProposal {
+                manga_id: 1,
+                title: "Gate".to_string(),
+                old_url: manga.url().to_string(),
+                new_url: "https://alive.example.org/manga/gate/".to_string(),
+                aggregator: "alive".to_string(),
+            };
+            let migrated = apply_proposal(&manga, &proposal);
+            assert_eq!(migrated.url(), "https://alive.example.org/manga/gate/");
+            assert_eq!(
+                migrated.url_with_chapter(),
+                Some("https://alive.example.org/manga/gate-chapter-12-1/".to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Named snapshots of the database (`manga snapshot create|restore|list`), to checkpoint before experiments such
+// as a bulk dedupe or a new site migration.  Each snapshot is two files in the snapshot directory (default
+// "snapshots", or snapshot_dir in the config):
+//      <name>.sqlite3.lz4  a consistent copy of the database (VACUUM INTO), lz4 compressed with its size prepended
+//      <name>.json         {"name":"before-dedupe","created_millis":1694008642000,"database":"漫画.sqlite3",
+//                           "manga_count":812,"size_bytes":1171456,"compressed_bytes":402113}
+// Restoring puts the copy in place of the database, the current one kept as "<db>.bak" the way sync pull does.
+pub mod snapshots {
+    use serde::{Deserialize, Serialize};
+    use std::{fs, path::PathBuf};
+
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+
+    pub const K_DEFAULT_SNAPSHOT_DIR: &str = "snapshots";
+    const K_SNAPSHOT_SUFFIX: &str = ".sqlite3.lz4";
+    const K_METADATA_SUFFIX: &str = ".json";
+    const K_BACKUP_SUFFIX: &str = ".bak";
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct SnapshotMetadata {
+        pub name: String,
+        pub created_millis: i64,
+        pub database: String, // the database it was taken of
+        pub manga_count: usize,
+        pub size_bytes: u64,
+        pub compressed_bytes: u64,
+    }
+
+    // names become file names: letters, digits, '-', '_' and '.', never a path
+    fn check_name(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let is_valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+        match is_valid {
+            true => Ok(()),
+            false => Err(format!(
+                "snapshot: invalid name '{}', expected letters, digits, '-', '_' or '.'",
+                name
+            )
+            .into()),
+        }
+    }
+
+    fn snapshot_path(snapshot_dir: &str, name: &str) -> PathBuf {
+        PathBuf::from(snapshot_dir).join(format!("{}{}", name, K_SNAPSHOT_SUFFIX))
+    }
+
+    fn metadata_path(snapshot_dir: &str, name: &str) -> PathBuf {
+        PathBuf::from(snapshot_dir).join(format!("{}{}", name, K_METADATA_SUFFIX))
+    }
+
+    // an existing snapshot of the same name is only replaced when told to
+    pub fn create(
+        db_full_paths: &str,
+        snapshot_dir: &str,
+        name: &str,
+        replace: bool,
+    ) -> Result<SnapshotMetadata, Box<dyn std::error::Error>> {
+        check_name(name)?;
+        let path = snapshot_path(snapshot_dir, name);
+        if path.exists() && !replace {
+            return Err(format!(
+                "snapshot: '{}' already exists in '{}', use --force to replace it",
+                name, snapshot_dir
+            )
+            .into());
+        }
+        fs::create_dir_all(snapshot_dir)?;
+        model_sqlite3_manga::create_tables(db_full_paths)?;
+
+        // a consistent copy, even while someone else writes to the database
+        let copy_path = PathBuf::from(snapshot_dir).join(format!("{}.tmp", name));
+        fs::remove_file(&copy_path).unwrap_or_default();
+        let conn = model_sqlite3_connection::open(db_full_paths)?;
+        conn.execute(
+            "VACUUM INTO ?1",
+            rusqlite::params![copy_path.to_string_lossy()],
+        )?;
+        let manga_count = conn.query_row("SELECT COUNT(*) FROM manga", [], |row| {
+            row.get::<usize, i64>(0)
+        })? as usize;
+        let bytes = fs::read(&copy_path)?;
+        fs::remove_file(&copy_path)?;
+        let compressed = lz4_flex::block::compress_prepend_size(&bytes);
+        fs::write(&path, &compressed)?;
+
+        let metadata = SnapshotMetadata {
+            name: name.to_string(),
+            created_millis: chrono::Utc::now().timestamp_millis(),
+            database: db_full_paths.to_string(),
+            manga_count,
+            size_bytes: bytes.len() as u64,
+            compressed_bytes: compressed.len() as u64,
+        };
+        fs::write(
+            metadata_path(snapshot_dir, name),
+            serde_json::to_string_pretty(&metadata)?,
+        )?;
+        Ok(metadata)
+    }
+
+    // oldest first; a directory which does not exist has none
+    pub fn list(snapshot_dir: &str) -> Result<Vec<SnapshotMetadata>, Box<dyn std::error::Error>> {
+        let entries = match fs::read_dir(snapshot_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let is_metadata = path
+                .file_name()
+                .is_some_and(|file_name| file_name.to_string_lossy().ends_with(K_METADATA_SUFFIX));
+            if is_metadata {
+                let metadata: SnapshotMetadata = serde_json::from_str(&fs::read_to_string(&path)?)?;
+                if snapshot_path(snapshot_dir, &metadata.name).exists() {
+                    snapshots.push(metadata);
+                }
+            }
+        }
+        snapshots.sort_by_key(|metadata| metadata.created_millis);
+        Ok(snapshots)
+    }
+
+    pub fn restore(
+        db_full_paths: &str,
+        snapshot_dir: &str,
+        name: &str,
+    ) -> Result<SnapshotMetadata, Box<dyn std::error::Error>> {
+        check_name(name)?;
+        let path = snapshot_path(snapshot_dir, name);
+        if !path.exists() {
+            return Err(format!("snapshot: no snapshot '{}' in '{}'", name, snapshot_dir).into());
+        }
+        let metadata: SnapshotMetadata =
+            serde_json::from_str(&fs::read_to_string(metadata_path(snapshot_dir, name))?)?;
+        let bytes = lz4_flex::block::decompress_size_prepended(&fs::read(&path)?)
+            .map_err(|e| format!("snapshot: '{}' is corrupt: {}", path.display(), e))?;
+
+        // written next to the database first, so that a failure leaves the database as it was
+        let restore_path = format!("{}.restore", db_full_paths);
+        fs::write(&restore_path, &bytes)?;
+        if PathBuf::from(db_full_paths).exists() {
+            fs::copy(
+                db_full_paths,
+                format!("{}{}", db_full_paths, K_BACKUP_SUFFIX),
+            )?;
+        }
+        fs::rename(&restore_path, db_full_paths)?;
+        Ok(metadata)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::model_manga::model_manga::MangaModel;
+
+        #[test]
+        fn test_snapshots() {
+            let db_full_paths = "samples/test_snapshots.db";
+            let snapshot_dir = "samples/test_snapshots";
+            fs::remove_dir_all(snapshot_dir).unwrap_or_default();
+            for suffix in ["", K_BACKUP_SUFFIX] {
+                fs::remove_file(format!("{}{}", db_full_paths, suffix)).unwrap_or_default();
+            }
+            model_sqlite3_manga::create_tables(db_full_paths).unwrap();
+            let gate =
+                MangaModel::new_from_required_elements("Gate", "https://example.com/gate/", 1)
+                    .unwrap();
+            model_sqlite3_manga::insert_manga(db_full_paths, &gate).unwrap();
+
+            assert!(list(snapshot_dir).unwrap().is_empty());
+            assert!(create(db_full_paths, snapshot_dir, "../escape", false).is_err());
+            let metadata = create(db_full_paths, snapshot_dir, "before-dedupe", false).unwrap();
+            assert_eq!(metadata.manga_count, 1);
+            assert!(metadata.compressed_bytes < metadata.size_bytes);
+            assert!(create(db_full_paths, snapshot_dir, "before-dedupe", false).is_err());
+            assert_eq!(list(snapshot_dir).unwrap(), vec![metadata]);
+
+            model_sqlite3_manga::delete_manga(db_full_paths, 1).unwrap();
+            restore(db_full_paths, snapshot_dir, "before-dedupe").unwrap();
+            assert_eq!(
+                model_sqlite3_manga::select_manga_by_id(db_full_paths, 1)
+                    .unwrap()
+                    .title(),
+                "Gate"
+            );
+            assert!(restore(db_full_paths, snapshot_dir, "no-such-snapshot").is_err());
+
+            fs::remove_dir_all(snapshot_dir).unwrap();
+            for suffix in ["", K_BACKUP_SUFFIX] {
+                fs::remove_file(format!("{}{}", db_full_paths, suffix)).unwrap_or_default();
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+// Three-way merge of the legacy import (`-i bookmarks.json -c 漫画.csv -d 漫画.sqlite3 --merge`), instead of
+// "CSV first, then bookmarks" where neither ever overwrites a row already in the database (so edits made in the
+// CSV were silently dropped).  Rows of the three sources are matched by the GUID of the bookmark, else by
+// normalized url, and each field is merged on its own between the sources which carry it:
+//      bookmarks   title, url, url_with_chapter, chapter           (last_update: lastModified of the bookmark)
+//      CSV         the same, plus notes and tags                   (last_update column)
+//      database    all of them                                     (updated_at)
+// When the sources disagree on a field, the value of the most recently modified source wins (ties keep the
+// database's); when all three disagree there is no telling which edit is meant, so the database keeps its
+// value and the field is listed as a conflict.  Rows in no database yet are inserted.
+// The [merge] table of the config names, per field, a source which wins whenever it has the row instead (i.e.
+// `notes = "csv"` for notes only ever edited in the CSV), see config::MergePolicyConfig.
+pub mod source_merge {
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::config::config::{MergePolicyConfig, MergeRule};
+    use crate::manga_diff::manga_diff;
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_bookmark_origin::model_sqlite3_bookmark_origin::{
+        self, BookmarkOrigin,
+    };
+    use crate::model_sqlite3_connection::model_sqlite3_connection;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum Source {
+        Bookmarks,
+        Csv,
+        Database,
+    }
+
+    // fields merged, and which sources carry each
+    const K_MERGED_FIELDS: [&str; 6] = [
+        "title",
+        "url",
+        "url_with_chapter",
+        "chapter",
+        "notes",
+        "tags",
+    ];
+    const K_BOOKMARK_FIELDS: [&str; 4] = ["title", "url", "url_with_chapter", "chapter"];
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct MergeConflict {
+        pub id: i64,
+        pub title: String,
+        pub field: &'static str,
+        pub values: Vec<(Source, String)>, // bookmarks, CSV, database
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+    pub struct MergeOutcome {
+        pub updated: Vec<MangaModel>, // rows of the database with the fields which won merged in
+        pub inserted: Vec<MangaModel>, // in the bookmarks and/or CSV only
+        pub conflicts: Vec<MergeConflict>,
+    }
+
+    // the same row in each source
+    #[derive(Debug, Default)]
+    struct MergeEntry {
+        possible_bookmark: Option<MangaModel>,
+        possible_csv: Option<MangaModel>,
+        possible_database: Option<MangaModel>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    enum EntryKey {
+        Database(i64),
+        Url(String), // normalized
+    }
+
+    // "2023-09-06T13:57:22" of bookmarks and CSV, or "2023-09-06 13:57:22" written by the database, as UTC
+    pub fn last_update_millis(manga: &MangaModel) -> Option<i64> {
+        let last_update = manga.last_update()?;
+        ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+            .iter()
+            .find_map(|format| chrono::NaiveDateTime::parse_from_str(&last_update, format).ok())
+            .map(|datetime| datetime.and_utc().timestamp_millis())
+    }
+
+    fn modified_millis(source: Source, manga: &MangaModel) -> i64 {
+        match source {
+            Source::Database => manga
+                .updated_at_millis()
+                .or(manga.last_update_millis())
+                .unwrap_or(0),
+            Source::Bookmarks | Source::Csv => last_update_millis(manga).unwrap_or(0),
+        }
+    }
+
+    fn carries(source: Source, field: &str) -> bool {
+        match source {
+            Source::Bookmarks => K_BOOKMARK_FIELDS.contains(&field),
+            Source::Csv | Source::Database => true,
+        }
+    }
+
+    fn field_value(manga: &MangaModel, field: &str) -> String {
+        manga_diff::field_values(manga)
+            .into_iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value)
+            .unwrap_or_default()
+    }
+
+    // field of merged taken over from winner
+    fn take_field(merged: &mut MangaModel, winner: &MangaModel, field: &str) {
+        match field {
+            "title" => {
+                merged.set_title(winner.title().to_string());
+                merged.set_title_romanized(winner.title_romanized());
+            }
+            "url" => merged.set_url(winner.url().to_string()),
+            "url_with_chapter" => merged.set_url_with_chapter(winner.url_with_chapter()),
+            "chapter" => merged.set_chapter(winner.chapter()),
+            "notes" => merged.set_notes(winner.notes()),
+            "tags" => merged.set_tags(winner.tags().iter().map(|tag| tag.to_string()).collect()),
+            _ => {}
+        }
+    }
+
+    fn preferred_source(policy: &MergePolicyConfig, field: &str) -> Option<Source> {
+        match policy.rule(field) {
+            MergeRule::Newest => None,
+            MergeRule::Bookmarks => Some(Source::Bookmarks),
+            MergeRule::Csv => Some(Source::Csv),
+            MergeRule::Database => Some(Source::Database),
+        }
+    }
+
+    fn merge_entry(entry: &MergeEntry, policy: &MergePolicyConfig, outcome: &mut MergeOutcome) {
+        let sources: Vec<(Source, &MangaModel)> = [
+            (Source::Bookmarks, entry.possible_bookmark.as_ref()),
+            (Source::Csv, entry.possible_csv.as_ref()),
+            (Source::Database, entry.possible_database.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(source, possible_manga)| possible_manga.map(|manga| (source, manga)))
+        .collect();
+        // newest first, the database first among equals
+        let mut by_recency = sources.clone();
+        by_recency.sort_by_key(|(source, manga)| {
+            (
+                std::cmp::Reverse(modified_millis(*source, manga)),
+                *source != Source::Database,
+            )
+        });
+        let (_, newest) = by_recency[0];
+        let mut merged = match entry.possible_database.as_ref() {
+            Some(database) => database.clone(),
+            None => newest.clone(),
+        };
+
+        for field in K_MERGED_FIELDS {
+            let values: Vec<(Source, &MangaModel, String)> = by_recency
+                .iter()
+                .filter(|(source, _)| carries(*source, field))
+                .map(|(source, manga)| (*source, *manga, field_value(manga, field)))
+                .collect();
+            let mut distinct: Vec<&String> = values.iter().map(|(_, _, value)| value).collect();
+            distinct.sort();
+            distinct.dedup();
+            if distinct.len() <= 1 {
+                continue;
+            }
+            let possible_preferred = preferred_source(policy, field)
+                .and_then(|preferred| values.iter().find(|(source, _, _)| *source == preferred));
+            if let Some((_, winner, _)) = possible_preferred {
+                take_field(&mut merged, winner, field);
+                continue;
+            }
+            if values.len() == 3 && distinct.len() == 3 {
+                let mut conflict_values: Vec<(Source, String)> = values
+                    .iter()
+                    .map(|(source, _, value)| (*source, value.clone()))
+                    .collect();
+                conflict_values.sort_by_key(|(source, _)| *source as u8);
+                outcome.conflicts.push(MergeConflict {
+                    id: merged.id(),
+                    title: merged.title().to_string(),
+                    field,
+                    values: conflict_values,
+                });
+                continue;
+            }
+            if let Some((_, winner, _)) = values.first() {
+                take_field(&mut merged, winner, field);
+            }
+        }
+
+        match entry.possible_database.as_ref() {
+            Some(database) => {
+                if !manga_diff::field_diffs(database, &merged).is_empty() {
+                    outcome.updated.push(merged);
+                }
+            }
+            None => outcome.inserted.push(merged),
+        }
+    }
+
+    pub fn merge(
+        bookmarks: &[MangaModel],
+        csv_rows: &[MangaModel],
+        database: &[MangaModel],
+        policy: &MergePolicyConfig,
+    ) -> MergeOutcome {
+        let mut entries: BTreeMap<EntryKey, MergeEntry> = BTreeMap::new();
+        let mut key_by_guid: HashMap<String, EntryKey> = HashMap::new();
+        let mut key_by_url: HashMap<String, EntryKey> = HashMap::new();
+        for manga in database.iter() {
+            let key = EntryKey::Database(manga.id());
+            if let Some(guid) = manga.bookmark_guid() {
+                key_by_guid.entry(guid).or_insert(key.clone());
+            }
+            key_by_url
+                .entry(model_sqlite3_manga::normalized_url(manga.url()))
+                .or_insert(key.clone());
+            entries.entry(key).or_default().possible_database = Some(manga.clone());
+        }
+        for bookmark in bookmarks.iter() {
+            let normalized = model_sqlite3_manga::normalized_url(bookmark.url());
+            let key = bookmark
+                .bookmark_guid()
+                .and_then(|guid| key_by_guid.get(&guid).cloned())
+                .or_else(|| key_by_url.get(&normalized).cloned())
+                .unwrap_or(EntryKey::Url(normalized.clone()));
+            // the CSV may know the bookmark by its new url only
+            key_by_url.entry(normalized).or_insert(key.clone());
+            entries.entry(key).or_default().possible_bookmark = Some(bookmark.clone());
+        }
+        for csv_row in csv_rows.iter() {
+            let normalized = model_sqlite3_manga::normalized_url(csv_row.url());
+            let key = key_by_url
+                .get(&normalized)
+                .cloned()
+                .unwrap_or(EntryKey::Url(normalized));
+            entries.entry(key).or_default().possible_csv = Some(csv_row.clone());
+        }
+
+        let mut outcome = MergeOutcome::default();
+        for entry in entries.values() {
+            merge_entry(entry, policy, &mut outcome);
+        }
+        outcome
+    }
+
+    // writes the outcome of merge(): a changed url is recorded in url_history (as "merge"), merged tags replace
+    // those of the row; origins are those of the bookmarks merged
+    pub fn apply(
+        db_full_paths: &str,
+        outcome: &MergeOutcome,
+        origins: &[BookmarkOrigin],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut written = Vec::new();
+        for manga in outcome.updated.iter() {
+            let current = model_sqlite3_manga::select_manga_by_id(db_full_paths, manga.id())?;
+            if current.url() != manga.url() {
+                model_sqlite3_manga::update_url(db_full_paths, manga.id(), manga.url(), "merge")?;
+            }
+            let mut stamped = manga.clone();
+            stamped.set_last_update(None); // update_manga() stamps them
+            stamped.set_last_update_millis(None);
+            model_sqlite3_manga::update_manga(db_full_paths, &stamped)?;
+            let tags: Vec<String> = manga.tags().iter().map(|tag| tag.to_string()).collect();
+            if tags != current.tags() {
+                model_sqlite3_manga::replace_tags_of_manga(db_full_paths, manga.id(), &tags)?;
+            }
+            written.push(manga.clone());
+        }
+        let mut conn = model_sqlite3_connection::open(db_full_paths)?;
+        written
+            .extend(model_sqlite3_manga::bulk_insert_manga(&mut conn, &outcome.inserted)?.mangas);
+        model_sqlite3_bookmark_origin::upsert_bookmark_origins(&mut conn, origins, &written)?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn manga(id: i64, title: &str, url: &str, last_update: &str) -> MangaModel {
+            let mut manga = MangaModel::new_from_required_elements(title, url, id).unwrap();
+            manga.set_last_update(Some(last_update.to_string()));
+            manga
+        }
+
+        #[test]
+        fn test_merge() {
+            let mut db_gate = manga(1, "Gate", "https://example.com/gate/", "");
+            db_gate.set_chapter(Some("10".to_string()));
+            db_gate.set_notes(Some("JSDF".to_string()));
+            db_gate.set_bookmark_guid(Some("guid-gate".to_string()));
+            db_gate.set_updated_at_millis(Some(
+                chrono::NaiveDate::from_ymd_opt(2023, 9, 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .timestamp_millis(),
+            ));
+            let db_akira = manga(2, "Akira", "https://example.com/akira/", "");
+
+            // bookmark renamed (found by its GUID) after the database, the CSV edited the notes later still, and
+            // the chapter is different everywhere
+            let mut bookmark_gate = manga(
+                101,
+                "Gate (JSDF)",
+                "https://example.com/gate/",
+                "2023-09-02T00:00:00",
+            );
+            bookmark_gate.set_bookmark_guid(Some("guid-gate".to_string()));
+            bookmark_gate.set_chapter(Some("11".to_string()));
+            let mut csv_gate = manga(
+                102,
+                "Gate",
+                "https://example.com/gate/",
+                "2023-09-03T00:00:00",
+            );
+            csv_gate.set_chapter(Some("12".to_string()));
+            csv_gate.set_notes(Some("Isekai with the JSDF".to_string()));
+            let csv_blame = manga(
+                103,
+                "Blame!",
+                "https://example.com/blame/",
+                "2023-09-03T00:00:00",
+            );
+
+            let outcome = merge(
+                &[bookmark_gate.clone()],
+                &[csv_gate.clone(), csv_blame.clone()],
+                &[db_gate.clone(), db_akira.clone()],
+                &MergePolicyConfig::default(),
+            );
+            assert_eq!(outcome.updated.len(), 1);
+            let gate = &outcome.updated[0];
+            assert_eq!(gate.id(), 1);
+            assert_eq!(gate.notes(), Some("Isekai with the JSDF".to_string()));
+            assert_eq!(gate.chapter(), Some("10".to_string()));
+            assert_eq!(gate.title(), "Gate");
+            assert_eq!(
+                outcome.conflicts,
+                vec![MergeConflict {
+                    id: 1,
+                    title: "Gate".to_string(),
+                    field: "chapter",
+                    values: vec![
+                        (Source::Bookmarks, "11".to_string()),
+                        (Source::Csv, "12".to_string()),
+                        (Source::Database, "10".to_string()),
+                    ],
+                }]
+            );
+            assert_eq!(
+                outcome
+                    .inserted
+                    .iter()
+                    .map(|manga| manga.title())
+                    .collect::<Vec<&str>>(),
+                vec!["Blame!"]
+            );
+
+            // with a policy: the title of the bookmark although the CSV is newer, and no conflict on the chapter
+            let policy = MergePolicyConfig {
+                title: MergeRule::Bookmarks,
+                chapter: MergeRule::Database,
+                notes: MergeRule::Bookmarks, // which bookmarks do not carry: newest
+                ..MergePolicyConfig::default()
+            };
+            let outcome = merge(
+                &[bookmark_gate],
+                &[csv_gate, csv_blame],
+                &[db_gate, db_akira],
+                &policy,
+            );
+            let gate = &outcome.updated[0];
+            assert_eq!(gate.title(), "Gate (JSDF)");
+            assert_eq!(gate.chapter(), Some("10".to_string()));
+            assert_eq!(gate.notes(), Some("Isekai with the JSDF".to_string()));
+            assert!(outcome.conflicts.is_empty());
+            assert_eq!(outcome.inserted.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,479 @@
+// Derived numbers about the whole collection (as opposed to a single MangaModel), printed by
+// `manga stats` either as text or as JSON (`--json`) for plotting or scripting externally.
+pub mod stats {
+    use chrono::{Datelike, TimeZone};
+    use serde::Serialize;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::model_sqlite3_link_checks::model_sqlite3_link_checks::{
+        LinkCheck, K_STATUS_UNREACHABLE,
+    };
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions::ReadingSession;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct UnreadEntry {
+        pub id: i64,
+        pub title: String,
+        pub chapter: Option<String>,
+        pub latest_available_chapter: Option<String>,
+        pub unread: u32,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+    pub struct CollectionStats {
+        pub total: usize,
+        pub with_feed_url: usize,
+        pub checked: usize, // update checker found at least a chapter
+        pub with_unread: usize,
+        pub total_unread: u32,
+        pub most_unread: Vec<UnreadEntry>, // most piled-up first
+    }
+
+    // unread is 0 when the update checker has not found anything (yet) for this manga
+    pub fn to_unread_entry(manga: &MangaModel) -> UnreadEntry {
+        UnreadEntry {
+            id: manga.id(),
+            title: manga.title().to_string(),
+            chapter: manga.chapter(),
+            latest_available_chapter: manga.latest_available_chapter(),
+            unread: manga.unread_count().unwrap_or(0),
+        }
+    }
+
+    // only series with at least 1 unread chapter, most unread first (ties by title so output is stable)
+    pub fn unread_entries(mangas: &[MangaModel]) -> Vec<UnreadEntry> {
+        let mut entries = mangas
+            .iter()
+            .map(to_unread_entry)
+            .filter(|entry| entry.unread > 0)
+            .collect::<Vec<UnreadEntry>>();
+        entries.sort_by(|a, b| b.unread.cmp(&a.unread).then(a.title.cmp(&b.title)));
+        entries
+    }
+
+    pub fn collection_stats(mangas: &[MangaModel], most_unread_count: usize) -> CollectionStats {
+        let mut most_unread = unread_entries(mangas);
+        let stats = CollectionStats {
+            total: mangas.len(),
+            with_feed_url: mangas.iter().filter(|m| m.feed_url().is_some()).count(),
+            checked: mangas.iter().filter(|m| m.unread_count().is_some()).count(),
+            with_unread: most_unread.len(),
+            total_unread: most_unread.iter().map(|entry| entry.unread).sum(),
+            most_unread: Vec::new(),
+        };
+        most_unread.truncate(most_unread_count);
+        CollectionStats {
+            most_unread,
+            ..stats
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct PeriodCount {
+        pub period: String, // i.e. "2023-W36" for weeks, "2023-09" for months
+        pub chapters: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct SeriesCount {
+        pub manga_id: i64,
+        pub title: String,
+        pub chapters: usize,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+    pub struct PaceStats {
+        pub total_chapters: usize,
+        pub per_week: Vec<PeriodCount>, // oldest first, weeks without reading are omitted
+        pub per_month: Vec<PeriodCount>, // oldest first, months without reading are omitted
+        pub longest_streak_days: usize, // consecutive (local) days with at least one chapter read
+        pub longest_streak_start: Option<String>, // "YYYY-MM-DD"
+        pub most_read: Vec<SeriesCount>, // most chapters first
+    }
+
+    fn to_local_date(millis: i64) -> Option<chrono::NaiveDate> {
+        chrono::Local
+            .timestamp_millis_opt(millis)
+            .single()
+            .map(|datetime| datetime.date_naive())
+    }
+
+    fn count_by_period(
+        dates: &[chrono::NaiveDate],
+        to_period: impl Fn(&chrono::NaiveDate) -> String,
+    ) -> Vec<PeriodCount> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for date in dates {
+            *counts.entry(to_period(date)).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(period, chapters)| PeriodCount { period, chapters })
+            .collect()
+    }
+
+    // returns (length, first day) of the longest run of consecutive days; earliest run wins ties
+    fn longest_streak(dates: &[chrono::NaiveDate]) -> (usize, Option<chrono::NaiveDate>) {
+        let unique_dates = dates
+            .iter()
+            .cloned()
+            .collect::<BTreeSet<chrono::NaiveDate>>();
+        let mut longest: (usize, Option<chrono::NaiveDate>) = (0, None);
+        let mut current: (usize, Option<chrono::NaiveDate>) = (0, None);
+        let mut possible_previous: Option<chrono::NaiveDate> = None;
+        for date in unique_dates {
+            current = match possible_previous {
+                Some(previous) if previous.succ_opt() == Some(date) => (current.0 + 1, current.1),
+                _ => (1, Some(date)),
+            };
+            if current.0 > longest.0 {
+                longest = current;
+            }
+            possible_previous = Some(date);
+        }
+        longest
+    }
+
+    // titles maps manga_id to title, sessions of manga which are no longer in the database are still counted
+    pub fn pace_stats(
+        sessions: &[ReadingSession],
+        titles: &HashMap<i64, String>,
+        most_read_count: usize,
+    ) -> PaceStats {
+        let dates = sessions
+            .iter()
+            .filter_map(|session| to_local_date(session.read_at_millis))
+            .collect::<Vec<chrono::NaiveDate>>();
+        let (longest_streak_days, possible_streak_start) = longest_streak(&dates);
+
+        let mut chapters_per_manga: HashMap<i64, usize> = HashMap::new();
+        for session in sessions {
+            *chapters_per_manga.entry(session.manga_id).or_insert(0) += 1;
+        }
+        let mut most_read = chapters_per_manga
+            .into_iter()
+            .map(|(manga_id, chapters)| SeriesCount {
+                manga_id,
+                title: titles
+                    .get(&manga_id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("(deleted id={})", manga_id)),
+                chapters,
+            })
+            .collect::<Vec<SeriesCount>>();
+        most_read.sort_by(|a, b| {
+            b.chapters
+                .cmp(&a.chapters)
+                .then(a.manga_id.cmp(&b.manga_id))
+        });
+        most_read.truncate(most_read_count);
+
+        PaceStats {
+            total_chapters: sessions.len(),
+            per_week: count_by_period(&dates, |date| {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }),
+            per_month: count_by_period(&dates, |date| date.format("%Y-%m").to_string()),
+            longest_streak_days,
+            longest_streak_start: possible_streak_start
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+            most_read,
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct SiteAvailability {
+        pub domain: String,
+        pub entries: usize,     // distinct manga checked on this domain
+        pub failing: usize,     // entries whose latest check failed
+        pub latest_status: u16, // of the most recent check on this domain, 0 when unreachable
+        pub avg_response_millis: Option<i64>, // reachable checks only
+        pub current_failure_streak: usize, // consecutive failed checks up to the most recent one
+        pub longest_failure_streak: usize,
+        pub last_checked_millis: i64,
+    }
+
+    // checks must be oldest first (as returned by select_link_checks()); grouped by the domain of the url
+    // that was checked, so history stays with the old site after a url has moved.  Most degraded first.
+    pub fn site_availability(checks: &[LinkCheck]) -> Vec<SiteAvailability> {
+        let mut checks_per_domain: BTreeMap<String, Vec<&LinkCheck>> = BTreeMap::new();
+        for check in checks {
+            let domain = match url::Url::parse(&check.url) {
+                Ok(url) => url.host_str().unwrap_or_default().to_string(),
+                Err(_) => continue,
+            };
+            checks_per_domain.entry(domain).or_default().push(check);
+        }
+
+        let mut sites = checks_per_domain
+            .into_iter()
+            .map(|(domain, domain_checks)| {
+                let mut latest_per_manga: HashMap<i64, &LinkCheck> = HashMap::new();
+                let (mut current_failure_streak, mut longest_failure_streak) = (0, 0);
+                for check in domain_checks.iter() {
+                    latest_per_manga.insert(check.manga_id, check);
+                    current_failure_streak = match check.is_failure() {
+                        true => current_failure_streak + 1,
+                        false => 0,
+                    };
+                    longest_failure_streak = longest_failure_streak.max(current_failure_streak);
+                }
+                let reachable_millis = domain_checks
+                    .iter()
+                    .filter(|check| check.status != K_STATUS_UNREACHABLE)
+                    .map(|check| check.response_millis)
+                    .collect::<Vec<i64>>();
+                let latest = domain_checks.last().unwrap(); // every domain has at least one check
+                SiteAvailability {
+                    domain,
+                    entries: latest_per_manga.len(),
+                    failing: latest_per_manga
+                        .values()
+                        .filter(|check| check.is_failure())
+                        .count(),
+                    latest_status: latest.status,
+                    avg_response_millis: match reachable_millis.is_empty() {
+                        true => None,
+                        false => Some(
+                            reachable_millis.iter().sum::<i64>() / reachable_millis.len() as i64,
+                        ),
+                    },
+                    current_failure_streak,
+                    longest_failure_streak,
+                    last_checked_millis: latest.checked_at_millis,
+                }
+            })
+            .collect::<Vec<SiteAvailability>>();
+        sites.sort_by(|a, b| {
+            b.current_failure_streak
+                .cmp(&a.current_failure_streak)
+                .then(b.failing.cmp(&a.failing))
+                .then(a.domain.cmp(&b.domain))
+        });
+        sites
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct DomainReport {
+        pub domain: String,
+        pub series: usize,
+        pub with_unread: usize,
+        pub total_unread: u32,
+        pub last_checked_millis: Option<i64>, // None when no link check was recorded on this domain
+        pub last_success_millis: Option<i64>, // most recent check which did not fail
+    }
+
+    // series are grouped by the domain of their current url, checks (oldest first, as returned by
+    // select_link_checks()) by the domain of the url that was checked; domains without series are left out.
+    // Most series first, ties by domain.
+    pub fn domain_report(mangas: &[MangaModel], checks: &[LinkCheck]) -> Vec<DomainReport> {
+        let mut reports: BTreeMap<String, DomainReport> = BTreeMap::new();
+        for manga in mangas {
+            let domain = model_sqlite3_manga::url_domain(manga.url());
+            if domain.is_empty() {
+                continue;
+            }
+            let report = reports
+                .entry(domain.clone())
+                .or_insert_with(|| DomainReport {
+                    domain,
+                    series: 0,
+                    with_unread: 0,
+                    total_unread: 0,
+                    last_checked_millis: None,
+                    last_success_millis: None,
+                });
+            let unread = manga.unread_count().unwrap_or(0);
+            report.series += 1;
+            report.with_unread += (unread > 0) as usize;
+            report.total_unread += unread;
+        }
+        for check in checks {
+            let possible_report = reports.get_mut(&model_sqlite3_manga::url_domain(&check.url));
+            if let Some(report) = possible_report {
+                report.last_checked_millis = Some(check.checked_at_millis);
+                if !check.is_failure() {
+                    report.last_success_millis = Some(check.checked_at_millis);
+                }
+            }
+        }
+
+        let mut reports = reports.into_values().collect::<Vec<DomainReport>>();
+        reports.sort_by(|a, b| b.series.cmp(&a.series).then(a.domain.cmp(&b.domain)));
+        reports
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn make_sample_row(id: i64, chapter: &str, possible_latest: Option<&str>) -> MangaModel {
+            let mut manga = MangaModel::with_values(
+                id,
+                format!("manga {}", id),
+                None,
+                format!("https://example.com/manga-{}/", id),
+                None,
+                Some(chapter.to_string()),
+                None,
+                None,
+                None,
+                Vec::new(),
+                None,
+            )
+            .unwrap();
+            manga.set_latest_available_chapter(possible_latest.map(|s| s.to_string()));
+            manga
+        }
+
+        #[test]
+        fn test_collection_stats() {
+            let mangas = vec![
+                make_sample_row(1, "10", Some("12")),
+                make_sample_row(2, "5", Some("5")),
+                make_sample_row(3, "1", Some("21")),
+                make_sample_row(4, "1", None),
+            ];
+            let stats = collection_stats(&mangas, 1);
+            assert_eq!(stats.total, 4);
+            assert_eq!(stats.checked, 3);
+            assert_eq!(stats.with_unread, 2);
+            assert_eq!(stats.total_unread, 22);
+            assert_eq!(stats.most_unread.len(), 1);
+            assert_eq!(stats.most_unread[0].id, 3);
+        }
+
+        fn make_session(manga_id: i64, date: &str) -> ReadingSession {
+            // noon local time, so that the date does not shift regardless of the timezone tests run in
+            let datetime = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap();
+            ReadingSession {
+                id: 0,
+                manga_id,
+                chapter: "1".to_string(),
+                read_at_millis: chrono::Local
+                    .from_local_datetime(&datetime)
+                    .unwrap()
+                    .timestamp_millis(),
+            }
+        }
+
+        #[test]
+        fn test_pace_stats() {
+            let sessions = vec![
+                make_session(1, "2023-09-04"), // Monday, W36
+                make_session(1, "2023-09-05"),
+                make_session(2, "2023-09-05"),
+                make_session(1, "2023-09-06"),
+                make_session(2, "2023-10-01"), // Sunday, W39
+                make_session(2, "2023-10-02"), // Monday, W40
+            ];
+            let titles = HashMap::from([(1, "Gate".to_string())]);
+            let pace = pace_stats(&sessions, &titles, 5);
+
+            assert_eq!(pace.total_chapters, 6);
+            assert_eq!(
+                pace.per_month,
+                vec![
+                    PeriodCount {
+                        period: "2023-09".to_string(),
+                        chapters: 4
+                    },
+                    PeriodCount {
+                        period: "2023-10".to_string(),
+                        chapters: 2
+                    },
+                ]
+            );
+            assert_eq!(pace.per_week.len(), 3);
+            assert_eq!(pace.per_week[0].period, "2023-W36");
+            assert_eq!(pace.longest_streak_days, 3);
+            assert_eq!(pace.longest_streak_start, Some("2023-09-04".to_string()));
+            assert_eq!(pace.most_read[0].title, "Gate");
+            assert_eq!(pace.most_read[1].title, "(deleted id=2)");
+        }
+
+        fn make_check(manga_id: i64, url: &str, status: u16, checked_at_millis: i64) -> LinkCheck {
+            LinkCheck {
+                id: 0,
+                manga_id,
+                url: url.to_string(),
+                status,
+                response_millis: 100 * manga_id,
+                checked_at_millis,
+            }
+        }
+
+        #[test]
+        fn test_site_availability() {
+            let checks = vec![
+                make_check(1, "https://alive.example.com/gate/", 200, 1),
+                make_check(2, "https://dying.example.org/a/", 200, 2),
+                make_check(3, "https://dying.example.org/b/", 404, 3),
+                make_check(2, "https://dying.example.org/a/", 0, 4),
+                make_check(3, "https://dying.example.org/b/", 503, 5),
+                make_check(1, "https://alive.example.com/gate/", 200, 6),
+            ];
+            let sites = site_availability(&checks);
+            assert_eq!(sites.len(), 2);
+
+            let dying = &sites[0];
+            assert_eq!(dying.domain, "dying.example.org");
+            assert_eq!(dying.entries, 2);
+            assert_eq!(dying.failing, 2);
+            assert_eq!(dying.latest_status, 503);
+            assert_eq!(dying.current_failure_streak, 3);
+            assert_eq!(dying.longest_failure_streak, 3);
+            // unreachable check has no meaningful response time
+            assert_eq!(dying.avg_response_millis, Some((200 + 300 + 300) / 3));
+
+            let alive = &sites[1];
+            assert_eq!(alive.failing, 0);
+            assert_eq!(alive.current_failure_streak, 0);
+            assert_eq!(alive.last_checked_millis, 6);
+        }
+
+        #[test]
+        fn test_domain_report() {
+            let mut mangas = vec![
+                make_sample_row(1, "10", Some("12")),
+                make_sample_row(2, "5", Some("5")),
+                make_sample_row(3, "1", Some("21")),
+            ];
+            mangas[2].set_url("https://other.example.org/manga-3/".to_string());
+            let checks = vec![
+                make_check(1, "https://example.com/manga-1/", 200, 1),
+                make_check(2, "https://example.com/manga-2/", 503, 2),
+                make_check(3, "https://other.example.org/manga-3/", 0, 3),
+                make_check(4, "https://gone.example.net/x/", 200, 4),
+            ];
+            let reports = domain_report(&mangas, &checks);
+            assert_eq!(
+                reports,
+                vec![
+                    DomainReport {
+                        domain: "example.com".to_string(),
+                        series: 2,
+                        with_unread: 1,
+                        total_unread: 2,
+                        last_checked_millis: Some(2),
+                        last_success_millis: Some(1),
+                    },
+                    DomainReport {
+                        domain: "other.example.org".to_string(),
+                        series: 1,
+                        with_unread: 1,
+                        total_unread: 20,
+                        last_checked_millis: Some(3),
+                        last_success_millis: None,
+                    },
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,3471 @@
+// Subcommands (i.e. `manga feed --out updates.xml`) which are dispatched when the first argument
+// is NOT a flag; the legacy `-i/-o/-c/-d` import remains the default when no subcommand is given
+pub mod subcommands {
+    use std::{
+        collections::HashSet,
+        fs::File,
+        io::{self, BufWriter, Read, Write},
+    };
+
+    use crate::annotate::annotate;
+    use crate::config::config;
+    use crate::cover_cache::cover_cache;
+    use crate::cron_schedule::cron_schedule::CronSchedule;
+    use crate::daemon::daemon;
+    use crate::daemon_lock::daemon_lock;
+    use crate::delta_export::delta_export::{self, DeltaFormat};
+    use crate::enrichment::enrichment;
+    use crate::error::error::Error;
+    use crate::git_export::git_export;
+    #[cfg(feature = "grpc")]
+    use crate::grpc_server::grpc_server;
+    use crate::html_report::html_report;
+    use crate::import_sources::import_sources;
+    use crate::json_rpc::json_rpc;
+    use crate::manga_diff::manga_diff;
+    use crate::mangadex::mangadex;
+    use crate::markdown_table::markdown_table;
+    use crate::model_atom_manga::model_atom_manga;
+    use crate::model_csv_manga::model_csv_manga;
+    use crate::model_manga::model_manga::{Chapter, MangaModel};
+    use crate::model_netscape_bookmarks::model_netscape_bookmarks;
+    use crate::model_opml_manga::model_opml_manga;
+    use crate::model_sqlite3_change_log::model_sqlite3_change_log;
+    use crate::model_sqlite3_link_checks::model_sqlite3_link_checks;
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_archive::model_sqlite3_manga_archive::{self, ArchivedManga};
+    use crate::model_sqlite3_manga_fts::model_sqlite3_manga_fts;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata;
+    use crate::model_sqlite3_reading_sessions::model_sqlite3_reading_sessions;
+    use crate::model_xlsx_manga::model_xlsx_manga;
+    use crate::native_host::native_host;
+    use crate::normalize_pipeline::normalize_pipeline::{self, Pipeline};
+    use crate::notifier::notifier;
+    use crate::remote_sync::remote_sync;
+    use crate::rest_server::rest_server;
+    use crate::service::service::{
+        self, HistoryFormat, MangaSearchRequest, MangaService, SearchHit,
+    };
+    use crate::shadow_database::shadow_database::ShadowDatabase;
+    use crate::shell::shell;
+    use crate::site_migration::site_migration;
+    use crate::snapshots::snapshots;
+    use crate::stats::stats;
+    use crate::tag_expression::tag_expression::{self, TagExpr};
+    use crate::title_repair::title_repair;
+    use crate::tui::tui;
+    use crate::update_checker::update_checker;
+
+    const K_DEFAULT_DB_FILE: &str = "漫画.sqlite3";
+    const K_DEFAULT_FEED_DAYS: i64 = 7;
+    const K_STATS_TOP_COUNT: usize = 10;
+    const K_DEFAULT_REPORT_TITLE: &str = "漫画";
+    const K_DEFAULT_SEARCH_LIMIT: usize = 20;
+    const K_DEFAULT_XLSX_FILE: &str = "漫画.xlsx";
+
+    // returns the value that follows the flag, i.e. ["--out", "updates.xml"] => Some("updates.xml")
+    pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    }
+
+    // values of each time the flag is given, i.e. ["--url-filter", "a", "--url-filter", "b"] => ["a", "b"]
+    pub fn flag_values(args: &[String], flag: &str) -> Vec<String> {
+        args.iter()
+            .enumerate()
+            .filter(|(_, arg)| *arg == flag)
+            .filter_map(|(i, _)| args.get(i + 1))
+            .cloned()
+            .collect()
+    }
+
+    pub fn has_flag(args: &[String], flag: &str) -> bool {
+        args.iter().any(|arg| arg == flag)
+    }
+
+    // first argument is the executable, so a subcommand is the 2nd argument as long as it's not a flag
+    pub fn is_subcommand(args: &[String]) -> bool {
+        match args.get(1) {
+            Some(arg) => !arg.starts_with('-'),
+            None => false,
+        }
+    }
+
+    // `-d <db.sqlite3>` is shared by all subcommands, same default as the legacy import
+    pub fn db_full_paths(args: &[String]) -> String {
+        flag_value(args, "-d").unwrap_or_else(|| K_DEFAULT_DB_FILE.to_string())
+    }
+
+    // `--config <file.toml>` is shared by all subcommands; missing file is same as empty config
+    pub fn load_config(args: &[String]) -> Result<config::Config, Box<dyn std::error::Error>> {
+        config::load(
+            &flag_value(args, "--config")
+                .unwrap_or_else(|| config::K_DEFAULT_CONFIG_FILE.to_string()),
+        )
+    }
+
+    // `--stages <stage,...>` (else [normalize] stages of the config) less `--skip-stages <stage,...>`, cleaning titles
+    // with the [[title_rules]] and sanitizing with [normalize] sanitize of the config; `--scheme-policy
+    // <allow|warn|reject>` (else [normalize] scheme_policy) for urls of other schemes than allowed_schemes, those of
+    // skip_schemes being skipped beforehand;
+    // `--explain` prints what each stage changed for every row
+    pub fn normalize_pipeline_of(
+        args: &[String],
+        config: &config::Config,
+    ) -> Result<Pipeline, Box<dyn std::error::Error>> {
+        let possible_stages = match flag_value(args, "--stages")
+            .map(|stages| vec![stages])
+            .or(config.normalize.stages.clone())
+        {
+            Some(names) => Some(normalize_pipeline::parse_stages(&names)?),
+            None => None,
+        };
+        let skipped_stages = normalize_pipeline::parse_stages(
+            &flag_value(args, "--skip-stages")
+                .into_iter()
+                .collect::<Vec<String>>(),
+        )?;
+        let mut pipeline =
+            Pipeline::new(possible_stages, &skipped_stages, config.title_rules.clone());
+        if let Some(substitutions) = &config.normalize.sanitize {
+            pipeline.sanitize_substitutions = substitutions.clone();
+        }
+        pipeline.scheme_policy = match flag_value(args, "--scheme-policy") {
+            Some(policy) => policy.parse()?,
+            None => config.normalize.scheme_policy,
+        };
+        if let Some(schemes) = &config.normalize.allowed_schemes {
+            pipeline.allowed_schemes = schemes.clone();
+        }
+        if let Some(schemes) = &config.normalize.skip_schemes {
+            pipeline.skip_schemes = schemes.iter().map(|scheme| scheme.to_lowercase()).collect();
+        }
+        pipeline.romanization = config.romanization_options();
+        pipeline.explain = has_flag(args, "--explain");
+        Ok(pipeline)
+    }
+
+    // `--title-collision <update-title|keep-both|ask>`, else title_collision of the config
+    pub fn title_collision_of(
+        args: &[String],
+        config: &config::Config,
+    ) -> Result<config::TitleCollisionPolicy, String> {
+        match flag_value(args, "--title-collision") {
+            Some(policy) => policy.parse(),
+            None => Ok(config.title_collision),
+        }
+    }
+
+    // `--include-folder <folder>`, `--exclude-folder <folder>` and `--url-filter <regex|glob>`, each of which can be
+    // repeated: which bookmarks the import keeps (see service::BookmarkFilter)
+    pub fn bookmark_filter_of(args: &[String]) -> Result<service::BookmarkFilter, String> {
+        let mut bookmark_filter = service::BookmarkFilter {
+            include_folders: flag_values(args, "--include-folder"),
+            exclude_folders: flag_values(args, "--exclude-folder"),
+            ..service::BookmarkFilter::default()
+        };
+        for pattern in flag_values(args, "--url-filter") {
+            bookmark_filter.add_url_pattern(&pattern)?;
+        }
+        Ok(bookmark_filter)
+    }
+
+    // on_title_collision of imports (see model_sqlite3_manga::upsert_manga_with()): true renames the row of id
+    // to the title of manga; ask prompts for each, a prompt which cannot be answered keeps both
+    pub fn renames_on_title_collision(
+        policy: config::TitleCollisionPolicy,
+        id: i64,
+        title: &str,
+        manga: &MangaModel,
+    ) -> bool {
+        match policy {
+            config::TitleCollisionPolicy::UpdateTitle => true,
+            config::TitleCollisionPolicy::KeepBoth => false,
+            config::TitleCollisionPolicy::Ask => confirm(&format!(
+                "'{}' (id={}) is bookmarked as '{}' at {}, rename it (keeping '{}' as an alternative title)?",
+                title,
+                id,
+                manga.title(),
+                manga.url(),
+                title
+            ))
+            .unwrap_or(false),
+        }
+    }
+
+    // with [git_export] in the config: the collection before an import, to tell what the import changed
+    pub fn git_export_snapshot(
+        args: &[String],
+        db_full_paths: &str,
+    ) -> Option<(config::GitExportConfig, Vec<MangaModel>)> {
+        let git_export_config = load_config(args).ok()?.git_export?;
+        match select_all_manga_or_empty(db_full_paths) {
+            Ok(mangas) => Some((git_export_config, mangas)),
+            Err(e) => {
+                eprintln!("git_export: {}", e);
+                None
+            }
+        }
+    }
+
+    // the import already happened, so a failing commit is reported but is not an error of the import
+    pub fn git_export_commit(
+        possible_snapshot: Option<(config::GitExportConfig, Vec<MangaModel>)>,
+        db_full_paths: &str,
+        source: &str,
+    ) {
+        let (git_export_config, before) = match possible_snapshot {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        let result = select_all_manga_or_empty(db_full_paths).and_then(|after| {
+            git_export::commit_exports(&git_export_config, source, &before, &after)
+        });
+        match result {
+            Ok(Some(summary)) => eprintln!(
+                "git_export: committed to '{}': {}",
+                git_export_config.repo, summary
+            ),
+            Ok(None) => eprintln!("git_export: no changes to commit"),
+            Err(e) => eprintln!("git_export: {}", e),
+        }
+    }
+
+    // either `--out <file>` or stdout
+    fn output_writer(args: &[String]) -> Result<Box<dyn Write>, Box<dyn std::error::Error>> {
+        match flag_value(args, "--out") {
+            Some(output_file_paths) => match File::create(&output_file_paths) {
+                Ok(output_file) => Ok(Box::new(BufWriter::new(output_file))),
+                Err(e) => {
+                    Err(format!("Error opening output file '{}': {}", output_file_paths, e).into())
+                }
+            },
+            None => Ok(Box::new(BufWriter::new(io::stdout()))),
+        }
+    }
+
+    pub fn write_usage(usage_writer: &mut dyn Write, executable: &str) -> io::Result<()> {
+        writeln!(
+            usage_writer,
+            "Usage: {} <subcommand> [-d <db.sqlite3>] [--config <config.toml>] [options]",
+            executable
+        )?;
+        writeln!(usage_writer, "Subcommands:")?;
+        writeln!(
+            usage_writer,
+            "  feed [--out <updates.xml>] [--days <N>] [--limit <N>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Atom feed of entries changed in the last N days (default {})",
+            K_DEFAULT_FEED_DAYS
+        )?;
+        writeln!(usage_writer, "  check [--id <N>]")?;
+        writeln!(
+            usage_writer,
+            "      Look for new chapters, via the MangaDex API for MangaDex series, via feed_url"
+        )?;
+        writeln!(usage_writer, "      when set, otherwise by scraping url;")?;
+        writeln!(
+            usage_writer,
+            "      unread chapters are posted to the [[webhooks]] listed in the config"
+        )?;
+        writeln!(usage_writer, "  continue <id> [--open] [--no-verify]")?;
+        writeln!(
+            usage_writer,
+            "      Url of the chapter after the last read one (verified to exist via HEAD)"
+        )?;
+        writeln!(
+            usage_writer,
+            "  daemon [-i <bookmarks.json>] [--check-schedule \"0 */6 * * *\"] [-D] [--force]"
+        )?;
+        writeln!(usage_writer, "         [--metrics-bind <address:port>]")?;
+        writeln!(
+            usage_writer,
+            "      Re-import bookmarks whenever the file changes, and run 'check' on the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      cron-like schedule (or check_schedule in the config); while it runs,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      migrate-site, sync pull and the dedupe of tui refuse to run on the same"
+        )?;
+        writeln!(
+            usage_writer,
+            "      database unless given --force (which also starts a second daemon);"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --metrics-bind <address:port> (or metrics_bind in the config) serves"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Prometheus /metrics there: rows, imports, update checks, HTTP errors and"
+        )?;
+        writeln!(usage_writer, "      new chapters found")?;
+        writeln!(
+            usage_writer,
+            "  list [--unread] [--tag <tag>|--genre <genre>] [--tag-expr <expression>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "       [--domain <domain>] [--since <date>] [--until <date>] [--added]"
+        )?;
+        writeln!(
+            usage_writer,
+            "       [--min-chapter <chapter>] [--max-chapter <chapter>] [--field <key>[=<value>]]"
+        )?;
+        writeln!(usage_writer, "       [--include-archived] [--json]")?;
+        writeln!(
+            usage_writer,
+            "      List all manga, or only those with unread chapters (most unread first);"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --genre <genre> is the same as --tag genre:<genre>; --tag-expr combines"
+        )?;
+        writeln!(
+            usage_writer,
+            "      tags with AND, OR, NOT and parentheses, i.e. \"isekai AND NOT dropped\";"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --since/--until (YYYY-MM-DD or RFC 3339) keep those last updated in that"
+        )?;
+        writeln!(
+            usage_writer,
+            "      range, or with --added, those added to the database in that range;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --min-chapter/--max-chapter keep those read that far, furthest first;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --field keeps those with that user field (set with 'field'), or that value"
+        )?;
+        writeln!(usage_writer, "  tui [--force]")?;
+        writeln!(
+            usage_writer,
+            "      Browse and search the collection in the terminal, edit chapter/notes/tags,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      open entries in the browser and review duplicates ('?' lists the keys)"
+        )?;
+        writeln!(usage_writer, "  shell [--history <file>]")?;
+        writeln!(
+            usage_writer,
+            "      Line-edited prompt (search, show, set chapter, tag, open) with tab completion"
+        )?;
+        writeln!(
+            usage_writer,
+            "      of titles and a history kept in ~/{}",
+            shell::K_DEFAULT_HISTORY_FILE
+        )?;
+        writeln!(
+            usage_writer,
+            "  search [<title>] [--tag <tag>]... [--limit <N>] [--include-archived] [--full-text]"
+        )?;
+        writeln!(usage_writer, "         [--json]")?;
+        writeln!(
+            usage_writer,
+            "      Best matches first: exact, then prefix (romanized too), part of the title,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      full-text (title, notes) and, when nothing closer is found, titles a few"
+        )?;
+        writeln!(
+            usage_writer,
+            "      typos away; --full-text lists only the full-text matches, by relevance"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --tag (repeatable, each must hold) keeps the manga of a tag, or of a tag"
+        )?;
+        writeln!(
+            usage_writer,
+            "      expression ('#アニメ化 OR isekai'); without <title>, all of them by title"
+        )?;
+        writeln!(
+            usage_writer,
+            "  annotate [<word>...] [--tag <tag>] [--domain <domain>] [--tag-expr <expression>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Open the matching manga (all the words in title, notes or tags, and the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      filters of list) in $EDITOR as YAML; title, chapter, notes and tags"
+        )?;
+        writeln!(
+            usage_writer,
+            "      changed there are saved when the editor exits"
+        )?;
+        writeln!(
+            usage_writer,
+            "  archive <id>... [--reason <reason>] | archive --list [--json]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Move finished or dropped series (and their reading history) out of the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      collection into the archive, where list, check and the exports no longer"
+        )?;
+        writeln!(
+            usage_writer,
+            "      see them; list and search still show them given --include-archived"
+        )?;
+        writeln!(
+            usage_writer,
+            "  stats [--pace|--sites|--domains] [--days <N>] [--json]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Collection summary including unread chapter counts and the domains with"
+        )?;
+        writeln!(usage_writer, "      the most series, or with --pace,")?;
+        writeln!(
+            usage_writer,
+            "      chapters read per week/month, longest streak and most read series,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      with --sites, per-domain availability from 'verify-urls' results,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      or with --domains, per domain the number of series, how many of them"
+        )?;
+        writeln!(
+            usage_writer,
+            "      have unread chapters (and how many chapters) and the last successful"
+        )?;
+        writeln!(
+            usage_writer,
+            "      'verify-urls' check, to tell which sites are worth keeping"
+        )?;
+        writeln!(
+            usage_writer,
+            "  enrich [--id <N>] [--source myanimelist|anilist] [--interactive]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Look up series not yet enriched on MyAnimeList or AniList (enrichment_source"
+        )?;
+        writeln!(
+            usage_writer,
+            "      in the config) by romanized title, storing the MAL url, authors, status,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      synopsis, cover and genre: tags; ambiguous matches are skipped unless"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --interactive, which asks which one it is"
+        )?;
+        writeln!(
+            usage_writer,
+            "  fetch-covers [--id <N>] [--dir <covers>] [--max-mb <N>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Download the cover images found by 'enrich' into cover_cache_dir (config),"
+        )?;
+        writeln!(
+            usage_writer,
+            "      removing the oldest ones beyond cover_cache_max_megabytes (default {})",
+            cover_cache::K_DEFAULT_MAX_MEGABYTES
+        )?;
+        writeln!(
+            usage_writer,
+            "  report [--out <manga.html>] [--title <title>] [--no-covers]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      A single self-contained HTML page of the collection (search, sortable"
+        )?;
+        writeln!(
+            usage_writer,
+            "      columns), embedding the covers cached by 'fetch-covers' unless --no-covers"
+        )?;
+        writeln!(
+            usage_writer,
+            "  export [--since <date|millis|last-export>] [--format csv|ndjson|urls] [--out <file>]"
+        )?;
+        writeln!(usage_writer, "       [--tombstones <deleted.csv>] [--urls base|chapter] [--tag <tag>] [--domain <domain>]")?;
+        writeln!(
+            usage_writer,
+            "      The collection, or with --since only the rows added or changed since then"
+        )?;
+        writeln!(
+            usage_writer,
+            "      plus the rows deleted since then, for feeding other systems incrementally;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      'last-export' is when the previous export ran; NDJSON tells deletions by"
+        )?;
+        writeln!(
+            usage_writer,
+            "      \"op\":\"delete\", CSV writes them to --tombstones (else lists them on stderr);"
+        )?;
+        writeln!(
+            usage_writer,
+            "      urls is one url per line (of the series, or --urls chapter the one read last)"
+        )?;
+        writeln!(
+            usage_writer,
+            "      for link checkers and archivers such as wget -i or ArchiveBox"
+        )?;
+        writeln!(usage_writer, "  export-bookmarks [--out <bookmarks.html>] [--folders tag|status|none] [--root <name>]")?;
+        writeln!(
+            usage_writer,
+            "      Netscape bookmarks.html any browser imports, one folder per first tag (or"
+        )?;
+        writeln!(
+            usage_writer,
+            "      per publication status), linking to the last read chapter, tags in TAGS"
+        )?;
+        writeln!(
+            usage_writer,
+            "  export-history [--format csv|json] [--days <N>] [--out <history.csv>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Reading history, one row per chapter read (manga, chapter, timestamp)"
+        )?;
+        writeln!(
+            usage_writer,
+            "  export-markdown [--out <manga.md>] [--tag <tag>] [--domain <domain>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "       [--tag-expr <expression>] [--since <date>] [--until <date>] [--added]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      GitHub-flavored Markdown table (title linking to the last read chapter,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      chapter, last update, tags) sorted by title, for READMEs, gists and forums"
+        )?;
+        writeln!(
+            usage_writer,
+            "  export-opml [--out <manga.opml>] [--title <title>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      OPML of the series with a feed (feed_url, or derived from url by the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      [[feed_rules]] in the config) for feed readers, one folder per first tag"
+        )?;
+        writeln!(
+            usage_writer,
+            "  export-xlsx [--out <manga.xlsx>] [--tag <tag>] [--domain <domain>]  (default {})",
+            K_DEFAULT_XLSX_FILE
+        )?;
+        writeln!(
+            usage_writer,
+            "       [--tag-expr <expression>] [--since <date>] [--until <date>] [--added]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Excel workbook with a frozen header row, filters, clickable urls and the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      last update as a date cell; both take the filters of 'list', i.e."
+        )?;
+        writeln!(
+            usage_writer,
+            "      --since <date> for only what changed since the previous export"
+        )?;
+        writeln!(
+            usage_writer,
+            "  import <source>... | --all [--stages <stage,...>] [--skip-stages <stage,...>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "         [--explain] [--title-collision <update-title|keep-both|ask>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "         [--scheme-policy <allow|warn|reject>] [--resume]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Import the named [sources.<name>] of the config (Firefox/Chrome bookmarks JSON,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      places.sqlite, or CSV) one after the other, tagging each bookmark with its"
+        )?;
+        writeln!(
+            usage_writer,
+            "      source (\"source:<name>\" unless the source sets its own tag); titles and"
+        )?;
+        writeln!(
+            usage_writer,
+            "      urls go through the normalization stages, see 'normalize'.  A bookmark of a"
+        )?;
+        writeln!(
+            usage_writer,
+            "      known url under another title adds a row of its own (keep-both, default),"
+        )?;
+        writeln!(
+            usage_writer,
+            "      renames that row keeping its old title in alt_titles (update-title), or asks;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      else title_collision of the config.  Bookmarks of other schemes than http(s)"
+        )?;
+        writeln!(
+            usage_writer,
+            "      (i.e. about:downloads) are imported (allow, default), warned about, or"
+        )?;
+        writeln!(
+            usage_writer,
+            "      rejected and listed with why; else [normalize] scheme_policy of the config."
+        )?;
+        writeln!(
+            usage_writer,
+            "      The browser's own (about:, place:, javascript:, file:...) are only counted,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      see [normalize] skip_schemes of the config.  Bookmarks are written in"
+        )?;
+        writeln!(
+            usage_writer,
+            "      batches, each checkpointed in the import_log table: --resume carries on an"
+        )?;
+        writeln!(
+            usage_writer,
+            "      interrupted import of a source after its last batch"
+        )?;
+        writeln!(
+            usage_writer,
+            "  import -i <bookmarks.json>...|-p <places.sqlite>|--profile <name>|--auto-input"
+        )?;
+        writeln!(
+            usage_writer,
+            "         [--format firefox|chrome|html] [-o <output.csv>] [-c <last.csv>] [--merge]"
+        )?;
+        writeln!(
+            usage_writer,
+            "         [--sync [--sync-report <report.json>]] [--stream] [--folder-tags]"
+        )?;
+        writeln!(
+            usage_writer,
+            "         [--include-folder <folder>]... [--exclude-folder <folder>]... [--url-filter <regex|glob>]..."
+        )?;
+        writeln!(
+            usage_writer,
+            "      The import of the bookmarks without a subcommand, same flags (see --help)"
+        )?;
+        writeln!(
+            usage_writer,
+            "  normalize <title> <url> [--stages <stage,...>] [--skip-stages <stage,...>] [--json]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      What an import would make of a bookmark, stage by stage; stages run in the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      order of --stages (else [normalize] stages of the config, else the default"
+        )?;
+        writeln!(
+            usage_writer,
+            "      {}), less --skip-stages",
+            normalize_pipeline::K_DEFAULT_STAGES
+                .iter()
+                .map(|stage| stage.name())
+                .collect::<Vec<&str>>()
+                .join(",")
+        )?;
+        writeln!(usage_writer, "  export-yaml [--out <manga.yaml>]")?;
+        writeln!(usage_writer, "  import-yaml <manga.yaml>")?;
+        writeln!(
+            usage_writer,
+            "      The collection as one YAML list (sorted by id, tags nested) to keep in git and"
+        )?;
+        writeln!(
+            usage_writer,
+            "      edit by hand; importing replaces the manga of each id with its entry, and adds"
+        )?;
+        writeln!(
+            usage_writer,
+            "      entries without an id (or updates the one with the same title and url)"
+        )?;
+        writeln!(
+            usage_writer,
+            "  dedupe [--keep-japanese] [--apply | --dry-run] [--force]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      List the groups of rows which look like the same series (same base url, or"
+        )?;
+        writeln!(
+            usage_writer,
+            "      same romanized title); --apply keeps the oldest row of each group, which"
+        )?;
+        writeln!(
+            usage_writer,
+            "      takes over the tags of the others, and deletes the rest (as the dedupe of"
+        )?;
+        writeln!(
+            usage_writer,
+            "      tui does), refusing while the daemon runs unless --force.  --keep-japanese"
+        )?;
+        writeln!(
+            usage_writer,
+            "      only takes groups of a Japanese title with romaji/English look-alikes: the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Japanese row is kept, taking over the furthest chapter, notes, tags and"
+        )?;
+        writeln!(
+            usage_writer,
+            "      titles of the others.  Nothing is deleted without --apply (--dry-run)"
+        )?;
+        writeln!(usage_writer, "  diff --csv <漫画.csv> [--json]")?;
+        writeln!(
+            usage_writer,
+            "      Compare a CSV with the database, rows matched by title and url the way the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      -c import matches them: rows only in the CSV, only in the database, and the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      fields which differ (database value => CSV value)"
+        )?;
+        writeln!(usage_writer, "  export-obsidian <vault-dir> [--prune]")?;
+        writeln!(
+            usage_writer,
+            "      One Markdown note per manga (url, chapter, tags, status as frontmatter, notes"
+        )?;
+        writeln!(usage_writer, "      as the body) named \"<title> (<id>).md\"; re-running only rewrites what changed,")?;
+        writeln!(
+            usage_writer,
+            "      --prune also removes the notes of manga no longer in the database"
+        )?;
+        writeln!(usage_writer, "  mark-read <id> <chapter>|--to-latest")?;
+        writeln!(
+            usage_writer,
+            "  mark-read --all --to-latest [--tag <tag>] [--domain <domain>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Set the last read chapter (and its url) and log it as a reading session;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --to-latest uses the latest chapter found by 'check' (single transaction)"
+        )?;
+        writeln!(
+            usage_writer,
+            "  migrate-site <dead-domain> [--yes] [--preview] [--force]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Look up every manga on a dead domain on the [[aggregators]] in the config"
+        )?;
+        writeln!(
+            usage_writer,
+            "      (by romanized title), and replace its url after confirmation; --preview"
+        )?;
+        writeln!(
+            usage_writer,
+            "      applies them all to a copy of the database first and shows the rows that"
+        )?;
+        writeln!(usage_writer, "      would change before committing")?;
+        writeln!(usage_writer, "  verify-urls [--id <N>]")?;
+        writeln!(
+            usage_writer,
+            "      HEAD every url, log the result and report broken ones; urls which answer"
+        )?;
+        writeln!(
+            usage_writer,
+            "      301/308 are replaced by where they moved to (old url kept in url_history)"
+        )?;
+        writeln!(usage_writer, "  repair-titles [--id <N>|--all] [--apply]")?;
+        writeln!(
+            usage_writer,
+            "      Replace titles like \"Read Online Free - Chapter 58\" by the page's og:title,"
+        )?;
+        writeln!(
+            usage_writer,
+            "      cleaned by the [[title_rules]] in the config; asks for each unless --apply"
+        )?;
+        writeln!(usage_writer, "  clean-titles [--id <N>] [--apply]")?;
+        writeln!(
+            usage_writer,
+            "      Strip site boilerplate (i.e. \" – Rawkuma\") from the titles already in the"
+        )?;
+        writeln!(
+            usage_writer,
+            "      database with the [[title_rules]] of their domain (also applied to every"
+        )?;
+        writeln!(
+            usage_writer,
+            "      import, see title_rules_file); asks for each unless --apply"
+        )?;
+        writeln!(
+            usage_writer,
+            "  re-romanize [--style <hepburn|kunrei>] [--dry-run]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Romanize the Japanese titles again in the romanization style (or --style) and"
+        )?;
+        writeln!(
+            usage_writer,
+            "      with the romanization dictionary of the config, i.e. after changing either;"
+        )?;
+        writeln!(usage_writer, "      --dry-run only lists what would change")?;
+        writeln!(usage_writer, "  serve [--bind <address:port>]")?;
+        writeln!(
+            usage_writer,
+            "      HTTP/JSON API over the database (default {}, no authentication):",
+            rest_server::K_DEFAULT_BIND
+        )?;
+        writeln!(
+            usage_writer,
+            "      GET|POST /manga, GET|PUT|PATCH|DELETE /manga/<id>, GET|POST /manga/search;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      described by /openapi.json and browsable at /swagger-ui;"
+        )?;
+        writeln!(
+            usage_writer,
+            "      read-only GraphQL at POST /graphql (GraphiQL explorer at GET /graphql);"
+        )?;
+        writeln!(
+            usage_writer,
+            "      a dashboard for browsing and marking chapters read at / (use --bind"
+        )?;
+        writeln!(
+            usage_writer,
+            "      0.0.0.0:8080 to reach it from other devices on the LAN)"
+        )?;
+        writeln!(usage_writer, "  serve-grpc [--bind <address:port>] [-D]")?;
+        writeln!(
+            usage_writer,
+            "      Same as 'serve' but gRPC (see proto/manga.proto), plus ImportBookmarks;"
+        )?;
+        writeln!(usage_writer, "      only when built with --features grpc")?;
+        writeln!(
+            usage_writer,
+            "  native-host [--stages <stage,...>] [--skip-stages <stage,...>]"
+        )?;
+        writeln!(
+            usage_writer,
+            "  native-host --manifest <host script> --extension-id <id>"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Native messaging host of a companion browser extension: each bookmark it"
+        )?;
+        writeln!(
+            usage_writer,
+            "      sends is normalized as an import, marks its manga read at the chapter of"
+        )?;
+        writeln!(
+            usage_writer,
+            "      the url, or adds it.  --manifest prints the manifest to install as"
+        )?;
+        writeln!(
+            usage_writer,
+            "      ~/.mozilla/native-messaging-hosts/{}.json (Linux) or",
+            native_host::K_HOST_NAME
+        )?;
+        writeln!(
+            usage_writer,
+            "      ~/Library/Application Support/Mozilla/NativeMessagingHosts/{}.json (macOS);",
+            native_host::K_HOST_NAME
+        )?;
+        writeln!(usage_writer, "      unix only")?;
+        writeln!(usage_writer, "  openapi [--out <openapi.json>]")?;
+        writeln!(
+            usage_writer,
+            "      The OpenAPI document of 'serve', without starting the server"
+        )?;
+        writeln!(usage_writer, "  schema [--out <schema.sql>]")?;
+        writeln!(
+            usage_writer,
+            "      The CREATE TABLE/INDEX/TRIGGER/VIEW statements of the database this version"
+        )?;
+        writeln!(
+            usage_writer,
+            "      creates (columns added since included), for tools built against it"
+        )?;
+        writeln!(usage_writer, "  field <id> [<key> [<value>|--clear]]")?;
+        writeln!(
+            usage_writer,
+            "      Show the user fields of a manga, or show, set or remove one of them, i.e."
+        )?;
+        writeln!(
+            usage_writer,
+            "      'field 12 rating 9'; fields need no schema change, see list --field"
+        )?;
+        writeln!(
+            usage_writer,
+            "  tags [list] [--json] | tags rename <old> <new> | tags merge <from> <into>"
+        )?;
+        writeln!(usage_writer, "       | tags delete-orphaned")?;
+        writeln!(
+            usage_writer,
+            "      The tags with how many manga each is on; rename one everywhere, merge one"
+        )?;
+        writeln!(
+            usage_writer,
+            "      into another (the manga of both keep one), or delete those on no manga"
+        )?;
+        writeln!(usage_writer, "  set-feed <id> <feed_url>|--clear")?;
+        writeln!(
+            usage_writer,
+            "      Store (or remove) the per-series RSS/Atom feed used by 'check'"
+        )?;
+        writeln!(usage_writer, "  sync push|pull|status [--force]")?;
+        writeln!(
+            usage_writer,
+            "      Upload/download the database to the WebDAV directory or S3 bucket of [sync]"
+        )?;
+        writeln!(
+            usage_writer,
+            "      in the config; refuses to overwrite changes the other side has not seen"
+        )?;
+        writeln!(
+            usage_writer,
+            "      (push after someone else pushed, pull over unpushed changes) unless --force"
+        )?;
+        writeln!(
+            usage_writer,
+            "  snapshot create|restore <name> [--dir <snapshots>] [--force] | snapshot list"
+        )?;
+        writeln!(
+            usage_writer,
+            "      Checkpoint the database (lz4 compressed, with its manga count) before an"
+        )?;
+        writeln!(
+            usage_writer,
+            "      experiment, and put it back afterwards (the current one kept as .bak);"
+        )?;
+        writeln!(
+            usage_writer,
+            "      --force replaces a snapshot of the same name, or restores while the daemon"
+        )?;
+        writeln!(
+            usage_writer,
+            "      runs; snapshots are kept in snapshot_dir of the config (default '{}')",
+            snapshots::K_DEFAULT_SNAPSHOT_DIR
+        )?;
+        writeln!(
+            usage_writer,
+            "Without a subcommand, {} --rpc [-d <db.sqlite3>] speaks JSON-RPC on stdin/stdout (unix only)",
+            executable
+        )?;
+        writeln!(
+            usage_writer,
+            "(list, search, upsert, mark_read) for editors and launchers"
+        )?;
+        Ok(())
+    }
+
+    pub fn print_usage(executable: &str) {
+        write_usage(&mut io::stdout(), executable).unwrap_or_default();
+    }
+
+    // the lines of write_usage() about that subcommand (some have more than one), None if there is no such one
+    pub fn subcommand_usage(executable: &str, subcommand: &str) -> Option<String> {
+        let mut usage = Vec::new();
+        write_usage(&mut usage, executable).ok()?;
+        let mut lines = Vec::new();
+        let mut is_in_section = false;
+        let usage = String::from_utf8_lossy(&usage);
+        for line in usage.lines() {
+            // "  <subcommand> [flags]" starts a section, "      what it does" and "       [more flags]" go on
+            if let Some(header) = line
+                .strip_prefix("  ")
+                .filter(|rest| !rest.starts_with(' '))
+            {
+                is_in_section = header.split_whitespace().next() == Some(subcommand);
+            } else if !line.starts_with("  ") {
+                is_in_section = false;
+            }
+            if is_in_section {
+                lines.push(line);
+            }
+        }
+        match lines.is_empty() {
+            true => None,
+            false => Some(format!(
+                "Usage: {} {} [-d <db.sqlite3>] [--config <config.toml>] [options]\n{}\n",
+                executable,
+                subcommand,
+                lines.join("\n")
+            )),
+        }
+    }
+
+    // `help <subcommand>` and `<subcommand> --help`
+    fn print_subcommand_usage(
+        executable: &str,
+        subcommand: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match subcommand_usage(executable, subcommand) {
+            Some(usage) => {
+                print!("{}", usage);
+                Ok(())
+            }
+            None => {
+                print_usage(executable);
+                Err(format!("Unknown subcommand: '{}'", subcommand).into())
+            }
+        }
+    }
+
+    // `import -i <bookmarks.json>` (or -p, --profile, --auto-input) is the legacy import under the name of a
+    // subcommand: the args of it, without "import", or None for the import of named sources
+    pub fn legacy_import_args(args: &[String]) -> Option<Vec<String>> {
+        let is_legacy_import = args.get(1).is_some_and(|arg| arg == "import")
+            && ["-i", "-p", "--profile", "--auto-input"]
+                .iter()
+                .any(|flag| has_flag(args, flag));
+        match is_legacy_import {
+            true => Some(
+                args.iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != 1)
+                    .map(|(_, arg)| arg.clone())
+                    .collect(),
+            ),
+            false => None,
+        }
+    }
+
+    // args are the full process args (args[0] is the executable, args[1] is the subcommand)
+    pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let executable = args.get(0).map(|s| s.as_str()).unwrap_or("manga");
+        let subcommand_args = &args[2.min(args.len())..];
+        if let Some(subcommand) = args
+            .get(1)
+            .filter(|_| has_flag(subcommand_args, "--help") || has_flag(subcommand_args, "-h"))
+        {
+            return print_subcommand_usage(executable, subcommand);
+        }
+        match args.get(1).map(|s| s.as_str()) {
+            Some("feed") => run_feed(subcommand_args),
+            Some("annotate") => run_annotate(subcommand_args),
+            Some("archive") => run_archive(subcommand_args),
+            Some("check") => run_check(subcommand_args),
+            Some("set-feed") => run_set_feed(subcommand_args),
+            Some("repair-titles") => run_repair_titles(subcommand_args),
+            Some("clean-titles") => run_clean_titles(subcommand_args),
+            Some("re-romanize") => run_re_romanize(subcommand_args),
+            Some("continue") => run_continue(subcommand_args),
+            Some("daemon") => run_daemon(subcommand_args),
+            Some("dedupe") => run_dedupe(subcommand_args),
+            Some("diff") => run_diff(subcommand_args),
+            Some("enrich") => run_enrich(subcommand_args),
+            Some("export") => run_export(subcommand_args),
+            Some("export-bookmarks") => run_export_bookmarks(subcommand_args),
+            Some("export-history") => run_export_history(subcommand_args),
+            Some("export-markdown") => run_export_markdown(subcommand_args),
+            Some("export-obsidian") => run_export_obsidian(subcommand_args),
+            Some("export-opml") => run_export_opml(subcommand_args),
+            Some("export-xlsx") => run_export_xlsx(subcommand_args),
+            Some("export-yaml") => run_export_yaml(subcommand_args),
+            Some("fetch-covers") => run_fetch_covers(subcommand_args),
+            Some("field") => run_field(subcommand_args),
+            Some("import") => run_import(subcommand_args),
+            Some("import-yaml") => run_import_yaml(subcommand_args),
+            Some("list") => run_list(subcommand_args),
+            Some("mark-read") => run_mark_read(subcommand_args),
+            Some("native-host") => run_native_host(subcommand_args),
+            Some("normalize") => run_normalize(subcommand_args),
+            Some("migrate-site") => run_migrate_site(subcommand_args),
+            Some("openapi") => run_openapi(subcommand_args),
+            Some("report") => run_report(subcommand_args),
+            Some("schema") => run_schema(subcommand_args),
+            Some("search") => run_search(subcommand_args),
+            Some("serve") => run_serve(subcommand_args),
+            Some("serve-grpc") => run_serve_grpc(subcommand_args),
+            Some("shell") => run_shell(subcommand_args),
+            Some("snapshot") => run_snapshot(subcommand_args),
+            Some("stats") => run_stats(subcommand_args),
+            Some("sync") => run_sync(subcommand_args),
+            Some("tags") => run_tags(subcommand_args),
+            Some("tui") => run_tui(subcommand_args),
+            Some("verify-urls") => run_verify_urls(subcommand_args),
+            Some("help") => match subcommand_args.first() {
+                Some(subcommand) => print_subcommand_usage(executable, subcommand),
+                None => {
+                    print_usage(executable);
+                    Ok(())
+                }
+            },
+            Some(unknown) => {
+                print_usage(executable);
+                Err(format!("Unknown subcommand: '{}'", unknown).into())
+            }
+            None => {
+                print_usage(executable);
+                Err("No subcommand given".into())
+            }
+        }
+    }
+
+    // unlike select_all_manga(), an empty database is not an error for reporting subcommands
+    pub fn select_all_manga_or_empty(
+        db_full_paths: &str,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        match model_sqlite3_manga::select_all_manga(db_full_paths) {
+            Ok(mangas) => Ok(mangas),
+            Err(Error::Sqlite(rusqlite::Error::QueryReturnedNoRows)) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn run_feed(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let days = match flag_value(args, "--days") {
+            Some(days) => days.parse::<i64>()?,
+            None => K_DEFAULT_FEED_DAYS,
+        };
+        let possible_limit = match flag_value(args, "--limit") {
+            Some(limit) => Some(limit.parse::<usize>()?),
+            None => None,
+        };
+
+        let since_millis = chrono::Utc::now().timestamp_millis() - days * 24 * 60 * 60 * 1000;
+        let mut mangas =
+            model_sqlite3_manga::select_manga_updated_since(&db_full_paths, since_millis)?;
+        if let Some(limit) = possible_limit {
+            mangas.truncate(limit);
+        }
+
+        let mut writer = output_writer(args)?;
+        model_atom_manga::write_atom_feed(
+            &mut writer,
+            &format!("Manga updates (last {} days)", days),
+            &mangas,
+        )?;
+        // chatter goes to stderr so that stdout can be redirected as the feed itself
+        eprintln!(
+            "feed: {} entries written from '{}'",
+            mangas.len(),
+            db_full_paths
+        );
+        Ok(())
+    }
+
+    fn run_check(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let possible_id = match flag_value(args, "--id") {
+            Some(id) => Some(id.parse::<i64>()?),
+            None => None,
+        };
+        // make sure older databases have the feed_url/latest_available_chapter columns
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let config = load_config(args)?;
+
+        let updates = update_checker::check_for_updates(
+            &db_full_paths,
+            possible_id,
+            config
+                .mangadex_language
+                .as_deref()
+                .unwrap_or(mangadex::K_DEFAULT_LANGUAGE),
+        )?;
+        for update in updates.iter() {
+            println!(
+                "{}: '{}' chapter {} ({:?}) {}",
+                update.manga_id, update.title, update.chapter, update.source, update.url
+            );
+        }
+        println!("check: {} new chapter(s) found", updates.len());
+
+        // recorded as seen only once notified, else the next check finds (and notifies) them again
+        let failed_count = notifier::notify(&config.webhooks, &updates);
+        if failed_count > 0 {
+            return Err(format!(
+                "check: {} webhook(s) failed, the new chapters will be notified again",
+                failed_count
+            )
+            .into());
+        }
+        update_checker::record_updates(&db_full_paths, &updates)
+    }
+
+    // `--since`/`--until` <date>, either "2023-09-01" (local midnight; a plain --until date includes that
+    // day) or RFC 3339
+    fn date_flag(
+        args: &[String],
+        flag: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, Box<dyn std::error::Error>> {
+        let text = match flag_value(args, flag) {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(&text) {
+            return Ok(Some(datetime.with_timezone(&chrono::Utc)));
+        }
+        let date = chrono::NaiveDate::parse_from_str(&text, "%Y-%m-%d").map_err(|_| {
+            format!(
+                "{}: expected YYYY-MM-DD or RFC 3339 date, got '{}'",
+                flag, text
+            )
+        })?;
+        let date = match flag == "--until" {
+            true => date.succ_opt().unwrap_or(date),
+            false => date,
+        };
+        let midnight = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|datetime| {
+                chrono::TimeZone::from_local_datetime(&chrono::Local, &datetime).earliest()
+            })
+            .ok_or_else(|| format!("{}: no such local time '{}'", flag, text))?;
+        Ok(Some(midnight.with_timezone(&chrono::Utc)))
+    }
+
+    // `--min-chapter`/`--max-chapter` <chapter>, i.e. "100" or "12.5"
+    fn chapter_flag(
+        args: &[String],
+        flag: &str,
+    ) -> Result<Option<Chapter>, Box<dyn std::error::Error>> {
+        match flag_value(args, flag) {
+            Some(text) => match Chapter::parse(&text) {
+                Some(chapter) => Ok(Some(chapter)),
+                None => Err(format!("{}: expected a chapter number, got '{}'", flag, text).into()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    // rows for `list` and the table exports: with --since/--until only those last updated (or with --added,
+    // first added) in that range, with --min-chapter/--max-chapter only those read that far (furthest read
+    // first), with --field rating=9 only those with that user field, and with --tag-expr "isekai AND NOT
+    // dropped" only those tags match, all filtered by the database; every row otherwise
+    fn select_filtered_manga(
+        args: &[String],
+        service: &MangaService,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let (possible_since, possible_until) =
+            (date_flag(args, "--since")?, date_flag(args, "--until")?);
+        let (possible_min_chapter, possible_max_chapter) = (
+            chapter_flag(args, "--min-chapter")?,
+            chapter_flag(args, "--max-chapter")?,
+        );
+        let has_chapter_range = possible_min_chapter.is_some() || possible_max_chapter.is_some();
+        let select_by_chapter_range = || {
+            model_sqlite3_manga::select_by_chapter_range(
+                &db_full_paths,
+                possible_min_chapter.as_ref(),
+                possible_max_chapter.as_ref(),
+            )
+        };
+        let mut mangas = match (possible_since, possible_until, has_flag(args, "--added")) {
+            (None, None, _) if has_chapter_range => select_by_chapter_range()?,
+            (None, None, _) => service.list()?,
+            (_, _, true) => {
+                model_sqlite3_manga::added_between(&db_full_paths, possible_since, possible_until)?
+            }
+            (_, _, false) => model_sqlite3_manga::updated_between(
+                &db_full_paths,
+                possible_since,
+                possible_until,
+            )?,
+        };
+        if has_chapter_range && (possible_since.is_some() || possible_until.is_some()) {
+            let in_range_ids: HashSet<i64> = select_by_chapter_range()?
+                .iter()
+                .map(|manga| manga.id())
+                .collect();
+            mangas.retain(|manga| in_range_ids.contains(&manga.id()));
+        }
+        if let Some(field) = flag_value(args, "--field") {
+            let (key, possible_value) = match field.split_once('=') {
+                Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                None => (field, None),
+            };
+            let matching_ids: HashSet<i64> = model_sqlite3_manga::select_by_field(
+                &db_full_paths,
+                &key,
+                possible_value.as_deref(),
+            )?
+            .iter()
+            .map(|manga| manga.id())
+            .collect();
+            mangas.retain(|manga| matching_ids.contains(&manga.id()));
+        }
+        if let Some(tag_expr) = flag_value(args, "--tag-expr") {
+            let matching_ids: HashSet<i64> = service
+                .search(&MangaSearchRequest {
+                    title: None,
+                    title_romanized: None,
+                    url: None,
+                    url_with_chapter: None,
+                    chapter: None,
+                    last_update: None,
+                    notes: None,
+                    tags: None,
+                    tag_expr: Some(tag_expr),
+                })?
+                .iter()
+                .map(|manga| manga.id())
+                .collect();
+            mangas.retain(|manga| matching_ids.contains(&manga.id()));
+        }
+        Ok(mangas)
+    }
+
+    fn run_list(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let possible_tag = flag_value(args, "--tag").or_else(|| {
+            flag_value(args, "--genre").and_then(|genre| enrichment::genre_tag(&genre))
+        });
+        let possible_domain = flag_value(args, "--domain");
+        let mangas = select_filtered_manga(args, &service)?
+            .into_iter()
+            .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+            .collect::<Vec<MangaModel>>();
+
+        let entries = match has_flag(args, "--unread") {
+            true => stats::unread_entries(&mangas),
+            false => mangas.iter().map(stats::to_unread_entry).collect(),
+        };
+        // archived series have no unread chapters, they are never checked
+        let archived: Vec<ArchivedManga> = match has_flag(args, "--include-archived") {
+            true => {
+                model_sqlite3_manga_archive::select_archived_manga(service.db_full_paths(), "")?
+                    .into_iter()
+                    .filter(|archived| {
+                        matches_tag_and_domain(&archived.manga, &possible_tag, &possible_domain)
+                    })
+                    .collect()
+            }
+            false => Vec::new(),
+        };
+        if has_flag(args, "--json") {
+            match has_flag(args, "--include-archived") {
+                true => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "manga": entries,
+                        "archived": archived,
+                    }))?
+                ),
+                false => println!("{}", serde_json::to_string_pretty(&entries)?),
+            }
+            return Ok(());
+        }
+        for entry in entries.iter() {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                entry.id,
+                entry.title,
+                entry.chapter.as_deref().unwrap_or("-"),
+                entry.latest_available_chapter.as_deref().unwrap_or("-"),
+                entry.unread
+            );
+        }
+        print_archived(&archived);
+        Ok(())
+    }
+
+    // after the active ones, i.e. "archived  3  Gate  12  2023-09-06 (finished)"
+    fn print_archived(archived: &[ArchivedManga]) {
+        for archived_manga in archived.iter() {
+            let archived_at = chrono::TimeZone::timestamp_millis_opt(
+                &chrono::Local,
+                archived_manga.archived_at_millis,
+            )
+            .single()
+            .map(|datetime| datetime.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+            println!(
+                "archived\t{}\t{}\t{}\t{}{}",
+                archived_manga.manga.id(),
+                archived_manga.manga.title(),
+                archived_manga.manga.chapter().unwrap_or("-".to_string()),
+                archived_at,
+                archived_manga
+                    .reason
+                    .as_ref()
+                    .map_or(String::new(), |reason| format!(" ({})", reason))
+            );
+        }
+    }
+
+    // `annotate <word>... [--tag <tag>] [--domain <domain>] [--tag-expr <expression>] ...`: the words (every
+    // argument up to the first flag) must all be in the title, romanized title, notes or tags; the flags are
+    // those of list
+    fn run_annotate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?
+            .with_romanization(load_config(args)?.romanization_options());
+        let possible_tag = flag_value(args, "--tag").or_else(|| {
+            flag_value(args, "--genre").and_then(|genre| enrichment::genre_tag(&genre))
+        });
+        let possible_domain = flag_value(args, "--domain");
+        let words = args
+            .iter()
+            .take_while(|arg| !arg.starts_with('-'))
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<String>>();
+        let mangas = select_filtered_manga(args, &service)?
+            .into_iter()
+            .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+            .filter(|manga| {
+                let text = [
+                    manga.title().to_string(),
+                    manga.title_romanized().unwrap_or_default(),
+                    manga.notes().unwrap_or_default(),
+                    manga.tags().join(" "),
+                ]
+                .join(" ")
+                .to_lowercase();
+                words.iter().all(|word| text.contains(word))
+            })
+            .collect::<Vec<MangaModel>>();
+        if mangas.is_empty() {
+            eprintln!("annotate: no manga matches");
+            return Ok(());
+        }
+
+        let edited = annotate::edit_in_editor(&annotate::to_yaml(&mangas)?)?;
+        let changes = annotate::changes(&mangas, &annotate::from_yaml(&edited)?)?;
+        let (changed, mut failed) = (changes.len(), 0);
+        for (id, request) in changes {
+            if let Err(e) = service.update(id, request) {
+                eprintln!("annotate: id={}: {}", id, e);
+                failed += 1;
+            }
+        }
+        eprintln!(
+            "annotate: {} updated, {} unchanged, {} failed",
+            changed - failed,
+            mangas.len() - changed,
+            failed
+        );
+        Ok(())
+    }
+
+    // `archive <id>... [--reason <reason>]`, `archive --list [--json]`
+    fn run_archive(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        if has_flag(args, "--list") {
+            let archived = model_sqlite3_manga_archive::select_archived_manga(&db_full_paths, "")?;
+            match has_flag(args, "--json") {
+                true => println!("{}", serde_json::to_string_pretty(&archived)?),
+                false => print_archived(&archived),
+            }
+            return Ok(());
+        }
+        let possible_reason = flag_value(args, "--reason");
+        let ids = args
+            .iter()
+            .take_while(|arg| !arg.starts_with('-'))
+            .map(|id| id.parse::<i64>())
+            .collect::<Result<Vec<i64>, _>>()?;
+        if ids.is_empty() {
+            return Err("archive: <id> is required".into());
+        }
+        for id in ids {
+            let archived = model_sqlite3_manga_archive::archive_manga(
+                &db_full_paths,
+                id,
+                possible_reason.as_deref(),
+            )
+            .map_err(|e| match e {
+                Error::NotFound(_) => format!("archive: no manga id={}", id),
+                e => format!("archive: id={}: {}", id, e),
+            })?;
+            eprintln!(
+                "archive: id={} '{}' archived ({} chapter(s) of reading history)",
+                id,
+                archived.manga.title(),
+                archived.history.reading_sessions.len()
+            );
+        }
+        Ok(())
+    }
+
+    // the query is every argument up to the first flag, i.e. `search dr stone --limit 5`
+    fn run_search(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let limit = match flag_value(args, "--limit") {
+            Some(limit) => limit.parse::<usize>()?,
+            None => K_DEFAULT_SEARCH_LIMIT,
+        };
+        let query = args
+            .iter()
+            .take_while(|arg| !arg.starts_with('-'))
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(" ");
+        // `--tag <tag>`: only the manga tagged so (see tagged_manga()), all of them when there is no <title>
+        let possible_tagged_ids: Option<HashSet<i64>> = match flag_values(args, "--tag") {
+            tags if tags.is_empty() => None,
+            tags => {
+                let tagged = tagged_manga(service.db_full_paths(), &tags)?;
+                if query.trim().is_empty() {
+                    let mangas: Vec<MangaModel> = tagged.into_iter().take(limit).collect();
+                    print_manga_ids_and_titles(args, &mangas)?;
+                    return Ok(());
+                }
+                Some(tagged.iter().map(|manga| manga.id()).collect())
+            }
+        };
+        let is_tagged = |manga: &MangaModel| {
+            possible_tagged_ids
+                .as_ref()
+                .is_none_or(|tagged_ids| tagged_ids.contains(&manga.id()))
+        };
+        if has_flag(args, "--full-text") {
+            let mangas: Vec<MangaModel> =
+                model_sqlite3_manga_fts::search_manga_fts(service.db_full_paths(), &query)?
+                    .into_iter()
+                    .filter(|manga| is_tagged(manga))
+                    .take(limit)
+                    .collect();
+            return print_manga_ids_and_titles(args, &mangas);
+        }
+        // ranked before the tags are looked at, so that there are still limit hits after them
+        let hits: Vec<SearchHit> = service
+            .ranked_search(
+                &query,
+                match possible_tagged_ids {
+                    Some(_) => usize::MAX,
+                    None => limit,
+                },
+            )?
+            .into_iter()
+            .filter(|hit| is_tagged(&hit.manga))
+            .take(limit)
+            .collect();
+        let archived: Vec<ArchivedManga> = match has_flag(args, "--include-archived") {
+            true => {
+                model_sqlite3_manga_archive::select_archived_manga(service.db_full_paths(), &query)?
+                    .into_iter()
+                    .take(limit)
+                    .collect()
+            }
+            false => Vec::new(),
+        };
+        if has_flag(args, "--json") {
+            match has_flag(args, "--include-archived") {
+                true => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "hits": hits,
+                        "archived": archived,
+                    }))?
+                ),
+                false => println!("{}", serde_json::to_string_pretty(&hits)?),
+            }
+            return Ok(());
+        }
+        for hit in hits.iter() {
+            println!(
+                "{:>5.1}\t{}\t{}\t{}",
+                hit.score,
+                hit.manga.id(),
+                hit.manga.title(),
+                hit.matched_by
+                    .iter()
+                    .map(|kind| kind.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            );
+        }
+        print_archived(&archived);
+        Ok(())
+    }
+
+    // manga matching every one of tags, each a tag or a tag expression ('isekai OR seinen', see tag_expression),
+    // by title
+    fn tagged_manga(
+        db_full_paths: &str,
+        tags: &[String],
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        let mut mangas = match tags {
+            [tag] if matches!(tag_expression::parse(tag)?, TagExpr::Tag(_)) => {
+                model_sqlite3_manga::select_manga_by_tag(db_full_paths, tag)?
+            }
+            tags => model_sqlite3_manga::select_by_tag_expr(
+                db_full_paths,
+                &tags
+                    .iter()
+                    .map(|tag| format!("({})", tag))
+                    .collect::<Vec<String>>()
+                    .join(" AND "),
+            )?,
+        };
+        mangas.sort_by(|a, b| a.title().cmp(b.title()));
+        Ok(mangas)
+    }
+
+    // "<id>\t<title>" lines, or with --json the whole of each manga
+    fn print_manga_ids_and_titles(
+        args: &[String],
+        mangas: &[MangaModel],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match has_flag(args, "--json") {
+            true => println!("{}", serde_json::to_string_pretty(mangas)?),
+            false => {
+                for manga in mangas.iter() {
+                    println!("{}\t{}", manga.id(), manga.title());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn run_stats(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let mangas = select_all_manga_or_empty(&db_full_paths)?;
+
+        if has_flag(args, "--pace") {
+            return print_pace_stats(args, &db_full_paths, &mangas);
+        }
+        if has_flag(args, "--sites") {
+            return print_site_stats(args, &db_full_paths);
+        }
+        if has_flag(args, "--domains") {
+            return print_domain_stats(args, &db_full_paths, &mangas);
+        }
+        let collection_stats = stats::collection_stats(&mangas, K_STATS_TOP_COUNT);
+        let domain_counts = model_sqlite3_manga::count_by_domain(&db_full_paths)?;
+        if has_flag(args, "--json") {
+            let mut collection_json = serde_json::to_value(&collection_stats)?;
+            collection_json["domains"] = serde_json::json!(domain_counts.len());
+            collection_json["top_domains"] = domain_counts
+                .iter()
+                .take(K_STATS_TOP_COUNT)
+                .map(|(domain, series)| serde_json::json!({ "domain": domain, "series": series }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&collection_json)?);
+            return Ok(());
+        }
+        println!("total:         {}", collection_stats.total);
+        println!("with feed_url: {}", collection_stats.with_feed_url);
+        println!("checked:       {}", collection_stats.checked);
+        println!(
+            "unread:        {} chapter(s) in {} series",
+            collection_stats.total_unread, collection_stats.with_unread
+        );
+        for entry in collection_stats.most_unread.iter() {
+            println!("  {:>4}  {} ({})", entry.unread, entry.title, entry.id);
+        }
+        println!("domains:       {}", domain_counts.len());
+        for (domain, series) in domain_counts.iter().take(K_STATS_TOP_COUNT) {
+            println!("  {:>4}  {}", series, domain);
+        }
+        Ok(())
+    }
+
+    // all time unless --days is given
+    fn since_millis_from_days(args: &[String]) -> Result<i64, Box<dyn std::error::Error>> {
+        match flag_value(args, "--days") {
+            Some(days) => {
+                Ok(chrono::Utc::now().timestamp_millis()
+                    - days.parse::<i64>()? * 24 * 60 * 60 * 1000)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn run_report(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let config = load_config(args)?;
+        let cache_dir = config
+            .cover_cache_dir
+            .unwrap_or_else(|| cover_cache::K_DEFAULT_COVER_CACHE_DIR.to_string());
+        let possible_cover_cache_dir = match has_flag(args, "--no-covers") {
+            true => None,
+            false => Some(cache_dir.as_str()),
+        };
+        let entries = service.report_entries(possible_cover_cache_dir)?;
+
+        let title =
+            flag_value(args, "--title").unwrap_or_else(|| K_DEFAULT_REPORT_TITLE.to_string());
+        let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let mut writer = output_writer(args)?;
+        html_report::write_report(&mut writer, &title, &generated_at, &entries)?;
+        writer.flush()?;
+        eprintln!(
+            "report: {} manga written ({} with covers)",
+            entries.len(),
+            entries.iter().filter(|entry| entry.cover.is_some()).count()
+        );
+        Ok(())
+    }
+
+    fn run_export_history(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let format = match flag_value(args, "--format") {
+            Some(format) => format.parse::<HistoryFormat>()?,
+            None => HistoryFormat::Csv,
+        };
+        let mut writer = output_writer(args)?;
+        let rows_count =
+            service.export_history(&mut writer, format, since_millis_from_days(args)?)?;
+        eprintln!("export-history: {} rows written", rows_count);
+        Ok(())
+    }
+
+    fn run_export_bookmarks(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let grouping = match flag_value(args, "--folders") {
+            Some(grouping) => grouping.parse::<service::FolderGrouping>()?,
+            None => service::FolderGrouping::Tag,
+        };
+        let root_folder = flag_value(args, "--root")
+            .unwrap_or_else(|| model_netscape_bookmarks::K_DEFAULT_ROOT_FOLDER.to_string());
+        let entries = service.bookmark_entries(grouping)?;
+        let mut writer = output_writer(args)?;
+        model_netscape_bookmarks::write_bookmarks_html(&mut writer, &root_folder, &entries)?;
+        eprintln!("export-bookmarks: {} bookmarks written", entries.len());
+        Ok(())
+    }
+
+    fn run_export_markdown(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let possible_tag = flag_value(args, "--tag");
+        let possible_domain = flag_value(args, "--domain");
+        let mut mangas = select_filtered_manga(args, &service)?
+            .into_iter()
+            .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+            .collect::<Vec<MangaModel>>();
+        mangas.sort_by_key(|manga| manga.title().to_lowercase());
+        let mut writer = output_writer(args)?;
+        markdown_table::write_table(&mut writer, &mangas)?;
+        eprintln!("export-markdown: {} rows written", mangas.len());
+        Ok(())
+    }
+
+    fn run_export_opml(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let config = load_config(args)?;
+        let mangas = service.list()?;
+        let outlines: Vec<model_opml_manga::FeedOutline> = mangas
+            .iter()
+            .filter_map(|manga| model_opml_manga::to_feed_outline(manga, &config.feed_rules))
+            .collect();
+        let title =
+            flag_value(args, "--title").unwrap_or_else(|| K_DEFAULT_REPORT_TITLE.to_string());
+        let mut writer = output_writer(args)?;
+        model_opml_manga::write_opml(&mut writer, &title, &outlines)?;
+        eprintln!(
+            "export-opml: {} feeds written, {} manga without a feed",
+            outlines.len(),
+            mangas.len() - outlines.len()
+        );
+        Ok(())
+    }
+
+    // binary, so a file rather than stdout
+    fn run_export_xlsx(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let possible_tag = flag_value(args, "--tag");
+        let possible_domain = flag_value(args, "--domain");
+        let mangas = select_filtered_manga(args, &service)?
+            .into_iter()
+            .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+            .collect::<Vec<MangaModel>>();
+        let output_file_paths =
+            flag_value(args, "--out").unwrap_or_else(|| K_DEFAULT_XLSX_FILE.to_string());
+        std::fs::write(&output_file_paths, model_xlsx_manga::to_xlsx(&mangas)?)?;
+        eprintln!(
+            "export-xlsx: {} rows written to '{}'",
+            mangas.len(),
+            output_file_paths
+        );
+        Ok(())
+    }
+
+    // `--since` of export: a date (see date_flag()), epoch millis, or "last-export" (everything when there
+    // was none yet)
+    fn since_millis_of_export(
+        args: &[String],
+        db_full_paths: &str,
+    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        match flag_value(args, "--since") {
+            Some(since) if since == "last-export" => Ok(Some(
+                model_sqlite3_change_log::select_last_export_millis(db_full_paths)?.unwrap_or(0),
+            )),
+            Some(since) => match since.parse::<i64>() {
+                Ok(millis) => Ok(Some(millis)),
+                Err(_) => Ok(date_flag(args, "--since")?.map(|since| since.timestamp_millis())),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn run_export(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let db_full_paths = service.db_full_paths();
+        let format = match flag_value(args, "--format") {
+            Some(format) => format.parse::<DeltaFormat>()?,
+            None => DeltaFormat::Csv,
+        };
+        let possible_since_millis = since_millis_of_export(args, db_full_paths)?;
+        let started_millis = chrono::Utc::now().timestamp_millis();
+        let (mangas, tombstones) = match possible_since_millis {
+            Some(since_millis) => (
+                model_sqlite3_manga::changed_between(
+                    db_full_paths,
+                    chrono::TimeZone::timestamp_millis_opt(&chrono::Utc, since_millis).single(),
+                    None,
+                )?,
+                model_sqlite3_change_log::select_tombstones_since(db_full_paths, since_millis)?,
+            ),
+            None => (service.list()?, Vec::new()),
+        };
+        let possible_tag = flag_value(args, "--tag");
+        let possible_domain = flag_value(args, "--domain");
+        let mangas = mangas
+            .into_iter()
+            .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+            .collect::<Vec<MangaModel>>();
+        let rows = delta_export::to_delta_rows(&mangas);
+
+        let mut writer = output_writer(args)?;
+        match format {
+            DeltaFormat::Ndjson => delta_export::write_ndjson(&mut writer, &rows, &tombstones)?,
+            DeltaFormat::Urls => {
+                let with_chapter = match flag_value(args, "--urls").as_deref() {
+                    None | Some("base") => false,
+                    Some("chapter") => true,
+                    Some(other) => {
+                        return Err(format!(
+                            "export: unknown --urls '{}', expected base or chapter",
+                            other
+                        )
+                        .into())
+                    }
+                };
+                let urls_count = delta_export::write_urls(&mut writer, &rows, with_chapter)?;
+                eprintln!("export: {} url(s) written", urls_count);
+            }
+            DeltaFormat::Csv => {
+                delta_export::write_rows_csv(&mut writer, &rows)?;
+                match flag_value(args, "--tombstones") {
+                    Some(tombstones_full_paths) => delta_export::write_tombstones_csv(
+                        &mut BufWriter::new(File::create(&tombstones_full_paths)?),
+                        &tombstones,
+                    )?,
+                    None => {
+                        for tombstone in tombstones.iter() {
+                            eprintln!(
+                                "deleted: id={} '{}' {}",
+                                tombstone.manga_id, tombstone.title, tombstone.url
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        writer.flush()?;
+        model_sqlite3_change_log::mark_exported(db_full_paths, started_millis)?;
+        eprintln!(
+            "export: {} row(s) added or changed, {} deleted",
+            rows.len(),
+            tombstones.len()
+        );
+        Ok(())
+    }
+
+    fn run_export_yaml(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let mut writer = output_writer(args)?;
+        let entries_count = service.export_yaml(&mut writer)?;
+        eprintln!("export-yaml: {} entries written", entries_count);
+        Ok(())
+    }
+
+    // `import <source>... | --all`
+    fn run_import(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let config = load_config(args)?;
+        let names: Vec<String> = match has_flag(args, "--all") {
+            true => config.sources.keys().cloned().collect(),
+            false => args
+                .iter()
+                .take_while(|arg| !arg.starts_with('-'))
+                .cloned()
+                .collect(),
+        };
+        if names.is_empty() {
+            return Err(format!(
+                "import: expected the name of a source, or --all; configured: {}",
+                config
+                    .sources
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+            .into());
+        }
+        // all names are checked before anything is imported
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !config.sources.contains_key(*name))
+        {
+            return Err(format!("import: no [sources.{}] in the config", unknown).into());
+        }
+        let pipeline = normalize_pipeline_of(args, &config)?;
+        let title_collision = title_collision_of(args, &config)?;
+        let service = MangaService::open(&db_full_paths(args))?;
+        let possible_git_export = git_export_snapshot(args, service.db_full_paths());
+        let mut failed_sources = 0;
+        for name in names.iter() {
+            let source = &config.sources[name];
+            let imported = import_sources::import_source(
+                service.db_full_paths(),
+                name,
+                source,
+                &pipeline,
+                has_flag(args, "--resume"),
+                &mut |id, title, manga| {
+                    renames_on_title_collision(title_collision, id, title, manga)
+                },
+            );
+            match imported {
+                Ok(summary) => {
+                    if let Some(resumed_at) = summary.resumed_at {
+                        eprintln!(
+                            "import: {}: resumed after {} of {} already written",
+                            name, resumed_at, summary.read
+                        );
+                    }
+                    for failure in summary.failures.iter() {
+                        eprintln!("import: {}: {}", name, failure);
+                    }
+                    for rejection in summary.rejected.iter() {
+                        eprintln!("import: {}: rejected {}", name, rejection);
+                    }
+                    if !summary.skipped.is_empty() {
+                        eprintln!(
+                            "import: {}: skipped {}",
+                            name,
+                            normalize_pipeline::skip_report(&summary.skipped)
+                        );
+                    }
+                    eprintln!(
+                        "import: {}: {} read, {} added, {} renamed, {} rejected, {} failed, tagged '{}'",
+                        name,
+                        summary.read,
+                        summary.added,
+                        summary.renamed,
+                        summary.rejected.len(),
+                        summary.failures.len(),
+                        import_sources::source_tag(name, source)
+                    );
+                }
+                Err(e) => {
+                    failed_sources += 1;
+                    eprintln!("import: {}: {}", name, e);
+                }
+            }
+        }
+        git_export_commit(
+            possible_git_export,
+            service.db_full_paths(),
+            &format!("import {}", names.join(" ")),
+        );
+        match failed_sources {
+            0 => Ok(()),
+            _ => Err(format!(
+                "import: {} of {} sources failed",
+                failed_sources,
+                names.len()
+            )
+            .into()),
+        }
+    }
+
+    // rows which cannot be read are reported and skipped
+    pub fn read_csv_rows(
+        csv_full_paths: &str,
+    ) -> Result<Vec<MangaModel>, Box<dyn std::error::Error>> {
+        // "-" is stdin, as for `-c -`
+        let csv_reader: Box<dyn Read> = match csv_full_paths {
+            "-" => Box::new(io::stdin()),
+            _ => Box::new(
+                File::open(csv_full_paths)
+                    .map_err(|e| format!("cannot read '{}': {}", csv_full_paths, e))?,
+            ),
+        };
+        let mut csv_util = model_csv_manga::Utils::new(Some(Box::new(io::sink())), csv_reader);
+        let mut csv_mangas = Vec::new();
+        while let Some(result) = csv_util.next() {
+            match result {
+                Ok(manga) => csv_mangas.push(manga),
+                Err(e) => eprintln!("'{}': {}", csv_full_paths, e),
+            }
+        }
+        Ok(csv_mangas)
+    }
+
+    // `diff --csv <file.csv> [--json]`
+    // `dedupe [--keep-japanese] [--apply | --dry-run] [--force]`: the groups of
+    // model_sqlite3_manga::duplicate_groups(), the oldest row of each kept with --apply; or with --keep-japanese,
+    // those of model_sqlite3_manga::prune_duplicates()
+    fn run_dedupe(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let apply = has_flag(args, "--apply");
+        if apply && has_flag(args, "--dry-run") {
+            return Err("dedupe: either --apply or --dry-run, not both".into());
+        }
+        if apply {
+            daemon_lock::refuse_while_active(&db_full_paths, "dedupe", has_flag(args, "--force"))?;
+        }
+        let service = MangaService::open(&db_full_paths)?;
+        if has_flag(args, "--keep-japanese") {
+            let pruned = service.prune_duplicates(!apply)?;
+            for group in pruned.iter() {
+                println!(
+                    "keep   id={} '{}' {}",
+                    group.kept.id(),
+                    group.kept.title(),
+                    group.kept.url()
+                );
+                for manga in group.deleted.iter() {
+                    println!(
+                        "{} id={} '{}' {}",
+                        if apply { "delete" } else { "      " },
+                        manga.id(),
+                        manga.title(),
+                        manga.url()
+                    );
+                }
+                println!();
+            }
+            println!(
+                "dedupe: {} Japanese title(s) with romaji/English look-alikes{}",
+                pruned.len(),
+                match (apply, pruned.is_empty()) {
+                    (true, false) => ", merged into them and deleted",
+                    (false, false) => ", nothing deleted without --apply",
+                    (_, true) => "",
+                }
+            );
+            return Ok(());
+        }
+        let groups = model_sqlite3_manga::duplicate_groups(&service.list()?);
+        for group in groups.iter() {
+            for (i, manga) in group.iter().enumerate() {
+                println!(
+                    "{} id={} '{}' {}",
+                    match (i, apply) {
+                        (0, _) => "keep  ",
+                        (_, true) => "delete",
+                        (_, false) => "      ",
+                    },
+                    manga.id(),
+                    manga.title(),
+                    manga.url()
+                );
+            }
+            if apply {
+                service.merge_duplicates(group[0].id(), &group[1..])?;
+            }
+            println!();
+        }
+        println!(
+            "dedupe: {} group(s) of look-alike rows{}",
+            groups.len(),
+            match (apply, groups.is_empty()) {
+                (true, false) => ", the others of each deleted",
+                (false, false) => ", nothing deleted without --apply",
+                (_, true) => "",
+            }
+        );
+        Ok(())
+    }
+
+    fn run_diff(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let csv_full_paths = match flag_value(args, "--csv") {
+            Some(csv_full_paths) => csv_full_paths,
+            None => return Err("diff: --csv <file.csv> is required".into()),
+        };
+        let csv_mangas = read_csv_rows(&csv_full_paths)?;
+        let db_full_paths = db_full_paths(args);
+        let db_mangas = select_all_manga_or_empty(&db_full_paths)?;
+
+        // same as bulk_insert_manga(): the same title and normalized url is the same manga
+        let manga_diff = manga_diff::diff_by(
+            &db_mangas,
+            &csv_mangas,
+            |manga| {
+                (
+                    manga.title().to_string(),
+                    model_sqlite3_manga::normalized_url(manga.url()),
+                )
+            },
+            Some(&manga_diff::K_CSV_FIELDS),
+        );
+        if has_flag(args, "--json") {
+            let report = serde_json::json!({
+                "only_in_csv": manga_diff.only_after,
+                "only_in_db": manga_diff.only_before,
+                "changed": manga_diff.changed,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        println!(
+            "only in '{}' ({}):",
+            csv_full_paths,
+            manga_diff.only_after.len()
+        );
+        for manga in manga_diff.only_after.iter() {
+            println!("    '{}' {}", manga.title(), manga.url());
+        }
+        println!(
+            "only in '{}' ({}):",
+            db_full_paths,
+            manga_diff.only_before.len()
+        );
+        for manga in manga_diff.only_before.iter() {
+            println!("    id={} '{}' {}", manga.id(), manga.title(), manga.url());
+        }
+        println!("different, database => CSV ({}):", manga_diff.changed.len());
+        for changed in manga_diff.changed.iter() {
+            println!("    id={} '{}'", changed.id, changed.title);
+            for field in changed.fields.iter() {
+                println!(
+                    "        {}: '{}' => '{}'",
+                    field.field, field.before, field.after
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // `normalize <title> <url>`: the stages of an import explained for a single bookmark
+    fn run_normalize(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let (title, url) = match (args.first(), args.get(1)) {
+            (Some(title), Some(url)) if !url.starts_with("--") => (title, url),
+            _ => return Err("normalize: <title> and <url> are required".into()),
+        };
+        let pipeline = normalize_pipeline_of(args, &load_config(args)?)?;
+        let (row, changes) = pipeline.run(title, url)?;
+        if has_flag(args, "--json") {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "stages": pipeline.stages,
+                    "row": row,
+                    "changes": changes,
+                }))?
+            );
+            return Ok(());
+        }
+        print!("{}", normalize_pipeline::explain(title, url, &changes));
+        println!("title:            {}", row.title);
+        println!(
+            "title_romanized:  {}",
+            row.title_romanized.unwrap_or_default()
+        );
+        println!("url:              {}", row.url);
+        println!(
+            "url_with_chapter: {}",
+            row.url_with_chapter.unwrap_or_default()
+        );
+        println!("chapter:          {}", row.chapter.unwrap_or_default());
+        Ok(())
+    }
+
+    fn run_import_yaml(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let yaml_file_paths = match args.first().filter(|arg| !arg.starts_with('-')) {
+            Some(yaml_file_paths) => yaml_file_paths,
+            None => return Err("import-yaml: <manga.yaml> is required".into()),
+        };
+        let yaml_file = match File::open(yaml_file_paths) {
+            Ok(yaml_file) => yaml_file,
+            Err(e) => {
+                return Err(format!("Error opening input file '{}': {}", yaml_file_paths, e).into())
+            }
+        };
+        let service = MangaService::open(&db_full_paths(args))?
+            .with_romanization(load_config(args)?.romanization_options());
+        let possible_git_export = git_export_snapshot(args, service.db_full_paths());
+        let summary = service.import_yaml(io::BufReader::new(yaml_file))?;
+        for failure in summary.failures.iter() {
+            eprintln!("import-yaml: {}", failure);
+        }
+        eprintln!(
+            "import-yaml: {} added, {} updated, {} unchanged, {} failed",
+            summary.added,
+            summary.updated,
+            summary.unchanged,
+            summary.failures.len()
+        );
+        git_export_commit(possible_git_export, service.db_full_paths(), "import-yaml");
+        Ok(())
+    }
+
+    // `snapshot create|restore <name> [--dir <snapshots>] [--force]`, `snapshot list`
+    fn run_snapshot(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let snapshot_dir = flag_value(args, "--dir")
+            .or(load_config(args)?.snapshot_dir)
+            .unwrap_or_else(|| snapshots::K_DEFAULT_SNAPSHOT_DIR.to_string());
+        let force = has_flag(args, "--force");
+        let name = || match args.get(1) {
+            Some(name) if !name.starts_with("--") => Ok(name.clone()),
+            _ => Err("snapshot: <name> is required"),
+        };
+        let describe = |metadata: &snapshots::SnapshotMetadata| {
+            let created =
+                chrono::TimeZone::timestamp_millis_opt(&chrono::Local, metadata.created_millis)
+                    .single()
+                    .map(|datetime| datetime.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+            format!(
+                "'{}' of {}, {} manga, {} ({} KiB compressed)",
+                metadata.name,
+                created,
+                metadata.manga_count,
+                metadata.database,
+                metadata.compressed_bytes / 1024
+            )
+        };
+        match args.first().map(|s| s.as_str()) {
+            Some("create") => {
+                let metadata = snapshots::create(&db_full_paths, &snapshot_dir, &name()?, force)?;
+                eprintln!("snapshot: created {}", describe(&metadata));
+            }
+            Some("restore") => {
+                // replaces the whole file, the daemon would keep writing to the old one
+                daemon_lock::refuse_while_active(&db_full_paths, "snapshot restore", force)?;
+                let metadata = snapshots::restore(&db_full_paths, &snapshot_dir, &name()?)?;
+                eprintln!(
+                    "snapshot: restored {} (previous database kept as '{}.bak')",
+                    describe(&metadata),
+                    db_full_paths
+                );
+            }
+            Some("list") => {
+                for metadata in snapshots::list(&snapshot_dir)?.iter() {
+                    println!("{}", describe(metadata));
+                }
+            }
+            _ => return Err("snapshot: expected create, restore or list".into()),
+        }
+        Ok(())
+    }
+
+    // `sync push|pull|status [--force]`
+    fn run_sync(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let sync_config = match load_config(args)?.sync {
+            Some(sync_config) => sync_config,
+            None => return Err("sync: no [sync] section in the config".into()),
+        };
+        // make sure the change counting triggers exist before comparing anything
+        MangaService::open(&db_full_paths)?;
+        let force = has_flag(args, "--force");
+        let outcome = match args.first().map(|s| s.as_str()) {
+            Some("push") => remote_sync::push(&db_full_paths, &sync_config, force)?,
+            Some("pull") => {
+                // replaces the whole file, the daemon would keep writing to the old one
+                daemon_lock::refuse_while_active(&db_full_paths, "sync pull", force)?;
+                remote_sync::pull(&db_full_paths, &sync_config, force)?
+            }
+            Some("status") => {
+                let status = remote_sync::status(&db_full_paths, &sync_config)?;
+                println!("remote:           {}", status.remote);
+                println!("local changes:    {}", status.local_changes);
+                println!(
+                    "synced revision:  {}",
+                    status
+                        .synced_revision
+                        .map_or("never synced".to_string(), |revision| revision.to_string())
+                );
+                println!(
+                    "remote revision:  {}",
+                    status.possible_remote_revision.map_or(
+                        "nothing pushed yet".to_string(),
+                        |revision| format!(
+                            "{} (pushed by {})",
+                            revision.revision, revision.pushed_by
+                        )
+                    )
+                );
+                return Ok(());
+            }
+            _ => return Err("sync: expected push, pull or status".into()),
+        };
+        match outcome {
+            remote_sync::SyncOutcome::Pushed(revision) => {
+                eprintln!("sync: pushed revision {}", revision)
+            }
+            remote_sync::SyncOutcome::Pulled(revision) => eprintln!(
+                "sync: pulled revision {} (previous database kept as '{}.bak')",
+                revision, db_full_paths
+            ),
+            remote_sync::SyncOutcome::UpToDate(revision) => {
+                eprintln!("sync: up to date with revision {}", revision)
+            }
+        }
+        Ok(())
+    }
+
+    // `export-obsidian <vault-dir> [--prune]`
+    fn run_export_obsidian(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let vault_dir = match args.first().filter(|arg| !arg.starts_with('-')) {
+            Some(vault_dir) => vault_dir,
+            None => return Err("export-obsidian: <vault-dir> is required".into()),
+        };
+        let service = MangaService::open(&db_full_paths(args))?;
+        let summary = service.export_obsidian(vault_dir, has_flag(args, "--prune"))?;
+        eprintln!(
+            "export-obsidian: {} notes written, {} unchanged, {} removed",
+            summary.written, summary.unchanged, summary.removed
+        );
+        Ok(())
+    }
+
+    fn print_site_stats(
+        args: &[String],
+        db_full_paths: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let checks = model_sqlite3_link_checks::select_link_checks(
+            db_full_paths,
+            since_millis_from_days(args)?,
+        )?;
+        let sites = stats::site_availability(&checks);
+        if has_flag(args, "--json") {
+            println!("{}", serde_json::to_string_pretty(&sites)?);
+            return Ok(());
+        }
+        if sites.is_empty() {
+            println!("no link checks recorded yet, run 'verify-urls' first");
+            return Ok(());
+        }
+        println!(
+            "{:<32} {:>7} {:>7} {:>6} {:>8} {:>6} {:>7}  last checked",
+            "domain", "entries", "failing", "status", "avg ms", "streak", "longest"
+        );
+        for site in sites.iter() {
+            println!(
+                "{:<32} {:>7} {:>7} {:>6} {:>8} {:>6} {:>7}  {}",
+                site.domain,
+                site.entries,
+                site.failing,
+                site.latest_status,
+                site.avg_response_millis
+                    .map(|millis| millis.to_string())
+                    .unwrap_or("-".to_string()),
+                site.current_failure_streak,
+                site.longest_failure_streak,
+                chrono::TimeZone::timestamp_millis_opt(&chrono::Local, site.last_checked_millis)
+                    .single()
+                    .map(|datetime| datetime.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    fn print_domain_stats(
+        args: &[String],
+        db_full_paths: &str,
+        mangas: &[MangaModel],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let checks = model_sqlite3_link_checks::select_link_checks(db_full_paths, 0)?;
+        let reports = stats::domain_report(mangas, &checks);
+        if has_flag(args, "--json") {
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+            return Ok(());
+        }
+        let to_date = |possible_millis: Option<i64>| {
+            possible_millis
+                .and_then(|millis| {
+                    chrono::TimeZone::timestamp_millis_opt(&chrono::Local, millis).single()
+                })
+                .map(|datetime| datetime.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or("-".to_string())
+        };
+        println!(
+            "{:<32} {:>6} {:>7} {:>8}  {:<16}  last checked",
+            "domain", "series", "unread", "chapters", "last ok"
+        );
+        for report in reports.iter() {
+            println!(
+                "{:<32} {:>6} {:>7} {:>8}  {:<16}  {}",
+                report.domain,
+                report.series,
+                report.with_unread,
+                report.total_unread,
+                to_date(report.last_success_millis),
+                to_date(report.last_checked_millis)
+            );
+        }
+        Ok(())
+    }
+
+    fn print_pace_stats(
+        args: &[String],
+        db_full_paths: &str,
+        mangas: &[MangaModel],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let sessions = model_sqlite3_reading_sessions::select_reading_sessions(
+            db_full_paths,
+            since_millis_from_days(args)?,
+            i64::MAX,
+        )?;
+        let titles = mangas
+            .iter()
+            .map(|manga| (manga.id(), manga.title().to_string()))
+            .collect();
+        let pace = stats::pace_stats(&sessions, &titles, K_STATS_TOP_COUNT);
+        if has_flag(args, "--json") {
+            println!("{}", serde_json::to_string_pretty(&pace)?);
+            return Ok(());
+        }
+        println!("chapters read:  {}", pace.total_chapters);
+        println!(
+            "longest streak: {} day(s){}",
+            pace.longest_streak_days,
+            pace.longest_streak_start
+                .map(|start| format!(" starting {}", start))
+                .unwrap_or_default()
+        );
+        println!("per month:");
+        for period in pace.per_month.iter() {
+            println!("  {}  {:>4}", period.period, period.chapters);
+        }
+        println!("per week:");
+        for period in pace.per_week.iter() {
+            println!("  {}  {:>4}", period.period, period.chapters);
+        }
+        println!("most read:");
+        for series in pace.most_read.iter() {
+            println!(
+                "  {:>4}  {} ({})",
+                series.chapters, series.title, series.manga_id
+            );
+        }
+        Ok(())
+    }
+
+    // --tag is case-insensitive exact match on any tag, --domain matches host and its subdomains
+    fn matches_tag_and_domain(
+        manga: &MangaModel,
+        possible_tag: &Option<String>,
+        possible_domain: &Option<String>,
+    ) -> bool {
+        let tag_matches = match possible_tag {
+            Some(tag) => manga
+                .tags()
+                .iter()
+                .any(|t| t.trim().to_lowercase() == tag.trim().to_lowercase()),
+            None => true,
+        };
+        let domain_matches = match possible_domain {
+            Some(domain) => match url::Url::parse(manga.url()) {
+                Ok(url) => url.host_str().map_or(false, |host| {
+                    host == domain || host.ends_with(&format!(".{}", domain))
+                }),
+                Err(_) => false,
+            },
+            None => true,
+        };
+        tag_matches && domain_matches
+    }
+
+    // `mark-read <id> <chapter>`, `mark-read <id> --to-latest`, or in bulk
+    // `mark-read --all --to-latest [--tag <tag>] [--domain <domain>]`
+    fn run_mark_read(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let to_latest = has_flag(args, "--to-latest");
+
+        let candidates = if has_flag(args, "--all") {
+            if !to_latest {
+                return Err("mark-read: --all requires --to-latest".into());
+            }
+            let possible_tag = flag_value(args, "--tag");
+            let possible_domain = flag_value(args, "--domain");
+            service
+                .list()?
+                .into_iter()
+                .filter(|manga| matches_tag_and_domain(manga, &possible_tag, &possible_domain))
+                .collect::<Vec<MangaModel>>()
+        } else {
+            let id = match args.get(0) {
+                Some(id) => id.parse::<i64>()?,
+                None => return Err("mark-read: missing <id>".into()),
+            };
+            vec![service.get(id)?]
+        };
+
+        let read_at = chrono::Local::now();
+        let mut mangas_read = Vec::new();
+        for manga in candidates.iter() {
+            let chapter = if to_latest {
+                // only those which actually have something unread
+                match (manga.latest_available_chapter(), manga.unread_count()) {
+                    (Some(latest), Some(unread)) if unread > 0 => latest,
+                    _ => continue,
+                }
+            } else {
+                // normalize "12-1" (as it appears in urls) to "12.1"
+                match args.get(1).map(|chapter| Chapter::parse(chapter)) {
+                    Some(Some(chapter)) => chapter.to_string(),
+                    Some(None) => {
+                        return Err(format!("mark-read: invalid chapter '{}'", args[1]).into())
+                    }
+                    None => return Err("mark-read: missing <chapter> (or --to-latest)".into()),
+                }
+            };
+            mangas_read.push(service::as_read(manga, &chapter, &read_at));
+        }
+
+        let rows_updated =
+            model_sqlite3_manga::update_read_chapters(service.db_full_paths(), &mangas_read)?;
+        for manga in mangas_read.iter() {
+            println!(
+                "mark-read: id={} '{}' chapter {} {}",
+                manga.id(),
+                manga.title(),
+                manga.chapter().unwrap_or_default(),
+                manga.url_with_chapter().unwrap_or_default()
+            );
+        }
+        println!("mark-read: {} row(s) updated", rows_updated);
+        Ok(())
+    }
+
+    // `field <id>` lists the user fields, `field <id> <key>` shows one, `field <id> <key> <value>|--clear` sets it
+    fn run_field(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let id = match args.get(0) {
+            Some(id) => id.parse::<i64>()?,
+            None => return Err("field: missing <id>".into()),
+        };
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let key = match args.get(1).filter(|key| !key.starts_with('-')) {
+            Some(key) => key,
+            None => {
+                let manga = model_sqlite3_manga::select_manga_by_id(&db_full_paths, id)?;
+                for (key, value) in manga.user_fields().iter() {
+                    println!("{}\t{}", key, value);
+                }
+                return Ok(());
+            }
+        };
+        let possible_value = match args.get(2).map(|s| s.as_str()) {
+            Some("--clear") => None,
+            Some(value) if !value.starts_with('-') => Some(value),
+            _ => {
+                let possible_value = model_sqlite3_manga::get_field(&db_full_paths, id, key)?;
+                println!("{}", possible_value.unwrap_or_default());
+                return Ok(());
+            }
+        };
+        match model_sqlite3_manga::set_field(&db_full_paths, id, key, possible_value)? {
+            true => {
+                println!("field: id={} {}={:?}", id, key, possible_value);
+                Ok(())
+            }
+            false => Err(format!("field: id={} not found in '{}'", id, db_full_paths).into()),
+        }
+    }
+
+    // `tags [list] [--json]`, `tags rename <old> <new>`, `tags merge <from> <into>`, `tags delete-orphaned`
+    fn run_tags(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&db_full_paths(args))?;
+        let tag_args: Vec<&String> = args
+            .iter()
+            .take_while(|arg| !arg.starts_with('-'))
+            .collect();
+        let two_tags = |action: &str| match (tag_args.get(1), tag_args.get(2)) {
+            (Some(first), Some(second)) => Ok((first.as_str(), second.as_str())),
+            _ => Err(format!("tags {}: two tags are required", action)),
+        };
+        match tag_args.first().map(|s| s.as_str()) {
+            None | Some("list") => {
+                let counts = service.tag_counts()?;
+                match has_flag(args, "--json") {
+                    true => println!("{}", serde_json::to_string_pretty(&counts)?),
+                    false => {
+                        for (tag, count) in counts.iter() {
+                            println!("{}\t{}", count, tag);
+                        }
+                    }
+                }
+            }
+            Some("rename") => {
+                let (old_tag, new_tag) = two_tags("rename")?;
+                let renamed_count = service.rename_tag(old_tag, new_tag)?;
+                eprintln!(
+                    "tags: '{}' renamed to '{}' ({} manga)",
+                    old_tag, new_tag, renamed_count
+                );
+            }
+            Some("merge") => {
+                let (from_tag, into_tag) = two_tags("merge")?;
+                let merged_count = service.merge_tags(from_tag, into_tag)?;
+                eprintln!(
+                    "tags: '{}' merged into '{}' ({} manga)",
+                    from_tag, into_tag, merged_count
+                );
+            }
+            Some("delete-orphaned") => {
+                let deleted_tags = service.delete_orphaned_tags()?;
+                for tag in deleted_tags.iter() {
+                    println!("{}", tag);
+                }
+                eprintln!("tags: {} tag(s) on no manga deleted", deleted_tags.len());
+            }
+            Some(unknown) => {
+                return Err(format!(
+                    "tags: unknown '{}', expected list, rename, merge or delete-orphaned",
+                    unknown
+                )
+                .into())
+            }
+        }
+        Ok(())
+    }
+
+    fn run_set_feed(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let id = match args.get(0) {
+            Some(id) => id.parse::<i64>()?,
+            None => return Err("set-feed: missing <id>".into()),
+        };
+        let possible_feed_url = match args.get(1).map(|s| s.as_str()) {
+            Some("--clear") => None,
+            Some(feed_url) => Some(url::Url::parse(feed_url)?.to_string()),
+            None => return Err("set-feed: missing <feed_url> (or --clear)".into()),
+        };
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+
+        match model_sqlite3_manga::update_feed_url(
+            &db_full_paths,
+            id,
+            possible_feed_url.as_deref(),
+        )? {
+            true => {
+                println!("set-feed: id={} feed_url={:?}", id, possible_feed_url);
+                Ok(())
+            }
+            false => Err(format!("set-feed: id={} not found in '{}'", id, db_full_paths).into()),
+        }
+    }
+
+    fn run_verify_urls(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let mangas = match flag_value(args, "--id") {
+            Some(id) => vec![model_sqlite3_manga::select_manga_by_id(
+                &db_full_paths,
+                id.parse::<i64>()?,
+            )?],
+            None => select_all_manga_or_empty(&db_full_paths)?,
+        };
+
+        let (mut broken_count, mut unreachable_count, mut moved_count) = (0, 0, 0);
+        for manga in mangas.iter() {
+            let probe_result = update_checker::probe_url(manga.url());
+            let (status, response_millis) = match &probe_result {
+                Ok(probe) => (probe.status, probe.response_millis),
+                Err(_) => (model_sqlite3_link_checks::K_STATUS_UNREACHABLE, 0),
+            };
+            model_sqlite3_link_checks::insert_link_check(
+                &db_full_paths,
+                manga.id(),
+                manga.url(),
+                status,
+                response_millis,
+                chrono::Utc::now().timestamp_millis(),
+            )?;
+            match probe_result {
+                Ok(probe) => {
+                    if let Some(moved_to) = &probe.possible_moved_to {
+                        update_checker::heal_moved_url(
+                            &db_full_paths,
+                            manga,
+                            moved_to,
+                            "permanent redirect",
+                        );
+                        moved_count += 1;
+                    }
+                    if probe.status >= 400 {
+                        broken_count += 1;
+                        println!(
+                            "id={} {} '{}' ({} ms)",
+                            manga.id(),
+                            probe.status,
+                            manga.url(),
+                            probe.response_millis
+                        );
+                    }
+                }
+                Err(e) => {
+                    unreachable_count += 1;
+                    println!("id={} unreachable '{}': {}", manga.id(), manga.url(), e);
+                }
+            }
+        }
+        println!(
+            "verify-urls: {} checked, {} broken, {} unreachable, {} moved",
+            mangas.len(),
+            broken_count,
+            unreachable_count,
+            moved_count
+        );
+        Ok(())
+    }
+
+    // y/N prompt on stdin, anything but "y"/"yes" is a no
+    fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        print!("{} [y/N] ", prompt);
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    // `--preview`: apply runs on a shadow copy of the database, whose changed rows are printed and then committed
+    // to the database after confirmation (or with assume_yes)
+    pub fn run_previewed(
+        db_full_paths: &str,
+        operation: &str,
+        assume_yes: bool,
+        apply: impl FnOnce(&str) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let shadow = ShadowDatabase::create(db_full_paths)?;
+        apply(shadow.full_paths())?;
+        let manga_diff = shadow.diff()?;
+        if manga_diff.is_empty() {
+            println!("{}: nothing would change", operation);
+            return Ok(());
+        }
+        for line in manga_diff.to_lines() {
+            println!("{}", line);
+        }
+        println!(
+            "{}: {} row(s) would be added, {} deleted, {} changed",
+            operation,
+            manga_diff.only_after.len(),
+            manga_diff.only_before.len(),
+            manga_diff.changed.len()
+        );
+        if !assume_yes && !confirm(&format!("commit to '{}'?", db_full_paths))? {
+            println!("{}: nothing committed", operation);
+            return Ok(());
+        }
+        shadow.commit()?;
+        println!(
+            "{}: committed (previous database kept as '{}.bak')",
+            operation, db_full_paths
+        );
+        Ok(())
+    }
+
+    fn run_migrate_site(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let dead_domain = match args.get(0) {
+            Some(dead_domain) if !dead_domain.starts_with('-') => dead_domain.to_lowercase(),
+            _ => return Err("migrate-site: missing <dead-domain>".into()),
+        };
+        daemon_lock::refuse_while_active(
+            &db_full_paths,
+            "migrate-site",
+            has_flag(args, "--force"),
+        )?;
+        let config = load_config(args)?;
+        if config.aggregators.is_empty() {
+            return Err("migrate-site: no [[aggregators]] in the config to search on".into());
+        }
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let mangas = model_sqlite3_manga::select_by_domain(&db_full_paths, &dead_domain)?;
+
+        let proposals =
+            site_migration::propose_migrations(&mangas, &dead_domain, &config.aggregators);
+        println!(
+            "migrate-site: {} of {} manga on '{}' found elsewhere",
+            proposals.len(),
+            mangas.len(),
+            dead_domain
+        );
+
+        let assume_yes = has_flag(args, "--yes");
+        let apply_proposals = |target_full_paths: &str,
+                               confirm_each: bool|
+         -> Result<usize, Box<dyn std::error::Error>> {
+            let mut applied_count = 0;
+            for proposal in proposals.iter() {
+                println!(
+                    "id={} '{}' ({})\n    {}\n => {}",
+                    proposal.manga_id,
+                    proposal.title,
+                    proposal.aggregator,
+                    proposal.old_url,
+                    proposal.new_url
+                );
+                if confirm_each && !confirm("apply?")? {
+                    continue;
+                }
+                match mangas.iter().find(|manga| manga.id() == proposal.manga_id) {
+                    Some(manga) => {
+                        let migrated = site_migration::apply_proposal(manga, proposal);
+                        // url first so that the dead one is kept in url_history
+                        model_sqlite3_manga::update_url(
+                            target_full_paths,
+                            manga.id(),
+                            migrated.url(),
+                            "migrate-site",
+                        )?;
+                        model_sqlite3_manga::update_manga(target_full_paths, &migrated)?;
+                        applied_count += 1;
+                    }
+                    None => continue,
+                }
+            }
+            Ok(applied_count)
+        };
+        if has_flag(args, "--preview") {
+            return run_previewed(
+                &db_full_paths,
+                "migrate-site",
+                assume_yes,
+                |shadow_full_paths| apply_proposals(shadow_full_paths, false).map(|_| ()),
+            );
+        }
+        let applied_count = apply_proposals(&db_full_paths, !assume_yes)?;
+        println!("migrate-site: {} url(s) replaced", applied_count);
+        Ok(())
+    }
+
+    fn run_repair_titles(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let config = load_config(args)?;
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        // without --id/--all, only the titles that look like site boilerplate are fetched
+        let mangas = match flag_value(args, "--id") {
+            Some(id) => vec![model_sqlite3_manga::select_manga_by_id(
+                &db_full_paths,
+                id.parse::<i64>()?,
+            )?],
+            None => select_all_manga_or_empty(&db_full_paths)?
+                .into_iter()
+                .filter(|manga| {
+                    has_flag(args, "--all")
+                        || title_repair::looks_bad_title(manga, &config.title_rules)
+                })
+                .collect(),
+        };
+
+        let assume_yes = has_flag(args, "--apply");
+        let mut repaired_count = 0;
+        for manga in mangas.iter() {
+            let proposal = match title_repair::propose_title(manga, &config.title_rules) {
+                Ok(Some(proposal)) => proposal,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!(
+                        "ERROR: repair-titles(id={}, url='{}'): {}",
+                        manga.id(),
+                        manga.url(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            println!(
+                "id={}\n    '{}'\n => '{}'",
+                proposal.manga_id, proposal.old_title, proposal.new_title
+            );
+            if !assume_yes && !confirm("apply?")? {
+                continue;
+            }
+            let repaired =
+                title_repair::apply_proposal(manga, &proposal, &config.romanization_options());
+            match model_sqlite3_manga::update_manga(&db_full_paths, &repaired) {
+                Ok(()) => repaired_count += 1,
+                // most likely UNIQUE(title, url), the same series is already in the database under that title
+                Err(e) => println!("ERROR: repair-titles(id={}): {}", manga.id(), e),
+            }
+        }
+        println!(
+            "repair-titles: {} of {} title(s) repaired",
+            repaired_count,
+            mangas.len()
+        );
+        Ok(())
+    }
+
+    // offline counterpart of repair-titles: only the [[title_rules]], applied to the titles already stored
+    fn run_clean_titles(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let config = load_config(args)?;
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        if config.title_rules.is_empty() {
+            println!("clean-titles: no [[title_rules]] in the config");
+            return Ok(());
+        }
+        let mangas = match flag_value(args, "--id") {
+            Some(id) => vec![model_sqlite3_manga::select_manga_by_id(
+                &db_full_paths,
+                id.parse::<i64>()?,
+            )?],
+            None => select_all_manga_or_empty(&db_full_paths)?,
+        };
+
+        let proposals = title_repair::propose_stripped_titles(&mangas, &config.title_rules);
+        let assume_yes = has_flag(args, "--apply");
+        let mut cleaned_count = 0;
+        for (manga, proposal) in mangas.iter().filter_map(|manga| {
+            proposals
+                .iter()
+                .find(|proposal| proposal.manga_id == manga.id())
+                .map(|proposal| (manga, proposal))
+        }) {
+            println!(
+                "id={}\n    '{}'\n => '{}'",
+                proposal.manga_id, proposal.old_title, proposal.new_title
+            );
+            if !assume_yes && !confirm("apply?")? {
+                continue;
+            }
+            let cleaned =
+                title_repair::apply_proposal(manga, proposal, &config.romanization_options());
+            match model_sqlite3_manga::update_manga(&db_full_paths, &cleaned) {
+                Ok(()) => cleaned_count += 1,
+                // UNIQUE(title, url): the clean title is already in the database for that url
+                Err(e) => println!("ERROR: clean-titles(id={}): {}", manga.id(), e),
+            }
+        }
+        println!(
+            "clean-titles: {} of {} title(s) cleaned",
+            cleaned_count,
+            proposals.len()
+        );
+        Ok(())
+    }
+
+    // romanized titles of another style or dictionary (or none) are replaced; titles which are not Japanese keep
+    // whatever romanized title was given to them
+    fn run_re_romanize(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let mut romanization = load_config(args)?.romanization_options();
+        if let Some(style) = flag_value(args, "--style") {
+            romanization.style = style.parse()?;
+        }
+        let dry_run = has_flag(args, "--dry-run");
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let mangas = select_all_manga_or_empty(&db_full_paths)?;
+
+        let mut changed_count = 0;
+        for manga in mangas.iter() {
+            let romanized = match MangaModel::romanize_title_with(manga.title(), &romanization) {
+                Some(romanized) if Some(&romanized) != manga.title_romanized().as_ref() => {
+                    romanized
+                }
+                _ => continue,
+            };
+            println!(
+                "id={}\n    '{}'\n => '{}'",
+                manga.id(),
+                manga.title_romanized().unwrap_or_default(),
+                romanized
+            );
+            if dry_run {
+                changed_count += 1;
+                continue;
+            }
+            let mut re_romanized = manga.clone();
+            re_romanized.set_title_romanized(Some(romanized));
+            match model_sqlite3_manga::update_manga(&db_full_paths, &re_romanized) {
+                Ok(()) => changed_count += 1,
+                Err(e) => println!("ERROR: re-romanize(id={}): {}", manga.id(), e),
+            }
+        }
+        println!(
+            "re-romanize: {} of {} title(s) {} ({:?})",
+            changed_count,
+            mangas.len(),
+            match dry_run {
+                true => "would change",
+                false => "changed",
+            },
+            romanization.style
+        );
+        Ok(())
+    }
+
+    // numbered choice on stdin, Ok(None) on empty (or out of range) answer
+    fn choose(
+        prompt: &str,
+        choices_count: usize,
+    ) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        print!("{} [1-{}, Enter to skip] ", prompt, choices_count);
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        Ok(match answer.trim().parse::<usize>() {
+            Ok(choice) if choice >= 1 && choice <= choices_count => Some(choice - 1),
+            _ => None,
+        })
+    }
+
+    fn run_enrich(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let config = load_config(args)?;
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let mangas = match flag_value(args, "--id") {
+            Some(id) => vec![model_sqlite3_manga::select_manga_by_id(
+                &db_full_paths,
+                id.parse::<i64>()?,
+            )?],
+            None => select_all_manga_or_empty(&db_full_paths)?
+                .into_iter()
+                .filter(|manga| {
+                    manga.my_anime_list().is_none()
+                        && matches!(
+                            model_sqlite3_manga_metadata::select_manga_metadata(
+                                &db_full_paths,
+                                manga.id()
+                            ),
+                            Ok(None)
+                        )
+                })
+                .collect(),
+        };
+        let source = match flag_value(args, "--source") {
+            Some(source) => source.parse::<config::EnrichmentSource>()?,
+            None => config.enrichment_source,
+        };
+
+        let interactive = has_flag(args, "--interactive");
+        let (mut enriched_count, mut ambiguous_count, mut not_found_count) = (0, 0, 0);
+        for manga in mangas.iter() {
+            let searched_title = enrichment::search_title(manga, &config.title_rules);
+            let matches = match enrichment::search(source, manga.id(), &searched_title) {
+                Ok(matches) => matches,
+                Err(e) => {
+                    println!(
+                        "ERROR: enrich(id={}, '{}'): {}",
+                        manga.id(),
+                        searched_title,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let possible_found = match matches
+                .iter()
+                .find(|found| enrichment::is_confident_match(&searched_title, found))
+            {
+                Some(found) => Some(found),
+                None if matches.is_empty() => {
+                    not_found_count += 1;
+                    println!("id={} '{}': not found", manga.id(), searched_title);
+                    None
+                }
+                None if interactive => {
+                    println!("id={} '{}' ({}):", manga.id(), searched_title, manga.url());
+                    for (index, found) in matches.iter().enumerate() {
+                        println!(
+                            "  {}) {} [{}] {}",
+                            index + 1,
+                            found.titles.first().map(|t| t.as_str()).unwrap_or("?"),
+                            found.metadata.publication_status.as_deref().unwrap_or("-"),
+                            found.metadata.source_url.as_deref().unwrap_or("")
+                        );
+                    }
+                    choose("which one?", matches.len())?.map(|index| &matches[index])
+                }
+                None => {
+                    ambiguous_count += 1;
+                    println!(
+                        "id={} '{}': {} possible match(es), re-run with --interactive to choose",
+                        manga.id(),
+                        searched_title,
+                        matches.len()
+                    );
+                    None
+                }
+            };
+            if let Some(found) = possible_found {
+                let tags_count = enrichment::apply_match(&db_full_paths, found)?;
+                enriched_count += 1;
+                println!(
+                    "id={} '{}' => {} (+{} genre tag(s))",
+                    manga.id(),
+                    searched_title,
+                    found.metadata.source_url.as_deref().unwrap_or("-"),
+                    tags_count
+                );
+            }
+        }
+        println!(
+            "enrich: {} enriched, {} ambiguous, {} not found (of {})",
+            enriched_count,
+            ambiguous_count,
+            not_found_count,
+            mangas.len()
+        );
+        Ok(())
+    }
+
+    fn run_fetch_covers(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let config = load_config(args)?;
+        model_sqlite3_manga::create_tables(&db_full_paths)?;
+        let cache_dir = flag_value(args, "--dir")
+            .or(config.cover_cache_dir)
+            .unwrap_or_else(|| cover_cache::K_DEFAULT_COVER_CACHE_DIR.to_string());
+        let max_megabytes = match flag_value(args, "--max-mb") {
+            Some(max_megabytes) => max_megabytes.parse::<u64>()?,
+            None => config
+                .cover_cache_max_megabytes
+                .unwrap_or(cover_cache::K_DEFAULT_MAX_MEGABYTES),
+        };
+        let manga_ids: Vec<i64> = match flag_value(args, "--id") {
+            Some(id) => vec![id.parse::<i64>()?],
+            None => select_all_manga_or_empty(&db_full_paths)?
+                .iter()
+                .map(|manga| manga.id())
+                .collect(),
+        };
+
+        let (mut downloaded_count, mut cached_count) = (0, 0);
+        for manga_id in manga_ids {
+            let possible_cover_url =
+                model_sqlite3_manga_metadata::select_manga_metadata(&db_full_paths, manga_id)?
+                    .and_then(|metadata| metadata.cover_url);
+            let cover_url = match possible_cover_url {
+                Some(cover_url) => cover_url,
+                None => continue,
+            };
+            match cover_cache::fetch_cover(&cache_dir, &cover_url) {
+                Ok(true) => downloaded_count += 1,
+                Ok(false) => cached_count += 1,
+                Err(e) => println!(
+                    "ERROR: fetch-covers(id={}, '{}'): {}",
+                    manga_id, cover_url, e
+                ),
+            }
+        }
+        let removed_count =
+            cover_cache::enforce_size_limit(&cache_dir, max_megabytes * 1024 * 1024)?;
+        println!(
+            "fetch-covers: {} downloaded, {} already cached, {} removed to stay under {} MB ('{}')",
+            downloaded_count, cached_count, removed_count, max_megabytes, cache_dir
+        );
+        Ok(())
+    }
+
+    // hands the url to the desktop's default browser
+    pub fn open_in_browser(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let status = if cfg!(target_os = "windows") {
+            std::process::Command::new("cmd")
+                .args(["/C", "start", "", url])
+                .status()?
+        } else if cfg!(target_os = "macos") {
+            std::process::Command::new("open").arg(url).status()?
+        } else {
+            std::process::Command::new("xdg-open").arg(url).status()?
+        };
+        match status.success() {
+            true => Ok(()),
+            false => Err(format!("failed to open '{}' ({})", url, status).into()),
+        }
+    }
+
+    fn run_continue(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let db_full_paths = db_full_paths(args);
+        let id = match args.get(0) {
+            Some(id) => id.parse::<i64>()?,
+            None => return Err("continue: missing <id>".into()),
+        };
+        let manga = model_sqlite3_manga::select_manga_by_id(&db_full_paths, id)?;
+        let possible_chapter = manga.chapter().and_then(|chapter| Chapter::parse(&chapter));
+        if manga.chapter().is_some() && possible_chapter.is_none() {
+            return Err(format!(
+                "continue: cannot tell what comes after chapter '{}'",
+                manga.chapter().unwrap_or_default()
+            )
+            .into());
+        }
+
+        // "12" => "13", but "12.1" => "12-2" which may not exist, in which case "13" is next
+        let mut candidate_urls = vec![model_sqlite3_manga::increment_chapter(&manga).1];
+        if let Some(chapter) = possible_chapter {
+            let mut next_major = manga.clone();
+            next_major.set_chapter(Some(chapter.major().to_string()));
+            let next_major_url = model_sqlite3_manga::increment_chapter(&next_major).1;
+            if !candidate_urls.contains(&next_major_url) {
+                candidate_urls.push(next_major_url);
+            }
+        }
+
+        let possible_next_url = match has_flag(args, "--no-verify") {
+            true => candidate_urls.first().cloned(),
+            false => {
+                let mut possible_found = None;
+                for candidate_url in candidate_urls.iter() {
+                    if update_checker::url_exists(candidate_url)? {
+                        possible_found = Some(candidate_url.clone());
+                        break;
+                    }
+                    eprintln!("continue: '{}' does not exist (yet)", candidate_url);
+                }
+                possible_found
+            }
+        };
+        let next_url = match possible_next_url {
+            Some(next_url) => next_url,
+            None => return Err(format!("continue: no next chapter found for id={}", id).into()),
+        };
+
+        println!("{}", next_url);
+        if has_flag(args, "--open") {
+            open_in_browser(&next_url)?;
+        }
+        Ok(())
+    }
+
+    fn run_openapi(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut output_writer = output_writer(args)?;
+        writeln!(output_writer, "{}", rest_server::openapi_json()?)?;
+        output_writer.flush()?;
+        Ok(())
+    }
+
+    // create_tables() reports its progress on stdout, so without --out the statements go to (a duplicate of) the
+    // original stdout and the rest to stderr, as with --rpc
+    fn run_schema(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut output_writer = match flag_value(args, "--out") {
+            Some(_) => output_writer(args)?,
+            None => json_rpc::protocol_writer()?,
+        };
+        writeln!(
+            output_writer,
+            "-- schema of {} {}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        for sql in model_sqlite3_manga::schema_ddl()? {
+            writeln!(output_writer, "{};\n", sql)?;
+        }
+        output_writer.flush()?;
+        Ok(())
+    }
+
+    fn run_tui(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let config = load_config(args)?;
+        tui::run(tui::TuiOptions {
+            db_full_paths: db_full_paths(args),
+            cover_cache_dir: config
+                .cover_cache_dir
+                .unwrap_or_else(|| cover_cache::K_DEFAULT_COVER_CACHE_DIR.to_string()),
+            force: has_flag(args, "--force"),
+        })
+    }
+
+    fn run_shell(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let history_full_paths = flag_value(args, "--history").unwrap_or_else(|| {
+            match std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+                Ok(home) => std::path::Path::new(&home)
+                    .join(shell::K_DEFAULT_HISTORY_FILE)
+                    .to_string_lossy()
+                    .to_string(),
+                Err(_) => shell::K_DEFAULT_HISTORY_FILE.to_string(),
+            }
+        });
+        shell::run(shell::ShellOptions {
+            db_full_paths: db_full_paths(args),
+            history_full_paths,
+        })
+    }
+
+    // `native-host` is started by the browser; `--manifest <host script> --extension-id <id>` prints what to install
+    fn run_native_host(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(host_path) = flag_value(args, "--manifest") {
+            let extension_id = flag_value(args, "--extension-id")
+                .ok_or("native-host: --manifest needs --extension-id <id>")?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&native_host::manifest(&host_path, &extension_id))?
+            );
+            return Ok(());
+        }
+        // from here on, anything printed goes to stderr so that stdout only carries messages
+        let mut writer = json_rpc::protocol_writer()?;
+        let pipeline = normalize_pipeline_of(args, &load_config(args)?)?;
+        native_host::run(&mut writer, &db_full_paths(args), &pipeline)
+    }
+
+    fn run_serve(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        rest_server::run(rest_server::ServerOptions {
+            db_full_paths: db_full_paths(args),
+            bind: flag_value(args, "--bind")
+                .unwrap_or_else(|| rest_server::K_DEFAULT_BIND.to_string()),
+        })
+    }
+
+    #[cfg(feature = "grpc")]
+    fn run_serve_grpc(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let config = load_config(args)?;
+        grpc_server::run(grpc_server::GrpcServerOptions {
+            db_full_paths: db_full_paths(args),
+            bind: flag_value(args, "--bind")
+                .unwrap_or_else(|| grpc_server::K_DEFAULT_BIND.to_string()),
+            romanization: config.romanization_options(),
+            title_rules: config.title_rules,
+            title_collision: config.title_collision,
+            debug_flag: has_flag(args, "-D"),
+        })
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    fn run_serve_grpc(_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        Err("serve-grpc: not available, rebuild with `cargo build --features grpc`".into())
+    }
+
+    fn run_daemon(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let config = load_config(args)?;
+        // command line wins over config
+        let possible_check_schedule =
+            match flag_value(args, "--check-schedule").or(config.check_schedule.clone()) {
+                Some(expression) => Some(CronSchedule::parse(&expression)?),
+                None => None,
+            };
+        let possible_bookmarks_full_paths = flag_value(args, "-i");
+        if possible_bookmarks_full_paths.is_none() && possible_check_schedule.is_none() {
+            return Err(
+                "daemon: nothing to do, need either -i <bookmarks.json> or a check schedule".into(),
+            );
+        }
+
+        let possible_metrics_bind =
+            flag_value(args, "--metrics-bind").or(config.metrics_bind.clone());
+
+        daemon::run(daemon::DaemonOptions {
+            db_full_paths: db_full_paths(args),
+            possible_bookmarks_full_paths,
+            possible_check_schedule,
+            possible_metrics_bind,
+            config,
+            debug_flag: has_flag(args, "-D"),
+            force: has_flag(args, "--force"),
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn to_args(args: &[&str]) -> Vec<String> {
+            args.iter().map(|s| s.to_string()).collect()
+        }
+
+        #[test]
+        fn test_flag_value() {
+            let args = to_args(&["--out", "updates.xml", "-d", "my.sqlite3", "--verbose"]);
+            assert_eq!(flag_value(&args, "--out"), Some("updates.xml".to_string()));
+            assert_eq!(flag_value(&args, "-d"), Some("my.sqlite3".to_string()));
+            assert_eq!(flag_value(&args, "--verbose"), None); // flag without value
+            assert_eq!(flag_value(&args, "--missing"), None);
+            assert!(has_flag(&args, "--verbose"));
+            assert!(!has_flag(&args, "--missing"));
+        }
+
+        #[test]
+        fn test_is_subcommand() {
+            assert!(is_subcommand(&to_args(&[
+                "manga", "feed", "--out", "x.xml"
+            ])));
+            assert!(!is_subcommand(&to_args(&["manga", "-i", "bookmarks.json"])));
+            assert!(!is_subcommand(&to_args(&["manga"])));
+        }
+
+        #[test]
+        fn test_legacy_import_args() {
+            assert_eq!(
+                legacy_import_args(&to_args(&[
+                    "manga", "import", "-i", "b.json", "-d", "my.db"
+                ])),
+                Some(to_args(&["manga", "-i", "b.json", "-d", "my.db"]))
+            );
+            assert_eq!(
+                legacy_import_args(&to_args(&["manga", "import", "firefox_main"])),
+                None
+            );
+            assert_eq!(
+                legacy_import_args(&to_args(&["manga", "-i", "b.json"])),
+                None
+            );
+        }
+
+        #[test]
+        fn test_subcommand_usage() {
+            let usage = subcommand_usage("manga", "native-host").unwrap();
+            assert!(usage.starts_with("Usage: manga native-host "));
+            // both of its lines, and what it does, but nothing of the next subcommand
+            assert!(usage.contains("\n  native-host [--stages"));
+            assert!(usage.contains("\n  native-host --manifest"));
+            assert!(usage.contains("Native messaging host"));
+            assert!(!usage.contains("openapi"));
+            assert!(subcommand_usage("manga", "dedupe")
+                .unwrap()
+                .contains("--apply"));
+            assert!(subcommand_usage("manga", "dedupe")
+                .unwrap()
+                .contains("--keep-japanese"));
+            assert_eq!(subcommand_usage("manga", "no-such-subcommand"), None);
+        }
+
+        #[test]
+        fn test_date_flag() {
+            let args = to_args(&["--since", "2023-09-01", "--until", "2023-09-30"]);
+            let since = date_flag(&args, "--since").unwrap().unwrap();
+            let until = date_flag(&args, "--until").unwrap().unwrap();
+            // the whole of the --until day is in the range
+            assert_eq!(until - since, chrono::Duration::days(30));
+            let args = to_args(&["--since", "2023-09-01T12:00:00Z", "--until", "yesterday"]);
+            assert_eq!(
+                date_flag(&args, "--since")
+                    .unwrap()
+                    .unwrap()
+                    .timestamp_millis(),
+                1693569600000
+            );
+            assert!(date_flag(&args, "--until").is_err());
+            assert_eq!(date_flag(&args, "--missing").unwrap(), None);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// Boolean expressions over tags, i.e. `isekai AND NOT dropped OR (seinen AND ongoing)`, for filtering in the
+// database rather than after loading every row (see model_sqlite3_manga::select_by_tag_expr()):
+//      expression  = and_term { OR and_term }
+//      and_term    = factor { AND factor }
+//      factor      = NOT factor | "(" expression ")" | tag
+//      tag         = word | "quoted words"     i.e. seinen, genre:Action, "genre:Slice of Life"
+// so NOT binds tighter than AND, which binds tighter than OR.  Keywords are case-insensitive (a tag named
+// "and" has to be quoted), and tags match the way `--tag` does: trimmed, ignoring ASCII case.
+pub mod tag_expression {
+    use rusqlite::types::Value;
+    use std::fmt;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum TagExpr {
+        Tag(String),
+        Not(Box<TagExpr>),
+        And(Box<TagExpr>, Box<TagExpr>),
+        Or(Box<TagExpr>, Box<TagExpr>),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Token {
+        Word(String),
+        Quoted(String),
+        Open,
+        Close,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TagExprError(pub String);
+
+    impl fmt::Display for TagExprError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "invalid tag expression: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for TagExprError {}
+
+    fn tokenize(expression: &str) -> Result<Vec<Token>, TagExprError> {
+        let mut tokens = Vec::new();
+        let mut chars = expression.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c.is_whitespace() => {}
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                '"' => {
+                    let mut quoted = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => quoted.push(c),
+                            None => return Err(TagExprError("unterminated quote".to_string())),
+                        }
+                    }
+                    tokens.push(Token::Quoted(quoted));
+                }
+                c => {
+                    let mut word = c.to_string();
+                    while let Some(&next) = chars.peek() {
+                        if next.is_whitespace() || next == '(' || next == ')' || next == '"' {
+                            break;
+                        }
+                        word.push(next);
+                        chars.next();
+                    }
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+        matches!(token, Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    struct Parser {
+        tokens: Vec<Token>,
+        position: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.position)
+        }
+
+        fn expression(&mut self) -> Result<TagExpr, TagExprError> {
+            let mut left = self.and_term()?;
+            while is_keyword(self.peek(), "OR") {
+                self.position += 1;
+                left = TagExpr::Or(Box::new(left), Box::new(self.and_term()?));
+            }
+            Ok(left)
+        }
+
+        fn and_term(&mut self) -> Result<TagExpr, TagExprError> {
+            let mut left = self.factor()?;
+            while is_keyword(self.peek(), "AND") {
+                self.position += 1;
+                left = TagExpr::And(Box::new(left), Box::new(self.factor()?));
+            }
+            Ok(left)
+        }
+
+        fn factor(&mut self) -> Result<TagExpr, TagExprError> {
+            let token = self.peek().cloned();
+            self.position += 1;
+            match token {
+                Some(Token::Word(word)) if word.eq_ignore_ascii_case("NOT") => {
+                    Ok(TagExpr::Not(Box::new(self.factor()?)))
+                }
+                Some(Token::Word(word))
+                    if word.eq_ignore_ascii_case("AND") || word.eq_ignore_ascii_case("OR") =>
+                {
+                    Err(TagExprError(format!("expected a tag before '{}'", word)))
+                }
+                Some(Token::Word(tag)) => Ok(TagExpr::Tag(tag)),
+                Some(Token::Quoted(tag)) => match tag.trim().is_empty() {
+                    true => Err(TagExprError("empty tag".to_string())),
+                    false => Ok(TagExpr::Tag(tag.trim().to_string())),
+                },
+                Some(Token::Open) => {
+                    let inner = self.expression()?;
+                    match self.peek() {
+                        Some(Token::Close) => {
+                            self.position += 1;
+                            Ok(inner)
+                        }
+                        _ => Err(TagExprError("missing ')'".to_string())),
+                    }
+                }
+                Some(Token::Close) => Err(TagExprError("unexpected ')'".to_string())),
+                None => Err(TagExprError("expected a tag".to_string())),
+            }
+        }
+    }
+
+    pub fn parse(expression: &str) -> Result<TagExpr, TagExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(expression)?,
+            position: 0,
+        };
+        let parsed = parser.expression()?;
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(Token::Close) => Err(TagExprError("unexpected ')'".to_string())),
+            Some(_) => Err(TagExprError("expected AND or OR between tags".to_string())),
+        }
+    }
+
+    impl TagExpr {
+        // SQL condition on the manga row aliased as manga_alias (i.e. "m"), one EXISTS subquery per tag, and the
+        // tags bound to its ?1, ?2, ... placeholders, in order
+        pub fn to_sql(&self, manga_alias: &str) -> (String, Vec<Value>) {
+            let mut sql_params = Vec::new();
+            let sql = self.push_sql(manga_alias, &mut sql_params);
+            (sql, sql_params)
+        }
+
+        fn push_sql(&self, manga_alias: &str, sql_params: &mut Vec<Value>) -> String {
+            match self {
+                TagExpr::Tag(tag) => {
+                    sql_params.push(Value::Text(tag.trim().to_string()));
+                    format!(
+                        "EXISTS (SELECT 1 FROM manga_to_tags_map AS expr_mt JOIN tags AS expr_t ON expr_t.id = expr_mt.tag_id \
+                         WHERE expr_mt.manga_id = {}.id AND TRIM(expr_t.tag) = ?{} COLLATE NOCASE)",
+                        manga_alias,
+                        sql_params.len()
+                    )
+                }
+                TagExpr::Not(inner) => format!("NOT {}", inner.push_sql(manga_alias, sql_params)),
+                TagExpr::And(left, right) => {
+                    let left = left.push_sql(manga_alias, sql_params);
+                    format!("({} AND {})", left, right.push_sql(manga_alias, sql_params))
+                }
+                TagExpr::Or(left, right) => {
+                    let left = left.push_sql(manga_alias, sql_params);
+                    format!("({} OR {})", left, right.push_sql(manga_alias, sql_params))
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn tag(name: &str) -> Box<TagExpr> {
+            Box::new(TagExpr::Tag(name.to_string()))
+        }
+
+        #[test]
+        fn test_parse() {
+            assert_eq!(
+                parse("isekai AND NOT dropped OR (seinen and ongoing)").unwrap(),
+                TagExpr::Or(
+                    Box::new(TagExpr::And(
+                        tag("isekai"),
+                        Box::new(TagExpr::Not(tag("dropped")))
+                    )),
+                    Box::new(TagExpr::And(tag("seinen"), tag("ongoing"))),
+                )
+            );
+            assert_eq!(
+                parse("\"genre:Slice of Life\" OR \"and\"").unwrap(),
+                TagExpr::Or(tag("genre:Slice of Life"), tag("and"))
+            );
+            for invalid in [
+                "",
+                "isekai AND",
+                "isekai seinen",
+                "(isekai",
+                "isekai)",
+                "OR x",
+                "\"x",
+            ] {
+                assert!(parse(invalid).is_err(), "'{}' should not parse", invalid);
+            }
+
+            let (sql, sql_params) = parse("x AND NOT \"it's\"").unwrap().to_sql("m");
+            assert!(sql.contains("TRIM(expr_t.tag) = ?1 COLLATE NOCASE"));
+            assert!(
+                sql.contains("NOT EXISTS") && sql.contains("TRIM(expr_t.tag) = ?2 COLLATE NOCASE")
+            );
+            assert!(!sql.contains("it's"));
+            assert_eq!(
+                sql_params,
+                vec![
+                    Value::Text("x".to_string()),
+                    Value::Text("it's".to_string())
+                ]
+            );
+        }
+    }
+}
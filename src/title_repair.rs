@@ -0,0 +1,284 @@
+// Repairs bookmark titles which say more about the site than the series (i.e. "Read Online Free - Chapter 58")
+// by fetching the series page, taking its og:title (or <title> when there is none), and cleaning it with
+// the [[title_rules]] of that domain from the TOML config followed by the generic rules below.
+// The [[title_rules]] alone (no fetch, no generic rules) are also applied to bookmark titles as they are
+// imported, and to the rows already in the database by `manga clean-titles`.
+pub mod title_repair {
+    use regex::Regex;
+
+    use crate::config::config::{RomanizationOptions, TitleRuleConfig};
+    use crate::model_manga::model_manga::MangaModel;
+    use crate::site_migration::site_migration;
+    use crate::update_checker::update_checker;
+
+    // noise that almost every aggregator adds to its titles; anything site-specific (i.e. the site name)
+    // belongs in [[title_rules]] instead
+    const K_GENERIC_STRIP_PATTERNS: [&str; 4] = [
+        r"(?i)\bread\s+(?:\w+\s+)?online(?:\s+(?:for\s+)?free)?\b",
+        r"(?i)\b(?:manga|manhwa|manhua)\s+online\b",
+        r"(?i)\b(?:chapter|chap|ch\.?|episode|ep\.?)\s*\d+(?:[.\-]\d+)?",
+        r"(?i)\b(?:all\s+chapters|latest\s+chapters?|english\s+translated?|raw)\b",
+    ];
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TitleProposal {
+        pub manga_id: i64,
+        pub old_title: String,
+        pub new_title: String,
+    }
+
+    // og:title (either attribute order), otherwise <title>
+    pub fn page_title(html: &str) -> Option<String> {
+        let og_title_regexes = [
+            Regex::new(r#"(?is)<meta[^>]+property\s*=\s*["']og:title["'][^>]*content\s*=\s*["']([^"']*)["']"#).unwrap(),
+            Regex::new(r#"(?is)<meta[^>]+content\s*=\s*["']([^"']*)["'][^>]*property\s*=\s*["']og:title["']"#).unwrap(),
+            Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap(),
+        ];
+        og_title_regexes
+            .iter()
+            .filter_map(|regex| regex.captures(html))
+            .map(|c| update_checker::xml_text(&c[1]))
+            .find(|title| !title.is_empty())
+    }
+
+    // strip what is left dangling once the noise is removed: "Gate -  - " => "Gate"
+    fn tidy(title: &str) -> String {
+        let separators_regex = Regex::new(r"\s*[-|–—:]\s*(?:[-|–—:]\s*)+").unwrap();
+        let whitespace_regex = Regex::new(r"\s+").unwrap();
+        let title = whitespace_regex.replace_all(title, " ");
+        let title = separators_regex.replace_all(&title, " - ");
+        title
+            .trim_matches(|c: char| c.is_whitespace() || "-|–—:".contains(c))
+            .to_string()
+    }
+
+    fn strip_domain_patterns(title: &str, url: &str, rules: &[TitleRuleConfig]) -> String {
+        let mut stripped = title.to_string();
+        for rule in rules
+            .iter()
+            .filter(|rule| site_migration::is_on_domain(url, &rule.domain))
+        {
+            for pattern in rule.strip.iter() {
+                match Regex::new(pattern) {
+                    Ok(regex) => stripped = regex.replace_all(&stripped, "").to_string(),
+                    Err(e) => println!(
+                        "WARNING: title_rules: invalid strip pattern '{}' for '{}': {}",
+                        pattern, rule.domain, e
+                    ),
+                }
+            }
+        }
+        stripped
+    }
+
+    // domain rules first (they usually anchor on the site name, which generic cleaning may have mangled)
+    pub fn clean_title(title: &str, url: &str, rules: &[TitleRuleConfig]) -> String {
+        let mut cleaned = strip_domain_patterns(title, url, rules);
+        for pattern in K_GENERIC_STRIP_PATTERNS.iter() {
+            cleaned = Regex::new(pattern)
+                .unwrap()
+                .replace_all(&cleaned, "")
+                .to_string();
+        }
+        tidy(&cleaned)
+    }
+
+    // only the [[title_rules]] of the domain, so that titles of sites without rules are left exactly as they
+    // are; the title is kept when nothing would be left of it
+    pub fn strip_boilerplate(title: &str, url: &str, rules: &[TitleRuleConfig]) -> String {
+        let stripped = strip_domain_patterns(title, url, rules);
+        if stripped == title {
+            return title.to_string();
+        }
+        match tidy(&stripped) {
+            tidied if tidied.is_empty() => title.to_string(),
+            tidied => tidied,
+        }
+    }
+
+    // proposals of every manga whose title strip_boilerplate() would change
+    pub fn propose_stripped_titles(
+        mangas: &[MangaModel],
+        rules: &[TitleRuleConfig],
+    ) -> Vec<TitleProposal> {
+        mangas
+            .iter()
+            .filter_map(|manga| {
+                let new_title = strip_boilerplate(manga.title(), manga.url(), rules);
+                match new_title == manga.title() {
+                    true => None,
+                    false => Some(TitleProposal {
+                        manga_id: manga.id(),
+                        old_title: manga.title().to_string(),
+                        new_title,
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    // bookmarks about to be imported; returns how many titles were changed
+    pub fn strip_imported_titles(
+        mangas: &mut [MangaModel],
+        rules: &[TitleRuleConfig],
+        romanization: &RomanizationOptions,
+    ) -> usize {
+        let mut stripped_count = 0;
+        for manga in mangas.iter_mut() {
+            let new_title = strip_boilerplate(manga.title(), manga.url(), rules);
+            if new_title != manga.title() {
+                let proposal = TitleProposal {
+                    manga_id: manga.id(),
+                    old_title: manga.title().to_string(),
+                    new_title,
+                };
+                *manga = apply_proposal(manga, &proposal, romanization);
+                stripped_count += 1;
+            }
+        }
+        stripped_count
+    }
+
+    // a title is bad if cleaning would change it, or if nothing is left of it after cleaning
+    pub fn looks_bad_title(manga: &MangaModel, rules: &[TitleRuleConfig]) -> bool {
+        let cleaned = clean_title(manga.title(), manga.url(), rules);
+        cleaned.is_empty() || cleaned != manga.title()
+    }
+
+    // Ok(None) when the page has no usable title, or it cleans up to the title we already have
+    pub fn propose_title(
+        manga: &MangaModel,
+        rules: &[TitleRuleConfig],
+    ) -> Result<Option<TitleProposal>, Box<dyn std::error::Error>> {
+        let html = update_checker::fetch_text(manga.url())?;
+        let new_title = match page_title(&html) {
+            Some(title) => clean_title(&title, manga.url(), rules),
+            None => return Ok(None),
+        };
+        if new_title.is_empty() || new_title == manga.title() {
+            return Ok(None);
+        }
+        Ok(Some(TitleProposal {
+            manga_id: manga.id(),
+            old_title: manga.title().to_string(),
+            new_title,
+        }))
+    }
+
+    // new title is sanitized the same way imported titles are, and romanized again since it changed
+    pub fn apply_proposal(
+        manga: &MangaModel,
+        proposal: &TitleProposal,
+        romanization: &RomanizationOptions,
+    ) -> MangaModel {
+        let new_title = crate::my_libs::sanitize_string(&proposal.new_title);
+        let mut repaired = manga.clone();
+        repaired.set_title_romanized(MangaModel::romanize_title_with(&new_title, romanization));
+        repaired.set_title(new_title);
+        repaired
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_page_title() {
+            let html = r#"<html><head><title>Gate | Example Scans</title>
+                <meta content="Gate &amp; Friends" property="og:title"/></head></html>"#;
+            assert_eq!(page_title(html), Some("Gate & Friends".to_string()));
+            let html = "<html><head><title>\n  Gate | Example Scans\n</title></head></html>";
+            assert_eq!(page_title(html), Some("Gate | Example Scans".to_string()));
+            assert_eq!(page_title("<html></html>"), None);
+        }
+
+        #[test]
+        fn test_clean_title() {
+            let rules = vec![TitleRuleConfig {
+                domain: "example.com".to_string(),
+                strip: vec![r"\s*\|\s*Example Scans$".to_string()],
+            }];
+            assert_eq!(
+                clean_title(
+                    "Read Online Free - Chapter 58",
+                    "https://example.com/x/",
+                    &rules
+                ),
+                ""
+            );
+            assert_eq!(
+                clean_title(
+                    "Gate - Chapter 58 | Example Scans",
+                    "https://www.example.com/gate/",
+                    &rules
+                ),
+                "Gate"
+            );
+            // rules of other domains are not applied
+            assert_eq!(
+                clean_title("Gate | Example Scans", "https://example.org/gate/", &rules),
+                "Gate | Example Scans"
+            );
+            assert_eq!(
+                clean_title(
+                    "Gate Manga Online - Read Manga Online For Free",
+                    "https://example.org/gate/",
+                    &[]
+                ),
+                "Gate"
+            );
+        }
+
+        #[test]
+        fn test_strip_boilerplate() {
+            let rules = vec![TitleRuleConfig {
+                domain: "rawkuma.com".to_string(),
+                strip: vec![
+                    r"(?i)\s*[-–]\s*Rawkuma$".to_string(),
+                    r"(?i)^Read\s+|\s+Online\s+Free$".to_string(),
+                ],
+            }];
+            assert_eq!(
+                strip_boilerplate(
+                    "Read Gate Online Free – Rawkuma",
+                    "https://rawkuma.com/manga/gate/",
+                    &rules
+                ),
+                "Gate"
+            );
+            // no rule for the domain, not even the generic cleaning
+            assert_eq!(
+                strip_boilerplate(
+                    "Gate - Chapter 58 – Rawkuma",
+                    "https://example.org/gate/",
+                    &rules
+                ),
+                "Gate - Chapter 58 – Rawkuma"
+            );
+            // nothing left, title kept
+            assert_eq!(
+                strip_boilerplate(" – Rawkuma", "https://rawkuma.com/", &rules),
+                " – Rawkuma"
+            );
+
+            let mut mangas = vec![
+                MangaModel::new_from_required_elements(
+                    "Gate – Rawkuma",
+                    "https://rawkuma.com/manga/gate/",
+                    1,
+                )
+                .unwrap(),
+                MangaModel::new_from_required_elements("Akira", "https://rawkuma.com/akira/", 2)
+                    .unwrap(),
+            ];
+            let proposals = propose_stripped_titles(&mangas, &rules);
+            assert_eq!(proposals.len(), 1);
+            assert_eq!(proposals[0].new_title, "Gate");
+            assert_eq!(
+                strip_imported_titles(&mut mangas, &rules, &RomanizationOptions::default()),
+                1
+            );
+            assert_eq!(mangas[0].title(), "Gate");
+        }
+    }
+}
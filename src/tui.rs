@@ -0,0 +1,697 @@
+// Terminal UI over the collection: `manga tui`
+// A scrollable table of every manga, narrowed down as one types a search, with the selected entry's details
+// (url, tags, notes, enrichment and cached cover) below it.  All edits are written straight to the database:
+//      Up/Down/PgUp/PgDn/Home/End   move                 /   search (title, tags, notes, url)
+//      c   set the chapter read (logged like `mark-read`)  n   edit notes     t   edit tags (comma separated)
+//      o   open in the browser      D   dedupe review    r   reload         q   quit
+// The dedupe review walks through the groups found by model_sqlite3_manga::duplicate_groups(); "k" keeps the
+// selected row of the group (taking over the tags of the others) and deletes the rest, "s" skips the group.
+pub mod tui {
+    use ratatui::{
+        crossterm::event::{self, Event, KeyCode, KeyEventKind},
+        layout::{Constraint, Layout},
+        style::{Modifier, Style},
+        text::Line,
+        widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
+        DefaultTerminal, Frame,
+    };
+
+    use crate::cover_cache::cover_cache;
+    use crate::daemon_lock::daemon_lock;
+    use crate::model_manga::model_manga::{MangaModel, MangaUpdateRequest};
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+    use crate::model_sqlite3_manga_metadata::model_sqlite3_manga_metadata::{self, MangaMetadata};
+    use crate::service::service::MangaService;
+    use crate::subcommands::subcommands;
+
+    const K_PAGE_ROWS: isize = 10;
+
+    pub struct TuiOptions {
+        pub db_full_paths: String,
+        pub cover_cache_dir: String,
+        pub force: bool, // dedupe even while the daemon runs
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum EditField {
+        Chapter,
+        Notes,
+        Tags,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Mode {
+        Browse,
+        Search,
+        Edit(EditField),
+        Dedupe,
+    }
+
+    struct App {
+        service: MangaService,
+        cover_cache_dir: String,
+        mangas: Vec<MangaModel>,
+        search: String,
+        visible: Vec<usize>, // indices into mangas which match the search
+        table_state: TableState,
+        mode: Mode,
+        input: String, // what is being typed in Edit mode
+        duplicate_groups: Vec<Vec<MangaModel>>,
+        force_dedupe: bool,
+        group_index: usize,
+        group_state: TableState,
+        // metadata of the selected manga, only re-read when the selection changes
+        selected_metadata: Option<(i64, Option<MangaMetadata>)>,
+        status: String,
+        // the database layer prints progress to stdout, which scribbles over the screen
+        needs_full_redraw: bool,
+        quit: bool,
+    }
+
+    fn contains_ignore_case(possible_text: Option<&str>, lowercase_search: &str) -> bool {
+        possible_text.map_or(false, |text| text.to_lowercase().contains(lowercase_search))
+    }
+
+    fn trimmed_tags(manga: &MangaModel) -> Vec<String> {
+        manga
+            .tags()
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    impl App {
+        fn load(
+            service: MangaService,
+            cover_cache_dir: &str,
+        ) -> Result<App, Box<dyn std::error::Error>> {
+            let mut app = App {
+                service,
+                cover_cache_dir: cover_cache_dir.to_string(),
+                mangas: Vec::new(),
+                search: String::new(),
+                visible: Vec::new(),
+                table_state: TableState::default(),
+                mode: Mode::Browse,
+                input: String::new(),
+                duplicate_groups: Vec::new(),
+                force_dedupe: false,
+                group_index: 0,
+                group_state: TableState::default(),
+                selected_metadata: None,
+                status: String::new(),
+                needs_full_redraw: false,
+                quit: false,
+            };
+            app.reload()?;
+            app.status = format!("{} manga loaded, '?' lists the keys", app.mangas.len());
+            Ok(app)
+        }
+
+        // re-reads everything, keeping the same manga selected when it is still there
+        fn reload(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            let possible_selected_id = self.selected_manga().map(|manga| manga.id());
+            self.mangas = self.service.list()?;
+            self.mangas
+                .sort_by_key(|manga| manga.title().to_lowercase());
+            self.apply_search();
+            if let Some(selected_id) = possible_selected_id {
+                if let Some(row) = self
+                    .visible
+                    .iter()
+                    .position(|&i| self.mangas[i].id() == selected_id)
+                {
+                    self.table_state.select(Some(row));
+                }
+            }
+            self.selected_metadata = None;
+            Ok(())
+        }
+
+        fn apply_search(&mut self) {
+            let search = self.search.trim().to_lowercase();
+            self.visible = self
+                .mangas
+                .iter()
+                .enumerate()
+                .filter(|(_, manga)| {
+                    search.is_empty()
+                        || contains_ignore_case(Some(manga.title()), &search)
+                        || contains_ignore_case(manga.title_romanized().as_deref(), &search)
+                        || contains_ignore_case(Some(manga.url()), &search)
+                        || contains_ignore_case(manga.notes().as_deref(), &search)
+                        || trimmed_tags(manga)
+                            .iter()
+                            .any(|tag| tag.to_lowercase().contains(&search))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            let selected = match self.visible.is_empty() {
+                true => None,
+                false => Some(
+                    self.table_state
+                        .selected()
+                        .unwrap_or(0)
+                        .min(self.visible.len() - 1),
+                ),
+            };
+            self.table_state.select(selected);
+        }
+
+        fn selected_manga(&self) -> Option<&MangaModel> {
+            self.table_state
+                .selected()
+                .and_then(|row| self.visible.get(row))
+                .map(|&i| &self.mangas[i])
+        }
+
+        fn selected_duplicate(&self) -> Option<&MangaModel> {
+            self.duplicate_groups
+                .get(self.group_index)
+                .and_then(|group| group.get(self.group_state.selected().unwrap_or(0)))
+        }
+
+        fn move_selection(state: &mut TableState, row_count: usize, delta: isize) {
+            if row_count == 0 {
+                state.select(None);
+                return;
+            }
+            let current = state.selected().unwrap_or(0) as isize;
+            let next = (current + delta).clamp(0, row_count as isize - 1);
+            state.select(Some(next as usize));
+        }
+
+        fn refresh_selected_metadata(&mut self) {
+            let possible_id = self.selected_manga().map(|manga| manga.id());
+            match (possible_id, &self.selected_metadata) {
+                (Some(id), Some((metadata_id, _))) if id == *metadata_id => {}
+                (Some(id), _) => {
+                    // not being able to read it only means less details are shown
+                    let possible_metadata = model_sqlite3_manga_metadata::select_manga_metadata(
+                        self.service.db_full_paths(),
+                        id,
+                    )
+                    .unwrap_or(None);
+                    self.selected_metadata = Some((id, possible_metadata));
+                }
+                (None, _) => self.selected_metadata = None,
+            }
+        }
+
+        fn handle_key(&mut self, key: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+            match self.mode {
+                Mode::Browse => self.handle_browse_key(key),
+                Mode::Search => {
+                    match key {
+                        KeyCode::Enter => self.mode = Mode::Browse,
+                        KeyCode::Esc => {
+                            self.search.clear();
+                            self.mode = Mode::Browse;
+                        }
+                        KeyCode::Backspace => {
+                            self.search.pop();
+                        }
+                        KeyCode::Char(c) => self.search.push(c),
+                        _ => {}
+                    }
+                    self.apply_search();
+                    Ok(())
+                }
+                Mode::Edit(field) => match key {
+                    KeyCode::Enter => {
+                        self.mode = Mode::Browse;
+                        let input = std::mem::take(&mut self.input);
+                        self.save_edit(field, &input)
+                    }
+                    KeyCode::Esc => {
+                        self.input.clear();
+                        self.mode = Mode::Browse;
+                        self.status = "edit cancelled".to_string();
+                        Ok(())
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                        Ok(())
+                    }
+                    KeyCode::Char(c) => {
+                        self.input.push(c);
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                },
+                Mode::Dedupe => self.handle_dedupe_key(key),
+            }
+        }
+
+        fn handle_browse_key(&mut self, key: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+            let row_count = self.visible.len();
+            match key {
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Up | KeyCode::Char('k') => {
+                    Self::move_selection(&mut self.table_state, row_count, -1)
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    Self::move_selection(&mut self.table_state, row_count, 1)
+                }
+                KeyCode::PageUp => {
+                    Self::move_selection(&mut self.table_state, row_count, -K_PAGE_ROWS)
+                }
+                KeyCode::PageDown => {
+                    Self::move_selection(&mut self.table_state, row_count, K_PAGE_ROWS)
+                }
+                KeyCode::Home => {
+                    Self::move_selection(&mut self.table_state, row_count, isize::MIN / 2)
+                }
+                KeyCode::End => {
+                    Self::move_selection(&mut self.table_state, row_count, isize::MAX / 2)
+                }
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.apply_search();
+                }
+                KeyCode::Char('c') | KeyCode::Char('n') | KeyCode::Char('t') => {
+                    let manga = match self.selected_manga() {
+                        Some(manga) => manga,
+                        None => return Ok(()),
+                    };
+                    let (field, current) = match key {
+                        KeyCode::Char('c') => (EditField::Chapter, manga.chapter()),
+                        KeyCode::Char('n') => (EditField::Notes, manga.notes()),
+                        _ => (EditField::Tags, Some(trimmed_tags(manga).join(", "))),
+                    };
+                    self.input = current.unwrap_or_default();
+                    self.mode = Mode::Edit(field);
+                }
+                KeyCode::Char('o') => {
+                    if let Some(manga) = self.selected_manga() {
+                        let url = manga
+                            .url_with_chapter()
+                            .unwrap_or_else(|| manga.url().to_string());
+                        subcommands::open_in_browser(&url)?;
+                        self.status = format!("opened {}", url);
+                        self.needs_full_redraw = true;
+                    }
+                }
+                KeyCode::Char('D') => {
+                    daemon_lock::refuse_while_active(
+                        self.service.db_full_paths(),
+                        "dedupe",
+                        self.force_dedupe,
+                    )?;
+                    self.duplicate_groups = model_sqlite3_manga::duplicate_groups(&self.mangas);
+                    self.group_index = 0;
+                    self.group_state.select(Some(0));
+                    match self.duplicate_groups.is_empty() {
+                        true => self.status = "no duplicates found".to_string(),
+                        false => self.mode = Mode::Dedupe,
+                    }
+                }
+                KeyCode::Char('r') => {
+                    self.reload()?;
+                    self.status = format!("{} manga reloaded", self.mangas.len());
+                }
+                KeyCode::Char('?') => {
+                    self.status =
+                        "/ search, c chapter, n notes, t tags, o open, D dedupe, r reload, q quit"
+                            .to_string()
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+
+        fn save_edit(
+            &mut self,
+            field: EditField,
+            input: &str,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            let manga = match self.selected_manga() {
+                Some(manga) => manga.clone(),
+                None => return Ok(()),
+            };
+            let input = input.trim();
+            self.needs_full_redraw = true;
+            let update = |notes: Option<String>, tags: Option<String>| MangaUpdateRequest {
+                title: None,
+                title_romanized: None,
+                url: None,
+                url_with_chapter: None,
+                chapter: None,
+                last_update: None,
+                notes,
+                tags,
+            };
+            match field {
+                EditField::Chapter => {
+                    // "12-1" (as it appears in urls) is stored as "12.1", same as `mark-read`
+                    let manga_read = self.service.mark_read(manga.id(), Some(input))?;
+                    self.status = format!(
+                        "id={} chapter {}",
+                        manga.id(),
+                        manga_read.chapter().unwrap_or_default()
+                    );
+                }
+                EditField::Notes => {
+                    self.service
+                        .update(manga.id(), update(Some(input.to_string()), None))?;
+                    self.status = format!("id={} notes saved", manga.id());
+                }
+                EditField::Tags => {
+                    let updated = self
+                        .service
+                        .update(manga.id(), update(None, Some(input.to_string())))?;
+                    self.status = format!("id={} tags: {}", manga.id(), updated.tags().join(", "));
+                }
+            }
+            self.reload()
+        }
+
+        fn handle_dedupe_key(&mut self, key: KeyCode) -> Result<(), Box<dyn std::error::Error>> {
+            let group_len = self
+                .duplicate_groups
+                .get(self.group_index)
+                .map_or(0, |group| group.len());
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Browse,
+                // 'k' keeps here, so no vi-style up
+                KeyCode::Up => Self::move_selection(&mut self.group_state, group_len, -1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    Self::move_selection(&mut self.group_state, group_len, 1)
+                }
+                KeyCode::Left | KeyCode::Char('p') => {
+                    self.group_index = self.group_index.saturating_sub(1);
+                    self.group_state.select(Some(0));
+                }
+                KeyCode::Right | KeyCode::Char('s') => {
+                    if self.group_index + 1 < self.duplicate_groups.len() {
+                        self.group_index += 1;
+                        self.group_state.select(Some(0));
+                    } else {
+                        self.status = "that was the last group".to_string();
+                    }
+                }
+                KeyCode::Char('o') => {
+                    if let Some(manga) = self.selected_duplicate() {
+                        let url = manga.url().to_string();
+                        subcommands::open_in_browser(&url)?;
+                        self.needs_full_redraw = true;
+                    }
+                }
+                KeyCode::Char('k') => self.keep_selected_duplicate()?,
+                _ => {}
+            }
+            Ok(())
+        }
+
+        // see MangaService::merge_duplicates()
+        fn keep_selected_duplicate(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+            let keeper = match self.selected_duplicate() {
+                Some(manga) => manga.clone(),
+                None => return Ok(()),
+            };
+            let others: Vec<MangaModel> = self.duplicate_groups[self.group_index]
+                .iter()
+                .filter(|manga| manga.id() != keeper.id())
+                .cloned()
+                .collect();
+            self.needs_full_redraw = true;
+            self.service.merge_duplicates(keeper.id(), &others)?;
+            self.status = format!(
+                "kept id={}, deleted id={}",
+                keeper.id(),
+                others
+                    .iter()
+                    .map(|manga| manga.id().to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            );
+
+            self.reload()?;
+            self.duplicate_groups = model_sqlite3_manga::duplicate_groups(&self.mangas);
+            self.group_index = self
+                .group_index
+                .min(self.duplicate_groups.len().saturating_sub(1));
+            self.group_state.select(Some(0));
+            if self.duplicate_groups.is_empty() {
+                self.mode = Mode::Browse;
+            }
+            Ok(())
+        }
+    }
+
+    fn manga_row(manga: &MangaModel) -> Row<'static> {
+        Row::new(vec![
+            manga.id().to_string(),
+            manga.title().to_string(),
+            manga.chapter().unwrap_or_default(),
+            match (manga.latest_available_chapter(), manga.unread_count()) {
+                (Some(latest), Some(unread)) if unread > 0 => format!("{} (+{})", latest, unread),
+                (Some(latest), _) => latest,
+                (None, _) => String::new(),
+            },
+            trimmed_tags(manga).join(", "),
+        ])
+    }
+
+    const K_COLUMN_WIDTHS: [Constraint; 5] = [
+        Constraint::Length(6),
+        Constraint::Fill(3),
+        Constraint::Length(9),
+        Constraint::Length(14),
+        Constraint::Fill(1),
+    ];
+
+    fn manga_table<'a>(rows: Vec<Row<'a>>, title: String) -> Table<'a> {
+        Table::new(rows, K_COLUMN_WIDTHS)
+            .header(
+                Row::new(vec!["id", "title", "chapter", "latest", "tags"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    }
+
+    fn details_lines(app: &App, manga: &MangaModel) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(format!(
+                "{}{}",
+                manga.title(),
+                manga
+                    .title_romanized()
+                    .map(|romanized| format!(" ({})", romanized))
+                    .unwrap_or_default()
+            )),
+            Line::from(
+                manga
+                    .url_with_chapter()
+                    .unwrap_or_else(|| manga.url().to_string()),
+            ),
+            Line::from(format!(
+                "last read: {}",
+                manga.last_update().unwrap_or_default()
+            )),
+        ];
+        if let Some(notes) = manga.notes() {
+            lines.push(Line::from(format!("notes: {}", notes)));
+        }
+        if let Some((_, Some(metadata))) = &app.selected_metadata {
+            if !metadata.authors.is_empty() {
+                lines.push(Line::from(format!(
+                    "authors: {}",
+                    metadata.authors.join(", ")
+                )));
+            }
+            if let Some(status) = &metadata.publication_status {
+                lines.push(Line::from(format!(
+                    "status: {} ({})",
+                    status, metadata.source
+                )));
+            }
+            if let Some(cover_path) = metadata.cover_url.as_deref().and_then(|cover_url| {
+                cover_cache::cached_cover_path(&app.cover_cache_dir, cover_url)
+            }) {
+                lines.push(Line::from(format!("cover: {}", cover_path.display())));
+            }
+            if let Some(synopsis) = &metadata.synopsis {
+                lines.push(Line::from(synopsis.clone()));
+            }
+        }
+        lines
+    }
+
+    fn footer_text(app: &App) -> String {
+        match app.mode {
+            Mode::Search => format!("search: {}_", app.search),
+            Mode::Edit(field) => format!(
+                "{}: {}_   (Enter saves, Esc cancels)",
+                match field {
+                    EditField::Chapter => "chapter",
+                    EditField::Notes => "notes",
+                    EditField::Tags => "tags",
+                },
+                app.input
+            ),
+            Mode::Dedupe => format!(
+                "k keep selected (delete the others), s/Right skip, p/Left previous, o open, Esc back   {}",
+                app.status
+            ),
+            Mode::Browse => app.status.clone(),
+        }
+    }
+
+    fn draw(frame: &mut Frame, app: &mut App) {
+        let [table_area, details_area, footer_area] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        if app.mode == Mode::Dedupe {
+            let group = app.duplicate_groups[app.group_index].clone();
+            let title = format!(
+                " duplicates {}/{} ",
+                app.group_index + 1,
+                app.duplicate_groups.len()
+            );
+            let table = manga_table(group.iter().map(manga_row).collect(), title);
+            frame.render_stateful_widget(table, table_area, &mut app.group_state);
+            let details = match app.selected_duplicate() {
+                Some(manga) => vec![
+                    Line::from(manga.url().to_string()),
+                    Line::from(format!("notes: {}", manga.notes().unwrap_or_default())),
+                ],
+                None => Vec::new(),
+            };
+            frame.render_widget(
+                Paragraph::new(details).block(Block::default().borders(Borders::ALL)),
+                details_area,
+            );
+        } else {
+            let title = match app.search.is_empty() {
+                true => format!(" manga ({}) ", app.visible.len()),
+                false => format!(
+                    " manga ({}/{}) matching '{}' ",
+                    app.visible.len(),
+                    app.mangas.len(),
+                    app.search
+                ),
+            };
+            let rows: Vec<Row> = app
+                .visible
+                .iter()
+                .map(|&i| manga_row(&app.mangas[i]))
+                .collect();
+            frame.render_stateful_widget(
+                manga_table(rows, title),
+                table_area,
+                &mut app.table_state,
+            );
+            let details = match app.selected_manga() {
+                Some(manga) => details_lines(app, manga),
+                None => Vec::new(),
+            };
+            frame.render_widget(
+                Paragraph::new(details)
+                    .wrap(Wrap { trim: true })
+                    .block(Block::default().borders(Borders::ALL)),
+                details_area,
+            );
+        }
+        frame.render_widget(Paragraph::new(footer_text(app)), footer_area);
+    }
+
+    fn event_loop(
+        terminal: &mut DefaultTerminal,
+        app: &mut App,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        while !app.quit {
+            app.refresh_selected_metadata();
+            if app.needs_full_redraw {
+                terminal.clear()?;
+                app.needs_full_redraw = false;
+            }
+            terminal.draw(|frame| draw(frame, app))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                // a failed edit or delete is reported, not fatal
+                if let Err(e) = app.handle_key(key.code) {
+                    app.status = format!("error: {}", e);
+                    app.needs_full_redraw = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // blocks until 'q'
+    pub fn run(options: TuiOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let service = MangaService::open(&options.db_full_paths)?;
+        let mut app = App::load(service, &options.cover_cache_dir)?;
+        app.force_dedupe = options.force;
+        let mut terminal = ratatui::init();
+        let result = event_loop(&mut terminal, &mut app);
+        ratatui::restore();
+        result
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn type_keys(app: &mut App, text: &str) {
+            for c in text.chars() {
+                app.handle_key(KeyCode::Char(c)).unwrap();
+            }
+        }
+
+        #[test]
+        fn test_search_edit_and_dedupe() {
+            let db_full_paths = "samples/test_tui.db";
+            std::fs::remove_file(db_full_paths).unwrap_or_default();
+            let service = MangaService::open(db_full_paths).unwrap();
+            for (title, url) in [
+                ("Gate", "https://example.com/manga/gate/"),
+                ("Akira", "https://example.com/manga/akira/"),
+                ("AKIRA!", "https://other.com/akira/"),
+            ] {
+                let manga = MangaModel::new_from_required_elements(title, url, 1).unwrap();
+                model_sqlite3_manga::insert_manga(db_full_paths, &manga).unwrap();
+            }
+            let mut app = App::load(service, "samples/no_such_covers").unwrap();
+            assert_eq!(app.visible.len(), 3);
+
+            app.handle_key(KeyCode::Char('/')).unwrap();
+            type_keys(&mut app, "gat");
+            app.handle_key(KeyCode::Enter).unwrap();
+            assert_eq!(app.visible.len(), 1);
+            assert_eq!(app.selected_manga().unwrap().title(), "Gate");
+
+            app.handle_key(KeyCode::Char('t')).unwrap();
+            type_keys(&mut app, "seinen, isekai");
+            app.handle_key(KeyCode::Enter).unwrap();
+            app.handle_key(KeyCode::Char('c')).unwrap();
+            type_keys(&mut app, "12-1");
+            app.handle_key(KeyCode::Enter).unwrap();
+            let gate = app.selected_manga().unwrap();
+            assert_eq!(trimmed_tags(gate), vec!["seinen", "isekai"]);
+            assert_eq!(gate.chapter(), Some("12.1".to_string()));
+
+            app.handle_key(KeyCode::Char('c')).unwrap();
+            app.input = "not a chapter".to_string();
+            assert!(app.handle_key(KeyCode::Enter).is_err());
+
+            app.handle_key(KeyCode::Esc).unwrap();
+            app.handle_key(KeyCode::Char('D')).unwrap();
+            assert_eq!(app.mode, Mode::Dedupe);
+            assert_eq!(app.duplicate_groups.len(), 1);
+            app.handle_key(KeyCode::Char('k')).unwrap();
+            assert_eq!(app.mode, Mode::Browse);
+            assert_eq!(app.mangas.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,557 @@
+// Detects chapters newer than what was last read.  Many aggregators publish a per-series RSS (or Atom)
+// feed, which is cheap to fetch and far more reliable than guessing from HTML, so when a manga has a
+// feed_url we'll parse that first, and only fall back to scraping the series home page (url) when
+// there is no feed, the feed failed to download, or the feed had nothing that looked like a chapter.
+// Series on MangaDex (see mangadex) are asked to the MangaDex API before either of those.
+pub mod update_checker {
+    use regex::Regex;
+    use std::{io::Read, sync::OnceLock, time::Duration};
+    use url::Url;
+
+    use crate::mangadex::mangadex;
+    use crate::model_manga::model_manga::{Chapter, MangaModel};
+    use crate::model_sqlite3_manga::model_sqlite3_manga;
+
+    // some sites refuse anything that does not look like a browser
+    const K_USER_AGENT: &str =
+        "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/118.0";
+    const K_TIMEOUT_SECONDS: u64 = 30;
+    const K_MAX_REDIRECTS: usize = 5;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum UpdateSource {
+        MangaDex, // asked the MangaDex API
+        Feed,     // parsed from feed_url
+        Html,     // scraped from url
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ChapterUpdate {
+        pub manga_id: i64,
+        pub title: String,
+        pub chapter: String, // normalized, i.e. "12-1" => "12.1" (same as MangaModel::url_and_chapter())
+        pub url: String,     // link to the chapter itself
+        pub source: UpdateSource,
+    }
+
+    fn agent() -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(K_TIMEOUT_SECONDS))
+            .user_agent(K_USER_AGENT)
+            .build()
+    }
+
+    pub fn fetch_text(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let body = agent().get(url).call()?.into_string()?;
+        Ok(body)
+    }
+
+    // for binaries (i.e. cover images); anything larger than max_bytes is an error rather than truncated
+    pub fn fetch_bytes(url: &str, max_bytes: u64) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut body = Vec::new();
+        agent()
+            .get(url)
+            .call()?
+            .into_reader()
+            .take(max_bytes + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > max_bytes {
+            return Err(format!("'{}' is larger than {} bytes", url, max_bytes).into());
+        }
+        Ok(body)
+    }
+
+    // i.e. GraphQL APIs, which take the query as a JSON body
+    pub fn post_json(
+        url: &str,
+        body: &serde_json::Value,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let response = agent()
+            .post(url)
+            .set("Accept", "application/json")
+            .send_json(body.clone())?
+            .into_string()?;
+        Ok(response)
+    }
+
+    // 301 and 308 mean "update your bookmark", while 302/303/307 mean "just this once"
+    pub fn is_permanent_redirect(status: u16) -> bool {
+        status == 301 || status == 308
+    }
+
+    // follows redirects by hand (rather than letting ureq do it) so that we can tell whether the url moved
+    // for good; returns the final response and, only if EVERY hop was permanent, the url it has moved to.  The
+    // ureq::Error is boxed, it is much larger than the response
+    fn call_following_redirects(
+        method: &str,
+        url: &str,
+    ) -> Result<(ureq::Response, Option<String>), Box<ureq::Error>> {
+        let no_redirect_agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(K_TIMEOUT_SECONDS))
+            .user_agent(K_USER_AGENT)
+            .redirects(0)
+            .build();
+        let mut current_url = url.to_string();
+        let mut all_permanent = true;
+        let mut response = no_redirect_agent.request(method, &current_url).call()?;
+        for _ in 0..K_MAX_REDIRECTS {
+            if !(300..400).contains(&response.status()) {
+                break;
+            }
+            let possible_next_url = response
+                .header("location")
+                .and_then(|location| Url::parse(&current_url).ok()?.join(location).ok());
+            let next_url = match possible_next_url {
+                Some(next_url) => next_url.to_string(),
+                None => break, // a redirect without a (valid) Location, nothing to follow
+            };
+            all_permanent = all_permanent && is_permanent_redirect(response.status());
+            current_url = next_url;
+            response = no_redirect_agent.request(method, &current_url).call()?;
+        }
+        let possible_moved_to =
+            match all_permanent && current_url != url && (200..300).contains(&response.status()) {
+                true => Some(current_url),
+                false => None,
+            };
+        Ok((response, possible_moved_to))
+    }
+
+    // same as fetch_text(), plus the new url if the page has permanently moved
+    pub fn fetch_text_and_moved_to(
+        url: &str,
+    ) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+        let (response, possible_moved_to) = call_following_redirects("GET", url)?;
+        Ok((response.into_string()?, possible_moved_to))
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct LinkProbe {
+        pub status: u16, // final status, after redirects
+        pub response_millis: i64,
+        pub possible_moved_to: Option<String>, // see call_following_redirects()
+    }
+
+    // like url_exists(), but keeps the details; 4xx/5xx are Ok() with that status, Err() only when unreachable
+    pub fn probe_url(url: &str) -> Result<LinkProbe, Box<dyn std::error::Error>> {
+        let started = std::time::Instant::now();
+        let result = match call_following_redirects("HEAD", url) {
+            Err(e) if matches!(*e, ureq::Error::Status(405, _)) => {
+                call_following_redirects("GET", url)
+            }
+            result => result,
+        };
+        let response_millis = started.elapsed().as_millis() as i64;
+        match result {
+            Ok((response, possible_moved_to)) => Ok(LinkProbe {
+                status: response.status(),
+                response_millis,
+                possible_moved_to,
+            }),
+            Err(e) => match *e {
+                ureq::Error::Status(status, _) => Ok(LinkProbe {
+                    status,
+                    response_millis,
+                    possible_moved_to: None,
+                }),
+                e => Err(e.into()),
+            },
+        }
+    }
+
+    // HEAD request (redirects are followed); Ok(false) when the site says 4xx/5xx, Err() when the site
+    // could not be reached at all.  Some sites do not implement HEAD (405), so we'll retry those with GET
+    pub fn url_exists(url: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        match agent().head(url).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::Status(405, _)) => match agent().get(url).call() {
+                Ok(_) => Ok(true),
+                Err(ureq::Error::Status(_, _)) => Ok(false),
+                Err(e) => Err(e.into()),
+            },
+            Err(ureq::Error::Status(_, _)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // locate chapter number from either a title ("Gate - Chapter 12.1", "第12話") or an url ("gate-chapter-12-1/")
+    // the keyword must not end a longer word ("watch-12", "sleep-2"); \b would do, if not for slugs such as
+    // "gate_ch_12" where "_" counts as a word character
+    pub fn chapter_from_text(text: &str) -> Option<String> {
+        static CHAPTER_REGEXES: OnceLock<(Regex, Regex)> = OnceLock::new();
+        let (chapter_regex, japanese_chapter_regex) = CHAPTER_REGEXES.get_or_init(|| {
+            (
+                Regex::new(
+                    r"(?i)(?:^|[^a-z])(?:chapter|chap|ch|episode|ep)\.?[\s_\-]*(\d+(?:[.\-]\d+)?)",
+                )
+                .unwrap(),
+                Regex::new(r"第\s*(\d+(?:\.\d+)?)\s*[話话]").unwrap(),
+            )
+        });
+        let captures = chapter_regex
+            .captures(text)
+            .or_else(|| japanese_chapter_regex.captures(text))?;
+        Some(captures.get(1)?.as_str().replace("-", "."))
+    }
+
+    // compared via model_manga::Chapter (so "12.10" is newer than "12.9"); unparsable chapters are treated as chapter 0
+    pub fn is_newer_chapter(candidate: &str, possible_current: Option<&str>) -> bool {
+        let to_chapter =
+            |chapter: &str| Chapter::parse(chapter).unwrap_or(Chapter::parse("0").unwrap());
+        match possible_current {
+            Some(current) => to_chapter(candidate) > to_chapter(current),
+            None => true,
+        }
+    }
+
+    // fold() helper over (chapter, url) pairs; on ties, the first one found wins
+    pub fn keep_newest(
+        possible_latest: Option<(String, String)>,
+        found: (String, String),
+    ) -> Option<(String, String)> {
+        match &possible_latest {
+            Some((latest_chapter, _)) if !is_newer_chapter(&found.0, Some(latest_chapter)) => {
+                possible_latest
+            }
+            _ => Some(found),
+        }
+    }
+
+    // strips CDATA wrapper and unescapes the common XML entities
+    pub fn xml_text(raw: &str) -> String {
+        raw.trim()
+            .trim_start_matches("<![CDATA[")
+            .trim_end_matches("]]>")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&")
+            .trim()
+            .to_string()
+    }
+
+    // works for both RSS 2.0 (<item><title/><link>url</link></item>) and Atom (<entry><title/><link href="url"/></entry>)
+    // returns (chapter, chapter_url) of the highest chapter found in the feed
+    pub fn latest_chapter_from_feed(feed_xml: &str) -> Option<(String, String)> {
+        static FEED_REGEXES: OnceLock<(Regex, Regex, Regex, Regex)> = OnceLock::new();
+        let (item_regex, title_regex, rss_link_regex, atom_link_regex) =
+            FEED_REGEXES.get_or_init(|| {
+                (
+                    Regex::new(r"(?s)<(?:item|entry)\b.*?</(?:item|entry)>").unwrap(),
+                    Regex::new(r"(?s)<title[^>]*>(.*?)</title>").unwrap(),
+                    Regex::new(r"(?s)<link[^>]*>(.*?)</link>").unwrap(),
+                    Regex::new(r#"<link[^>]*href\s*=\s*["']([^"']+)["']"#).unwrap(),
+                )
+            });
+
+        item_regex
+            .find_iter(feed_xml)
+            .filter_map(|item| {
+                let item = item.as_str();
+                let possible_title = title_regex.captures(item).map(|c| xml_text(&c[1]));
+                let link = rss_link_regex
+                    .captures(item)
+                    .or_else(|| atom_link_regex.captures(item))
+                    .map(|c| xml_text(&c[1]))?;
+                let chapter = possible_title
+                    .as_deref()
+                    .and_then(chapter_from_text)
+                    .or_else(|| chapter_from_text(&link))?;
+                Some((chapter, link))
+            })
+            .fold(None, keep_newest)
+    }
+
+    // fallback: look for anchors on the series page which point to chapters of THIS series
+    // (i.e. series "https://example.com/gate/" links to "https://example.com/gate-chapter-12/")
+    pub fn latest_chapter_from_html(html: &str, series_url: &str) -> Option<(String, String)> {
+        static HREF_REGEX: OnceLock<Regex> = OnceLock::new();
+        let href_regex =
+            HREF_REGEX.get_or_init(|| Regex::new(r#"href\s*=\s*["']([^"'#]+)["']"#).unwrap());
+        let base_url = Url::parse(series_url).ok()?;
+        // last non-empty path segment is the series slug, i.e. "gate"
+        let slug = base_url
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .unwrap_or("")
+            .to_string();
+
+        href_regex
+            .captures_iter(html)
+            .filter_map(|c| base_url.join(&xml_text(&c[1])).ok())
+            .map(|url| url.to_string())
+            .filter(|url| slug.is_empty() || url.contains(&slug))
+            .filter_map(|url| chapter_from_text(&url).map(|chapter| (chapter, url)))
+            .fold(None, keep_newest)
+    }
+
+    // returns the newest chapter available on the site (regardless of whether it was read or not), and the
+    // new url of the series page if it answered with a permanent redirect (only known when the page was scraped)
+    pub fn check_manga(
+        manga: &MangaModel,
+        mangadex_language: &str,
+    ) -> Result<(Option<ChapterUpdate>, Option<String>), Box<dyn std::error::Error>> {
+        let to_update = |(chapter, url): (String, String), source: UpdateSource| ChapterUpdate {
+            manga_id: manga.id(),
+            title: manga.title().to_string(),
+            chapter,
+            url,
+            source,
+        };
+
+        if let Some(mangadex_id) = mangadex::mangadex_id(manga) {
+            match mangadex::latest_chapter(&mangadex_id, mangadex_language) {
+                Ok(Some(found)) => {
+                    return Ok((Some(to_update(found, UpdateSource::MangaDex)), None))
+                }
+                Ok(None) => println!(
+                    "WARNING: check_manga(id={}): no '{}' chapters on MangaDex for '{}'",
+                    manga.id(),
+                    mangadex_language,
+                    mangadex_id
+                ),
+                Err(e) => println!(
+                    "WARNING: check_manga(id={}): MangaDex lookup of '{}' failed: {}",
+                    manga.id(),
+                    mangadex_id,
+                    e
+                ),
+            }
+        }
+
+        // a MangaDex feed_url is only there to carry the MangaDex id, it is not RSS
+        let possible_feed_url = manga
+            .feed_url()
+            .filter(|feed_url| mangadex::mangadex_id_from_url(feed_url).is_none());
+        if let Some(feed_url) = possible_feed_url {
+            match fetch_text(&feed_url) {
+                Ok(feed_xml) => match latest_chapter_from_feed(&feed_xml) {
+                    Some(found) => return Ok((Some(to_update(found, UpdateSource::Feed)), None)),
+                    None => println!(
+                        "WARNING: check_manga(id={}): no chapters found in feed '{}', falling back to '{}'",
+                        manga.id(),
+                        feed_url,
+                        manga.url()
+                    ),
+                },
+                Err(e) => println!(
+                    "WARNING: check_manga(id={}): failed fetching feed '{}': {}, falling back to '{}'",
+                    manga.id(),
+                    feed_url,
+                    e,
+                    manga.url()
+                ),
+            }
+        }
+
+        let (html, possible_moved_to) = fetch_text_and_moved_to(manga.url())?;
+        // relative links on the page are relative to where it lives now
+        let series_url = possible_moved_to.as_deref().unwrap_or(manga.url());
+        let possible_update = latest_chapter_from_html(&html, series_url)
+            .map(|found| to_update(found, UpdateSource::Html));
+        Ok((possible_update, possible_moved_to))
+    }
+
+    // records the new url (old one is kept in url_history), reporting rather than failing, since a
+    // UNIQUE(title, url) clash with another row should not stop the update check
+    pub fn heal_moved_url(db_full_paths: &str, manga: &MangaModel, moved_to: &str, reason: &str) {
+        match model_sqlite3_manga::update_url(db_full_paths, manga.id(), moved_to, reason) {
+            Ok(true) => println!(
+                "id={}: '{}' has moved to '{}' ({})",
+                manga.id(),
+                manga.url(),
+                moved_to,
+                reason
+            ),
+            Ok(false) => {}
+            Err(e) => println!(
+                "ERROR: heal_moved_url(id={}, '{}' => '{}'): {}",
+                manga.id(),
+                manga.url(),
+                moved_to,
+                e
+            ),
+        }
+    }
+
+    // checks every manga (or just the one if possible_id is given) and returns only the chapters which are both
+    // unread and not previously seen by the checker; those are NOT recorded as seen (latest_available_chapter)
+    // until the caller has notified them and calls record_updates(), so that a failed notification is retried on
+    // the next check.  Newer chapters already read are recorded right away
+    pub fn check_for_updates(
+        db_full_paths: &str,
+        possible_id: Option<i64>,
+        mangadex_language: &str,
+    ) -> Result<Vec<ChapterUpdate>, Box<dyn std::error::Error>> {
+        check_for_updates_with(
+            db_full_paths,
+            possible_id,
+            mangadex_language,
+            &mut |_, _| {},
+        )
+    }
+
+    // same, on_failure being told of each manga whose site could not be checked (besides the ERROR printed)
+    pub fn check_for_updates_with(
+        db_full_paths: &str,
+        possible_id: Option<i64>,
+        mangadex_language: &str,
+        on_failure: &mut dyn FnMut(&MangaModel, &dyn std::error::Error),
+    ) -> Result<Vec<ChapterUpdate>, Box<dyn std::error::Error>> {
+        let mangas = match possible_id {
+            Some(id) => vec![model_sqlite3_manga::select_manga_by_id(db_full_paths, id)?],
+            // tags are of no use to the checker
+            None => model_sqlite3_manga::select_all_manga_with_options(
+                db_full_paths,
+                model_sqlite3_manga::SelectOptions { with_tags: false },
+            )?,
+        };
+
+        let mut new_updates = Vec::new();
+        for manga in mangas {
+            let check_result = check_manga(&manga, mangadex_language);
+            // record the visit even if nothing was found, but not if the site could not be reached
+            if check_result.is_ok() {
+                model_sqlite3_manga::update_last_checked_millis(
+                    db_full_paths,
+                    manga.id(),
+                    chrono::Utc::now().timestamp_millis(),
+                )?;
+            }
+            if let Ok((_, Some(moved_to))) = &check_result {
+                heal_moved_url(db_full_paths, &manga, moved_to, "permanent redirect");
+            }
+            let update = match check_result {
+                Ok((Some(update), _)) => update,
+                Ok((None, _)) => continue,
+                Err(e) => {
+                    // one dead site should not stop the rest from being checked
+                    println!(
+                        "ERROR: check_for_updates(id={}, url='{}'): {}",
+                        manga.id(),
+                        manga.url(),
+                        e
+                    );
+                    on_failure(&manga, e.as_ref());
+                    continue;
+                }
+            };
+            let previously_seen = manga.latest_available_chapter();
+            if !is_newer_chapter(&update.chapter, previously_seen.as_deref()) {
+                continue;
+            }
+            match is_newer_chapter(&update.chapter, manga.chapter().as_deref()) {
+                true => new_updates.push(update),
+                false => record_updates(db_full_paths, std::slice::from_ref(&update))?,
+            }
+        }
+        Ok(new_updates)
+    }
+
+    // the chapters of updates are seen: check_for_updates() no longer returns them
+    pub fn record_updates(
+        db_full_paths: &str,
+        updates: &[ChapterUpdate],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for update in updates {
+            model_sqlite3_manga::update_latest_available_chapter(
+                db_full_paths,
+                update.manga_id,
+                &update.chapter,
+            )?;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_chapter_from_text() {
+            assert_eq!(
+                chapter_from_text("Gate - Chapter 12"),
+                Some("12".to_string())
+            );
+            assert_eq!(
+                chapter_from_text("https://example.com/gate-chapter-12-1/"),
+                Some("12.1".to_string())
+            );
+            assert_eq!(chapter_from_text("ゲート 第45話"), Some("45".to_string()));
+            assert_eq!(chapter_from_text("https://example.com/gate/"), None);
+            assert_eq!(chapter_from_text("Gate Ch.12"), Some("12".to_string()));
+            assert_eq!(
+                chapter_from_text("https://example.com/gate_ch_12/"),
+                Some("12".to_string())
+            );
+            assert_eq!(chapter_from_text("Gate ep12"), Some("12".to_string()));
+            // the keyword ends a longer word
+            assert_eq!(chapter_from_text("https://example.com/watch-12/"), None);
+            assert_eq!(chapter_from_text("Batch-5"), None);
+            assert_eq!(chapter_from_text("deep-3"), None);
+            assert_eq!(chapter_from_text("Sleep-2"), None);
+        }
+
+        #[test]
+        fn test_is_newer_chapter() {
+            assert!(is_newer_chapter("12.1", Some("12")));
+            assert!(!is_newer_chapter("12", Some("12")));
+            assert!(is_newer_chapter("12.10", Some("12.9")));
+            assert!(!is_newer_chapter("9", Some("10")));
+            assert!(is_newer_chapter("1", None));
+        }
+
+        #[test]
+        fn test_latest_chapter_from_feed() {
+            let rss = r#"<?xml version="1.0"?><rss><channel><title>Gate</title>
+                <item><title>Gate Chapter 11</title><link>https://example.com/gate-chapter-11/</link></item>
+                <item><title><![CDATA[Gate Chapter 12 & more]]></title><link>https://example.com/gate-chapter-12/</link></item>
+                <item><title>Gate Chapter 10</title><link>https://example.com/gate-chapter-10/</link></item>
+                </channel></rss>"#;
+            assert_eq!(
+                latest_chapter_from_feed(rss),
+                Some((
+                    "12".to_string(),
+                    "https://example.com/gate-chapter-12/".to_string()
+                ))
+            );
+
+            let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Gate</title>
+                <entry><title>New release</title><link href="https://example.com/gate-chapter-3-1/"/></entry>
+                </feed>"#;
+            assert_eq!(
+                latest_chapter_from_feed(atom),
+                Some((
+                    "3.1".to_string(),
+                    "https://example.com/gate-chapter-3-1/".to_string()
+                ))
+            );
+        }
+
+        #[test]
+        fn test_is_permanent_redirect() {
+            assert!(is_permanent_redirect(301));
+            assert!(is_permanent_redirect(308));
+            assert!(!is_permanent_redirect(302));
+            assert!(!is_permanent_redirect(307));
+        }
+
+        #[test]
+        fn test_latest_chapter_from_html() {
+            let html = r#"<html><body>
+                <a href="/gate-chapter-8/">8</a>
+                <a href="https://example.com/gate-chapter-9/">9</a>
+                <a href="https://example.com/other-manga-chapter-99/">other</a>
+                </body></html>"#;
+            assert_eq!(
+                latest_chapter_from_html(html, "https://example.com/gate/"),
+                Some((
+                    "9".to_string(),
+                    "https://example.com/gate-chapter-9/".to_string()
+                ))
+            );
+        }
+    }
+}